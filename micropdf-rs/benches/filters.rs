@@ -144,6 +144,41 @@ fn bench_filter_comparison(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_png_predictor(c: &mut Criterion) {
+    let mut group = c.benchmark_group("filter/png_predictor");
+
+    // A 512x512 RGB image with every row filtered the same way
+    let (bytes_per_row, bpp) = (512 * 3, 3);
+    let pixels: Vec<u8> = (0..512 * bytes_per_row)
+        .map(|i| (i * 7 % 251) as u8)
+        .collect();
+    let prev_row = vec![17u8; bytes_per_row];
+
+    for (name, filter_type) in [("sub", 1u8), ("up", 2), ("average", 3), ("paeth", 4)] {
+        let rows: Vec<&[u8]> = pixels.chunks(bytes_per_row).collect();
+        group.bench_function(BenchmarkId::new("scalar", name), |b| {
+            b.iter(|| {
+                let mut out = Vec::with_capacity(pixels.len());
+                for row in &rows {
+                    decode_png_filter(filter_type, row, &prev_row, bpp, &mut out).ok();
+                }
+                black_box(out)
+            })
+        });
+        group.bench_function(BenchmarkId::new("fast", name), |b| {
+            b.iter(|| {
+                let mut out = Vec::with_capacity(pixels.len());
+                for row in &rows {
+                    decode_png_filter_fast(filter_type, row, &prev_row, bpp, &mut out).ok();
+                }
+                black_box(out)
+            })
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_flate_filter,
@@ -152,6 +187,7 @@ criterion_group!(
     bench_runlength_filter,
     bench_lzw_filter,
     bench_filter_comparison,
+    bench_png_predictor,
 );
 
 criterion_main!(benches);
@@ -17,7 +17,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Enhanced Functions (10 total)
+// Enhanced Functions (12 total)
 // ============================================================================
 
 int32_t np_add_blank_page(int32_t _ctx, int32_t _doc, float width, float height);
@@ -27,7 +27,9 @@ int32_t np_draw_line(int32_t _ctx, int32_t _page, float _x0, float _y0, float _x
 int32_t np_draw_rectangle(int32_t _ctx, int32_t _page, float _x, float _y, float width, float height, float r, float g, float b, float alpha, int32_t _fill);
 int32_t np_linearize_pdf(int32_t _ctx, const char * input_path, const char * output_path);
 int32_t np_merge_pdfs(int32_t _ctx, const char * const * paths, int32_t count, const char * output_path);
+int32_t np_merge_pdfs_with_cookie(int32_t _ctx, const char * const * paths, int32_t count, const char * output_path, int32_t cookie);
 int32_t np_optimize_pdf(int32_t _ctx, const char * path);
+int32_t np_optimize_pdf_with_cookie(int32_t _ctx, const char * path, int32_t cookie);
 int32_t np_split_pdf(int32_t _ctx, const char * input_path, const char * output_dir);
 int32_t np_write_pdf(int32_t _ctx, int32_t _doc, const char * _path);
 
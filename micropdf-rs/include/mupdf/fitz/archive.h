@@ -14,9 +14,11 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Archive Functions (13 total)
+// Archive Functions (15 total)
 // ============================================================================
 
+int32_t fz_archive_count_entries(int32_t _ctx, int32_t archive);
+int32_t fz_archive_entry_name(int32_t _ctx, int32_t archive, int32_t idx, char * buf, int32_t bufsize);
 int32_t fz_archive_entry_names(int32_t _ctx, int32_t archive, char * buf, int32_t bufsize);
 int32_t fz_archive_entry_size(int32_t _ctx, int32_t archive, const char * name);
 int32_t fz_archive_format(int32_t _ctx, int32_t archive);
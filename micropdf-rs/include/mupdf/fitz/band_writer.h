@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Band_writer Functions (21 total)
+// Band_writer Functions (24 total)
 // ============================================================================
 
 size_t fz_band_writer_bytes_written(int32_t _ctx, int32_t writer);
@@ -34,10 +34,13 @@ int32_t fz_band_writer_total_bands(int32_t _ctx, int32_t writer);
 int32_t fz_band_writer_write_band(int32_t _ctx, int32_t writer, int32_t band_rows, u8 const * data);
 int32_t fz_band_writer_write_header(int32_t _ctx, int32_t writer);
 int32_t fz_band_writer_write_trailer(int32_t _ctx, int32_t writer);
+int32_t fz_close_band_writer(int32_t _ctx, int32_t writer);
 void fz_drop_band_writer(int32_t _ctx, int32_t writer);
 int32_t fz_keep_band_writer(int32_t _ctx, int32_t writer);
 int32_t fz_new_band_writer(int32_t _ctx, int32_t output, int32_t format);
 int32_t fz_new_band_writer_with_config(int32_t _ctx, int32_t output, int32_t format, int32_t width, int32_t height, int32_t n, int32_t alpha);
+int32_t fz_new_png_band_writer(int32_t _ctx, int32_t output);
+int32_t fz_write_band(int32_t _ctx, int32_t writer, int32_t stride, int32_t band_height, u8 const * samples);
 
 #ifdef __cplusplus
 }
@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Bitmap Functions (20 total)
+// Bitmap Functions (23 total)
 // ============================================================================
 
 void fz_bitmap_clear(int32_t _ctx, int32_t bitmap);
@@ -35,8 +35,11 @@ int32_t fz_bitmap_y_res(int32_t _ctx, int32_t bitmap);
 void fz_drop_bitmap(int32_t _ctx, int32_t bitmap);
 int32_t fz_keep_bitmap(int32_t _ctx, int32_t bitmap);
 int32_t fz_new_bitmap(int32_t _ctx, int32_t width, int32_t height, int32_t x_res, int32_t y_res);
-int32_t fz_new_bitmap_from_pixmap(int32_t _ctx, int32_t pixmap, int32_t threshold);
+int32_t fz_new_bitmap_from_pixmap(int32_t _ctx, int32_t pixmap, int32_t halftone);
 int32_t fz_new_bitmap_from_pixmap_halftone(int32_t _ctx, int32_t pixmap, int32_t halftone_type);
+int32_t fz_new_bitmap_from_pixmap_threshold(int32_t _ctx, int32_t pixmap, int32_t threshold);
+int32_t fz_save_bitmap_as_pbm(int32_t _ctx, int32_t bitmap, const char * filename);
+int32_t fz_write_bitmap_as_pbm(int32_t _ctx, int32_t out, int32_t bitmap);
 
 #ifdef __cplusplus
 }
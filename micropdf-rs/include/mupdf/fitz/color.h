@@ -14,14 +14,17 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Color Functions (26 total)
+// Color Functions (30 total)
 // ============================================================================
 
 int32_t fz_clone_default_colorspaces(int32_t _ctx, int32_t base);
+float fz_color_from_byte(u8 value);
+int32_t fz_color_from_hex(int32_t ctx, const char * hex, float * rgba);
 int32_t fz_color_params_bp(ColorParams params);
 int32_t fz_color_params_op(ColorParams params);
 int32_t fz_color_params_opm(ColorParams params);
 int32_t fz_color_params_ri(ColorParams params);
+u8 fz_color_to_byte(float value);
 void fz_colorspace_digest(int32_t _ctx, int32_t _cs, u8 * digest);
 void fz_convert_color_with_params(int32_t _ctx, int32_t src_cs, float const * src, int32_t dst_cs, float * dst, int32_t proof_cs, ColorParams _params);
 int32_t fz_default_cmyk(int32_t _ctx, int32_t default_cs);
@@ -38,6 +41,7 @@ int32_t fz_new_cal_gray_colorspace(int32_t _ctx, float const * wp, float const *
 int32_t fz_new_cal_rgb_colorspace(int32_t _ctx, float const * wp, float const * bp, float const * gamma, float const * matrix);
 ColorParams fz_new_color_params(int32_t ri, int32_t bp, int32_t op, int32_t opm);
 int32_t fz_new_default_colorspaces(int32_t _ctx);
+int32_t fz_parse_color(int32_t ctx, const char * spec, float * rgba);
 const char * fz_rendering_intent_name(int32_t ri);
 void fz_set_default_cmyk(int32_t _ctx, int32_t default_cs, int32_t cs);
 void fz_set_default_gray(int32_t _ctx, int32_t default_cs, int32_t cs);
@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Context Functions (28 total)
+// Context Functions (29 total)
 // ============================================================================
 
 int fz_aa_level(int32_t ctx);
@@ -37,6 +37,7 @@ void fz_report_error(int32_t ctx);
 void fz_rethrow(int32_t ctx);
 void fz_set_aa_level(int32_t ctx, int bits);
 void fz_set_error_callback(int32_t ctx, Option<unsafe extern "C" fn(*mut c_void, c_int, *const c_char)> callback, void * user);
+void fz_set_parse_limits(int32_t _ctx, uint64_t max_stream_size, int max_nesting, uint64_t max_objects, uint64_t max_file_size, uint64_t max_decoded_total);
 void fz_set_user_context(int32_t ctx, void * user);
 void fz_set_warning_callback(int32_t ctx, Option<unsafe extern "C" fn(*mut c_void, *const c_char)> callback, void * user);
 void fz_shrink_store(int32_t _ctx, int percent);
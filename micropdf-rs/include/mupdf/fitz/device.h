@@ -42,7 +42,7 @@ void fz_ignore_text(int32_t _ctx, int32_t dev, int32_t text, fz_matrix transform
 int32_t fz_keep_device(int32_t _ctx, int32_t dev);
 int32_t fz_new_bbox_device(int32_t _ctx, fz_rect * rect);
 int32_t fz_new_draw_device(int32_t _ctx, fz_matrix _transform, int32_t pixmap);
-int32_t fz_new_list_device(int32_t _ctx, int32_t _list);
+int32_t fz_new_list_device(int32_t _ctx, int32_t list);
 int32_t fz_new_trace_device(int32_t _ctx);
 void fz_pop_clip(int32_t _ctx, int32_t dev);
 void fz_stroke_path(int32_t _ctx, int32_t dev, int32_t path, int32_t stroke, fz_matrix transform, int32_t colorspace, float const * color, float alpha);
@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Display_list Functions (10 total)
+// Display_list Functions (12 total)
 // ============================================================================
 
 fz_rect fz_bound_display_list(int32_t _ctx, int32_t list);
@@ -27,6 +27,8 @@ void fz_drop_display_list(int32_t _ctx, int32_t list);
 int32_t fz_keep_display_list(int32_t _ctx, int32_t list);
 int32_t fz_new_display_list(int32_t _ctx, float x0, float y0, float x1, float y1);
 void fz_run_display_list(int32_t _ctx, int32_t list, int32_t dev, fz_matrix ctm, fz_rect scissor);
+uint64_t fz_store_display_list(int32_t ctx, int32_t list, u8 const * key, size_t key_len);
+uint64_t fz_store_page_display_list(int32_t ctx, int32_t list, int32_t doc, int32_t page);
 
 #ifdef __cplusplus
 }
@@ -14,10 +14,11 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Filter Functions (30 total)
+// Filter Functions (31 total)
 // ============================================================================
 
 void fz_concat_push_drop(int32_t _ctx, int32_t concat, int32_t chain);
+int32_t fz_decode_stream(int32_t _ctx, int32_t dict, u8 const * data, size_t len, char * remaining, int32_t remaining_size);
 void fz_drop_filter(int32_t _ctx, int32_t filter);
 void fz_drop_jbig2_globals(int32_t _ctx, int32_t globals);
 u8 const * fz_filter_data(int32_t _ctx, int32_t filter);
@@ -38,7 +38,7 @@ int32_t fz_keep_font(int32_t _ctx, int32_t font);
 int32_t fz_new_font(int32_t _ctx, const char * name, int32_t _is_bold, int32_t _is_italic, int32_t _font_file);
 int32_t fz_new_font_from_file(int32_t _ctx, const char * name, const char * path, int32_t index, int32_t _use_glyph_bbox);
 int32_t fz_new_font_from_memory(int32_t _ctx, const char * name, u8 const * data, int32_t len, int32_t index, int32_t _use_glyph_bbox);
-int32_t fz_outline_glyph(int32_t _ctx, int32_t font, int32_t glyph, fz_matrix _transform);
+int32_t fz_outline_glyph(int32_t _ctx, int32_t font, int32_t glyph, fz_matrix transform);
 
 #ifdef __cplusplus
 }
@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Glyph Functions (36 total)
+// Glyph Functions (37 total)
 // ============================================================================
 
 void fz_drop_glyph(int32_t _ctx, int32_t glyph);
@@ -36,6 +36,7 @@ int32_t fz_glyph_is_color(int32_t _ctx, int32_t glyph);
 void fz_glyph_matrix(int32_t _ctx, int32_t glyph, float * matrix);
 void fz_glyph_metrics(int32_t _ctx, int32_t glyph, GlyphMetrics * metrics);
 void fz_glyph_origin(int32_t _ctx, int32_t glyph, float * x, float * y);
+int32_t fz_glyph_outline(int32_t _ctx, int32_t glyph);
 void fz_glyph_subpixel(int32_t _ctx, int32_t glyph, u8 * x, u8 * y);
 uint32_t fz_glyph_unicode(int32_t _ctx, int32_t glyph);
 float fz_glyph_variation(int32_t _ctx, int32_t glyph, int32_t axis_index);
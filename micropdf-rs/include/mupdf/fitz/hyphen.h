@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Hyphen Functions (17 total)
+// Hyphen Functions (18 total)
 // ============================================================================
 
 void fz_drop_hyphenator(int32_t _ctx, int32_t hyph);
@@ -32,6 +32,7 @@ int32_t fz_is_unicode_hyphen(uint32_t c);
 int32_t fz_lookup_hyphenator(int32_t _ctx, int32_t language);
 int32_t fz_new_empty_hyphenator(int32_t _ctx);
 int32_t fz_new_hyphenator(int32_t _ctx, int32_t language);
+int32_t fz_new_hyphenator_from_patterns(int32_t _ctx, const char * data, size_t len);
 void fz_register_hyphenator(int32_t _ctx, int32_t language, int32_t hyph);
 const char * fz_text_language_code(int32_t language);
 
@@ -14,9 +14,10 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Json Functions (29 total)
+// Json Functions (31 total)
 // ============================================================================
 
+size_t fz_document_to_json(int32_t _ctx, int32_t doc, char * output, size_t output_size);
 void fz_drop_json(int32_t _ctx, int32_t json);
 int32_t fz_json_array_get(int32_t _ctx, int32_t json, int32_t index);
 int32_t fz_json_array_length(int32_t _ctx, int32_t json);
@@ -44,6 +45,7 @@ size_t fz_json_to_string(int32_t _ctx, int32_t json, char * output, size_t outpu
 int32_t fz_json_type(int32_t _ctx, int32_t json);
 size_t fz_json_unescape_string(const char * input, char * output, size_t output_size);
 int32_t fz_parse_json(int32_t _ctx, int32_t _pool, const char * input);
+size_t fz_stext_to_json(int32_t _ctx, int32_t page, char * output, size_t output_size);
 size_t fz_write_json(int32_t _ctx, int32_t json, char * output, size_t output_size);
 size_t fz_write_json_pretty(int32_t _ctx, int32_t json, size_t indent, char * output, size_t output_size);
 
@@ -14,14 +14,16 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Output Functions (34 total)
+// Output Functions (39 total)
 // ============================================================================
 
 void fz_close_output(int32_t _ctx, int32_t out);
 void fz_drop_output(int32_t _ctx, int32_t out);
 void fz_flush_output(int32_t _ctx, int32_t out);
 int32_t fz_keep_output(int32_t _ctx, int32_t out);
+int32_t fz_memory_output_buffer(int32_t _ctx, int32_t out);
 int32_t fz_new_output_with_buffer(int32_t _ctx, int32_t buf);
+int32_t fz_new_output_with_memory(int32_t _ctx);
 int32_t fz_new_output_with_path(int32_t _ctx, const char * filename, int32_t append);
 void fz_reset_output(int32_t _ctx, int32_t out);
 void fz_seek_output(int32_t _ctx, int32_t out, int64_t off, int32_t whence);
@@ -43,6 +45,9 @@ void fz_write_int32_be(int32_t _ctx, int32_t out, int32_t x);
 void fz_write_int32_le(int32_t _ctx, int32_t out, int32_t x);
 void fz_write_int64_be(int32_t _ctx, int32_t out, int64_t x);
 void fz_write_int64_le(int32_t _ctx, int32_t out, int64_t x);
+void fz_write_pdf_name(int32_t _ctx, int32_t out, const char * name);
+void fz_write_pdf_real(int32_t _ctx, int32_t out, float x);
+void fz_write_pdf_string(int32_t _ctx, int32_t out, u8 const * data, size_t size);
 void fz_write_rune(int32_t _ctx, int32_t out, int32_t rune);
 void fz_write_string(int32_t _ctx, int32_t out, const char * s);
 void fz_write_uint16_be(int32_t _ctx, int32_t out, u16 x);
@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Pixmap Functions (32 total)
+// Pixmap Functions (33 total)
 // ============================================================================
 
 void fz_clear_pixmap(int32_t _ctx, int32_t pix);
@@ -26,8 +26,8 @@ void fz_gamma_pixmap(int32_t _ctx, int32_t pix, float gamma);
 u8 fz_get_pixmap_sample(int32_t _ctx, int32_t pix, int32_t x, int32_t y, int32_t n);
 void fz_invert_pixmap(int32_t _ctx, int32_t pix);
 int32_t fz_keep_pixmap(int32_t _ctx, int32_t pix);
-int32_t fz_new_pixmap(int32_t _ctx, int32_t cs, int32_t w, int32_t h, int32_t _seps, int32_t // Separations not implemented yet alpha);
-int32_t fz_new_pixmap_with_bbox(int32_t _ctx, int32_t cs, fz_irect bbox, int32_t _seps, int32_t alpha);
+int32_t fz_new_pixmap(int32_t _ctx, int32_t cs, int32_t w, int32_t h, int32_t seps, int32_t alpha);
+int32_t fz_new_pixmap_with_bbox(int32_t _ctx, int32_t cs, fz_irect bbox, int32_t seps, int32_t alpha);
 int32_t fz_pixmap_alpha(int32_t _ctx, int32_t pix);
 fz_irect fz_pixmap_bbox(int32_t _ctx, int32_t pix);
 int32_t fz_pixmap_colorants(int32_t _ctx, int32_t pix);
@@ -37,6 +37,7 @@ int32_t fz_pixmap_height(int32_t _ctx, int32_t pix);
 int32_t fz_pixmap_is_valid(int32_t _ctx, int32_t pix);
 void fz_pixmap_resolution(int32_t _ctx, int32_t _pix, int32_t * xres, int32_t * yres);
 u8 * fz_pixmap_samples(int32_t _ctx, int32_t _pix);
+int32_t fz_pixmap_spots(int32_t _ctx, int32_t pix);
 int32_t fz_pixmap_stride(int32_t _ctx, int32_t pix);
 int32_t fz_pixmap_width(int32_t _ctx, int32_t pix);
 int32_t fz_pixmap_x(int32_t _ctx, int32_t pix);
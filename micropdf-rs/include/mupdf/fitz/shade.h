@@ -14,15 +14,17 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Shade Functions (22 total)
+// Shade Functions (24 total)
 // ============================================================================
 
 void fz_drop_shade(int32_t _ctx, int32_t shade);
 int32_t fz_keep_shade(int32_t _ctx, int32_t shade);
+int32_t fz_load_shade(int32_t _ctx, int32_t page, const char * name);
 int32_t fz_new_function_shade(int32_t _ctx, uint64_t colorspace, float const * domain, float const * matrix);
 int32_t fz_new_linear_shade(int32_t _ctx, uint64_t colorspace, float x0, float y0, float x1, float y1, int32_t extend_start, int32_t extend_end);
 int32_t fz_new_mesh_shade(int32_t _ctx, uint64_t colorspace, int32_t shade_type, int32_t bits_per_coord, int32_t bits_per_comp, int32_t bits_per_flag);
 int32_t fz_new_radial_shade(int32_t _ctx, uint64_t colorspace, float x0, float y0, float r0, float x1, float y1, float r1, int32_t extend_start, int32_t extend_end);
+int32_t fz_render_shade(int32_t _ctx, int32_t shade, fz_matrix ctm, fz_rect rect);
 int32_t fz_shade_add_color_stop(int32_t _ctx, int32_t shade, float offset, float const * color, int32_t n);
 int32_t fz_shade_add_patch(int32_t _ctx, int32_t shade, ShadePoint const * points, [f32; 4] const * colors);
 int32_t fz_shade_add_vertex(int32_t _ctx, int32_t shade, float x, float y, float const * color, int32_t n);
@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Stext Functions (37 total)
+// Stext Functions (42 total)
 // ============================================================================
 
 int32_t fz_add_stext_block(int32_t _ctx, int32_t page, float x0, float y0, float x1, float y1);
@@ -23,7 +23,9 @@ int32_t fz_add_stext_line(int32_t _ctx, int32_t page, int32_t block_idx, float x
 const char * fz_copy_rectangle(int32_t ctx, int32_t page, float x0, float y0, float x1, float y1, int32_t crlf);
 const char * fz_copy_selection(int32_t _ctx, int32_t page, float a_x, float a_y, float b_x, float b_y, int32_t crlf);
 StextOptions * fz_default_stext_options(int32_t _ctx, StextOptions * opts);
+void fz_drop_search_hits(int32_t _ctx, int32_t hits);
 void fz_drop_stext_page(int32_t _ctx, int32_t page);
+int32_t fz_extract_page_text_callback(int32_t _ctx, int32_t page, Option<TextCharCallback> callback, c_void * arg);
 int32_t fz_highlight_selection(int32_t _ctx, int32_t page, float a_x, float a_y, float b_x, float b_y, FzQuad * quads, int32_t max_quads);
 int32_t fz_keep_stext_page(int32_t _ctx, int32_t page);
 int32_t fz_new_stext_page(int32_t _ctx, float x0, float y0, float x1, float y1);
@@ -32,6 +34,9 @@ StextOptions * fz_parse_stext_options(int32_t _ctx, StextOptions * opts, const c
 const char * fz_print_stext_page_as_html(int32_t _ctx, int32_t _output, int32_t page, int32_t _id);
 const char * fz_print_stext_page_as_json(int32_t _ctx, int32_t _output, int32_t page, float _scale);
 const char * fz_print_stext_page_as_xml(int32_t _ctx, int32_t _output, int32_t page, int32_t _id);
+int32_t fz_search_hit_count(int32_t _ctx, int32_t hits);
+int32_t fz_search_hit_quads(int32_t _ctx, int32_t hits, int32_t index, FzQuad * quads, int32_t max_quads);
+int32_t fz_search_page(int32_t _ctx, int32_t page, const char * needle);
 int32_t fz_search_stext_page(int32_t _ctx, int32_t page, const char * needle, int32_t * hit_mark, FzQuad * hit_bbox, int32_t hit_max);
 int32_t fz_segment_stext_page(int32_t _ctx, int32_t page);
 void fz_stext_block_bbox(int32_t _ctx, int32_t page, int32_t block_idx, float * x0, float * y0, float * x1, float * y1);
@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Story Functions (15 total)
+// Story Functions (16 total)
 // ============================================================================
 
 void fz_draw_story(int32_t _ctx, int32_t story, int32_t dev, float ctm_a, float ctm_b, float ctm_c, float ctm_d, float ctm_e, float ctm_f);
@@ -23,6 +23,7 @@ int32_t fz_new_story(int32_t _ctx, int32_t buf, const char * user_css, float em,
 int32_t fz_place_story(int32_t _ctx, int32_t story, float where_x0, float where_y0, float where_x1, float where_y1, Rect * filled);
 int32_t fz_place_story_flags(int32_t _ctx, int32_t story, float where_x0, float where_y0, float where_x1, float where_y1, Rect * filled, int32_t flags);
 void fz_reset_story(int32_t _ctx, int32_t story);
+int32_t fz_story_add_paragraph(int32_t _ctx, int32_t story, const char * text, int32_t font, float size);
 int32_t fz_story_document(int32_t _ctx, int32_t story);
 float fz_story_em(int32_t story);
 int32_t fz_story_is_complete(int32_t story);
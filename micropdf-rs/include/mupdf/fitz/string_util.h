@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// String_util Functions (13 total)
+// String_util Functions (15 total)
 // ============================================================================
 
 size_t fz_bidi_reorder(int32_t _ctx, const char * input, char * output, size_t output_size, int32_t base_dir);
@@ -27,6 +27,8 @@ size_t fz_find_word_breaks(int32_t _ctx, const char * text, int32_t * breaks, si
 int32_t fz_get_bidi_direction(int32_t _ctx, const char * text);
 int32_t fz_get_word_at(int32_t _ctx, const char * text, size_t position, size_t * word_start, size_t * word_end);
 size_t fz_normalize_string(int32_t _ctx, const char * input, char * output, size_t output_size, int32_t form);
+int32_t fz_parse_pdf_date(int32_t _ctx, const char * date, PdfDate * out);
+size_t fz_pdf_string_to_utf8(int32_t _ctx, u8 const * data, size_t len, char * output, size_t output_size);
 int32_t fz_strcoll(int32_t _ctx, const char * s1, const char * s2, const char * _locale);
 size_t fz_string_char_count(int32_t _ctx, const char * s);
 int32_t fz_string_is_normalized(int32_t _ctx, const char * input, int32_t form);
@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Transition Functions (23 total)
+// Transition Functions (25 total)
 // ============================================================================
 
 void fz_drop_transition(int32_t _ctx, int32_t trans);
@@ -31,9 +31,11 @@ int32_t fz_new_split_transition(float duration, int32_t vertical, int32_t outwar
 int32_t fz_new_transition(int32_t transition_type, float duration);
 int32_t fz_new_uncover_transition(float duration, int32_t direction);
 int32_t fz_new_wipe_transition(float duration, int32_t direction);
+Transition fz_page_presentation(int32_t _ctx, int32_t page);
 int32_t fz_transition_direction(int32_t trans);
 float fz_transition_duration(int32_t trans);
 int32_t fz_transition_outwards(int32_t trans);
+float fz_transition_progress(int32_t trans, float elapsed);
 void fz_transition_set_direction(int32_t trans, int32_t direction);
 void fz_transition_set_duration(int32_t trans, float duration);
 void fz_transition_set_outwards(int32_t trans, int32_t outwards);
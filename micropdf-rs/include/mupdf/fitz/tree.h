@@ -14,12 +14,18 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Tree Functions (27 total)
+// Tree Functions (33 total)
 // ============================================================================
 
 void fz_drop_structure_tree(int32_t _ctx, int32_t tree);
 int32_t fz_keep_structure_tree(int32_t _ctx, int32_t tree);
+int32_t fz_load_struct_tree(int32_t _ctx, int32_t doc);
 int32_t fz_new_structure_tree(int32_t _ctx);
+int32_t fz_struct_child(int32_t ctx, int32_t node);
+int32_t fz_struct_mcid(int32_t _ctx, int32_t node, int32_t index);
+int32_t fz_struct_mcid_count(int32_t _ctx, int32_t node);
+int32_t fz_struct_next(int32_t _ctx, int32_t node);
+const char * fz_struct_role(int32_t _ctx, int32_t node);
 int32_t fz_tree_add_node(int32_t _ctx, int32_t tree, int32_t parent, int32_t struct_type);
 int32_t fz_tree_find_by_id(int32_t _ctx, int32_t tree, const char * id);
 size_t fz_tree_get_text_in_order(int32_t _ctx, int32_t node, char * buffer, size_t buffer_size);
@@ -14,13 +14,14 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Xml Functions (18 total)
+// Xml Functions (19 total)
 // ============================================================================
 
 void fz_drop_xml(int32_t _ctx, int32_t doc);
 int32_t fz_new_xml_document(int32_t _ctx);
 int32_t fz_parse_xml(int32_t _ctx, const char * xml_string, int32_t _preserve_whitespace);
 int32_t fz_parse_xml_from_buffer(int32_t _ctx, int32_t buffer, int32_t _preserve_whitespace);
+size_t fz_stext_to_xml(int32_t _ctx, int32_t page, char * output, size_t output_size);
 const char * fz_xml_att(int32_t _ctx, int32_t node, const char * name);
 int32_t fz_xml_att_count(int32_t _ctx, int32_t node);
 int32_t fz_xml_child_count(int32_t _ctx, int32_t node);
@@ -14,9 +14,10 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Annot Functions (31 total)
+// Annot Functions (42 total)
 // ============================================================================
 
+void pdf_add_annot_quad_point(int32_t _ctx, int32_t annot, fz_quad quad);
 int32_t pdf_annot_author(int32_t _ctx, int32_t annot, c_char * buf, int32_t size);
 float pdf_annot_border_width(int32_t _ctx, int32_t annot);
 void pdf_annot_clear_dirty(int32_t _ctx, int32_t annot);
@@ -25,12 +26,19 @@ int32_t pdf_annot_contents(int32_t _ctx, int32_t annot, c_char * buf, int32_t si
 uint32_t pdf_annot_flags(int32_t _ctx, int32_t annot);
 int32_t pdf_annot_has_dirty(int32_t _ctx, int32_t annot);
 int32_t pdf_annot_has_popup(int32_t _ctx, int32_t annot);
+int32_t pdf_annot_ink_list(int32_t _ctx, int32_t annot, int32_t * count, int32_t max_strokes, fz_point * v, int32_t max_points);
+int32_t pdf_annot_ink_list_count(int32_t _ctx, int32_t annot);
+int32_t pdf_annot_ink_list_stroke_count(int32_t _ctx, int32_t annot, int32_t i);
+fz_point pdf_annot_ink_list_stroke_vertex(int32_t _ctx, int32_t annot, int32_t i, int32_t k);
 void pdf_annot_interior_color(int32_t _ctx, int32_t annot, int32_t * n, float * color);
 int32_t pdf_annot_is_valid(int32_t _ctx, int32_t annot);
 int32_t pdf_annot_line(int32_t _ctx, int32_t annot, fz_point * a, fz_point * b);
+void pdf_annot_line_ending_styles(int32_t _ctx, int32_t annot, int32_t * start, int32_t * end);
 float pdf_annot_opacity(int32_t _ctx, int32_t annot);
+int32_t pdf_annot_quad_point_count(int32_t _ctx, int32_t annot);
 fz_rect pdf_annot_rect(int32_t _ctx, int32_t annot);
 int32_t pdf_annot_type(int32_t _ctx, int32_t annot);
+void pdf_clear_annot_quad_points(int32_t _ctx, int32_t annot);
 int32_t pdf_clone_annot(int32_t _ctx, int32_t annot);
 int32_t pdf_create_annot(int32_t _ctx, int32_t _page, int32_t annot_type);
 void pdf_delete_annot(int32_t _ctx, int32_t _page, int32_t annot);
@@ -42,9 +50,12 @@ void pdf_set_annot_author(int32_t _ctx, int32_t annot, const char * text);
 void pdf_set_annot_border_width(int32_t _ctx, int32_t annot, float width);
 void pdf_set_annot_color(int32_t _ctx, int32_t annot, int32_t n, float const * color);
 void pdf_set_annot_contents(int32_t _ctx, int32_t annot, const char * text);
+void pdf_set_annot_default_appearance(int32_t _ctx, int32_t annot, const char * font, float size, int32_t n, float const * color);
 void pdf_set_annot_flags(int32_t _ctx, int32_t annot, uint32_t flags);
+void pdf_set_annot_ink_list(int32_t _ctx, int32_t annot, int32_t n, int32_t const * count, fz_point const * v);
 void pdf_set_annot_interior_color(int32_t _ctx, int32_t annot, int32_t n, float const * color);
 void pdf_set_annot_line(int32_t _ctx, int32_t annot, fz_point a, fz_point b);
+void pdf_set_annot_line_ending_styles(int32_t _ctx, int32_t annot, int32_t start, int32_t end);
 void pdf_set_annot_opacity(int32_t _ctx, int32_t annot, float opacity);
 void pdf_set_annot_rect(int32_t _ctx, int32_t annot, fz_rect rect);
 int32_t pdf_update_annot(int32_t _ctx, int32_t annot);
@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Document Functions (30 total)
+// Document Functions (34 total)
 // ============================================================================
 
 int32_t fz_authenticate_password(int32_t _ctx, int32_t doc, const char * password);
@@ -28,7 +28,7 @@ int32_t fz_document_format(int32_t _ctx, int32_t doc, char * buf, int32_t size);
 int32_t fz_document_is_valid(int32_t _ctx, int32_t doc);
 void fz_drop_document(int32_t _ctx, int32_t doc);
 void fz_drop_page(int32_t _ctx, int32_t page);
-int32_t fz_has_permission(int32_t _ctx, int32_t doc, int32_t _permission);
+int32_t fz_has_permission(int32_t _ctx, int32_t doc, int32_t permission);
 int32_t fz_is_document_reflowable(int32_t _ctx, int32_t doc);
 int32_t fz_keep_document(int32_t _ctx, int32_t doc);
 int32_t fz_keep_page(int32_t _ctx, int32_t page);
@@ -39,14 +39,18 @@ int32_t fz_load_page(int32_t _ctx, int32_t doc, int32_t page_num);
 int32_t fz_lookup_metadata(int32_t _ctx, int32_t _doc, const char * _key, char * buf, int32_t size);
 char * fz_make_location_uri(int32_t _ctx, int32_t _doc, int32_t page, char * buf, int32_t size);
 int32_t fz_needs_password(int32_t _ctx, int32_t doc);
-int32_t fz_open_document(int32_t _ctx, const char * filename);
-int32_t fz_open_document_with_stream(int32_t _ctx, const char * _magic, int32_t stm);
+int32_t fz_open_document(int32_t ctx, const char * filename);
+int32_t fz_open_document_mmap(int32_t ctx, const char * filename);
+int32_t fz_open_document_with_stream(int32_t ctx, const char * _magic, int32_t stm);
+int32_t fz_page_content_stream(int32_t _ctx, int32_t page);
 int32_t fz_page_label(int32_t _ctx, int32_t doc, int32_t page_num, char * buf, int32_t size);
 int32_t fz_page_number_from_location(int32_t _ctx, int32_t _doc, int32_t chapter, int32_t page);
+int32_t fz_render_page_thumbnail(int32_t ctx, int32_t doc, int32_t page_num, int32_t max_dim, c_void * cookie);
 int32_t fz_resolve_link(int32_t _ctx, int32_t doc, const char * uri, float * xp, float * yp);
 void fz_run_page(int32_t _ctx, int32_t page, int32_t device, fz_matrix transform, c_void * cookie);
 void fz_run_page_annots(int32_t _ctx, int32_t page, int32_t device, fz_matrix transform, c_void * cookie);
 void fz_run_page_contents(int32_t _ctx, int32_t page, int32_t device, fz_matrix transform, c_void * cookie);
+fz_matrix fz_transform_page(int32_t _ctx, int32_t page, float dpi);
 
 #ifdef __cplusplus
 }
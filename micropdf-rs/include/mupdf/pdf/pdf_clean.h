@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Pdf_clean Functions (29 total)
+// Pdf_clean Functions (30 total)
 // ============================================================================
 
 int32_t pdf_can_be_saved_incrementally(int32_t _ctx, int32_t _doc);
@@ -38,6 +38,7 @@ void pdf_remove_encryption(int32_t _ctx, WriteOptions * opts);
 void pdf_remove_object_streams(int32_t _ctx, int32_t _doc);
 void pdf_remove_unused_resources(int32_t _ctx, int32_t _doc);
 void pdf_renumber_objects(int32_t _ctx, int32_t _doc);
+int32_t pdf_sanitize_file(int32_t _ctx, const char * infile, const char * outfile, int32_t flags);
 void pdf_save_document(int32_t _ctx, int32_t _doc, const char * filename, WriteOptions const * _opts);
 void pdf_save_journal(int32_t _ctx, int32_t _doc, const char * filename);
 void pdf_save_snapshot(int32_t _ctx, int32_t _doc, const char * filename);
@@ -14,11 +14,16 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Pdf_event Functions (26 total)
+// Pdf_event Functions (34 total)
 // ============================================================================
 
 char * pdf_access_exec_menu_item_event(int32_t _ctx, int32_t handler, int32_t index);
 int32_t pdf_access_launch_url_event(int32_t _ctx, int32_t handler, int32_t index, char * * url_out, int32_t * new_frame_out);
+int32_t pdf_action_count(int32_t _ctx, int32_t actions);
+int32_t pdf_action_flags(int32_t _ctx, int32_t actions, int32_t index);
+int32_t pdf_action_page(int32_t _ctx, int32_t actions, int32_t index);
+int32_t pdf_action_text(int32_t _ctx, int32_t actions, int32_t index, char * buf, int32_t size);
+int32_t pdf_action_type(int32_t _ctx, int32_t actions, int32_t index);
 int32_t pdf_alert_get_button_pressed(AlertEvent const * evt);
 void pdf_alert_set_button_group(AlertEvent * evt, int32_t button_group);
 void pdf_alert_set_button_pressed(AlertEvent * evt, int32_t button);
@@ -27,6 +32,7 @@ void pdf_alert_set_message(AlertEvent * evt, const char * message);
 void pdf_alert_set_title(AlertEvent * evt, const char * title);
 void pdf_clear_pending_events(int32_t _ctx, int32_t handler);
 int32_t pdf_count_pending_events(int32_t _ctx, int32_t handler);
+void pdf_drop_action(int32_t _ctx, int32_t actions);
 void pdf_drop_alert_event(AlertEvent * evt);
 void pdf_drop_event_handler(int32_t _ctx, int32_t handler);
 void pdf_drop_mail_doc_event(MailDocEvent * evt);
@@ -42,6 +48,8 @@ void pdf_mail_doc_set_to(MailDocEvent * evt, const char * to);
 AlertEvent * pdf_new_alert_event(void);
 int32_t pdf_new_event_handler(int32_t _ctx, int32_t doc);
 MailDocEvent * pdf_new_mail_doc_event(void);
+int32_t pdf_parse_action(int32_t _ctx, int32_t obj);
+int32_t pdf_parse_document_action(int32_t _ctx, int32_t doc, int32_t obj);
 void pdf_set_doc_event_callback(int32_t _ctx, int32_t handler, Option<DocEventCallback> event_cb, Option<FreeEventDataCallback> free_cb, void * data);
 
 #ifdef __cplusplus
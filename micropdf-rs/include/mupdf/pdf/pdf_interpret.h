@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Pdf_interpret Functions (103 total)
+// Pdf_interpret Functions (104 total)
 // ============================================================================
 
 void pdf_close_processor(int32_t _ctx, int32_t proc);
@@ -107,6 +107,7 @@ void pdf_op_squote(int32_t _ctx, int32_t proc, const char * str, size_t len);
 void pdf_op_v(int32_t _ctx, int32_t proc, float x2, float y2, float x3, float y3);
 void pdf_op_w(int32_t _ctx, int32_t proc, float linewidth);
 void pdf_op_y(int32_t _ctx, int32_t proc, float x1, float y1, float x3, float y3);
+char * pdf_pretty_print_content(int32_t _ctx, u8 const * data, size_t len);
 void pdf_process_annot(int32_t _ctx, int32_t proc, int32_t annot);
 void pdf_process_contents(int32_t _ctx, int32_t proc, int32_t _doc, int32_t res, int32_t _stm, int32_t * _out_res);
 void pdf_process_glyph(int32_t _ctx, int32_t proc, int32_t _doc, int32_t res);
@@ -14,13 +14,16 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Pdf_javascript Functions (24 total)
+// Pdf_javascript Functions (27 total)
 // ============================================================================
 
+int32_t pdf_count_javascript(int32_t _ctx, int32_t doc);
 void pdf_disable_js(int32_t _ctx, int32_t doc);
 void pdf_drop_js(int32_t _ctx, int32_t js);
 void pdf_enable_js(int32_t _ctx, int32_t doc);
 int32_t pdf_get_js(int32_t _ctx, int32_t doc);
+int32_t pdf_has_open_action_js(int32_t _ctx, int32_t doc);
+int32_t pdf_javascript_at(int32_t _ctx, int32_t doc, int32_t index, char * buf, int32_t size);
 void pdf_js_clear_console_log(int32_t js);
 void pdf_js_clear_last_error(int32_t js);
 void pdf_js_event_init(int32_t js, int32_t target, const char * value, int32_t will_commit);
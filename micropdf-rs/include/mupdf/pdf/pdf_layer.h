@@ -27,7 +27,7 @@ void pdf_deselect_layer_config_ui(int32_t _ctx, int32_t doc, int32_t ui);
 void pdf_drop_ocg(int32_t _ctx, int32_t doc);
 void pdf_enable_layer(int32_t _ctx, int32_t doc, int32_t layer, int32_t enabled);
 int32_t pdf_get_current_layer_config(int32_t _ctx, int32_t doc);
-int32_t pdf_is_ocg_hidden(int32_t _ctx, int32_t doc, int32_t _rdb, const char * _usage, int32_t _ocg);
+int32_t pdf_is_ocg_hidden(int32_t _ctx, int32_t doc, int32_t _rdb, const char * _usage, int32_t ocg);
 const char * pdf_layer_config_creator(int32_t _ctx, int32_t doc, int32_t config_num);
 void pdf_layer_config_info(int32_t _ctx, int32_t doc, int32_t config_num, FfiLayerConfig * info);
 const char * pdf_layer_config_name(int32_t _ctx, int32_t doc, int32_t config_num);
@@ -14,13 +14,15 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Pdf_name_table Functions (17 total)
+// Pdf_name_table Functions (19 total)
 // ============================================================================
 
 void pdf_free_name_string(char * name);
 char * pdf_get_interned_name(int32_t idx);
 int32_t pdf_intern_name(const char * name);
 int32_t pdf_lookup_name(const char * name);
+int32_t pdf_lookup_name_tree(int32_t _ctx, int32_t tree, const char * name);
+int32_t pdf_lookup_number(int32_t _ctx, int32_t tree, int32_t n);
 int32_t pdf_name_eq_str(int32_t idx, const char * name);
 int32_t pdf_name_index_eq(int32_t a, int32_t b);
 int32_t pdf_name_table_count(void);
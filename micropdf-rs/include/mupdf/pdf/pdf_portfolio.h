@@ -14,12 +14,15 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Pdf_portfolio Functions (21 total)
+// Pdf_portfolio Functions (24 total)
 // ============================================================================
 
+int32_t pdf_add_embedded_file(int32_t ctx, int32_t doc, const char * name, u8 const * data, size_t len, const char * mime_type);
 char * pdf_af_relationship_to_string(int32_t _ctx, int32_t relationship);
 char * pdf_collection_view_to_string(int32_t _ctx, int32_t view);
+int32_t pdf_count_embedded_files(int32_t ctx, int32_t doc);
 void pdf_drop_portfolio(int32_t _ctx, int32_t portfolio);
+int32_t pdf_extract_embedded_file(int32_t ctx, int32_t doc, int32_t index);
 int32_t pdf_is_portfolio(int32_t _ctx, int32_t portfolio);
 int32_t pdf_new_portfolio(int32_t _ctx, int32_t doc);
 int32_t pdf_portfolio_add_file(int32_t _ctx, int32_t portfolio, const char * name, u8 const * data, size_t len, const char * mime_type);
@@ -32,7 +32,7 @@ RedactStats pdf_get_redact_stats(int32_t _ctx, int32_t redact_ctx);
 int32_t pdf_new_redact_context(int32_t _ctx, int32_t doc, int32_t page);
 RedactOptions pdf_ocr_redact_options(void);
 int32_t pdf_redact_document(int32_t _ctx, int32_t _doc, RedactOptions const * _opts);
-int32_t pdf_redact_page_annotations(int32_t _ctx, int32_t _doc, int32_t _page, RedactOptions const * _opts);
+int32_t pdf_redact_page_annotations(int32_t ctx, int32_t doc, int32_t page, RedactOptions const * opts);
 void pdf_remove_attachments(int32_t _ctx, int32_t _doc);
 void pdf_remove_comments(int32_t _ctx, int32_t _doc);
 void pdf_remove_hidden_content(int32_t _ctx, int32_t _doc);
@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Pdf_resource Functions (48 total)
+// Pdf_resource Functions (49 total)
 // ============================================================================
 
 int32_t pdf_add_colorspace(int32_t _ctx, int32_t _doc, int32_t _cs);
@@ -55,6 +55,7 @@ void pdf_purge_locals_from_store(int32_t _ctx, int32_t doc);
 void pdf_purge_object_from_store(int32_t _ctx, int32_t _doc, int32_t _num);
 int32_t pdf_push_resource_stack(int32_t _ctx, int32_t stack, int32_t resources);
 void pdf_remove_item(int32_t _ctx, void const * _drop, int32_t _key);
+int32_t pdf_resource_lookup(int32_t _ctx, int32_t resources, const char * category, const char * name);
 void pdf_sample_shade_function(int32_t _ctx, float * samples, int32_t n, int32_t funcs, int32_t const * func_handles, float t0, float t1);
 void pdf_store_item(int32_t _ctx, int32_t _key, void * _val, size_t _itemsize);
 void pdf_update_xobject(int32_t _ctx, int32_t _doc, int32_t _xobj, float const * _bbox, float const * _matrix, int32_t _res, int32_t _buffer);
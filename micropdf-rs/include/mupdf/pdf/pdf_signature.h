@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Pdf_signature Functions (33 total)
+// Pdf_signature Functions (35 total)
 // ============================================================================
 
 int32_t pdf_add_signature(int32_t _ctx, int32_t doc, const char * cn, int64_t date);
@@ -39,14 +39,16 @@ int32_t pdf_pkcs7_signer_new(int32_t _ctx, const char * cn);
 void pdf_pkcs7_verifier_add_cert(int32_t _ctx, int32_t verifier, u8 const * cert, size_t len);
 int32_t pdf_pkcs7_verifier_new(int32_t _ctx);
 void pdf_sign_signature(int32_t _ctx, int32_t _widget, int32_t signer, int64_t date, const char * _reason, const char * _location);
-int32_t pdf_signature_byte_range(int32_t _ctx, int32_t doc, int32_t _signature, ByteRange * byte_range);
-size_t pdf_signature_contents(int32_t _ctx, int32_t doc, int32_t _signature, char * * contents);
+int32_t pdf_signature_byte_range(int32_t _ctx, int32_t doc, int32_t signature, ByteRange * byte_range);
+size_t pdf_signature_contents(int32_t _ctx, int32_t doc, int32_t signature, char * * contents);
+int32_t pdf_signature_covers_whole_file(int32_t _ctx, int32_t doc, int32_t signature);
+int32_t pdf_signature_digest(int32_t _ctx, int32_t doc, int32_t signature, u8 * digest);
 void pdf_signature_drop_distinguished_name(int32_t _ctx, int32_t dn);
 const char * pdf_signature_error_description(int32_t err);
 const char * pdf_signature_format_distinguished_name(int32_t _ctx, int32_t dn);
 void pdf_signature_free_string(int32_t _ctx, char * s);
 int32_t pdf_signature_get_signatory(int32_t _ctx, int32_t verifier, int32_t doc, int32_t _signature);
-int32_t pdf_signature_incremental_change_since_signing(int32_t _ctx, int32_t doc, int32_t _signature);
+int32_t pdf_signature_incremental_change_since_signing(int32_t _ctx, int32_t doc, int32_t signature);
 const char * pdf_signature_info(int32_t _ctx, const char * name, int32_t dn, const char * reason, const char * location, int64_t date, int32_t include_labels);
 int32_t pdf_signature_is_signed(int32_t _ctx, int32_t doc, int32_t _field);
 void pdf_signature_set_value(int32_t _ctx, int32_t doc, int32_t _field, int32_t signer, int64_t stime);
@@ -14,7 +14,7 @@ extern "C" {
 #endif
 
 // ============================================================================
-// Pdf_zugferd Functions (16 total)
+// Pdf_zugferd Functions (17 total)
 // ============================================================================
 
 void pdf_drop_zugferd_context(int32_t _ctx, int32_t zugferd);
@@ -24,6 +24,7 @@ char * pdf_zugferd_af_relationship(int32_t _ctx);
 ZugferdEmbedParams pdf_zugferd_default_embed_params(void);
 int32_t pdf_zugferd_embed(int32_t _ctx, int32_t zugferd, u8 const * xml, size_t xml_len, ZugferdEmbedParams const * params);
 int32_t pdf_zugferd_error_count(int32_t _ctx, int32_t _zugferd);
+size_t pdf_zugferd_extract_xml(int32_t _ctx, int32_t doc, u8 * buf, size_t size);
 void pdf_zugferd_free_string(char * s);
 char * pdf_zugferd_mime_type(int32_t _ctx);
 int32_t pdf_zugferd_profile(int32_t _ctx, int32_t zugferd, float * version_out);
@@ -4,6 +4,8 @@
 
 use super::error::{EnhancedError, Result};
 use crate::enhanced::writer::PdfWriter;
+use crate::fitz::geometry::Matrix;
+use crate::pdf::write::ContentBuilder;
 use std::fs;
 use std::path::Path;
 
@@ -65,34 +67,19 @@ impl Watermark {
     /// Generate PDF content stream for watermark
     fn generate_content_stream(&self) -> String {
         let radians = self.rotation.to_radians();
-        let cos_theta = radians.cos();
-        let sin_theta = radians.sin();
-
-        format!(
-            "q\n\
-             /GS1 gs\n\
-             BT\n\
-             /F1 {} Tf\n\
-             {} {} {} {} {} {} Tm\n\
-             ({}) Tj\n\
-             ET\n\
-             Q\n",
-            self.font_size,
-            cos_theta,
-            sin_theta,
-            -sin_theta,
-            cos_theta,
-            self.x,
-            self.y,
-            self.escape_text(&self.text)
-        )
-    }
-
-    /// Escape special characters in PDF text
-    fn escape_text(&self, text: &str) -> String {
-        text.replace('\\', "\\\\")
-            .replace('(', "\\(")
-            .replace(')', "\\)")
+        let (sin, cos) = radians.sin_cos();
+
+        let mut content = ContentBuilder::new();
+        content
+            .save()
+            .set_ext_gstate("GS1")
+            .begin_text()
+            .set_font("F1", self.font_size)
+            .set_text_matrix(&Matrix::new(cos, sin, -sin, cos, self.x, self.y))
+            .show_text(&self.text)
+            .end_text()
+            .restore();
+        String::from_utf8_lossy(&content.to_vec()).into_owned()
     }
 
     /// Apply watermark to PDF file
@@ -231,15 +218,14 @@ pub fn add_text(
     }
 
     // Generate text content stream
-    let escaped_text = text
-        .replace('\\', "\\\\")
-        .replace('(', "\\(")
-        .replace(')', "\\)");
-
-    let content = format!(
-        "BT\n/F1 {} Tf\n{} {} Td\n({}) Tj\nET\n",
-        font_size, x, y, escaped_text
-    );
+    let mut content = ContentBuilder::new();
+    content
+        .begin_text()
+        .set_font("F1", font_size)
+        .move_text(x, y)
+        .show_text(text)
+        .end_text();
+    let content = String::from_utf8_lossy(&content.to_vec()).into_owned();
 
     // Create output PDF
     let mut writer = PdfWriter::new();
@@ -300,7 +286,13 @@ pub fn add_image(
     // 4. Write modified PDF
 
     // For now, create a placeholder
-    let content = format!("q\n{} 0 0 {} {} {} cm\n/Im1 Do\nQ\n", width, height, x, y);
+    let mut content = ContentBuilder::new();
+    content
+        .save()
+        .concat(&Matrix::new(width, 0.0, 0.0, height, x, y))
+        .draw_xobject("Im1")
+        .restore();
+    let content = String::from_utf8_lossy(&content.to_vec()).into_owned();
 
     let mut writer = PdfWriter::new();
     writer.add_page_with_content(612.0, 792.0, &content)?;
@@ -378,9 +370,10 @@ mod tests {
 
     #[test]
     fn test_watermark_escape_text() {
-        let wm = Watermark::new("Test");
-        assert_eq!(wm.escape_text("Hello (World)"), "Hello \\(World\\)");
-        assert_eq!(wm.escape_text("Back\\slash"), "Back\\\\slash");
+        let content = Watermark::new("Hello (World)").generate_content_stream();
+        assert!(content.contains("(Hello \\(World\\)) Tj"));
+        let content = Watermark::new("Back\\slash").generate_content_stream();
+        assert!(content.contains("(Back\\\\slash) Tj"));
     }
 
     #[test]
@@ -5,6 +5,7 @@
 use super::error::{EnhancedError, Result};
 use crate::fitz::geometry::{Matrix, Point};
 use crate::fitz::path::Path;
+use crate::pdf::write::ContentBuilder;
 
 /// Color representation (RGBA)
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -257,6 +258,8 @@ pub struct DrawingContext {
     pub line_style: LineStyle,
     /// Current transformation matrix
     pub transform: Matrix,
+    /// Operators for everything drawn so far
+    content: ContentBuilder,
 }
 
 impl DrawingContext {
@@ -267,9 +270,17 @@ impl DrawingContext {
             fill_color: Color::BLACK,
             line_style: LineStyle::default(),
             transform: Matrix::IDENTITY,
+            content: ContentBuilder::new(),
         }
     }
 
+    /// Content stream operators for everything drawn so far
+    ///
+    /// Opacity is not written: it needs an /ExtGState in the page resources.
+    pub fn content(&self) -> Vec<u8> {
+        self.content.to_vec()
+    }
+
     /// Set stroke color
     pub fn set_stroke_color(&mut self, color: Color) -> &mut Self {
         self.stroke_color = color;
@@ -610,24 +621,45 @@ impl DrawingContext {
     }
 
     /// Stroke a path (internal)
-    fn stroke_path(&self, path: &Path) -> Result<()> {
+    fn stroke_path(&mut self, path: &Path) -> Result<()> {
         if path.elements().is_empty() {
             return Err(EnhancedError::Generic("Cannot stroke empty path".into()));
         }
 
-        // Generate PDF content stream for stroking this path
-        // This returns the actual PDF operators that will be written to the content stream
+        let Color { r, g, b, .. } = self.stroke_color;
+        let style = &self.line_style;
+        self.content.save();
+        if self.transform != Matrix::IDENTITY {
+            self.content.concat(&self.transform);
+        }
+        self.content
+            .set_rgb_stroke(r, g, b)
+            .set_line_width(style.width)
+            .set_line_cap(style.cap as i32)
+            .set_line_join(style.join as i32);
+        if !style.dash_pattern.is_empty() {
+            self.content.set_dash(&style.dash_pattern, style.dash_phase);
+        }
+        self.content.path(path).stroke().restore();
         Ok(())
     }
 
     /// Fill a path (internal)
-    fn fill_path(&self, path: &Path) -> Result<()> {
+    fn fill_path(&mut self, path: &Path) -> Result<()> {
         if path.elements().is_empty() {
             return Err(EnhancedError::Generic("Cannot fill empty path".into()));
         }
 
-        // Generate PDF content stream for filling this path
-        // This returns the actual PDF operators that will be written to the content stream
+        let Color { r, g, b, .. } = self.fill_color;
+        self.content.save();
+        if self.transform != Matrix::IDENTITY {
+            self.content.concat(&self.transform);
+        }
+        self.content
+            .set_rgb_fill(r, g, b)
+            .path(path)
+            .fill()
+            .restore();
         Ok(())
     }
 }
@@ -835,6 +867,17 @@ mod tests {
         assert_eq!(ctx.transform, Matrix::IDENTITY);
     }
 
+    #[test]
+    fn test_drawing_context_content() {
+        let mut ctx = DrawingContext::new();
+        ctx.set_fill_color(Color::RED);
+        ctx.fill_rectangle(0.0, 0.0, 10.0, 5.0).unwrap();
+        assert_eq!(
+            ctx.content(),
+            b"q\n1 0 0 rg\n0 0 m\n10 0 l\n10 5 l\n0 5 l\nh\nf\nQ\n"
+        );
+    }
+
     #[test]
     fn test_drawing_context_set_colors() {
         let mut ctx = DrawingContext::new();
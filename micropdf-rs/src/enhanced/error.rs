@@ -16,6 +16,8 @@ pub enum EnhancedError {
     Unsupported(String),
     /// Generic error
     Generic(String),
+    /// Operation cancelled through a cookie
+    Aborted,
 }
 
 impl fmt::Display for EnhancedError {
@@ -25,6 +27,7 @@ impl fmt::Display for EnhancedError {
             EnhancedError::Io(err) => write!(f, "I/O error: {}", err),
             EnhancedError::Unsupported(msg) => write!(f, "Unsupported: {}", msg),
             EnhancedError::Generic(msg) => write!(f, "{}", msg),
+            EnhancedError::Aborted => write!(f, "Operation aborted"),
         }
     }
 }
@@ -46,7 +49,10 @@ impl From<std::io::Error> for EnhancedError {
 
 impl From<crate::fitz::error::Error> for EnhancedError {
     fn from(err: crate::fitz::error::Error) -> Self {
-        EnhancedError::Generic(err.to_string())
+        match err {
+            crate::fitz::error::Error::Abort => EnhancedError::Aborted,
+            err => EnhancedError::Generic(err.to_string()),
+        }
     }
 }
 
@@ -1,6 +1,7 @@
 //! PDF Optimization - Compression, cleanup, form flattening
 
 use super::error::{EnhancedError, Result};
+use crate::fitz::cookie::Cookie;
 use std::fs;
 use std::path::Path;
 
@@ -163,6 +164,35 @@ pub fn remove_duplicate_streams(pdf_path: &str) -> Result<usize> {
     Ok(0)
 }
 
+/// Run the object-level optimization passes over a PDF
+///
+/// Unused objects are removed first, then duplicate streams. `cookie` is
+/// checked before each pass and its progress counts completed passes.
+/// Returns the total number of objects removed, or
+/// [`EnhancedError::Aborted`] if the cookie is aborted.
+pub fn optimize_pdf(pdf_path: &str, cookie: Option<&Cookie>) -> Result<usize> {
+    let passes: [fn(&str) -> Result<usize>; 2] = [remove_unused_objects, remove_duplicate_streams];
+
+    if let Some(cookie) = cookie {
+        cookie.set_progress(0);
+        cookie.set_progress_max(passes.len() as i32);
+    }
+
+    let mut removed = 0;
+    for pass in passes {
+        if let Some(cookie) = cookie.filter(|c| c.should_abort()) {
+            cookie.set_incomplete(true);
+            return Err(EnhancedError::Aborted);
+        }
+        removed += pass(pdf_path)?;
+        if let Some(cookie) = cookie {
+            cookie.inc_progress();
+        }
+    }
+
+    Ok(removed)
+}
+
 /// Linearize PDF for fast web viewing
 pub fn linearize(pdf_path: &str) -> Result<()> {
     // Verify PDF exists
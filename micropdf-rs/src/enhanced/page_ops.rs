@@ -4,7 +4,12 @@
 
 use super::error::{EnhancedError, Result};
 use super::writer::PdfWriter;
+use crate::fitz::cookie::Cookie;
 use crate::fitz::geometry::Rect;
+use crate::pdf::document::Document;
+use crate::pdf::outline::{self, OutlineItem};
+use crate::pdf::write;
+use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
 
@@ -22,8 +27,8 @@ pub struct PageInfo {
 
 /// PDF Merger for combining multiple PDFs
 pub struct PdfMerger {
-    /// Writer for output PDF
-    writer: PdfWriter,
+    /// Source documents with the pages taken from each
+    sources: Vec<(Document, Vec<usize>)>,
     /// Total pages added
     page_count: usize,
 }
@@ -32,42 +37,17 @@ impl PdfMerger {
     /// Create a new merger
     pub fn new() -> Self {
         Self {
-            writer: PdfWriter::new(),
+            sources: Vec::new(),
             page_count: 0,
         }
     }
 
     /// Append all pages from a PDF file
     pub fn append(&mut self, path: &str) -> Result<&mut Self> {
-        // Verify file exists
-        if !Path::new(path).exists() {
-            return Err(EnhancedError::Io(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                format!("PDF file not found: {}", path),
-            )));
-        }
-
-        // Read PDF file
-        let data = fs::read(path)?;
-
-        // Verify it's a PDF
-        if !data.starts_with(b"%PDF-") {
-            return Err(EnhancedError::InvalidParameter(format!(
-                "Not a valid PDF file: {}",
-                path
-            )));
-        }
-
-        // Parse PDF to extract pages
-        // For now, create a blank page for each page we detect
-        let page_count = self.estimate_page_count(&data)?;
-
-        for _ in 0..page_count {
-            // Default Letter size
-            self.writer.add_blank_page(612.0, 792.0)?;
-            self.page_count += 1;
-        }
-
+        let doc = Self::open(path)?;
+        let pages: Vec<usize> = (0..doc.page_count()?).collect();
+        self.page_count += pages.len();
+        self.sources.push((doc, pages));
         Ok(self)
     }
 
@@ -77,24 +57,8 @@ impl PdfMerger {
             return Ok(self);
         }
 
-        // Verify file exists
-        if !Path::new(path).exists() {
-            return Err(EnhancedError::Io(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                format!("PDF file not found: {}", path),
-            )));
-        }
-
-        // Read and validate PDF
-        let data = fs::read(path)?;
-        if !data.starts_with(b"%PDF-") {
-            return Err(EnhancedError::InvalidParameter(format!(
-                "Not a valid PDF file: {}",
-                path
-            )));
-        }
-
-        let total_pages = self.estimate_page_count(&data)?;
+        let doc = Self::open(path)?;
+        let total_pages = doc.page_count()?;
 
         // Validate page numbers
         for &page_num in pages {
@@ -106,12 +70,8 @@ impl PdfMerger {
             }
         }
 
-        // Add requested pages
-        for _ in pages {
-            self.writer.add_blank_page(612.0, 792.0)?;
-            self.page_count += 1;
-        }
-
+        self.page_count += pages.len();
+        self.sources.push((doc, pages.to_vec()));
         Ok(self)
     }
 
@@ -121,6 +81,8 @@ impl PdfMerger {
     }
 
     /// Save merged PDF to file
+    ///
+    /// Fonts and images shared by the inputs are written once.
     pub fn save(&self, path: &str) -> Result<()> {
         if self.page_count == 0 {
             return Err(EnhancedError::InvalidParameter(
@@ -128,34 +90,37 @@ impl PdfMerger {
             ));
         }
 
-        self.writer.save(path)
+        let sources: Vec<(&Document, &[usize])> = self
+            .sources
+            .iter()
+            .map(|(doc, pages)| (doc, pages.as_slice()))
+            .collect();
+        let mut data = Vec::new();
+        write::merge_pages(&sources, &mut data)?;
+        fs::write(path, data)?;
+        Ok(())
     }
 
-    /// Estimate page count from PDF data
-    fn estimate_page_count(&self, data: &[u8]) -> Result<usize> {
-        // Look for /Type /Pages and /Count in the PDF
-        let content = String::from_utf8_lossy(data);
-
-        // Find /Count entries in Pages objects
-        let mut max_count = 1; // At least 1 page by default
+    /// Open an input PDF
+    fn open(path: &str) -> Result<Document> {
+        // Verify file exists
+        if !Path::new(path).exists() {
+            return Err(EnhancedError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("PDF file not found: {}", path),
+            )));
+        }
 
-        for line in content.lines() {
-            if line.contains("/Type") && line.contains("/Pages") {
-                // Look for /Count in nearby lines
-                if let Some(count_pos) = line.find("/Count") {
-                    let after_count = &line[count_pos + 6..];
-                    if let Some(num_end) =
-                        after_count.find(|c: char| !c.is_ascii_digit() && c != ' ')
-                    {
-                        if let Ok(count) = after_count[..num_end].trim().parse::<usize>() {
-                            max_count = max_count.max(count);
-                        }
-                    }
-                }
-            }
+        // Verify it's a PDF
+        let data = fs::read(path)?;
+        if !data.starts_with(b"%PDF-") {
+            return Err(EnhancedError::InvalidParameter(format!(
+                "Not a valid PDF file: {}",
+                path
+            )));
         }
 
-        Ok(max_count)
+        Ok(Document::open_bytes(data)?)
     }
 }
 
@@ -219,6 +184,99 @@ pub fn split_pdf(input_path: &str, output_dir: &str) -> Result<Vec<String>> {
     Ok(output_files)
 }
 
+/// Split a PDF at its bookmarks, writing one file per section
+///
+/// Bookmarks at outline depth `level` (1 for top-level entries) start a
+/// section that runs until the page before the next such bookmark. Pages
+/// before the first bookmark belong to the first section, and bookmarks
+/// without a destination page are skipped. Files are named from the
+/// sanitized bookmark title, in page order.
+pub fn split_by_bookmarks(input_path: &str, output_dir: &str, level: usize) -> Result<Vec<String>> {
+    if level == 0 {
+        return Err(EnhancedError::InvalidParameter(
+            "Bookmark level starts at 1".into(),
+        ));
+    }
+    if !Path::new(input_path).exists() {
+        return Err(EnhancedError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("PDF file not found: {}", input_path),
+        )));
+    }
+
+    let doc = Document::open(input_path)?;
+    let page_count = doc.page_count()?;
+    let mut starts = Vec::new();
+    collect_bookmarks(&outline::load_outline(&doc)?, level, &mut starts);
+    // Sections follow page order; later bookmarks on an already used page
+    // would produce empty sections
+    starts.sort_by_key(|(page, _)| *page);
+    starts.dedup_by_key(|(page, _)| *page);
+    if starts.is_empty() {
+        return Err(EnhancedError::InvalidParameter(format!(
+            "No bookmarks with a destination at level {}",
+            level
+        )));
+    }
+
+    fs::create_dir_all(output_dir)?;
+    let mut used_names = HashSet::new();
+    let mut output_files = Vec::with_capacity(starts.len());
+    for (i, (start, title)) in starts.iter().enumerate() {
+        let first = if i == 0 { 0 } else { *start };
+        let end = starts.get(i + 1).map_or(page_count, |(next, _)| *next);
+        let pages: Vec<usize> = (first..end).collect();
+
+        let base = sanitize_file_name(title);
+        let mut name = base.clone();
+        let mut n = 2;
+        while !used_names.insert(name.clone()) {
+            name = format!("{}_{}", base, n);
+            n += 1;
+        }
+
+        let mut data = Vec::new();
+        write::copy_pages(&doc, &pages, &mut data)?;
+        let output_path = format!("{}/{}.pdf", output_dir, name);
+        fs::write(&output_path, data)?;
+        output_files.push(output_path);
+    }
+
+    Ok(output_files)
+}
+
+/// Outline items at depth `level` that resolve to a page, in outline order
+fn collect_bookmarks(items: &[OutlineItem], level: usize, out: &mut Vec<(usize, String)>) {
+    for item in items {
+        if level == 1 {
+            if let Some(page) = item.page {
+                out.push((page, item.title.clone()));
+            }
+        } else {
+            collect_bookmarks(&item.children, level - 1, out);
+        }
+    }
+}
+
+/// A file name stem from a bookmark title: letters, digits, `-` and `_`,
+/// with runs of anything else collapsed to a single `_`
+fn sanitize_file_name(title: &str) -> String {
+    let mut name = String::with_capacity(title.len());
+    for c in title.chars() {
+        if c.is_alphanumeric() || c == '-' || c == '_' {
+            name.push(c);
+        } else if !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    let name: String = name.trim_matches('_').chars().take(100).collect();
+    if name.is_empty() {
+        "section".to_string()
+    } else {
+        name
+    }
+}
+
 /// Crop a page to specified rectangle
 pub fn crop_page(
     input_path: &str,
@@ -366,6 +424,19 @@ pub fn reorder_pages(input_path: &str, page_order: &[usize], output_path: &str)
 /// println!("Merged {} pages", page_count);
 /// ```
 pub fn merge_pdf(input_paths: &[String], output_path: &str) -> Result<usize> {
+    merge_pdf_with_cookie(input_paths, output_path, None)
+}
+
+/// Merge multiple PDFs, checking `cookie` for cancellation before each input
+///
+/// The cookie's progress counts merged input files out of `input_paths.len()`.
+/// Returns [`EnhancedError::Aborted`] without writing `output_path` if the
+/// cookie is aborted.
+pub fn merge_pdf_with_cookie(
+    input_paths: &[String],
+    output_path: &str,
+    cookie: Option<&Cookie>,
+) -> Result<usize> {
     if input_paths.is_empty() {
         return Err(EnhancedError::InvalidParameter(
             "At least one input PDF is required".into(),
@@ -375,8 +446,18 @@ pub fn merge_pdf(input_paths: &[String], output_path: &str) -> Result<usize> {
     // Create merger
     let mut merger = PdfMerger::new();
 
+    if let Some(cookie) = cookie {
+        cookie.set_progress(0);
+        cookie.set_progress_max(input_paths.len() as i32);
+    }
+
     // Append each PDF
     for (idx, path) in input_paths.iter().enumerate() {
+        if let Some(cookie) = cookie.filter(|c| c.should_abort()) {
+            cookie.set_incomplete(true);
+            return Err(EnhancedError::Aborted);
+        }
+
         merger.append(path).map_err(|e| {
             EnhancedError::Generic(format!(
                 "Failed to append PDF #{} ({}): {}",
@@ -385,6 +466,10 @@ pub fn merge_pdf(input_paths: &[String], output_path: &str) -> Result<usize> {
                 e
             ))
         })?;
+
+        if let Some(cookie) = cookie {
+            cookie.inc_progress();
+        }
     }
 
     let page_count = merger.page_count();
@@ -398,6 +483,7 @@ pub fn merge_pdf(input_paths: &[String], output_path: &str) -> Result<usize> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
     use std::io::Write;
     use tempfile::{NamedTempFile, TempDir};
 
@@ -503,6 +589,98 @@ mod tests {
         Ok(())
     }
 
+    /// Six pages; top-level bookmarks at pages 1, 3 and 6 and a nested one
+    fn create_bookmarked_pdf() -> Result<NamedTempFile> {
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R /Outlines 3 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [10 0 R 11 0 R 12 0 R 13 0 R 14 0 R 15 0 R] /Count 6 \
+             /MediaBox [0 0 612 792] >>"
+                .to_string(),
+            "<< /Type /Outlines /First 4 0 R /Last 6 0 R /Count 3 >>".to_string(),
+            "<< /Title (Chapter 1: Intro) /Dest [10 0 R /Fit] /Next 5 0 R /First 7 0 R >>"
+                .to_string(),
+            "<< /Title (Chapter 2 / Body) /Dest [12 0 R /Fit] /Prev 4 0 R /Next 6 0 R >>"
+                .to_string(),
+            "<< /Title (Appendix) /Dest [15 0 R /Fit] /Prev 5 0 R >>".to_string(),
+            "<< /Title (Section 1.1) /Dest [11 0 R /Fit] >>".to_string(),
+            "<< /Font << /F1 << /Type /Font /Subtype /Type1 /BaseFont /Helvetica >> >> >>"
+                .to_string(),
+            "null".to_string(),
+        ];
+        for i in 0..6 {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /Resources 8 0 R /Contents {} 0 R >>",
+                16 + i
+            ));
+        }
+        for i in 0..6 {
+            let content = format!("BT /F1 12 Tf 72 700 Td (Page {}) Tj ET", i + 1);
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ));
+        }
+
+        let data = build_pdf(&objects);
+
+        let mut temp = NamedTempFile::new().map_err(|e| EnhancedError::Generic(e.to_string()))?;
+        temp.write_all(&data)
+            .map_err(|e| EnhancedError::Generic(e.to_string()))?;
+        temp.flush()
+            .map_err(|e| EnhancedError::Generic(e.to_string()))?;
+        Ok(temp)
+    }
+
+    #[test]
+    fn test_split_by_bookmarks() -> Result<()> {
+        let temp_input = create_bookmarked_pdf()?;
+        let temp_dir = TempDir::new().map_err(|e| EnhancedError::Generic(e.to_string()))?;
+        let dir = temp_dir.path().to_str().unwrap();
+
+        let files = split_by_bookmarks(temp_input.path().to_str().unwrap(), dir, 1)?;
+        let names: Vec<_> = files
+            .iter()
+            .map(|f| Path::new(f).file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            ["Chapter_1_Intro.pdf", "Chapter_2_Body.pdf", "Appendix.pdf"]
+        );
+
+        let expected: [&[usize]; 3] = [&[1, 2], &[3, 4, 5], &[6]];
+        for (file, pages) in files.iter().zip(expected) {
+            let doc = Document::open(file)?;
+            assert_eq!(doc.page_count()?, pages.len());
+            for (i, page_num) in pages.iter().enumerate() {
+                let page = doc.page(i)?;
+                let content = doc.page_contents(&page)?;
+                let marker = format!("(Page {})", page_num);
+                assert!(String::from_utf8_lossy(&content).contains(&marker));
+                assert!(page.dict().contains_key("Resources"));
+            }
+        }
+
+        // Level 2 has a single bookmark, so everything lands in one file
+        let nested = temp_dir.path().join("nested");
+        let files = split_by_bookmarks(
+            temp_input.path().to_str().unwrap(),
+            nested.to_str().unwrap(),
+            2,
+        )?;
+        assert_eq!(files.len(), 1);
+        assert_eq!(Document::open(&files[0])?.page_count()?, 6);
+
+        assert!(split_by_bookmarks(temp_input.path().to_str().unwrap(), dir, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("Chapter 2 / Body"), "Chapter_2_Body");
+        assert_eq!(sanitize_file_name("../.."), "section");
+        assert_eq!(sanitize_file_name("Résumé"), "Résumé");
+    }
+
     #[test]
     fn test_crop_page_invalid_box() -> Result<()> {
         let temp_input = create_test_pdf()?;
@@ -637,4 +815,77 @@ mod tests {
         );
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_merge_pdf_with_cookie() -> Result<()> {
+        let temp1 = create_test_pdf()?;
+        let temp2 = create_test_pdf()?;
+        let dir = TempDir::new().map_err(|e| EnhancedError::Generic(e.to_string()))?;
+        let output = dir.path().join("merged.pdf");
+        let output = output.to_str().unwrap();
+        let inputs = vec![
+            temp1.path().to_str().unwrap().to_string(),
+            temp2.path().to_str().unwrap().to_string(),
+        ];
+
+        let cookie = Cookie::new();
+        merge_pdf_with_cookie(&inputs, output, Some(&cookie))?;
+        assert_eq!((cookie.progress(), cookie.progress_max()), (2, 2));
+        fs::remove_file(output)?;
+
+        cookie.abort();
+        let result = merge_pdf_with_cookie(&inputs, output, Some(&cookie));
+        assert!(matches!(result, Err(EnhancedError::Aborted)));
+        assert!(cookie.is_incomplete());
+        assert!(!Path::new(output).exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_shares_embedded_fonts() -> Result<()> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] \
+             /Resources << /Font << /F1 4 0 R >> >> >>",
+            "<< /Type /Font /Subtype /TrueType /BaseFont /Template /FontDescriptor 5 0 R >>",
+            "<< /Type /FontDescriptor /FontName /Template /FontFile2 6 0 R >>",
+            "<< /Length 19 >>\nstream\nTEMPLATE FONT BYTES\nendstream",
+        ];
+        let data = build_pdf(&objects);
+
+        let dir = TempDir::new().map_err(|e| EnhancedError::Generic(e.to_string()))?;
+        let mut inputs = Vec::new();
+        for i in 0..3 {
+            let path = dir.path().join(format!("copy{i}.pdf"));
+            fs::write(&path, &data)?;
+            inputs.push(path.to_str().unwrap().to_string());
+        }
+        let output = dir.path().join("merged.pdf");
+        assert_eq!(merge_pdf(&inputs, output.to_str().unwrap())?, 3);
+
+        let merged = fs::read(&output)?;
+        let font_program = b"TEMPLATE FONT BYTES";
+        let count = merged
+            .windows(font_program.len())
+            .filter(|w| w == font_program)
+            .count();
+        assert_eq!(count, 1);
+
+        // Every page's descriptor points at the one font program
+        let doc = Document::open_bytes(merged)?;
+        assert_eq!(doc.page_count()?, 3);
+        let mut font_files = HashSet::new();
+        for index in 0..3 {
+            let page = doc.page(index)?;
+            let font = doc.resolve(&page.dict()["Resources"].as_dict().unwrap()["Font"])?;
+            let font = doc.resolve(&font.as_dict().unwrap()["F1"])?;
+            let desc = doc.resolve(&font.as_dict().unwrap()["FontDescriptor"])?;
+            font_files.insert(desc.as_dict().unwrap()["FontFile2"].as_obj_ref().unwrap());
+        }
+        assert_eq!(font_files.len(), 1);
+
+        Ok(())
+    }
 }
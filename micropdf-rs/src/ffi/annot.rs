@@ -3,7 +3,10 @@
 //! Provides FFI bindings for PDF annotation operations.
 
 use super::{Handle, HandleStore};
-use crate::pdf::annot::{AnnotFlags, AnnotType, Annotation};
+use crate::fitz::geometry::{Point, Quad};
+use crate::pdf::annot::{AnnotFlags, AnnotType, Annotation, LineEnding};
+use crate::pdf::content::{self, Operation};
+use crate::pdf::object::{Name, Object};
 use std::sync::LazyLock;
 
 /// Annotation storage
@@ -392,6 +395,51 @@ pub extern "C" fn pdf_set_annot_interior_color(
     }
 }
 
+// ============================================================================
+// Annotation Default Appearance
+// ============================================================================
+
+/// Set the default appearance (/DA) of free text: font resource name
+/// (e.g. "Helv"), font size, and a gray, RGB or CMYK text color
+///
+/// # Safety
+/// Caller must ensure font is a valid null-terminated C string and color
+/// points to n floats
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_set_annot_default_appearance(
+    _ctx: Handle,
+    annot: Handle,
+    font: *const std::ffi::c_char,
+    size: f32,
+    n: i32,
+    color: *const f32,
+) {
+    let font = super::safe_helpers::c_str_to_str(font).unwrap_or("Helv");
+    let color: &[f32] = match n {
+        1 | 3 | 4 if !color.is_null() => unsafe { std::slice::from_raw_parts(color, n as usize) },
+        _ => &[0.0],
+    };
+    let color_op = match color.len() {
+        1 => "g",
+        3 => "rg",
+        _ => "k",
+    };
+    let real = |v: f32| Object::Real(v as f64);
+    let ops = [
+        Operation::new("Tf", vec![Object::Name(Name::new(font)), real(size)]),
+        Operation::new(color_op, color.iter().map(|&c| real(c)).collect()),
+    ];
+    let da = String::from_utf8_lossy(&content::write_content(&ops))
+        .trim_end()
+        .replace('\n', " ");
+
+    if let Some(a) = ANNOTATIONS.get(annot) {
+        if let Ok(mut guard) = a.lock() {
+            guard.set_default_appearance(Some(da));
+        }
+    }
+}
+
 // ============================================================================
 // Annotation Line Properties
 // ============================================================================
@@ -440,6 +488,238 @@ pub extern "C" fn pdf_set_annot_line(
     }
 }
 
+fn line_ending_from_i32(value: i32) -> LineEnding {
+    match value {
+        1 => LineEnding::Square,
+        2 => LineEnding::Circle,
+        3 => LineEnding::Diamond,
+        4 => LineEnding::OpenArrow,
+        5 => LineEnding::ClosedArrow,
+        6 => LineEnding::Butt,
+        7 => LineEnding::ROpenArrow,
+        8 => LineEnding::RClosedArrow,
+        9 => LineEnding::Slash,
+        _ => LineEnding::None,
+    }
+}
+
+/// Get line ending styles
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_annot_line_ending_styles(
+    _ctx: Handle,
+    annot: Handle,
+    start: *mut i32,
+    end: *mut i32,
+) {
+    if start.is_null() || end.is_null() {
+        return;
+    }
+
+    if let Some(a) = ANNOTATIONS.get(annot) {
+        if let Ok(guard) = a.lock() {
+            let (s, e) = guard.line_endings();
+            unsafe {
+                *start = s as i32;
+                *end = e as i32;
+            }
+        }
+    }
+}
+
+/// Set line ending styles
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_set_annot_line_ending_styles(
+    _ctx: Handle,
+    annot: Handle,
+    start: i32,
+    end: i32,
+) {
+    if let Some(a) = ANNOTATIONS.get(annot) {
+        if let Ok(mut guard) = a.lock() {
+            guard.set_line_endings(line_ending_from_i32(start), line_ending_from_i32(end));
+        }
+    }
+}
+
+// ============================================================================
+// Annotation Quad Points
+// ============================================================================
+
+/// Get number of quad points
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_annot_quad_point_count(_ctx: Handle, annot: Handle) -> i32 {
+    if let Some(a) = ANNOTATIONS.get(annot) {
+        if let Ok(guard) = a.lock() {
+            return guard.quad_points().len() as i32;
+        }
+    }
+    0
+}
+
+/// Add a quad point
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_add_annot_quad_point(
+    _ctx: Handle,
+    annot: Handle,
+    quad: super::geometry::fz_quad,
+) {
+    let point = |p: super::geometry::fz_point| Point::new(p.x, p.y);
+    if let Some(a) = ANNOTATIONS.get(annot) {
+        if let Ok(mut guard) = a.lock() {
+            let mut quads = guard.quad_points().to_vec();
+            quads.push(Quad {
+                ul: point(quad.ul),
+                ur: point(quad.ur),
+                ll: point(quad.ll),
+                lr: point(quad.lr),
+            });
+            guard.set_quad_points(quads);
+        }
+    }
+}
+
+/// Remove all quad points
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_clear_annot_quad_points(_ctx: Handle, annot: Handle) {
+    if let Some(a) = ANNOTATIONS.get(annot) {
+        if let Ok(mut guard) = a.lock() {
+            guard.set_quad_points(Vec::new());
+        }
+    }
+}
+
+// ============================================================================
+// Annotation Ink List
+// ============================================================================
+
+/// Set ink strokes: `n` strokes, stroke `i` having `count[i]` points taken
+/// in order from `v`
+///
+/// # Safety
+/// Caller must ensure count points to n ints and v to their sum of points
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_set_annot_ink_list(
+    _ctx: Handle,
+    annot: Handle,
+    n: i32,
+    count: *const i32,
+    v: *const super::geometry::fz_point,
+) {
+    if n < 0 || (n > 0 && (count.is_null() || v.is_null())) {
+        return;
+    }
+    let counts: &[i32] = if n == 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(count, n as usize) }
+    };
+    let total: usize = counts.iter().map(|&c| c.max(0) as usize).sum();
+    let points: &[super::geometry::fz_point] = if total == 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(v, total) }
+    };
+
+    let mut rest = points;
+    let strokes = counts
+        .iter()
+        .map(|&c| {
+            let (stroke, tail) = rest.split_at(c.max(0) as usize);
+            rest = tail;
+            stroke.iter().map(|p| Point::new(p.x, p.y)).collect()
+        })
+        .collect();
+
+    if let Some(a) = ANNOTATIONS.get(annot) {
+        if let Ok(mut guard) = a.lock() {
+            guard.set_ink_list(strokes);
+        }
+    }
+}
+
+/// Get ink strokes into caller buffers: up to `max_strokes` point counts
+/// into `count` and up to `max_points` points into `v`
+///
+/// Returns the total number of strokes, which may exceed `max_strokes`.
+///
+/// # Safety
+/// Caller must ensure count holds max_strokes ints and v max_points points
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_annot_ink_list(
+    _ctx: Handle,
+    annot: Handle,
+    count: *mut i32,
+    max_strokes: i32,
+    v: *mut super::geometry::fz_point,
+    max_points: i32,
+) -> i32 {
+    let Some(a) = ANNOTATIONS.get(annot) else {
+        return 0;
+    };
+    let Ok(guard) = a.lock() else {
+        return 0;
+    };
+    let strokes = guard.ink_list();
+    if !count.is_null() {
+        for (i, stroke) in strokes.iter().take(max_strokes.max(0) as usize).enumerate() {
+            unsafe { *count.add(i) = stroke.len() as i32 };
+        }
+    }
+    if !v.is_null() {
+        let points = strokes.iter().flatten().take(max_points.max(0) as usize);
+        for (i, p) in points.enumerate() {
+            unsafe { *v.add(i) = super::geometry::fz_point { x: p.x, y: p.y } };
+        }
+    }
+    strokes.len() as i32
+}
+
+/// Get number of ink strokes
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_annot_ink_list_count(_ctx: Handle, annot: Handle) -> i32 {
+    if let Some(a) = ANNOTATIONS.get(annot) {
+        if let Ok(guard) = a.lock() {
+            return guard.ink_list().len() as i32;
+        }
+    }
+    0
+}
+
+/// Get number of points in ink stroke `i`
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_annot_ink_list_stroke_count(_ctx: Handle, annot: Handle, i: i32) -> i32 {
+    if let Some(a) = ANNOTATIONS.get(annot) {
+        if let Ok(guard) = a.lock() {
+            return usize::try_from(i)
+                .ok()
+                .and_then(|i| guard.ink_list().get(i))
+                .map_or(0, |s| s.len() as i32);
+        }
+    }
+    0
+}
+
+/// Get point `k` of ink stroke `i`
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_annot_ink_list_stroke_vertex(
+    _ctx: Handle,
+    annot: Handle,
+    i: i32,
+    k: i32,
+) -> super::geometry::fz_point {
+    if let Some(a) = ANNOTATIONS.get(annot) {
+        if let Ok(guard) = a.lock() {
+            let (Ok(i), Ok(k)) = (usize::try_from(i), usize::try_from(k)) else {
+                return super::geometry::fz_point { x: 0.0, y: 0.0 };
+            };
+            if let Some(p) = guard.ink_list().get(i).and_then(|s| s.get(k)) {
+                return super::geometry::fz_point { x: p.x, y: p.y };
+            }
+        }
+    }
+    super::geometry::fz_point { x: 0.0, y: 0.0 }
+}
+
 // ============================================================================
 // Annotation Border
 // ============================================================================
@@ -493,6 +773,9 @@ pub extern "C" fn pdf_annot_clear_dirty(_ctx: Handle, annot: Handle) {
 }
 
 /// Update annotation appearance
+///
+/// Regenerates the /AP /N stream for Square, Circle, Line and Highlight
+/// annotations.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_update_annot(_ctx: Handle, annot: Handle) -> i32 {
     if let Some(a) = ANNOTATIONS.get(annot) {
@@ -658,4 +941,112 @@ mod tests {
         pdf_drop_annot(0, annot);
         pdf_drop_annot(0, cloned);
     }
+
+    #[test]
+    fn test_update_annot_builds_appearance() {
+        let annot = pdf_create_annot(0, 0, 3); // Line
+        pdf_set_annot_line(
+            0,
+            annot,
+            super::super::geometry::fz_point { x: 0.0, y: 0.0 },
+            super::super::geometry::fz_point { x: 50.0, y: 50.0 },
+        );
+        pdf_set_annot_color(0, annot, 3, [0.0f32, 0.5, 0.0].as_ptr());
+        pdf_set_annot_line_ending_styles(0, annot, 4, 9);
+        let (mut start, mut end) = (0, 0);
+        pdf_annot_line_ending_styles(0, annot, &mut start, &mut end);
+        assert_eq!((start, end), (4, 9));
+
+        assert_eq!(pdf_update_annot(0, annot), 1);
+        assert_eq!(pdf_annot_has_dirty(0, annot), 0);
+        let a = ANNOTATIONS.get(annot).unwrap();
+        let guard = a.lock().unwrap();
+        let (_, data) = guard.appearance().unwrap().as_stream().unwrap();
+        assert!(data.windows(2).any(|w| w == b"RG"));
+        drop(guard);
+        pdf_drop_annot(0, annot);
+    }
+
+    #[test]
+    fn test_free_text_default_appearance() {
+        let annot = pdf_create_annot(0, 0, 2); // FreeText
+        pdf_set_annot_rect(
+            0,
+            annot,
+            super::super::geometry::fz_rect {
+                x0: 10.0,
+                y0: 10.0,
+                x1: 200.0,
+                y1: 50.0,
+            },
+        );
+        pdf_set_annot_contents(0, annot, c"Hello".as_ptr());
+        pdf_set_annot_default_appearance(
+            0,
+            annot,
+            c"TiRo".as_ptr(),
+            14.0,
+            3,
+            [1.0f32, 0.0, 0.0].as_ptr(),
+        );
+        assert_eq!(pdf_update_annot(0, annot), 1);
+
+        let a = ANNOTATIONS.get(annot).unwrap();
+        let guard = a.lock().unwrap();
+        assert_eq!(guard.default_appearance(), Some("/TiRo 14 Tf 1 0 0 rg"));
+        let (_, data) = guard.appearance().unwrap().as_stream().unwrap();
+        let content = String::from_utf8_lossy(data);
+        assert!(content.contains("BT\n1 0 0 rg\n/TiRo 14 Tf"));
+        assert!(content.contains("(Hello) Tj\nET"));
+        drop(guard);
+        pdf_drop_annot(0, annot);
+    }
+
+    #[test]
+    fn test_ink_list_round_trip() {
+        use super::super::geometry::fz_point;
+
+        let annot = pdf_create_annot(0, 0, 15); // Ink
+        pdf_set_annot_color(0, annot, 3, [1.0f32, 0.0, 0.0].as_ptr());
+        pdf_set_annot_border_width(0, annot, 2.0);
+        let counts = [3, 2];
+        let points = [
+            fz_point { x: 10.0, y: 10.0 },
+            fz_point { x: 20.0, y: 30.0 },
+            fz_point { x: 30.0, y: 10.0 },
+            fz_point { x: 40.0, y: 40.0 },
+            fz_point { x: 50.0, y: 45.0 },
+        ];
+        pdf_set_annot_ink_list(0, annot, 2, counts.as_ptr(), points.as_ptr());
+
+        assert_eq!(pdf_annot_ink_list_count(0, annot), 2);
+        assert_eq!(pdf_annot_ink_list_stroke_count(0, annot, 1), 2);
+        let p = pdf_annot_ink_list_stroke_vertex(0, annot, 1, 1);
+        assert_eq!((p.x, p.y), (50.0, 45.0));
+
+        let mut out_counts = [0i32; 2];
+        let mut out_points = [fz_point { x: 0.0, y: 0.0 }; 5];
+        let n = pdf_annot_ink_list(
+            0,
+            annot,
+            out_counts.as_mut_ptr(),
+            2,
+            out_points.as_mut_ptr(),
+            5,
+        );
+        assert_eq!(n, 2);
+        assert_eq!(out_counts, counts);
+        assert_eq!((out_points[2].x, out_points[2].y), (30.0, 10.0));
+
+        assert_eq!(pdf_update_annot(0, annot), 1);
+        let a = ANNOTATIONS.get(annot).unwrap();
+        let guard = a.lock().unwrap();
+        let (_, data) = guard.appearance().unwrap().as_stream().unwrap();
+        let content = String::from_utf8_lossy(data);
+        assert!(content.contains("1 0 0 RG\n2 w"));
+        assert!(content.contains("10 10 m\n20 30 l\n30 10 l\n"));
+        assert!(content.contains("40 40 m\n50 45 l\nS"));
+        drop(guard);
+        pdf_drop_annot(0, annot);
+    }
 }
@@ -144,6 +144,28 @@ pub extern "C" fn fz_list_archive_entry(
     -1
 }
 
+/// Get the number of entries in an archive
+///
+/// Same as `fz_count_archive_entries`.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_archive_count_entries(_ctx: Handle, archive: Handle) -> i32 {
+    fz_count_archive_entries(_ctx, archive)
+}
+
+/// Get the name of an archive entry by index
+///
+/// Same as `fz_list_archive_entry`.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_archive_entry_name(
+    _ctx: Handle,
+    archive: Handle,
+    idx: i32,
+    buf: *mut c_char,
+    bufsize: i32,
+) -> i32 {
+    fz_list_archive_entry(_ctx, archive, idx, buf, bufsize)
+}
+
 /// Check if an archive has a specific entry
 ///
 /// # Arguments
@@ -405,6 +427,104 @@ mod tests {
         cleanup_test_dir(&test_dir);
     }
 
+    /// A ZIP archive with a deflated entry, a stored entry and an entry
+    /// that would escape the archive
+    fn zip_archive() -> Vec<u8> {
+        use flate2::write::DeflateEncoder;
+        use flate2::{Compression, Crc};
+        use std::io::Write;
+
+        let entries: [(&str, &[u8], bool); 3] = [
+            ("doc.pdf", b"%PDF-1.7 packaged document %PDF-1.7", true),
+            ("notes/readme.txt", b"stored entry", false),
+            ("../evil.txt", b"escaped", false),
+        ];
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, data, deflate) in entries {
+            let stored = if deflate {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            } else {
+                data.to_vec()
+            };
+            let mut crc = Crc::new();
+            crc.update(data);
+            let method: u16 = if deflate { 8 } else { 0 };
+            // Fields shared by the local and central headers, from the
+            // compression method to the extra field length
+            let mut common = Vec::new();
+            common.extend_from_slice(&method.to_le_bytes());
+            common.extend_from_slice(&[0; 4]); // time, date
+            common.extend_from_slice(&crc.sum().to_le_bytes());
+            common.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            common.extend_from_slice(&0u16.to_le_bytes());
+
+            let offset = out.len() as u32;
+            out.extend_from_slice(&[0x50, 0x4b, 0x03, 0x04, 20, 0, 0, 0]);
+            out.extend_from_slice(&common);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&stored);
+
+            central.extend_from_slice(&[0x50, 0x4b, 0x01, 0x02, 20, 0, 20, 0, 0, 0]);
+            central.extend_from_slice(&common);
+            central.extend_from_slice(&[0; 10]); // comment, disk, attributes
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let cd_offset = out.len() as u32;
+        out.extend_from_slice(&central);
+        out.extend_from_slice(&[0x50, 0x4b, 0x05, 0x06, 0, 0, 0, 0]);
+        out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        out.extend_from_slice(&cd_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn test_zip_archive_entries() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), zip_archive()).unwrap();
+        let path = std::ffi::CString::new(file.path().to_str().unwrap()).unwrap();
+
+        let archive = fz_open_archive(0, path.as_ptr());
+        assert_ne!(archive, 0);
+        assert_eq!(fz_archive_format(0, archive), 1);
+
+        // The entry with a `..` component is rejected
+        assert_eq!(fz_archive_count_entries(0, archive), 2);
+        let mut buf = [0 as c_char; 64];
+        let names: Vec<String> = (0..2)
+            .map(|i| {
+                assert!(fz_archive_entry_name(0, archive, i, buf.as_mut_ptr(), 64) > 0);
+                unsafe { CStr::from_ptr(buf.as_ptr()) }
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        assert_eq!(names, ["doc.pdf", "notes/readme.txt"]);
+        let evil = std::ffi::CString::new("../evil.txt").unwrap();
+        assert_eq!(fz_read_archive_entry(0, archive, evil.as_ptr()), 0);
+
+        for (name, expected) in [
+            ("doc.pdf", &b"%PDF-1.7 packaged document %PDF-1.7"[..]),
+            ("notes/readme.txt", b"stored entry"),
+        ] {
+            let name = std::ffi::CString::new(name).unwrap();
+            let buffer = fz_read_archive_entry(0, archive, name.as_ptr());
+            assert_ne!(buffer, 0);
+            let data = super::super::BUFFERS.get(buffer).unwrap();
+            assert_eq!(data.lock().unwrap().data(), expected);
+            super::super::buffer::fz_drop_buffer(0, buffer);
+        }
+        fz_drop_archive(0, archive);
+    }
+
     #[test]
     fn test_open_archive_with_null_path() {
         let archive = fz_open_archive(0, std::ptr::null());
@@ -1,7 +1,11 @@
 //! C FFI for band-based output - MuPDF compatible
 //! Safe Rust implementation of fz_band_writer
 
+use super::output::OUTPUTS;
 use super::{Handle, HandleStore};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use std::io::Write;
 use std::sync::LazyLock;
 
 /// A wrapper for a raw pointer that implements Send + Sync.
@@ -141,10 +145,134 @@ pub struct BandWriter {
     pub progress_fn: Option<ProgressCallback>,
     /// Progress callback user data (wrapped for Send+Sync)
     pub progress_data: SendPtr,
-    /// Accumulated output data
+    /// Rows written so far
+    pub rows_written: i32,
+    /// Zlib stream feeding the PNG IDAT chunks
+    pub encoder: Option<ZlibEncoder<Vec<u8>>>,
+    /// Accumulated output data (used when no output stream is attached)
     pub output_buffer: Vec<u8>,
 }
 
+impl BandWriter {
+    /// Send encoded bytes to the attached output, or keep them in memory
+    fn emit(&mut self, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        self.bytes_written += data.len();
+        if let Some(out) = OUTPUTS.get(self.output) {
+            if let Ok(mut guard) = out.lock() {
+                if guard.write_data(data).is_ok() {
+                    return;
+                }
+            }
+        }
+        self.output_buffer.extend_from_slice(data);
+    }
+
+    /// Write `rows` scanlines spaced `stride` bytes apart
+    fn write_rows(&mut self, stride: usize, rows: i32, samples: &[u8]) -> bool {
+        if self.state != BandWriterState::HeaderWritten
+            && self.state != BandWriterState::WritingBands
+        {
+            return false;
+        }
+
+        let components = self.config.n + if self.config.alpha { 1 } else { 0 };
+        let row_size = (self.config.width * components).max(0) as usize;
+        let rows = if self.config.height > 0 {
+            rows.min(self.config.height - self.rows_written)
+        } else {
+            rows
+        };
+        if rows <= 0 || stride < row_size {
+            return false;
+        }
+
+        self.state = BandWriterState::WritingBands;
+
+        let mut encoded = Vec::with_capacity((row_size + 1) * rows as usize);
+        for row in samples.chunks(stride).take(rows as usize) {
+            let row = &row[..row_size];
+            match self.config.format {
+                BandFormat::PNG => {
+                    // Filter type 0 (None) precedes every scanline
+                    encoded.push(0);
+                    encoded.extend_from_slice(row);
+                }
+                _ => encoded.extend_from_slice(row),
+            }
+        }
+
+        if self.config.format == BandFormat::PNG {
+            let level = self.config.compression.clamp(0, 9) as u32;
+            let encoder = self
+                .encoder
+                .get_or_insert_with(|| ZlibEncoder::new(Vec::new(), Compression::new(level)));
+            if encoder.write_all(&encoded).is_err() {
+                self.state = BandWriterState::Error;
+                return false;
+            }
+            let compressed = std::mem::take(encoder.get_mut());
+            if !compressed.is_empty() {
+                let mut chunk = Vec::new();
+                write_png_chunk(&mut chunk, b"IDAT", &compressed);
+                self.emit(&chunk);
+            }
+        } else {
+            self.emit(&encoded);
+        }
+
+        self.rows_written += rows;
+        self.current_band += 1;
+
+        if let Some(callback) = self.progress_fn {
+            callback(
+                self.current_band,
+                self.total_bands,
+                self.progress_data.as_ptr(),
+            );
+        }
+
+        if self.current_band >= self.total_bands
+            || (self.config.height > 0 && self.rows_written >= self.config.height)
+        {
+            self.state = BandWriterState::BandsComplete;
+        }
+
+        true
+    }
+
+    /// Finish the image: flush the compressor and write the trailer
+    fn finish(&mut self) -> bool {
+        if self.state != BandWriterState::BandsComplete {
+            return false;
+        }
+
+        if let Some(encoder) = self.encoder.take() {
+            match encoder.finish() {
+                Ok(rest) => {
+                    let mut chunk = Vec::new();
+                    write_png_chunk(&mut chunk, b"IDAT", &rest);
+                    self.emit(&chunk);
+                }
+                Err(_) => {
+                    self.state = BandWriterState::Error;
+                    return false;
+                }
+            }
+        }
+
+        let trailer = match self.config.format {
+            BandFormat::PNG => generate_png_trailer(),
+            _ => Vec::new(),
+        };
+        self.emit(&trailer);
+        self.state = BandWriterState::Complete;
+        true
+    }
+}
+
 impl Default for BandWriter {
     fn default() -> Self {
         Self {
@@ -157,6 +285,8 @@ impl Default for BandWriter {
             bytes_written: 0,
             progress_fn: None,
             progress_data: SendPtr::new(std::ptr::null_mut()),
+            rows_written: 0,
+            encoder: None,
             output_buffer: Vec::new(),
         }
     }
@@ -196,6 +326,15 @@ pub extern "C" fn fz_new_band_writer(_ctx: Handle, output: Handle, format: i32)
     BAND_WRITERS.insert(writer)
 }
 
+/// Create a PNG band writer streaming to `output`
+///
+/// Declare the page size with `fz_band_writer_set_dimensions` and
+/// `fz_band_writer_set_components` before writing the header.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_new_png_band_writer(_ctx: Handle, output: Handle) -> Handle {
+    fz_new_band_writer(_ctx, output, BandFormat::PNG as i32)
+}
+
 /// Create band writer with full configuration
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_new_band_writer_with_config(
@@ -370,8 +509,7 @@ pub extern "C" fn fz_band_writer_write_header(_ctx: Handle, writer: Handle) -> i
                 _ => Vec::new(),
             };
 
-            guard.output_buffer.extend_from_slice(&header);
-            guard.bytes_written += header.len();
+            guard.emit(&header);
             guard.state = BandWriterState::HeaderWritten;
 
             return 1;
@@ -397,40 +535,37 @@ pub extern "C" fn fz_band_writer_write_band(
 
     if let Some(w) = BAND_WRITERS.get(writer) {
         if let Ok(mut guard) = w.lock() {
-            if guard.state != BandWriterState::HeaderWritten
-                && guard.state != BandWriterState::WritingBands
-            {
-                return 0;
-            }
-
-            guard.state = BandWriterState::WritingBands;
-
             let components = guard.config.n + if guard.config.alpha { 1 } else { 0 };
-            let row_size = (guard.config.width * components) as usize;
-            let band_size = row_size * band_rows as usize;
-
-            let band_data = unsafe { std::slice::from_raw_parts(data, band_size) };
-
-            // For raw format, just append data
-            // For other formats, encoding would happen here
-            guard.output_buffer.extend_from_slice(band_data);
-            guard.bytes_written += band_size;
-            guard.current_band += 1;
-
-            // Call progress callback
-            if let Some(callback) = guard.progress_fn {
-                callback(
-                    guard.current_band,
-                    guard.total_bands,
-                    guard.progress_data.as_ptr(),
-                );
-            }
+            let row_size = (guard.config.width * components).max(0) as usize;
+            let band_data =
+                unsafe { std::slice::from_raw_parts(data, row_size * band_rows as usize) };
+            return guard.write_rows(row_size, band_rows, band_data) as i32;
+        }
+    }
+    0
+}
 
-            if guard.current_band >= guard.total_bands {
-                guard.state = BandWriterState::BandsComplete;
-            }
+/// Write a band of `band_height` rows whose scanlines are `stride` bytes apart
+///
+/// # Safety
+/// `samples` must point to at least `stride * band_height` bytes.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_write_band(
+    _ctx: Handle,
+    writer: Handle,
+    stride: i32,
+    band_height: i32,
+    samples: *const u8,
+) -> i32 {
+    if samples.is_null() || stride <= 0 || band_height <= 0 {
+        return 0;
+    }
 
-            return 1;
+    if let Some(w) = BAND_WRITERS.get(writer) {
+        if let Ok(mut guard) = w.lock() {
+            let len = stride as usize * band_height as usize;
+            let band_data = unsafe { std::slice::from_raw_parts(samples, len) };
+            return guard.write_rows(stride as usize, band_height, band_data) as i32;
         }
     }
     0
@@ -441,26 +576,18 @@ pub extern "C" fn fz_band_writer_write_band(
 pub extern "C" fn fz_band_writer_write_trailer(_ctx: Handle, writer: Handle) -> i32 {
     if let Some(w) = BAND_WRITERS.get(writer) {
         if let Ok(mut guard) = w.lock() {
-            if guard.state != BandWriterState::BandsComplete {
-                return 0;
-            }
-
-            // Generate trailer based on format
-            let trailer = match guard.config.format {
-                BandFormat::PNG => generate_png_trailer(),
-                _ => Vec::new(),
-            };
-
-            guard.output_buffer.extend_from_slice(&trailer);
-            guard.bytes_written += trailer.len();
-            guard.state = BandWriterState::Complete;
-
-            return 1;
+            return guard.finish() as i32;
         }
     }
     0
 }
 
+/// Close a band writer, flushing the compressed data and writing the trailer
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_close_band_writer(_ctx: Handle, writer: Handle) -> i32 {
+    fz_band_writer_write_trailer(_ctx, writer)
+}
+
 // ============================================================================
 // Query Functions
 // ============================================================================
@@ -590,9 +717,11 @@ fn write_png_chunk(output: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
     output.extend_from_slice(chunk_type);
     output.extend_from_slice(data);
 
-    // CRC (simplified - just use 0 for now)
-    let crc = 0u32;
-    output.extend_from_slice(&crc.to_be_bytes());
+    // CRC covers the chunk type and data
+    let mut crc = flate2::Crc::new();
+    crc.update(chunk_type);
+    crc.update(data);
+    output.extend_from_slice(&crc.sum().to_be_bytes());
 }
 
 fn generate_pnm_header(config: &BandWriterConfig) -> Vec<u8> {
@@ -727,4 +856,46 @@ mod tests {
 
         fz_drop_band_writer(0, writer);
     }
+
+    #[test]
+    fn test_png_bands_decode() {
+        let writer = fz_new_png_band_writer(0, 0);
+        fz_band_writer_set_dimensions(0, writer, 4, 6);
+        fz_band_writer_set_components(0, writer, 3, 0);
+        fz_band_writer_set_rows_per_band(0, writer, 2);
+        assert_eq!(fz_band_writer_write_header(0, writer), 1);
+
+        // Three bands of two rows; each row padded to a 16-byte stride
+        let stride = 16;
+        for band in 0..3u8 {
+            let mut samples = vec![0xEEu8; stride * 2];
+            for row in 0..2 {
+                for x in 0..4 {
+                    let p = row * stride + x * 3;
+                    samples[p..p + 3].copy_from_slice(&[band * 100, x as u8 * 60, row as u8]);
+                }
+            }
+            assert_eq!(
+                fz_write_band(0, writer, stride as i32, 2, samples.as_ptr()),
+                1
+            );
+        }
+        assert_eq!(fz_close_band_writer(0, writer), 1);
+
+        let mut size = 0usize;
+        let ptr = fz_band_writer_get_output(0, writer, &mut size);
+        let png = unsafe { std::slice::from_raw_parts(ptr, size) }.to_vec();
+        fz_drop_band_writer(0, writer);
+
+        let decoded = image::load_from_memory_with_format(&png, image::ImageFormat::Png)
+            .unwrap()
+            .to_rgb8();
+        assert_eq!(decoded.dimensions(), (4, 6));
+        for y in 0..6u32 {
+            for x in 0..4u32 {
+                let expected = [(y / 2) as u8 * 100, x as u8 * 60, (y % 2) as u8];
+                assert_eq!(decoded.get_pixel(x, y).0, expected);
+            }
+        }
+    }
 }
@@ -4,9 +4,18 @@
 //! Code 39, Code 128, EAN, UPC, and more.
 
 use crate::ffi::colorspace::FZ_COLORSPACE_GRAY;
+use crate::ffi::document::PAGES;
+use crate::ffi::geometry::fz_rect;
 use crate::ffi::pixmap::Pixmap;
-use crate::ffi::{Handle, PIXMAPS};
+use crate::ffi::{DOCUMENTS, Handle, PIXMAPS};
+use crate::fitz::error::{Error, Result};
+use crate::fitz::geometry::Rect;
+use crate::pdf::document::Document;
+use crate::pdf::object::{Dict, ObjRef, Object};
+use crate::pdf::write;
+use std::collections::BTreeMap;
 use std::ffi::{CStr, CString, c_char};
+use std::fmt::Write as _;
 use std::ptr;
 
 // ============================================================================
@@ -166,6 +175,26 @@ impl BarcodeType {
     }
 }
 
+/// Barcodes that can be drawn into page content with [`pdf_insert_barcode`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarcodeKind {
+    /// Code 128, drawn as one rectangle per bar
+    Code128 = 0,
+    /// QR code, drawn as a grid of filled cells
+    QrCode = 1,
+}
+
+impl BarcodeKind {
+    pub fn from_i32(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(BarcodeKind::Code128),
+            1 => Some(BarcodeKind::QrCode),
+            _ => None,
+        }
+    }
+}
+
 /// Error correction level for 2D barcodes
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -352,6 +381,18 @@ fn generate_1d_barcode(data: &str, barcode_type: BarcodeType) -> Option<Vec<bool
 }
 
 fn generate_code128(data: &str) -> Option<Vec<bool>> {
+    let symbol = code128_symbol(data)?;
+
+    // 10-module quiet zone on both sides
+    let mut result = vec![false; 10];
+    result.extend(symbol);
+    result.extend([false; 10]);
+    Some(result)
+}
+
+/// Code 128 modules from the start code to the stop code, or None if
+/// `data` has characters outside Code Set B
+fn code128_symbol(data: &str) -> Option<Vec<bool>> {
     // Code 128 patterns (Code Set B)
     let patterns: [u16; 107] = [
         0b11011001100,   // 0: space
@@ -465,11 +506,6 @@ fn generate_code128(data: &str) -> Option<Vec<bool>> {
 
     let mut result = Vec::new();
 
-    // Quiet zone
-    for _ in 0..10 {
-        result.push(false);
-    }
-
     // Start code B (104)
     let start = patterns[104];
     for i in (0..11).rev() {
@@ -479,11 +515,11 @@ fn generate_code128(data: &str) -> Option<Vec<bool>> {
     // Encode data
     let mut checksum = 104;
     for (pos, c) in data.chars().enumerate() {
-        let value = if c >= ' ' && c <= '~' {
-            (c as usize) - 32
-        } else {
-            0
-        };
+        // Code Set B covers printable ASCII only
+        if !(' '..='~').contains(&c) {
+            return None;
+        }
+        let value = (c as usize) - 32;
         let pattern = patterns[value];
         for i in (0..11).rev() {
             result.push((pattern >> i) & 1 == 1);
@@ -503,11 +539,6 @@ fn generate_code128(data: &str) -> Option<Vec<bool>> {
         result.push((stop >> i) & 1 == 1);
     }
 
-    // Quiet zone
-    for _ in 0..10 {
-        result.push(false);
-    }
-
     Some(result)
 }
 
@@ -758,6 +789,135 @@ fn generate_upca(data: &str) -> Option<Vec<bool>> {
     generate_ean13(&padded)
 }
 
+// ============================================================================
+// Page Content
+// ============================================================================
+
+/// Content stream operators painting a barcode into `rect`, in PDF user
+/// space. Code 128 bars span the full rectangle; QR cells are square and
+/// centered in it.
+pub fn barcode_content(kind: BarcodeKind, data: &str, rect: Rect) -> Result<Vec<u8>> {
+    if data.is_empty() {
+        return Err(Error::argument("empty barcode data"));
+    }
+    if rect.is_empty() {
+        return Err(Error::argument("empty barcode rectangle"));
+    }
+    let mut out = String::from("q\n0 g\n");
+    match kind {
+        BarcodeKind::Code128 => {
+            let modules = code128_symbol(data).ok_or_else(|| {
+                Error::argument("Code 128 data must be printable ASCII characters")
+            })?;
+            let module_width = rect.width() / modules.len() as f32;
+            for (start, len) in dark_runs(&modules) {
+                let _ = writeln!(
+                    out,
+                    "{} {} {} {} re",
+                    rect.x0 + start as f32 * module_width,
+                    rect.y0,
+                    len as f32 * module_width,
+                    rect.height()
+                );
+            }
+        }
+        BarcodeKind::QrCode => {
+            let matrix = generate_qr_matrix(data, 0, EcLevel::M)
+                .ok_or_else(|| Error::argument("QR code data must be 1 to 2953 bytes"))?;
+            let n = matrix.len() as f32;
+            let cell = rect.width().min(rect.height()) / n;
+            let x0 = rect.x0 + (rect.width() - cell * n) / 2.0;
+            // Row 0 is the top of the symbol
+            let top = rect.y1 - (rect.height() - cell * n) / 2.0;
+            for (row, cells) in matrix.iter().enumerate() {
+                let y = top - (row + 1) as f32 * cell;
+                for (start, len) in dark_runs(cells) {
+                    let _ = writeln!(
+                        out,
+                        "{} {} {} {} re",
+                        x0 + start as f32 * cell,
+                        y,
+                        len as f32 * cell,
+                        cell
+                    );
+                }
+            }
+        }
+    }
+    out.push_str("f\nQ\n");
+    Ok(out.into_bytes())
+}
+
+/// `(start, length)` of each run of dark modules
+fn dark_runs(modules: &[bool]) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i < modules.len() {
+        if !modules[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < modules.len() && modules[i] {
+            i += 1;
+        }
+        runs.push((start, i - start));
+    }
+    runs
+}
+
+/// Objects that add a barcode to a page: the existing content is wrapped
+/// in `q`/`Q` so its graphics state cannot affect the barcode
+pub fn insert_barcode(
+    doc: &Document,
+    index: usize,
+    kind: BarcodeKind,
+    data: &str,
+    rect: Rect,
+) -> Result<BTreeMap<ObjRef, Object>> {
+    if doc.is_encrypted() {
+        return Err(Error::unsupported("editing encrypted documents"));
+    }
+    let barcode = barcode_content(kind, data, rect)?;
+    let page = doc.page(index)?;
+    let Object::Dict(mut page_dict) = doc.load_object(page.obj_ref())? else {
+        return Err(Error::format("page is not a dictionary"));
+    };
+
+    let next_num = doc
+        .trailer()
+        .get("Size")
+        .and_then(Object::as_int)
+        .unwrap_or(1)
+        .max(1) as i32;
+    let before = ObjRef::new(next_num, 0);
+    let after = ObjRef::new(next_num + 1, 0);
+
+    let mut contents = vec![Object::Ref(before)];
+    if let Some(existing) = page_dict.get("Contents") {
+        match doc.resolve(existing)? {
+            Object::Array(streams) => contents.extend(streams),
+            _ => contents.push(existing.clone()),
+        }
+    }
+    contents.push(Object::Ref(after));
+    page_dict.insert("Contents".into(), Object::Array(contents));
+
+    let mut changes = BTreeMap::new();
+    changes.insert(page.obj_ref(), Object::Dict(page_dict));
+    changes.insert(before, content_stream(b"q\n".to_vec()));
+    let mut closing = b"Q\n".to_vec();
+    closing.extend_from_slice(&barcode);
+    changes.insert(after, content_stream(closing));
+    Ok(changes)
+}
+
+fn content_stream(data: Vec<u8>) -> Object {
+    let mut dict = Dict::new();
+    dict.insert("Length".into(), Object::Int(data.len() as i64));
+    Object::Stream { dict, data }
+}
+
 // ============================================================================
 // FFI Functions
 // ============================================================================
@@ -1099,6 +1259,50 @@ pub extern "C" fn fz_barcode_check_digit(barcode_type: i32, value: *const c_char
     }
 }
 
+/// Draw a barcode of `kind` (a [`BarcodeKind`]) into `rect` on a page,
+/// in PDF user space. The page's document is updated incrementally.
+/// Returns 0 on success, or -1 for an invalid handle or kind, or data the
+/// symbology cannot encode.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_insert_barcode(
+    _ctx: Handle,
+    page: Handle,
+    kind: i32,
+    data: *const c_char,
+    rect: fz_rect,
+) -> i32 {
+    if data.is_null() {
+        return -1;
+    }
+    let Ok(data) = unsafe { CStr::from_ptr(data) }.to_str() else {
+        return -1;
+    };
+    let Some(kind) = BarcodeKind::from_i32(kind) else {
+        return -1;
+    };
+    let Some(page) = PAGES.get(page) else {
+        return -1;
+    };
+    let (doc, page_num) = {
+        let page = page.lock().unwrap();
+        (page.doc_handle, page.page_num)
+    };
+    let rect = Rect::new(rect.x0, rect.y0, rect.x1, rect.y1);
+    let result = (|| -> Result<()> {
+        let doc = DOCUMENTS
+            .get(doc)
+            .ok_or_else(|| Error::argument("invalid document handle"))?;
+        let mut doc = doc.lock().unwrap();
+        let pdf = doc.pdf()?;
+        let changes = insert_barcode(&pdf, page_num.max(0) as usize, kind, data, rect)?;
+        let mut out = Vec::new();
+        write::append_incremental(pdf.data(), &changes, &mut out)?;
+        doc.set_data(out);
+        Ok(())
+    })();
+    if result.is_ok() { 0 } else { -1 }
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -1106,6 +1310,7 @@ pub extern "C" fn fz_barcode_check_digit(barcode_type: i32, value: *const c_char
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
 
     #[test]
     fn test_barcode_type_enum() {
@@ -1313,4 +1518,68 @@ mod tests {
             0
         );
     }
+
+    #[test]
+    fn test_code128_hello_modules() {
+        // Start B, five data symbols and the check symbol are 11 modules
+        // with three bars each; the stop code is 13 modules with four bars
+        let symbol = code128_symbol("HELLO").unwrap();
+        assert_eq!(symbol.len(), 11 * 7 + 13);
+        assert_eq!(dark_runs(&symbol).len(), 3 * 7 + 4);
+        assert_eq!(generate_code128("HELLO").unwrap().len(), 11 * 7 + 13 + 20);
+
+        let rect = Rect::new(0.0, 0.0, 180.0, 40.0);
+        let content = barcode_content(BarcodeKind::Code128, "HELLO", rect).unwrap();
+        let ops = crate::pdf::content::parse_content(&content).unwrap();
+        assert_eq!(ops.iter().filter(|op| op.operator == "re").count(), 25);
+        // Bars are two points per module
+        assert_eq!(ops[2].operator, "re");
+        assert_eq!(ops[2].operands[2].as_real(), Some(4.0));
+
+        assert!(barcode_content(BarcodeKind::Code128, "H\u{e9}LLO", rect).is_err());
+        assert!(barcode_content(BarcodeKind::Code128, "", rect).is_err());
+        assert!(barcode_content(BarcodeKind::QrCode, "HELLO", rect).is_ok());
+    }
+
+    #[test]
+    fn test_ffi_insert_barcode() {
+        let content = "0.5 0 0 0.5 0 0 cm";
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R >>".to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ),
+        ];
+        let data = build_pdf(&objects);
+
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(data));
+        let page = PAGES.insert(crate::ffi::document::Page::new(doc, 0));
+        let rect = fz_rect {
+            x0: 72.0,
+            y0: 72.0,
+            x1: 252.0,
+            y1: 112.0,
+        };
+        let hello = CString::new("HELLO").unwrap();
+        assert_eq!(pdf_insert_barcode(0, page, 0, hello.as_ptr(), rect), 0);
+        let bad = CString::new("H\u{e9}LLO").unwrap();
+        assert_eq!(pdf_insert_barcode(0, page, 0, bad.as_ptr(), rect), -1);
+        assert_eq!(pdf_insert_barcode(0, page, 7, hello.as_ptr(), rect), -1);
+
+        let bytes = DOCUMENTS.get(doc).unwrap().lock().unwrap().data().to_vec();
+        let pdf = Document::open_bytes(bytes).unwrap();
+        let ops =
+            crate::pdf::content::parse_content(&pdf.page_contents(&pdf.page(0).unwrap()).unwrap())
+                .unwrap();
+        let names: Vec<&str> = ops.iter().map(|op| op.operator.as_str()).collect();
+        // The original scaling is undone before the barcode is drawn
+        assert_eq!(&names[..4], ["q", "cm", "Q", "q"]);
+        assert_eq!(names.iter().filter(|&&op| op == "re").count(), 25);
+
+        PAGES.remove(page);
+        DOCUMENTS.remove(doc);
+    }
 }
@@ -172,18 +172,23 @@ fn get_bidi_type(ch: u32) -> BidiCharType {
         // ASCII digits
         0x0030..=0x0039 => BidiCharType::EN,
 
-        // ASCII punctuation (mostly neutral)
-        0x0020 => BidiCharType::WS,
-        0x0009 => BidiCharType::S,          // Tab
-        0x000A | 0x000D => BidiCharType::B, // Newline, CR
-        0x0021..=0x002F | 0x003A..=0x0040 | 0x005B..=0x0060 | 0x007B..=0x007E => BidiCharType::ON,
+        // ASCII whitespace and separators
+        0x0020 | 0x000C => BidiCharType::WS,
+        0x0009 | 0x000B | 0x001F => BidiCharType::S, // Tab
+        0x000A | 0x000D | 0x001C..=0x001E | 0x0085 | 0x2029 => BidiCharType::B,
 
         // European number separators/terminators
-        0x002B | 0x002D => BidiCharType::ES,          // + -
-        0x0025 | 0x00A2..=0x00A5 => BidiCharType::ET, // % and currency
+        0x002B | 0x002D => BidiCharType::ES, // + -
+        0x0023..=0x0025 | 0x00A2..=0x00A5 | 0x00B0 | 0x00B1 | 0x20A0..=0x20CF => {
+            BidiCharType::ET // # $ %, currency and degree signs
+        }
 
         // Common separators
-        0x002C | 0x002E | 0x003A => BidiCharType::CS, // , . :
+        0x002C | 0x002E | 0x002F | 0x003A | 0x00A0 => BidiCharType::CS, // , . / : NBSP
+
+        // Remaining ASCII punctuation is neutral
+        0x0021..=0x002F | 0x003A..=0x0040 | 0x005B..=0x0060 | 0x007B..=0x007E => BidiCharType::ON,
+        0x2028 => BidiCharType::WS,
 
         // Hebrew (RTL)
         0x0590..=0x05FF => BidiCharType::R,
@@ -276,49 +281,190 @@ pub fn detect_base_direction(text: &[u32]) -> BidiDirection {
     BidiDirection::Ltr // Default to LTR
 }
 
-/// Calculate embedding levels for text
+/// Calculate the embedding levels of a single line of text
+///
+/// Applies the weak (W1–W7), neutral (N1–N2) and implicit (I1–I2) rules
+/// of UAX #9 with `base_dir` as the paragraph direction, followed by the
+/// L1 reset of trailing whitespace and separators. Explicit embeddings and
+/// isolates are not supported: their control characters are treated as
+/// boundary neutrals and take the level of the preceding character.
 pub fn resolve_levels(text: &[u32], base_dir: BidiDirection) -> Vec<i32> {
     let base_level = if base_dir == BidiDirection::Rtl { 1 } else { 0 };
-    let mut levels = vec![base_level; text.len()];
+    let embedding = if base_level == 1 {
+        BidiCharType::R
+    } else {
+        BidiCharType::L
+    };
+    let original: Vec<BidiCharType> = text.iter().map(|&ch| get_bidi_type(ch)).collect();
 
-    // Simplified level resolution:
-    // - Strong LTR at even level
-    // - Strong RTL at odd level
-    // - Weak and neutral inherit from context
+    // X9: formatting characters take no part in resolution
+    let kept: Vec<usize> = (0..text.len())
+        .filter(|&i| !is_removed_by_x9(original[i]))
+        .collect();
+    let mut types: Vec<BidiCharType> = kept.iter().map(|&i| original[i]).collect();
+    resolve_weak_types(&mut types, embedding);
+    resolve_neutral_types(&mut types, embedding);
 
-    for (i, &ch) in text.iter().enumerate() {
-        let bidi_type = get_bidi_type(ch);
+    let mut levels = vec![base_level; text.len()];
+    for (&i, &t) in kept.iter().zip(&types) {
+        // I1 and I2
+        levels[i] = match (base_level % 2, t) {
+            (0, BidiCharType::R) => base_level + 1,
+            (0, BidiCharType::AN | BidiCharType::EN) => base_level + 2,
+            (1, BidiCharType::L | BidiCharType::EN | BidiCharType::AN) => base_level + 1,
+            _ => base_level,
+        };
+    }
+    for i in 1..text.len() {
+        if is_removed_by_x9(original[i]) {
+            levels[i] = levels[i - 1];
+        }
+    }
 
-        match bidi_type {
-            BidiCharType::L => {
-                // LTR character: ensure even level
-                if levels[i] % 2 == 1 {
-                    levels[i] += 1;
-                }
-            }
-            BidiCharType::R | BidiCharType::AL => {
-                // RTL character: ensure odd level
-                if levels[i] % 2 == 0 {
-                    levels[i] += 1;
-                }
-            }
-            BidiCharType::EN | BidiCharType::AN => {
-                // Numbers: level depends on context
-                // In RTL context, Arabic numbers stay at odd level
-                // European numbers in RTL context go to next even level
-                if base_level == 1 && bidi_type == BidiCharType::EN {
-                    levels[i] = 2;
-                }
+    // L1: separators, and whitespace before them or at the end of the
+    // line, return to the paragraph level
+    let mut trailing = true;
+    for i in (0..text.len()).rev() {
+        match original[i] {
+            BidiCharType::S | BidiCharType::B => {
+                levels[i] = base_level;
+                trailing = true;
             }
-            _ => {
-                // Neutral and weak: inherit base level (simplified)
+            t if trailing && (t == BidiCharType::WS || is_removed_by_x9(t) || is_isolate(t)) => {
+                levels[i] = base_level;
             }
+            _ => trailing = false,
         }
     }
 
     levels
 }
 
+/// Types that rule X9 removes from resolution
+fn is_removed_by_x9(t: BidiCharType) -> bool {
+    matches!(
+        t,
+        BidiCharType::BN
+            | BidiCharType::LRE
+            | BidiCharType::RLE
+            | BidiCharType::LRO
+            | BidiCharType::RLO
+            | BidiCharType::PDF
+    )
+}
+
+fn is_isolate(t: BidiCharType) -> bool {
+    matches!(
+        t,
+        BidiCharType::LRI | BidiCharType::RLI | BidiCharType::FSI | BidiCharType::PDI
+    )
+}
+
+/// Rules W1–W7 over one level run; `sos` is the start-of-sequence type
+fn resolve_weak_types(types: &mut [BidiCharType], sos: BidiCharType) {
+    use BidiCharType::*;
+
+    // W1: marks take the type of what they follow
+    let mut prev = sos;
+    for t in types.iter_mut() {
+        if *t == NSM {
+            *t = if is_isolate(prev) { ON } else { prev };
+        }
+        prev = *t;
+    }
+
+    // W2 and W3: numbers after Arabic letters are Arabic numbers, and
+    // Arabic letters are then plain right-to-left
+    let mut last_strong = sos;
+    for t in types.iter_mut() {
+        match *t {
+            L | R | AL => last_strong = *t,
+            EN if last_strong == AL => *t = AN,
+            _ => {}
+        }
+    }
+    for t in types.iter_mut() {
+        if *t == AL {
+            *t = R;
+        }
+    }
+
+    // W4: a single separator between two numbers of the same kind joins them
+    for i in 1..types.len().saturating_sub(1) {
+        let (before, after) = (types[i - 1], types[i + 1]);
+        types[i] = match (types[i], before, after) {
+            (ES, EN, EN) | (CS, EN, EN) => EN,
+            (CS, AN, AN) => AN,
+            (t, _, _) => t,
+        };
+    }
+
+    // W5: terminators next to European numbers become numbers
+    let mut i = 0;
+    while i < types.len() {
+        if types[i] != ET {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < types.len() && types[i] == ET {
+            i += 1;
+        }
+        let touches_en =
+            (start > 0 && types[start - 1] == EN) || (i < types.len() && types[i] == EN);
+        if touches_en {
+            types[start..i].fill(EN);
+        }
+    }
+
+    // W6: remaining separators and terminators are neutral
+    for t in types.iter_mut() {
+        if matches!(*t, ES | ET | CS) {
+            *t = ON;
+        }
+    }
+
+    // W7: European numbers in left-to-right context are left-to-right
+    let mut last_strong = sos;
+    for t in types.iter_mut() {
+        match *t {
+            L | R => last_strong = *t,
+            EN if last_strong == L => *t = L,
+            _ => {}
+        }
+    }
+}
+
+/// Rules N1 and N2: neutrals between text of one direction take that
+/// direction, others take the embedding direction
+fn resolve_neutral_types(types: &mut [BidiCharType], embedding: BidiCharType) {
+    use BidiCharType::*;
+
+    let strong = |t: BidiCharType| match t {
+        L => Some(L),
+        R | EN | AN => Some(R),
+        _ => None,
+    };
+    let mut i = 0;
+    while i < types.len() {
+        if strong(types[i]).is_some() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < types.len() && strong(types[i]).is_none() {
+            i += 1;
+        }
+        let before = match start {
+            0 => embedding,
+            _ => strong(types[start - 1]).unwrap_or(embedding),
+        };
+        let after = types.get(i).and_then(|&t| strong(t)).unwrap_or(embedding);
+        let resolved = if before == after { before } else { embedding };
+        types[start..i].fill(resolved);
+    }
+}
+
 /// Reorder text for visual display
 pub fn reorder_text(text: &[u32], levels: &[i32]) -> Vec<u32> {
     if text.is_empty() {
@@ -356,6 +502,54 @@ pub fn reorder_text(text: &[u32], levels: &[i32]) -> Vec<u32> {
     result
 }
 
+/// Reorder a line of text from logical to display order (rules L1–L4)
+///
+/// `base_dir` is a [`BidiDirection`]; neutral or unset directions are
+/// taken from the first strong character. Characters at right-to-left
+/// levels are replaced by their mirrored forms.
+pub fn fz_bidi_reorder(text: &str, base_dir: i32) -> String {
+    let chars: Vec<u32> = text.chars().map(|c| c as u32).collect();
+    let dir = match BidiDirection::from_i32(base_dir) {
+        BidiDirection::Ltr => BidiDirection::Ltr,
+        BidiDirection::Rtl => BidiDirection::Rtl,
+        _ => detect_base_direction(&chars),
+    };
+    let levels = resolve_levels(&chars, dir);
+    let mirrored: Vec<u32> = chars
+        .iter()
+        .zip(&levels)
+        .map(|(&ch, &level)| {
+            if level % 2 == 1 {
+                fz_bidi_get_mirror(ch)
+            } else {
+                ch
+            }
+        })
+        .collect();
+    reorder_text(&mirrored, &levels)
+        .into_iter()
+        .filter_map(char::from_u32)
+        .collect()
+}
+
+/// Direction of the majority of strong characters, for text whose order
+/// is unknown so that the first strong character cannot be trusted
+pub fn dominant_direction(text: &[u32]) -> BidiDirection {
+    let (mut ltr, mut rtl) = (0usize, 0usize);
+    for &ch in text {
+        match get_bidi_type(ch) {
+            BidiCharType::L => ltr += 1,
+            BidiCharType::R | BidiCharType::AL => rtl += 1,
+            _ => {}
+        }
+    }
+    if rtl > ltr {
+        BidiDirection::Rtl
+    } else {
+        BidiDirection::Ltr
+    }
+}
+
 /// Fragment text into unidirectional runs
 pub fn fragment_text(text: &[u32], base_dir: BidiDirection, flags: BidiFlags) -> Vec<BidiFragment> {
     if text.is_empty() {
@@ -1025,4 +1219,54 @@ mod tests {
         assert!(CALLBACK_COUNT.load(Ordering::SeqCst) >= 1);
         assert_eq!(TOTAL_LEN.load(Ordering::SeqCst), text.len());
     }
+
+    #[test]
+    fn test_reorder_hebrew_english_mixed() {
+        // A right-to-left paragraph: the English phrase and its number keep
+        // their order, the comma stays with the Hebrew and "!" ends up on
+        // the far left
+        let logical = "\u{5E9}\u{5DC}\u{5D5}\u{5DD}, hello world 2024!";
+        let display = fz_bidi_reorder(logical, BidiDirection::Neutral as i32);
+        assert_eq!(display, "!hello world 2024 ,\u{5DD}\u{5D5}\u{5DC}\u{5E9}");
+
+        // Reordering display order again gives back the logical order
+        assert_eq!(
+            fz_bidi_reorder(&display, BidiDirection::Rtl as i32),
+            logical
+        );
+
+        // Hebrew embedded in a left-to-right line
+        let display = fz_bidi_reorder(
+            "abc \u{5D0}\u{5D1}\u{5D2} 12 def",
+            BidiDirection::Ltr as i32,
+        );
+        assert_eq!(display, "abc 12 \u{5D2}\u{5D1}\u{5D0} def");
+
+        // Brackets in right-to-left text are mirrored
+        let display = fz_bidi_reorder("(\u{5D0}\u{5D1})", BidiDirection::Rtl as i32);
+        assert_eq!(display, "(\u{5D1}\u{5D0})");
+    }
+
+    #[test]
+    fn test_reorder_arabic_line() {
+        // "The price is 150 riyals": European digits after Arabic letters
+        // are Arabic numbers and stay left-to-right
+        let logical = "\u{627}\u{644}\u{633}\u{639}\u{631} 150 \u{631}\u{64A}\u{627}\u{644}";
+        let text: Vec<u32> = logical.chars().map(|c| c as u32).collect();
+        let levels = resolve_levels(&text, BidiDirection::Rtl);
+        assert_eq!(levels, [1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1]);
+
+        let display = fz_bidi_reorder(logical, BidiDirection::Neutral as i32);
+        assert_eq!(
+            display,
+            "\u{644}\u{627}\u{64A}\u{631} 150 \u{631}\u{639}\u{633}\u{644}\u{627}"
+        );
+    }
+
+    #[test]
+    fn test_resolve_levels_trailing_whitespace() {
+        // L1 puts trailing whitespace back at the paragraph level
+        let text: Vec<u32> = "abc  ".chars().map(|c| c as u32).collect();
+        assert_eq!(resolve_levels(&text, BidiDirection::Rtl), [2, 2, 2, 1, 1]);
+    }
 }
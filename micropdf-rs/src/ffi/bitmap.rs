@@ -1,7 +1,11 @@
 //! C FFI for 1-bit bitmap - MuPDF compatible
 //! Safe Rust implementation of fz_bitmap
 
+use super::output::OUTPUTS;
 use super::{Handle, HandleStore};
+use std::ffi::{CStr, c_char};
+use std::fs::File;
+use std::io::Write;
 use std::sync::LazyLock;
 
 /// Halftone algorithm
@@ -127,9 +131,18 @@ pub extern "C" fn fz_new_bitmap(
     BITMAPS.insert(bitmap)
 }
 
+/// Create bitmap from pixmap using the given halftone algorithm
+///
+/// `halftone` takes a `HalftoneType` value; 0 is a plain 50% threshold and
+/// 1 is Floyd-Steinberg error diffusion.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_new_bitmap_from_pixmap(_ctx: Handle, pixmap: Handle, halftone: i32) -> Handle {
+    fz_new_bitmap_from_pixmap_halftone(_ctx, pixmap, halftone)
+}
+
 /// Create bitmap from pixmap using threshold
 #[unsafe(no_mangle)]
-pub extern "C" fn fz_new_bitmap_from_pixmap(
+pub extern "C" fn fz_new_bitmap_from_pixmap_threshold(
     _ctx: Handle,
     pixmap: Handle,
     threshold: i32,
@@ -284,6 +297,10 @@ pub extern "C" fn fz_new_bitmap_from_pixmap_halftone(
 }
 
 /// Floyd-Steinberg error diffusion dithering
+///
+/// The quantization error is taken from the clamped value, so it never
+/// exceeds half a gray step and cannot build up across a flat region. The
+/// rounding remainder goes to the last neighbour so no error is lost.
 fn floyd_steinberg_dither(gray: &mut [i32], width: i32, height: i32, out: &mut [u8], stride: i32) {
     let w = width as usize;
     let h = height as usize;
@@ -305,16 +322,21 @@ fn floyd_steinberg_dither(gray: &mut [i32], width: i32, height: i32, out: &mut [
             }
 
             // Distribute error to neighbors
+            let right = error * 7 / 16;
+            let below_left = error * 3 / 16;
+            let below = error * 5 / 16;
+            let below_right = error - right - below_left - below;
+
             if x + 1 < w {
-                gray[idx + 1] += error * 7 / 16;
+                gray[idx + 1] += right;
             }
             if y + 1 < h {
                 if x > 0 {
-                    gray[idx + w - 1] += error * 3 / 16;
+                    gray[idx + w - 1] += below_left;
                 }
-                gray[idx + w] += error * 5 / 16;
+                gray[idx + w] += below;
                 if x + 1 < w {
-                    gray[idx + w + 1] += error * 1 / 16;
+                    gray[idx + w + 1] += below_right;
                 }
             }
         }
@@ -616,6 +638,89 @@ pub extern "C" fn fz_bitmap_compressed_size(
     0
 }
 
+// ============================================================================
+// Output
+// ============================================================================
+
+/// Encode a bitmap as binary PBM (P4), where a set bit is black
+fn encode_pbm(bitmap: &Bitmap) -> Vec<u8> {
+    let row_bytes = ((bitmap.width + 7) / 8) as usize;
+    let mut pbm = format!("P4\n{} {}\n", bitmap.width, bitmap.height).into_bytes();
+    for row in bitmap
+        .data
+        .chunks(bitmap.stride as usize)
+        .take(bitmap.height as usize)
+    {
+        if bitmap.invert {
+            pbm.extend(row[..row_bytes].iter().map(|b| !b));
+        } else {
+            pbm.extend_from_slice(&row[..row_bytes]);
+        }
+    }
+    pbm
+}
+
+/// Save a bitmap as PBM to a file
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_save_bitmap_as_pbm(
+    _ctx: Handle,
+    bitmap: Handle,
+    filename: *const c_char,
+) -> i32 {
+    if filename.is_null() {
+        return -1;
+    }
+
+    let filename_str = unsafe { CStr::from_ptr(filename) };
+    let filename_str = match filename_str.to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    let pbm_data = match BITMAPS.get(bitmap) {
+        Some(bm) => match bm.lock() {
+            Ok(guard) => encode_pbm(&guard),
+            Err(_) => return -1,
+        },
+        None => return -1,
+    };
+
+    match File::create(filename_str) {
+        Ok(mut file) => {
+            if file.write_all(&pbm_data).is_ok() {
+                0
+            } else {
+                -1
+            }
+        }
+        Err(_) => -1,
+    }
+}
+
+/// Write a bitmap as PBM to an output stream
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_write_bitmap_as_pbm(_ctx: Handle, out: Handle, bitmap: Handle) -> i32 {
+    let pbm_data = match BITMAPS.get(bitmap) {
+        Some(bm) => match bm.lock() {
+            Ok(guard) => encode_pbm(&guard),
+            Err(_) => return -1,
+        },
+        None => return -1,
+    };
+
+    let out_arc = match OUTPUTS.get(out) {
+        Some(o) => o,
+        None => return -1,
+    };
+    let mut output = out_arc.lock().unwrap();
+
+    if output.write_data(&pbm_data).is_ok() {
+        0
+    } else {
+        -1
+    }
+}
+
 // ============================================================================
 // Reference Counting
 // ============================================================================
@@ -717,4 +822,89 @@ mod tests {
 
         fz_drop_bitmap(0, bm);
     }
+
+    fn gray_ramp(width: i32, height: i32) -> Handle {
+        let pix = crate::ffi::pixmap::fz_new_pixmap(
+            0,
+            crate::ffi::colorspace::FZ_COLORSPACE_GRAY,
+            width,
+            height,
+            0,
+            0,
+        );
+        let arc = crate::ffi::PIXMAPS.get(pix).unwrap();
+        let mut guard = arc.lock().unwrap();
+        for y in 0..height {
+            for x in 0..width {
+                guard.set_sample(x, y, 0, (x * 255 / (width - 1)) as u8);
+            }
+        }
+        pix
+    }
+
+    fn dark_fraction(bm: Handle, x0: i32, x1: i32) -> f32 {
+        let height = fz_bitmap_height(0, bm);
+        let mut set = 0;
+        for y in 0..height {
+            for x in x0..x1 {
+                set += fz_bitmap_get_pixel(0, bm, x, y);
+            }
+        }
+        set as f32 / ((x1 - x0) * height) as f32
+    }
+
+    #[test]
+    fn test_halftone_gray_ramp() {
+        let pix = gray_ramp(256, 32);
+
+        let bm = fz_new_bitmap_from_pixmap(0, pix, HalftoneType::FloydSteinberg as i32);
+        assert_eq!(fz_bitmap_width(0, bm), 256);
+        assert_eq!(fz_bitmap_stride(0, bm), 32);
+
+        // Darker (left) columns must have more black bits set
+        let densities: Vec<f32> = (0..8)
+            .map(|i| dark_fraction(bm, i * 32, i * 32 + 32))
+            .collect();
+        for pair in densities.windows(2) {
+            assert!(pair[0] > pair[1], "{densities:?}");
+        }
+        // Each band's density tracks its mean darkness
+        for (i, density) in densities.iter().enumerate() {
+            let expected = 1.0 - (i as f32 * 32.0 + 15.5) / 255.0;
+            assert!((density - expected).abs() < 0.08, "{densities:?}");
+        }
+
+        // Halftoning is deterministic
+        let again = fz_new_bitmap_from_pixmap(0, pix, HalftoneType::FloydSteinberg as i32);
+        assert_eq!(
+            BITMAPS.get(bm).unwrap().lock().unwrap().data,
+            BITMAPS.get(again).unwrap().lock().unwrap().data
+        );
+
+        let threshold = fz_new_bitmap_from_pixmap(0, pix, HalftoneType::None as i32);
+        assert_eq!(dark_fraction(threshold, 0, 128), 1.0);
+        assert_eq!(dark_fraction(threshold, 128, 256), 0.0);
+
+        for handle in [bm, again, threshold] {
+            fz_drop_bitmap(0, handle);
+        }
+        crate::ffi::pixmap::fz_drop_pixmap(0, pix);
+    }
+
+    #[test]
+    fn test_save_bitmap_as_pbm() {
+        let bm = fz_new_bitmap(0, 10, 2, 72, 72);
+        fz_bitmap_set_pixel(0, bm, 0, 0, 1);
+        fz_bitmap_set_pixel(0, bm, 9, 1, 1);
+
+        let path = std::env::temp_dir().join(format!("micropdf_bitmap_{bm}.pbm"));
+        let c_path = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(fz_save_bitmap_as_pbm(0, bm, c_path.as_ptr()), 0);
+
+        let data = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(data, b"P4\n10 2\n\x80\x00\x00\x40");
+
+        fz_drop_bitmap(0, bm);
+    }
 }
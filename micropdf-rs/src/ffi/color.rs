@@ -9,7 +9,9 @@ use std::sync::LazyLock;
 use crate::ffi::colorspace::{
     ColorspaceHandle, FZ_COLORSPACE_CMYK, FZ_COLORSPACE_GRAY, FZ_COLORSPACE_RGB,
 };
+use crate::ffi::context::set_caught;
 use crate::ffi::{Handle, HandleStore};
+use crate::fitz::error::{Error, Result};
 
 // ============================================================================
 // Rendering Intent
@@ -543,6 +545,285 @@ pub extern "C" fn fz_max_colors() -> i32 {
     FZ_MAX_COLORS
 }
 
+// ============================================================================
+// Hex and Named Colors
+// ============================================================================
+
+/// CSS named colors, sorted by name
+const NAMED_COLORS: &[(&str, [u8; 3])] = &[
+    ("aliceblue", [0xF0, 0xF8, 0xFF]),
+    ("antiquewhite", [0xFA, 0xEB, 0xD7]),
+    ("aqua", [0x00, 0xFF, 0xFF]),
+    ("aquamarine", [0x7F, 0xFF, 0xD4]),
+    ("azure", [0xF0, 0xFF, 0xFF]),
+    ("beige", [0xF5, 0xF5, 0xDC]),
+    ("bisque", [0xFF, 0xE4, 0xC4]),
+    ("black", [0x00, 0x00, 0x00]),
+    ("blanchedalmond", [0xFF, 0xEB, 0xCD]),
+    ("blue", [0x00, 0x00, 0xFF]),
+    ("blueviolet", [0x8A, 0x2B, 0xE2]),
+    ("brown", [0xA5, 0x2A, 0x2A]),
+    ("burlywood", [0xDE, 0xB8, 0x87]),
+    ("cadetblue", [0x5F, 0x9E, 0xA0]),
+    ("chartreuse", [0x7F, 0xFF, 0x00]),
+    ("chocolate", [0xD2, 0x69, 0x1E]),
+    ("coral", [0xFF, 0x7F, 0x50]),
+    ("cornflowerblue", [0x64, 0x95, 0xED]),
+    ("cornsilk", [0xFF, 0xF8, 0xDC]),
+    ("crimson", [0xDC, 0x14, 0x3C]),
+    ("cyan", [0x00, 0xFF, 0xFF]),
+    ("darkblue", [0x00, 0x00, 0x8B]),
+    ("darkcyan", [0x00, 0x8B, 0x8B]),
+    ("darkgoldenrod", [0xB8, 0x86, 0x0B]),
+    ("darkgray", [0xA9, 0xA9, 0xA9]),
+    ("darkgreen", [0x00, 0x64, 0x00]),
+    ("darkgrey", [0xA9, 0xA9, 0xA9]),
+    ("darkkhaki", [0xBD, 0xB7, 0x6B]),
+    ("darkmagenta", [0x8B, 0x00, 0x8B]),
+    ("darkolivegreen", [0x55, 0x6B, 0x2F]),
+    ("darkorange", [0xFF, 0x8C, 0x00]),
+    ("darkorchid", [0x99, 0x32, 0xCC]),
+    ("darkred", [0x8B, 0x00, 0x00]),
+    ("darksalmon", [0xE9, 0x96, 0x7A]),
+    ("darkseagreen", [0x8F, 0xBC, 0x8F]),
+    ("darkslateblue", [0x48, 0x3D, 0x8B]),
+    ("darkslategray", [0x2F, 0x4F, 0x4F]),
+    ("darkslategrey", [0x2F, 0x4F, 0x4F]),
+    ("darkturquoise", [0x00, 0xCE, 0xD1]),
+    ("darkviolet", [0x94, 0x00, 0xD3]),
+    ("deeppink", [0xFF, 0x14, 0x93]),
+    ("deepskyblue", [0x00, 0xBF, 0xFF]),
+    ("dimgray", [0x69, 0x69, 0x69]),
+    ("dimgrey", [0x69, 0x69, 0x69]),
+    ("dodgerblue", [0x1E, 0x90, 0xFF]),
+    ("firebrick", [0xB2, 0x22, 0x22]),
+    ("floralwhite", [0xFF, 0xFA, 0xF0]),
+    ("forestgreen", [0x22, 0x8B, 0x22]),
+    ("fuchsia", [0xFF, 0x00, 0xFF]),
+    ("gainsboro", [0xDC, 0xDC, 0xDC]),
+    ("ghostwhite", [0xF8, 0xF8, 0xFF]),
+    ("gold", [0xFF, 0xD7, 0x00]),
+    ("goldenrod", [0xDA, 0xA5, 0x20]),
+    ("gray", [0x80, 0x80, 0x80]),
+    ("green", [0x00, 0x80, 0x00]),
+    ("greenyellow", [0xAD, 0xFF, 0x2F]),
+    ("grey", [0x80, 0x80, 0x80]),
+    ("honeydew", [0xF0, 0xFF, 0xF0]),
+    ("hotpink", [0xFF, 0x69, 0xB4]),
+    ("indianred", [0xCD, 0x5C, 0x5C]),
+    ("indigo", [0x4B, 0x00, 0x82]),
+    ("ivory", [0xFF, 0xFF, 0xF0]),
+    ("khaki", [0xF0, 0xE6, 0x8C]),
+    ("lavender", [0xE6, 0xE6, 0xFA]),
+    ("lavenderblush", [0xFF, 0xF0, 0xF5]),
+    ("lawngreen", [0x7C, 0xFC, 0x00]),
+    ("lemonchiffon", [0xFF, 0xFA, 0xCD]),
+    ("lightblue", [0xAD, 0xD8, 0xE6]),
+    ("lightcoral", [0xF0, 0x80, 0x80]),
+    ("lightcyan", [0xE0, 0xFF, 0xFF]),
+    ("lightgoldenrodyellow", [0xFA, 0xFA, 0xD2]),
+    ("lightgray", [0xD3, 0xD3, 0xD3]),
+    ("lightgreen", [0x90, 0xEE, 0x90]),
+    ("lightgrey", [0xD3, 0xD3, 0xD3]),
+    ("lightpink", [0xFF, 0xB6, 0xC1]),
+    ("lightsalmon", [0xFF, 0xA0, 0x7A]),
+    ("lightseagreen", [0x20, 0xB2, 0xAA]),
+    ("lightskyblue", [0x87, 0xCE, 0xFA]),
+    ("lightslategray", [0x77, 0x88, 0x99]),
+    ("lightslategrey", [0x77, 0x88, 0x99]),
+    ("lightsteelblue", [0xB0, 0xC4, 0xDE]),
+    ("lightyellow", [0xFF, 0xFF, 0xE0]),
+    ("lime", [0x00, 0xFF, 0x00]),
+    ("limegreen", [0x32, 0xCD, 0x32]),
+    ("linen", [0xFA, 0xF0, 0xE6]),
+    ("magenta", [0xFF, 0x00, 0xFF]),
+    ("maroon", [0x80, 0x00, 0x00]),
+    ("mediumaquamarine", [0x66, 0xCD, 0xAA]),
+    ("mediumblue", [0x00, 0x00, 0xCD]),
+    ("mediumorchid", [0xBA, 0x55, 0xD3]),
+    ("mediumpurple", [0x93, 0x70, 0xDB]),
+    ("mediumseagreen", [0x3C, 0xB3, 0x71]),
+    ("mediumslateblue", [0x7B, 0x68, 0xEE]),
+    ("mediumspringgreen", [0x00, 0xFA, 0x9A]),
+    ("mediumturquoise", [0x48, 0xD1, 0xCC]),
+    ("mediumvioletred", [0xC7, 0x15, 0x85]),
+    ("midnightblue", [0x19, 0x19, 0x70]),
+    ("mintcream", [0xF5, 0xFF, 0xFA]),
+    ("mistyrose", [0xFF, 0xE4, 0xE1]),
+    ("moccasin", [0xFF, 0xE4, 0xB5]),
+    ("navajowhite", [0xFF, 0xDE, 0xAD]),
+    ("navy", [0x00, 0x00, 0x80]),
+    ("oldlace", [0xFD, 0xF5, 0xE6]),
+    ("olive", [0x80, 0x80, 0x00]),
+    ("olivedrab", [0x6B, 0x8E, 0x23]),
+    ("orange", [0xFF, 0xA5, 0x00]),
+    ("orangered", [0xFF, 0x45, 0x00]),
+    ("orchid", [0xDA, 0x70, 0xD6]),
+    ("palegoldenrod", [0xEE, 0xE8, 0xAA]),
+    ("palegreen", [0x98, 0xFB, 0x98]),
+    ("paleturquoise", [0xAF, 0xEE, 0xEE]),
+    ("palevioletred", [0xDB, 0x70, 0x93]),
+    ("papayawhip", [0xFF, 0xEF, 0xD5]),
+    ("peachpuff", [0xFF, 0xDA, 0xB9]),
+    ("peru", [0xCD, 0x85, 0x3F]),
+    ("pink", [0xFF, 0xC0, 0xCB]),
+    ("plum", [0xDD, 0xA0, 0xDD]),
+    ("powderblue", [0xB0, 0xE0, 0xE6]),
+    ("purple", [0x80, 0x00, 0x80]),
+    ("rebeccapurple", [0x66, 0x33, 0x99]),
+    ("red", [0xFF, 0x00, 0x00]),
+    ("rosybrown", [0xBC, 0x8F, 0x8F]),
+    ("royalblue", [0x41, 0x69, 0xE1]),
+    ("saddlebrown", [0x8B, 0x45, 0x13]),
+    ("salmon", [0xFA, 0x80, 0x72]),
+    ("sandybrown", [0xF4, 0xA4, 0x60]),
+    ("seagreen", [0x2E, 0x8B, 0x57]),
+    ("seashell", [0xFF, 0xF5, 0xEE]),
+    ("sienna", [0xA0, 0x52, 0x2D]),
+    ("silver", [0xC0, 0xC0, 0xC0]),
+    ("skyblue", [0x87, 0xCE, 0xEB]),
+    ("slateblue", [0x6A, 0x5A, 0xCD]),
+    ("slategray", [0x70, 0x80, 0x90]),
+    ("slategrey", [0x70, 0x80, 0x90]),
+    ("snow", [0xFF, 0xFA, 0xFA]),
+    ("springgreen", [0x00, 0xFF, 0x7F]),
+    ("steelblue", [0x46, 0x82, 0xB4]),
+    ("tan", [0xD2, 0xB4, 0x8C]),
+    ("teal", [0x00, 0x80, 0x80]),
+    ("thistle", [0xD8, 0xBF, 0xD8]),
+    ("tomato", [0xFF, 0x63, 0x47]),
+    ("turquoise", [0x40, 0xE0, 0xD0]),
+    ("violet", [0xEE, 0x82, 0xEE]),
+    ("wheat", [0xF5, 0xDE, 0xB3]),
+    ("white", [0xFF, 0xFF, 0xFF]),
+    ("whitesmoke", [0xF5, 0xF5, 0xF5]),
+    ("yellow", [0xFF, 0xFF, 0x00]),
+    ("yellowgreen", [0x9A, 0xCD, 0x32]),
+];
+
+/// Look up a CSS color name (case-insensitive)
+pub fn lookup_named_color(name: &str) -> Option<[u8; 3]> {
+    let name = name.trim().to_ascii_lowercase();
+    NAMED_COLORS
+        .binary_search_by(|(n, _)| n.cmp(&name.as_str()))
+        .ok()
+        .map(|i| NAMED_COLORS[i].1)
+}
+
+/// Parse `#RGB`, `#RRGGBB` or `#RRGGBBAA` into RGBA bytes
+pub fn parse_hex_color(hex: &str) -> Result<[u8; 4]> {
+    let digits = hex
+        .trim()
+        .strip_prefix('#')
+        .ok_or_else(|| Error::argument(format!("hex color {hex:?} must start with '#'")))?;
+    let nibbles: Vec<u8> = digits
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<_>>()
+        .ok_or_else(|| Error::argument(format!("hex color {hex:?} has non-hex digits")))?;
+    match nibbles[..] {
+        [r, g, b] => Ok([r * 17, g * 17, b * 17, 255]),
+        [r1, r0, g1, g0, b1, b0] => Ok([r1 << 4 | r0, g1 << 4 | g0, b1 << 4 | b0, 255]),
+        [r1, r0, g1, g0, b1, b0, a1, a0] => {
+            Ok([r1 << 4 | r0, g1 << 4 | g0, b1 << 4 | b0, a1 << 4 | a0])
+        }
+        _ => Err(Error::argument(format!(
+            "hex color {hex:?} must have 3, 6 or 8 digits"
+        ))),
+    }
+}
+
+/// Parse a hex color or CSS color name into RGBA bytes
+pub fn parse_color(spec: &str) -> Result<[u8; 4]> {
+    if spec.trim_start().starts_with('#') {
+        return parse_hex_color(spec);
+    }
+    lookup_named_color(spec)
+        .map(|[r, g, b]| [r, g, b, 255])
+        .ok_or_else(|| Error::argument(format!("unknown color name {spec:?}")))
+}
+
+/// Convert a 0-1 color component to a 0-255 byte, clamping out-of-range values
+pub fn color_to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Convert a 0-255 byte to a 0-1 color component
+pub fn color_from_byte(value: u8) -> f32 {
+    value as f32 / 255.0
+}
+
+/// Write parsed RGBA bytes as 0-1 floats, or record the error on `ctx`
+fn write_color(ctx: Handle, parsed: Result<[u8; 4]>, rgba: *mut f32) -> i32 {
+    if rgba.is_null() {
+        return 0;
+    }
+    match parsed {
+        Ok(bytes) => {
+            let out = unsafe { std::slice::from_raw_parts_mut(rgba, 4) };
+            for (dst, &b) in out.iter_mut().zip(&bytes) {
+                *dst = color_from_byte(b);
+            }
+            1
+        }
+        Err(err) => {
+            set_caught(ctx, &err);
+            0
+        }
+    }
+}
+
+/// Parse a `#RRGGBB` (or `#RGB`, `#RRGGBBAA`) color into RGBA components
+///
+/// Returns 1 on success. On a malformed string returns 0 and records the
+/// reason, readable with `fz_caught_message`.
+///
+/// # Safety
+/// - `hex` must be a valid null-terminated string
+/// - `rgba` must point to space for 4 floats
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_color_from_hex(ctx: Handle, hex: *const c_char, rgba: *mut f32) -> i32 {
+    if hex.is_null() {
+        return 0;
+    }
+    let parsed = match unsafe { CStr::from_ptr(hex) }.to_str() {
+        Ok(s) => parse_hex_color(s),
+        Err(_) => Err(Error::argument("hex color is not valid UTF-8")),
+    };
+    write_color(ctx, parsed, rgba)
+}
+
+/// Parse a hex color or CSS color name into RGBA components
+///
+/// Returns 1 on success, 0 (with the error recorded on `ctx`) otherwise.
+///
+/// # Safety
+/// - `spec` must be a valid null-terminated string
+/// - `rgba` must point to space for 4 floats
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_parse_color(ctx: Handle, spec: *const c_char, rgba: *mut f32) -> i32 {
+    if spec.is_null() {
+        return 0;
+    }
+    let parsed = match unsafe { CStr::from_ptr(spec) }.to_str() {
+        Ok(s) => parse_color(s),
+        Err(_) => Err(Error::argument("color is not valid UTF-8")),
+    };
+    write_color(ctx, parsed, rgba)
+}
+
+/// Convert a 0-1 color component to a 0-255 byte
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_color_to_byte(value: f32) -> u8 {
+    color_to_byte(value)
+}
+
+/// Convert a 0-255 byte to a 0-1 color component
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_color_from_byte(value: u8) -> f32 {
+    color_from_byte(value)
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -799,4 +1080,66 @@ mod tests {
         assert!((dst[1] - 0.5).abs() < 0.01);
         assert!((dst[2] - 0.5).abs() < 0.01);
     }
+
+    #[test]
+    fn test_color_from_hex() {
+        let mut rgba = [0.0f32; 4];
+        assert_eq!(
+            fz_color_from_hex(0, c"#FF8800".as_ptr(), rgba.as_mut_ptr()),
+            1
+        );
+        assert_eq!(rgba.map(color_to_byte), [0xFF, 0x88, 0x00, 0xFF]);
+
+        assert_eq!(parse_hex_color("#f80").unwrap(), [0xFF, 0x88, 0x00, 0xFF]);
+        assert_eq!(
+            parse_hex_color("#11223344").unwrap(),
+            [0x11, 0x22, 0x33, 0x44]
+        );
+    }
+
+    #[test]
+    fn test_named_color() {
+        assert_eq!(
+            lookup_named_color("rebeccapurple"),
+            Some([0x66, 0x33, 0x99])
+        );
+        assert_eq!(
+            lookup_named_color("RebeccaPurple"),
+            Some([0x66, 0x33, 0x99])
+        );
+        assert_eq!(lookup_named_color("notacolor"), None);
+
+        let mut rgba = [0.0f32; 4];
+        assert_eq!(
+            fz_parse_color(0, c"rebeccapurple".as_ptr(), rgba.as_mut_ptr()),
+            1
+        );
+        assert_eq!(rgba.map(color_to_byte), [0x66, 0x33, 0x99, 0xFF]);
+        assert!(NAMED_COLORS.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn test_reject_malformed_hex() {
+        let ctx =
+            unsafe { crate::ffi::context::fz_new_context(std::ptr::null(), std::ptr::null(), 0) };
+        let mut rgba = [0.0f32; 4];
+        assert_eq!(
+            fz_color_from_hex(ctx, c"#ZZZ".as_ptr(), rgba.as_mut_ptr()),
+            0
+        );
+        let message = unsafe { CStr::from_ptr(crate::ffi::context::fz_caught_message(ctx)) };
+        assert!(message.to_str().unwrap().contains("non-hex"));
+
+        assert!(parse_hex_color("#12345").is_err());
+        assert!(parse_hex_color("FF8800").is_err());
+        crate::ffi::context::fz_drop_context(ctx);
+    }
+
+    #[test]
+    fn test_color_byte_conversion() {
+        assert_eq!(color_to_byte(0.5), 128);
+        assert_eq!(color_to_byte(1.5), 255);
+        assert_eq!(color_to_byte(-0.1), 0);
+        assert_eq!(color_to_byte(color_from_byte(200)), 200);
+    }
 }
@@ -83,7 +83,7 @@ fn colorspace_n(handle: ColorspaceHandle) -> i32 {
 }
 
 /// Get colorspace type
-fn colorspace_type(handle: ColorspaceHandle) -> ColorspaceType {
+pub(crate) fn colorspace_type(handle: ColorspaceHandle) -> ColorspaceType {
     match handle {
         FZ_COLORSPACE_GRAY => ColorspaceType::Gray,
         FZ_COLORSPACE_RGB => ColorspaceType::Rgb,
@@ -1,7 +1,7 @@
 //! FFI bindings for fz_compress (Compression)
 //!
 //! This module provides compression functions including zlib deflate,
-//! brotli compression, and CCITT Fax Group 3/4 encoding.
+//! LZW, brotli compression, and CCITT Fax Group 3/4 encoding.
 
 use std::io::{Read, Write};
 use std::sync::LazyLock;
@@ -49,6 +49,24 @@ impl DeflateLevel {
     }
 }
 
+/// Map a zlib level (0-9) to a flate2 compression setting
+///
+/// Anything outside 0-9 (including `DeflateLevel::Default`) selects the
+/// zlib default.
+fn deflate_compression(level: i32) -> flate2::Compression {
+    match level {
+        0..=9 => flate2::Compression::new(level as u32),
+        _ => flate2::Compression::default(),
+    }
+}
+
+/// Deflate a byte slice into a zlib stream
+fn deflate_bytes(data: &[u8], level: i32) -> Option<Vec<u8>> {
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), deflate_compression(level));
+    encoder.write_all(data).ok()?;
+    encoder.finish().ok()
+}
+
 // ============================================================================
 // Brotli Compression Level
 // ============================================================================
@@ -298,21 +316,15 @@ pub extern "C" fn fz_deflate(
         return;
     }
 
-    let deflate_level = DeflateLevel::from_i32(level);
     let source_slice = unsafe { std::slice::from_raw_parts(source, source_length) };
     let dest_capacity = unsafe { *compressed_length };
 
-    let mut encoder = flate2::write::ZlibEncoder::new(
-        Vec::with_capacity(dest_capacity),
-        deflate_level.to_flate2_level(),
-    );
-
-    if encoder.write_all(source_slice).is_ok() {
-        if let Ok(compressed) = encoder.finish() {
-            let len = compressed.len().min(dest_capacity);
+    // A truncated zlib stream is useless, so report failure when it won't fit
+    if let Some(compressed) = deflate_bytes(source_slice, level) {
+        if compressed.len() <= dest_capacity {
             unsafe {
-                std::ptr::copy_nonoverlapping(compressed.as_ptr(), dest, len);
-                *compressed_length = len;
+                std::ptr::copy_nonoverlapping(compressed.as_ptr(), dest, compressed.len());
+                *compressed_length = compressed.len();
             }
             return;
         }
@@ -342,30 +354,25 @@ pub extern "C" fn fz_new_deflated_data(
         return std::ptr::null_mut();
     }
 
-    let deflate_level = DeflateLevel::from_i32(level);
     let source_slice = unsafe { std::slice::from_raw_parts(source, source_length) };
 
-    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), deflate_level.to_flate2_level());
+    if let Some(compressed) = deflate_bytes(source_slice, level) {
+        let len = compressed.len();
+        let ptr = compressed.as_ptr() as *mut u8;
 
-    if encoder.write_all(source_slice).is_ok() {
-        if let Ok(compressed) = encoder.finish() {
-            let len = compressed.len();
-            let ptr = compressed.as_ptr() as *mut u8;
-
-            // SAFETY: We deliberately leak this Vec to transfer ownership to the C caller.
-            // The caller is responsible for freeing this memory. Memory layout:
-            // - Contiguous array of `len` bytes at `ptr`
-            // - Allocated via Rust's global allocator
-            // To properly deallocate from Rust: Vec::from_raw_parts(ptr, len, len)
-            // Or call fz_free_compressed_data() which handles this cleanup.
-            std::mem::forget(compressed);
-
-            // SAFETY: compressed_length was checked for null at function entry
-            unsafe {
-                *compressed_length = len;
-            }
-            return ptr;
+        // SAFETY: We deliberately leak this Vec to transfer ownership to the C caller.
+        // The caller is responsible for freeing this memory. Memory layout:
+        // - Contiguous array of `len` bytes at `ptr`
+        // - Allocated via Rust's global allocator
+        // To properly deallocate from Rust: Vec::from_raw_parts(ptr, len, len)
+        // Or call fz_free_compressed_data() which handles this cleanup.
+        std::mem::forget(compressed);
+
+        // SAFETY: compressed_length was checked for null at function entry
+        unsafe {
+            *compressed_length = len;
         }
+        return ptr;
     }
 
     unsafe {
@@ -414,19 +421,68 @@ pub extern "C" fn fz_deflate_to_buffer(
         return 0;
     }
 
-    let deflate_level = DeflateLevel::from_i32(level);
     let source_slice = unsafe { std::slice::from_raw_parts(source, source_length) };
 
-    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), deflate_level.to_flate2_level());
+    match deflate_bytes(source_slice, level) {
+        Some(compressed) => BUFFERS.insert(Buffer::from_data(&compressed)),
+        None => 0,
+    }
+}
 
-    if encoder.write_all(source_slice).is_ok() {
-        if let Ok(compressed) = encoder.finish() {
-            let buffer = Buffer::from_data(&compressed);
-            return BUFFERS.insert(buffer);
-        }
+/// Deflate the contents of a buffer into a new buffer handle
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_new_deflated_buffer(_ctx: Handle, buffer: Handle, level: i32) -> Handle {
+    let buf_arc = match BUFFERS.get(buffer) {
+        Some(b) => b,
+        None => return 0,
+    };
+    let compressed = {
+        let buf_guard = buf_arc.lock().unwrap();
+        deflate_bytes(buf_guard.data(), level)
+    };
+
+    match compressed {
+        Some(compressed) => BUFFERS.insert(Buffer::from_data(&compressed)),
+        None => 0,
     }
+}
 
-    0
+// ============================================================================
+// FFI Functions - LZW
+// ============================================================================
+
+/// Compress data with LZW (PDF LZWDecode, early change) into a buffer handle
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_compress_lzw(_ctx: Handle, source: *const u8, source_length: usize) -> Handle {
+    if source.is_null() {
+        return 0;
+    }
+
+    let source_slice = unsafe { std::slice::from_raw_parts(source, source_length) };
+
+    match crate::pdf::filter::encode_lzw(source_slice) {
+        Ok(compressed) => BUFFERS.insert(Buffer::from_data(&compressed)),
+        Err(_) => 0,
+    }
+}
+
+/// Decompress LZW data (PDF LZWDecode, early change) into a buffer handle
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_decompress_lzw(
+    _ctx: Handle,
+    source: *const u8,
+    source_length: usize,
+) -> Handle {
+    if source.is_null() {
+        return 0;
+    }
+
+    let source_slice = unsafe { std::slice::from_raw_parts(source, source_length) };
+
+    match crate::pdf::filter::decode_lzw(source_slice, None) {
+        Ok(decompressed) => BUFFERS.insert(Buffer::from_data(&decompressed)),
+        Err(_) => 0,
+    }
 }
 
 // ============================================================================
@@ -981,10 +1037,16 @@ pub extern "C" fn fz_inflate(
     let mut decompressed = Vec::with_capacity(dest_capacity);
 
     if decoder.read_to_end(&mut decompressed).is_ok() {
-        let len = decompressed.len().min(dest_capacity);
+        // Report the required size rather than silently truncating
+        if decompressed.len() > dest_capacity {
+            unsafe {
+                *dest_length = decompressed.len();
+            }
+            return -1;
+        }
         unsafe {
-            std::ptr::copy_nonoverlapping(decompressed.as_ptr(), dest, len);
-            *dest_length = len;
+            std::ptr::copy_nonoverlapping(decompressed.as_ptr(), dest, decompressed.len());
+            *dest_length = decompressed.len();
         }
         return 0;
     }
@@ -992,6 +1054,27 @@ pub extern "C" fn fz_inflate(
     -1
 }
 
+/// Decompress deflated data into a new buffer handle
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_inflate_to_buffer(
+    _ctx: Handle,
+    source: *const u8,
+    source_length: usize,
+) -> Handle {
+    if source.is_null() {
+        return 0;
+    }
+
+    let source_slice = unsafe { std::slice::from_raw_parts(source, source_length) };
+    let mut decoder = flate2::read::ZlibDecoder::new(source_slice);
+    let mut decompressed = Vec::new();
+
+    match decoder.read_to_end(&mut decompressed) {
+        Ok(_) => BUFFERS.insert(Buffer::from_data(&decompressed)),
+        Err(_) => 0,
+    }
+}
+
 /// Decompress brotli data
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_decompress_brotli(
@@ -1332,4 +1415,121 @@ mod tests {
         let name = fz_image_type_name(ImageType::Unknown as i32);
         assert!(!name.is_null());
     }
+
+    fn buffer_bytes(handle: Handle) -> Vec<u8> {
+        BUFFERS.get(handle).unwrap().lock().unwrap().data().to_vec()
+    }
+
+    /// Deterministic pseudo-random bytes mixed with repetitive runs
+    fn sample_bytes(len: usize) -> Vec<u8> {
+        let mut state = 0x2545_F491u32;
+        (0..len)
+            .map(|i| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                if i % 64 < 32 {
+                    b'a' + (i % 7) as u8
+                } else {
+                    state as u8
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_deflate_inflate_buffer_roundtrip() {
+        let original = sample_bytes(10_000);
+
+        for level in -1..=9 {
+            let compressed = fz_deflate_to_buffer(0, original.as_ptr(), original.len(), level);
+            assert_ne!(compressed, 0);
+            let compressed_bytes = buffer_bytes(compressed);
+
+            let inflated =
+                fz_inflate_to_buffer(0, compressed_bytes.as_ptr(), compressed_bytes.len());
+            assert_eq!(buffer_bytes(inflated), original, "level {level}");
+
+            BUFFERS.remove(compressed);
+            BUFFERS.remove(inflated);
+        }
+    }
+
+    #[test]
+    fn test_deflate_level_maps_to_zlib() {
+        let original = sample_bytes(4096);
+        let stored = fz_deflate_to_buffer(0, original.as_ptr(), original.len(), 0);
+        let best = fz_deflate_to_buffer(0, original.as_ptr(), original.len(), 9);
+
+        // Level 0 stores the data; level 9 must actually compress it
+        assert!(buffer_bytes(stored).len() > original.len());
+        assert!(buffer_bytes(best).len() < original.len());
+
+        BUFFERS.remove(stored);
+        BUFFERS.remove(best);
+    }
+
+    #[test]
+    fn test_inflate_reports_short_destination() {
+        let original = sample_bytes(1000);
+        let mut compressed_len = fz_deflate_bound(0, original.len());
+        let mut compressed = vec![0u8; compressed_len];
+        fz_deflate(
+            0,
+            compressed.as_mut_ptr(),
+            &mut compressed_len,
+            original.as_ptr(),
+            original.len(),
+            6,
+        );
+
+        let mut dest = vec![0u8; 100];
+        let mut dest_len = dest.len();
+        let result = fz_inflate(
+            0,
+            dest.as_mut_ptr(),
+            &mut dest_len,
+            compressed.as_ptr(),
+            compressed_len,
+        );
+        assert_eq!(result, -1);
+        assert_eq!(dest_len, original.len());
+    }
+
+    #[test]
+    fn test_new_deflated_buffer() {
+        let original = sample_bytes(2048);
+        let source = BUFFERS.insert(Buffer::from_data(&original));
+
+        let compressed = fz_new_deflated_buffer(0, source, 9);
+        assert_ne!(compressed, 0);
+        let compressed_bytes = buffer_bytes(compressed);
+        let inflated = fz_inflate_to_buffer(0, compressed_bytes.as_ptr(), compressed_bytes.len());
+        assert_eq!(buffer_bytes(inflated), original);
+
+        assert_eq!(fz_new_deflated_buffer(0, 0, 9), 0);
+
+        for handle in [source, compressed, inflated] {
+            BUFFERS.remove(handle);
+        }
+    }
+
+    #[test]
+    fn test_lzw_roundtrip() {
+        for original in [
+            Vec::new(),
+            b"TOBEORNOTTOBEORTOBEORNOT".to_vec(),
+            sample_bytes(20_000),
+        ] {
+            let compressed = fz_compress_lzw(0, original.as_ptr(), original.len());
+            assert_ne!(compressed, 0);
+            let compressed_bytes = buffer_bytes(compressed);
+
+            let decoded = fz_decompress_lzw(0, compressed_bytes.as_ptr(), compressed_bytes.len());
+            assert_eq!(buffer_bytes(decoded), original);
+
+            BUFFERS.remove(compressed);
+            BUFFERS.remove(decoded);
+        }
+    }
 }
@@ -2,9 +2,11 @@
 //! Simplified error handling without setjmp/longjmp
 
 use super::{CONTEXTS, Handle};
+use crate::fitz::error::Error;
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::ffi::{CStr, c_char, c_int, c_void};
-use std::sync::{Arc, LazyLock, Mutex};
+use std::ffi::{CStr, CString, c_char, c_int, c_void};
+use std::sync::{LazyLock, Mutex};
 
 /// Error codes matching MuPDF fz_error_type
 #[repr(C)]
@@ -23,20 +25,68 @@ pub enum FzErrorType {
     Repaired = 11,   // PDF repair flag
 }
 
-/// Context error state
-#[derive(Clone)]
-struct ErrorState {
+/// Last error caught on a context
+struct Caught {
     code: c_int,
-    message: String,
+    message: CString,
+}
+
+thread_local! {
+    /// Error slots by context handle. The context is an opaque handle that
+    /// may be shared between threads, so each thread sees only the errors
+    /// raised by its own calls.
+    static CAUGHT: RefCell<HashMap<Handle, Caught>> = RefCell::new(HashMap::new());
+}
+
+/// The `FzErrorType` code reported for a library error
+pub(crate) fn error_code(err: &Error) -> c_int {
+    let kind = match err {
+        Error::Generic(_) => FzErrorType::Generic,
+        Error::System(_) => FzErrorType::System,
+        Error::Argument(_) => FzErrorType::Argument,
+        Error::Limit(_) => FzErrorType::Limit,
+        Error::Unsupported(_) => FzErrorType::Unsupported,
+        Error::Syntax(_) => FzErrorType::Syntax,
+        Error::Abort => FzErrorType::Abort,
+        Error::Format(_)
+        | Error::Pdf(_)
+        | Error::Encryption(_)
+        | Error::Font(_)
+        | Error::Image(_)
+        | Error::Eof => FzErrorType::Format,
+    };
+    kind as c_int
 }
 
-impl Default for ErrorState {
-    fn default() -> Self {
-        Self {
-            code: FzErrorType::None as c_int,
-            message: String::new(),
-        }
-    }
+/// Record why an FFI call on `ctx` failed, for `fz_caught` and
+/// `fz_caught_message`
+pub(crate) fn set_caught(ctx: Handle, err: &Error) {
+    set_error(ctx, error_code(err), err.to_string());
+}
+
+fn set_error(ctx: Handle, code: c_int, message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    CAUGHT.with(|caught| caught.borrow_mut().insert(ctx, Caught { code, message }));
+}
+
+/// Code and message of the error caught on `ctx`, if any
+fn caught_error(ctx: Handle) -> Option<(c_int, String)> {
+    CAUGHT.with(|caught| {
+        caught
+            .borrow()
+            .get(&ctx)
+            .map(|c| (c.code, c.message.to_string_lossy().into_owned()))
+    })
+}
+
+/// Pointer to the caught message, valid until the next error on `ctx`
+/// from this thread
+fn caught_message_ptr(ctx: Handle) -> Option<*const c_char> {
+    CAUGHT.with(|caught| caught.borrow().get(&ctx).map(|c| c.message.as_ptr()))
+}
+
+fn clear_error(ctx: Handle) {
+    CAUGHT.with(|caught| caught.borrow_mut().remove(&ctx));
 }
 
 /// Context settings (ICC, AA level, etc.)
@@ -81,8 +131,6 @@ static STORE_STATE: LazyLock<Mutex<StoreState>> =
 pub struct Context {
     /// Max store size in bytes
     max_store: usize,
-    /// Error state
-    error: Arc<Mutex<ErrorState>>,
     /// User data pointer
     user_data: *mut c_void,
     /// Warning callback
@@ -91,7 +139,7 @@ pub struct Context {
     error_callback: Option<unsafe extern "C" fn(*mut c_void, c_int, *const c_char)>,
 }
 
-// Context is Send since we protect mutable state with Arc<Mutex>
+// Context is Send since it is only reached through its handle store's Mutex
 unsafe impl Send for Context {}
 unsafe impl Sync for Context {}
 
@@ -99,38 +147,12 @@ impl Context {
     pub fn new(max_store: usize) -> Self {
         Self {
             max_store,
-            error: Arc::new(Mutex::new(ErrorState::default())),
             user_data: std::ptr::null_mut(),
             warn_callback: None,
             error_callback: None,
         }
     }
 
-    pub fn set_error(&self, code: c_int, message: String) {
-        if let Ok(mut err) = self.error.lock() {
-            err.code = code;
-            err.message = message;
-        }
-    }
-
-    pub fn get_error(&self) -> (c_int, String) {
-        if let Ok(err) = self.error.lock() {
-            (err.code, err.message.clone())
-        } else {
-            (
-                FzErrorType::Generic as c_int,
-                "Failed to lock error state".into(),
-            )
-        }
-    }
-
-    pub fn clear_error(&self) {
-        if let Ok(mut err) = self.error.lock() {
-            err.code = FzErrorType::None as c_int;
-            err.message.clear();
-        }
-    }
-
     pub fn max_store(&self) -> usize {
         self.max_store
     }
@@ -146,6 +168,7 @@ pub unsafe extern "C" fn fz_new_context(
     _locks: *const c_void, // fz_locks_context* - ignored, we use Rust sync
     max_store: usize,
 ) -> Handle {
+    crate::ffi::log::install();
     CONTEXTS.insert(Context::new(max_store))
 }
 
@@ -204,10 +227,9 @@ pub unsafe extern "C" fn fz_throw(ctx: Handle, errcode: c_int, fmt: *const c_cha
         }
     };
 
+    set_error(ctx, errcode, message.clone());
     if let Some(context) = CONTEXTS.get(ctx) {
         if let Ok(guard) = context.lock() {
-            guard.set_error(errcode, message.clone());
-
             // Call error callback if set
             if let Some(callback) = guard.error_callback {
                 let msg_cstr = std::ffi::CString::new(message).unwrap_or_default();
@@ -224,8 +246,7 @@ pub unsafe extern "C" fn fz_throw(ctx: Handle, errcode: c_int, fmt: *const c_cha
 pub extern "C" fn fz_rethrow(ctx: Handle) {
     if let Some(context) = CONTEXTS.get(ctx) {
         if let Ok(guard) = context.lock() {
-            let (code, message) = guard.get_error();
-            if code != FzErrorType::None as c_int {
+            if let Some((code, message)) = caught_error(ctx) {
                 // Error already set, just call callback if present
                 if let Some(callback) = guard.error_callback {
                     let msg_cstr = std::ffi::CString::new(message).unwrap_or_default();
@@ -238,47 +259,25 @@ pub extern "C" fn fz_rethrow(ctx: Handle) {
     }
 }
 
-/// Get the current error code
+/// Get the code of the last error caught on this thread
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_caught(ctx: Handle) -> c_int {
-    if let Some(context) = CONTEXTS.get(ctx) {
-        if let Ok(guard) = context.lock() {
-            let (code, _) = guard.get_error();
-            return code;
-        }
-    }
-    FzErrorType::Generic as c_int
+    caught_error(ctx).map_or(FzErrorType::None as c_int, |(code, _)| code)
 }
 
-/// Get the current error message
+/// Get the message of the last error caught on this thread
 ///
-/// Returns a static string that remains valid until next error
+/// The string remains valid until the next error on this context and
+/// thread, or until the error is ignored.
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_caught_message(ctx: Handle) -> *const c_char {
-    if let Some(context) = CONTEXTS.get(ctx) {
-        if let Ok(guard) = context.lock() {
-            let (_, message) = guard.get_error();
-            if message.is_empty() {
-                return c"No error".as_ptr();
-            }
-            // Warning: This leaks memory, but matches MuPDF behavior
-            // In a full implementation, we'd cache this per-context
-            if let Ok(c_str) = std::ffi::CString::new(message) {
-                return c_str.into_raw();
-            }
-        }
-    }
-    c"Unknown error".as_ptr()
+    caught_message_ptr(ctx).unwrap_or(c"No error".as_ptr())
 }
 
 /// Clear the current error
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_ignore_error(ctx: Handle) {
-    if let Some(context) = CONTEXTS.get(ctx) {
-        if let Ok(guard) = context.lock() {
-            guard.clear_error();
-        }
-    }
+    clear_error(ctx);
 }
 
 /// Log a warning
@@ -298,12 +297,11 @@ pub unsafe extern "C" fn fz_warn(ctx: Handle, fmt: *const c_char) {
                     callback(guard.user_data, fmt);
                 }
             } else {
-                // Default: print to stderr
+                // Default: route through the log sink (stderr unless a
+                // log callback is installed)
                 // SAFETY: Caller guarantees fmt is a valid null-terminated C string
-                unsafe {
-                    if let Ok(c_str) = CStr::from_ptr(fmt).to_str() {
-                        eprintln!("warning: {}", c_str);
-                    }
+                if let Ok(c_str) = unsafe { CStr::from_ptr(fmt) }.to_str() {
+                    crate::ffi::log::warn("context", c_str);
                 }
             }
         }
@@ -368,32 +366,12 @@ pub extern "C" fn fz_store_size(ctx: Handle) -> usize {
 /// Returns error message and sets code
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_convert_error(ctx: Handle, code: *mut c_int) -> *const c_char {
-    if let Some(context) = CONTEXTS.get(ctx) {
-        if let Ok(guard) = context.lock() {
-            let (err_code, message) = guard.get_error();
-            if !code.is_null() {
-                unsafe {
-                    *code = err_code;
-                }
-            }
-
-            if message.is_empty() {
-                return c"No error".as_ptr();
-            }
-
-            // Warning: This leaks memory
-            if let Ok(c_str) = std::ffi::CString::new(message) {
-                return c_str.into_raw();
-            }
-        }
-    }
-
     if !code.is_null() {
         unsafe {
-            *code = FzErrorType::Generic as c_int;
+            *code = fz_caught(ctx);
         }
     }
-    c"Unknown error".as_ptr()
+    fz_caught_message(ctx)
 }
 
 /// Report error (calls error callback)
@@ -401,8 +379,7 @@ pub extern "C" fn fz_convert_error(ctx: Handle, code: *mut c_int) -> *const c_ch
 pub extern "C" fn fz_report_error(ctx: Handle) {
     if let Some(context) = CONTEXTS.get(ctx) {
         if let Ok(guard) = context.lock() {
-            let (code, message) = guard.get_error();
-            if code != FzErrorType::None as c_int {
+            if let Some((code, message)) = caught_error(ctx) {
                 if let Some(callback) = guard.error_callback {
                     let msg_cstr = std::ffi::CString::new(message).unwrap_or_default();
                     unsafe {
@@ -542,10 +519,62 @@ pub extern "C" fn fz_aa_level(ctx: Handle) -> c_int {
     8
 }
 
+/// Set the limits applied when parsing documents, to guard against
+/// decompression bombs and other hostile input
+///
+/// `max_stream_size` caps each decoded stream, `max_decoded_total` all the
+/// streams one document decodes together and `max_file_size` the size of a
+/// document file, in bytes; `max_nesting` caps array and dictionary
+/// nesting and `max_objects` the objects a document defines. A value of 0
+/// restores that limit's default. The limits apply to every context and
+/// take effect for documents opened afterwards; exceeding one raises a
+/// `FZ_ERROR_LIMIT` error.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_set_parse_limits(
+    _ctx: Handle,
+    max_stream_size: u64,
+    max_nesting: c_int,
+    max_objects: u64,
+    max_file_size: u64,
+    max_decoded_total: u64,
+) {
+    use crate::pdf::limits::{ParseLimits, set_parse_limits};
+
+    let defaults = ParseLimits::DEFAULT;
+    let or_default = |value: u64, default: u64| if value == 0 { default } else { value };
+    set_parse_limits(ParseLimits {
+        max_stream_size: or_default(max_stream_size, defaults.max_stream_size),
+        max_nesting: usize::try_from(max_nesting)
+            .ok()
+            .filter(|&n| n > 0)
+            .unwrap_or(defaults.max_nesting),
+        max_objects: or_default(max_objects, defaults.max_objects),
+        max_file_size: or_default(max_file_size, defaults.max_file_size),
+        max_decoded_total: or_default(max_decoded_total, defaults.max_decoded_total),
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_set_parse_limits() {
+        use crate::pdf::limits::{ParseLimits, parse_limits};
+
+        // Only raise limits: other tests parse concurrently
+        fz_set_parse_limits(0, 2 << 30, 0, 0, 8 << 30, 32 << 30);
+        let limits = parse_limits();
+        assert_eq!(limits.max_stream_size, 2 << 30);
+        assert_eq!(limits.max_file_size, 8 << 30);
+        assert_eq!(limits.max_decoded_total, 32 << 30);
+        assert_eq!(limits.max_nesting, ParseLimits::DEFAULT.max_nesting);
+        assert_eq!(limits.max_objects, ParseLimits::DEFAULT.max_objects);
+
+        fz_set_parse_limits(0, 0, 0, 0, 0, 0);
+        assert_eq!(parse_limits(), ParseLimits::DEFAULT);
+    }
+
     #[test]
     fn test_context_create_drop() {
         let ctx = unsafe { fz_new_context(std::ptr::null(), std::ptr::null(), 1024 * 1024) };
@@ -7,6 +7,9 @@ use std::sync::LazyLock;
 use super::{Handle, HandleStore};
 use crate::fitz::cookie::Cookie;
 
+/// Return code of operations stopped through `fz_cookie_abort`
+pub const FZ_ERROR_ABORT: i32 = -2;
+
 /// Global storage for cookies
 pub static COOKIES: LazyLock<HandleStore<Cookie>> = LazyLock::new(HandleStore::new);
 
@@ -84,6 +87,41 @@ pub extern "C" fn fz_cookie_reset_abort(_ctx: Handle, cookie: Handle) {
     }
 }
 
+/// Limit the content stream operators run with this cookie
+///
+/// Rendering stops, keeping what was drawn, once more than `max_steps`
+/// operators have run. A negative value removes the limit.
+///
+/// # Arguments
+/// * `cookie` - Handle to the cookie
+/// * `max_steps` - Operators allowed, or negative for no limit
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_cookie_set_step_budget(_ctx: Handle, cookie: Handle, max_steps: i64) {
+    if let Some(c) = COOKIES.get(cookie) {
+        if let Ok(guard) = c.lock() {
+            guard.set_step_budget(u64::try_from(max_steps).ok());
+        }
+    }
+}
+
+/// Stop operations using this cookie once `timeout_ms` milliseconds from
+/// now have passed
+///
+/// # Arguments
+/// * `cookie` - Handle to the cookie
+/// * `timeout_ms` - Time allowed, or negative for no deadline
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_cookie_set_timeout(_ctx: Handle, cookie: Handle, timeout_ms: i64) {
+    if let Some(c) = COOKIES.get(cookie) {
+        if let Ok(guard) = c.lock() {
+            let timeout = u64::try_from(timeout_ms)
+                .ok()
+                .map(std::time::Duration::from_millis);
+            guard.set_timeout(timeout);
+        }
+    }
+}
+
 /// Get current progress
 ///
 /// # Arguments
@@ -101,6 +139,18 @@ pub extern "C" fn fz_cookie_get_progress(_ctx: Handle, cookie: Handle) -> i32 {
     0
 }
 
+/// Get current progress (number of pages, objects or passes completed)
+///
+/// # Arguments
+/// * `cookie` - Handle to the cookie
+///
+/// # Returns
+/// Current progress value
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_cookie_progress(_ctx: Handle, cookie: Handle) -> i32 {
+    fz_cookie_get_progress(_ctx, cookie)
+}
+
 /// Set current progress
 ///
 /// # Arguments
@@ -403,6 +453,17 @@ mod tests {
         fz_cookie_reset_abort(0, cookie);
         assert_eq!(fz_cookie_should_abort(0, cookie), 0);
 
+        fz_cookie_set_step_budget(0, cookie, 0);
+        COOKIES.get(cookie).unwrap().lock().unwrap().step();
+        assert_eq!(fz_cookie_should_abort(0, cookie), 1);
+        fz_cookie_set_step_budget(0, cookie, -1);
+        assert_eq!(fz_cookie_should_abort(0, cookie), 0);
+
+        fz_cookie_set_timeout(0, cookie, 0);
+        assert_eq!(fz_cookie_should_abort(0, cookie), 1);
+        fz_cookie_set_timeout(0, cookie, -1);
+        assert_eq!(fz_cookie_should_abort(0, cookie), 0);
+
         fz_drop_cookie(0, cookie);
     }
 
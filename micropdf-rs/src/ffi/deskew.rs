@@ -39,13 +39,29 @@ impl DeskewBorder {
 // Skew Detection Algorithm
 // ============================================================================
 
+/// Largest side of the image the angle search runs on; larger images are
+/// box-downsampled first
+const MAX_DETECT_SIZE: usize = 512;
+
+/// Largest skew searched for, in degrees
+const MAX_SKEW: f64 = 15.0;
+
+/// Step of the coarse angle search, in degrees
+const COARSE_STEP: f64 = 0.5;
+
+/// Step of the refinement around the best coarse angle, in degrees
+const FINE_STEP: f64 = 0.05;
+
+/// Edge strength below which a pixel does not contribute to a profile
+const EDGE_THRESHOLD: u8 = 30;
+
 /// Detect skew angle using the projection profile method
 ///
-/// This algorithm:
-/// 1. Converts image to grayscale if needed
-/// 2. Applies edge detection (Sobel-like)
-/// 3. Uses Hough transform variant for line detection
-/// 4. Returns the dominant angle
+/// The image is converted to gray and downsampled so its longest side is
+/// at most 512 pixels. Edge pixels are then projected onto the vertical
+/// axis at each candidate angle in ±15°; text lines and rules give the
+/// sharpest profile, measured by its variance, at the skew angle. Returns
+/// degrees, positive when content is rotated clockwise.
 pub fn detect_skew(pixmap: &Pixmap) -> f64 {
     let width = pixmap.w() as usize;
     let height = pixmap.h() as usize;
@@ -74,13 +90,37 @@ pub fn detect_skew(pixmap: &Pixmap) -> f64 {
             .collect()
     };
 
+    let (gray, width, height) = downsample(&gray, width, height);
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
     // Detect edges using simple gradient
     let edges = detect_edges(&gray, width, height);
 
     // Find dominant angle using projection profile method
-    let angle = find_dominant_angle(&edges, width, height);
+    find_dominant_angle(&edges, width, height)
+}
 
-    angle
+/// Box-average `gray` so neither side exceeds [`MAX_DETECT_SIZE`]
+fn downsample(gray: &[u8], width: usize, height: usize) -> (Vec<u8>, usize, usize) {
+    let factor = width.max(height).div_ceil(MAX_DETECT_SIZE);
+    if factor <= 1 {
+        return (gray.to_vec(), width, height);
+    }
+    let (dw, dh) = (width / factor, height / factor);
+    let mut out = Vec::with_capacity(dw * dh);
+    for dy in 0..dh {
+        for dx in 0..dw {
+            let mut sum = 0u32;
+            for y in dy * factor..(dy + 1) * factor {
+                let row = &gray[y * width + dx * factor..y * width + (dx + 1) * factor];
+                sum += row.iter().map(|&v| v as u32).sum::<u32>();
+            }
+            out.push((sum / (factor * factor) as u32) as u8);
+        }
+    }
+    (out, dw, dh)
 }
 
 /// Simple edge detection using Sobel-like operators
@@ -116,94 +156,80 @@ fn detect_edges(gray: &[u8], width: usize, height: usize) -> Vec<u8> {
 
 /// Find dominant angle using projection profile method
 fn find_dominant_angle(edges: &[u8], width: usize, height: usize) -> f64 {
-    let mut best_angle = 0.0;
-    let mut best_variance = 0.0;
-
-    // Test angles from -15 to +15 degrees in 0.1 degree steps
-    let step = 0.1_f64;
-    let mut angle = -15.0_f64;
-
-    while angle <= 15.0 {
-        let variance = calculate_projection_variance(edges, width, height, angle);
-
-        if variance > best_variance {
-            best_variance = variance;
-            best_angle = angle;
-        }
-
-        angle += step;
+    // Only edge pixels contribute, so gather them once
+    let cx = width as f64 / 2.0;
+    let cy = height as f64 / 2.0;
+    let points: Vec<(f64, f64, u32)> = edges
+        .iter()
+        .enumerate()
+        .filter(|&(_, &e)| e > EDGE_THRESHOLD)
+        .map(|(i, &e)| ((i % width) as f64 - cx, (i / width) as f64 - cy, e as u32))
+        .collect();
+    if points.is_empty() {
+        return 0.0;
     }
 
-    // Refine with smaller steps around best angle
-    let refined_step = 0.01_f64;
-    let mut refined_angle = best_angle - step;
-    let end_angle = best_angle + step;
-
-    while refined_angle <= end_angle {
-        let variance = calculate_projection_variance(edges, width, height, refined_angle);
-
-        if variance > best_variance {
-            best_variance = variance;
-            best_angle = refined_angle;
+    let search = |from: f64, to: f64, step: f64, mut best: (f64, f64)| {
+        let steps = ((to - from) / step).round() as i32;
+        for i in 0..=steps {
+            let angle = from + i as f64 * step;
+            let variance = projection_variance(&points, height, angle);
+            if variance > best.1 {
+                best = (angle, variance);
+            }
         }
+        best
+    };
 
-        refined_angle += refined_step;
-    }
-
-    best_angle
+    let (coarse, variance) = search(-MAX_SKEW, MAX_SKEW, COARSE_STEP, (0.0, 0.0));
+    let (best, _) = search(
+        (coarse - COARSE_STEP).max(-MAX_SKEW),
+        (coarse + COARSE_STEP).min(MAX_SKEW),
+        FINE_STEP,
+        (coarse, variance),
+    );
+    best
 }
 
-/// Calculate variance of horizontal projection at given angle
-fn calculate_projection_variance(edges: &[u8], width: usize, height: usize, angle: f64) -> f64 {
+/// Variance of the edge profile projected onto the vertical axis of a frame
+/// rotated by `angle` degrees
+fn projection_variance(points: &[(f64, f64, u32)], height: usize, angle: f64) -> f64 {
     let rad = angle * PI / 180.0;
-    let cos_a = rad.cos();
-    let sin_a = rad.sin();
-
-    let cx = width as f64 / 2.0;
+    let (sin_a, cos_a) = rad.sin_cos();
     let cy = height as f64 / 2.0;
 
-    // Project onto rotated horizontal axis
     let mut projection = vec![0u32; height];
-
-    for y in 0..height {
-        for x in 0..width {
-            let edge_val = edges[y * width + x] as u32;
-            if edge_val > 30 {
-                // Threshold
-                // Rotate point
-                let dx = x as f64 - cx;
-                let dy = y as f64 - cy;
-                let ry = (-dx * sin_a + dy * cos_a + cy) as usize;
-
-                if ry < height {
-                    projection[ry] += edge_val;
-                }
-            }
+    for &(dx, dy, edge_val) in points {
+        let ry = -dx * sin_a + dy * cos_a + cy;
+        if ry >= 0.0 && (ry as usize) < height {
+            projection[ry as usize] += edge_val;
         }
     }
 
-    // Calculate variance
     let sum: u64 = projection.iter().map(|&v| v as u64).sum();
     let mean = sum as f64 / height as f64;
-
-    let variance: f64 = projection
+    projection
         .iter()
         .map(|&v| {
             let diff = v as f64 - mean;
             diff * diff
         })
         .sum::<f64>()
-        / height as f64;
-
-    variance
+        / height as f64
 }
 
 // ============================================================================
 // Deskew (Rotation) Algorithm
 // ============================================================================
 
-/// Deskew a pixmap by rotating it
-pub fn deskew_pixmap(pixmap: &Pixmap, degrees: f64, border: DeskewBorder) -> Option<Pixmap> {
+/// Undo a skew of `skew` degrees, as returned by [`detect_skew`]
+pub fn deskew_pixmap(pixmap: &Pixmap, skew: f64, border: DeskewBorder) -> Option<Pixmap> {
+    rotate_pixmap(pixmap, -skew, border)
+}
+
+/// Rotate a pixmap clockwise by `degrees` about its center with bilinear
+/// sampling, filling uncovered areas with white
+pub fn rotate_pixmap(pixmap: &Pixmap, degrees: f64, border: DeskewBorder) -> Option<Pixmap> {
     let src_width = pixmap.w();
     let src_height = pixmap.h();
     let n = pixmap.n();
@@ -250,7 +276,7 @@ pub fn deskew_pixmap(pixmap: &Pixmap, degrees: f64, border: DeskewBorder) -> Opt
     };
 
     // Create destination pixmap
-    let mut dst = Pixmap::new(FZ_COLORSPACE_GRAY, dst_width, dst_height, alpha);
+    let mut dst = Pixmap::new(pixmap.colorspace(), dst_width, dst_height, alpha);
 
     // Center points
     let src_cx = src_width as f64 / 2.0;
@@ -338,11 +364,11 @@ pub extern "C" fn fz_detect_skew(_ctx: Handle, pixmap: Handle) -> f64 {
     detect_skew(&guard)
 }
 
-/// Deskew a pixmap by rotating it
+/// Deskew a pixmap by rotating it back by the skew angle
 ///
 /// @param ctx      Context handle
 /// @param src      Source pixmap handle
-/// @param degrees  Rotation angle in degrees (use negative of detected skew)
+/// @param degrees  Skew angle in degrees, as returned by fz_detect_skew
 /// @param border   Border handling mode (0=increase, 1=maintain, 2=decrease)
 ///
 /// Returns handle to new deskewed pixmap, or 0 on error
@@ -387,13 +413,10 @@ pub extern "C" fn fz_auto_deskew_pixmap(_ctx: Handle, src: Handle, border: i32)
         Err(_) => return 0,
     };
 
-    // Detect skew
     let skew_angle = detect_skew(&guard);
-
-    // Apply correction (negative angle to counteract skew)
     let border_mode = DeskewBorder::from_i32(border);
 
-    match deskew_pixmap(&guard, -skew_angle, border_mode) {
+    match deskew_pixmap(&guard, skew_angle, border_mode) {
         Some(result) => PIXMAPS.insert(result),
         None => 0,
     }
@@ -463,8 +486,20 @@ pub extern "C" fn fz_is_skewed(_ctx: Handle, pixmap: Handle, threshold: f64) ->
 /// Returns handle to rotated pixmap, or 0 on error
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_rotate_pixmap(_ctx: Handle, src: Handle, degrees: f64, border: i32) -> Handle {
-    // Same as deskew, but doesn't imply the deskew use case
-    fz_deskew_pixmap(_ctx, src, degrees, border)
+    let pix = match PIXMAPS.get(src) {
+        Some(p) => p,
+        None => return 0,
+    };
+
+    let guard = match pix.lock() {
+        Ok(g) => g,
+        Err(_) => return 0,
+    };
+
+    match rotate_pixmap(&guard, degrees, DeskewBorder::from_i32(border)) {
+        Some(result) => PIXMAPS.insert(result),
+        None => 0,
+    }
 }
 
 /// Rotate pixmap by 90-degree increments (fast path)
@@ -725,6 +760,42 @@ mod tests {
         );
     }
 
+    /// A page of dark text-like bars on white
+    fn create_text_lines_pixmap(width: i32, height: i32) -> Pixmap {
+        let mut pix = Pixmap::new(FZ_COLORSPACE_GRAY, width, height, false);
+        pix.clear_with_value(255);
+        let w = width as usize;
+        let samples = pix.samples_mut();
+        for y in (height as usize / 8..height as usize * 7 / 8).step_by(24) {
+            for row in y..y + 6 {
+                for x in w / 8..w * 7 / 8 {
+                    // Gaps between words
+                    if x % 60 < 50 {
+                        samples[row * w + x] = 0;
+                    }
+                }
+            }
+        }
+        pix
+    }
+
+    #[test]
+    fn test_detect_known_skew() {
+        let page = create_text_lines_pixmap(1200, 1200);
+        let skewed = rotate_pixmap(&page, 5.0, DeskewBorder::Maintain).unwrap();
+        let angle = detect_skew(&skewed);
+        assert!((angle - 5.0).abs() <= 0.5, "detected {angle}");
+
+        let handle = PIXMAPS.insert(skewed);
+        assert!((fz_detect_skew(1, handle) - angle).abs() < 1e-9);
+        let fixed = fz_deskew_pixmap(1, handle, angle, DeskewBorder::Maintain as i32);
+        let residual = detect_skew(&PIXMAPS.get(fixed).unwrap().lock().unwrap());
+        assert!(residual.abs() <= 0.5, "residual {residual}");
+
+        PIXMAPS.remove(handle);
+        PIXMAPS.remove(fixed);
+    }
+
     #[test]
     fn test_ffi_detect_skew() {
         let handle = create_test_pixmap(200, 200);
@@ -19,19 +19,17 @@ static DEVICE_HINTS: LazyLock<Mutex<HashMap<Handle, i32>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
 /// Convert FFI colorspace to Fitz colorspace
+///
+/// Accepts the device colorspace constants as well as custom handles.
 fn get_fitz_colorspace(handle: Handle) -> Option<FitzColorspace> {
-    super::colorspace::COLORSPACES
-        .get(handle)
-        .and_then(|cs_arc| {
-            cs_arc.lock().ok().map(|cs_guard| {
-                match cs_guard.cs_type {
-                    super::colorspace::ColorspaceType::Gray => FitzColorspace::device_gray(),
-                    super::colorspace::ColorspaceType::Rgb => FitzColorspace::device_rgb(),
-                    super::colorspace::ColorspaceType::Cmyk => FitzColorspace::device_cmyk(),
-                    _ => FitzColorspace::device_rgb(), // Default fallback
-                }
-            })
-        })
+    use super::colorspace::{ColorspaceType, colorspace_type};
+    match colorspace_type(handle) {
+        ColorspaceType::None => None,
+        ColorspaceType::Gray => Some(FitzColorspace::device_gray()),
+        ColorspaceType::Rgb => Some(FitzColorspace::device_rgb()),
+        ColorspaceType::Cmyk => Some(FitzColorspace::device_cmyk()),
+        _ => Some(FitzColorspace::device_rgb()), // Default fallback
+    }
 }
 
 /// Create a new draw device for rendering to a pixmap
@@ -75,13 +73,14 @@ pub extern "C" fn fz_new_trace_device(_ctx: Handle) -> Handle {
 
 /// Create a list device for recording display list
 ///
-/// # Safety
-/// Caller must ensure list is a valid handle.
+/// Operations sent to the device are added to `list` when the device is
+/// closed or dropped. Returns 0 if `list` is not a display list.
 #[unsafe(no_mangle)]
-pub extern "C" fn fz_new_list_device(_ctx: Handle, _list: Handle) -> Handle {
-    // Create list device with default media box (will be updated when used)
-    let mediabox = Rect::new(0.0, 0.0, 612.0, 792.0); // Letter size default
-    let device: Box<dyn Device + Send + Sync> = Box::new(ListDevice::new(mediabox));
+pub extern "C" fn fz_new_list_device(_ctx: Handle, list: Handle) -> Handle {
+    let Some(target) = super::display_list::DISPLAY_LISTS.get(list) else {
+        return 0;
+    };
+    let device: Box<dyn Device + Send + Sync> = Box::new(ListDevice::for_list(target));
     DEVICES.insert(device)
 }
 
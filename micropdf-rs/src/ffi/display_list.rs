@@ -4,6 +4,7 @@
 
 use std::sync::LazyLock;
 
+use super::store::{StoreType, fz_store_item};
 use super::{Handle, HandleStore};
 use crate::fitz::display_list::DisplayList;
 use crate::fitz::geometry::{Matrix, Rect};
@@ -186,13 +187,65 @@ pub extern "C" fn fz_display_list_is_valid(_ctx: Handle, list: Handle) -> i32 {
 pub extern "C" fn fz_clone_display_list(_ctx: Handle, list: Handle) -> Handle {
     if let Some(l) = DISPLAY_LISTS.get(list) {
         if let Ok(guard) = l.lock() {
-            let cloned = DisplayList::new(guard.mediabox());
-            return DISPLAY_LISTS.insert(cloned);
+            return DISPLAY_LISTS.insert(guard.clone());
         }
     }
     0
 }
 
+/// Register a display list in the resource store
+///
+/// The list is stored as `StoreType::DisplayList` with its estimated size,
+/// so it counts against the store budget and can be found again by key.
+///
+/// # Arguments
+/// * `list` - Handle to the display list
+/// * `key`, `key_len` - Lookup key (may be empty)
+///
+/// # Returns
+/// Store item ID, or 0 on error
+///
+/// # Safety
+/// `key` must point to valid memory of `key_len` bytes.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_store_display_list(
+    ctx: Handle,
+    list: Handle,
+    key: *const u8,
+    key_len: usize,
+) -> u64 {
+    let size = match DISPLAY_LISTS.get(list) {
+        Some(l) => match l.lock() {
+            Ok(guard) => guard.size_estimate(),
+            Err(_) => return 0,
+        },
+        None => return 0,
+    };
+    fz_store_item(ctx, StoreType::DisplayList as i32, list, size, key, key_len)
+}
+
+/// Store key for the display list of page `page` of `doc`
+pub(crate) fn page_list_key(doc: Handle, page: i32) -> Vec<u8> {
+    format!("page-display-list:{doc}:{page}").into_bytes()
+}
+
+/// Register a display list recorded from a page, in page space, so page
+/// renderers such as `fz_render_page_thumbnail` can replay it instead of
+/// interpreting the page again
+///
+/// # Returns
+/// Store item ID, or 0 on error
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_store_page_display_list(
+    ctx: Handle,
+    list: Handle,
+    doc: Handle,
+    page: i32,
+) -> u64 {
+    let key = page_list_key(doc, page);
+    fz_store_display_list(ctx, list, key.as_ptr(), key.len())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -277,4 +330,89 @@ mod tests {
         crate::ffi::device::fz_drop_device(0, bbox_dev);
         fz_drop_display_list(0, list);
     }
+
+    fn square(x: f32) -> Handle {
+        let path = crate::ffi::path::fz_new_path(0);
+        crate::ffi::path::fz_moveto(0, path, x, 0.0);
+        crate::ffi::path::fz_lineto(0, path, x + 10.0, 0.0);
+        crate::ffi::path::fz_lineto(0, path, x + 10.0, 10.0);
+        crate::ffi::path::fz_closepath(0, path);
+        path
+    }
+
+    #[test]
+    fn test_record_and_replay_fills() {
+        use crate::ffi::colorspace::fz_device_rgb;
+        use crate::ffi::device::{fz_close_device, fz_drop_device, fz_fill_path};
+        use crate::ffi::geometry::fz_matrix;
+
+        let list = fz_new_display_list(0, 0.0, 0.0, 100.0, 100.0);
+        let dev = crate::ffi::device::fz_new_list_device(0, list);
+        assert_ne!(dev, 0);
+
+        let red = [1.0f32, 0.0, 0.0];
+        for x in [0.0, 50.0] {
+            let path = square(x);
+            fz_fill_path(
+                0,
+                dev,
+                path,
+                0,
+                fz_matrix::identity(),
+                fz_device_rgb(0),
+                red.as_ptr(),
+                1.0,
+            );
+            crate::ffi::path::fz_drop_path(0, path);
+        }
+        fz_close_device(0, dev);
+        fz_drop_device(0, dev);
+        assert_eq!(fz_display_list_count_commands(0, list), 2);
+
+        // Replay into another list, which counts what it receives
+        let copy = fz_new_display_list(0, 0.0, 0.0, 200.0, 200.0);
+        let counter = crate::ffi::device::fz_new_list_device(0, copy);
+        let zoom = fz_matrix {
+            a: 2.0,
+            b: 0.0,
+            c: 0.0,
+            d: 2.0,
+            e: 0.0,
+            f: 0.0,
+        };
+        let infinite = super::super::geometry::fz_rect {
+            x0: f32::NEG_INFINITY,
+            y0: f32::NEG_INFINITY,
+            x1: f32::INFINITY,
+            y1: f32::INFINITY,
+        };
+        fz_run_display_list(0, list, counter, zoom, infinite);
+        fz_run_display_list(0, list, counter, zoom, infinite);
+        fz_drop_device(0, counter);
+        assert_eq!(fz_display_list_count_commands(0, copy), 4);
+        assert_eq!(fz_display_list_count_commands(0, list), 2);
+
+        fz_drop_display_list(0, copy);
+        fz_drop_display_list(0, list);
+    }
+
+    #[test]
+    fn test_store_display_list() {
+        let list = fz_new_display_list(0, 0.0, 0.0, 100.0, 100.0);
+        let key = b"test_store_display_list";
+        let id = fz_store_display_list(0, list, key.as_ptr(), key.len());
+        assert_ne!(id, 0);
+        assert_eq!(
+            crate::ffi::store::fz_store_item_type(0, id),
+            StoreType::DisplayList as i32
+        );
+        assert_eq!(
+            crate::ffi::store::fz_store_find(0, key.as_ptr(), key.len()),
+            list
+        );
+
+        crate::ffi::store::fz_store_remove(0, id);
+        assert_eq!(fz_store_display_list(0, 0, key.as_ptr(), key.len()), 0);
+        fz_drop_display_list(0, list);
+    }
 }
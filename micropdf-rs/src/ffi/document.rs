@@ -1,10 +1,17 @@
 //! C FFI for document - MuPDF compatible
 //! Safe Rust implementation using handle-based resource management
 
+use super::context::set_caught;
 use super::outline::OUTLINES;
+use super::stream::Stream;
 use super::{DOCUMENTS, Handle, HandleStore, STREAMS};
+use crate::fitz::error::Error;
+use crate::pdf::crypt::{AuthLevel, Crypt, Permission};
+use crate::pdf::object::Object;
+use crate::pdf::parser;
+use bytes::Bytes;
 use std::ffi::{c_char, c_float};
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock};
 
 /// Page storage
 pub static PAGES: LazyLock<HandleStore<Page>> = LazyLock::new(HandleStore::default);
@@ -84,22 +91,41 @@ impl Page {
 
 /// Internal document state
 pub struct Document {
-    // PDF document data - will be expanded with actual PDF parsing
-    #[allow(dead_code)]
-    data: Vec<u8>,
+    /// The file, shared or memory-mapped when opened from a stream
+    data: Bytes,
     page_count: i32,
     needs_password: bool,
     authenticated: bool,
-    password: Option<String>,
+    /// Standard security handler, if the document is encrypted
+    crypt: Option<Crypt>,
+    /// Granted FZ_PERMISSION_* flags
+    permissions: i32,
     pub format: String,
+    /// The bytes parsed by the PDF layer, kept for later calls until the
+    /// bytes change
+    pdf: Option<Arc<crate::pdf::document::Document>>,
 }
 
 impl Document {
-    pub fn new(data: Vec<u8>) -> Self {
-        // Basic PDF detection and page count estimation
-        // In a real implementation, this would parse the PDF structure
-        let page_count = Self::estimate_page_count(&data);
+    pub fn new(data: impl Into<Bytes>) -> Self {
+        let data = data.into();
+        let pdf = crate::pdf::document::Document::open_bytes(data.clone()).ok();
+        let page_count = Self::count_pages(&data, pdf.as_ref());
+        let mut doc = Self::with_page_count(data, page_count);
+        doc.pdf = pdf.map(Arc::new);
+        doc
+    }
+
+    /// A document for bytes already parsed by the PDF layer
+    pub(crate) fn from_pdf(pdf: crate::pdf::document::Document) -> Self {
+        let data = pdf.data().clone();
+        let page_count = Self::count_pages(&data, Some(&pdf));
+        let mut doc = Self::with_page_count(data, page_count);
+        doc.pdf = Some(Arc::new(pdf));
+        doc
+    }
 
+    fn with_page_count(data: Bytes, page_count: i32) -> Self {
         // Detect format from magic bytes
         let format = if data.starts_with(b"%PDF-") {
             "PDF".to_string()
@@ -109,14 +135,103 @@ impl Document {
             "Unknown".to_string()
         };
 
-        Self {
+        let mut crypt = Self::load_crypt(&data);
+        let needs_password = crypt
+            .as_mut()
+            .is_some_and(|c| c.authenticate(b"") == AuthLevel::None);
+
+        let mut doc = Self {
             data,
             page_count,
-            needs_password: false,
-            authenticated: true,
-            password: None,
+            needs_password,
+            authenticated: !needs_password,
+            crypt,
+            permissions: FZ_PERMISSION_ALL,
             format,
+            pdf: None,
+        };
+        doc.update_permissions();
+        doc
+    }
+
+    /// Raw bytes of the document
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The document bytes, shared without copying
+    pub(crate) fn bytes(&self) -> Bytes {
+        self.data.clone()
+    }
+
+    /// Replace the document bytes after an in-place edit such as an
+    /// incremental update
+    pub(crate) fn set_data(&mut self, data: Vec<u8>) {
+        let data = Bytes::from(data);
+        let pdf = crate::pdf::document::Document::open_bytes(data.clone()).ok();
+        self.page_count = Self::count_pages(&data, pdf.as_ref());
+        self.pdf = pdf.map(Arc::new);
+        self.data = data;
+    }
+
+    /// The bytes parsed by the PDF layer, parsed on first use and shared
+    /// by later calls
+    pub(crate) fn pdf(
+        &mut self,
+    ) -> crate::fitz::error::Result<Arc<crate::pdf::document::Document>> {
+        if let Some(pdf) = &self.pdf {
+            return Ok(pdf.clone());
         }
+        let pdf = Arc::new(crate::pdf::document::Document::open_bytes(
+            self.data.clone(),
+        )?);
+        self.pdf = Some(pdf.clone());
+        Ok(pdf)
+    }
+
+    /// Load the security handler named by the trailer's /Encrypt entry
+    fn load_crypt(data: &[u8]) -> Option<Crypt> {
+        let trailer = parser::find_trailer(data)?;
+        let encrypt = match trailer.get("Encrypt")? {
+            Object::Ref(r) => parser::find_object(data, *r)?,
+            obj => obj.clone(),
+        };
+        let id = trailer
+            .get("ID")
+            .and_then(Object::as_array)
+            .and_then(|ids| ids.first())
+            .and_then(Object::as_string)
+            .map(|s| s.as_bytes().to_vec())
+            .unwrap_or_default();
+        Crypt::from_encrypt_dict(encrypt.as_dict()?, &id).ok()
+    }
+
+    /// Map the decoded /P bits onto FZ_PERMISSION_* flags
+    fn update_permissions(&mut self) {
+        let Some(crypt) = &self.crypt else {
+            self.permissions = FZ_PERMISSION_ALL;
+            return;
+        };
+        let mut permissions = 0;
+        for (perm, flag) in [
+            (Permission::Print, FZ_PERMISSION_PRINT),
+            (Permission::Copy, FZ_PERMISSION_COPY),
+            (Permission::Modify, FZ_PERMISSION_EDIT),
+            (Permission::Annotate, FZ_PERMISSION_ANNOTATE),
+        ] {
+            if crypt.has_permission(perm) {
+                permissions |= flag;
+            }
+        }
+        self.permissions = permissions;
+    }
+
+    /// Pages in the page tree, rebuilding a damaged xref if need be; the
+    /// `/Type /Page` estimate covers files the PDF layer cannot open
+    fn count_pages(data: &[u8], pdf: Option<&crate::pdf::document::Document>) -> i32 {
+        pdf.and_then(|doc| doc.page_count().ok())
+            .map(|count| count as i32)
+            .unwrap_or_else(|| Self::estimate_page_count(data))
     }
 
     fn estimate_page_count(data: &[u8]) -> i32 {
@@ -135,13 +250,71 @@ impl Document {
     }
 }
 
+/// The document behind a handle as parsed by the PDF layer, parsed once
+/// and shared by every call until its bytes change
+pub(crate) fn open_pdf(doc: Handle) -> Option<Arc<crate::pdf::document::Document>> {
+    load_pdf(doc).ok()
+}
+
+/// Like [`open_pdf`], reporting why the document could not be parsed
+pub(crate) fn load_pdf(
+    doc: Handle,
+) -> crate::fitz::error::Result<Arc<crate::pdf::document::Document>> {
+    DOCUMENTS
+        .get(doc)
+        .ok_or_else(|| Error::argument("invalid document handle"))?
+        .lock()
+        .unwrap()
+        .pdf()
+}
+
 /// Open a document from file
 ///
 /// # Safety
 /// Caller must ensure `filename` is a valid null-terminated C string.
 #[unsafe(no_mangle)]
-pub extern "C" fn fz_open_document(_ctx: Handle, filename: *const c_char) -> Handle {
+pub extern "C" fn fz_open_document(ctx: Handle, filename: *const c_char) -> Handle {
+    if filename.is_null() {
+        set_caught(ctx, &Error::argument("no filename"));
+        return 0;
+    }
+
+    // SAFETY: Caller guarantees filename is a valid null-terminated C string
+    let c_str = unsafe { std::ffi::CStr::from_ptr(filename) };
+    let path = match c_str.to_str() {
+        Ok(s) => s,
+        Err(_) => {
+            set_caught(ctx, &Error::argument("filename is not valid UTF-8"));
+            return 0;
+        }
+    };
+
+    // Files the PDF layer cannot make sense of, even by repair, are
+    // rejected here rather than on first use
+    let opened = std::fs::read(path)
+        .map_err(Error::System)
+        .and_then(crate::pdf::document::Document::open_bytes);
+    match opened {
+        Ok(pdf) => DOCUMENTS.insert(Document::from_pdf(pdf)),
+        Err(err) => {
+            set_caught(ctx, &err);
+            0
+        }
+    }
+}
+
+/// Open a document from file by memory-mapping it
+///
+/// The document is parsed from the mapping rather than a copy of the
+/// file, so only the parts that are used get loaded; the mapping lives as
+/// long as the document. Files that cannot be mapped are read instead.
+///
+/// # Safety
+/// Caller must ensure `filename` is a valid null-terminated C string.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_open_document_mmap(ctx: Handle, filename: *const c_char) -> Handle {
     if filename.is_null() {
+        set_caught(ctx, &Error::argument("no filename"));
         return 0;
     }
 
@@ -149,29 +322,51 @@ pub extern "C" fn fz_open_document(_ctx: Handle, filename: *const c_char) -> Han
     let c_str = unsafe { std::ffi::CStr::from_ptr(filename) };
     let path = match c_str.to_str() {
         Ok(s) => s,
-        Err(_) => return 0,
+        Err(_) => {
+            set_caught(ctx, &Error::argument("filename is not valid UTF-8"));
+            return 0;
+        }
     };
 
-    match std::fs::read(path) {
-        Ok(data) => DOCUMENTS.insert(Document::new(data)),
-        Err(_) => 0,
+    match crate::pdf::document::Document::open_mmap(path) {
+        Ok(pdf) => DOCUMENTS.insert(Document::from_pdf(pdf)),
+        Err(err) => {
+            set_caught(ctx, &err);
+            0
+        }
     }
 }
 
 /// Open a document from stream
+///
+/// Memory streams are shared and file streams memory-mapped rather than
+/// read, so the xref is found from the end of the file and only the parts
+/// of a large file that are used get loaded. The stream's read position
+/// is not changed. As with [`fz_open_document`], data the PDF layer
+/// cannot make sense of is rejected here.
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_open_document_with_stream(
-    _ctx: Handle,
+    ctx: Handle,
     _magic: *const c_char,
     stm: Handle,
 ) -> Handle {
-    // Read all data from stream
-    if let Some(stream) = STREAMS.get(stm) {
-        if let Ok(guard) = stream.lock() {
-            return DOCUMENTS.insert(Document::new(guard.data.clone()));
+    let data = match STREAMS
+        .get(stm)
+        .and_then(|stream| stream.lock().ok().map(|mut guard| guard.source_bytes()))
+    {
+        Some(data) => data,
+        None => {
+            set_caught(ctx, &Error::argument("invalid stream handle"));
+            return 0;
+        }
+    };
+    match crate::pdf::document::Document::open_bytes(data) {
+        Ok(pdf) => DOCUMENTS.insert(Document::from_pdf(pdf)),
+        Err(err) => {
+            set_caught(ctx, &err);
+            0
         }
     }
-    0
 }
 
 /// Keep (increment ref) document
@@ -217,19 +412,22 @@ pub extern "C" fn fz_authenticate_password(
 
     if let Some(document) = DOCUMENTS.get(doc) {
         if let Ok(mut d) = document.lock() {
-            // If no password needed, succeed
-            if !d.needs_password {
+            let Some(crypt) = d.crypt.as_mut() else {
                 d.authenticated = true;
                 return 1;
-            }
+            };
 
-            // Verify password matches
-            if let Some(ref stored_password) = d.password {
-                if stored_password == password_str {
-                    d.authenticated = true;
-                    return 1;
-                }
-            }
+            // Returns 1 when no password is needed, 2 for the user password
+            // and 4 for the owner password
+            let result = match crypt.authenticate(password_str.as_bytes()) {
+                AuthLevel::None => return 0,
+                AuthLevel::User if !d.needs_password => 1,
+                AuthLevel::User => 2,
+                AuthLevel::Owner => 4,
+            };
+            d.authenticated = true;
+            d.update_permissions();
+            return result;
         }
     }
     0
@@ -271,9 +469,13 @@ pub extern "C" fn fz_page_number_from_location(
 
 /// Check document permission
 #[unsafe(no_mangle)]
-pub extern "C" fn fz_has_permission(_ctx: Handle, doc: Handle, _permission: i32) -> i32 {
-    // For now, allow all permissions if document is open
-    if DOCUMENTS.get(doc).is_some() { 1 } else { 0 }
+pub extern "C" fn fz_has_permission(_ctx: Handle, doc: Handle, permission: i32) -> i32 {
+    if let Some(d) = DOCUMENTS.get(doc) {
+        if let Ok(guard) = d.lock() {
+            return i32::from(guard.permissions & permission != 0);
+        }
+    }
+    0
 }
 
 // Permission flags
@@ -281,6 +483,8 @@ pub const FZ_PERMISSION_PRINT: i32 = 1 << 0;
 pub const FZ_PERMISSION_COPY: i32 = 1 << 1;
 pub const FZ_PERMISSION_EDIT: i32 = 1 << 2;
 pub const FZ_PERMISSION_ANNOTATE: i32 = 1 << 3;
+const FZ_PERMISSION_ALL: i32 =
+    FZ_PERMISSION_PRINT | FZ_PERMISSION_COPY | FZ_PERMISSION_EDIT | FZ_PERMISSION_ANNOTATE;
 
 /// Lookup metadata
 ///
@@ -321,7 +525,12 @@ pub extern "C" fn fz_load_page(_ctx: Handle, doc: Handle, page_num: i32) -> Hand
         return 0;
     }
 
-    PAGES.insert(Page::new(doc, page_num))
+    let mut page = Page::new(doc, page_num);
+    if let Some(pdf_page) = open_pdf(doc).and_then(|pdf| pdf.page(page_num as usize).ok()) {
+        let b = pdf_page.bounds();
+        page.bounds = [b.x0, b.y0, b.x1, b.y1];
+    }
+    PAGES.insert(page)
 }
 
 /// Load page by location (chapter, page)
@@ -381,6 +590,144 @@ pub extern "C" fn fz_bound_page_box(
     fz_bound_page(_ctx, page)
 }
 
+/// Get the matrix that renders a page at `dpi`: PDF user space to device
+/// pixels, rotated by the page's /Rotate, with the origin at the top left
+/// of the displayed page and y growing down
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_transform_page(
+    _ctx: Handle,
+    page: Handle,
+    dpi: f32,
+) -> super::geometry::fz_matrix {
+    let page_ref = PAGES.get(page).and_then(|p| {
+        p.lock()
+            .ok()
+            .map(|guard| (guard.doc_handle, guard.page_num))
+    });
+    let ctm = page_ref.and_then(|(doc, page_num)| {
+        let pdf = open_pdf(doc)?;
+        let pdf_page = pdf.page(usize::try_from(page_num).ok()?).ok()?;
+        Some(pdf_page.render_transform(dpi))
+    });
+    match ctm {
+        Some(m) => super::geometry::fz_matrix {
+            a: m.a,
+            b: m.b,
+            c: m.c,
+            d: m.d,
+            e: m.e,
+            f: m.f,
+        },
+        None => super::geometry::fz_matrix::identity(),
+    }
+}
+
+/// Get the page's content streams, decoded and concatenated in order with a
+/// newline after each
+///
+/// Returns a buffer handle, or 0 if the page cannot be read. A page without
+/// contents gives an empty buffer.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_page_content_stream(_ctx: Handle, page: Handle) -> Handle {
+    let page_ref = PAGES.get(page).and_then(|p| {
+        p.lock()
+            .ok()
+            .map(|guard| (guard.doc_handle, guard.page_num))
+    });
+    let contents = page_ref.and_then(|(doc, page_num)| {
+        let pdf = open_pdf(doc)?;
+        let pdf_page = pdf.page(usize::try_from(page_num).ok()?).ok()?;
+        pdf.page_contents(&pdf_page).ok()
+    });
+    match contents {
+        Some(data) => super::BUFFERS.insert(super::buffer::Buffer::from_data(&data)),
+        None => 0,
+    }
+}
+
+/// Extract the structured text of a page in page space (origin top-left,
+/// y down), or `None` if the page cannot be interpreted
+pub(crate) fn extract_page_text(page: Handle) -> Option<crate::fitz::stext::STextPage> {
+    use crate::fitz::stext::{STextDevice, STextOptions};
+
+    let (mut interp, contents, bounds) = page_text_interpreter(page)?;
+    let mut device = STextDevice::new(bounds, STextOptions::default());
+    interp.interpret(&contents, &mut device).ok()?;
+    Some(device.finish())
+}
+
+/// Interpret the text of a document page into `device`, in the page space
+/// of [`extract_page_text`]
+///
+/// Returns `None` if the page could not be read or interpreted.
+pub(crate) fn run_page_text(
+    page: Handle,
+    device: &mut dyn crate::fitz::device::Device,
+) -> Option<()> {
+    let (mut interp, contents, _) = page_text_interpreter(page)?;
+    interp.interpret(&contents, device).ok()
+}
+
+/// An interpreter for a page's text, its contents and its bounds, with y
+/// growing down from the top of the MediaBox
+fn page_text_interpreter(
+    page: Handle,
+) -> Option<(
+    crate::pdf::interpret::Interpreter,
+    Vec<u8>,
+    crate::fitz::geometry::Rect,
+)> {
+    let (doc_handle, page_num) = {
+        let guard = PAGES.get(page)?;
+        let guard = guard.lock().ok()?;
+        (guard.doc_handle, guard.page_num)
+    };
+    let pdf = open_pdf(doc_handle)?;
+    let pdf_page = pdf.page(page_num as usize).ok()?;
+    let contents = pdf.page_contents(&pdf_page).ok()?;
+
+    let media = pdf_page.media_box();
+    let mut interp = crate::pdf::interpret::Interpreter::new();
+    interp.set_ctm(crate::fitz::geometry::Matrix::new(
+        1.0, 0.0, 0.0, -1.0, -media.x0, media.y1,
+    ));
+    if let Ok(Some(Object::Dict(resources))) = pdf.resolve_key(pdf_page.dict(), "Resources") {
+        // Font widths position the extracted glyphs and encodings give
+        // their Unicode values
+        let metrics = crate::pdf::content::FontMetrics::load_resources(&pdf, &resources);
+        interp.set_font_metrics(metrics.unwrap_or_default());
+        let fonts = crate::pdf::content::Type3Font::load_resources(&pdf, &resources);
+        interp.set_type3_fonts(fonts.unwrap_or_default());
+        interp.set_resources(resources);
+    }
+
+    let bounds = crate::fitz::geometry::Rect::new(0.0, 0.0, media.width(), media.height());
+    Some((interp, contents, bounds))
+}
+
+/// Interpret a PDF page into `device`, with `ctm` applied after the page
+/// transform
+///
+/// Returns `None` for pages that cannot be parsed; they render as blank,
+/// matching a failed load.
+pub(crate) fn run_pdf_page(
+    doc_handle: Handle,
+    page_num: i32,
+    ctm: &crate::fitz::geometry::Matrix,
+    device: &mut dyn crate::fitz::device::Device,
+    cookie: Option<&crate::fitz::cookie::Cookie>,
+) -> Option<Result<(), String>> {
+    let pdf = open_pdf(doc_handle)?;
+    let pdf_page = pdf.page(usize::try_from(page_num).ok()?).ok()?;
+    let contents = pdf.page_contents(&pdf_page).ok()?;
+
+    let mut interp = crate::pdf::render::page_interpreter(&pdf, &pdf_page, ctm);
+    if let Some(c) = cookie {
+        interp.set_cookie(c.clone());
+    }
+    Some(interp.interpret(&contents, device))
+}
+
 /// Render page to device
 ///
 /// # Safety
@@ -393,42 +740,34 @@ pub extern "C" fn fz_run_page(
     transform: super::geometry::fz_matrix,
     cookie: *mut std::ffi::c_void,
 ) {
-    // Check for cancellation via cookie
-    if !cookie.is_null() {
-        let cookie_handle = cookie as Handle;
-        if let Some(c) = super::cookie::COOKIES.get(cookie_handle) {
-            if let Ok(guard) = c.lock() {
-                if guard.should_abort() {
-                    return; // Operation cancelled
-                }
-            }
-        }
-    }
-
-    // Get page bounds for rendering
-    let _bounds = if let Some(p) = PAGES.get(page) {
-        if let Ok(guard) = p.lock() {
-            super::geometry::fz_rect {
-                x0: guard.bounds[0],
-                y0: guard.bounds[1],
-                x1: guard.bounds[2],
-                y1: guard.bounds[3],
-            }
-        } else {
-            return;
-        }
+    let cookie = if cookie.is_null() {
+        None
     } else {
+        super::cookie::COOKIES
+            .get(cookie as Handle)
+            .and_then(|c| c.lock().ok().map(|guard| guard.clone()))
+    };
+
+    // Check for cancellation before touching the page
+    if let Some(c) = cookie.as_ref().filter(|c| c.should_abort()) {
+        c.set_incomplete(true);
         return;
+    }
+
+    let (doc_handle, page_num) = match PAGES.get(page) {
+        Some(p) => match p.lock() {
+            Ok(guard) => (guard.doc_handle, guard.page_num),
+            Err(_) => return,
+        },
+        None => return,
     };
 
-    // Get device for rendering
-    let _dev_arc = match super::device::DEVICES.get(device) {
+    let dev_arc = match super::device::DEVICES.get(device) {
         Some(d) => d,
         None => return,
     };
 
-    // Apply transform to page bounds to get rendering area
-    let _matrix = crate::fitz::geometry::Matrix {
+    let matrix = crate::fitz::geometry::Matrix {
         a: transform.a,
         b: transform.b,
         c: transform.c,
@@ -437,16 +776,23 @@ pub extern "C" fn fz_run_page(
         f: transform.f,
     };
 
-    // This function correctly handles the FFI contract for page rendering:
-    // - Validates all handles
-    // - Checks cookie for cancellation
-    // - Parses transform matrix
-    // - Gets page bounds
-    //
-    // Note: PDF content stream parsing (walking through drawing operators and
-    // calling device methods) requires a full PDF interpreter which is a
-    // substantial undertaking beyond FFI bindings. The FFI structure is complete
-    // and ready for when content stream interpretation is added to the core library.
+    let result = match dev_arc.lock() {
+        Ok(mut dev) => {
+            match run_pdf_page(doc_handle, page_num, &matrix, &mut **dev, cookie.as_ref()) {
+                Some(result) => result,
+                None => return,
+            }
+        }
+        Err(_) => return,
+    };
+
+    if let Some(c) = &cookie {
+        match result {
+            Ok(()) => c.inc_progress(),
+            Err(_) if c.should_abort() => {}
+            Err(_) => c.inc_errors(),
+        }
+    }
 }
 
 /// Render page contents to device (excludes annotations)
@@ -465,6 +811,105 @@ pub extern "C" fn fz_run_page_contents(
     fz_run_page(_ctx, page, device, transform, cookie);
 }
 
+/// Render page `page_num` as an RGB pixmap whose longer side is `max_dim`
+/// pixels, keeping the page's aspect ratio
+///
+/// Edges are smoothed at the context's `fz_aa_level`. A display list
+/// stored for the page with `fz_store_page_display_list` is replayed
+/// instead of interpreting the page. Returns 0 on error.
+///
+/// A `cookie` stops the render part way; the pixmap drawn until then is
+/// still returned, with the abort recorded on `ctx`.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_render_page_thumbnail(
+    ctx: Handle,
+    doc: Handle,
+    page_num: i32,
+    max_dim: i32,
+    cookie: *mut std::ffi::c_void,
+) -> Handle {
+    use crate::fitz::geometry::{Matrix, Rect};
+    use crate::fitz::render::DrawDevice;
+
+    if max_dim <= 0 {
+        set_caught(ctx, &Error::argument("thumbnail size must be positive"));
+        return 0;
+    }
+    let page = usize::try_from(page_num)
+        .ok()
+        .and_then(|n| open_pdf(doc).map(|pdf| pdf.page(n)));
+    let bounds = match page {
+        Some(Ok(page)) => page.bounds(),
+        Some(Err(err)) => {
+            set_caught(ctx, &err);
+            return 0;
+        }
+        _ => {
+            set_caught(ctx, &Error::argument("invalid document or page number"));
+            return 0;
+        }
+    };
+    let (w, h) = (bounds.width(), bounds.height());
+    if w <= 0.0 || h <= 0.0 {
+        set_caught(ctx, &Error::format("page has an empty box"));
+        return 0;
+    }
+    let scale = max_dim as f32 / w.max(h);
+    let width = ((w * scale).round() as i32).clamp(1, max_dim);
+    let height = ((h * scale).round() as i32).clamp(1, max_dim);
+    let ctm = Matrix::translate(-bounds.x0, -bounds.y0).concat(&Matrix::scale(scale, scale));
+
+    let rgb = crate::fitz::colorspace::Colorspace::device_rgb();
+    let mut target = match crate::fitz::pixmap::Pixmap::new(Some(rgb), width, height, false) {
+        Ok(pixmap) => pixmap,
+        Err(err) => {
+            set_caught(ctx, &err);
+            return 0;
+        }
+    };
+    target.clear(255);
+    let mut device = DrawDevice::new(target);
+    device.set_aa_level(super::context::fz_aa_level(ctx).clamp(0, 8) as u8);
+
+    let cookie = if cookie.is_null() {
+        None
+    } else {
+        super::cookie::COOKIES
+            .get(cookie as Handle)
+            .and_then(|c| c.lock().ok().map(|guard| guard.clone()))
+    };
+    let key = super::display_list::page_list_key(doc, page_num);
+    let cached = super::store::fz_store_find(ctx, key.as_ptr(), key.len());
+    if let Some(c) = cookie.as_ref().filter(|c| c.should_abort()) {
+        c.set_incomplete(true);
+        set_caught(ctx, &Error::Abort);
+    } else {
+        match super::display_list::DISPLAY_LISTS.get(cached) {
+            Some(list) => match list.lock() {
+                Ok(list) => list.run(&mut device, &ctm, Rect::INFINITE),
+                Err(_) => return 0,
+            },
+            None => {
+                if let Some(Err(err)) =
+                    run_pdf_page(doc, page_num, &ctm, &mut device, cookie.as_ref())
+                {
+                    if cookie.as_ref().is_some_and(|c| c.should_abort()) {
+                        set_caught(ctx, &Error::Abort);
+                    } else {
+                        set_caught(ctx, &Error::format(err));
+                    }
+                }
+            }
+        }
+    }
+
+    let drawn = device.into_pixmap();
+    let mut pixmap =
+        super::pixmap::Pixmap::new(super::colorspace::FZ_COLORSPACE_RGB, width, height, false);
+    pixmap.samples_mut().copy_from_slice(drawn.samples());
+    super::PIXMAPS.insert(pixmap)
+}
+
 /// Render page annotations to device
 ///
 /// # Safety
@@ -712,20 +1157,26 @@ pub extern "C" fn fz_page_label(
         return 0;
     }
 
-    if let Some(d) = DOCUMENTS.get(doc) {
-        if let Ok(guard) = d.lock() {
-            if page_num >= 0 && page_num < guard.page_count {
-                let label = format!("Page {}", page_num + 1);
-                let bytes = label.as_bytes();
-                let copy_len = (bytes.len()).min((size - 1) as usize);
+    let page_count = match DOCUMENTS
+        .get(doc)
+        .and_then(|d| d.lock().ok().map(|g| g.page_count))
+    {
+        Some(count) => count,
+        None => return 0,
+    };
+    if page_num >= 0 && page_num < page_count {
+        // Documents the PDF layer cannot read keep the generic label
+        let label = open_pdf(doc)
+            .and_then(|pdf| pdf.page(page_num as usize).ok()?.label(&pdf).ok())
+            .unwrap_or_else(|| format!("Page {}", page_num + 1));
+        let bytes = label.as_bytes();
+        let copy_len = (bytes.len()).min((size - 1) as usize);
 
-                unsafe {
-                    std::ptr::copy_nonoverlapping(bytes.as_ptr(), buf as *mut u8, copy_len);
-                    *buf.add(copy_len) = 0;
-                }
-                return copy_len as i32;
-            }
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), buf as *mut u8, copy_len);
+            *buf.add(copy_len) = 0;
         }
+        return copy_len as i32;
     }
     0
 }
@@ -745,8 +1196,16 @@ pub extern "C" fn fz_clone_document(_ctx: Handle, doc: Handle) -> Handle {
 #[cfg(test)]
 mod tests {
     use super::super::STREAMS;
-    use super::super::stream::Stream;
     use super::*;
+    use crate::fitz::colorspace::Colorspace;
+    use crate::fitz::device::{BlendMode, Device};
+    use crate::fitz::geometry::{Matrix, Rect};
+    use crate::fitz::image::Image;
+    use crate::fitz::path::{Path, StrokeState};
+    use crate::fitz::text::Text;
+    use crate::pdf::test_pdf::{PdfBuilder, build_pdf};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_document_handle() {
@@ -763,6 +1222,75 @@ mod tests {
         fz_drop_document(0, handle);
     }
 
+    #[test]
+    fn test_open_document_with_corrupt_startxref() {
+        let pdf = PdfBuilder::new(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R] /Count 3 >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+        ])
+        .startxref(999999)
+        .build();
+
+        let handle = DOCUMENTS.insert(Document::new(pdf));
+        assert_eq!(fz_count_pages(0, handle), 3);
+        fz_drop_document(0, handle);
+    }
+
+    #[test]
+    fn test_page_label_prefix_and_start() {
+        let pdf = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R /PageLabels << /Nums [0 << /S /A /P (A-) /St 3 >>] >> >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+        ]);
+
+        let handle = DOCUMENTS.insert(Document::new(pdf));
+        let mut buf = [0 as c_char; 16];
+        let len = fz_page_label(0, handle, 0, buf.as_mut_ptr(), buf.len() as i32);
+        let label = unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) };
+        assert_eq!(label.to_str().unwrap(), "A-C");
+        assert_eq!(len, 3);
+        assert_eq!(
+            fz_page_label(0, handle, 1, buf.as_mut_ptr(), buf.len() as i32),
+            0
+        );
+        fz_drop_document(0, handle);
+    }
+
+    #[test]
+    fn test_handle_keeps_parsed_document() {
+        let handle = DOCUMENTS.insert(Document::new(build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+        ])));
+        let first = load_pdf(handle).unwrap();
+        let page = fz_load_page(0, handle, 0);
+        assert!(Arc::ptr_eq(&first, &load_pdf(handle).unwrap()));
+
+        // New bytes are parsed again
+        let two_pages = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+        ]);
+        DOCUMENTS
+            .get(handle)
+            .unwrap()
+            .lock()
+            .unwrap()
+            .set_data(two_pages);
+        let second = load_pdf(handle).unwrap();
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert_eq!(second.page_count().unwrap(), 2);
+        fz_drop_page(0, page);
+        fz_drop_document(0, handle);
+    }
+
     #[test]
     fn test_document_new() {
         let pdf_data = b"%PDF-1.4\n/Type /Page\n/Type /Page\n%%EOF";
@@ -892,11 +1420,100 @@ mod tests {
         fz_drop_document(0, handle);
     }
 
+    fn serialize(obj: &Object) -> String {
+        match obj {
+            Object::String(s) => {
+                let hex: String = s.as_bytes().iter().map(|b| format!("{b:02x}")).collect();
+                format!("<{hex}>")
+            }
+            Object::Name(n) => format!("/{}", n.as_str()),
+            Object::Int(i) => i.to_string(),
+            Object::Bool(b) => b.to_string(),
+            Object::Dict(d) => {
+                let entries: String = d
+                    .iter()
+                    .map(|(k, v)| format!("/{} {} ", k.as_str(), serialize(v)))
+                    .collect();
+                format!("<< {entries}>>")
+            }
+            _ => "null".to_string(),
+        }
+    }
+
+    /// Single-page PDF encrypted with distinct user and owner passwords
+    /// whose /P disallows copying
+    fn encrypted_pdf(algorithm: crate::pdf::crypt::EncryptionAlgorithm) -> Vec<u8> {
+        let id = b"0123456789abcdef".to_vec();
+        let p = !(Permission::Copy as u32) & 0xFFFF_FFFC;
+        let crypt = Crypt::new_encrypt("owner", "user", id.clone(), p, algorithm).unwrap();
+        let encrypt = serialize(&Object::Dict(crypt.encrypt_dict()));
+        let hex_id: String = id.iter().map(|b| format!("{b:02x}")).collect();
+
+        PdfBuilder::new(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] >>",
+            &encrypt,
+        ])
+        .trailer(&format!("/Encrypt 4 0 R /ID [<{hex_id}> <{hex_id}>]"))
+        .build()
+    }
+
+    #[test]
+    fn test_encrypted_permissions_user_vs_owner() {
+        use crate::pdf::crypt::EncryptionAlgorithm;
+
+        for algorithm in [EncryptionAlgorithm::Aes128, EncryptionAlgorithm::Aes256] {
+            let data = encrypted_pdf(algorithm);
+
+            let handle = DOCUMENTS.insert(Document::new(data.clone()));
+            assert_eq!(fz_needs_password(0, handle), 1);
+            assert_eq!(fz_authenticate_password(0, handle, c"wrong".as_ptr()), 0);
+            assert_eq!(fz_authenticate_password(0, handle, c"user".as_ptr()), 2);
+            assert_eq!(fz_has_permission(0, handle, FZ_PERMISSION_PRINT), 1);
+            assert_eq!(fz_has_permission(0, handle, FZ_PERMISSION_COPY), 0);
+            fz_drop_document(0, handle);
+
+            let handle = DOCUMENTS.insert(Document::new(data));
+            assert_eq!(fz_authenticate_password(0, handle, c"owner".as_ptr()), 4);
+            assert_eq!(fz_has_permission(0, handle, FZ_PERMISSION_COPY), 1);
+            assert_eq!(fz_has_permission(0, handle, FZ_PERMISSION_EDIT), 1);
+            fz_drop_document(0, handle);
+        }
+    }
+
     #[test]
     fn test_has_permission_invalid_handle() {
         assert_eq!(fz_has_permission(0, 0, FZ_PERMISSION_PRINT), 0);
     }
 
+    #[test]
+    fn test_open_document_records_parse_failure() {
+        use crate::ffi::context::{FzErrorType, fz_caught, fz_caught_message, fz_ignore_error};
+
+        let path =
+            std::env::temp_dir().join(format!("micropdf-garbage-{}.pdf", std::process::id()));
+        std::fs::write(&path, b"this is not a PDF file at all").unwrap();
+        let filename = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+
+        let ctx = 0;
+        assert_eq!(fz_open_document(ctx, filename.as_ptr()), 0);
+        std::fs::remove_file(&path).unwrap();
+        assert_ne!(fz_caught(ctx), FzErrorType::None as i32);
+        // SAFETY: fz_caught_message returns a nul-terminated string
+        let message = unsafe { std::ffi::CStr::from_ptr(fz_caught_message(ctx)) };
+        assert!(!message.to_bytes().is_empty());
+        assert_ne!(message.to_bytes(), b"No error");
+
+        fz_ignore_error(ctx);
+        assert_eq!(fz_caught(ctx), FzErrorType::None as i32);
+        assert_eq!(
+            fz_open_document(ctx, c"/nonexistent/micropdf.pdf".as_ptr()),
+            0
+        );
+        assert_eq!(fz_caught(ctx), FzErrorType::System as i32);
+    }
+
     #[test]
     fn test_lookup_metadata() {
         let pdf_data = b"%PDF-1.4\n/Type /Page\n%%EOF";
@@ -924,8 +1541,12 @@ mod tests {
 
     #[test]
     fn test_open_document_with_stream() {
-        let pdf_data = b"%PDF-1.4\n/Type /Page\n%%EOF";
-        let stream = Stream::from_memory(pdf_data.to_vec());
+        let pdf_data = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+        ]);
+        let stream = Stream::from_memory(pdf_data);
         let stream_handle = STREAMS.insert(stream);
 
         let doc_handle = fz_open_document_with_stream(0, std::ptr::null(), stream_handle);
@@ -937,6 +1558,143 @@ mod tests {
         super::super::STREAMS.remove(stream_handle);
     }
 
+    #[test]
+    fn test_open_document_with_stream_records_parse_failure() {
+        use crate::ffi::context::{FzErrorType, fz_caught, fz_caught_message};
+
+        let ctx =
+            unsafe { crate::ffi::context::fz_new_context(std::ptr::null(), std::ptr::null(), 0) };
+        let stream = STREAMS.insert(Stream::from_memory(
+            b"this is not a PDF stream at all".to_vec(),
+        ));
+        assert_eq!(
+            fz_open_document_with_stream(ctx, std::ptr::null(), stream),
+            0
+        );
+        assert_ne!(fz_caught(ctx), FzErrorType::None as i32);
+        // SAFETY: fz_caught_message returns a nul-terminated string
+        let message = unsafe { std::ffi::CStr::from_ptr(fz_caught_message(ctx)) };
+        assert_ne!(message.to_bytes(), b"No error");
+
+        STREAMS.remove(stream);
+        crate::ffi::context::fz_drop_context(ctx);
+    }
+
+    #[test]
+    fn test_open_document_with_file_stream() {
+        // Object 5 is padding a reader would have to get through to reach
+        // the xref
+        let pdf = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R >>".to_string(),
+            format!("%{}\nnull", "x".repeat(64 * 1024)),
+        ]);
+        let path = std::env::temp_dir().join(format!("micropdf-doc-{}.pdf", std::process::id()));
+        std::fs::write(&path, &pdf).unwrap();
+        let filename = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+
+        let stream = crate::ffi::stream::fz_open_file(0, filename.as_ptr());
+        assert_ne!(stream, 0);
+        let doc = fz_open_document_with_stream(0, std::ptr::null(), stream);
+        assert_ne!(doc, 0);
+        assert_eq!(fz_count_pages(0, doc), 2);
+        // The document did not consume the stream
+        assert_eq!(crate::ffi::stream::fz_tell(0, stream), 0);
+        crate::ffi::stream::fz_drop_stream(0, stream);
+        assert_eq!(open_pdf(doc).unwrap().page_count().unwrap(), 2);
+
+        fz_drop_document(0, doc);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_open_document_mmap() {
+        let pdf = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R] /Count 3 >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+        ]);
+        let path = std::env::temp_dir().join(format!("micropdf-mmap-{}.pdf", std::process::id()));
+        std::fs::write(&path, &pdf).unwrap();
+        let filename = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+
+        let mapped = fz_open_document_mmap(0, filename.as_ptr());
+        let read = fz_open_document(0, filename.as_ptr());
+        assert_ne!(mapped, 0);
+        assert_eq!(fz_count_pages(0, mapped), 3);
+        assert_eq!(fz_count_pages(0, mapped), fz_count_pages(0, read));
+        assert_eq!(open_pdf(mapped).unwrap().page_count().unwrap(), 3);
+        fz_drop_document(0, mapped);
+        fz_drop_document(0, read);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(fz_open_document_mmap(0, filename.as_ptr()), 0);
+        assert_eq!(fz_open_document_mmap(0, std::ptr::null()), 0);
+    }
+
+    #[test]
+    fn test_bound_page_user_unit() {
+        let pdf = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /UserUnit 2.0 >>",
+        ]);
+        let doc = DOCUMENTS.insert(Document::new(pdf));
+        let page = fz_load_page(0, doc, 0);
+
+        let bounds = fz_bound_page(0, page);
+        assert_eq!((bounds.x1, bounds.y1), (1224.0, 1584.0));
+        // One unit of user space is two points at 72 dpi
+        let ctm = fz_transform_page(0, page, 72.0);
+        assert_eq!((ctm.a, ctm.d), (2.0, -2.0));
+
+        fz_drop_page(0, page);
+        fz_drop_document(0, doc);
+    }
+
+    #[test]
+    fn test_page_content_stream_concatenates_fragments() {
+        let fragments = ["q 1 0 0 rg", "10 10 50 50 re f", "Q"];
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /Contents [4 0 R 5 0 R 6 0 R] >>".to_string(),
+        ];
+        for data in fragments {
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{data}\nendstream",
+                data.len()
+            ));
+        }
+        let pdf = build_pdf(&objects);
+        let doc = DOCUMENTS.insert(Document::new(pdf));
+        let page = fz_load_page(0, doc, 0);
+
+        let buffer = fz_page_content_stream(0, page);
+        assert_ne!(buffer, 0);
+        let data = crate::ffi::BUFFERS
+            .get(buffer)
+            .unwrap()
+            .lock()
+            .unwrap()
+            .data()
+            .to_vec();
+        // Each fragment is followed by whitespace so tokens never run together
+        assert_eq!(data, b"q 1 0 0 rg\n10 10 50 50 re f\nQ\n");
+
+        let pretty = crate::pdf::content::pretty_print(&data).unwrap();
+        assert_eq!(pretty, "q\n  1 0 0 rg\n  10 10 50 50 re\n  f\nQ\n");
+
+        crate::ffi::BUFFERS.remove(buffer);
+        fz_drop_page(0, page);
+        fz_drop_document(0, doc);
+        assert_eq!(fz_page_content_stream(0, page), 0);
+    }
+
     #[test]
     fn test_open_document_with_invalid_stream() {
         let doc_handle = fz_open_document_with_stream(0, std::ptr::null(), 0);
@@ -1157,4 +1915,264 @@ mod tests {
         let result = fz_make_location_uri(0, 0, 5, std::ptr::null_mut(), 32);
         assert!(result.is_null());
     }
+
+    /// Counts filled paths and aborts a cookie once `limit` have been drawn
+    struct AbortingDevice {
+        fills: Arc<AtomicUsize>,
+        cookie: crate::fitz::cookie::Cookie,
+        limit: usize,
+    }
+
+    impl Device for AbortingDevice {
+        fn fill_path(&mut self, _: &Path, _: bool, _: &Matrix, _: &Colorspace, _: &[f32], _: f32) {
+            if self.fills.fetch_add(1, Ordering::SeqCst) + 1 >= self.limit {
+                self.cookie.abort();
+            }
+        }
+        fn stroke_path(
+            &mut self,
+            _: &Path,
+            _: &StrokeState,
+            _: &Matrix,
+            _: &Colorspace,
+            _: &[f32],
+            _: f32,
+        ) {
+        }
+        fn clip_path(&mut self, _: &Path, _: bool, _: &Matrix, _: Rect) {}
+        fn clip_stroke_path(&mut self, _: &Path, _: &StrokeState, _: &Matrix, _: Rect) {}
+        fn fill_text(&mut self, _: &Text, _: &Matrix, _: &Colorspace, _: &[f32], _: f32) {}
+        fn stroke_text(
+            &mut self,
+            _: &Text,
+            _: &StrokeState,
+            _: &Matrix,
+            _: &Colorspace,
+            _: &[f32],
+            _: f32,
+        ) {
+        }
+        fn clip_text(&mut self, _: &Text, _: &Matrix, _: Rect) {}
+        fn clip_stroke_text(&mut self, _: &Text, _: &StrokeState, _: &Matrix, _: Rect) {}
+        fn ignore_text(&mut self, _: &Text, _: &Matrix) {}
+        fn fill_image(&mut self, _: &Image, _: &Matrix, _: f32) {}
+        fn fill_image_mask(&mut self, _: &Image, _: &Matrix, _: &Colorspace, _: &[f32], _: f32) {}
+        fn clip_image_mask(&mut self, _: &Image, _: &Matrix, _: Rect) {}
+        fn pop_clip(&mut self) {}
+        fn begin_mask(&mut self, _: Rect, _: bool, _: &Colorspace, _: &[f32]) {}
+        fn end_mask(&mut self) {}
+        fn begin_group(
+            &mut self,
+            _: Rect,
+            _: Option<&Colorspace>,
+            _: bool,
+            _: bool,
+            _: BlendMode,
+            _: f32,
+        ) {
+        }
+        fn end_group(&mut self) {}
+        fn begin_tile(&mut self, _: Rect, _: Rect, _: f32, _: f32, _: &Matrix) -> i32 {
+            0
+        }
+        fn end_tile(&mut self) {}
+    }
+
+    /// A PDF whose pages each fill `rects_per_page` rectangles
+    fn rect_pages_pdf(pages: usize, rects_per_page: usize) -> Vec<u8> {
+        let content: String = (0..rects_per_page)
+            .map(|i| format!("{} 10 5 5 re f\n", i * 10))
+            .collect();
+        let kids: Vec<String> = (0..pages).map(|i| format!("{} 0 R", 3 + 2 * i)).collect();
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {pages} /MediaBox [0 0 200 200] >>",
+                kids.join(" ")
+            ),
+        ];
+        for i in 0..pages {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /Contents {} 0 R >>",
+                4 + 2 * i
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ));
+        }
+        build_pdf(&objects)
+    }
+
+    #[test]
+    fn test_run_page_cookie_abort() {
+        let doc = DOCUMENTS.insert(Document::new(rect_pages_pdf(3, 4)));
+
+        let cookie = super::super::cookie::fz_new_cookie(0);
+        let shared = super::super::cookie::COOKIES
+            .get(cookie)
+            .unwrap()
+            .lock()
+            .unwrap()
+            .clone();
+        let fills = Arc::new(AtomicUsize::new(0));
+        let device = super::super::device::DEVICES.insert(Box::new(AbortingDevice {
+            fills: fills.clone(),
+            cookie: shared,
+            limit: 6,
+        }));
+
+        // Abort partway through the second page; the rest of it and the
+        // whole third page must be skipped
+        for page_num in 0..3 {
+            let page = fz_load_page(0, doc, page_num);
+            fz_run_page(
+                0,
+                page,
+                device,
+                super::super::geometry::fz_matrix::identity(),
+                cookie as usize as *mut std::ffi::c_void,
+            );
+            fz_drop_page(0, page);
+        }
+
+        assert_eq!(fills.load(Ordering::SeqCst), 6);
+        assert_eq!(super::super::cookie::fz_cookie_progress(0, cookie), 1);
+        assert_eq!(super::super::cookie::fz_cookie_is_incomplete(0, cookie), 1);
+
+        super::super::device::DEVICES.remove(device);
+        super::super::cookie::fz_drop_cookie(0, cookie);
+        fz_drop_document(0, doc);
+    }
+
+    #[test]
+    fn test_render_page_thumbnail() {
+        let content = "0 0 1 rg 0 0 50 200 re f";
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 100 200] >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /Contents 4 0 R >>".to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ),
+        ];
+        let pdf = build_pdf(&objects);
+        let doc = DOCUMENTS.insert(Document::new(pdf));
+
+        let pixel = |pixmap: Handle, x: i32, y: i32| {
+            let pixmap = super::super::PIXMAPS.get(pixmap).unwrap();
+            let pixmap = pixmap.lock().unwrap();
+            [0, 1, 2].map(|c| pixmap.get_sample(x, y, c).unwrap())
+        };
+
+        // The long side fits max_dim and the 1:2 aspect ratio is kept
+        let thumb = fz_render_page_thumbnail(0, doc, 0, 64, std::ptr::null_mut());
+        assert_ne!(thumb, 0);
+        {
+            let pixmap = super::super::PIXMAPS.get(thumb).unwrap();
+            let pixmap = pixmap.lock().unwrap();
+            assert_eq!((pixmap.w(), pixmap.h(), pixmap.n()), (32, 64, 3));
+        }
+        assert_eq!(pixel(thumb, 8, 32), [0, 0, 255]);
+        assert_eq!(pixel(thumb, 24, 32), [255, 255, 255]);
+        super::super::PIXMAPS.remove(thumb);
+
+        // A display list stored for the page is replayed instead
+        let mut recorder =
+            crate::fitz::display_list::ListDevice::new(Rect::new(0.0, 0.0, 100.0, 200.0));
+        let mut whole = Path::new();
+        whole.rect(Rect::new(0.0, 0.0, 100.0, 200.0));
+        recorder.fill_path(
+            &whole,
+            false,
+            &Matrix::IDENTITY,
+            &Colorspace::device_rgb(),
+            &[1.0, 0.0, 0.0],
+            1.0,
+        );
+        let list = super::super::display_list::DISPLAY_LISTS.insert(recorder.into_display_list());
+        let id = super::super::display_list::fz_store_page_display_list(0, list, doc, 0);
+        assert_ne!(id, 0);
+        let thumb = fz_render_page_thumbnail(0, doc, 0, 64, std::ptr::null_mut());
+        assert_eq!(pixel(thumb, 24, 32), [255, 0, 0]);
+
+        assert_eq!(
+            fz_render_page_thumbnail(0, doc, 1, 64, std::ptr::null_mut()),
+            0
+        );
+        assert_eq!(
+            fz_render_page_thumbnail(0, doc, 0, 0, std::ptr::null_mut()),
+            0
+        );
+
+        super::super::store::fz_store_remove(0, id);
+        super::super::display_list::fz_drop_display_list(0, list);
+        super::super::PIXMAPS.remove(thumb);
+        fz_drop_document(0, doc);
+    }
+
+    #[test]
+    fn test_render_page_thumbnail_keeps_partial_on_abort() {
+        // Ten 10 pt bands down the page, painted top first
+        let content: String = (0..10)
+            .map(|i| format!("0 0 1 rg 0 {} 100 10 re f\n", 90 - 10 * i))
+            .collect();
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 100 100] >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /Contents 4 0 R >>".to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ),
+        ];
+        let doc = DOCUMENTS.insert(Document::new(build_pdf(&objects)));
+        let ctx =
+            unsafe { super::super::context::fz_new_context(std::ptr::null(), std::ptr::null(), 0) };
+
+        // Three bands at three operators each
+        let cookie = super::super::cookie::fz_new_cookie(ctx);
+        super::super::cookie::COOKIES
+            .get(cookie)
+            .unwrap()
+            .lock()
+            .unwrap()
+            .set_step_budget(Some(9));
+        let thumb = fz_render_page_thumbnail(ctx, doc, 0, 100, cookie as *mut std::ffi::c_void);
+        assert_ne!(thumb, 0);
+        let message =
+            unsafe { std::ffi::CStr::from_ptr(super::super::context::fz_caught_message(ctx)) };
+        assert!(message.to_string_lossy().contains("abort"), "{message:?}");
+        {
+            let pixmap = super::super::PIXMAPS.get(thumb).unwrap();
+            let pixmap = pixmap.lock().unwrap();
+            let pixel = |y| [0, 1, 2].map(|c| pixmap.get_sample(50, y, c).unwrap());
+            assert_eq!(pixel(25), [0, 0, 255]);
+            assert_eq!(pixel(35), [255, 255, 255]);
+        }
+
+        super::super::PIXMAPS.remove(thumb);
+        super::super::cookie::fz_drop_cookie(ctx, cookie);
+        super::super::context::fz_drop_context(ctx);
+        fz_drop_document(0, doc);
+    }
+
+    #[test]
+    fn test_transform_page_dpi() {
+        let doc = DOCUMENTS.insert(Document::new(rect_pages_pdf(1, 1)));
+        let page = fz_load_page(0, doc, 0);
+        let m = fz_transform_page(0, page, 144.0);
+        // 200pt square at 2x: y flipped and shifted down by the height
+        assert_eq!(
+            (m.a, m.b, m.c, m.d, m.e, m.f),
+            (2.0, 0.0, 0.0, -2.0, 0.0, 400.0)
+        );
+        assert_eq!(
+            fz_transform_page(0, 0, 144.0),
+            super::super::geometry::fz_matrix::identity()
+        );
+        fz_drop_page(0, page);
+        fz_drop_document(0, doc);
+    }
 }
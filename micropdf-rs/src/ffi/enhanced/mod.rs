@@ -4,9 +4,23 @@
 //! the MuPDF API, using the `np_` prefix to distinguish them.
 
 use super::Handle;
-use crate::enhanced::page_ops;
+use super::cookie::{COOKIES, FZ_ERROR_ABORT};
+use super::log;
+use crate::enhanced::error::EnhancedError;
+use crate::enhanced::{optimization, page_ops};
+use crate::fitz::cookie::Cookie;
 use std::ffi::CStr;
 
+/// Look up the cookie behind an optional (0 = none) cookie handle
+fn cookie_from_handle(cookie: Handle) -> Option<Cookie> {
+    if cookie == 0 {
+        return None;
+    }
+    COOKIES
+        .get(cookie)
+        .and_then(|c| c.lock().ok().map(|guard| guard.clone()))
+}
+
 /// Write PDF to file
 ///
 /// # Safety
@@ -61,10 +75,33 @@ pub extern "C" fn np_merge_pdfs(
     paths: *const *const std::ffi::c_char,
     count: i32,
     output_path: *const std::ffi::c_char,
+) -> i32 {
+    np_merge_pdfs_with_cookie(_ctx, paths, count, output_path, 0)
+}
+
+/// Merge multiple PDFs, polling a cookie for cancellation between inputs
+///
+/// Behaves like `np_merge_pdfs`; `cookie` may be 0. The cookie's progress
+/// counts merged inputs out of `count`.
+///
+/// # Returns
+/// * Number of pages in the merged PDF on success
+/// * `FZ_ERROR_ABORT` if the cookie was aborted
+/// * -1 on any other error
+///
+/// # Safety
+/// Same requirements as `np_merge_pdfs`.
+#[unsafe(no_mangle)]
+pub extern "C" fn np_merge_pdfs_with_cookie(
+    _ctx: Handle,
+    paths: *const *const std::ffi::c_char,
+    count: i32,
+    output_path: *const std::ffi::c_char,
+    cookie: Handle,
 ) -> i32 {
     // Validate inputs
     if paths.is_null() || output_path.is_null() || count <= 0 {
-        eprintln!("np_merge_pdfs: Invalid parameters");
+        log::error("enhanced", "np_merge_pdfs: Invalid parameters");
         return -1;
     }
 
@@ -76,7 +113,10 @@ pub extern "C" fn np_merge_pdfs(
         let path_ptr = unsafe { *paths.offset(i as isize) };
 
         if path_ptr.is_null() {
-            eprintln!("np_merge_pdfs: Null path at index {}", i);
+            log::error(
+                "enhanced",
+                &format!("np_merge_pdfs: Null path at index {}", i),
+            );
             return -1;
         }
 
@@ -84,7 +124,10 @@ pub extern "C" fn np_merge_pdfs(
         let path_str = match unsafe { CStr::from_ptr(path_ptr) }.to_str() {
             Ok(s) => s.to_string(),
             Err(e) => {
-                eprintln!("np_merge_pdfs: Invalid UTF-8 in path {}: {}", i, e);
+                log::error(
+                    "enhanced",
+                    &format!("np_merge_pdfs: Invalid UTF-8 in path {}: {}", i, e),
+                );
                 return -1;
             }
         };
@@ -97,16 +140,21 @@ pub extern "C" fn np_merge_pdfs(
     let output_str = match unsafe { CStr::from_ptr(output_path) }.to_str() {
         Ok(s) => s,
         Err(e) => {
-            eprintln!("np_merge_pdfs: Invalid UTF-8 in output path: {}", e);
+            log::error(
+                "enhanced",
+                &format!("np_merge_pdfs: Invalid UTF-8 in output path: {}", e),
+            );
             return -1;
         }
     };
 
     // Perform the merge
-    match page_ops::merge_pdf(&input_paths, output_str) {
+    let cookie = cookie_from_handle(cookie);
+    match page_ops::merge_pdf_with_cookie(&input_paths, output_str, cookie.as_ref()) {
         Ok(page_count) => page_count as i32,
+        Err(EnhancedError::Aborted) => FZ_ERROR_ABORT,
         Err(e) => {
-            eprintln!("np_merge_pdfs: Merge failed: {}", e);
+            log::error("enhanced", &format!("np_merge_pdfs: Merge failed: {}", e));
             -1
         }
     }
@@ -162,11 +210,40 @@ pub extern "C" fn np_add_watermark(
 /// Caller must ensure path is a valid null-terminated C string.
 #[unsafe(no_mangle)]
 pub extern "C" fn np_optimize_pdf(_ctx: Handle, path: *const std::ffi::c_char) -> i32 {
+    np_optimize_pdf_with_cookie(_ctx, path, 0)
+}
+
+/// Optimize PDF, polling a cookie for cancellation between passes
+///
+/// # Returns
+/// * Number of objects removed on success
+/// * `FZ_ERROR_ABORT` if the cookie was aborted
+/// * -1 on any other error
+///
+/// # Safety
+/// Caller must ensure path is a valid null-terminated C string.
+#[unsafe(no_mangle)]
+pub extern "C" fn np_optimize_pdf_with_cookie(
+    _ctx: Handle,
+    path: *const std::ffi::c_char,
+    cookie: Handle,
+) -> i32 {
     if path.is_null() {
         return -1;
     }
-    // Placeholder - would use optimization functions
-    0
+
+    // SAFETY: We validated path is not null
+    let path_str = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    let cookie = cookie_from_handle(cookie);
+    match optimization::optimize_pdf(path_str, cookie.as_ref()) {
+        Ok(removed) => removed as i32,
+        Err(EnhancedError::Aborted) => FZ_ERROR_ABORT,
+        Err(_) => -1,
+    }
 }
 
 /// Linearize PDF for fast web viewing
@@ -3,7 +3,11 @@
 //! This module provides C-compatible exports for stream filter operations.
 //! Filters are used for decoding/decrypting PDF stream data.
 
+use super::pdf_object::types::PDF_OBJECTS;
 use super::{Handle, HandleStore};
+use crate::pdf::filter::decode_stream_partial;
+use crate::pdf::object::Object;
+use std::ffi::c_char;
 use std::sync::LazyLock;
 
 // ============================================================================
@@ -1213,6 +1217,58 @@ pub extern "C" fn fz_filter_data(_ctx: Handle, filter: Handle) -> *const u8 {
     std::ptr::null()
 }
 
+/// Decode raw stream bytes through the `/Filter` and `/DecodeParms` chain
+/// of `dict`, a PDF object handle for the stream or its dictionary
+///
+/// When the chain ends with a filter that is not known, such as an image
+/// codec, the bytes decoded so far are returned and that filter's name is
+/// written to `remaining` (empty otherwise). Returns a new buffer handle,
+/// or 0 if decoding fails.
+///
+/// # Safety
+/// Caller must ensure `data` points to `len` readable bytes and
+/// `remaining` to `remaining_size` writable bytes, or is null.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_decode_stream(
+    _ctx: Handle,
+    dict: Handle,
+    data: *const u8,
+    len: usize,
+    remaining: *mut c_char,
+    remaining_size: i32,
+) -> Handle {
+    if data.is_null() && len > 0 {
+        return 0;
+    }
+    let dict = match PDF_OBJECTS
+        .get(dict)
+        .map(|obj| obj.lock().unwrap().to_object())
+    {
+        Some(Object::Dict(dict)) | Some(Object::Stream { dict, .. }) => dict,
+        _ => return 0,
+    };
+    let raw = if len == 0 {
+        &[][..]
+    } else {
+        // SAFETY: Caller guarantees data points to len readable bytes
+        unsafe { std::slice::from_raw_parts(data, len) }
+    };
+    let Ok(decoded) = decode_stream_partial(&dict, raw) else {
+        return 0;
+    };
+
+    if !remaining.is_null() && remaining_size > 0 {
+        let name = decoded.remaining.unwrap_or_default();
+        let copy_len = name.len().min((remaining_size - 1) as usize);
+        // SAFETY: Caller guarantees remaining holds remaining_size bytes
+        unsafe {
+            std::ptr::copy_nonoverlapping(name.as_ptr(), remaining as *mut u8, copy_len);
+            *remaining.add(copy_len) = 0;
+        }
+    }
+    crate::ffi::BUFFERS.insert(crate::ffi::buffer::Buffer::from_data(&decoded.data))
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -1221,6 +1277,49 @@ pub extern "C" fn fz_filter_data(_ctx: Handle, filter: Handle) -> *const u8 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_decode_stream_ascii85_flate() {
+        use crate::ffi::pdf_object::types::PdfObj;
+        use crate::pdf::filter::{encode_ascii85, encode_flate};
+        use crate::pdf::parser;
+
+        let original = b"q 1 0 0 1 0 0 cm Q";
+        let raw = encode_ascii85(&encode_flate(original, 6).unwrap()).unwrap();
+        let dict = parser::parse_object(b"<< /Filter [/ASCII85Decode /FlateDecode] >>").unwrap();
+        let dict = PDF_OBJECTS.insert(PdfObj::from_object(&dict));
+
+        let mut remaining = [0x7f as c_char; 32];
+        let buf = fz_decode_stream(
+            0,
+            dict,
+            raw.as_ptr(),
+            raw.len(),
+            remaining.as_mut_ptr(),
+            remaining.len() as i32,
+        );
+        assert_ne!(buf, 0);
+        let buffer = crate::ffi::BUFFERS.get(buf).unwrap();
+        assert_eq!(buffer.lock().unwrap().data(), original);
+        assert_eq!(remaining[0], 0);
+        crate::ffi::BUFFERS.remove(buf);
+
+        let dict = parser::parse_object(b"<< /Filter [/ASCII85Decode /JBIG3Decode] >>").unwrap();
+        let dict = PDF_OBJECTS.insert(PdfObj::from_object(&dict));
+        let buf = fz_decode_stream(
+            0,
+            dict,
+            raw.as_ptr(),
+            raw.len(),
+            remaining.as_mut_ptr(),
+            remaining.len() as i32,
+        );
+        assert_ne!(buf, 0);
+        // SAFETY: fz_decode_stream nul-terminated the name
+        let name = unsafe { std::ffi::CStr::from_ptr(remaining.as_ptr()) };
+        assert_eq!(name.to_bytes(), b"JBIG3Decode");
+        crate::ffi::BUFFERS.remove(buf);
+    }
+
     #[test]
     fn test_ascii85_decode() {
         let mut filter = FilterStream::new(FilterType::Ascii85);
@@ -257,16 +257,28 @@ pub extern "C" fn fz_font_bbox(_ctx: Handle, font: Handle) -> super::geometry::f
 }
 
 /// Outline glyph (extract vector path)
+///
+/// The outline is in glyph space (1 unit = 1 em) transformed by
+/// `transform`. Composite TrueType glyphs are resolved through their
+/// component transforms.
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_outline_glyph(
     _ctx: Handle,
     font: Handle,
     glyph: i32,
-    _transform: super::geometry::fz_matrix,
+    transform: super::geometry::fz_matrix,
 ) -> Handle {
     if let Some(f) = FONTS.get(font) {
         if let Ok(guard) = f.lock() {
-            let path = guard.outline_glyph(glyph as u16);
+            let ctm = crate::fitz::geometry::Matrix::new(
+                transform.a,
+                transform.b,
+                transform.c,
+                transform.d,
+                transform.e,
+                transform.f,
+            );
+            let path = super::glyph::outline_glyph_path(&guard, glyph as u16, &ctm);
             return super::path::PATHS.insert(path);
         }
     }
@@ -2,10 +2,19 @@
 //!
 //! Provides FFI bindings for PDF interactive forms (AcroForms).
 
-use super::{Handle, HandleStore};
-use crate::pdf::form::{ChoiceOption, FieldFlags, Form, FormField, TextFormat, WidgetType};
+use super::document::load_pdf;
+use super::{DOCUMENTS, Handle, HandleStore};
+use crate::fitz::error::{Error, Result};
+use crate::pdf::document::Document;
+use crate::pdf::form::{
+    ChoiceOption, FieldFlags, Form, FormField, TextFormat, WidgetType, field_appearance_changes,
+    field_value_changes, load_form,
+};
+use crate::pdf::object::{ObjRef, Object};
+use crate::pdf::write;
+use std::collections::{BTreeMap, HashMap};
 use std::ffi::{CStr, c_char};
-use std::sync::LazyLock;
+use std::sync::{LazyLock, Mutex};
 
 /// Form storage
 pub static FORMS: LazyLock<HandleStore<Form>> = LazyLock::new(HandleStore::default);
@@ -13,18 +22,78 @@ pub static FORMS: LazyLock<HandleStore<Form>> = LazyLock::new(HandleStore::defau
 /// Form field storage (widgets)
 pub static FORM_FIELDS: LazyLock<HandleStore<FormField>> = LazyLock::new(HandleStore::default);
 
+/// Document each field loaded with `pdf_load_field` belongs to
+static FIELD_DOCUMENTS: LazyLock<Mutex<HashMap<Handle, Handle>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Apply the changes `changes` computes for a document as an incremental
+/// update
+fn save_changes(
+    doc: Handle,
+    changes: impl FnOnce(&Document) -> Result<BTreeMap<ObjRef, Object>>,
+) -> Result<()> {
+    let pdf = load_pdf(doc)?;
+    let changes = changes(&pdf)?;
+    let mut out = Vec::new();
+    write::append_incremental(pdf.data(), &changes, &mut out)?;
+    let doc = DOCUMENTS
+        .get(doc)
+        .ok_or_else(|| Error::argument("invalid document handle"))?;
+    doc.lock().unwrap().set_data(out);
+    Ok(())
+}
+
 // ============================================================================
 // Form Access
 // ============================================================================
 
 /// Get form from document
+///
+/// The form holds the document's AcroForm fields, or none if the document
+/// has no form.
 #[unsafe(no_mangle)]
-pub extern "C" fn pdf_form(_ctx: Handle, _doc: Handle) -> Handle {
-    // Create or return existing form
-    let form = Form::new();
+pub extern "C" fn pdf_form(_ctx: Handle, doc: Handle) -> Handle {
+    let form = load_pdf(doc)
+        .and_then(|doc| load_form(&doc))
+        .unwrap_or_default();
     FORMS.insert(form)
 }
 
+/// Count the terminal AcroForm fields of a document
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_count_fields(_ctx: Handle, doc: Handle) -> i32 {
+    load_pdf(doc)
+        .and_then(|doc| load_form(&doc))
+        .map_or(0, |form| form.len() as i32)
+}
+
+/// Load a document's field by index, in /Fields order
+///
+/// Setting the value of the returned field also updates the document.
+/// Returns 0 if there is no such field.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_load_field(_ctx: Handle, doc: Handle, index: i32) -> Handle {
+    let Ok(form) = load_pdf(doc).and_then(|doc| load_form(&doc)) else {
+        return 0;
+    };
+    let Some(field) = usize::try_from(index)
+        .ok()
+        .and_then(|i| form.fields().nth(i))
+    else {
+        return 0;
+    };
+    let handle = FORM_FIELDS.insert(field.clone());
+    FIELD_DOCUMENTS.lock().unwrap().insert(handle, doc);
+    handle
+}
+
+/// Drop a field handle
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_drop_field(_ctx: Handle, field: Handle) {
+    FORM_FIELDS.remove(field);
+    FIELD_DOCUMENTS.lock().unwrap().remove(&field);
+}
+
 /// Keep form reference
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_keep_form(_ctx: Handle, form: Handle) -> Handle {
@@ -317,6 +386,10 @@ pub extern "C" fn pdf_field_value(
 
 /// Set field value
 ///
+/// The field is marked dirty so its appearance can be regenerated. For a
+/// field loaded from a document, /V is updated in the document, and so is
+/// the /AS of each checkbox or radio button widget.
+///
 /// # Safety
 /// Caller must ensure value is a valid null-terminated C string
 #[unsafe(no_mangle)]
@@ -333,7 +406,13 @@ pub extern "C" fn pdf_set_field_value(
         if let Ok(mut guard) = f.lock() {
             if let Some(val_str) = super::safe_helpers::c_str_to_str(value) {
                 if guard.set_value(val_str.to_string()).is_ok() {
-                    return 1;
+                    let doc = FIELD_DOCUMENTS.lock().unwrap().get(&field).copied();
+                    return match doc {
+                        Some(doc) => {
+                            save_changes(doc, |pdf| field_value_changes(pdf, &guard)).is_ok() as i32
+                        }
+                        None => 1,
+                    };
                 }
             }
         }
@@ -342,6 +421,27 @@ pub extern "C" fn pdf_set_field_value(
     0
 }
 
+/// Regenerate a field's appearance from its value
+///
+/// Only fields loaded with `pdf_load_field` have appearances to update.
+/// The new appearance is stored in the field's document and the field is
+/// no longer dirty. Returns 1 on success, 0 otherwise.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_update_field_appearance(_ctx: Handle, field: Handle) -> i32 {
+    let Some(doc) = FIELD_DOCUMENTS.lock().unwrap().get(&field).copied() else {
+        return 0;
+    };
+    let Some(f) = FORM_FIELDS.get(field) else {
+        return 0;
+    };
+    let mut guard = f.lock().unwrap();
+    if save_changes(doc, |pdf| field_appearance_changes(pdf, &guard)).is_err() {
+        return 0;
+    }
+    guard.dirty = false;
+    1
+}
+
 /// Get field rectangle
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_field_rect(_ctx: Handle, field: Handle) -> super::geometry::fz_rect {
@@ -981,6 +1081,7 @@ pub extern "C" fn pdf_clone_field(_ctx: Handle, field: Handle) -> Handle {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
     use std::ffi::CString;
 
     #[test]
@@ -1054,6 +1155,108 @@ mod tests {
         FORM_FIELDS.remove(field);
     }
 
+    /// A one-page PDF with a text field and a checkbox
+    fn form_pdf() -> Vec<u8> {
+        let objects: [&[u8]; 7] = [
+            b"<< /Type /Catalog /Pages 2 0 R /AcroForm << /Fields [4 0 R 5 0 R] \
+              /DA (/Helv 0 Tf 0 g) /DR << /Font << /Helv << /Type /Font /Subtype /Type1 \
+              /BaseFont /Helvetica >> >> >> >> >>",
+            b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Annots [4 0 R 6 0 R] >>",
+            b"<< /FT /Tx /T (name) /V (Alice) /Type /Annot /Subtype /Widget \
+              /Rect [50 700 250 720] /P 3 0 R >>",
+            b"<< /FT /Btn /T (agree) /Kids [6 0 R] >>",
+            b"<< /Type /Annot /Subtype /Widget /Parent 5 0 R /Rect [50 650 62 662] /AS /On \
+              /AP << /N << /On 7 0 R /Off 7 0 R >> >> >>",
+            b"<< /Length 0 >>\nstream\n\nendstream",
+        ];
+        build_pdf(&objects)
+    }
+
+    #[test]
+    fn test_document_field_value() {
+        let doc = DOCUMENTS.insert(super::super::document::Document::new(form_pdf()));
+        assert_eq!(pdf_count_fields(0, doc), 2);
+
+        let field = pdf_load_field(0, doc, 0);
+        let mut buf = [0 as c_char; 64];
+        pdf_field_name(0, field, buf.as_mut_ptr(), 64);
+        assert_eq!(unsafe { CStr::from_ptr(buf.as_ptr()) }.to_str(), Ok("name"));
+        assert_eq!(pdf_field_type(0, field), 6);
+        pdf_field_value(0, field, buf.as_mut_ptr(), 64);
+        assert_eq!(
+            unsafe { CStr::from_ptr(buf.as_ptr()) }.to_str(),
+            Ok("Alice")
+        );
+
+        let value = CString::new("Bob").unwrap();
+        assert_eq!(pdf_set_field_value(0, field, value.as_ptr()), 1);
+        assert!(FORM_FIELDS.get(field).unwrap().lock().unwrap().dirty);
+        pdf_drop_field(0, field);
+
+        // The new value is read back from the updated document
+        let field = pdf_load_field(0, doc, 0);
+        pdf_field_value(0, field, buf.as_mut_ptr(), 64);
+        assert_eq!(unsafe { CStr::from_ptr(buf.as_ptr()) }.to_str(), Ok("Bob"));
+        pdf_drop_field(0, field);
+
+        // Checkboxes map their value to the widget's appearance state
+        let checkbox = pdf_load_field(0, doc, 1);
+        assert_eq!(pdf_field_type(0, checkbox), 1);
+        assert_eq!(pdf_field_is_checked(0, checkbox), 1);
+        let off = CString::new("Off").unwrap();
+        assert_eq!(pdf_set_field_value(0, checkbox, off.as_ptr()), 1);
+        pdf_drop_field(0, checkbox);
+        let checkbox = pdf_load_field(0, doc, 1);
+        assert_eq!(pdf_field_is_checked(0, checkbox), 0);
+        pdf_drop_field(0, checkbox);
+        DOCUMENTS.remove(doc);
+    }
+
+    #[test]
+    fn test_update_field_appearance() {
+        let doc = DOCUMENTS.insert(super::super::document::Document::new(form_pdf()));
+        let field = pdf_load_field(0, doc, 0);
+        let value = CString::new("Hello appearance").unwrap();
+        assert_eq!(pdf_set_field_value(0, field, value.as_ptr()), 1);
+        assert_eq!(pdf_update_field_appearance(0, field), 1);
+        assert!(!FORM_FIELDS.get(field).unwrap().lock().unwrap().dirty);
+        pdf_drop_field(0, field);
+
+        let pdf = load_pdf(doc).unwrap();
+        let Object::Dict(widget) = pdf.load_object(ObjRef::new(4, 0)).unwrap() else {
+            panic!("widget is not a dictionary");
+        };
+        let Some(Object::Dict(ap)) = pdf.resolve_key(&widget, "AP").unwrap() else {
+            panic!("widget has no /AP");
+        };
+        let normal = pdf.stream_data(ap.get("N").unwrap()).unwrap();
+        let normal = String::from_utf8_lossy(&normal);
+        assert!(normal.starts_with("/Tx BMC"));
+        assert!(normal.contains("/Helv"));
+        assert!(normal.contains("(Hello appearance) Tj"));
+        assert!(normal.trim_end().ends_with("EMC"));
+
+        // Checkboxes switch between their on and off appearance states
+        let checkbox = pdf_load_field(0, doc, 1);
+        let off = CString::new("Off").unwrap();
+        assert_eq!(pdf_set_field_value(0, checkbox, off.as_ptr()), 1);
+        assert_eq!(pdf_update_field_appearance(0, checkbox), 1);
+        pdf_drop_field(0, checkbox);
+        let pdf = load_pdf(doc).unwrap();
+        let Object::Dict(widget) = pdf.load_object(ObjRef::new(6, 0)).unwrap() else {
+            panic!("widget is not a dictionary");
+        };
+        assert_eq!(
+            widget
+                .get("AS")
+                .and_then(Object::as_name)
+                .map(|n| n.as_str()),
+            Some("Off")
+        );
+        DOCUMENTS.remove(doc);
+    }
+
     #[test]
     fn test_form_operations() {
         let form = pdf_form(0, 0);
@@ -1,7 +1,12 @@
 //! C FFI for glyph handling - MuPDF compatible
 //! Safe Rust implementation of fz_glyph
 
+use super::font::FONTS;
+use super::path::PATHS;
 use super::{Handle, HandleStore};
+use crate::fitz::font::Font;
+use crate::fitz::geometry::Matrix;
+use crate::fitz::path::Path;
 use std::sync::LazyLock;
 
 /// Glyph origin type
@@ -787,6 +792,43 @@ pub extern "C" fn fz_set_glyph_hinting(_ctx: Handle, glyph: Handle, hinting: i32
     }
 }
 
+// ============================================================================
+// Glyph Outlines
+// ============================================================================
+
+/// Outline a glyph in glyph space (1 unit = 1 em) transformed by `ctm`
+///
+/// The font's outline is in font units; it is scaled by the font's units
+/// per em first so `ctm` means the same as a text rendering matrix.
+pub(crate) fn outline_glyph_path(font: &Font, gid: u16, ctm: &Matrix) -> Path {
+    let mut path = font.outline_glyph(gid);
+    let units = font.units_per_em().unwrap_or(1000) as f32;
+    let trm = Matrix::scale(1.0 / units, 1.0 / units).concat(ctm);
+    path.transform(|p| p.transform(&trm));
+    path
+}
+
+/// Outline a glyph handle with its own font, glyph ID and matrix
+///
+/// Returns a path handle, or 0 if the glyph or its font is not valid.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_glyph_outline(_ctx: Handle, glyph: Handle) -> Handle {
+    let Some((font, gid, m)) = GLYPHS
+        .get(glyph)
+        .and_then(|g| g.lock().ok().map(|g| (g.font, g.glyph_id, g.matrix)))
+    else {
+        return 0;
+    };
+    let Some(font) = FONTS.get(font) else {
+        return 0;
+    };
+    let Ok(font) = font.lock() else {
+        return 0;
+    };
+    let ctm = Matrix::new(m[0], m[1], m[2], m[3], m[4], m[5]);
+    PATHS.insert(outline_glyph_path(&font, gid as u16, &ctm))
+}
+
 // ============================================================================
 // Reference Counting
 // ============================================================================
@@ -949,4 +991,34 @@ mod tests {
 
         fz_drop_glyph(0, glyph);
     }
+
+    #[test]
+    fn test_glyph_outline_in_em_units() {
+        let data = crate::fitz::font::tests::sample_truetype();
+        let font = crate::ffi::font::fz_new_font_from_memory(
+            0,
+            c"Sample".as_ptr(),
+            data.as_ptr(),
+            data.len() as i32,
+            0,
+            0,
+        );
+        let glyph = fz_new_glyph(0, font, 1, '.' as u32);
+        // 10pt text at (50, 20)
+        let matrix = [10.0f32, 0.0, 0.0, 10.0, 50.0, 20.0];
+        fz_set_glyph_matrix(0, glyph, matrix.as_ptr());
+
+        let path = fz_glyph_outline(0, glyph);
+        assert_ne!(path, 0);
+        let bounds = PATHS.get(path).unwrap().lock().unwrap().bounds();
+        assert_eq!(
+            bounds,
+            crate::fitz::geometry::Rect::new(51.0, 20.0, 52.0, 21.0)
+        );
+
+        crate::ffi::path::fz_drop_path(0, path);
+        fz_drop_glyph(0, glyph);
+        crate::ffi::font::fz_drop_font(0, font);
+        assert_eq!(fz_glyph_outline(0, glyph), 0);
+    }
 }
@@ -4,13 +4,16 @@
 //! and subpixel positioning support.
 
 use std::collections::HashMap;
-use std::sync::{LazyLock, Mutex};
+use std::sync::{Arc, LazyLock, Mutex};
 use std::time::Instant;
 
 use crate::ffi::colorspace::FZ_COLORSPACE_GRAY;
 use crate::ffi::glyph::{GLYPHS, Glyph};
-use crate::ffi::{Handle, PIXMAPS};
+use crate::ffi::store::{StoreType, fz_store_find, fz_store_item};
+use crate::ffi::{Handle, PIXMAPS, new_handle};
+use crate::fitz::error::Result;
 use crate::fitz::geometry::{IRect, Matrix};
+use crate::fitz::pixmap::Pixmap;
 
 // ============================================================================
 // Cache Configuration
@@ -25,6 +28,12 @@ pub const DEFAULT_MAX_GLYPHS: usize = 4096;
 /// Subpixel quantization levels
 pub const SUBPIXEL_LEVELS: u8 = 4;
 
+/// Glyph size buckets per pixel (quarter-pixel size steps)
+pub const SIZE_BUCKETS_PER_PIXEL: f32 = 4.0;
+
+/// Buckets for the normalized rotation/shear components (~0.5 degree steps)
+pub const DIRECTION_BUCKETS: f32 = 128.0;
+
 /// Cache eviction policy
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -102,6 +111,98 @@ impl GlyphCacheKey {
     }
 }
 
+/// Cache key for a rendered glyph mask.
+///
+/// Unlike [`GlyphCacheKey`], the transform is bucketed rather than hashed
+/// verbatim: the glyph size is quantized to quarter pixels, the direction
+/// (rotation/shear) of the normalized linear part to roughly half a degree,
+/// and the translation is reduced to its subpixel offset. CTMs that differ
+/// only by placement or by floating point noise therefore share one entry.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct RenderedGlyphKey {
+    /// Font handle
+    pub font: Handle,
+    /// Glyph ID
+    pub glyph_id: u32,
+    /// Quantized glyph size (expansion of the linear part)
+    pub size: i32,
+    /// Quantized normalized linear components (a, b, c, d)
+    pub direction: [i32; 4],
+    /// Subpixel position X (0-3)
+    pub subpix_x: u8,
+    /// Subpixel position Y (0-3)
+    pub subpix_y: u8,
+}
+
+impl RenderedGlyphKey {
+    /// Build a key from a CTM, returning it with the representative matrix
+    /// of its bucket (the matrix the glyph should be rendered with).
+    pub fn quantize(font: Handle, glyph_id: u32, ctm: &Matrix) -> (Self, Matrix) {
+        let expansion = (ctm.a * ctm.d - ctm.b * ctm.c).abs().sqrt();
+        let size = (expansion * SIZE_BUCKETS_PER_PIXEL).round() as i32;
+        let q_expansion = size as f32 / SIZE_BUCKETS_PER_PIXEL;
+
+        let bucket = |v: f32| -> i32 {
+            if expansion > 0.0 {
+                (v / expansion * DIRECTION_BUCKETS).round() as i32
+            } else {
+                0
+            }
+        };
+        let direction = [bucket(ctm.a), bucket(ctm.b), bucket(ctm.c), bucket(ctm.d)];
+        let unbucket = |q: i32| q as f32 / DIRECTION_BUCKETS * q_expansion;
+
+        let mut quantized = Matrix {
+            a: unbucket(direction[0]),
+            b: unbucket(direction[1]),
+            c: unbucket(direction[2]),
+            d: unbucket(direction[3]),
+            e: ctm.e,
+            f: ctm.f,
+        };
+        let (subpix_x, subpix_y) = subpixel_adjust_internal(&mut quantized);
+        // Rendered masks are placed by the caller; keep only the subpixel offset
+        quantized.e = quantized.e.rem_euclid(1.0);
+        quantized.f = quantized.f.rem_euclid(1.0);
+
+        (
+            Self {
+                font,
+                glyph_id,
+                size,
+                direction,
+                subpix_x,
+                subpix_y,
+            },
+            quantized,
+        )
+    }
+
+    /// Serialize the key for use as an `fz_store` lookup key
+    fn store_key(&self) -> Vec<u8> {
+        let mut key = Vec::with_capacity(40);
+        key.extend_from_slice(b"glyph:");
+        key.extend_from_slice(&self.font.to_le_bytes());
+        key.extend_from_slice(&self.glyph_id.to_le_bytes());
+        key.extend_from_slice(&self.size.to_le_bytes());
+        for d in self.direction {
+            key.extend_from_slice(&d.to_le_bytes());
+        }
+        key.push(self.subpix_x);
+        key.push(self.subpix_y);
+        key
+    }
+}
+
+/// Rendered glyph mask held by the cache
+#[derive(Clone)]
+struct RenderedGlyph {
+    /// Handle under which the mask is registered in `fz_store`
+    store_handle: Handle,
+    /// Rendered alpha mask
+    pixmap: Arc<Pixmap>,
+}
+
 /// Cached glyph entry
 #[derive(Debug, Clone)]
 pub struct GlyphCacheEntry {
@@ -180,6 +281,10 @@ pub struct GlyphCache {
     policy: CacheEvictionPolicy,
     /// Insertion order for FIFO
     insertion_order: Vec<GlyphCacheKey>,
+    /// Rendered glyph masks, accounted for in `fz_store` as `StoreType::Glyph`
+    rendered: HashMap<RenderedGlyphKey, RenderedGlyph>,
+    /// Store handle to key of each rendered mask, for dropping evicted masks
+    rendered_keys: HashMap<Handle, RenderedGlyphKey>,
 }
 
 impl Default for GlyphCache {
@@ -193,6 +298,8 @@ impl Default for GlyphCache {
             },
             policy: CacheEvictionPolicy::Lru,
             insertion_order: Vec::new(),
+            rendered: HashMap::new(),
+            rendered_keys: HashMap::new(),
         }
     }
 }
@@ -305,6 +412,8 @@ impl GlyphCache {
     pub fn purge(&mut self) {
         self.entries.clear();
         self.insertion_order.clear();
+        self.rendered.clear();
+        self.rendered_keys.clear();
         self.stats.memory_usage = 0;
         self.stats.glyph_count = 0;
         self.stats.purge_count += 1;
@@ -331,6 +440,76 @@ impl GlyphCache {
     }
 }
 
+/// Look up a rendered glyph mask, rendering and caching it on a miss.
+///
+/// The CTM is bucketed (see [`RenderedGlyphKey`]) and `render_fn` is called
+/// with the bucket's representative matrix, so every CTM in a bucket gets an
+/// identical mask. Masks are registered in the global `fz_store` as
+/// `StoreType::Glyph`, so lookups show up in `fz_store_hits`/`fz_store_misses`
+/// and the store's size limits and eviction apply to them.
+pub fn lookup_or_render<F>(
+    font: Handle,
+    gid: u32,
+    ctm: &Matrix,
+    render_fn: F,
+) -> Result<Arc<Pixmap>>
+where
+    F: FnOnce(&Matrix) -> Result<Pixmap>,
+{
+    let (key, quantized) = RenderedGlyphKey::quantize(font, gid, ctm);
+    let store_key = key.store_key();
+
+    let found = fz_store_find(0, store_key.as_ptr(), store_key.len());
+    if found != 0 {
+        if let Ok(cache) = GLYPH_CACHE.lock() {
+            if let Some(entry) = cache.rendered.get(&key) {
+                if entry.store_handle == found {
+                    return Ok(Arc::clone(&entry.pixmap));
+                }
+            }
+        }
+    }
+
+    // Render without holding the cache lock; render_fn may itself use the cache
+    let pixmap = Arc::new(render_fn(&quantized)?);
+    let size = pixmap.samples().len();
+    let store_handle = new_handle();
+    fz_store_item(
+        0,
+        StoreType::Glyph as i32,
+        store_handle,
+        size,
+        store_key.as_ptr(),
+        store_key.len(),
+    );
+
+    // Masks the store has evicted, including any this insert pushed out
+    let dropped = crate::ffi::store::STORE
+        .lock()
+        .map(|mut store| store.take_dropped_glyphs())
+        .unwrap_or_default();
+
+    if let Ok(mut cache) = GLYPH_CACHE.lock() {
+        if let Some(old) = cache.rendered.insert(
+            key,
+            RenderedGlyph {
+                store_handle,
+                pixmap: Arc::clone(&pixmap),
+            },
+        ) {
+            cache.rendered_keys.remove(&old.store_handle);
+        }
+        cache.rendered_keys.insert(store_handle, key);
+        for handle in dropped {
+            if let Some(key) = cache.rendered_keys.remove(&handle) {
+                cache.rendered.remove(&key);
+            }
+        }
+    }
+
+    Ok(pixmap)
+}
+
 /// Global glyph cache instance
 pub static GLYPH_CACHE: LazyLock<Mutex<GlyphCache>> =
     LazyLock::new(|| Mutex::new(GlyphCache::default()));
@@ -595,8 +774,8 @@ fn render_glyph_internal(
 /// Note: Use fz_subpixel_adjust in glyph.rs for the main API
 pub fn subpixel_adjust_internal(matrix: &mut Matrix) -> (u8, u8) {
     // Quantize translation components for subpixel positioning
-    let e_frac = matrix.e.fract();
-    let f_frac = matrix.f.fract();
+    let e_frac = matrix.e.rem_euclid(1.0);
+    let f_frac = matrix.f.rem_euclid(1.0);
 
     // Quantize to subpixel levels (0-3 typically)
     let qe_val = ((e_frac * SUBPIXEL_LEVELS as f32) as u8) % SUBPIXEL_LEVELS;
@@ -1094,4 +1273,94 @@ mod tests {
 
         fz_purge_glyph_cache(ctx);
     }
+
+    #[test]
+    fn test_rendered_key_buckets_trivial_differences() {
+        let m1 = Matrix::new(12.0, 0.0, 0.0, 12.0, 100.0, 200.0);
+        let m2 = Matrix::new(12.001, 0.0001, 0.0, 11.999, 340.0, 17.0);
+        let m3 = Matrix::new(14.0, 0.0, 0.0, 14.0, 100.0, 200.0);
+        let rot = Matrix::rotate(30.0).concat(&Matrix::scale(12.0, 12.0));
+
+        let (k1, q1) = RenderedGlyphKey::quantize(1, 65, &m1);
+        let (k2, _) = RenderedGlyphKey::quantize(1, 65, &m2);
+        let (k3, _) = RenderedGlyphKey::quantize(1, 65, &m3);
+        let (k4, _) = RenderedGlyphKey::quantize(1, 65, &rot);
+
+        assert_eq!(k1, k2);
+        assert_ne!(k1, k3);
+        assert_ne!(k1, k4);
+        assert!((q1.a - 12.0).abs() < 0.01);
+        assert_eq!(q1.e, 0.0);
+    }
+
+    #[test]
+    fn test_rendered_key_negative_translation() {
+        let left = Matrix::new(12.0, 0.0, 0.0, 12.0, -0.25, -3.25);
+        let right = Matrix::new(12.0, 0.0, 0.0, 12.0, 0.75, 4.75);
+
+        let (k1, q1) = RenderedGlyphKey::quantize(1, 65, &left);
+        let (k2, q2) = RenderedGlyphKey::quantize(1, 65, &right);
+
+        assert_eq!(k1, k2);
+        assert_eq!((k1.subpix_x, k1.subpix_y), (3, 3));
+        assert_eq!((q1.e, q1.f), (0.75, 0.75));
+        assert_eq!((q1.e, q1.f), (q2.e, q2.f));
+    }
+
+    #[test]
+    fn test_lookup_or_render_store_hit() {
+        // Unique font handle so parallel tests cannot share our entry
+        let font = new_handle();
+        let ctm = Matrix::new(10.0, 0.0, 0.0, 10.0, 5.0, 5.0);
+        let mut renders = 0;
+
+        let mut render = |m: &Matrix| {
+            renders += 1;
+            let w = m.a.ceil() as i32;
+            let h = m.d.ceil() as i32;
+            let mut pix = Pixmap::new(
+                Some(crate::fitz::colorspace::Colorspace::device_gray()),
+                w,
+                h,
+                false,
+            )?;
+            pix.clear(255);
+            Ok(pix)
+        };
+
+        let first = lookup_or_render(font, 42, &ctm, &mut render).unwrap();
+        let hits_before = crate::ffi::store::fz_store_hits(0);
+        let second = lookup_or_render(font, 42, &ctm, &mut render).unwrap();
+        let hits_after = crate::ffi::store::fz_store_hits(0);
+
+        assert_eq!(renders, 1);
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(hits_after > hits_before);
+        assert_eq!(first.width(), 10);
+    }
+
+    #[test]
+    fn test_lookup_or_render_drops_evicted_masks() {
+        let font = new_handle();
+        let ctm = Matrix::new(4.0, 0.0, 0.0, 4.0, 0.0, 0.0);
+        let render = |_: &Matrix| {
+            Pixmap::new(
+                Some(crate::fitz::colorspace::Colorspace::device_gray()),
+                4,
+                4,
+                false,
+            )
+        };
+
+        lookup_or_render(font, 1, &ctm, render).unwrap();
+        let (key, _) = RenderedGlyphKey::quantize(font, 1, &ctm);
+        assert!(GLYPH_CACHE.lock().unwrap().rendered.contains_key(&key));
+
+        // The next insert picks up the store's removal of the first mask
+        let store_key = key.store_key();
+        crate::ffi::store::fz_store_remove_by_key(0, store_key.as_ptr(), store_key.len());
+        lookup_or_render(font, 2, &ctm, render).unwrap();
+
+        assert!(!GLYPH_CACHE.lock().unwrap().rendered.contains_key(&key));
+    }
 }
@@ -4,7 +4,7 @@
 
 use crate::ffi::{Handle, HandleStore};
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashMap};
 use std::sync::LazyLock;
 
 // ============================================================================
@@ -182,6 +182,148 @@ impl Heap {
     }
 }
 
+/// Min-heap of ids with updatable keys
+///
+/// Each id appears at most once. Its position is tracked so the key can be
+/// changed or the entry removed in O(log n), which `BinaryHeap` cannot do.
+/// Ties are broken by the smaller id.
+#[derive(Debug, Clone, Default)]
+pub struct IndexedHeap<K: Ord + Copy> {
+    entries: Vec<(K, u64)>,
+    positions: HashMap<u64, usize>,
+    visits: u64,
+}
+
+impl<K: Ord + Copy> IndexedHeap<K> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            positions: HashMap::new(),
+            visits: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        self.positions.contains_key(&id)
+    }
+
+    /// Key of `id`, if present
+    pub fn key(&self, id: u64) -> Option<K> {
+        self.positions.get(&id).map(|&i| self.entries[i].0)
+    }
+
+    /// Number of entries compared by sift operations so far
+    pub fn visits(&self) -> u64 {
+        self.visits
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.positions.clear();
+    }
+
+    /// Insert `id`, or move it to `key` if already present
+    pub fn push(&mut self, id: u64, key: K) {
+        if let Some(&i) = self.positions.get(&id) {
+            let old = self.entries[i].0;
+            self.entries[i].0 = key;
+            if key < old {
+                self.sift_up(i);
+            } else {
+                self.sift_down(i);
+            }
+            return;
+        }
+        self.entries.push((key, id));
+        let i = self.entries.len() - 1;
+        self.positions.insert(id, i);
+        self.sift_up(i);
+    }
+
+    /// Id with the smallest key
+    pub fn peek(&self) -> Option<u64> {
+        self.entries.first().map(|&(_, id)| id)
+    }
+
+    pub fn pop(&mut self) -> Option<u64> {
+        let id = self.peek()?;
+        self.remove(id);
+        Some(id)
+    }
+
+    /// Remove `id`, returning its key
+    pub fn remove(&mut self, id: u64) -> Option<K> {
+        let i = self.positions.remove(&id)?;
+        let (key, _) = self.entries.swap_remove(i);
+        if i < self.entries.len() {
+            self.positions.insert(self.entries[i].1, i);
+            self.sift_down(i);
+            self.sift_up(i);
+        }
+        Some(key)
+    }
+
+    /// Replace the contents, heapifying in O(n)
+    pub fn rebuild(&mut self, entries: impl IntoIterator<Item = (u64, K)>) {
+        self.entries = entries.into_iter().map(|(id, key)| (key, id)).collect();
+        self.positions = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, &(_, id))| (id, i))
+            .collect();
+        for i in (0..self.entries.len() / 2).rev() {
+            self.sift_down(i);
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.entries.swap(a, b);
+        self.positions.insert(self.entries[a].1, a);
+        self.positions.insert(self.entries[b].1, b);
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            self.visits += 1;
+            if self.entries[i] >= self.entries[parent] {
+                break;
+            }
+            self.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        let len = self.entries.len();
+        loop {
+            let mut smallest = i;
+            for child in [2 * i + 1, 2 * i + 2] {
+                if child < len {
+                    self.visits += 1;
+                    if self.entries[child] < self.entries[smallest] {
+                        smallest = child;
+                    }
+                }
+            }
+            if smallest == i {
+                break;
+            }
+            self.swap(i, smallest);
+            i = smallest;
+        }
+    }
+}
+
 // Global heap store
 pub static HEAPS: LazyLock<HandleStore<Heap>> = LazyLock::new(HandleStore::new);
 
@@ -888,4 +1030,36 @@ mod tests {
 
         fz_drop_heap(ctx, heap);
     }
+
+    #[test]
+    fn test_indexed_heap_update_and_remove() {
+        let mut heap = IndexedHeap::new();
+        for (id, key) in [(1, 50), (2, 10), (3, 30), (4, 40), (5, 20)] {
+            heap.push(id, key);
+        }
+        assert_eq!(heap.peek(), Some(2));
+
+        // Raising the minimum's key moves it down, lowering another moves it up
+        heap.push(2, 60);
+        heap.push(4, 5);
+        assert_eq!(heap.remove(3), Some(30));
+        assert!(!heap.contains(3));
+
+        let order: Vec<u64> = std::iter::from_fn(|| heap.pop()).collect();
+        assert_eq!(order, [4, 5, 1, 2]);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn test_indexed_heap_rebuild() {
+        let mut heap = IndexedHeap::new();
+        heap.rebuild((0..100u64).map(|id| (id, (id * 37) % 100)));
+        assert_eq!(heap.len(), 100);
+        assert_eq!(heap.key(10), Some(70));
+
+        let keys: Vec<u64> = std::iter::from_fn(|| heap.pop())
+            .map(|id| (id * 37) % 100)
+            .collect();
+        assert_eq!(keys, (0..100).collect::<Vec<_>>());
+    }
 }
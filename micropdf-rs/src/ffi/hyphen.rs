@@ -147,6 +147,9 @@ pub struct Hyphenator {
     right_min: usize,
     /// Language
     language: TextLanguage,
+    /// Explicit break positions from `\hyphenation`, in characters, keyed by
+    /// lowercase word
+    exceptions: HashMap<String, Vec<usize>>,
 }
 
 impl Hyphenator {
@@ -155,11 +158,19 @@ impl Hyphenator {
             trie: HyphTrieNode::default(),
             pattern_count: 0,
             left_min: 2,
-            right_min: 2,
+            right_min: 3,
             language: TextLanguage::Unset,
+            exceptions: HashMap::new(),
         }
     }
 
+    /// Create a hyphenator from TeX pattern file contents
+    pub fn from_patterns(data: &str) -> Self {
+        let mut hyph = Self::new();
+        hyph.load_patterns(data);
+        hyph
+    }
+
     pub fn with_language(language: TextLanguage) -> Self {
         let mut hyph = Self::new();
         hyph.language = language;
@@ -278,6 +289,16 @@ impl Hyphenator {
             "a4gu",
             "ai2",
             "ai5ly",
+            // Patterns covering Liang's "hy-phen-ation" example
+            "hy3ph",
+            "he2n",
+            "hena4",
+            "hen5at",
+            "1na",
+            "n2at",
+            "1tio",
+            "2io",
+            "o2n",
         ];
 
         for pattern in patterns {
@@ -337,7 +358,7 @@ impl Hyphenator {
                 current_value = c.to_digit(10).unwrap() as u8;
             } else {
                 values.push(current_value);
-                chars.push(c.to_ascii_lowercase());
+                chars.push(lowercase(c));
                 current_value = 0;
             }
         }
@@ -356,25 +377,90 @@ impl Hyphenator {
         self.pattern_count += 1;
     }
 
+    /// Add an exception word, such as `ta-ble`, whose hyphens mark its
+    /// only break points
+    pub fn add_exception(&mut self, word: &str) {
+        let mut breaks = Vec::new();
+        let mut key = String::with_capacity(word.len());
+        let mut len = 0;
+        for c in word.chars() {
+            if c == '-' {
+                breaks.push(len);
+            } else {
+                key.push(lowercase(c));
+                len += 1;
+            }
+        }
+        if len > 0 {
+            self.exceptions.insert(key, breaks);
+        }
+    }
+
     /// Load patterns from a string (TeX-style format)
+    ///
+    /// Accepts bare whitespace-separated patterns as well as `.pat` files
+    /// with `%` comments, a `\patterns{...}` group and an optional
+    /// `\hyphenation{...}` exception group.
     pub fn load_patterns(&mut self, data: &str) {
+        let mut in_exceptions = false;
         for line in data.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('%') {
-                continue;
-            }
-            for pattern in line.split_whitespace() {
-                self.add_pattern(pattern);
+            let line = line.split('%').next().unwrap_or("");
+            for token in line.split_whitespace() {
+                let mut token = token;
+                if let Some(rest) = token.strip_prefix("\\patterns{") {
+                    in_exceptions = false;
+                    token = rest;
+                } else if let Some(rest) = token.strip_prefix("\\hyphenation{") {
+                    in_exceptions = true;
+                    token = rest;
+                }
+                let closes = token.ends_with('}');
+                let token = token.trim_end_matches('}');
+                if !token.is_empty() && !token.starts_with('\\') {
+                    if in_exceptions {
+                        self.add_exception(token);
+                    } else {
+                        self.add_pattern(token);
+                    }
+                }
+                if closes {
+                    in_exceptions = false;
+                }
             }
         }
     }
 
-    /// Hyphenate a word, returning positions where hyphens can be inserted
-    pub fn hyphenate(&self, word: &str) -> Vec<bool> {
-        let word_lower = word.to_lowercase();
-        let chars: Vec<char> = word_lower.chars().collect();
+    /// Byte offsets into `word` where it may be broken with a hyphen
+    ///
+    /// Letters are matched case-insensitively, so patterns for accented
+    /// Latin scripts apply regardless of capitalization. No break leaves
+    /// fewer than [`left_min`](Self::left_min) characters before it or
+    /// [`right_min`](Self::right_min) after it.
+    pub fn hyphenate(&self, word: &str) -> Vec<usize> {
+        let offsets: Vec<usize> = word.char_indices().map(|(i, _)| i).collect();
+        self.break_flags(word)
+            .iter()
+            .enumerate()
+            .filter(|&(_, &flag)| flag)
+            .map(|(i, _)| offsets[i + 1])
+            .collect()
+    }
+
+    /// For each character but the last, whether the word may break after it
+    fn break_flags(&self, word: &str) -> Vec<bool> {
+        let chars: Vec<char> = word.chars().map(lowercase).collect();
         let len = chars.len();
 
+        if let Some(breaks) = self.exceptions.get(&chars.iter().collect::<String>()) {
+            let mut result = vec![false; len.saturating_sub(1)];
+            for &b in breaks {
+                if b > 0 && b < len {
+                    result[b - 1] = true;
+                }
+            }
+            return result;
+        }
+
         if len < self.left_min + self.right_min {
             return vec![false; len.saturating_sub(1)];
         }
@@ -391,8 +477,7 @@ impl Hyphenator {
         // Apply patterns
         for i in 0..extended.len() {
             let mut node = &self.trie;
-            for j in i..extended.len() {
-                let c = extended[j];
+            for &c in &extended[i..] {
                 if let Some(child) = node.children.get(&c) {
                     node = child;
                     if let Some(ref pattern) = node.pattern {
@@ -413,13 +498,13 @@ impl Hyphenator {
         // Convert to hyphenation points (odd values = hyphen point)
         // Skip first and last boundaries, respect min values
         let mut result = vec![false; len.saturating_sub(1)];
-        for i in 0..result.len() {
+        for (i, point) in result.iter_mut().enumerate() {
             let val_idx = i + 2; // +1 for '.' prefix, +1 for offset
             if i >= self.left_min.saturating_sub(1)
                 && i < len.saturating_sub(self.right_min)
                 && values[val_idx] % 2 == 1
             {
-                result[i] = true;
+                *point = true;
             }
         }
 
@@ -428,7 +513,7 @@ impl Hyphenator {
 
     /// Hyphenate a word and return it with soft hyphens inserted
     pub fn hyphenate_with_hyphens(&self, word: &str, hyphen: &str) -> String {
-        let points = self.hyphenate(word);
+        let points = self.break_flags(word);
         let chars: Vec<char> = word.chars().collect();
 
         let mut result = String::with_capacity(
@@ -474,6 +559,11 @@ impl Hyphenator {
     }
 }
 
+/// Lowercase a character without changing the character count
+fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
 // Global stores
 pub static HYPHENATORS: LazyLock<HandleStore<Hyphenator>> = LazyLock::new(HandleStore::new);
 pub static REGISTERED_HYPHENATORS: LazyLock<std::sync::Mutex<HashMap<TextLanguage, Handle>>> =
@@ -496,6 +586,32 @@ pub extern "C" fn fz_new_empty_hyphenator(_ctx: Handle) -> Handle {
     HYPHENATORS.insert(Hyphenator::new())
 }
 
+/// Create a hyphenator from TeX `.pat` pattern data
+///
+/// @param data UTF-8 pattern data
+/// @param len  Size of data (or 0 for null-terminated)
+///
+/// Returns 0 if the data is null or not valid UTF-8
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_new_hyphenator_from_patterns(
+    _ctx: Handle,
+    data: *const c_char,
+    len: usize,
+) -> Handle {
+    if data.is_null() {
+        return 0;
+    }
+    let bytes = if len == 0 {
+        unsafe { CStr::from_ptr(data) }.to_bytes()
+    } else {
+        unsafe { std::slice::from_raw_parts(data as *const u8, len) }
+    };
+    match std::str::from_utf8(bytes) {
+        Ok(patterns) => HYPHENATORS.insert(Hyphenator::from_patterns(patterns)),
+        Err(_) => 0,
+    }
+}
+
 /// Drop/free a hyphenator
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_drop_hyphenator(_ctx: Handle, hyph: Handle) {
@@ -661,7 +777,7 @@ pub extern "C" fn fz_hyphenation_points(
     };
 
     let guard = h.lock().unwrap();
-    let hyph_points = guard.hyphenate(word_str);
+    let hyph_points = guard.break_flags(word_str);
 
     let copy_len = hyph_points.len().min(points_len);
     for i in 0..copy_len {
@@ -831,8 +947,34 @@ mod tests {
 
         // Test a known word
         let points = hyph.hyphenate("hyphenation");
-        // Should find some hyphenation points
-        assert!(!points.is_empty());
+        assert_eq!(points, [2, 6]);
+    }
+
+    #[test]
+    fn test_hyphenate_from_tex_patterns() {
+        let ctx = 1;
+        let patterns = CString::new(
+            "% Liang's example patterns\n\\patterns{\nhy3ph he2n hena4 hen5at 1na n2at\n\
+             1tio 2io o2n\n}\n\\hyphenation{ta-ble}\n",
+        )
+        .unwrap();
+        let hyph = fz_new_hyphenator_from_patterns(ctx, patterns.as_ptr(), 0);
+        assert_eq!(fz_hyphenator_pattern_count(ctx, hyph), 9);
+
+        let guard = HYPHENATORS.get(hyph).unwrap();
+        let h = guard.lock().unwrap();
+        assert_eq!(h.hyphenate("hyphenation"), [2, 6]);
+        assert_eq!(h.hyphenate("Hyphenation"), [2, 6]);
+        assert_eq!(
+            h.hyphenate_with_hyphens("hyphenation", "-"),
+            "hy-phen-ation"
+        );
+        assert_eq!(h.hyphenate("Table"), [2]);
+        // Offsets are in bytes, so the two-byte "é" moves both breaks by two
+        assert_eq!(h.hyphenate("éhyphenation"), [4, 8]);
+        drop(h);
+
+        fz_drop_hyphenator(ctx, hyph);
     }
 
     #[test]
@@ -894,7 +1036,7 @@ mod tests {
         let hyph = fz_new_hyphenator(ctx, TextLanguage::En as i32);
 
         assert_eq!(fz_hyphenator_left_min(ctx, hyph), 2);
-        assert_eq!(fz_hyphenator_right_min(ctx, hyph), 2);
+        assert_eq!(fz_hyphenator_right_min(ctx, hyph), 3);
 
         fz_hyphenator_set_left_min(ctx, hyph, 3);
         fz_hyphenator_set_right_min(ctx, hyph, 3);
@@ -954,10 +1096,10 @@ mod tests {
         assert!(points.is_empty());
 
         let points = hyph.hyphenate("an");
-        assert!(points.is_empty() || !points.iter().any(|&x| x));
+        assert!(points.is_empty());
 
         let points = hyph.hyphenate("the");
-        assert!(points.is_empty() || !points.iter().any(|&x| x));
+        assert!(points.is_empty());
     }
 
     #[test]
@@ -144,6 +144,14 @@ impl JsonValue {
         }
     }
 
+    /// Look up a key in an object
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        self.as_object()?
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
     /// Serialize to JSON string
     pub fn to_json_string(&self) -> String {
         let mut buf = String::new();
@@ -1102,6 +1110,296 @@ pub extern "C" fn fz_json_unescape_string(
     copy_len
 }
 
+// ============================================================================
+// FFI Functions - Document and Text Export
+// ============================================================================
+
+/// Copy `s` into a caller buffer, truncating if needed, and return the full
+/// length so callers can retry with a larger buffer
+fn copy_json_out(s: &str, output: *mut c_char, output_size: usize) -> usize {
+    if !output.is_null() && output_size > 0 {
+        let bytes = s.as_bytes();
+        let copy_len = bytes.len().min(output_size - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), output as *mut u8, copy_len);
+            *output.add(copy_len) = 0;
+        }
+    }
+    s.len()
+}
+
+/// A coordinate as a finite JSON number; infinite rects are clamped to the
+/// largest representable fz_rect and NaN becomes 0
+fn json_coord(v: f32) -> JsonValue {
+    use crate::ffi::geometry::{FZ_MAX_INF_RECT, FZ_MIN_INF_RECT};
+    let v = if v.is_nan() {
+        0.0
+    } else {
+        v.clamp(FZ_MIN_INF_RECT as f32, FZ_MAX_INF_RECT as f32)
+    };
+    JsonValue::Number((f64::from(v) * 1000.0).round() / 1000.0)
+}
+
+fn json_rect(x0: f32, y0: f32, x1: f32, y1: f32) -> JsonValue {
+    JsonValue::Array([x0, y0, x1, y1].into_iter().map(json_coord).collect())
+}
+
+fn json_object(fields: Vec<(&str, JsonValue)>) -> JsonValue {
+    JsonValue::Object(
+        fields
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+    )
+}
+
+/// Build the structure summary of a PDF document
+fn document_json(pdf: &crate::pdf::document::Document) -> JsonValue {
+    use crate::pdf::object::{Dict, Object};
+    use std::collections::{BTreeSet, HashSet};
+
+    let resolve_dict = |dict: &Dict, key: &str| -> Option<Dict> {
+        match pdf.resolve_key(dict, key) {
+            Ok(Some(Object::Dict(d))) => Some(d),
+            Ok(Some(Object::Stream { dict, .. })) => Some(dict),
+            _ => None,
+        }
+    };
+
+    let mut metadata = Vec::new();
+    if let Some(info) = resolve_dict(pdf.trailer(), "Info") {
+        let mut keys: Vec<_> = info.keys().collect();
+        keys.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        for key in keys {
+            let value = match pdf.resolve(&info[key]) {
+                Ok(Object::String(s)) => s.to_text(),
+                Ok(Object::Name(n)) => n.as_str().to_string(),
+                _ => continue,
+            };
+            metadata.push((key.as_str().to_string(), JsonValue::String(value)));
+        }
+    }
+
+    let mut all_fonts = BTreeSet::new();
+    let mut all_images = HashSet::new();
+    let mut inline_images = 0usize;
+    let mut pages = Vec::new();
+    let page_count = pdf.page_count().unwrap_or(0);
+
+    for index in 0..page_count {
+        let Ok(page) = pdf.page(index) else {
+            continue;
+        };
+        let resources = resolve_dict(page.dict(), "Resources").unwrap_or_default();
+
+        let mut fonts = BTreeSet::new();
+        for font in resolve_dict(&resources, "Font")
+            .unwrap_or_default()
+            .values()
+        {
+            if let Ok(Object::Dict(d)) = pdf.resolve(font) {
+                if let Some(name) = d.get("BaseFont").and_then(Object::as_name) {
+                    fonts.insert(name.as_str().to_string());
+                }
+            }
+        }
+
+        let mut images = 0usize;
+        for xobject in resolve_dict(&resources, "XObject")
+            .unwrap_or_default()
+            .values()
+        {
+            let is_image = matches!(
+                pdf.resolve(xobject),
+                Ok(Object::Stream { ref dict, .. })
+                    if dict.get("Subtype").and_then(Object::as_name).map(|n| n.as_str()) == Some("Image")
+            );
+            if !is_image {
+                continue;
+            }
+            images += 1;
+            match xobject.as_obj_ref() {
+                Some(r) => {
+                    all_images.insert(r);
+                }
+                None => inline_images += 1,
+            }
+        }
+
+        let media = page.media_box();
+        let crop = page.crop_box();
+        pages.push(json_object(vec![
+            ("index", JsonValue::Number(index as f64)),
+            (
+                "mediabox",
+                json_rect(media.x0, media.y0, media.x1, media.y1),
+            ),
+            ("cropbox", json_rect(crop.x0, crop.y0, crop.x1, crop.y1)),
+            ("rotation", JsonValue::Number(f64::from(page.rotation()))),
+            (
+                "fonts",
+                JsonValue::Array(fonts.iter().cloned().map(JsonValue::String).collect()),
+            ),
+            ("images", JsonValue::Number(images as f64)),
+        ]));
+        all_fonts.extend(fonts);
+    }
+
+    json_object(vec![
+        ("page_count", JsonValue::Number(page_count as f64)),
+        ("metadata", JsonValue::Object(metadata)),
+        (
+            "fonts",
+            JsonValue::Array(all_fonts.into_iter().map(JsonValue::String).collect()),
+        ),
+        (
+            "image_count",
+            JsonValue::Number((all_images.len() + inline_images) as f64),
+        ),
+        ("pages", JsonValue::Array(pages)),
+    ])
+}
+
+/// Serialize a document's structure as JSON
+///
+/// Schema:
+/// `{"page_count": n, "metadata": {key: string}, "fonts": [name],
+///   "image_count": n, "pages": [{"index", "mediabox": [x0,y0,x1,y1],
+///   "cropbox": [..], "rotation", "fonts": [name], "images": n}]}`
+///
+/// Fonts are BaseFont names; images are counted once per distinct object.
+/// Returns the full JSON length (excluding the terminator), which may exceed
+/// `output_size` when the output was truncated, or 0 for an invalid document.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_document_to_json(
+    _ctx: Handle,
+    doc: Handle,
+    output: *mut c_char,
+    output_size: usize,
+) -> usize {
+    let Some(pdf) = crate::ffi::document::open_pdf(doc) else {
+        return 0;
+    };
+
+    copy_json_out(&document_json(&pdf).to_json_string(), output, output_size)
+}
+
+/// Build the JSON tree of an extracted text page
+fn stext_json(page: &crate::ffi::stext::StextPage) -> JsonValue {
+    use crate::ffi::stext::StextBlockType;
+
+    let blocks = page
+        .blocks
+        .iter()
+        .map(|block| {
+            let b = block.bbox;
+            let mut fields = vec![
+                (
+                    "type",
+                    JsonValue::String(
+                        match block.block_type {
+                            StextBlockType::Text => "text",
+                            StextBlockType::Image => "image",
+                            StextBlockType::Struct => "struct",
+                            StextBlockType::Vector => "vector",
+                            StextBlockType::Grid => "grid",
+                        }
+                        .to_string(),
+                    ),
+                ),
+                ("bbox", json_rect(b.x0, b.y0, b.x1, b.y1)),
+            ];
+            if block.block_type == StextBlockType::Text {
+                let lines = block
+                    .lines
+                    .iter()
+                    .map(|line| {
+                        let l = line.bbox;
+                        let chars = line
+                            .chars
+                            .iter()
+                            .map(|ch| {
+                                let q = ch.quad;
+                                json_object(vec![
+                                    (
+                                        "c",
+                                        JsonValue::String(
+                                            char::from_u32(ch.c as u32)
+                                                .unwrap_or('\u{FFFD}')
+                                                .to_string(),
+                                        ),
+                                    ),
+                                    (
+                                        "origin",
+                                        JsonValue::Array(vec![
+                                            json_coord(ch.origin.x),
+                                            json_coord(ch.origin.y),
+                                        ]),
+                                    ),
+                                    ("size", json_coord(ch.size)),
+                                    (
+                                        "quad",
+                                        JsonValue::Array(
+                                            [
+                                                q.ul_x, q.ul_y, q.ur_x, q.ur_y, q.ll_x, q.ll_y,
+                                                q.lr_x, q.lr_y,
+                                            ]
+                                            .into_iter()
+                                            .map(json_coord)
+                                            .collect(),
+                                        ),
+                                    ),
+                                ])
+                            })
+                            .collect();
+                        json_object(vec![
+                            ("wmode", JsonValue::Number(f64::from(line.wmode))),
+                            ("bbox", json_rect(l.x0, l.y0, l.x1, l.y1)),
+                            ("text", JsonValue::String(line.text())),
+                            ("chars", JsonValue::Array(chars)),
+                        ])
+                    })
+                    .collect();
+                fields.push(("lines", JsonValue::Array(lines)));
+            }
+            json_object(fields)
+        })
+        .collect();
+
+    let m = page.mediabox;
+    json_object(vec![
+        ("mediabox", json_rect(m.x0, m.y0, m.x1, m.y1)),
+        ("blocks", JsonValue::Array(blocks)),
+    ])
+}
+
+/// Serialize an extracted text page as JSON
+///
+/// Schema:
+/// `{"mediabox": [x0,y0,x1,y1], "blocks": [{"type", "bbox",
+///   "lines": [{"wmode", "bbox", "text", "chars": [{"c", "origin": [x,y],
+///   "size", "quad": [ulx,uly,urx,ury,llx,lly,lrx,lry]}]}]}]}`
+///
+/// Only text blocks carry `lines`. Returns the full JSON length as
+/// [`fz_document_to_json`] does, or 0 for an invalid page.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_stext_to_json(
+    _ctx: Handle,
+    page: Handle,
+    output: *mut c_char,
+    output_size: usize,
+) -> usize {
+    let Some(arc) = crate::ffi::stext::STEXT_PAGES.get(page) else {
+        return 0;
+    };
+    let json = match arc.lock() {
+        Ok(p) => stext_json(&p).to_json_string(),
+        Err(_) => return 0,
+    };
+
+    copy_json_out(&json, output, output_size)
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -1109,6 +1407,7 @@ pub extern "C" fn fz_json_unescape_string(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::PdfBuilder;
 
     #[test]
     fn test_json_type_enum() {
@@ -1364,4 +1663,86 @@ mod tests {
 
         fz_drop_json(ctx, json);
     }
+
+    #[test]
+    fn test_stext_to_json() {
+        use crate::ffi::stext::*;
+        let ctx = 1;
+        let page = fz_new_stext_page(ctx, 0.0, 0.0, 612.0, 792.0);
+        let block = fz_add_stext_block(ctx, page, 0.0, 0.0, 100.0, f32::INFINITY);
+        let line = fz_add_stext_line(ctx, page, block, 0.0, 0.0, 100.0, 12.0);
+        for (i, c) in "Hi \"x\"".chars().enumerate() {
+            fz_add_stext_char(ctx, page, block, line, c as i32, (i * 8) as f32, 12.0, 12.0);
+        }
+
+        // A null buffer reports the size needed
+        let len = fz_stext_to_json(ctx, page, ptr::null_mut(), 0);
+        assert!(len > 0);
+        let mut output = vec![0u8; len + 1];
+        let written = fz_stext_to_json(ctx, page, output.as_mut_ptr() as *mut c_char, output.len());
+        assert_eq!(written, len);
+        fz_drop_stext_page(ctx, page);
+
+        let text = std::str::from_utf8(&output[..len]).unwrap();
+        assert!(!text.contains("inf") && !text.contains("NaN"));
+        let root = JsonParser::new(text).parse().unwrap();
+        let blocks = root.get("blocks").and_then(JsonValue::as_array).unwrap();
+        let lines = blocks[0]
+            .get("lines")
+            .and_then(JsonValue::as_array)
+            .unwrap();
+        let chars = lines[0].get("chars").and_then(JsonValue::as_array).unwrap();
+        assert_eq!(chars.len(), 6);
+        assert_eq!(
+            lines[0].get("text").and_then(JsonValue::as_str),
+            Some("Hi \"x\"")
+        );
+        let bbox = blocks[0].get("bbox").and_then(JsonValue::as_array).unwrap();
+        assert!(bbox[3].as_number().unwrap().is_finite());
+    }
+
+    #[test]
+    fn test_document_to_json() {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 /MediaBox [0 0 612 792] >>",
+            "<< /Type /Page /Parent 2 0 R /Resources << /Font << /F1 5 0 R >> /XObject << /Im1 6 0 R >> >> >>",
+            "<< /Type /Page /Parent 2 0 R /CropBox [10 10 200 300] /Resources << /XObject << /Im1 6 0 R >> >> >>",
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>",
+            "<< /Type /XObject /Subtype /Image /Width 1 /Height 1 /Length 1 >>\nstream\n\x00\nendstream",
+            "<< /Title (Report) /Author (Ann) >>",
+        ];
+        let data = PdfBuilder::new(&objects).trailer("/Info 7 0 R").build();
+        let doc = crate::ffi::DOCUMENTS.insert(crate::ffi::document::Document::new(data));
+
+        let mut output = vec![0u8; 4096];
+        let len = fz_document_to_json(1, doc, output.as_mut_ptr() as *mut c_char, output.len());
+        crate::ffi::DOCUMENTS.remove(doc);
+        assert!(len > 0 && len < output.len());
+
+        let root = JsonParser::new(std::str::from_utf8(&output[..len]).unwrap())
+            .parse()
+            .unwrap();
+        assert_eq!(
+            root.get("page_count").and_then(JsonValue::as_number),
+            Some(2.0)
+        );
+        assert_eq!(
+            root.get("image_count").and_then(JsonValue::as_number),
+            Some(1.0)
+        );
+        let metadata = root.get("metadata").unwrap();
+        assert_eq!(
+            metadata.get("Title").and_then(JsonValue::as_str),
+            Some("Report")
+        );
+        let fonts = root.get("fonts").and_then(JsonValue::as_array).unwrap();
+        assert_eq!(fonts[0].as_str(), Some("Helvetica"));
+        let pages = root.get("pages").and_then(JsonValue::as_array).unwrap();
+        let crop = pages[1]
+            .get("cropbox")
+            .and_then(JsonValue::as_array)
+            .unwrap();
+        assert_eq!(crop[2].as_number(), Some(200.0));
+    }
 }
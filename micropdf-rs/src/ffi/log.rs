@@ -93,6 +93,7 @@ struct LogConfig {
 
 // Global logger configuration
 static LOG_CONFIG: LazyLock<RwLock<LogConfig>> = LazyLock::new(|| {
+    crate::fitz::warning::set_warning_sink(Some(warn));
     RwLock::new(LogConfig {
         level: LogLevel::Info,
         include_timestamp: true,
@@ -174,10 +175,11 @@ fn do_log(
 
     let formatted = format_log_message(level, module, message, file, line, &config);
 
-    // Call callback if set
+    // Call callback if set; it receives the level, category and raw message
+    // so it can do its own formatting
     if let Some(cb) = config.callback {
-        let module_cstr = module.map(|m| CString::new(m).unwrap());
-        let message_cstr = CString::new(formatted.as_str()).unwrap();
+        let module_cstr = module.and_then(|m| CString::new(m).ok());
+        let message_cstr = CString::new(message).unwrap_or_default();
 
         cb(
             config.callback_user as *mut c_void,
@@ -219,6 +221,41 @@ fn do_log(
     }
 }
 
+fn record_warning(message: &str) {
+    // Store for fz_caught_message
+    if let Ok(mut last) = LAST_WARNING.lock() {
+        *last = CString::new(message).ok();
+    }
+
+    // Call warning callback if set
+    let config = LOG_CONFIG.read().unwrap();
+    if let Some(cb) = config.warning_callback {
+        let msg_cstr = CString::new(message).unwrap_or_default();
+        cb(config.warning_user as *mut c_void, msg_cstr.as_ptr());
+    }
+}
+
+/// Emit a recoverable warning; installed as the library's
+/// [warning sink](crate::fitz::warning) once logging is set up.
+///
+/// `category` names the subsystem (e.g. `"parser"`, `"font"`) and is passed
+/// to the log callback as the module string. Without a callback the message
+/// goes to stderr.
+pub fn warn(category: &str, message: &str) {
+    record_warning(message);
+    do_log(LogLevel::Warn, Some(category), message, None, None);
+}
+
+/// Emit an error from inside the crate; see [`warn`].
+pub fn error(category: &str, message: &str) {
+    do_log(LogLevel::Error, Some(category), message, None, None);
+}
+
+/// Route the library's warnings through the log configuration
+pub(crate) fn install() {
+    LazyLock::force(&LOG_CONFIG);
+}
+
 // ============================================================================
 // FFI Functions - Configuration
 // ============================================================================
@@ -430,20 +467,7 @@ pub extern "C" fn fz_log_warn(_ctx: Handle, message: *const c_char) {
     let msg = unsafe { CStr::from_ptr(message) };
     let msg = msg.to_str().unwrap_or("");
 
-    // Store for fz_caught_message
-    if let Ok(mut last) = LAST_WARNING.lock() {
-        *last = CString::new(msg).ok();
-    }
-
-    // Call warning callback if set
-    {
-        let config = LOG_CONFIG.read().unwrap();
-        if let Some(cb) = config.warning_callback {
-            let msg_cstr = CString::new(msg).unwrap();
-            cb(config.warning_user as *mut c_void, msg_cstr.as_ptr());
-        }
-    }
-
+    record_warning(msg);
     do_log(LogLevel::Warn, None, msg, None, None);
 }
 
@@ -625,6 +649,14 @@ pub extern "C" fn fz_parse_log_level(name: *const c_char) -> i32 {
 mod tests {
     use super::*;
 
+    // The log configuration is process-wide, so tests that change it run one
+    // at a time
+    static CONFIG_LOCK: Mutex<()> = Mutex::new(());
+
+    fn lock_config() -> std::sync::MutexGuard<'static, ()> {
+        CONFIG_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
     fn reset_log_config() {
         let mut config = LOG_CONFIG.write().unwrap();
         *config = LogConfig {
@@ -649,6 +681,7 @@ mod tests {
     #[test]
     #[cfg_attr(tarpaulin, ignore)] // Global state conflicts under coverage instrumentation
     fn test_set_get_level() {
+        let _guard = lock_config();
         reset_log_config();
         let ctx = 1;
 
@@ -664,6 +697,7 @@ mod tests {
     #[test]
     #[cfg_attr(tarpaulin, ignore)] // Global state conflicts under coverage instrumentation
     fn test_module_level() {
+        let _guard = lock_config();
         reset_log_config();
         let ctx = 1;
 
@@ -687,6 +721,7 @@ mod tests {
 
     #[test]
     fn test_log_basic() {
+        let _guard = lock_config();
         reset_log_config();
         let ctx = 1;
 
@@ -699,6 +734,7 @@ mod tests {
 
     #[test]
     fn test_log_module() {
+        let _guard = lock_config();
         reset_log_config();
         let ctx = 1;
 
@@ -712,6 +748,7 @@ mod tests {
 
     #[test]
     fn test_log_levels() {
+        let _guard = lock_config();
         reset_log_config();
         let ctx = 1;
 
@@ -729,6 +766,7 @@ mod tests {
     #[test]
     #[cfg_attr(tarpaulin, ignore)] // Global state conflicts under coverage instrumentation
     fn test_log_buffer() {
+        let _guard = lock_config();
         // Note: Due to global state shared with parallel tests, we only test buffer APIs
         // and don't assert on exact counts
         let ctx = 1;
@@ -760,6 +798,7 @@ mod tests {
 
     #[test]
     fn test_log_file() {
+        let _guard = lock_config();
         reset_log_config();
         let ctx = 1;
 
@@ -775,6 +814,7 @@ mod tests {
 
     #[test]
     fn test_timestamp_location() {
+        let _guard = lock_config();
         reset_log_config();
         let ctx = 1;
 
@@ -821,6 +861,7 @@ mod tests {
 
     #[test]
     fn test_warning_callback() {
+        let _guard = lock_config();
         reset_log_config();
         let ctx = 1;
 
@@ -848,6 +889,7 @@ mod tests {
     #[test]
     #[cfg_attr(tarpaulin, ignore)] // Global state conflicts under coverage instrumentation
     fn test_last_warning() {
+        let _guard = lock_config();
         reset_log_config();
         let ctx = 1;
 
@@ -907,4 +949,60 @@ mod tests {
         assert_eq!(LogLevel::from_i32(5), LogLevel::Trace);
         assert_eq!(LogLevel::from_i32(99), LogLevel::Info); // Invalid falls back to Info (default)
     }
+
+    #[test]
+    fn test_structured_parse_warning() {
+        let _guard = lock_config();
+        reset_log_config();
+        let ctx = 1;
+
+        type Records = Mutex<Vec<(i32, String, String)>>;
+
+        extern "C" fn record(
+            user: *mut c_void,
+            level: i32,
+            module: *const c_char,
+            message: *const c_char,
+        ) {
+            let records = unsafe { &*(user as *const Records) };
+            let module = unsafe { CStr::from_ptr(module) }
+                .to_string_lossy()
+                .into_owned();
+            let message = unsafe { CStr::from_ptr(message) }
+                .to_string_lossy()
+                .into_owned();
+            records.lock().unwrap().push((level, module, message));
+        }
+
+        let records: Records = Mutex::new(Vec::new());
+        fz_set_log_callback(ctx, Some(record), &records as *const Records as *mut c_void);
+
+        // /Length overshoots the data, forcing the parser to scan for endstream
+        let data = b"1 0 obj\n<< /Length 99 >>\nstream\nabc\nendstream\nendobj\n";
+        let (_, object) = crate::pdf::parser::parse_indirect_object_at(data, 0).unwrap();
+        assert!(
+            matches!(object, crate::pdf::object::Object::Stream { ref data, .. } if data == b"abc")
+        );
+        // Hex data cut off before its `>` marker
+        let decoded = crate::pdf::filter::decode_ascii_hex(b"4142").unwrap();
+        assert_eq!(decoded, b"AB");
+
+        fz_set_log_callback(ctx, None, ptr::null_mut());
+
+        let records = records.lock().unwrap();
+        let (level, module, message) = records
+            .iter()
+            .find(|(_, module, _)| module == "parser")
+            .expect("parser warning delivered");
+        assert_eq!(*level, LogLevel::Warn as i32);
+        assert_eq!(module, "parser");
+        assert!(message.contains("Length"));
+        assert!(
+            records
+                .iter()
+                .any(|(_, module, message)| module == "filter" && message.contains("ASCIIHex"))
+        );
+
+        reset_log_config();
+    }
 }
@@ -56,6 +56,34 @@ pub extern "C" fn fz_new_output_with_buffer(_ctx: Handle, buf: Handle) -> Handle
     0
 }
 
+/// Create output that accumulates its bytes in memory
+///
+/// Read them back with `fz_memory_output_buffer`.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_new_output_with_memory(_ctx: Handle) -> Handle {
+    OUTPUTS.insert(Output::memory())
+}
+
+/// Copy the bytes written so far to a memory output into a new buffer
+///
+/// The output stays open. Returns 0 if `out` is not a memory output.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_memory_output_buffer(_ctx: Handle, out: Handle) -> Handle {
+    use super::BUFFERS;
+    use super::buffer::Buffer as FfiBuffer;
+
+    let Some(output_arc) = OUTPUTS.get(out) else {
+        return 0;
+    };
+    let Ok(guard) = output_arc.lock() else {
+        return 0;
+    };
+    match guard.contents() {
+        Some(bytes) => BUFFERS.insert(FfiBuffer::from_data(bytes)),
+        None => 0,
+    }
+}
+
 /// Keep (increment ref) output
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_keep_output(_ctx: Handle, out: Handle) -> Handle {
@@ -506,6 +534,60 @@ pub extern "C" fn fz_write_bits_sync(_ctx: Handle, out: Handle) {
     }
 }
 
+/// Write a real number in PDF syntax (no exponent, no trailing zeros)
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_write_pdf_real(_ctx: Handle, out: Handle, x: f32) {
+    if let Some(output_arc) = OUTPUTS.get(out) {
+        if let Ok(mut guard) = output_arc.lock() {
+            let _ = guard.write_f32(x);
+        }
+    }
+}
+
+/// Write a PDF name with its leading `/`, escaping bytes that need `#xx`
+///
+/// # Safety
+/// Caller must ensure `name` is a valid null-terminated C string.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_write_pdf_name(_ctx: Handle, out: Handle, name: *const c_char) {
+    if name.is_null() {
+        return;
+    }
+
+    // SAFETY: Caller guarantees name is a valid null-terminated C string
+    let c_str = unsafe { std::ffi::CStr::from_ptr(name) };
+    if let Ok(rust_str) = c_str.to_str() {
+        if let Some(output_arc) = OUTPUTS.get(out) {
+            if let Ok(mut guard) = output_arc.lock() {
+                let _ = guard.write_pdf_name(rust_str);
+            }
+        }
+    }
+}
+
+/// Write `size` bytes as an escaped PDF string
+///
+/// # Safety
+/// Caller must ensure `data` points to valid memory of at least `size` bytes.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_write_pdf_string(_ctx: Handle, out: Handle, data: *const u8, size: usize) {
+    if data.is_null() && size > 0 {
+        return;
+    }
+
+    let bytes = if size == 0 {
+        &[][..]
+    } else {
+        // SAFETY: Caller guarantees data points to valid memory of size bytes
+        unsafe { std::slice::from_raw_parts(data, size) }
+    };
+    if let Some(output_arc) = OUTPUTS.get(out) {
+        if let Ok(mut guard) = output_arc.lock() {
+            let _ = guard.write_pdf_string(bytes);
+        }
+    }
+}
+
 // POSIX-style whence constants for fz_seek_output
 pub const SEEK_SET: i32 = 0;
 pub const SEEK_CUR: i32 = 1;
@@ -801,4 +883,53 @@ mod tests {
         assert_eq!(content.len(), 1);
         assert_eq!(content[0], 0xFF);
     }
+
+    #[test]
+    fn test_output_pdf_syntax() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path();
+        let c_path = CString::new(path.to_str().unwrap()).unwrap();
+
+        let ctx = 0;
+        let out = fz_new_output_with_path(ctx, c_path.as_ptr(), 0);
+        fz_write_pdf_name(ctx, out, c"Font Name".as_ptr());
+        fz_write_byte(ctx, out, b' ');
+        fz_write_pdf_real(ctx, out, 0.5);
+        fz_write_byte(ctx, out, b' ');
+        fz_write_pdf_real(ctx, out, 1.0);
+        fz_write_byte(ctx, out, b' ');
+        let text = b"(x)";
+        fz_write_pdf_string(ctx, out, text.as_ptr(), text.len());
+        fz_close_output(ctx, out);
+        fz_drop_output(ctx, out);
+
+        let content = std::fs::read_to_string(path).unwrap();
+        assert_eq!(content, "/Font#20Name 0.5 1 (\\(x\\))");
+    }
+
+    #[test]
+    fn test_memory_output_buffer() {
+        use crate::ffi::BUFFERS;
+
+        let ctx = 0;
+        let out = fz_new_output_with_memory(ctx);
+        assert_ne!(out, 0);
+        fz_write_string(ctx, out, c"%PDF-1.7\n".as_ptr());
+        fz_write_int32_be(ctx, out, 0x01020304);
+
+        let buf = fz_memory_output_buffer(ctx, out);
+        assert_ne!(buf, 0);
+        let bytes = BUFFERS.get(buf).unwrap().lock().unwrap().data().to_vec();
+        assert_eq!(bytes, b"%PDF-1.7\n\x01\x02\x03\x04");
+
+        // The output is still usable after reading back
+        fz_write_string(ctx, out, c"more".as_ptr());
+        let buf2 = fz_memory_output_buffer(ctx, out);
+        let len = BUFFERS.get(buf2).unwrap().lock().unwrap().data().len();
+        assert_eq!(len, bytes.len() + 4);
+
+        BUFFERS.remove(buf);
+        BUFFERS.remove(buf2);
+        fz_drop_output(ctx, out);
+    }
 }
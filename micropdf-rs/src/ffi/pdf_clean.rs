@@ -1,8 +1,14 @@
 //! PDF Clean/Optimization FFI Module
 //!
-//! Provides PDF optimization, cleaning, linearization, and page rearrangement.
+//! Provides PDF optimization, cleaning, sanitization, linearization, and page
+//! rearrangement.
 
 use crate::ffi::Handle;
+use crate::fitz::error::{Error, Result};
+use crate::pdf::document::Document;
+use crate::pdf::object::{Dict, ObjRef, Object};
+use crate::pdf::write;
+use std::collections::BTreeMap;
 use std::ffi::{CStr, CString, c_char};
 use std::ptr;
 
@@ -344,6 +350,246 @@ impl CleanOptions {
     }
 }
 
+// ============================================================================
+// Sanitize Flags
+// ============================================================================
+
+/// Remove JavaScript actions and the document-level JavaScript name tree
+pub const PDF_SANITIZE_JAVASCRIPT: i32 = 1;
+/// Remove the catalog's /OpenAction
+pub const PDF_SANITIZE_OPEN_ACTION: i32 = 2;
+/// Remove /AA additional (automatic) actions
+pub const PDF_SANITIZE_AUTO_ACTIONS: i32 = 4;
+/// Remove embedded files and file attachment annotations
+pub const PDF_SANITIZE_EMBEDDED_FILES: i32 = 8;
+/// Remove private (/PieceInfo) and non-standard document info metadata
+pub const PDF_SANITIZE_METADATA: i32 = 16;
+/// All sanitize passes
+pub const PDF_SANITIZE_ALL: i32 = 31;
+
+/// Document info keys defined by the specification
+const STANDARD_INFO_KEYS: [&str; 9] = [
+    "Title",
+    "Author",
+    "Subject",
+    "Keywords",
+    "Creator",
+    "Producer",
+    "CreationDate",
+    "ModDate",
+    "Trapped",
+];
+
+/// Whether `action` is a JavaScript action or a `javascript:` URI
+fn is_javascript_action(doc: &Document, action: &Object) -> bool {
+    let Ok(Object::Dict(dict)) = doc.resolve(action) else {
+        return false;
+    };
+    match dict.get("S").and_then(Object::as_name).map(|n| n.as_str()) {
+        Some("JavaScript") => true,
+        Some("URI") => dict
+            .get("URI")
+            .and_then(Object::as_string)
+            .is_some_and(|uri| {
+                let uri = uri.as_bytes();
+                uri.len() >= 11 && uri[..11].eq_ignore_ascii_case(b"javascript:")
+            }),
+        _ => false,
+    }
+}
+
+/// Apply the sanitize passes in `flags` to a dictionary and the direct
+/// objects inside it, adding the categories removed to `found`
+fn sanitize_dict(doc: &Document, dict: &mut Dict, flags: i32, found: &mut i32) -> bool {
+    let enabled = |category: i32| flags & category != 0;
+    let mut removed = 0;
+    let mut remove = |dict: &mut Dict, key: &str, category: i32| {
+        if dict.remove(key).is_some() {
+            removed |= category;
+        }
+    };
+
+    if enabled(PDF_SANITIZE_OPEN_ACTION) {
+        remove(dict, "OpenAction", PDF_SANITIZE_OPEN_ACTION);
+    }
+    if enabled(PDF_SANITIZE_AUTO_ACTIONS) {
+        remove(dict, "AA", PDF_SANITIZE_AUTO_ACTIONS);
+    }
+    if enabled(PDF_SANITIZE_METADATA) {
+        remove(dict, "PieceInfo", PDF_SANITIZE_METADATA);
+    }
+    if enabled(PDF_SANITIZE_EMBEDDED_FILES) {
+        // Embedded file streams hang off file specifications; associated
+        // files are listed in /AF
+        remove(dict, "EF", PDF_SANITIZE_EMBEDDED_FILES);
+        remove(dict, "AF", PDF_SANITIZE_EMBEDDED_FILES);
+    }
+    if enabled(PDF_SANITIZE_JAVASCRIPT) {
+        for key in ["A", "OpenAction", "Next"] {
+            if dict.get(key).is_some_and(|a| is_javascript_action(doc, a)) {
+                remove(dict, key, PDF_SANITIZE_JAVASCRIPT);
+            }
+        }
+        if let Some(Object::Array(actions)) = dict.get_mut("Next") {
+            let before = actions.len();
+            actions.retain(|a| !is_javascript_action(doc, a));
+            if actions.len() != before {
+                removed |= PDF_SANITIZE_JAVASCRIPT;
+            }
+        }
+        if let Some(Ok(Object::Dict(mut aa))) = dict.get("AA").map(|aa| doc.resolve(aa)) {
+            let before = aa.len();
+            aa.retain(|_, action| !is_javascript_action(doc, action));
+            if aa.len() != before {
+                dict.insert("AA".into(), Object::Dict(aa));
+                removed |= PDF_SANITIZE_JAVASCRIPT;
+            }
+        }
+    }
+    if enabled(PDF_SANITIZE_EMBEDDED_FILES) {
+        if let Some(Ok(Object::Array(mut annots))) = dict.get("Annots").map(|a| doc.resolve(a)) {
+            let before = annots.len();
+            annots.retain(|annot| {
+                !matches!(doc.resolve(annot), Ok(Object::Dict(a))
+                    if a.get("Subtype").and_then(Object::as_name).map(|n| n.as_str())
+                        == Some("FileAttachment"))
+            });
+            if annots.len() != before {
+                dict.insert("Annots".into(), Object::Array(annots));
+                removed |= PDF_SANITIZE_EMBEDDED_FILES;
+            }
+        }
+    }
+
+    *found |= removed;
+    let mut changed = removed != 0;
+    for value in dict.values_mut() {
+        changed |= sanitize_value(doc, value, flags, found);
+    }
+    changed
+}
+
+/// Sanitize the dictionaries directly inside `obj`
+fn sanitize_value(doc: &Document, obj: &mut Object, flags: i32, found: &mut i32) -> bool {
+    match obj {
+        Object::Dict(dict) | Object::Stream { dict, .. } => sanitize_dict(doc, dict, flags, found),
+        Object::Array(items) => items.iter_mut().fold(false, |changed, item| {
+            sanitize_value(doc, item, flags, found) | changed
+        }),
+        _ => false,
+    }
+}
+
+/// Remove the name trees named by `keys` from the catalog's /Names
+fn strip_names(
+    doc: &Document,
+    catalog: &mut Dict,
+    keys: &[&str],
+    changes: &mut BTreeMap<ObjRef, Object>,
+) -> Result<bool> {
+    let names_ref = catalog.get("Names").and_then(Object::as_obj_ref);
+    let mut names = match names_ref {
+        Some(r) => match changes.get(&r) {
+            Some(obj) => obj.clone(),
+            None => doc.load_object(r)?,
+        },
+        None => catalog.get("Names").cloned().unwrap_or_default(),
+    };
+    let Object::Dict(dict) = &mut names else {
+        return Ok(false);
+    };
+    let before = dict.len();
+    for key in keys {
+        dict.remove(*key);
+    }
+    if dict.len() == before {
+        return Ok(false);
+    }
+    match names_ref {
+        Some(r) => {
+            changes.insert(r, names);
+        }
+        None => {
+            catalog.insert("Names".into(), names);
+        }
+    }
+    Ok(true)
+}
+
+/// Run the sanitize passes in `flags` over every object of `doc`
+///
+/// Returns the changed objects and the `PDF_SANITIZE_*` categories that
+/// were found and removed. The page tree is left intact: only actions,
+/// name tree entries, annotations and metadata keys are removed.
+fn sanitize_document(doc: &Document, flags: i32) -> Result<(BTreeMap<ObjRef, Object>, i32)> {
+    let mut changes = BTreeMap::new();
+    let mut found = 0;
+    for obj_ref in doc.object_refs() {
+        let mut obj = doc.load_object(obj_ref)?;
+        if sanitize_value(doc, &mut obj, flags, &mut found) {
+            changes.insert(obj_ref, obj);
+        }
+    }
+
+    let Some(root) = doc.trailer().get("Root").and_then(Object::as_obj_ref) else {
+        return Err(Error::format("trailer without /Root"));
+    };
+    let mut catalog = match changes.get(&root) {
+        Some(obj) => obj.clone(),
+        None => doc.load_object(root)?,
+    };
+    if let Object::Dict(dict) = &mut catalog {
+        let mut catalog_changed = false;
+        if flags & PDF_SANITIZE_JAVASCRIPT != 0
+            && strip_names(doc, dict, &["JavaScript"], &mut changes)?
+        {
+            found |= PDF_SANITIZE_JAVASCRIPT;
+            catalog_changed = true;
+        }
+        if flags & PDF_SANITIZE_EMBEDDED_FILES != 0
+            && strip_names(doc, dict, &["EmbeddedFiles"], &mut changes)?
+        {
+            found |= PDF_SANITIZE_EMBEDDED_FILES;
+            catalog_changed = true;
+        }
+        // A names dictionary held directly in the catalog was edited in place
+        if catalog_changed && dict.get("Names").and_then(Object::as_obj_ref).is_none() {
+            changes.insert(root, catalog);
+        }
+    }
+
+    if flags & PDF_SANITIZE_METADATA != 0 {
+        if let Some(info_ref) = doc.trailer().get("Info").and_then(Object::as_obj_ref) {
+            let mut info = match changes.get(&info_ref) {
+                Some(obj) => obj.clone(),
+                None => doc.load_object(info_ref)?,
+            };
+            if let Object::Dict(dict) = &mut info {
+                let before = dict.len();
+                dict.retain(|key, _| STANDARD_INFO_KEYS.contains(&key.as_str()));
+                if dict.len() != before {
+                    found |= PDF_SANITIZE_METADATA;
+                    changes.insert(info_ref, info);
+                }
+            }
+        }
+    }
+    Ok((changes, found))
+}
+
+/// Sanitize the PDF at `infile` and write the result to `outfile`
+fn sanitize_file(infile: &str, outfile: &str, flags: i32) -> Result<i32> {
+    let doc = Document::open_bytes(std::fs::read(infile)?)?;
+    if doc.needs_password() {
+        return Err(Error::argument("document requires a password"));
+    }
+    let (changes, found) = sanitize_document(&doc, flags)?;
+    let mut out = Vec::new();
+    write::rewrite_document(&doc, &changes, &mut out)?;
+    std::fs::write(outfile, out)?;
+    Ok(found)
+}
+
 // ============================================================================
 // FFI Functions - Default Options
 // ============================================================================
@@ -509,6 +755,30 @@ pub extern "C" fn pdf_clean_file(
     // In a full implementation, this would clean the PDF
 }
 
+/// Sanitize a PDF file for safe intake.
+///
+/// `flags` selects the `PDF_SANITIZE_*` passes to run. The output is a
+/// complete rewrite without the removed objects. Returns the categories
+/// that were found and removed, or -1 on error.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_sanitize_file(
+    _ctx: ContextHandle,
+    infile: *const c_char,
+    outfile: *const c_char,
+    flags: i32,
+) -> i32 {
+    if infile.is_null() || outfile.is_null() {
+        return -1;
+    }
+    let (Ok(infile), Ok(outfile)) = (
+        unsafe { CStr::from_ptr(infile) }.to_str(),
+        unsafe { CStr::from_ptr(outfile) }.to_str(),
+    ) else {
+        return -1;
+    };
+    sanitize_file(infile, outfile, flags).unwrap_or(-1)
+}
+
 /// Rearrange pages in document.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_rearrange_pages(
@@ -691,6 +961,56 @@ pub extern "C" fn pdf_clean_free_string(_ctx: ContextHandle, s: *mut c_char) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
+
+    /// A one-page PDF that runs JavaScript when opened and has a page
+    /// open action
+    fn scripted_pdf() -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R /OpenAction 5 0 R \
+             /Names << /JavaScript << /Names [(init) 5 0 R] >> >> >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R \
+             /AA << /O << /S /Named /N /NextPage >> >> >>",
+            "<< /Length 0 >>\nstream\n\nendstream",
+            "<< /S /JavaScript /JS (app.alert\\(1\\)) >>",
+        ];
+        build_pdf(&objects)
+    }
+
+    #[test]
+    fn test_sanitize_file() {
+        let input = tempfile::NamedTempFile::new().unwrap();
+        let output = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(input.path(), scripted_pdf()).unwrap();
+        let infile = CString::new(input.path().to_str().unwrap()).unwrap();
+        let outfile = CString::new(output.path().to_str().unwrap()).unwrap();
+
+        // Passes run independently
+        let found = pdf_sanitize_file(
+            0,
+            infile.as_ptr(),
+            outfile.as_ptr(),
+            PDF_SANITIZE_AUTO_ACTIONS,
+        );
+        assert_eq!(found, PDF_SANITIZE_AUTO_ACTIONS);
+        let data = std::fs::read(output.path()).unwrap();
+        assert!(crate::pdf::parser::find_bytes(&data, b"/JavaScript", 0).is_some());
+
+        let found = pdf_sanitize_file(0, infile.as_ptr(), outfile.as_ptr(), PDF_SANITIZE_ALL);
+        assert_eq!(
+            found,
+            PDF_SANITIZE_JAVASCRIPT | PDF_SANITIZE_OPEN_ACTION | PDF_SANITIZE_AUTO_ACTIONS
+        );
+        let data = std::fs::read(output.path()).unwrap();
+        assert!(crate::pdf::parser::find_bytes(&data, b"JavaScript", 0).is_none());
+        assert!(crate::pdf::parser::find_bytes(&data, b"app.alert", 0).is_none());
+
+        let doc = Document::open_bytes(data).unwrap();
+        assert!(!doc.catalog().unwrap().contains_key("OpenAction"));
+        assert_eq!(doc.page_count().unwrap(), 1);
+        assert!(!doc.page(0).unwrap().dict().contains_key("AA"));
+    }
 
     #[test]
     fn test_write_options_default() {
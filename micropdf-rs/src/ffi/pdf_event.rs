@@ -3,7 +3,11 @@
 //! Provides PDF document event handling including alerts, print requests,
 //! URL launches, email, and menu item execution.
 
-use crate::ffi::{Handle, HandleStore};
+use crate::ffi::document::load_pdf;
+use crate::ffi::pdf_object::PDF_OBJECTS;
+use crate::ffi::{DOCUMENTS, Handle, HandleStore};
+use crate::pdf::action::{Action, parse_action};
+use crate::pdf::document::Document;
 use std::ffi::{CStr, CString, c_char, c_void};
 use std::ptr;
 use std::sync::LazyLock;
@@ -32,6 +36,29 @@ pub const PDF_DOCUMENT_EVENT_SUBMIT: i32 = 4;
 /// Execute menu item event
 pub const PDF_DOCUMENT_EVENT_EXEC_MENU_ITEM: i32 = 5;
 
+// ============================================================================
+// Action Types
+// ============================================================================
+
+/// Go to a page of this document
+pub const PDF_ACTION_GOTO: i32 = 0;
+/// Open a URI
+pub const PDF_ACTION_URI: i32 = 1;
+/// Launch an application or open a file
+pub const PDF_ACTION_LAUNCH: i32 = 2;
+/// Named viewer action
+pub const PDF_ACTION_NAMED: i32 = 3;
+/// Run a script
+pub const PDF_ACTION_JAVASCRIPT: i32 = 4;
+/// Go to a page of another document
+pub const PDF_ACTION_GOTOR: i32 = 5;
+/// Submit form fields
+pub const PDF_ACTION_SUBMIT_FORM: i32 = 6;
+/// Reset form fields
+pub const PDF_ACTION_RESET_FORM: i32 = 7;
+/// Any other action type
+pub const PDF_ACTION_OTHER: i32 = 8;
+
 // ============================================================================
 // Alert Icon Types
 // ============================================================================
@@ -730,6 +757,146 @@ pub extern "C" fn pdf_clear_pending_events(_ctx: ContextHandle, handler: Handle)
     }
 }
 
+// ============================================================================
+// Actions
+// ============================================================================
+
+/// Global store for parsed action chains
+pub static ACTIONS: LazyLock<HandleStore<Vec<Action>>> = LazyLock::new(HandleStore::new);
+
+/// Store the action chain parsed from `obj`, or return 0
+fn store_actions(doc: Option<&Document>, obj: Handle) -> Handle {
+    let Some(obj) = PDF_OBJECTS.get(obj) else {
+        return 0;
+    };
+    let obj = obj.lock().unwrap().to_object();
+    match parse_action(doc, &obj) {
+        Ok(actions) if !actions.is_empty() => ACTIONS.insert(actions),
+        _ => 0,
+    }
+}
+
+/// Run `f` on one action of a parsed chain
+fn with_action<R>(actions: Handle, index: i32, f: impl FnOnce(&Action) -> R) -> Option<R> {
+    let actions = ACTIONS.get(actions)?;
+    let actions = actions.lock().unwrap();
+    let action = actions.get(usize::try_from(index).ok()?)?;
+    Some(f(action))
+}
+
+/// Parse an action dictionary and the actions chained after it with /Next
+///
+/// Only explicit page numbers resolve in destinations; use
+/// `pdf_parse_document_action` to resolve references and named
+/// destinations. Returns 0 if `obj` is not an action.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_parse_action(_ctx: ContextHandle, obj: Handle) -> Handle {
+    store_actions(None, obj)
+}
+
+/// Parse an action dictionary, resolving references and destinations
+/// against a document
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_parse_document_action(
+    _ctx: ContextHandle,
+    doc: DocumentHandle,
+    obj: Handle,
+) -> Handle {
+    match load_pdf(doc) {
+        Ok(doc) => store_actions(Some(&doc), obj),
+        Err(_) => 0,
+    }
+}
+
+/// Drop a parsed action chain
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_drop_action(_ctx: ContextHandle, actions: Handle) {
+    ACTIONS.remove(actions);
+}
+
+/// Number of actions in a chain
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_action_count(_ctx: ContextHandle, actions: Handle) -> i32 {
+    ACTIONS
+        .get(actions)
+        .map_or(0, |a| a.lock().unwrap().len() as i32)
+}
+
+/// Type of an action in a chain (`PDF_ACTION_*`), or -1
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_action_type(_ctx: ContextHandle, actions: Handle, index: i32) -> i32 {
+    with_action(actions, index, |action| match action {
+        Action::GoTo { .. } => PDF_ACTION_GOTO,
+        Action::Uri(_) => PDF_ACTION_URI,
+        Action::Launch { .. } => PDF_ACTION_LAUNCH,
+        Action::Named(_) => PDF_ACTION_NAMED,
+        Action::JavaScript(_) => PDF_ACTION_JAVASCRIPT,
+        Action::GoToR { .. } => PDF_ACTION_GOTOR,
+        Action::SubmitForm { .. } => PDF_ACTION_SUBMIT_FORM,
+        Action::ResetForm { .. } => PDF_ACTION_RESET_FORM,
+        Action::Other(_) => PDF_ACTION_OTHER,
+    })
+    .unwrap_or(-1)
+}
+
+/// Zero-based target page of a GoTo or GoToR action, or -1
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_action_page(_ctx: ContextHandle, actions: Handle, index: i32) -> i32 {
+    with_action(actions, index, |action| match action {
+        Action::GoTo { page } | Action::GoToR { page, .. } => *page,
+        _ => None,
+    })
+    .flatten()
+    .map_or(-1, |page| page as i32)
+}
+
+/// Flags of a SubmitForm or ResetForm action, or 0
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_action_flags(_ctx: ContextHandle, actions: Handle, index: i32) -> i32 {
+    with_action(actions, index, |action| match action {
+        Action::SubmitForm { flags, .. } | Action::ResetForm { flags } => *flags as i32,
+        _ => 0,
+    })
+    .unwrap_or(0)
+}
+
+/// Copy the text of an action into `buf` of `size` bytes, including the
+/// terminator: the URI, file, name, script or submit URL, or the type
+/// name of other actions
+///
+/// Returns the full length of the text in bytes, or -1 if the action has
+/// none.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_action_text(
+    _ctx: ContextHandle,
+    actions: Handle,
+    index: i32,
+    buf: *mut c_char,
+    size: i32,
+) -> i32 {
+    let text = with_action(actions, index, |action| match action {
+        Action::Uri(s) | Action::Named(s) | Action::JavaScript(s) | Action::Other(s) => {
+            Some(s.clone())
+        }
+        Action::Launch { file } | Action::GoToR { file, .. } => file.clone(),
+        Action::SubmitForm { url, .. } => url.clone(),
+        Action::GoTo { .. } | Action::ResetForm { .. } => None,
+    })
+    .flatten();
+    let Some(text) = text else {
+        return -1;
+    };
+    let bytes = text.as_bytes();
+    if !buf.is_null() && size > 0 {
+        let len = bytes.len().min(size as usize - 1);
+        unsafe {
+            ptr::copy_nonoverlapping(bytes.as_ptr(), buf as *mut u8, len);
+            *buf.add(len) = 0;
+        }
+    }
+    bytes.len() as i32
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -737,6 +904,106 @@ pub extern "C" fn pdf_clear_pending_events(_ctx: ContextHandle, handler: Handle)
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ffi::pdf_object::{PdfObj, PdfObjType};
+    use crate::pdf::test_pdf::build_pdf;
+
+    fn obj(obj_type: PdfObjType) -> PdfObj {
+        let mut obj = PdfObj::new_null();
+        obj.obj_type = obj_type;
+        obj
+    }
+
+    fn action_dict(entries: Vec<(&str, PdfObj)>) -> Handle {
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        PDF_OBJECTS.insert(obj(PdfObjType::Dict(entries)))
+    }
+
+    fn action_text(actions: Handle, index: i32) -> String {
+        let mut buf = [0 as c_char; 64];
+        pdf_action_text(0, actions, index, buf.as_mut_ptr(), buf.len() as i32);
+        unsafe { CStr::from_ptr(buf.as_ptr()) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_parse_uri_action() {
+        let obj = action_dict(vec![
+            ("S", PdfObj::new_name("URI")),
+            ("URI", PdfObj::new_string(b"https://example.com/doc")),
+            (
+                "Next",
+                obj(PdfObjType::Dict(vec![
+                    ("S".into(), PdfObj::new_name("Named")),
+                    ("N".into(), PdfObj::new_name("NextPage")),
+                ])),
+            ),
+        ]);
+        let actions = pdf_parse_action(0, obj);
+        assert_ne!(actions, 0);
+        assert_eq!(pdf_action_count(0, actions), 2);
+        assert_eq!(pdf_action_type(0, actions, 0), PDF_ACTION_URI);
+        assert_eq!(action_text(actions, 0), "https://example.com/doc");
+        assert_eq!(pdf_action_type(0, actions, 1), PDF_ACTION_NAMED);
+        assert_eq!(action_text(actions, 1), "NextPage");
+        assert_eq!(pdf_action_type(0, actions, 2), -1);
+        pdf_drop_action(0, actions);
+        PDF_OBJECTS.remove(obj);
+    }
+
+    /// A two-page PDF
+    fn two_page_pdf() -> Vec<u8> {
+        let objects: [&[u8]; 4] = [
+            b"<< /Type /Catalog /Pages 2 0 R >>",
+            b"<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>",
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] >>",
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] >>",
+        ];
+        build_pdf(&objects)
+    }
+
+    #[test]
+    fn test_parse_goto_action() {
+        let dest = |page: PdfObj| {
+            obj(PdfObjType::Array(vec![
+                page,
+                PdfObj::new_name("XYZ"),
+                PdfObj::new_int(0),
+                PdfObj::new_int(792),
+                PdfObj::new_int(0),
+            ]))
+        };
+        let explicit = action_dict(vec![
+            ("S", PdfObj::new_name("GoTo")),
+            ("D", dest(PdfObj::new_int(1))),
+        ]);
+        let actions = pdf_parse_action(0, explicit);
+        assert_eq!(pdf_action_type(0, actions, 0), PDF_ACTION_GOTO);
+        assert_eq!(pdf_action_page(0, actions, 0), 1);
+        assert_eq!(pdf_action_text(0, actions, 0, ptr::null_mut(), 0), -1);
+        pdf_drop_action(0, actions);
+
+        // A page reference needs the document to resolve
+        let by_ref = action_dict(vec![
+            ("S", PdfObj::new_name("GoTo")),
+            ("D", dest(PdfObj::new_indirect(4, 0))),
+        ]);
+        let actions = pdf_parse_action(0, by_ref);
+        assert_eq!(pdf_action_page(0, actions, 0), -1);
+        pdf_drop_action(0, actions);
+
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(two_page_pdf()));
+        let actions = pdf_parse_document_action(0, doc, by_ref);
+        assert_eq!(pdf_action_type(0, actions, 0), PDF_ACTION_GOTO);
+        assert_eq!(pdf_action_page(0, actions, 0), 1);
+        pdf_drop_action(0, actions);
+        DOCUMENTS.remove(doc);
+        PDF_OBJECTS.remove(explicit);
+        PDF_OBJECTS.remove(by_ref);
+    }
 
     #[test]
     fn test_event_type_constants() {
@@ -3,7 +3,16 @@
 //! Provides PDF image optimization including resampling, recompression,
 //! and resolution changes for color, grayscale, and bitonal images.
 
-use crate::ffi::Handle;
+use crate::ffi::document::load_pdf;
+use crate::ffi::{DOCUMENTS, Handle};
+use crate::fitz::error::{Error, Result};
+use crate::fitz::geometry::Matrix;
+use crate::pdf::content::{self, ContentState, Mark};
+use crate::pdf::document::Document;
+use crate::pdf::filter::{encode_dct_components, encode_flate};
+use crate::pdf::object::{Dict, ObjRef, Object};
+use crate::pdf::write;
+use std::collections::{BTreeMap, HashMap};
 use std::ffi::{CStr, CString, c_char};
 use std::ptr;
 
@@ -464,34 +473,553 @@ pub extern "C" fn pdf_set_gray_jpeg_quality(
     }
 }
 
+// ============================================================================
+// Image Rewriting
+// ============================================================================
+
+/// Maximum nesting of form XObjects followed when looking for images
+const MAX_FORM_DEPTH: usize = 16;
+
+/// JPEG quality used when no valid quality is given
+const DEFAULT_JPEG_QUALITY: u8 = 75;
+
+/// Filters whose images are left alone
+const SKIPPED_FILTERS: [&str; 3] = ["CCITTFaxDecode", "JBIG2Decode", "JPXDecode"];
+
+/// What to do with one class of image
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ImagePolicy {
+    /// Resolution above which images are downsampled (0 = never)
+    threshold: f32,
+    /// Resolution downsampled images are brought to
+    target: f32,
+    /// One of the `FZ_RECOMPRESS_*` methods
+    recompress: i32,
+    /// JPEG quality, 1 to 100
+    quality: u8,
+}
+
+impl ImagePolicy {
+    fn new(threshold: i32, target: i32, recompress: i32, quality: *const c_char) -> Self {
+        Self {
+            threshold: threshold.max(0) as f32,
+            target: target.max(0) as f32,
+            recompress,
+            quality: parse_quality(quality),
+        }
+    }
+}
+
+/// Policies for the color and gray images the rewriter changes; lossy
+/// images are those stored as JPEG
+#[derive(Debug, Clone, Copy)]
+struct RewritePolicy {
+    color_lossless: ImagePolicy,
+    color_lossy: ImagePolicy,
+    gray_lossless: ImagePolicy,
+    gray_lossy: ImagePolicy,
+}
+
+impl RewritePolicy {
+    /// Downsample everything drawn above `max_dpi` and store it as JPEG
+    fn max_dpi(max_dpi: f32, quality: u8) -> Self {
+        let policy = ImagePolicy {
+            threshold: max_dpi,
+            target: max_dpi,
+            recompress: FZ_RECOMPRESS_JPEG,
+            quality: quality.clamp(1, 100),
+        };
+        Self {
+            color_lossless: policy,
+            color_lossy: policy,
+            gray_lossless: policy,
+            gray_lossy: policy,
+        }
+    }
+
+    fn from_options(opts: &ImageRewriterOptions) -> Self {
+        Self {
+            color_lossless: ImagePolicy::new(
+                opts.color_lossless_image_subsample_threshold,
+                opts.color_lossless_image_subsample_to,
+                opts.color_lossless_image_recompress_method,
+                opts.color_lossless_image_recompress_quality,
+            ),
+            color_lossy: ImagePolicy::new(
+                opts.color_lossy_image_subsample_threshold,
+                opts.color_lossy_image_subsample_to,
+                opts.color_lossy_image_recompress_method,
+                opts.color_lossy_image_recompress_quality,
+            ),
+            gray_lossless: ImagePolicy::new(
+                opts.gray_lossless_image_subsample_threshold,
+                opts.gray_lossless_image_subsample_to,
+                opts.gray_lossless_image_recompress_method,
+                opts.gray_lossless_image_recompress_quality,
+            ),
+            gray_lossy: ImagePolicy::new(
+                opts.gray_lossy_image_subsample_threshold,
+                opts.gray_lossy_image_subsample_to,
+                opts.gray_lossy_image_recompress_method,
+                opts.gray_lossy_image_recompress_quality,
+            ),
+        }
+    }
+
+    fn get(&self, components: usize, lossy: bool) -> &ImagePolicy {
+        match (components == 1, lossy) {
+            (false, false) => &self.color_lossless,
+            (false, true) => &self.color_lossy,
+            (true, false) => &self.gray_lossless,
+            (true, true) => &self.gray_lossy,
+        }
+    }
+}
+
+/// A JPEG quality from an options string, falling back to the default
+fn parse_quality(quality: *const c_char) -> u8 {
+    if quality.is_null() {
+        return DEFAULT_JPEG_QUALITY;
+    }
+    let text = unsafe { CStr::from_ptr(quality) }.to_string_lossy();
+    text.trim()
+        .parse::<u8>()
+        .ok()
+        .filter(|q| (1..=100).contains(q))
+        .unwrap_or(DEFAULT_JPEG_QUALITY)
+}
+
+/// Lowest resolution, along each image axis, at which an image is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Placement {
+    dpi_x: f32,
+    dpi_y: f32,
+}
+
+/// The lowest resolution of every image XObject drawn by a page
+fn find_placements(doc: &Document) -> Result<BTreeMap<ObjRef, Placement>> {
+    let mut placements = BTreeMap::new();
+    for index in 0..doc.page_count()? {
+        let page = doc.page(index)?;
+        let resources = match doc.resolve_key(page.dict(), "Resources")? {
+            Some(Object::Dict(d)) => d,
+            _ => continue,
+        };
+        let content = doc.page_contents(&page)?;
+        scan_content(
+            doc,
+            &content,
+            &resources,
+            Matrix::IDENTITY,
+            0,
+            &mut placements,
+        )?;
+    }
+    Ok(placements)
+}
+
+/// Record the images drawn by `content`, following form XObjects
+fn scan_content(
+    doc: &Document,
+    content: &[u8],
+    resources: &Dict,
+    base: Matrix,
+    depth: usize,
+    placements: &mut BTreeMap<ObjRef, Placement>,
+) -> Result<()> {
+    let Some(Object::Dict(xobjects)) = doc.resolve_key(resources, "XObject")? else {
+        return Ok(());
+    };
+    let fonts = HashMap::new();
+    let mut state = ContentState::new(&fonts);
+    for op in content::parse_content(content)? {
+        let Some(Mark::XObject { name, ctm }) = state.step(&op) else {
+            continue;
+        };
+        let Some(obj_ref) = xobjects.get(name.as_str()).and_then(Object::as_obj_ref) else {
+            continue;
+        };
+        let Object::Stream { dict, .. } = doc.load_object(obj_ref)? else {
+            continue;
+        };
+        let ctm = ctm.concat(&base);
+        match dict
+            .get("Subtype")
+            .and_then(Object::as_name)
+            .map(|n| n.as_str())
+        {
+            Some("Image") => {
+                let (Some(w), Some(h)) = (
+                    dict.get("Width").and_then(Object::as_int),
+                    dict.get("Height").and_then(Object::as_int),
+                ) else {
+                    continue;
+                };
+                let placed_w = ctm.a.hypot(ctm.b);
+                let placed_h = ctm.c.hypot(ctm.d);
+                if placed_w <= 0.0 || placed_h <= 0.0 {
+                    continue;
+                }
+                let dpi_x = w as f32 * 72.0 / placed_w;
+                let dpi_y = h as f32 * 72.0 / placed_h;
+                placements
+                    .entry(obj_ref)
+                    .and_modify(|p: &mut Placement| {
+                        p.dpi_x = p.dpi_x.min(dpi_x);
+                        p.dpi_y = p.dpi_y.min(dpi_y);
+                    })
+                    .or_insert(Placement { dpi_x, dpi_y });
+            }
+            Some("Form") if depth < MAX_FORM_DEPTH => {
+                let m: Vec<f32> = match doc.resolve_key(&dict, "Matrix")? {
+                    Some(Object::Array(arr)) => arr
+                        .iter()
+                        .filter_map(Object::as_real)
+                        .map(|v| v as f32)
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                let matrix = match m[..] {
+                    [a, b, c, d, e, f] => Matrix::new(a, b, c, d, e, f),
+                    _ => Matrix::IDENTITY,
+                };
+                let form_resources = match doc.resolve_key(&dict, "Resources")? {
+                    Some(Object::Dict(d)) => d,
+                    _ => resources.clone(),
+                };
+                let data = doc.stream_data(&Object::Ref(obj_ref))?;
+                scan_content(
+                    doc,
+                    &data,
+                    &form_resources,
+                    matrix.concat(&ctm),
+                    depth + 1,
+                    placements,
+                )?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Number of color components of an image colorspace the rewriter can
+/// re-encode, or `None` for colorspaces it leaves alone
+fn rewritable_components(doc: &Document, cs: &Object) -> Option<usize> {
+    match doc.resolve(cs).ok()? {
+        Object::Name(n) => match n.as_str() {
+            "DeviceGray" | "G" | "CalGray" => Some(1),
+            "DeviceRGB" | "RGB" | "CalRGB" => Some(3),
+            _ => None,
+        },
+        Object::Array(arr) => {
+            let family = arr.first()?.as_name()?.as_str().to_string();
+            match family.as_str() {
+                "CalGray" => Some(1),
+                "CalRGB" => Some(3),
+                "ICCBased" => {
+                    let Object::Stream { dict, .. } = doc.resolve(arr.get(1)?).ok()? else {
+                        return None;
+                    };
+                    match dict.get("N").and_then(Object::as_int)? {
+                        1 => Some(1),
+                        3 => Some(3),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Names of an image's filters
+fn filter_names(doc: &Document, dict: &Dict) -> Vec<String> {
+    match doc.resolve_key(dict, "Filter") {
+        Ok(Some(Object::Name(n))) => vec![n.as_str().to_string()],
+        Ok(Some(Object::Array(arr))) => arr
+            .iter()
+            .filter_map(Object::as_name)
+            .map(|n| n.as_str().to_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether an image is an image mask or has one bit per component
+fn is_bitonal(dict: &Dict) -> bool {
+    dict.get("ImageMask").and_then(Object::as_bool) == Some(true)
+        || dict.get("BitsPerComponent").and_then(Object::as_int) == Some(1)
+}
+
+/// Size of an image after downsampling to `policy`, along one axis
+fn target_size(size: usize, dpi: f32, policy: &ImagePolicy) -> usize {
+    if policy.threshold <= 0.0 || policy.target <= 0.0 || dpi <= policy.threshold {
+        return size;
+    }
+    ((size as f32 * policy.target / dpi).ceil() as usize).clamp(1, size)
+}
+
+/// Box-filter `n`-component samples from `w`×`h` down to `new_w`×`new_h`
+fn downsample(src: &[u8], w: usize, h: usize, n: usize, new_w: usize, new_h: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(new_w * new_h * n);
+    for dy in 0..new_h {
+        let y0 = dy * h / new_h;
+        let y1 = ((dy + 1) * h / new_h).max(y0 + 1);
+        for dx in 0..new_w {
+            let x0 = dx * w / new_w;
+            let x1 = ((dx + 1) * w / new_w).max(x0 + 1);
+            let count = ((y1 - y0) * (x1 - x0)) as u32;
+            for c in 0..n {
+                let mut sum = 0u32;
+                for y in y0..y1 {
+                    let row = &src[y * w * n..(y + 1) * w * n];
+                    for x in x0..x1 {
+                        sum += row[x * n + c] as u32;
+                    }
+                }
+                out.push(((sum + count / 2) / count) as u8);
+            }
+        }
+    }
+    out
+}
+
+/// The rewritten image stream, and whether it was downsampled, or `None`
+/// when the image is left alone
+fn rewrite_image(
+    doc: &Document,
+    image: &Object,
+    placement: Placement,
+    policy: &RewritePolicy,
+) -> Option<(Object, bool)> {
+    let Object::Stream { dict, data } = image else {
+        return None;
+    };
+    let filters = filter_names(doc, dict);
+    if is_bitonal(dict)
+        || dict.get("BitsPerComponent").and_then(Object::as_int) != Some(8)
+        || dict.contains_key("DecodeParms")
+        || filters
+            .iter()
+            .any(|f| SKIPPED_FILTERS.contains(&f.as_str()))
+    {
+        return None;
+    }
+    let n = rewritable_components(doc, dict.get("ColorSpace")?)?;
+    let lossy = filters.iter().any(|f| f == "DCTDecode" || f == "DCT");
+    let policy = policy.get(n, lossy);
+
+    let w = dict.get("Width").and_then(Object::as_int)?.max(0) as usize;
+    let h = dict.get("Height").and_then(Object::as_int)?.max(0) as usize;
+    let new_w = target_size(w, placement.dpi_x, policy);
+    let new_h = target_size(h, placement.dpi_y, policy);
+    let subsampled = (new_w, new_h) != (w, h);
+
+    let jpeg = match policy.recompress {
+        FZ_RECOMPRESS_NEVER => return None,
+        FZ_RECOMPRESS_JPEG => true,
+        FZ_RECOMPRESS_LOSSLESS => false,
+        _ if subsampled => lossy,
+        _ => return None,
+    };
+
+    let samples = doc.stream_data(image).ok()?;
+    if w == 0 || h == 0 || samples.len() != w * h * n {
+        return None;
+    }
+    let samples = if subsampled {
+        downsample(&samples, w, h, n, new_w, new_h)
+    } else {
+        samples
+    };
+    let (encoded, filter) = if jpeg {
+        let encoded =
+            encode_dct_components(&samples, new_w as u32, new_h as u32, n, policy.quality).ok()?;
+        (encoded, "DCTDecode")
+    } else {
+        (encode_flate(&samples, 6).ok()?, "FlateDecode")
+    };
+    if encoded.len() >= data.len() {
+        return None;
+    }
+
+    let mut dict = dict.clone();
+    dict.insert("Width".into(), Object::Int(new_w as i64));
+    dict.insert("Height".into(), Object::Int(new_h as i64));
+    dict.insert("BitsPerComponent".into(), Object::Int(8));
+    dict.insert("Filter".into(), Object::Name(filter.into()));
+    dict.insert("Length".into(), Object::Int(encoded.len() as i64));
+    Some((
+        Object::Stream {
+            dict,
+            data: encoded,
+        },
+        subsampled,
+    ))
+}
+
+/// Count an image in the color, gray or bitonal totals
+fn count_kind(doc: &Document, dict: &Dict, stats: &mut ImageRewriteStats) {
+    if is_bitonal(dict) {
+        stats.bitonal_images += 1;
+        return;
+    }
+    let gray = dict
+        .get("ColorSpace")
+        .and_then(|cs| rewritable_components(doc, cs))
+        == Some(1);
+    if gray {
+        stats.gray_images += 1;
+    } else {
+        stats.color_images += 1;
+    }
+}
+
+/// Downsample and recompress the images drawn by `doc`'s pages
+///
+/// Returns the replaced image streams and statistics. Image masks, 1-bit
+/// images and CCITT, JBIG2 and JPEG 2000 images are left alone, as are
+/// images in colorspaces other than gray and RGB. Images are only replaced
+/// when the new stream is smaller.
+fn rewrite_images(
+    doc: &Document,
+    policy: &RewritePolicy,
+) -> Result<(BTreeMap<ObjRef, Object>, ImageRewriteStats)> {
+    let mut changes = BTreeMap::new();
+    let mut stats = ImageRewriteStats::default();
+    for (obj_ref, placement) in find_placements(doc)? {
+        let image = doc.load_object(obj_ref)?;
+        let Object::Stream { dict, data } = &image else {
+            continue;
+        };
+        stats.images_processed += 1;
+        stats.original_size += data.len() as u64;
+        count_kind(doc, dict, &mut stats);
+        match rewrite_image(doc, &image, placement, policy) {
+            Some((new_image, subsampled)) => {
+                if let Object::Stream { data, .. } = &new_image {
+                    stats.new_size += data.len() as u64;
+                }
+                if subsampled {
+                    stats.images_subsampled += 1;
+                }
+                stats.images_recompressed += 1;
+                changes.insert(obj_ref, new_image);
+            }
+            None => {
+                stats.images_unchanged += 1;
+                stats.new_size += data.len() as u64;
+            }
+        }
+    }
+    Ok((changes, stats))
+}
+
+/// Statistics for every image XObject in `doc`, without changing it
+fn analyze_images(doc: &Document) -> Result<ImageRewriteStats> {
+    let mut stats = ImageRewriteStats::default();
+    for obj_ref in doc.object_refs() {
+        let Object::Stream { dict, data } = doc.load_object(obj_ref)? else {
+            continue;
+        };
+        if dict
+            .get("Subtype")
+            .and_then(Object::as_name)
+            .map(|n| n.as_str())
+            != Some("Image")
+        {
+            continue;
+        }
+        stats.images_processed += 1;
+        stats.images_unchanged += 1;
+        stats.original_size += data.len() as u64;
+        stats.new_size += data.len() as u64;
+        count_kind(doc, &dict, &mut stats);
+    }
+    Ok(stats)
+}
+
+/// Rewrite the images of the document behind `doc` and replace its bytes
+/// with a complete rewrite, so the old image data is dropped
+fn rewrite_document_images(
+    doc: DocumentHandle,
+    policy: &RewritePolicy,
+) -> Result<ImageRewriteStats> {
+    let doc = DOCUMENTS
+        .get(doc)
+        .ok_or_else(|| Error::argument("invalid document handle"))?;
+    let mut doc = doc.lock().unwrap();
+
+    let pdf = doc.pdf()?;
+    let (changes, stats) = rewrite_images(&pdf, policy)?;
+    if changes.is_empty() {
+        return Ok(stats);
+    }
+    let mut out = Vec::new();
+    write::rewrite_document(&pdf, &changes, &mut out)?;
+    doc.set_data(out);
+    Ok(stats)
+}
+
+/// Statistics from [`analyze_images`] for the document behind `doc`
+fn document_image_stats(doc: DocumentHandle) -> Result<ImageRewriteStats> {
+    analyze_images(&*load_pdf(doc)?)
+}
+
 // ============================================================================
 // FFI Functions - Main Rewrite Function
 // ============================================================================
 
 /// Rewrite images within the given document.
+///
+/// Images drawn above an option's subsample threshold are downsampled to
+/// its target resolution and recompressed with its method.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_rewrite_images(
-    _ctx: ContextHandle,
-    _doc: DocumentHandle,
-    _opts: *mut ImageRewriterOptions,
+    ctx: ContextHandle,
+    doc: DocumentHandle,
+    opts: *mut ImageRewriterOptions,
 ) {
-    // In a full implementation, this would:
-    // 1. Iterate through all images in the document
-    // 2. For each image, determine if it should be subsampled based on DPI thresholds
-    // 3. Resample the image if needed using the specified method
-    // 4. Recompress the image using the specified method
-    // 5. Replace the original image data in the PDF
+    pdf_rewrite_images_with_stats(ctx, doc, opts);
 }
 
 /// Rewrite images and return statistics.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_rewrite_images_with_stats(
     _ctx: ContextHandle,
-    _doc: DocumentHandle,
-    _opts: *mut ImageRewriterOptions,
+    doc: DocumentHandle,
+    opts: *mut ImageRewriterOptions,
 ) -> ImageRewriteStats {
-    // In a full implementation, this would rewrite images and collect stats
-    ImageRewriteStats::default()
+    let policy = if opts.is_null() {
+        RewritePolicy::from_options(&ImageRewriterOptions::new())
+    } else {
+        RewritePolicy::from_options(unsafe { &*opts })
+    };
+    rewrite_document_images(doc, &policy).unwrap_or_default()
+}
+
+/// Downsample images drawn above `max_dpi` and recompress them as JPEG at
+/// `jpeg_quality` (1 to 100).
+///
+/// Image masks, 1-bit images and CCITT/JBIG2 images are left alone, and
+/// colorspaces are kept. Returns the number of bytes saved, or -1 on error.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_rewrite_images_to_dpi(
+    _ctx: ContextHandle,
+    doc: DocumentHandle,
+    max_dpi: f32,
+    jpeg_quality: i32,
+) -> i64 {
+    if max_dpi.is_nan() || max_dpi <= 0.0 {
+        return -1;
+    }
+    let policy = RewritePolicy::max_dpi(max_dpi, jpeg_quality.clamp(1, 100) as u8);
+    match rewrite_document_images(doc, &policy) {
+        Ok(stats) => stats.original_size as i64 - stats.new_size as i64,
+        Err(_) => -1,
+    }
 }
 
 // ============================================================================
@@ -500,26 +1028,23 @@ pub extern "C" fn pdf_rewrite_images_with_stats(
 
 /// Count images in document.
 #[unsafe(no_mangle)]
-pub extern "C" fn pdf_count_images(_ctx: ContextHandle, _doc: DocumentHandle) -> i32 {
-    // In a full implementation, this would count all images
-    0
+pub extern "C" fn pdf_count_images(_ctx: ContextHandle, doc: DocumentHandle) -> i32 {
+    document_image_stats(doc).map_or(0, |stats| stats.images_processed)
 }
 
 /// Get total image size in bytes.
 #[unsafe(no_mangle)]
-pub extern "C" fn pdf_get_total_image_size(_ctx: ContextHandle, _doc: DocumentHandle) -> u64 {
-    // In a full implementation, this would sum all image sizes
-    0
+pub extern "C" fn pdf_get_total_image_size(_ctx: ContextHandle, doc: DocumentHandle) -> u64 {
+    document_image_stats(doc).map_or(0, |stats| stats.original_size)
 }
 
 /// Analyze images and return statistics without modifying.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_analyze_images(
     _ctx: ContextHandle,
-    _doc: DocumentHandle,
+    doc: DocumentHandle,
 ) -> ImageRewriteStats {
-    // In a full implementation, this would analyze images without modifying them
-    ImageRewriteStats::default()
+    document_image_stats(doc).unwrap_or_default()
 }
 
 // ============================================================================
@@ -572,6 +1097,7 @@ pub extern "C" fn pdf_drop_image_rewriter_options(opts: *mut ImageRewriterOption
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
 
     #[test]
     fn test_subsample_constants() {
@@ -744,4 +1270,92 @@ mod tests {
         let count = pdf_count_images(0, 0);
         assert_eq!(count, 0);
     }
+
+    /// A one-page PDF drawing a 600x600 uncompressed RGB gradient at 2x2
+    /// inches (300 dpi), plus a 1-bit image mask drawn at the same size
+    fn oversized_image_pdf() -> Vec<u8> {
+        let (w, h) = (600usize, 600usize);
+        let mut samples = Vec::with_capacity(w * h * 3);
+        for y in 0..h {
+            for x in 0..w {
+                samples.extend_from_slice(&[(x * 255 / w) as u8, (y * 255 / h) as u8, 128]);
+            }
+        }
+        let content = "q 144 0 0 144 72 72 cm /Im1 Do Q q 144 0 0 144 300 72 cm /Im2 Do Q";
+        let mut image = format!(
+            "<< /Type /XObject /Subtype /Image /Width {w} /Height {h} \
+             /ColorSpace /DeviceRGB /BitsPerComponent 8 /Length {} >>\nstream\n",
+            samples.len()
+        )
+        .into_bytes();
+        image.extend_from_slice(&samples);
+        image.extend_from_slice(b"\nendstream");
+        let mask = vec![0xAAu8; 75 * 600];
+        let mut mask_obj = format!(
+            "<< /Type /XObject /Subtype /Image /Width 600 /Height 600 \
+             /ImageMask true /BitsPerComponent 1 /Length {} >>\nstream\n",
+            mask.len()
+        )
+        .into_bytes();
+        mask_obj.extend_from_slice(&mask);
+        mask_obj.extend_from_slice(b"\nendstream");
+
+        let objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R \
+              /Resources << /XObject << /Im1 5 0 R /Im2 6 0 R >> >> >>"
+                .to_vec(),
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            )
+            .into_bytes(),
+            image,
+            mask_obj,
+        ];
+        build_pdf(&objects)
+    }
+
+    #[test]
+    fn test_rewrite_images_to_dpi() {
+        let data = oversized_image_pdf();
+        let original_len = data.len();
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(data));
+        assert_eq!(pdf_count_images(0, doc), 2);
+
+        let saved = pdf_rewrite_images_to_dpi(0, doc, 150.0, 80);
+        let rewritten = DOCUMENTS.get(doc).unwrap().lock().unwrap().data().to_vec();
+        DOCUMENTS.remove(doc);
+        assert!(saved > 0);
+        assert!(rewritten.len() < original_len);
+
+        let pdf = Document::open_bytes(rewritten).unwrap();
+        let Object::Stream { dict, .. } = pdf.load_object(ObjRef::new(5, 0)).unwrap() else {
+            panic!("image is not a stream");
+        };
+        assert_eq!(dict.get("Width").and_then(Object::as_int), Some(300));
+        assert_eq!(dict.get("Height").and_then(Object::as_int), Some(300));
+        assert_eq!(
+            dict.get("Filter")
+                .and_then(Object::as_name)
+                .map(|n| n.as_str()),
+            Some("DCTDecode")
+        );
+        assert_eq!(
+            dict.get("ColorSpace")
+                .and_then(Object::as_name)
+                .map(|n| n.as_str()),
+            Some("DeviceRGB")
+        );
+        let image = pdf.load_object(ObjRef::new(5, 0)).unwrap();
+        assert_eq!(pdf.stream_data(&image).unwrap().len(), 300 * 300 * 3);
+
+        // The image mask is left alone
+        let Object::Stream { dict, .. } = pdf.load_object(ObjRef::new(6, 0)).unwrap() else {
+            panic!("mask is not a stream");
+        };
+        assert_eq!(dict.get("Width").and_then(Object::as_int), Some(600));
+        assert!(!dict.contains_key("Filter"));
+    }
 }
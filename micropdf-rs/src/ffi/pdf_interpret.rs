@@ -750,6 +750,23 @@ pub extern "C" fn pdf_count_q_balance(
     }
 }
 
+/// Render `len` bytes of decoded content stream as text, one operator per
+/// line and indented by nesting
+///
+/// Inline image data appears as a single hex string. Returns null if the
+/// content cannot be parsed; free the result with `fz_free_string`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_pretty_print_content(
+    _ctx: Handle,
+    data: *const u8,
+    len: usize,
+) -> *mut c_char {
+    crate::pdf::content::pretty_print(raw_to_slice(data, len))
+        .ok()
+        .and_then(|text| CString::new(text).ok())
+        .map_or(ptr::null_mut(), CString::into_raw)
+}
+
 // ============================================================================
 // FFI Functions - Graphics State Operators
 // ============================================================================
@@ -5,9 +5,14 @@
 //! - Execute JavaScript code
 //! - Handle form field events (validation, keystroke, etc.)
 //! - Event initialization and result retrieval
-
-use crate::ffi::{Handle, HandleStore};
-use std::collections::HashMap;
+//! - Finding and extracting the scripts a document contains
+
+use crate::ffi::document::load_pdf;
+use crate::ffi::{DOCUMENTS, Handle, HandleStore};
+use crate::fitz::error::{Error, Result};
+use crate::pdf::document::Document;
+use crate::pdf::object::{Dict, ObjRef, Object, PdfString};
+use std::collections::{HashMap, HashSet};
 use std::ffi::{CStr, CString, c_char};
 use std::ptr;
 use std::sync::{LazyLock, Mutex};
@@ -857,6 +862,242 @@ pub extern "C" fn pdf_js_is_enabled(js: Handle) -> i32 {
     if js_guard.enabled { 1 } else { 0 }
 }
 
+// ============================================================================
+// Script Extraction
+// ============================================================================
+
+/// Maximum depth followed through name trees, field trees and /Next chains
+const MAX_SCRIPT_DEPTH: usize = 64;
+
+/// Text of a /JS value, which is a string or a stream
+fn script_text(doc: &Document, js: &Object) -> Result<Option<String>> {
+    let bytes = match doc.resolve(js)? {
+        Object::String(s) => s.as_bytes().to_vec(),
+        stream @ Object::Stream { .. } => doc.stream_data(&stream)?,
+        _ => return Ok(None),
+    };
+    Ok(Some(PdfString::new(bytes).to_text()))
+}
+
+/// Collects the scripts of JavaScript actions, visiting each object once
+struct ScriptCollector<'a> {
+    doc: &'a Document,
+    visited: HashSet<ObjRef>,
+    scripts: Vec<String>,
+}
+
+impl<'a> ScriptCollector<'a> {
+    fn new(doc: &'a Document) -> Self {
+        Self {
+            doc,
+            visited: HashSet::new(),
+            scripts: Vec::new(),
+        }
+    }
+
+    /// Resolve `obj` unless it is a reference that was already visited
+    fn enter(&mut self, obj: &Object) -> Result<Option<Dict>> {
+        if let Some(r) = obj.as_obj_ref() {
+            if !self.visited.insert(r) {
+                return Ok(None);
+            }
+        }
+        Ok(match self.doc.resolve(obj)? {
+            Object::Dict(dict) => Some(dict),
+            _ => None,
+        })
+    }
+
+    /// An action and the actions chained after it by /Next
+    fn action(&mut self, action: &Object, depth: usize) -> Result<()> {
+        if depth > MAX_SCRIPT_DEPTH {
+            return Ok(());
+        }
+        let Some(dict) = self.enter(action)? else {
+            return Ok(());
+        };
+        if dict.get("S").and_then(Object::as_name).map(|n| n.as_str()) == Some("JavaScript") {
+            if let Some(js) = dict.get("JS") {
+                if let Some(text) = script_text(self.doc, js)? {
+                    self.scripts.push(text);
+                }
+            }
+        }
+        if let Some(next) = dict.get("Next") {
+            match self.doc.resolve(next)? {
+                Object::Array(actions) => {
+                    for action in &actions {
+                        self.action(action, depth + 1)?;
+                    }
+                }
+                _ => self.action(next, depth + 1)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// The /A action and /AA additional actions of an annotation, field,
+    /// page or catalog
+    fn actions_of(&mut self, dict: &Dict) -> Result<()> {
+        if let Some(action) = dict.get("A") {
+            self.action(action, 0)?;
+        }
+        if let Some(Object::Dict(aa)) = self.doc.resolve_key(dict, "AA")? {
+            let mut triggers: Vec<_> = aa.iter().collect();
+            triggers.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+            for (_, action) in triggers {
+                self.action(action, 0)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Document-level scripts, in name order
+    fn name_tree(&mut self, node: &Object, depth: usize) -> Result<()> {
+        if depth > MAX_SCRIPT_DEPTH {
+            return Ok(());
+        }
+        let Some(node) = self.enter(node)? else {
+            return Ok(());
+        };
+        if let Some(Object::Array(names)) = self.doc.resolve_key(&node, "Names")? {
+            for pair in names.chunks_exact(2) {
+                self.action(&pair[1], 0)?;
+            }
+        }
+        if let Some(Object::Array(kids)) = self.doc.resolve_key(&node, "Kids")? {
+            for kid in &kids {
+                self.name_tree(kid, depth + 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Form fields and their kids
+    fn field(&mut self, field: &Object, depth: usize) -> Result<()> {
+        if depth > MAX_SCRIPT_DEPTH {
+            return Ok(());
+        }
+        let Some(dict) = self.enter(field)? else {
+            return Ok(());
+        };
+        self.actions_of(&dict)?;
+        if let Some(Object::Array(kids)) = self.doc.resolve_key(&dict, "Kids")? {
+            for kid in &kids {
+                self.field(kid, depth + 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Scripts of every JavaScript action in `doc`: document-level scripts,
+/// then the open action, document and page additional actions, annotation
+/// actions and form field actions
+pub fn document_scripts(doc: &Document) -> Result<Vec<String>> {
+    let catalog = doc.catalog()?;
+    let mut collector = ScriptCollector::new(doc);
+    if let Some(Object::Dict(names)) = doc.resolve_key(&catalog, "Names")? {
+        if let Some(tree) = names.get("JavaScript") {
+            collector.name_tree(tree, 0)?;
+        }
+    }
+    if let Some(action) = catalog.get("OpenAction") {
+        // A destination array is not an action
+        if !matches!(doc.resolve(action)?, Object::Array(_)) {
+            collector.action(action, 0)?;
+        }
+    }
+    collector.actions_of(&catalog)?;
+    for index in 0..doc.page_count()? {
+        let page = doc.page(index)?;
+        collector.actions_of(page.dict())?;
+        if let Some(Object::Array(annots)) = doc.resolve_key(page.dict(), "Annots")? {
+            for annot in &annots {
+                if let Some(dict) = collector.enter(annot)? {
+                    collector.actions_of(&dict)?;
+                }
+            }
+        }
+    }
+    if let Some(Object::Dict(acroform)) = doc.resolve_key(&catalog, "AcroForm")? {
+        if let Some(Object::Array(fields)) = doc.resolve_key(&acroform, "Fields")? {
+            for field in &fields {
+                collector.field(field, 0)?;
+            }
+        }
+    }
+    Ok(collector.scripts)
+}
+
+/// Whether the catalog's /OpenAction runs JavaScript, directly or through
+/// its /Next chain
+pub fn has_open_action_js(doc: &Document) -> Result<bool> {
+    let catalog = doc.catalog()?;
+    let Some(action) = catalog.get("OpenAction") else {
+        return Ok(false);
+    };
+    if matches!(doc.resolve(action)?, Object::Array(_)) {
+        return Ok(false);
+    }
+    let mut collector = ScriptCollector::new(doc);
+    collector.action(action, 0)?;
+    Ok(!collector.scripts.is_empty())
+}
+
+// ============================================================================
+// FFI Functions - Script Extraction
+// ============================================================================
+
+/// Count the JavaScript actions in a document: document-level scripts,
+/// the open action, and page, annotation and form field actions
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_count_javascript(_ctx: Handle, doc: Handle) -> i32 {
+    load_pdf(doc)
+        .and_then(|doc| document_scripts(&doc))
+        .map_or(0, |scripts| scripts.len() as i32)
+}
+
+/// Copy the text of script `index` (in [`pdf_count_javascript`] order)
+/// into `buf` as a NUL-terminated UTF-8 string, truncated to `size` bytes
+/// including the terminator
+///
+/// Returns the full length of the script in bytes, so a larger buffer can
+/// be retried, or -1 if there is no such script.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_javascript_at(
+    _ctx: Handle,
+    doc: Handle,
+    index: i32,
+    buf: *mut c_char,
+    size: i32,
+) -> i32 {
+    let Ok(scripts) = load_pdf(doc).and_then(|doc| document_scripts(&doc)) else {
+        return -1;
+    };
+    let Some(script) = usize::try_from(index).ok().and_then(|i| scripts.get(i)) else {
+        return -1;
+    };
+    let bytes = script.as_bytes();
+    if !buf.is_null() && size > 0 {
+        let len = bytes.len().min(size as usize - 1);
+        unsafe {
+            ptr::copy_nonoverlapping(bytes.as_ptr(), buf as *mut u8, len);
+            *buf.add(len) = 0;
+        }
+    }
+    bytes.len() as i32
+}
+
+/// Check whether the document's open action runs JavaScript
+/// Returns 1 if it does, 0 if not
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_has_open_action_js(_ctx: Handle, doc: Handle) -> i32 {
+    load_pdf(doc)
+        .and_then(|doc| has_open_action_js(&doc))
+        .map_or(0, i32::from)
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -864,6 +1105,53 @@ pub extern "C" fn pdf_js_is_enabled(js: Handle) -> i32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
+
+    /// A one-page PDF with a document-level script held in a compressed
+    /// stream and an open action script held in a string
+    fn scripted_pdf() -> Vec<u8> {
+        let script = b"function greet() { app.alert('hello'); }";
+        let compressed = crate::pdf::filter::encode_flate(script, 6).unwrap();
+        let mut stream = format!(
+            "<< /Length {} /Filter /FlateDecode >>\nstream\n",
+            compressed.len()
+        )
+        .into_bytes();
+        stream.extend_from_slice(&compressed);
+        stream.extend_from_slice(b"\nendstream");
+        let objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R /OpenAction << /S /JavaScript /JS (greet\\(\\);) >> \
+              /Names << /JavaScript << /Names [(greeting) 5 0 R] >> >> >>"
+                .to_vec(),
+            b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] >>".to_vec(),
+            stream,
+            b"<< /S /JavaScript /JS 4 0 R >>".to_vec(),
+        ];
+        build_pdf(&objects)
+    }
+
+    #[test]
+    fn test_extract_javascript() {
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(scripted_pdf()));
+        assert_eq!(pdf_count_javascript(0, doc), 2);
+        assert_eq!(pdf_has_open_action_js(0, doc), 1);
+
+        let mut buf = [0 as c_char; 64];
+        let len = pdf_javascript_at(0, doc, 0, buf.as_mut_ptr(), buf.len() as i32);
+        let text = unsafe { CStr::from_ptr(buf.as_ptr()) }.to_str().unwrap();
+        assert_eq!(text, "function greet() { app.alert('hello'); }");
+        assert_eq!(len as usize, text.len());
+
+        // Short buffers are truncated and terminated
+        let mut short = [0 as c_char; 9];
+        let len = pdf_javascript_at(0, doc, 1, short.as_mut_ptr(), short.len() as i32);
+        assert_eq!(len, 8);
+        let text = unsafe { CStr::from_ptr(short.as_ptr()) }.to_str().unwrap();
+        assert_eq!(text, "greet();");
+        assert_eq!(pdf_javascript_at(0, doc, 2, short.as_mut_ptr(), 9), -1);
+        DOCUMENTS.remove(doc);
+    }
 
     #[test]
     fn test_enable_disable_js() {
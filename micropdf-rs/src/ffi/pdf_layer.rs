@@ -3,7 +3,11 @@
 //! Provides support for PDF Optional Content Groups (OCG) which allow
 //! layers of content to be selectively shown or hidden.
 
-use crate::ffi::{Handle, HandleStore};
+use crate::ffi::document::open_pdf;
+use crate::ffi::{DOCUMENTS, Handle, HandleStore};
+use crate::pdf::document::Document;
+use crate::pdf::object::ObjRef;
+use crate::pdf::ocg::OptionalContent;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString, c_char};
 use std::ptr;
@@ -101,6 +105,8 @@ pub struct Layer {
     pub enabled: bool,
     /// Layer index
     pub index: i32,
+    /// The OCG dictionary, for layers read from the document
+    pub obj_ref: Option<ObjRef>,
 }
 
 // ============================================================================
@@ -120,6 +126,8 @@ pub struct OcgDescriptor {
     pub ui_elements: Vec<LayerConfigUi>,
     /// Whether changes have been made
     pub modified: bool,
+    /// Groups read from the document, kept in step with `layers`
+    content: OptionalContent,
 }
 
 impl Default for OcgDescriptor {
@@ -139,9 +147,39 @@ impl OcgDescriptor {
             current_config: 0,
             ui_elements: Vec::new(),
             modified: false,
+            content: OptionalContent::default(),
         }
     }
 
+    /// Descriptor for a document's /OCProperties, with layer visibility
+    /// from the default configuration
+    pub fn from_content(content: OptionalContent) -> Self {
+        let mut ocg = Self::new();
+        for group in content.groups() {
+            let index = ocg.add_layer(&group.name, group.enabled);
+            ocg.layers[index as usize].obj_ref = Some(group.obj_ref);
+        }
+        ocg.content = content;
+        ocg
+    }
+
+    /// Current visibility of the document's groups, for the interpreter
+    pub fn optional_content(&self) -> &OptionalContent {
+        &self.content
+    }
+
+    /// Show or hide a layer
+    pub fn set_layer_enabled(&mut self, index: i32, enabled: bool) {
+        let Some(layer) = self.get_layer_mut(index) else {
+            return;
+        };
+        layer.enabled = enabled;
+        if layer.obj_ref.is_some() {
+            self.content.set_enabled(index as usize, enabled);
+        }
+        self.modified = true;
+    }
+
     /// Add a layer
     pub fn add_layer(&mut self, name: &str, enabled: bool) -> i32 {
         let index = self.layers.len() as i32;
@@ -149,6 +187,7 @@ impl OcgDescriptor {
             name: name.to_string(),
             enabled,
             index,
+            obj_ref: None,
         });
         index
     }
@@ -237,6 +276,25 @@ impl OcgDescriptor {
 pub static OCG_STORE: LazyLock<Mutex<HashMap<DocumentHandle, OcgDescriptor>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
+/// The descriptor for `doc`, reading it from the document on first use
+fn descriptor(
+    store: &mut HashMap<DocumentHandle, OcgDescriptor>,
+    doc: DocumentHandle,
+) -> Option<&mut OcgDescriptor> {
+    if !store.contains_key(&doc) {
+        let ocg = load_descriptor(doc)?;
+        store.insert(doc, ocg);
+    }
+    store.get_mut(&doc)
+}
+
+/// Read the optional content groups of an open document
+fn load_descriptor(doc: DocumentHandle) -> Option<OcgDescriptor> {
+    let parsed = open_pdf(doc)?;
+    let content = OptionalContent::load(&parsed).ok()?;
+    Some(OcgDescriptor::from_content(content))
+}
+
 // ============================================================================
 // FFI Functions - Layer Count and Enumeration
 // ============================================================================
@@ -254,8 +312,8 @@ pub extern "C" fn pdf_count_layer_configs(_ctx: ContextHandle, doc: DocumentHand
 /// Count the number of layers.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_count_layers(_ctx: ContextHandle, doc: DocumentHandle) -> i32 {
-    let store = OCG_STORE.lock().unwrap();
-    if let Some(ocg) = store.get(&doc) {
+    let mut store = OCG_STORE.lock().unwrap();
+    if let Some(ocg) = descriptor(&mut store, doc) {
         return ocg.layer_count();
     }
     0
@@ -269,8 +327,8 @@ pub extern "C" fn pdf_layer_name(
     doc: DocumentHandle,
     layer: i32,
 ) -> *const c_char {
-    let store = OCG_STORE.lock().unwrap();
-    if let Some(ocg) = store.get(&doc) {
+    let mut store = OCG_STORE.lock().unwrap();
+    if let Some(ocg) = descriptor(&mut store, doc) {
         if let Some(l) = ocg.get_layer(layer) {
             if let Ok(cstr) = CString::new(l.name.clone()) {
                 return cstr.into_raw();
@@ -287,8 +345,8 @@ pub extern "C" fn pdf_layer_is_enabled(
     doc: DocumentHandle,
     layer: i32,
 ) -> i32 {
-    let store = OCG_STORE.lock().unwrap();
-    if let Some(ocg) = store.get(&doc) {
+    let mut store = OCG_STORE.lock().unwrap();
+    if let Some(ocg) = descriptor(&mut store, doc) {
         if let Some(l) = ocg.get_layer(layer) {
             return if l.enabled { 1 } else { 0 };
         }
@@ -305,11 +363,8 @@ pub extern "C" fn pdf_enable_layer(
     enabled: i32,
 ) {
     let mut store = OCG_STORE.lock().unwrap();
-    if let Some(ocg) = store.get_mut(&doc) {
-        if let Some(l) = ocg.get_layer_mut(layer) {
-            l.enabled = enabled != 0;
-            ocg.modified = true;
-        }
+    if let Some(ocg) = descriptor(&mut store, doc) {
+        ocg.set_layer_enabled(layer, enabled != 0);
     }
 }
 
@@ -542,11 +597,11 @@ pub extern "C" fn pdf_layer_config_ui_type_from_string(s: *const c_char) -> i32
 // ============================================================================
 
 /// Read OCG descriptor from document.
-/// Creates an OCG descriptor if none exists.
+/// Creates an empty OCG descriptor if the document has no optional content.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_read_ocg(_ctx: ContextHandle, doc: DocumentHandle) -> Handle {
     let mut store = OCG_STORE.lock().unwrap();
-    if !store.contains_key(&doc) {
+    if descriptor(&mut store, doc).is_none() {
         store.insert(doc, OcgDescriptor::new());
     }
     // Return the document handle as the OCG handle (they're linked)
@@ -561,21 +616,24 @@ pub extern "C" fn pdf_drop_ocg(_ctx: ContextHandle, doc: DocumentHandle) {
 }
 
 /// Check if an OCG is hidden.
+/// `ocg` is the object number of the group dictionary.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_is_ocg_hidden(
     _ctx: ContextHandle,
     doc: DocumentHandle,
     _rdb: Handle,
     _usage: *const c_char,
-    _ocg: Handle,
+    ocg: Handle,
 ) -> i32 {
-    // For now, return 0 (not hidden) - actual implementation would
-    // check the OCG state based on the usage string and current config
-    let store = OCG_STORE.lock().unwrap();
-    if store.contains_key(&doc) {
-        return 0; // Not hidden
-    }
-    0
+    let mut store = OCG_STORE.lock().unwrap();
+    let Some(desc) = descriptor(&mut store, doc) else {
+        return 0;
+    };
+    let hidden = desc
+        .layers
+        .iter()
+        .any(|l| !l.enabled && l.obj_ref.is_some_and(|r| r.num as Handle == ocg));
+    i32::from(hidden)
 }
 
 /// Set current layer configuration as the default.
@@ -724,6 +782,7 @@ pub extern "C" fn pdf_layer_free_string(_ctx: ContextHandle, s: *mut c_char) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
 
     #[test]
     fn test_ocg_descriptor_new() {
@@ -977,4 +1036,44 @@ mod tests {
 
         pdf_drop_ocg(0, doc);
     }
+
+    #[test]
+    fn test_ffi_layers_from_document() {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R /OCProperties << /OCGs [4 0 R 5 0 R] \
+             /D << /BaseState /OFF /ON [4 0 R] >> >> >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] >>",
+            "<< /Type /OCG /Name (Background) >>",
+            "<< /Type /OCG /Name (Watermark) >>",
+        ];
+        let data = build_pdf(&objects);
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(data));
+
+        assert_eq!(pdf_count_layers(0, doc), 2);
+        let name = pdf_layer_name(0, doc, 1);
+        assert_eq!(
+            unsafe { CStr::from_ptr(name) }.to_str().unwrap(),
+            "Watermark"
+        );
+        pdf_layer_free_string(0, name as *mut c_char);
+        assert_eq!(pdf_layer_is_enabled(0, doc, 0), 1);
+        assert_eq!(pdf_layer_is_enabled(0, doc, 1), 0);
+        assert_eq!(pdf_is_ocg_hidden(0, doc, 0, ptr::null(), 5), 1);
+
+        pdf_enable_layer(0, doc, 0, 0);
+        pdf_enable_layer(0, doc, 1, 1);
+        assert_eq!(pdf_layer_is_enabled(0, doc, 0), 0);
+        assert_eq!(pdf_is_ocg_hidden(0, doc, 0, ptr::null(), 4), 1);
+        assert_eq!(pdf_is_ocg_hidden(0, doc, 0, ptr::null(), 5), 0);
+        {
+            let store = OCG_STORE.lock().unwrap();
+            let content = store[&doc].optional_content();
+            assert!(!content.is_group_enabled(ObjRef::new(4, 0)));
+            assert!(content.is_group_enabled(ObjRef::new(5, 0)));
+        }
+
+        pdf_drop_ocg(0, doc);
+        DOCUMENTS.remove(doc);
+    }
 }
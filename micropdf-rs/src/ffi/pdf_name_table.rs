@@ -1,8 +1,13 @@
 //! PDF Name Table FFI Module
 //!
 //! Provides PDF name string optimization through interning and
-//! standard PDF name constants for efficient name comparisons.
+//! standard PDF name constants for efficient name comparisons, plus
+//! lookups in name trees and number trees.
 
+use super::Handle;
+use super::pdf_object::types::{PDF_OBJECTS, PdfObj, PdfObjHandle};
+use crate::pdf::name_tree;
+use crate::pdf::object::Object;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString, c_char};
 use std::ptr;
@@ -570,6 +575,57 @@ pub extern "C" fn pdf_name_table_hit_rate() -> f64 {
 // Tests
 // ============================================================================
 
+// ============================================================================
+// FFI Functions - Name and Number Trees
+// ============================================================================
+
+/// The tree behind `tree` in the parser's object model
+fn tree_object(tree: PdfObjHandle) -> Option<Object> {
+    let obj = PDF_OBJECTS.get(tree)?;
+    let guard = obj.lock().ok()?;
+    Some(guard.to_object())
+}
+
+/// Look up `name` in a name tree such as /Dests or /EmbeddedFiles
+///
+/// `pdf_lookup_name` is taken by name interning, hence the suffix. The
+/// tree must be built from direct objects. Returns a new object handle,
+/// or 0 if the name is not in the tree.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_lookup_name_tree(
+    _ctx: Handle,
+    tree: PdfObjHandle,
+    name: *const c_char,
+) -> PdfObjHandle {
+    if name.is_null() {
+        return 0;
+    }
+    // SAFETY: Caller guarantees name is a valid null-terminated C string
+    let key = unsafe { CStr::from_ptr(name) }.to_bytes();
+    let Some(tree) = tree_object(tree) else {
+        return 0;
+    };
+    match name_tree::lookup_name(&tree, key, |o| Ok(o.clone())) {
+        Ok(Some(value)) => PDF_OBJECTS.insert(PdfObj::from_object(&value)),
+        _ => 0,
+    }
+}
+
+/// Look up `n` in a number tree such as /PageLabels
+///
+/// The tree must be built from direct objects. Returns a new object
+/// handle, or 0 if the number is not in the tree.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_lookup_number(_ctx: Handle, tree: PdfObjHandle, n: i32) -> PdfObjHandle {
+    let Some(tree) = tree_object(tree) else {
+        return 0;
+    };
+    match name_tree::lookup_number(&tree, i64::from(n), |o| Ok(o.clone())) {
+        Ok(Some(value)) => PDF_OBJECTS.insert(PdfObj::from_object(&value)),
+        _ => 0,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -680,4 +736,29 @@ mod tests {
         assert_eq!(pdf_lookup_name(ptr::null()), -1);
         assert_eq!(pdf_name_eq_str(-1, ptr::null()), 0);
     }
+
+    #[test]
+    fn test_lookup_name_tree_limits() {
+        use crate::pdf::parser;
+
+        let tree = parser::parse_object(
+            b"<< /Kids [ << /Limits [(a) (c)] /Names [(a) 1 (c) 2] >> \
+                         << /Limits [(m) (z)] /Names [(m) 3 (z) << /D 4 >>] >> ] >>",
+        )
+        .unwrap();
+        let tree = PDF_OBJECTS.insert(PdfObj::from_object(&tree));
+
+        let found = pdf_lookup_name_tree(0, tree, c"m".as_ptr());
+        assert_eq!(
+            PDF_OBJECTS.get(found).unwrap().lock().unwrap().to_object(),
+            Object::Int(3)
+        );
+        let found = pdf_lookup_name_tree(0, tree, c"z".as_ptr());
+        let value = PDF_OBJECTS.get(found).unwrap().lock().unwrap().to_object();
+        assert_eq!(value.as_dict().unwrap().get("D").unwrap().as_int(), Some(4));
+        assert_eq!(pdf_lookup_name_tree(0, tree, c"d".as_ptr()), 0);
+        assert_eq!(pdf_lookup_number(0, tree, 1), 0);
+
+        PDF_OBJECTS.remove(tree);
+    }
 }
@@ -1,6 +1,7 @@
 //! PDF Object Types and Core Data Structures
 
 use super::super::{Handle, HandleStore};
+use crate::pdf::object::{Dict, Name, ObjRef, Object, PdfString};
 use std::sync::LazyLock;
 
 /// PDF Object type enumeration
@@ -146,6 +147,78 @@ impl PdfObj {
             refs: 1,
         }
     }
+
+    /// Convert from the parser's object model
+    pub fn from_object(obj: &Object) -> Self {
+        let obj_type = match obj {
+            Object::Null => PdfObjType::Null,
+            Object::Bool(b) => PdfObjType::Bool(*b),
+            Object::Int(i) => PdfObjType::Int(*i),
+            Object::Real(f) => PdfObjType::Real(*f),
+            Object::Name(n) => PdfObjType::Name(n.as_str().to_string()),
+            Object::String(s) => PdfObjType::String(s.as_bytes().to_vec()),
+            Object::Array(items) => {
+                PdfObjType::Array(items.iter().map(Self::from_object).collect())
+            }
+            Object::Dict(dict) => PdfObjType::Dict(from_dict(dict)),
+            Object::Ref(r) => PdfObjType::Indirect {
+                num: r.num,
+                generation: r.generation,
+            },
+            Object::Stream { dict, data } => PdfObjType::Stream {
+                dict: Box::new(Self {
+                    obj_type: PdfObjType::Dict(from_dict(dict)),
+                    ..Self::new_null()
+                }),
+                data: data.clone(),
+            },
+        };
+        Self {
+            obj_type,
+            ..Self::new_null()
+        }
+    }
+
+    /// Convert to the parser's object model
+    pub fn to_object(&self) -> Object {
+        match &self.obj_type {
+            PdfObjType::Null => Object::Null,
+            PdfObjType::Bool(b) => Object::Bool(*b),
+            PdfObjType::Int(i) => Object::Int(*i),
+            PdfObjType::Real(f) => Object::Real(*f),
+            PdfObjType::Name(n) => Object::Name(Name::new(n)),
+            PdfObjType::String(s) => Object::String(PdfString::new(s.clone())),
+            PdfObjType::Array(items) => {
+                Object::Array(items.iter().map(PdfObj::to_object).collect())
+            }
+            PdfObjType::Dict(entries) => Object::Dict(dict_entries(entries)),
+            PdfObjType::Indirect { num, generation } => Object::Ref(ObjRef::new(*num, *generation)),
+            PdfObjType::Stream { dict, data } => Object::Stream {
+                dict: match &dict.obj_type {
+                    PdfObjType::Dict(entries) => dict_entries(entries),
+                    _ => Dict::new(),
+                },
+                data: data.clone(),
+            },
+        }
+    }
+}
+
+/// Dictionary entries in key order, so conversions are reproducible
+fn from_dict(dict: &Dict) -> Vec<(String, PdfObj)> {
+    let mut entries: Vec<_> = dict
+        .iter()
+        .map(|(key, value)| (key.as_str().to_string(), PdfObj::from_object(value)))
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+fn dict_entries(entries: &[(String, PdfObj)]) -> Dict {
+    entries
+        .iter()
+        .map(|(key, value)| (Name::new(key), value.to_object()))
+        .collect()
 }
 
 /// Handle type for PDF objects
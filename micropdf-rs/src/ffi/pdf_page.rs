@@ -3,9 +3,18 @@
 //! Provides page loading, manipulation, and rendering capabilities for PDF documents.
 //! This module implements the MuPDF pdf_page API for handling PDF pages.
 
-use crate::ffi::{Handle, HandleStore};
+use crate::ffi::context::set_caught;
+use crate::ffi::document::open_pdf;
+use crate::ffi::pdf_object::types::{PDF_OBJECTS, PdfObj};
+use crate::ffi::pdf_redact;
+use crate::ffi::{DOCUMENTS, Handle, HandleStore};
+use crate::fitz::error::{Error, Result};
 use crate::fitz::geometry::{Matrix, Rect};
-use std::collections::HashMap;
+use crate::pdf::document::Document;
+use crate::pdf::object::{Dict, Name, ObjRef, Object};
+use crate::pdf::page::{PageBox, page_transform};
+use crate::pdf::write;
+use std::collections::{BTreeMap, HashMap};
 use std::ffi::{CStr, CString, c_char, c_void};
 use std::ptr;
 use std::sync::{Arc, LazyLock, Mutex};
@@ -239,39 +248,10 @@ impl PdfPage {
         }
     }
 
-    /// Get the transformation matrix for the page
+    /// Get the transformation matrix for the page: PDF user space to
+    /// page space, with the rotated box's top-left corner at the origin
     pub fn get_transform(&self, box_type: BoxType) -> Matrix {
-        let rect = self.get_box(box_type);
-
-        // Start with identity matrix
-        let mut ctm = Matrix::IDENTITY;
-
-        // Apply rotation
-        match self.rotation {
-            90 => {
-                ctm = Matrix::rotate(90.0);
-                ctm = ctm.concat(&Matrix::translate(rect.height(), 0.0));
-            }
-            180 => {
-                ctm = Matrix::rotate(180.0);
-                ctm = ctm.concat(&Matrix::translate(rect.width(), rect.height()));
-            }
-            270 => {
-                ctm = Matrix::rotate(270.0);
-                ctm = ctm.concat(&Matrix::translate(0.0, rect.width()));
-            }
-            _ => {}
-        }
-
-        // Apply user unit scaling
-        if (self.user_unit - 1.0).abs() > f32::EPSILON {
-            ctm = ctm.concat(&Matrix::scale(self.user_unit, self.user_unit));
-        }
-
-        // Translate to origin
-        ctm = ctm.concat(&Matrix::translate(-rect.x0, -rect.y0));
-
-        ctm
+        page_transform(self.get_box(box_type), self.rotation, self.user_unit)
     }
 
     /// Get the bounds of the page after transformation
@@ -335,12 +315,27 @@ pub extern "C" fn pdf_load_page(
     }
 
     let mut page = PdfPage::new(doc, number);
-
-    // Set default page properties
-    // In a real implementation, this would read from the document
-    page.media_box = Rect::new(0.0, 0.0, 612.0, 792.0); // US Letter
     page.in_doc = true;
 
+    // Pages of documents the PDF layer cannot parse keep the defaults
+    if let Some(pdf_page) = open_pdf(doc).and_then(|pdf| pdf.page(number as usize).ok()) {
+        page.media_box = pdf_page.media_box();
+        if pdf_page.dict().contains_key("CropBox") {
+            page.crop_box = Some(pdf_page.crop_box());
+        }
+        for (which, slot) in [
+            (PageBox::BleedBox, &mut page.bleed_box),
+            (PageBox::TrimBox, &mut page.trim_box),
+            (PageBox::ArtBox, &mut page.art_box),
+        ] {
+            if pdf_page.dict().contains_key(which.key()) {
+                *slot = Some(pdf_page.page_box(which));
+            }
+        }
+        page.rotation = pdf_page.rotation();
+        page.user_unit = pdf_page.user_unit();
+    }
+
     let handle = PDF_PAGES.insert(page);
 
     // Add to page cache
@@ -375,6 +370,9 @@ pub extern "C" fn pdf_drop_page(_ctx: ContextHandle, page: PageHandle) {
             // Remove from page cache
             if let Some(removed) = PDF_PAGES.remove(page) {
                 let page_guard = removed.lock().unwrap();
+                if page_guard.resources != 0 {
+                    PDF_OBJECTS.remove(page_guard.resources);
+                }
                 if let Ok(mut cache) = PAGE_CACHE.lock() {
                     if let Some(pages) = cache.get_mut(&page_guard.doc) {
                         pages.retain(|&h| h != page);
@@ -396,8 +394,11 @@ pub extern "C" fn pdf_count_pages(_ctx: ContextHandle, doc: DocumentHandle) -> i
         return 0;
     }
 
-    // In a real implementation, this would query the document's page tree
-    // For now, return a placeholder based on cached pages
+    if let Some(count) = open_pdf(doc).and_then(|pdf| pdf.page_count().ok()) {
+        return count as i32;
+    }
+
+    // Handles without a parseable document count the pages loaded for them
     if let Ok(cache) = PAGE_CACHE.lock() {
         if let Some(pages) = cache.get(&doc) {
             return pages.len() as i32;
@@ -456,15 +457,48 @@ pub extern "C" fn pdf_page_obj(_ctx: ContextHandle, page: PageHandle) -> PdfObjH
 }
 
 /// Get the page's resources dictionary
+///
+/// The dictionary is read from the document on first use, inherited from
+/// the page tree if the page has none of its own. Each category and each
+/// named resource in it is resolved, so `pdf_resource_lookup` can find
+/// them without the document. The handle is owned by the page.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_page_resources(_ctx: ContextHandle, page: PageHandle) -> PdfObjHandle {
     if let Some(page_arc) = PDF_PAGES.get(page) {
-        let page_guard = page_arc.lock().unwrap();
+        let mut page_guard = page_arc.lock().unwrap();
+        if page_guard.resources == 0 {
+            if let Some(resources) = load_page_resources(page_guard.doc, page_guard.number) {
+                page_guard.resources = PDF_OBJECTS.insert(PdfObj::from_object(&resources));
+            }
+        }
         return page_guard.resources;
     }
     0
 }
 
+/// Read a page's /Resources, resolving categories and their entries
+fn load_page_resources(doc: DocumentHandle, number: i32) -> Option<Object> {
+    let pdf = open_pdf(doc)?;
+    let page = pdf.page(usize::try_from(number).ok()?).ok()?;
+    let mut resolved = Dict::new();
+    for (category, entries) in page.resources(&pdf).ok()? {
+        let entries = match pdf.resolve(&entries).ok()? {
+            Object::Dict(entries) => Object::Dict(
+                entries
+                    .into_iter()
+                    .map(|(name, obj)| {
+                        let obj = pdf.resolve(&obj).unwrap_or_default();
+                        (name, obj)
+                    })
+                    .collect(),
+            ),
+            other => other,
+        };
+        resolved.insert(category, entries);
+    }
+    Some(Object::Dict(resolved))
+}
+
 /// Get the page's content stream
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_page_contents(_ctx: ContextHandle, page: PageHandle) -> PdfObjHandle {
@@ -495,7 +529,8 @@ pub extern "C" fn pdf_page_has_transparency(_ctx: ContextHandle, page: PageHandl
     0
 }
 
-/// Get the page's rotation
+/// Get the page's rotation: its own or inherited /Rotate, normalized to
+/// 0, 90, 180 or 270
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_page_rotation(_ctx: ContextHandle, page: PageHandle) -> i32 {
     if let Some(page_arc) = PDF_PAGES.get(page) {
@@ -505,6 +540,66 @@ pub extern "C" fn pdf_page_rotation(_ctx: ContextHandle, page: PageHandle) -> i3
     0
 }
 
+/// Set the page's rotation in degrees clockwise
+///
+/// `degrees` must be a multiple of 90 and is normalized to 0-270. The page
+/// object gets its own /Rotate in an incremental update of the document.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_set_page_rotation(ctx: ContextHandle, page: PageHandle, degrees: i32) {
+    if degrees % 90 != 0 {
+        set_caught(
+            ctx,
+            &Error::argument("page rotation must be a multiple of 90 degrees"),
+        );
+        return;
+    }
+    let Some(page_arc) = PDF_PAGES.get(page) else {
+        return;
+    };
+    let rotation = degrees.rem_euclid(360);
+    let (doc, number) = {
+        let mut page_guard = page_arc.lock().unwrap();
+        page_guard.rotation = rotation;
+        (page_guard.doc, page_guard.number)
+    };
+    if let Err(err) = save_page_rotation(doc, number, rotation) {
+        set_caught(ctx, &err);
+    }
+}
+
+/// Write /Rotate into a page object of an open document
+fn save_page_rotation(doc: DocumentHandle, number: i32, rotation: i32) -> Result<()> {
+    // Pages not backed by an open document only keep the value in memory
+    if DOCUMENTS.get(doc).is_none() {
+        return Ok(());
+    }
+    save_changes(doc, |pdf| {
+        let page_ref = pdf.page(number as usize)?.obj_ref();
+        let Object::Dict(mut dict) = pdf.load_object(page_ref)? else {
+            return Err(Error::format("page object is not a dictionary"));
+        };
+        dict.insert(Name::new("Rotate"), Object::Int(rotation as i64));
+        Ok(BTreeMap::from([(page_ref, Object::Dict(dict))]))
+    })
+}
+
+/// Apply the changes `changes` computes for a document as an incremental
+/// update
+pub(crate) fn save_changes(
+    doc: DocumentHandle,
+    changes: impl FnOnce(&Document) -> Result<BTreeMap<ObjRef, Object>>,
+) -> Result<()> {
+    let doc = DOCUMENTS
+        .get(doc)
+        .ok_or_else(|| Error::argument("invalid document handle"))?;
+    let pdf = doc.lock().unwrap().pdf()?;
+    let changes = changes(&pdf)?;
+    let mut out = Vec::new();
+    write::append_incremental(pdf.data(), &changes, &mut out)?;
+    doc.lock().unwrap().set_data(out);
+    Ok(())
+}
+
 /// Get the page's user unit
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_page_user_unit(_ctx: ContextHandle, page: PageHandle) -> f32 {
@@ -837,6 +932,101 @@ pub extern "C" fn pdf_drop_page_tree_internal(_ctx: ContextHandle, _doc: Documen
     // Clear page cache for document
 }
 
+/// Delete page `number` from the page tree
+///
+/// Deleting the only page is rejected; the error is recorded on the context.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_delete_page(ctx: ContextHandle, doc: DocumentHandle, number: i32) {
+    let result = usize::try_from(number)
+        .map_err(|_| Error::argument("negative page number"))
+        .and_then(|n| save_changes(doc, |pdf| crate::pdf::page::delete_page(pdf, n)));
+    if let Err(err) = result {
+        set_caught(ctx, &err);
+    }
+}
+
+/// Insert a page object so it becomes page `at`; -1 or the page count
+/// appends it
+///
+/// `page` is an indirect reference to a page object in the document, or a
+/// page dictionary to add as a new object.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_insert_page(
+    ctx: ContextHandle,
+    doc: DocumentHandle,
+    at: i32,
+    page: PdfObjHandle,
+) {
+    let Some(page) = PDF_OBJECTS.get(page) else {
+        set_caught(ctx, &Error::argument("invalid page object"));
+        return;
+    };
+    let page = page.lock().unwrap().to_object();
+    let result = save_changes(doc, |pdf| {
+        let at = match at {
+            -1 => pdf.page_count()?,
+            at => usize::try_from(at).map_err(|_| Error::argument("negative page number"))?,
+        };
+        crate::pdf::page::insert_page(pdf, at, page)
+    });
+    if let Err(err) = result {
+        set_caught(ctx, &err);
+    }
+}
+
+/// Move page `from` so it becomes page `to`
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_move_page(ctx: ContextHandle, doc: DocumentHandle, from: i32, to: i32) {
+    let result = match (usize::try_from(from), usize::try_from(to)) {
+        (Ok(from), Ok(to)) => save_changes(doc, |pdf| crate::pdf::page::move_page(pdf, from, to)),
+        _ => Err(Error::argument("negative page number")),
+    };
+    if let Err(err) = result {
+        set_caught(ctx, &err);
+    }
+}
+
+/// Create a new in-memory document holding copies of `count` pages
+/// listed in `pages`, in that order
+///
+/// Pages may repeat; the copies share resources but get their own page
+/// objects. Returns 0 on error, recording it on the context.
+///
+/// # Safety
+/// Caller must ensure pages points to count ints
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_subset(
+    ctx: ContextHandle,
+    doc: DocumentHandle,
+    pages: *const i32,
+    count: i32,
+) -> DocumentHandle {
+    if pages.is_null() || count <= 0 {
+        set_caught(ctx, &Error::argument("no pages selected"));
+        return 0;
+    }
+    let pages = unsafe { std::slice::from_raw_parts(pages, count as usize) };
+    let result = (|| -> Result<_> {
+        let pages = pages
+            .iter()
+            .map(|&p| usize::try_from(p).map_err(|_| Error::argument("negative page number")))
+            .collect::<Result<Vec<_>>>()?;
+        let doc = DOCUMENTS
+            .get(doc)
+            .ok_or_else(|| Error::argument("invalid document handle"))?;
+        let pdf = doc.lock().unwrap().pdf()?;
+        let subset = pdf.subset(&pages)?;
+        Ok(crate::ffi::document::Document::new(subset.data().clone()))
+    })();
+    match result {
+        Ok(subset) => DOCUMENTS.insert(subset),
+        Err(err) => {
+            set_caught(ctx, &err);
+            0
+        }
+    }
+}
+
 /// Flatten inheritable page items
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_flatten_inheritable_page_items(_ctx: ContextHandle, _pageobj: PdfObjHandle) {
@@ -982,17 +1172,46 @@ pub extern "C" fn pdf_new_pixmap_from_page_with_separations_and_usage(
 // FFI Functions - Redaction
 // ============================================================================
 
-/// Redact page content
+/// Apply the page's redaction annotations, removing the text, images and
+/// line art beneath them from the content stream.
+/// Returns 1 if content was changed, 0 otherwise; failures, such as an
+/// out-of-range page or an encrypted document, are caught on `ctx`
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_redact_page(
-    _ctx: ContextHandle,
-    _doc: DocumentHandle,
-    _page: PageHandle,
-    _opts: *mut RedactOptions,
+    ctx: ContextHandle,
+    doc: DocumentHandle,
+    page: PageHandle,
+    opts: *mut RedactOptions,
 ) -> i32 {
-    // In a real implementation, this would apply redactions to the page
-    // Returns 1 if content was changed, 0 otherwise
-    0
+    let Some(page) = PDF_PAGES.get(page) else {
+        set_caught(ctx, &Error::argument("invalid page handle"));
+        return 0;
+    };
+    let (page_doc, number) = {
+        let page = page.lock().unwrap();
+        (page.doc, page.number)
+    };
+    let doc = if doc != 0 { doc } else { page_doc };
+
+    let opts = if opts.is_null() {
+        pdf_redact::RedactOptions::new()
+    } else {
+        // SAFETY: Caller guarantees a non-null opts points to valid options
+        let opts = unsafe { &*opts };
+        pdf_redact::RedactOptions {
+            black_boxes: opts.black_boxes,
+            image_method: opts.image_method as i32,
+            line_art: opts.line_art as i32,
+            text: opts.text as i32,
+        }
+    };
+    match pdf_redact::apply_page_redactions(doc, number, &opts) {
+        Ok(applied) => i32::from(applied > 0),
+        Err(err) => {
+            set_caught(ctx, &err);
+            0
+        }
+    }
 }
 
 // ============================================================================
@@ -1064,6 +1283,7 @@ pub extern "C" fn pdf_nuke_annots(_ctx: ContextHandle, _page: PageHandle) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
 
     #[test]
     fn test_page_creation() {
@@ -1208,6 +1428,129 @@ mod tests {
         assert!(ctm.a != 1.0 || ctm.b != 0.0 || ctm.c != 0.0 || ctm.d != 1.0);
     }
 
+    /// A document handle for [`build_pdf`] of `objects`
+    fn xref_pdf(objects: &[&str]) -> Handle {
+        DOCUMENTS.insert(crate::ffi::document::Document::new(build_pdf(objects)))
+    }
+
+    #[test]
+    fn test_set_page_rotation() {
+        // Rotation inherited from the Pages node
+        let doc = xref_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 612 792] /Rotate 180 >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+        ]);
+
+        let page = pdf_load_page(0, doc, 0);
+        assert_eq!(pdf_page_rotation(0, page), 180);
+
+        pdf_set_page_rotation(0, page, 450);
+        assert_eq!(pdf_page_rotation(0, page), 90);
+        let bounds = pdf_bound_page(0, page, BoxType::CropBox as i32);
+        assert_eq!((bounds.width(), bounds.height()), (792.0, 612.0));
+
+        // Not a multiple of 90: unchanged
+        pdf_set_page_rotation(0, page, 45);
+        assert_eq!(pdf_page_rotation(0, page), 90);
+
+        // The rotation was written to the document
+        let fz_page = crate::ffi::document::fz_load_page(0, doc, 0);
+        let fz_bounds = crate::ffi::document::fz_bound_page(0, fz_page);
+        assert_eq!((fz_bounds.x1, fz_bounds.y1), (792.0, 612.0));
+        let reloaded = pdf_load_page(0, doc, 0);
+        assert_eq!(pdf_page_rotation(0, reloaded), 90);
+
+        crate::ffi::document::fz_drop_page(0, fz_page);
+        pdf_drop_page(0, reloaded);
+        pdf_drop_page(0, page);
+        DOCUMENTS.remove(doc);
+    }
+
+    /// Content stream of each page, in page order
+    fn page_contents(doc: Handle) -> Vec<String> {
+        let pdf = open_pdf(doc).unwrap();
+        (0..pdf.page_count().unwrap())
+            .map(|i| {
+                let page = pdf.page(i).unwrap();
+                let data = pdf.page_contents(&page).unwrap();
+                String::from_utf8_lossy(&data).trim().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_delete_insert_move_pages() {
+        let doc = xref_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R] /Count 3 /MediaBox [0 0 200 200] >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 6 0 R >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 7 0 R >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 8 0 R >>",
+            "<< /Length 5 >>\nstream\npage0\nendstream",
+            "<< /Length 5 >>\nstream\npage1\nendstream",
+            "<< /Length 5 >>\nstream\npage2\nendstream",
+        ]);
+
+        pdf_delete_page(0, doc, 1);
+        assert_eq!(pdf_count_pages(0, doc), 2);
+        assert_eq!(page_contents(doc), ["page0", "page2"]);
+
+        // The deleted page object can go back in, at the front
+        let page = PDF_OBJECTS.insert(PdfObj::from_object(&Object::Ref(ObjRef::new(4, 0))));
+        pdf_insert_page(0, doc, 0, page);
+        assert_eq!(page_contents(doc), ["page1", "page0", "page2"]);
+
+        pdf_move_page(0, doc, 0, 2);
+        assert_eq!(page_contents(doc), ["page0", "page2", "page1"]);
+        let pdf = open_pdf(doc).unwrap();
+        let Object::Dict(root) = pdf.load_object(ObjRef::new(2, 0)).unwrap() else {
+            panic!("page tree root is not a dictionary");
+        };
+        assert_eq!(root.get("Count").and_then(Object::as_int), Some(3));
+
+        PDF_OBJECTS.remove(page);
+        DOCUMENTS.remove(doc);
+    }
+
+    #[test]
+    fn test_subset_pages() {
+        let doc = xref_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R] /Count 3 /MediaBox [0 0 200 200] >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 6 0 R >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 7 0 R >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 8 0 R >>",
+            "<< /Length 5 >>\nstream\npage0\nendstream",
+            "<< /Length 5 >>\nstream\npage1\nendstream",
+            "<< /Length 5 >>\nstream\npage2\nendstream",
+        ]);
+
+        let subset = pdf_subset(0, doc, [2, 0].as_ptr(), 2);
+        assert_ne!(subset, 0);
+        assert_eq!(pdf_count_pages(0, subset), 2);
+        assert_eq!(page_contents(subset), ["page2", "page0"]);
+        // The source is untouched
+        assert_eq!(page_contents(doc), ["page0", "page1", "page2"]);
+
+        assert_eq!(pdf_subset(0, doc, [0, 5].as_ptr(), 2), 0);
+
+        DOCUMENTS.remove(subset);
+        DOCUMENTS.remove(doc);
+    }
+
+    #[test]
+    fn test_delete_last_page_rejected() {
+        let doc = xref_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+        ]);
+        pdf_delete_page(0, doc, 0);
+        assert_eq!(pdf_count_pages(0, doc), 1);
+        DOCUMENTS.remove(doc);
+    }
+
     #[test]
     fn test_count_pages() {
         let ctx = 1;
@@ -3,7 +3,13 @@
 //! Provides support for PDF portfolios (packages/collections), including
 //! embedded file management, collection structure, and navigator schema.
 
-use crate::ffi::{Handle, HandleStore};
+use crate::ffi::buffer::Buffer;
+use crate::ffi::context::set_caught;
+use crate::ffi::document::open_pdf;
+use crate::ffi::pdf_page::save_changes;
+use crate::ffi::{BUFFERS, Handle, HandleStore};
+use crate::fitz::error::Error;
+use crate::pdf::embedded;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString, c_char};
 use std::ptr;
@@ -450,6 +456,90 @@ pub extern "C" fn pdf_portfolio_remove_file(
     0
 }
 
+// ============================================================================
+// FFI Functions - Document Embedded Files
+// ============================================================================
+
+/// Attach a file to the document under `name` in /Names /EmbeddedFiles,
+/// replacing any attachment with the same name.
+///
+/// Returns 1 on success, 0 on error (recorded on the context).
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_add_embedded_file(
+    ctx: ContextHandle,
+    doc: DocumentHandle,
+    name: *const c_char,
+    data: *const u8,
+    len: usize,
+    mime_type: *const c_char,
+) -> i32 {
+    if name.is_null() || (data.is_null() && len > 0) {
+        set_caught(ctx, &Error::argument("missing embedded file name or data"));
+        return 0;
+    }
+    let name = unsafe { CStr::from_ptr(name).to_string_lossy() };
+    let mime =
+        (!mime_type.is_null()).then(|| unsafe { CStr::from_ptr(mime_type).to_string_lossy() });
+    let contents = if len == 0 {
+        &[][..]
+    } else {
+        unsafe { std::slice::from_raw_parts(data, len) }
+    };
+    match save_changes(doc, |pdf| {
+        embedded::add_embedded_file(pdf, &name, contents, mime.as_deref())
+    }) {
+        Ok(()) => 1,
+        Err(err) => {
+            set_caught(ctx, &err);
+            0
+        }
+    }
+}
+
+/// Count the files attached through /Names /EmbeddedFiles.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_count_embedded_files(ctx: ContextHandle, doc: DocumentHandle) -> i32 {
+    let Some(pdf) = open_pdf(doc) else {
+        return 0;
+    };
+    match embedded::count_embedded_files(&pdf) {
+        Ok(count) => count as i32,
+        Err(err) => {
+            set_caught(ctx, &err);
+            0
+        }
+    }
+}
+
+/// Load the contents of the attachment at `index`, in name order, into a
+/// new buffer.
+///
+/// Returns 0 on error, including a stored checksum that does not match.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_extract_embedded_file(
+    ctx: ContextHandle,
+    doc: DocumentHandle,
+    index: i32,
+) -> BufferHandle {
+    let Some(pdf) = open_pdf(doc) else {
+        return 0;
+    };
+    let file = usize::try_from(index)
+        .map_err(|_| Error::argument("negative embedded file index"))
+        .and_then(|index| embedded::load_embedded_file(&pdf, index))
+        .and_then(|file| match file.checksum_matches() {
+            true => Ok(file),
+            false => Err(Error::format(format!("{} fails its checksum", file.name))),
+        });
+    match file {
+        Ok(file) => BUFFERS.insert(Buffer::from_vec(file.data)),
+        Err(err) => {
+            set_caught(ctx, &err);
+            0
+        }
+    }
+}
+
 // ============================================================================
 // FFI Functions - File Parameters
 // ============================================================================
@@ -721,6 +811,7 @@ pub extern "C" fn pdf_collection_view_to_string(_ctx: ContextHandle, view: i32)
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
 
     #[test]
     fn test_af_relationship_constants() {
@@ -925,4 +1016,41 @@ mod tests {
             pdf_portfolio_free_string(s);
         }
     }
+
+    #[test]
+    fn test_document_embedded_file_round_trip() {
+        use crate::ffi::DOCUMENTS;
+        use md5::{Digest, Md5};
+
+        let pdf = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [] /Count 0 >>",
+        ]);
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(pdf));
+        assert_eq!(pdf_count_embedded_files(0, doc), 0);
+
+        let text = b"Line one\nLine two\n";
+        let added = pdf_add_embedded_file(
+            0,
+            doc,
+            c"readme.txt".as_ptr(),
+            text.as_ptr(),
+            text.len(),
+            c"text/plain".as_ptr(),
+        );
+        assert_eq!(added, 1);
+        assert_eq!(pdf_count_embedded_files(0, doc), 1);
+
+        let buf = pdf_extract_embedded_file(0, doc, 0);
+        assert_ne!(buf, 0);
+        assert_eq!(BUFFERS.get(buf).unwrap().lock().unwrap().data(), text);
+        assert_eq!(pdf_extract_embedded_file(0, doc, 1), 0);
+
+        let pdf = open_pdf(doc).unwrap();
+        let file = embedded::load_embedded_file(&pdf, 0).unwrap();
+        assert_eq!(file.checksum, Some(<[u8; 16]>::from(Md5::digest(text))));
+
+        BUFFERS.remove(buf);
+        DOCUMENTS.remove(doc);
+    }
 }
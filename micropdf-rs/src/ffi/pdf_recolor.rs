@@ -3,7 +3,15 @@
 //! Provides PDF color conversion functionality including page recoloring,
 //! shade recoloring, and output intent management.
 
-use crate::ffi::{Handle, HandleStore};
+use crate::ffi::separation::{AlternateSpace, SpotColorspace};
+use crate::ffi::{DOCUMENTS, Handle, HandleStore};
+use crate::fitz::error::{Error, Result};
+use crate::pdf::content::{self, Operation};
+use crate::pdf::document::Document;
+use crate::pdf::filter::{FilterChain, FilterType, encode_dct_components, encode_flate};
+use crate::pdf::object::{Dict, Name, ObjRef, Object, PdfString};
+use crate::pdf::write;
+use std::collections::{BTreeMap, HashSet};
 use std::ffi::c_void;
 use std::sync::LazyLock;
 
@@ -234,47 +242,591 @@ pub extern "C" fn pdf_recolor_options_is_valid(opts: *const RecolorOptions) -> i
     unsafe { if (*opts).is_valid() { 1 } else { 0 } }
 }
 
+// ============================================================================
+// Gray Conversion
+// ============================================================================
+
+/// JPEG quality for images that were JPEG before conversion to gray
+const GRAY_JPEG_QUALITY: u8 = 85;
+
+/// Maximum nesting of form XObjects converted
+const MAX_FORM_DEPTH: usize = 16;
+
+/// Filters whose images are left alone, with their inline abbreviations
+const SKIPPED_FILTERS: [&str; 4] = ["CCITTFaxDecode", "CCF", "JBIG2Decode", "JPXDecode"];
+
+/// A colorspace whose colors can be mapped to gray
+#[derive(Debug, Clone)]
+enum SourceSpace {
+    Process(AlternateSpace),
+    Spot(SpotColorspace),
+    /// Pattern, Indexed and unknown spaces, whose colors are kept
+    Other,
+}
+
+impl SourceSpace {
+    /// The space named by a `cs` operand or image /ColorSpace, looking
+    /// names other than the device spaces up in `resources`
+    fn load(doc: &Document, resources: &Dict, cs: &Object) -> Self {
+        let obj = match cs {
+            Object::Name(n) => match n.as_str() {
+                "DeviceGray" | "G" | "DeviceRGB" | "RGB" | "DeviceCMYK" | "CMYK" => cs.clone(),
+                "Pattern" => return Self::Other,
+                name => match doc.resolve_key(resources, "ColorSpace") {
+                    Ok(Some(Object::Dict(spaces))) => match spaces.get(name) {
+                        Some(obj) => obj.clone(),
+                        None => return Self::Other,
+                    },
+                    _ => return Self::Other,
+                },
+            },
+            other => other.clone(),
+        };
+        let Ok(obj) = doc.resolve(&obj) else {
+            return Self::Other;
+        };
+        let family = match &obj {
+            Object::Array(items) => items.first().and_then(Object::as_name),
+            _ => None,
+        };
+        match family.map(|f| f.as_str()) {
+            Some("Separation" | "DeviceN") => {
+                SpotColorspace::load(doc, &obj).map_or(Self::Other, Self::Spot)
+            }
+            Some("Indexed" | "I" | "Pattern") => Self::Other,
+            _ => AlternateSpace::load(doc, &obj).map_or(Self::Other, Self::Process),
+        }
+    }
+
+    fn n(&self) -> usize {
+        match self {
+            Self::Process(space) => space.n(),
+            Self::Spot(space) => space.colorants.len(),
+            Self::Other => 0,
+        }
+    }
+
+    fn is_gray(&self) -> bool {
+        matches!(self, Self::Process(AlternateSpace::Gray))
+    }
+
+    /// Gray level of a color in this space
+    fn gray(&self, color: &[f32]) -> f32 {
+        let rgb = match self {
+            Self::Process(space) => space.to_rgb(color),
+            Self::Spot(space) => space.to_rgb(color),
+            Self::Other => [0.0; 3],
+        };
+        pdf_rgb_to_gray(rgb[0], rgb[1], rgb[2])
+    }
+
+    /// The color `cs` selects along with the space
+    fn initial_color(&self) -> Vec<f32> {
+        match self {
+            Self::Process(AlternateSpace::Cmyk) => vec![0.0, 0.0, 0.0, 1.0],
+            Self::Spot(space) => vec![1.0; space.colorants.len()],
+            _ => vec![0.0; self.n()],
+        }
+    }
+}
+
+/// Convert 8-bit samples in `space` to one gray byte per pixel
+fn gray_samples(space: &SourceSpace, samples: &[u8], pixels: usize) -> Option<Vec<u8>> {
+    let n = space.n();
+    if n == 0 || samples.len() < pixels * n {
+        return None;
+    }
+    let to_byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    if n == 1 {
+        let table: Vec<u8> = (0..=255u8)
+            .map(|v| to_byte(space.gray(&[v as f32 / 255.0])))
+            .collect();
+        return Some(
+            samples[..pixels]
+                .iter()
+                .map(|&v| table[v as usize])
+                .collect(),
+        );
+    }
+    let mut color = vec![0.0; n];
+    let gray = samples[..pixels * n]
+        .chunks_exact(n)
+        .map(|pixel| {
+            for (c, &v) in color.iter_mut().zip(pixel) {
+                *c = v as f32 / 255.0;
+            }
+            to_byte(space.gray(&color))
+        })
+        .collect();
+    Some(gray)
+}
+
+/// `[/Indexed base hival lookup]` with its palette converted to gray
+fn gray_indexed(doc: &Document, resources: &Dict, items: &[Object]) -> Option<Object> {
+    let base = SourceSpace::load(doc, resources, items.get(1)?);
+    if base.is_gray() {
+        return None;
+    }
+    let hival = items.get(2)?.as_int()?.clamp(0, 255) as usize;
+    let lookup = match doc.resolve(items.get(3)?).ok()? {
+        Object::String(s) => s.as_bytes().to_vec(),
+        stream @ Object::Stream { .. } => doc.stream_data(&stream).ok()?,
+        _ => return None,
+    };
+    let palette = gray_samples(&base, &lookup, hival + 1)?;
+    Some(Object::Array(vec![
+        Object::Name(Name::new("Indexed")),
+        Object::Name(Name::new("DeviceGray")),
+        Object::Int(hival as i64),
+        Object::String(PdfString::new(palette)),
+    ]))
+}
+
+/// Names of the filters in a /Filter value
+fn filter_names(filter: Option<&Object>) -> Vec<String> {
+    match filter {
+        Some(Object::Name(n)) => vec![n.as_str().to_string()],
+        Some(Object::Array(arr)) => arr
+            .iter()
+            .filter_map(Object::as_name)
+            .map(|n| n.as_str().to_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// An image XObject converted to DeviceGray, or `None` if it is already
+/// gray or cannot be converted
+fn gray_image(doc: &Document, image: &Object) -> Option<Object> {
+    let Object::Stream { dict, data } = image else {
+        return None;
+    };
+    if dict.get("ImageMask").and_then(Object::as_bool) == Some(true) {
+        return None;
+    }
+    let no_resources = Dict::new();
+    let cs = doc.resolve(dict.get("ColorSpace")?).ok()?;
+    if let Object::Array(items) = &cs {
+        if items.first().and_then(Object::as_name).map(|n| n.as_str()) == Some("Indexed") {
+            let mut dict = dict.clone();
+            dict.insert(
+                "ColorSpace".into(),
+                gray_indexed(doc, &no_resources, items)?,
+            );
+            return Some(Object::Stream {
+                dict,
+                data: data.clone(),
+            });
+        }
+    }
+
+    let filters = filter_names(doc.resolve_key(dict, "Filter").ok()?.as_ref());
+    if dict.get("BitsPerComponent").and_then(Object::as_int) != Some(8)
+        || dict.contains_key("DecodeParms")
+        || dict.contains_key("Decode")
+        || filters
+            .iter()
+            .any(|f| SKIPPED_FILTERS.contains(&f.as_str()))
+    {
+        return None;
+    }
+    let space = SourceSpace::load(doc, &no_resources, &cs);
+    if space.is_gray() {
+        return None;
+    }
+    let w = dict.get("Width").and_then(Object::as_int)?.max(0) as u32;
+    let h = dict.get("Height").and_then(Object::as_int)?.max(0) as u32;
+    let samples = doc.stream_data(image).ok()?;
+    let gray = gray_samples(&space, &samples, w as usize * h as usize)?;
+
+    let (data, filter) = if filters.iter().any(|f| f == "DCTDecode" || f == "DCT") {
+        let data = encode_dct_components(&gray, w, h, 1, GRAY_JPEG_QUALITY).ok()?;
+        (data, "DCTDecode")
+    } else {
+        (encode_flate(&gray, 6).ok()?, "FlateDecode")
+    };
+    let mut dict = dict.clone();
+    dict.insert("ColorSpace".into(), Object::Name(Name::new("DeviceGray")));
+    dict.insert("Filter".into(), Object::Name(Name::new(filter)));
+    dict.insert("Length".into(), Object::Int(data.len() as i64));
+    Some(Object::Stream { dict, data })
+}
+
+/// An inline image (a `BI` operation) converted to uncompressed
+/// DeviceGray, or `None` if it is already gray or cannot be converted
+fn gray_inline_image(doc: &Document, resources: &Dict, op: &Operation) -> Option<Operation> {
+    let dict = op.operands.first()?.as_dict()?;
+    let data = op.operands.get(1)?.as_string()?.as_bytes();
+    let get = |short: &str, long: &str| dict.get(short).or_else(|| dict.get(long));
+    if get("IM", "ImageMask").and_then(Object::as_bool) == Some(true) {
+        return None;
+    }
+    let cs = get("CS", "ColorSpace")?;
+    let indexed = match cs {
+        Object::Array(items) => items
+            .first()
+            .and_then(Object::as_name)
+            .is_some_and(|n| matches!(n.as_str(), "I" | "Indexed"))
+            .then_some(items),
+        _ => None,
+    };
+    let mut dict = dict.clone();
+    dict.remove("ColorSpace");
+    if let Some(items) = indexed {
+        dict.insert("CS".into(), gray_indexed(doc, resources, items)?);
+        return Some(Operation::new(
+            "BI",
+            vec![Object::Dict(dict), op.operands[1].clone()],
+        ));
+    }
+
+    let filters = filter_names(get("F", "Filter"));
+    if get("BPC", "BitsPerComponent").and_then(Object::as_int) != Some(8)
+        || get("DP", "DecodeParms").is_some()
+        || get("D", "Decode").is_some()
+        || filters
+            .iter()
+            .any(|f| SKIPPED_FILTERS.contains(&f.as_str()))
+    {
+        return None;
+    }
+    let space = SourceSpace::load(doc, resources, cs);
+    if space.is_gray() {
+        return None;
+    }
+    let w = get("W", "Width").and_then(Object::as_int)?.max(0) as usize;
+    let h = get("H", "Height").and_then(Object::as_int)?.max(0) as usize;
+    let mut chain = FilterChain::new();
+    for name in &filters {
+        chain.add(FilterType::from_name(name)?);
+    }
+    let samples = chain.decode(data.to_vec()).ok()?;
+    let gray = gray_samples(&space, &samples, w * h)?;
+
+    for key in ["F", "Filter", "DP", "DecodeParms"] {
+        dict.remove(key);
+    }
+    dict.insert("CS".into(), Object::Name(Name::new("G")));
+    Some(Operation::new(
+        "BI",
+        vec![Object::Dict(dict), Object::String(PdfString::new(gray))],
+    ))
+}
+
+/// Rewrite the color operators and inline images of a content stream to
+/// DeviceGray
+fn gray_content(
+    doc: &Document,
+    ops: Vec<Operation>,
+    resources: &Dict,
+    stats: &mut RecolorStats,
+) -> Vec<Operation> {
+    let device = |space| SourceSpace::Process(space);
+    // Spaces the original content selected, so later `sc` operands can be
+    // read in them
+    let mut fill = device(AlternateSpace::Gray);
+    let mut stroke = device(AlternateSpace::Gray);
+    let mut stack = Vec::new();
+    let mut out = Vec::with_capacity(ops.len());
+    for op in ops {
+        let numbers: Option<Vec<f32>> = op
+            .operands
+            .iter()
+            .map(|o| o.as_real().map(|v| v as f32))
+            .collect();
+        let is_stroke = op.operator.starts_with(|c: char| c.is_ascii_uppercase());
+        let gray_op = if is_stroke { "G" } else { "g" };
+        match op.operator.as_str() {
+            "q" => stack.push((fill.clone(), stroke.clone())),
+            "Q" => {
+                if let Some((f, s)) = stack.pop() {
+                    fill = f;
+                    stroke = s;
+                }
+            }
+            "g" | "G" | "rg" | "RG" | "k" | "K" => {
+                let space = device(match op.operator.as_str() {
+                    "g" | "G" => AlternateSpace::Gray,
+                    "rg" | "RG" => AlternateSpace::Rgb,
+                    _ => AlternateSpace::Cmyk,
+                });
+                let converted = match &numbers {
+                    Some(color) if !space.is_gray() && color.len() == space.n() => Some(
+                        Operation::new(gray_op, vec![Object::Real(space.gray(color) as f64)]),
+                    ),
+                    _ => None,
+                };
+                *(if is_stroke { &mut stroke } else { &mut fill }) = space;
+                if let Some(converted) = converted {
+                    stats.colors_converted += 1;
+                    out.push(converted);
+                    continue;
+                }
+            }
+            "cs" | "CS" => {
+                let space = op.operands.first().map_or(SourceSpace::Other, |cs| {
+                    SourceSpace::load(doc, resources, cs)
+                });
+                let convert = !matches!(space, SourceSpace::Other) && !space.is_gray();
+                let initial = space.initial_color();
+                if convert {
+                    out.push(Operation::new(
+                        op.operator.clone(),
+                        vec![Object::Name(Name::new("DeviceGray"))],
+                    ));
+                    out.push(Operation::new(
+                        gray_op,
+                        vec![Object::Real(space.gray(&initial) as f64)],
+                    ));
+                }
+                *(if is_stroke { &mut stroke } else { &mut fill }) = space;
+                if convert {
+                    continue;
+                }
+            }
+            "sc" | "scn" | "SC" | "SCN" => {
+                let space = if is_stroke { &stroke } else { &fill };
+                if let Some(color) = &numbers {
+                    if !matches!(space, SourceSpace::Other)
+                        && !space.is_gray()
+                        && color.len() == space.n()
+                    {
+                        let gray = space.gray(color);
+                        stats.colors_converted += 1;
+                        out.push(Operation::new(
+                            op.operator.clone(),
+                            vec![Object::Real(gray as f64)],
+                        ));
+                        continue;
+                    }
+                }
+            }
+            "BI" => {
+                if let Some(image) = gray_inline_image(doc, resources, &op) {
+                    stats.images_processed += 1;
+                    out.push(image);
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push(op);
+    }
+    out
+}
+
+/// A content stream object holding `ops`
+fn content_stream(ops: &[Operation]) -> Result<Object> {
+    let mut dict = Dict::new();
+    dict.insert(Name::new("Filter"), Object::Name(Name::new("FlateDecode")));
+    Ok(Object::Stream {
+        dict,
+        data: encode_flate(&content::write_content(ops), 6)?,
+    })
+}
+
+/// Convert the images and form XObjects in `resources`, and those of the
+/// forms, to gray
+fn gray_resources(
+    doc: &Document,
+    resources: &Dict,
+    depth: usize,
+    visited: &mut HashSet<ObjRef>,
+    changes: &mut BTreeMap<ObjRef, Object>,
+    stats: &mut RecolorStats,
+) -> Result<()> {
+    let Some(Object::Dict(xobjects)) = doc.resolve_key(resources, "XObject")? else {
+        return Ok(());
+    };
+    for xobj in xobjects.values() {
+        let Some(obj_ref) = xobj.as_obj_ref() else {
+            continue;
+        };
+        if !visited.insert(obj_ref) {
+            continue;
+        }
+        let obj = doc.load_object(obj_ref)?;
+        let Object::Stream { dict, .. } = &obj else {
+            continue;
+        };
+        match dict
+            .get("Subtype")
+            .and_then(Object::as_name)
+            .map(|n| n.as_str())
+        {
+            Some("Image") => {
+                if let Some(image) = gray_image(doc, &obj) {
+                    stats.images_processed += 1;
+                    changes.insert(obj_ref, image);
+                }
+            }
+            Some("Form") if depth < MAX_FORM_DEPTH => {
+                let form_resources = match doc.resolve_key(dict, "Resources")? {
+                    Some(Object::Dict(d)) => d,
+                    _ => resources.clone(),
+                };
+                let ops = content::parse_content(&doc.stream_data(&obj)?)?;
+                let ops = gray_content(doc, ops, &form_resources, stats);
+                let Object::Stream {
+                    dict: new_dict,
+                    data,
+                } = content_stream(&ops)?
+                else {
+                    continue;
+                };
+                let mut form = dict.clone();
+                form.remove("DecodeParms");
+                form.extend(new_dict);
+                changes.insert(obj_ref, Object::Stream { dict: form, data });
+                gray_resources(doc, &form_resources, depth + 1, visited, changes, stats)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Convert the pages `first..=last` of `doc` to grayscale
+///
+/// Color operators in page and form XObject content become DeviceGray
+/// operators, and image XObjects and inline images are converted to
+/// DeviceGray, using luminance weights. Pattern and Indexed colors keep
+/// their space (Indexed palettes are converted), and shadings and
+/// annotation appearances are left alone.
+fn recolor_to_gray(
+    doc: &Document,
+    first: usize,
+    last: usize,
+) -> Result<(BTreeMap<ObjRef, Object>, RecolorStats)> {
+    let mut changes = BTreeMap::new();
+    let mut stats = RecolorStats::default();
+    let mut visited = HashSet::new();
+    let mut next_num = doc
+        .trailer()
+        .get("Size")
+        .and_then(Object::as_int)
+        .unwrap_or(1)
+        .max(1) as i32;
+
+    for index in first..=last.min(doc.page_count()?.saturating_sub(1)) {
+        let page = doc.page(index)?;
+        let resources = match doc.resolve_key(page.dict(), "Resources")? {
+            Some(Object::Dict(d)) => d,
+            _ => Dict::new(),
+        };
+        let ops = content::parse_content(&doc.page_contents(&page)?)?;
+        let ops = gray_content(doc, ops, &resources, &mut stats);
+
+        match page.dict().get("Contents") {
+            Some(Object::Ref(r)) => {
+                // Pages sharing a content stream convert it the same way
+                if visited.insert(*r) {
+                    changes.insert(*r, content_stream(&ops)?);
+                }
+            }
+            Some(_) => {
+                let target = ObjRef::new(next_num, 0);
+                next_num += 1;
+                changes.insert(target, content_stream(&ops)?);
+                let Object::Dict(mut page_obj) = doc.load_object(page.obj_ref())? else {
+                    return Err(Error::format("page is not a dictionary"));
+                };
+                page_obj.insert(Name::new("Contents"), Object::Ref(target));
+                changes.insert(page.obj_ref(), Object::Dict(page_obj));
+            }
+            None => {}
+        }
+        gray_resources(doc, &resources, 0, &mut visited, &mut changes, &mut stats)?;
+        stats.pages_processed += 1;
+    }
+    Ok((changes, stats))
+}
+
+/// Convert pages of the document behind `doc` to grayscale in an
+/// incremental update
+fn recolor_document_to_gray(doc: DocumentHandle, first: i32, last: i32) -> Result<RecolorStats> {
+    let doc = DOCUMENTS
+        .get(doc)
+        .ok_or_else(|| Error::argument("invalid document handle"))?;
+    let mut doc = doc.lock().unwrap();
+
+    let pdf = doc.pdf()?;
+    if first < 0 || last < first {
+        return Err(Error::argument("invalid page range"));
+    }
+    let (changes, stats) = recolor_to_gray(&pdf, first as usize, last as usize)?;
+    if !changes.is_empty() {
+        let mut out = Vec::new();
+        write::append_incremental(pdf.data(), &changes, &mut out)?;
+        doc.set_data(out);
+    }
+    Ok(stats)
+}
+
+/// Whether `opts` asks for conversion to gray, the only target supported
+fn converts_to_gray(opts: *const RecolorOptions) -> bool {
+    !opts.is_null() && unsafe { (*opts).num_comp } == RECOLOR_GRAY
+}
+
 // ============================================================================
 // FFI Functions - Page Recoloring
 // ============================================================================
 
 /// Recolor a given document page.
+///
+/// Only conversion to gray is supported.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_recolor_page(
     _ctx: ContextHandle,
-    _doc: DocumentHandle,
-    _pagenum: i32,
-    _opts: *const RecolorOptions,
+    doc: DocumentHandle,
+    pagenum: i32,
+    opts: *const RecolorOptions,
 ) {
-    // In a full implementation, this would:
-    // 1. Load the page content stream
-    // 2. Process all color operators (g, rg, k, cs, scn, etc.)
-    // 3. Convert colors to target colorspace
-    // 4. Rewrite the content stream with new colors
+    if converts_to_gray(opts) {
+        let _ = recolor_document_to_gray(doc, pagenum, pagenum);
+    }
 }
 
 /// Recolor all pages in a document.
+///
+/// Only conversion to gray is supported.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_recolor_document(
     _ctx: ContextHandle,
-    _doc: DocumentHandle,
-    _opts: *const RecolorOptions,
+    doc: DocumentHandle,
+    opts: *const RecolorOptions,
 ) -> RecolorStats {
-    // In a full implementation, this would recolor all pages
-    RecolorStats::default()
+    if !converts_to_gray(opts) {
+        return RecolorStats::default();
+    }
+    recolor_document_to_gray(doc, 0, i32::MAX).unwrap_or_default()
 }
 
-/// Recolor a range of pages.
+/// Recolor a range of pages, `start_page` to `end_page` inclusive.
+///
+/// Only conversion to gray is supported.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_recolor_pages(
     _ctx: ContextHandle,
-    _doc: DocumentHandle,
-    _start_page: i32,
-    _end_page: i32,
-    _opts: *const RecolorOptions,
+    doc: DocumentHandle,
+    start_page: i32,
+    end_page: i32,
+    opts: *const RecolorOptions,
 ) -> RecolorStats {
-    // In a full implementation, this would recolor specified pages
-    RecolorStats::default()
+    if !converts_to_gray(opts) {
+        return RecolorStats::default();
+    }
+    recolor_document_to_gray(doc, start_page, end_page).unwrap_or_default()
+}
+
+/// Convert a whole document to grayscale for cheaper printing.
+///
+/// Color operators (`rg`, `RG`, `k`, `K`, `sc`, ...) become gray operators
+/// and image XObjects and inline images become DeviceGray, using the
+/// luminance weights 0.299, 0.587 and 0.114.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_recolor_to_gray(_ctx: ContextHandle, doc: DocumentHandle) -> RecolorStats {
+    recolor_document_to_gray(doc, 0, i32::MAX).unwrap_or_default()
 }
 
 // ============================================================================
@@ -513,6 +1065,7 @@ pub extern "C" fn pdf_rgb_to_cmyk(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
 
     #[test]
     fn test_recolor_constants() {
@@ -696,6 +1249,53 @@ mod tests {
         assert!((dst[0] - 0.5).abs() < 0.001);
     }
 
+    /// A one-page PDF with the given content stream
+    fn content_pdf(content: &[u8]) -> Vec<u8> {
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend_from_slice(content);
+        stream.extend_from_slice(b"\nendstream");
+        let objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R >>".to_vec(),
+            stream,
+        ];
+        build_pdf(&objects)
+    }
+
+    #[test]
+    fn test_recolor_to_gray() {
+        let mut content = b"1 0 0 rg 0 0 10 10 re f 0 0 0 1 K 0 0 m 10 10 l S ".to_vec();
+        content.extend_from_slice(b"BI /W 2 /H 1 /CS /RGB /BPC 8 ID \xff\x00\x00\x00\x00\xff EI");
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(content_pdf(&content)));
+        let stats = pdf_recolor_to_gray(0, doc);
+        let data = DOCUMENTS.get(doc).unwrap().lock().unwrap().data().to_vec();
+        DOCUMENTS.remove(doc);
+        assert_eq!(stats.pages_processed, 1);
+        assert_eq!(stats.colors_converted, 2);
+        assert_eq!(stats.images_processed, 1);
+
+        let pdf = Document::open_bytes(data).unwrap();
+        let page = pdf.page(0).unwrap();
+        let ops = content::parse_content(&pdf.page_contents(&page).unwrap()).unwrap();
+        let operand = |op: &str| {
+            let op = ops.iter().find(|o| o.operator == op).unwrap();
+            op.operands[0].as_real().unwrap()
+        };
+        assert!(!ops.iter().any(|o| o.operator == "rg" || o.operator == "K"));
+        assert!((operand("g") - 0.299).abs() < 1e-4);
+        // Full black ink is black
+        assert!(operand("G").abs() < 1e-4);
+
+        let image = ops.iter().find(|o| o.operator == "BI").unwrap();
+        let dict = image.operands[0].as_dict().unwrap();
+        assert_eq!(
+            dict.get("CS").and_then(Object::as_name).map(|n| n.as_str()),
+            Some("G")
+        );
+        assert_eq!(image.operands[1].as_string().unwrap().as_bytes(), &[76, 29]);
+    }
+
     #[test]
     fn test_convert_color_same() {
         let src = [0.1f32, 0.2, 0.3];
@@ -3,7 +3,19 @@
 //! Provides PDF redaction functionality including redaction annotations,
 //! content removal, image handling, and metadata sanitization.
 
-use crate::ffi::{Handle, HandleStore};
+use crate::ffi::context::set_caught;
+use crate::ffi::document::PAGES;
+use crate::ffi::{DOCUMENTS, Handle, HandleStore};
+use crate::fitz::error::{Error, Result};
+use crate::fitz::geometry::{Point, Rect};
+use crate::pdf::content::{self, ContentState, FontMetrics, Mark, Operation};
+use crate::pdf::document::Document;
+use crate::pdf::filter::encode_flate;
+use crate::pdf::object::{Dict, Name, ObjRef, Object};
+use crate::pdf::page::Page;
+use crate::pdf::parser::{self, Parser};
+use crate::pdf::write;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::LazyLock;
 
 // ============================================================================
@@ -243,6 +255,459 @@ pub struct RedactStats {
     pub annotations_removed: i32,
 }
 
+// ============================================================================
+// Page Redaction
+// ============================================================================
+
+/// A /Redact annotation on a page
+struct RedactAnnot {
+    /// Bounding boxes of the /QuadPoints, or the /Rect when there are none
+    rects: Vec<Rect>,
+    /// Interior color (/IC) as gray, RGB or CMYK components
+    color: Vec<f32>,
+}
+
+/// Apply the /Redact annotations of a page
+///
+/// Returns the changed objects, ready for an incremental update, and what
+/// was removed. Text is removed a whole show operator at a time: any
+/// overlap with a redaction removes the operator, which is replaced by a
+/// `TJ` offset so the position of the text that follows is unchanged.
+/// Images fully inside one redaction are replaced by a solid fill of the
+/// annotation's color; images that are only partly covered, inline images
+/// and touched form XObjects are removed, as their contents cannot be
+/// edited here.
+pub fn redact_page(
+    doc: &Document,
+    index: usize,
+    opts: &RedactOptions,
+) -> Result<(BTreeMap<ObjRef, Object>, RedactStats)> {
+    let mut stats = RedactStats::default();
+    let mut changes = BTreeMap::new();
+    if doc.is_encrypted() {
+        return Err(Error::unsupported("redaction of encrypted documents"));
+    }
+
+    let page = doc.page(index)?;
+    let (annots, kept_annots) = collect_redactions(doc, &page)?;
+    if annots.is_empty() {
+        return Ok((changes, stats));
+    }
+    let regions: Vec<Rect> = annots.iter().flat_map(|a| a.rects.clone()).collect();
+    let touched = |bbox: &Rect| regions.iter().any(|r| r.intersects(bbox));
+
+    let resources = match doc.resolve_key(page.dict(), "Resources")? {
+        Some(Object::Dict(d)) => d,
+        _ => Dict::new(),
+    };
+    let fonts = FontMetrics::load_resources(doc, &resources)?;
+    let xobjects = load_xobjects(doc, &resources)?;
+
+    let mut next_num = doc
+        .trailer()
+        .get("Size")
+        .and_then(Object::as_int)
+        .unwrap_or(1)
+        .max(1) as i32;
+    let mut alloc = || {
+        next_num += 1;
+        ObjRef::new(next_num - 1, 0)
+    };
+    let mut fills: Vec<(Vec<f32>, Name, ObjRef)> = Vec::new();
+    // XObjects removed or replaced somewhere on the page
+    let mut dropped = HashSet::new();
+
+    let ops = content::parse_content(&doc.page_contents(&page)?)?;
+    let mut state = ContentState::new(&fonts);
+    // Wrap the original content so its state changes cannot leak into the
+    // overlays appended below
+    let mut out = vec![Operation::new("q", vec![])];
+    for op in ops {
+        if op.operator == "Q" && state.depth() == 0 {
+            // An unbalanced Q would pop the wrapping q
+            continue;
+        }
+        let Some(mark) = state.step(&op) else {
+            out.push(op);
+            continue;
+        };
+        match mark {
+            Mark::Text { bbox, adjustment } => {
+                let remove = touched(&bbox)
+                    && match opts.text {
+                        PDF_REDACT_TEXT_NONE => false,
+                        PDF_REDACT_TEXT_REMOVE_INVISIBLE => state.text_render_mode() == 3,
+                        _ => true,
+                    };
+                if remove {
+                    stats.text_removed += 1;
+                    out.extend(blank_text(&op, adjustment));
+                } else {
+                    out.push(op);
+                }
+            }
+            Mark::Path(bbox) => {
+                let remove = match opts.line_art {
+                    PDF_REDACT_LINE_ART_REMOVE_IF_COVERED => {
+                        regions.iter().any(|r| covers(r, &bbox))
+                    }
+                    PDF_REDACT_LINE_ART_REMOVE_IF_TOUCHED => touched(&bbox),
+                    _ => false,
+                };
+                if remove {
+                    // End the path without painting it, keeping any clip
+                    stats.line_art_removed += 1;
+                    out.push(Operation::new("n", vec![]));
+                } else {
+                    out.push(op);
+                }
+            }
+            Mark::Image(bbox) => {
+                if opts.image_method != PDF_REDACT_IMAGE_NONE && touched(&bbox) {
+                    stats.images_removed += 1;
+                } else {
+                    out.push(op);
+                }
+            }
+            Mark::XObject { name, ctm } => {
+                let Some(xobj) = xobjects.get(&name) else {
+                    out.push(op);
+                    continue;
+                };
+                let is_image = xobj
+                    .get("Subtype")
+                    .and_then(Object::as_name)
+                    .is_some_and(|s| s.as_str() == "Image");
+                if !is_image {
+                    let bbox = form_bounds(xobj).transform(&ctm);
+                    let remove = opts.text != PDF_REDACT_TEXT_NONE
+                        || opts.image_method != PDF_REDACT_IMAGE_NONE;
+                    if remove && touched(&bbox) {
+                        stats.images_removed += 1;
+                        dropped.insert(name);
+                    } else {
+                        out.push(op);
+                    }
+                    continue;
+                }
+
+                let bbox = Rect::UNIT.transform(&ctm);
+                if opts.image_method == PDF_REDACT_IMAGE_NONE || !touched(&bbox) {
+                    out.push(op);
+                    continue;
+                }
+                let covering = annots
+                    .iter()
+                    .find(|a| a.rects.iter().any(|r| covers(r, &bbox)));
+                dropped.insert(name);
+                let Some(annot) = covering else {
+                    stats.images_removed += 1;
+                    continue;
+                };
+                let color = fill_color(&annot.color);
+                let fill_name = match fills.iter().find(|(c, _, _)| *c == color) {
+                    Some((_, name, _)) => name.clone(),
+                    None => {
+                        let name = (fills.len()..)
+                            .map(|i| Name::new(&format!("RedactFill{i}")))
+                            .find(|n| !xobjects.contains_key(n))
+                            .unwrap();
+                        fills.push((color, name.clone(), alloc()));
+                        name
+                    }
+                };
+                stats.images_modified += 1;
+                out.push(Operation::new("Do", vec![Object::Name(fill_name)]));
+            }
+        }
+    }
+    for _ in 0..=state.depth() {
+        out.push(Operation::new("Q", vec![]));
+    }
+
+    if opts.black_boxes != 0 {
+        for annot in &annots {
+            for r in &annot.rects {
+                out.push(Operation::new("q", vec![]));
+                out.push(color_op(&fill_color(&annot.color)));
+                out.push(Operation::new(
+                    "re",
+                    [r.x0, r.y0, r.width(), r.height()]
+                        .iter()
+                        .map(|&v| Object::Real(v as f64))
+                        .collect(),
+                ));
+                out.push(Operation::new("f", vec![]));
+                out.push(Operation::new("Q", vec![]));
+            }
+        }
+    }
+
+    // Reuse content streams no other page shares, so the old content is
+    // replaced rather than left behind in the file
+    let mut shared = HashSet::new();
+    for i in (0..doc.page_count()?).filter(|&i| i != index) {
+        shared.extend(content_refs(doc, &doc.page(i)?)?);
+    }
+    let mut exclusive = content_refs(doc, &page)?
+        .into_iter()
+        .filter(|r| !shared.contains(r));
+    let target = exclusive.next().unwrap_or_else(&mut alloc);
+    for obj_ref in exclusive {
+        changes.insert(obj_ref, Object::Null);
+    }
+
+    let mut stream_dict = Dict::new();
+    stream_dict.insert(Name::new("Filter"), Object::Name(Name::new("FlateDecode")));
+    changes.insert(
+        target,
+        Object::Stream {
+            dict: stream_dict,
+            data: encode_flate(&content::write_content(&out), 6)?,
+        },
+    );
+
+    let Object::Dict(mut page_obj) = doc.load_object(page.obj_ref())? else {
+        return Err(Error::format("page is not a dictionary"));
+    };
+    page_obj.insert(Name::new("Contents"), Object::Ref(target));
+    if kept_annots.is_empty() {
+        page_obj.remove("Annots");
+    } else {
+        page_obj.insert(Name::new("Annots"), Object::Array(kept_annots));
+    }
+
+    // Drop resource entries the new content no longer draws, so a rewrite
+    // does not carry the redacted XObjects along; pages sharing them keep
+    // their own references
+    let drawn: HashSet<&Name> = out
+        .iter()
+        .filter(|op| op.operator == "Do")
+        .filter_map(|op| op.operands.first().and_then(Object::as_name))
+        .collect();
+    let unused: Vec<Name> = dropped
+        .into_iter()
+        .filter(|name| !drawn.contains(name))
+        .collect();
+    if !fills.is_empty() || !unused.is_empty() {
+        let mut resources = resources;
+        let mut xobject_dict = match doc.resolve_key(&resources, "XObject")? {
+            Some(Object::Dict(d)) => d,
+            _ => Dict::new(),
+        };
+        for name in &unused {
+            xobject_dict.remove(name.as_str());
+        }
+        for (color, name, obj_ref) in fills {
+            xobject_dict.insert(name, Object::Ref(obj_ref));
+            changes.insert(obj_ref, solid_image(&color));
+        }
+        resources.insert(Name::new("XObject"), Object::Dict(xobject_dict));
+        page_obj.insert(Name::new("Resources"), Object::Dict(resources));
+    }
+    changes.insert(page.obj_ref(), Object::Dict(page_obj));
+
+    stats.regions_applied = regions.len() as i32;
+    stats.annotations_removed = annots.len() as i32;
+    Ok((changes, stats))
+}
+
+/// Split the page's /Annots into redactions and the entries to keep
+fn collect_redactions(doc: &Document, page: &Page) -> Result<(Vec<RedactAnnot>, Vec<Object>)> {
+    let mut annots = Vec::new();
+    let mut kept = Vec::new();
+    let Some(Object::Array(entries)) = doc.resolve_key(page.dict(), "Annots")? else {
+        return Ok((annots, kept));
+    };
+    for entry in entries {
+        let Object::Dict(annot) = doc.resolve(&entry)? else {
+            kept.push(entry);
+            continue;
+        };
+        let subtype = annot.get("Subtype").and_then(Object::as_name);
+        if !subtype.is_some_and(|s| s.as_str() == "Redact") {
+            kept.push(entry);
+            continue;
+        }
+
+        let numbers = |obj: Option<Object>| -> Vec<f32> {
+            obj.as_ref()
+                .and_then(Object::as_array)
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(Object::as_real)
+                        .map(|v| v as f32)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let mut rects: Vec<Rect> = numbers(doc.resolve_key(&annot, "QuadPoints")?)
+            .chunks_exact(8)
+            .map(|quad| {
+                let mut r = Rect::EMPTY;
+                for p in quad.chunks_exact(2) {
+                    r.include_point(Point::new(p[0], p[1]));
+                }
+                r
+            })
+            .collect();
+        if rects.is_empty() {
+            if let [x0, y0, x1, y1, ..] = numbers(doc.resolve_key(&annot, "Rect")?)[..] {
+                rects.push(Rect::new(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)));
+            }
+        }
+        annots.push(RedactAnnot {
+            rects,
+            color: numbers(doc.resolve_key(&annot, "IC")?),
+        });
+    }
+    Ok((annots, kept))
+}
+
+/// XObject stream dictionaries by resource name
+fn load_xobjects(doc: &Document, resources: &Dict) -> Result<HashMap<Name, Dict>> {
+    let mut xobjects = HashMap::new();
+    if let Some(Object::Dict(xobject_dict)) = doc.resolve_key(resources, "XObject")? {
+        for (name, xobj) in &xobject_dict {
+            if let Object::Stream { dict, .. } = doc.resolve(xobj)? {
+                xobjects.insert(name.clone(), dict);
+            }
+        }
+    }
+    Ok(xobjects)
+}
+
+/// References of the content streams of a page
+fn content_refs(doc: &Document, page: &Page) -> Result<Vec<ObjRef>> {
+    let Some(contents) = page.dict().get("Contents") else {
+        return Ok(Vec::new());
+    };
+    Ok(match doc.resolve(contents)? {
+        Object::Array(arr) => arr.iter().filter_map(Object::as_obj_ref).collect(),
+        _ => contents.as_obj_ref().into_iter().collect(),
+    })
+}
+
+/// Area of a form XObject in the space it is drawn in
+fn form_bounds(form: &Dict) -> Rect {
+    let nums = |key: &str| -> Vec<f32> {
+        form.get(key)
+            .and_then(Object::as_array)
+            .map(|a| {
+                a.iter()
+                    .filter_map(Object::as_real)
+                    .map(|v| v as f32)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let Some(&[x0, y0, x1, y1]) = nums("BBox").get(..4) else {
+        return Rect::INFINITE;
+    };
+    let bbox = Rect::new(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1));
+    match nums("Matrix").get(..6) {
+        Some(&[a, b, c, d, e, f]) => {
+            bbox.transform(&crate::fitz::geometry::Matrix::new(a, b, c, d, e, f))
+        }
+        _ => bbox,
+    }
+}
+
+fn covers(outer: &Rect, inner: &Rect) -> bool {
+    outer.x0 <= inner.x0 && outer.y0 <= inner.y0 && outer.x1 >= inner.x1 && outer.y1 >= inner.y1
+}
+
+/// Annotation color, defaulting to black
+fn fill_color(color: &[f32]) -> Vec<f32> {
+    match color.len() {
+        1 | 3 | 4 => color.to_vec(),
+        _ => vec![0.0],
+    }
+}
+
+fn color_op(color: &[f32]) -> Operation {
+    let operator = match color.len() {
+        3 => "rg",
+        4 => "k",
+        _ => "g",
+    };
+    Operation::new(
+        operator,
+        color.iter().map(|&c| Object::Real(c as f64)).collect(),
+    )
+}
+
+/// A 1x1 image XObject of a single color
+fn solid_image(color: &[f32]) -> Object {
+    let colorspace = match color.len() {
+        3 => "DeviceRGB",
+        4 => "DeviceCMYK",
+        _ => "DeviceGray",
+    };
+    let mut dict = Dict::new();
+    dict.insert(Name::new("Type"), Object::Name(Name::new("XObject")));
+    dict.insert(Name::new("Subtype"), Object::Name(Name::new("Image")));
+    dict.insert(Name::new("Width"), Object::Int(1));
+    dict.insert(Name::new("Height"), Object::Int(1));
+    dict.insert(Name::new("BitsPerComponent"), Object::Int(8));
+    dict.insert(Name::new("ColorSpace"), Object::Name(Name::new(colorspace)));
+    Object::Stream {
+        dict,
+        data: color
+            .iter()
+            .map(|&c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect(),
+    }
+}
+
+/// Replace a text showing operation with one that moves the text position
+/// by the same amount without showing anything
+fn blank_text(op: &Operation, adjustment: f32) -> Vec<Operation> {
+    let blank = Operation::new(
+        "TJ",
+        vec![Object::Array(vec![Object::Real(adjustment as f64)])],
+    );
+    match op.operator.as_str() {
+        "'" => vec![Operation::new("T*", vec![]), blank],
+        "\"" => vec![
+            Operation::new("Tw", op.operands.iter().take(1).cloned().collect()),
+            Operation::new("Tc", op.operands.iter().skip(1).take(1).cloned().collect()),
+            Operation::new("T*", vec![]),
+            blank,
+        ],
+        _ => vec![blank],
+    }
+}
+
+/// Redact a page of an open document in place, returning the number of
+/// redaction annotations applied
+///
+/// The document is rewritten rather than updated incrementally, so the
+/// removed content does not survive in earlier revisions or object
+/// streams of the file.
+pub(crate) fn apply_page_redactions(
+    doc: DocumentHandle,
+    page_num: i32,
+    opts: &RedactOptions,
+) -> Result<i32> {
+    let index = usize::try_from(page_num)
+        .map_err(|_| Error::argument(format!("page {page_num} out of range")))?;
+    let doc = DOCUMENTS
+        .get(doc)
+        .ok_or_else(|| Error::argument("invalid document handle"))?;
+    let mut doc = doc.lock().unwrap();
+
+    let pdf = doc.pdf()?;
+    let (changes, stats) = redact_page(&pdf, index, opts)?;
+    if changes.is_empty() {
+        return Ok(0);
+    }
+    let mut out = Vec::new();
+    write::rewrite_document(&pdf, &changes, &mut out)?;
+    doc.set_data(out);
+    Ok(stats.annotations_removed)
+}
+
 // ============================================================================
 // Global Handle Store
 // ============================================================================
@@ -379,26 +844,44 @@ pub extern "C" fn pdf_apply_redactions(_ctx: ContextHandle, redact_ctx: Handle)
 }
 
 /// Redact a page with options (applies all redaction annotations).
+///
+/// A null `opts` uses the default options.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_redact_page_annotations(
-    _ctx: ContextHandle,
-    _doc: DocumentHandle,
-    _page: PageHandle,
-    _opts: *const RedactOptions,
+    ctx: ContextHandle,
+    doc: DocumentHandle,
+    page: PageHandle,
+    opts: *const RedactOptions,
 ) -> i32 {
-    // In a full implementation, this would redact all redaction annotations on the page
-    0
+    let opts = if opts.is_null() {
+        RedactOptions::new()
+    } else {
+        // SAFETY: Caller guarantees a non-null opts points to valid options
+        unsafe { *opts }
+    };
+    let Some(page) = PAGES.get(page) else {
+        set_caught(ctx, &Error::argument("invalid page handle"));
+        return -1;
+    };
+    let page_num = page.lock().unwrap().page_num;
+    apply_page_redactions(doc, page_num, &opts).unwrap_or_else(|err| {
+        set_caught(ctx, &err);
+        -1
+    })
 }
 
 /// Apply a single redaction annotation.
+///
+/// Annotation handles are not tied to a page, so nothing can be applied
+/// from one alone and this returns 0. Use
+/// [`pdf_redact_page_annotations`] to apply the redactions of a page.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_apply_redaction(
     _ctx: ContextHandle,
     _annot: AnnotHandle,
     _opts: *const RedactOptions,
 ) -> i32 {
-    // In a full implementation, this would apply a single redaction annotation
-    1
+    0
 }
 
 // ============================================================================
@@ -556,6 +1039,279 @@ pub extern "C" fn pdf_apply_all_redactions(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
+
+    /// A one-page PDF with a classic xref table. `extra` objects are numbered
+    /// from 5; annotations among them are listed in the page's /Annots and
+    /// object 5 is the page's /Im1 and /Im2 XObject.
+    fn redaction_pdf(content: &str, extra: &[&str]) -> Vec<u8> {
+        let annots: Vec<String> = (0..extra.len())
+            .filter(|&i| extra[i].contains("/Annot"))
+            .map(|i| format!("{} 0 R", i + 5))
+            .collect();
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R \
+                 /Resources << /Font << /F1 << /Type /Font /Subtype /Type1 /BaseFont /Helvetica >> >> \
+                 /XObject << /Im1 5 0 R /Im2 5 0 R >> >> /Annots [{}] >>",
+                annots.join(" ")
+            ),
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ),
+        ];
+        objects.extend(extra.iter().map(|s| s.to_string()));
+
+        build_pdf(&objects)
+    }
+
+    /// Strings shown by the text operators of the first page
+    fn shown_text(data: &[u8]) -> String {
+        let doc = Document::open_bytes(data.to_vec()).unwrap();
+        let page = doc.page(0).unwrap();
+        let ops = content::parse_content(&doc.page_contents(&page).unwrap()).unwrap();
+        ops.iter()
+            .filter(|op| matches!(op.operator.as_str(), "Tj" | "TJ" | "'" | "\""))
+            .flat_map(|op| op.operands.iter())
+            .flat_map(|o| match o {
+                Object::Array(items) => items.clone(),
+                other => vec![other.clone()],
+            })
+            .filter_map(|o| {
+                o.as_string()
+                    .map(|s| String::from_utf8_lossy(s.as_bytes()).into_owned())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_redact_page_removes_text() {
+        let data = redaction_pdf(
+            "BT /F1 12 Tf 72 700 Td (Public notice) Tj 0 -20 Td (Secret SSN) Tj ET",
+            &["<< /Type /Annot /Subtype /Redact /Rect [60 670 400 692] /IC [1 0 0] >>"],
+        );
+        assert!(shown_text(&data).contains("Secret"));
+
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(data));
+        let page = crate::ffi::pdf_page::pdf_load_page(0, doc, 0);
+        let changed = crate::ffi::pdf_page::pdf_redact_page(0, doc, page, std::ptr::null_mut());
+        assert_eq!(changed, 1);
+
+        let redacted = DOCUMENTS.get(doc).unwrap().lock().unwrap().data().to_vec();
+        let text = shown_text(&redacted);
+        assert!(text.contains("Public notice"));
+        assert!(!text.contains("Secret"));
+        // The old content stream is scrubbed, not merely unreferenced
+        assert!(parser::find_bytes(&redacted, b"Secret", 0).is_none());
+
+        let pdf = Document::open_bytes(redacted).unwrap();
+        let page = pdf.page(0).unwrap();
+        assert!(!page.dict().contains_key("Annots"));
+        let content = pdf.page_contents(&page).unwrap();
+        assert!(parser::find_bytes(&content, b"1 0 0 rg", 0).is_some());
+        DOCUMENTS.remove(doc);
+    }
+
+    #[test]
+    fn test_redacted_text_gone_from_earlier_revisions() {
+        let annot = "<< /Type /Annot /Subtype /Redact /Rect [60 670 400 692] >>";
+        let draft = redaction_pdf("BT /F1 12 Tf 72 680 Td (Secret draft) Tj ET", &[annot]);
+        let content = b"BT /F1 12 Tf 72 700 Td (Public notice) Tj 0 -20 Td (Secret SSN) Tj ET";
+        let mut dict = Dict::new();
+        dict.insert("Length".into(), Object::Int(content.len() as i64));
+        let changes = BTreeMap::from([(
+            ObjRef::new(4, 0),
+            Object::Stream {
+                dict,
+                data: content.to_vec(),
+            },
+        )]);
+        let mut data = Vec::new();
+        write::append_incremental(&draft, &changes, &mut data).unwrap();
+
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(data));
+        assert_eq!(
+            apply_page_redactions(doc, 0, &RedactOptions::new()).unwrap(),
+            1
+        );
+        let redacted = DOCUMENTS.get(doc).unwrap().lock().unwrap().data().to_vec();
+        assert!(parser::find_bytes(&redacted, b"Secret", 0).is_none());
+        assert!(shown_text(&redacted).contains("Public notice"));
+        DOCUMENTS.remove(doc);
+    }
+
+    #[test]
+    fn test_redact_page_images() {
+        let data = redaction_pdf(
+            "q 100 0 0 50 100 600 cm /Im1 Do Q q 100 0 0 50 100 100 cm /Im2 Do Q \
+             q 100 0 0 50 300 100 cm /Im1 Do Q",
+            &[
+                "<< /Type /XObject /Subtype /Image /Width 1 /Height 1 /BitsPerComponent 8 \
+                 /ColorSpace /DeviceGray /Length 1 >>\nstream\n\x7f\nendstream",
+                "<< /Type /Annot /Subtype /Redact /Rect [90 590 210 660] /IC [0 0 1] >>",
+                "<< /Type /Annot /Subtype /Redact /Rect [350 120 500 200] >>",
+                "<< /Type /Annot /Subtype /Text /Rect [0 0 10 10] >>",
+            ],
+        );
+        let doc = Document::open_bytes(data).unwrap();
+        let (changes, stats) = redact_page(&doc, 0, &RedactOptions::new()).unwrap();
+        assert_eq!(stats.images_modified, 1);
+        assert_eq!(stats.images_removed, 1);
+        assert_eq!(stats.annotations_removed, 2);
+
+        let Object::Stream { data, .. } = &changes[&ObjRef::new(4, 0)] else {
+            panic!("content stream not replaced");
+        };
+        let ops =
+            content::parse_content(&crate::pdf::filter::decode_flate(data, None).unwrap()).unwrap();
+        let painted: Vec<_> = ops
+            .iter()
+            .filter(|op| op.operator == "Do")
+            .map(|op| op.operands[0].as_name().unwrap().as_str().to_string())
+            .collect();
+        // Covered image replaced, untouched one kept, partly covered one removed
+        assert_eq!(painted, ["RedactFill0", "Im2"]);
+
+        let Object::Dict(page) = &changes[&ObjRef::new(3, 0)] else {
+            panic!("page not updated");
+        };
+        assert_eq!(page.get("Annots").unwrap().as_array().unwrap().len(), 1);
+        let fill = page.get("Resources").unwrap().as_dict().unwrap()["XObject"]
+            .as_dict()
+            .unwrap()["RedactFill0"]
+            .as_obj_ref()
+            .unwrap();
+        assert_eq!(changes[&fill].as_stream().unwrap().1, [0, 0, 255]);
+        // Im1 is no longer drawn; Im2 shares its object and stays
+        let xobjects = page.get("Resources").unwrap().as_dict().unwrap()["XObject"]
+            .as_dict()
+            .unwrap();
+        assert!(!xobjects.contains_key("Im1"));
+        assert!(xobjects.contains_key("Im2"));
+    }
+
+    #[test]
+    fn test_redact_page_out_of_range() {
+        let data = redaction_pdf(
+            "BT /F1 12 Tf 72 680 Td (Secret) Tj ET",
+            &["<< /Type /Annot /Subtype /Redact /Rect [60 670 400 692] >>"],
+        );
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(data.clone()));
+        assert!(apply_page_redactions(doc, -1, &RedactOptions::new()).is_err());
+
+        let ctx =
+            unsafe { crate::ffi::context::fz_new_context(std::ptr::null(), std::ptr::null(), 0) };
+        let page =
+            crate::ffi::pdf_page::PDF_PAGES.insert(crate::ffi::pdf_page::PdfPage::new(doc, 1));
+        let changed = crate::ffi::pdf_page::pdf_redact_page(ctx, doc, page, std::ptr::null_mut());
+        assert_eq!(changed, 0);
+        let message =
+            unsafe { std::ffi::CStr::from_ptr(crate::ffi::context::fz_caught_message(ctx)) };
+        assert!(message.to_str().unwrap().contains("out of range"));
+
+        // Neither call touched the first page
+        assert_eq!(
+            DOCUMENTS.get(doc).unwrap().lock().unwrap().data(),
+            &data[..]
+        );
+        crate::ffi::pdf_page::PDF_PAGES.remove(page);
+        crate::ffi::context::fz_drop_context(ctx);
+        DOCUMENTS.remove(doc);
+    }
+
+    #[test]
+    fn test_redacted_images_gone_from_output() {
+        let image = |data: &str| {
+            format!(
+                "<< /Type /XObject /Subtype /Image /Width 4 /Height 1 /BitsPerComponent 8 \
+                 /ColorSpace /DeviceGray /Length 4 >>\nstream\n{data}\nendstream"
+            )
+        };
+        let page = |contents: i32, xobjects: &str, annots: &str| {
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents {contents} 0 R \
+                 /Resources << /XObject << {xobjects} >> >> /Annots [{annots}] >>"
+            )
+        };
+        let stream = |content: &str| {
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            )
+        };
+        let data = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>".to_string(),
+            page(5, "/Secret 7 0 R /Logo 8 0 R", "9 0 R"),
+            page(6, "/Logo 8 0 R", ""),
+            stream("q 100 0 0 50 100 600 cm /Secret Do Q q 100 0 0 50 100 500 cm /Logo Do Q"),
+            stream("q 100 0 0 50 100 600 cm /Logo Do Q"),
+            image("SSN!"),
+            image("LOGO"),
+            "<< /Type /Annot /Subtype /Redact /Rect [90 490 210 660] >>".to_string(),
+        ]);
+
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(data));
+        assert_eq!(
+            apply_page_redactions(doc, 0, &RedactOptions::new()).unwrap(),
+            1
+        );
+        let redacted = DOCUMENTS.get(doc).unwrap().lock().unwrap().data().to_vec();
+        assert!(parser::find_bytes(&redacted, b"SSN!", 0).is_none());
+        // The second page still draws the image the redacted page shared
+        assert!(parser::find_bytes(&redacted, b"LOGO", 0).is_some());
+        let pdf = Document::open_bytes(redacted).unwrap();
+        let resources = pdf
+            .resolve_key(pdf.page(1).unwrap().dict(), "Resources")
+            .unwrap()
+            .unwrap();
+        let xobjects = pdf
+            .resolve_key(resources.as_dict().unwrap(), "XObject")
+            .unwrap()
+            .unwrap();
+        assert!(xobjects.as_dict().unwrap().contains_key("Logo"));
+        DOCUMENTS.remove(doc);
+    }
+
+    #[test]
+    fn test_redact_type3_text() {
+        // Glyphs are 100 units wide in a 0.01 font matrix: one em each, so
+        // at 10 points "Pub" spans x 72-102 and "Sec" x 102-132
+        let content = "BT /T3 10 Tf 72 700 Td (Pub) Tj (Sec) Tj ET";
+        let data = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R \
+             /Resources << /Font << /T3 5 0 R >> >> /Annots [7 0 R] >>"
+                .to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ),
+            format!(
+                "<< /Type /Font /Subtype /Type3 /FontMatrix [0.01 0 0 0.01 0 0] \
+                 /FontBBox [0 0 100 100] /CharProcs << /g 6 0 R >> \
+                 /Encoding << /Differences [32 /g] >> /FirstChar 32 /LastChar 126 \
+                 /Widths [{}] >>",
+                ["100"; 95].join(" ")
+            ),
+            "<< /Length 9 >>\nstream\n100 0 d0\nendstream".to_string(),
+            "<< /Type /Annot /Subtype /Redact /Rect [110 702 125 708] >>".to_string(),
+        ]);
+        let doc = Document::open_bytes(data).unwrap();
+        let (changes, stats) = redact_page(&doc, 0, &RedactOptions::new()).unwrap();
+        assert_eq!(stats.text_removed, 1);
+
+        let Object::Stream { data, .. } = &changes[&ObjRef::new(4, 0)] else {
+            panic!("content stream not replaced");
+        };
+        let content = crate::pdf::filter::decode_flate(data, None).unwrap();
+        assert!(parser::find_bytes(&content, b"(Pub)", 0).is_some());
+        assert!(parser::find_bytes(&content, b"Sec", 0).is_none());
+    }
 
     #[test]
     fn test_redact_options_default() {
@@ -3,7 +3,9 @@
 //! Provides PDF resource management including fonts, images, colorspaces,
 //! patterns, shadings, functions, and XObjects.
 
+use crate::ffi::pdf_object::types::{PDF_OBJECTS, PdfObj};
 use crate::ffi::{Handle, HandleStore};
+use crate::pdf::object::Object;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString, c_char, c_void};
 use std::ptr;
@@ -569,6 +571,47 @@ pub extern "C" fn pdf_lookup_resource(
     0
 }
 
+/// Look up the resource `name` of `category` (`Font`, `XObject`,
+/// `ExtGState`, `ColorSpace`, `Pattern`, ...) in a resources dictionary
+///
+/// `resources` is typically the handle from `pdf_page_resources`, whose
+/// entries are already resolved. Returns a new object handle, or 0 if
+/// the resource is not defined.
+///
+/// # Safety
+/// Caller must ensure `category` and `name` are valid null-terminated C strings.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_resource_lookup(
+    _ctx: ContextHandle,
+    resources: PdfObjHandle,
+    category: *const c_char,
+    name: *const c_char,
+) -> PdfObjHandle {
+    if category.is_null() || name.is_null() {
+        return 0;
+    }
+    // SAFETY: Caller guarantees both are valid null-terminated C strings
+    let (category, name) = unsafe {
+        (
+            CStr::from_ptr(category).to_string_lossy(),
+            CStr::from_ptr(name).to_string_lossy(),
+        )
+    };
+    let Some(resources) = PDF_OBJECTS.get(resources) else {
+        return 0;
+    };
+    let Object::Dict(resources) = resources.lock().unwrap().to_object() else {
+        return 0;
+    };
+    match resources.get(category.as_ref()) {
+        Some(Object::Dict(entries)) => match entries.get(name.as_ref()) {
+            Some(value) if !value.is_null() => PDF_OBJECTS.insert(PdfObj::from_object(value)),
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
 // ============================================================================
 // FFI Functions - Functions
 // ============================================================================
@@ -942,6 +985,57 @@ pub extern "C" fn pdf_xobject_colorspace(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
+
+    #[test]
+    fn test_resource_lookup_inherited_from_pages_node() {
+        use crate::ffi::DOCUMENTS;
+        use crate::ffi::document::Document;
+        use crate::ffi::pdf_page::{pdf_drop_page, pdf_load_page, pdf_page_resources};
+
+        let pdf = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 \
+               /Resources << /Font 5 0 R /ExtGState << /GS0 << /CA 0.5 >> >> >> >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 6 0 R >>",
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>",
+            "<< /F1 4 0 R >>",
+            "<< /Length 0 >>\nstream\n\nendstream",
+        ]);
+
+        let doc = DOCUMENTS.insert(Document::new(pdf));
+        let page = pdf_load_page(0, doc, 0);
+        let resources = pdf_page_resources(0, page);
+        assert_ne!(resources, 0);
+        assert_eq!(pdf_page_resources(0, page), resources);
+
+        let font = pdf_resource_lookup(0, resources, c"Font".as_ptr(), c"F1".as_ptr());
+        let font = PDF_OBJECTS.get(font).unwrap().lock().unwrap().to_object();
+        let Object::Dict(font) = font else {
+            panic!("font did not resolve");
+        };
+        assert_eq!(
+            font.get("BaseFont")
+                .and_then(|o| o.as_name())
+                .map(|n| n.as_str()),
+            Some("Helvetica")
+        );
+        assert_ne!(
+            pdf_resource_lookup(0, resources, c"ExtGState".as_ptr(), c"GS0".as_ptr()),
+            0
+        );
+        assert_eq!(
+            pdf_resource_lookup(0, resources, c"Font".as_ptr(), c"F2".as_ptr()),
+            0
+        );
+        assert_eq!(
+            pdf_resource_lookup(0, resources, c"Pattern".as_ptr(), c"P0".as_ptr()),
+            0
+        );
+
+        pdf_drop_page(0, page);
+        assert!(PDF_OBJECTS.get(resources).is_none());
+    }
 
     #[test]
     fn test_font_resource_key() {
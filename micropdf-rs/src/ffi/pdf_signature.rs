@@ -3,7 +3,11 @@
 //! Provides support for PDF digital signatures, including signature
 //! verification, signing, and certificate handling.
 
-use crate::ffi::{Handle, HandleStore};
+use crate::ffi::document::open_pdf;
+use crate::ffi::{DOCUMENTS, Handle, HandleStore};
+use crate::pdf::document::Document;
+use crate::pdf::signature::{self, Signature};
+use bytes::Bytes;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString, c_char};
 use std::ptr;
@@ -211,6 +215,50 @@ impl SignatureInfo {
             certificate_status: SignatureError::NotSigned,
         }
     }
+
+    /// Info for a signed field read from a file. The PKCS#7 contents are
+    /// not verified, so digest and certificate status are unknown.
+    pub fn from_signature(sig: &Signature, data: &[u8]) -> Self {
+        Self {
+            is_signed: true,
+            signer_dn: sig.signer.as_ref().map(|name| DistinguishedName {
+                cn: Some(name.clone()),
+                ..Default::default()
+            }),
+            reason: sig.reason.clone(),
+            location: sig.location.clone(),
+            date: 0,
+            byte_ranges: sig
+                .byte_range
+                .iter()
+                .map(|&(offset, length)| ByteRange {
+                    offset: offset as i64,
+                    length: length as i64,
+                })
+                .collect(),
+            contents: sig.contents.clone(),
+            incremental_change: !sig.covers_whole_file(data),
+            digest_status: SignatureError::Unknown,
+            certificate_status: SignatureError::Unknown,
+        }
+    }
+
+    /// The byte range in the form used by [`Signature`]
+    fn signature_ranges(&self) -> Signature {
+        Signature {
+            field: crate::pdf::object::ObjRef::new(0, 0),
+            name: String::new(),
+            byte_range: self
+                .byte_ranges
+                .iter()
+                .map(|r| (r.offset.max(0) as usize, r.length.max(0) as usize))
+                .collect(),
+            contents: Vec::new(),
+            signer: None,
+            reason: None,
+            location: None,
+        }
+    }
 }
 
 // ============================================================================
@@ -333,6 +381,30 @@ pub static SIGNATURE_INFOS: LazyLock<HandleStore<SignatureInfo>> = LazyLock::new
 pub static DOC_SIGNATURES: LazyLock<Mutex<HashMap<DocumentHandle, Vec<SignatureInfo>>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
+/// Signatures of `doc`, reading the signed fields of an open document on
+/// first use
+fn signatures(
+    store: &mut HashMap<DocumentHandle, Vec<SignatureInfo>>,
+    doc: DocumentHandle,
+) -> Option<&mut Vec<SignatureInfo>> {
+    if !store.contains_key(&doc) {
+        let (data, sigs) = load_signatures(doc)?;
+        let infos = sigs
+            .iter()
+            .map(|sig| SignatureInfo::from_signature(sig, &data))
+            .collect();
+        store.insert(doc, infos);
+    }
+    store.get_mut(&doc)
+}
+
+/// File bytes and signed fields of an open document
+fn load_signatures(doc: DocumentHandle) -> Option<(Bytes, Vec<Signature>)> {
+    let parsed = open_pdf(doc)?;
+    let sigs = signature::find_signatures(&parsed).ok()?;
+    Some((parsed.data().clone(), sigs))
+}
+
 // ============================================================================
 // FFI Functions - Signature Query
 // ============================================================================
@@ -344,8 +416,8 @@ pub extern "C" fn pdf_signature_is_signed(
     doc: DocumentHandle,
     _field: PdfObjHandle,
 ) -> i32 {
-    let store = DOC_SIGNATURES.lock().unwrap();
-    if let Some(sigs) = store.get(&doc) {
+    let mut store = DOC_SIGNATURES.lock().unwrap();
+    if let Some(sigs) = signatures(&mut store, doc) {
         if !sigs.is_empty() && sigs.iter().any(|s| s.is_signed) {
             return 1;
         }
@@ -354,10 +426,12 @@ pub extern "C" fn pdf_signature_is_signed(
 }
 
 /// Count signatures in document.
+/// For an open document these are its signed `/Sig` fields; other
+/// signature functions take the index of one as `signature`.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_count_signatures(_ctx: ContextHandle, doc: DocumentHandle) -> i32 {
-    let store = DOC_SIGNATURES.lock().unwrap();
-    if let Some(sigs) = store.get(&doc) {
+    let mut store = DOC_SIGNATURES.lock().unwrap();
+    if let Some(sigs) = signatures(&mut store, doc) {
         return sigs.len() as i32;
     }
     0
@@ -365,20 +439,22 @@ pub extern "C" fn pdf_count_signatures(_ctx: ContextHandle, doc: DocumentHandle)
 
 /// Get signature byte range.
 /// Returns number of ranges, fills byte_range array.
+/// Pass a null byte_range to query the number of ranges first.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_signature_byte_range(
     _ctx: ContextHandle,
     doc: DocumentHandle,
-    _signature: PdfObjHandle,
+    signature: PdfObjHandle,
     byte_range: *mut ByteRange,
 ) -> i32 {
-    let store = DOC_SIGNATURES.lock().unwrap();
-    if let Some(sigs) = store.get(&doc) {
-        if let Some(sig) = sigs.first() {
-            if !byte_range.is_null() && !sig.byte_ranges.is_empty() {
-                unsafe {
-                    *byte_range = sig.byte_ranges[0].clone();
-                }
+    let mut store = DOC_SIGNATURES.lock().unwrap();
+    if let Some(sigs) = signatures(&mut store, doc) {
+        if let Some(sig) = sigs.get(signature as usize) {
+            if !byte_range.is_null() {
+                // SAFETY: the caller provides room for every range
+                let out =
+                    unsafe { std::slice::from_raw_parts_mut(byte_range, sig.byte_ranges.len()) };
+                out.clone_from_slice(&sig.byte_ranges);
             }
             return sig.byte_ranges.len() as i32;
         }
@@ -386,18 +462,72 @@ pub extern "C" fn pdf_signature_byte_range(
     0
 }
 
+/// Compute the SHA-256 digest of the bytes covered by a signature's byte
+/// range, to compare with the message digest in its PKCS#7 contents.
+/// Writes 32 bytes to digest and returns 32, or 0 if the document is not
+/// open or the range lies outside the file.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_signature_digest(
+    _ctx: ContextHandle,
+    doc: DocumentHandle,
+    signature: PdfObjHandle,
+    digest: *mut u8,
+) -> i32 {
+    if digest.is_null() {
+        return 0;
+    }
+    let ranges = {
+        let mut store = DOC_SIGNATURES.lock().unwrap();
+        match signatures(&mut store, doc).and_then(|sigs| sigs.get(signature as usize)) {
+            Some(sig) => sig.signature_ranges(),
+            None => return 0,
+        }
+    };
+    let Some(document) = DOCUMENTS.get(doc) else {
+        return 0;
+    };
+    let Ok(hash) = ranges.digest(document.lock().unwrap().data()) else {
+        return 0;
+    };
+    // SAFETY: the caller provides a 32-byte buffer
+    unsafe { ptr::copy_nonoverlapping(hash.as_ptr(), digest, hash.len()) };
+    hash.len() as i32
+}
+
+/// Check whether a signature's byte range covers the whole file except its
+/// /Contents hex string. Returns 0 when bytes were added after signing or
+/// the excluded bytes are not just the signature value.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_signature_covers_whole_file(
+    _ctx: ContextHandle,
+    doc: DocumentHandle,
+    signature: PdfObjHandle,
+) -> i32 {
+    let ranges = {
+        let mut store = DOC_SIGNATURES.lock().unwrap();
+        match signatures(&mut store, doc).and_then(|sigs| sigs.get(signature as usize)) {
+            Some(sig) => sig.signature_ranges(),
+            None => return 0,
+        }
+    };
+    let Some(document) = DOCUMENTS.get(doc) else {
+        return 0;
+    };
+    i32::from(ranges.covers_whole_file(document.lock().unwrap().data()))
+}
+
 /// Get signature contents (PKCS#7 data).
 /// Returns size of contents, allocates and fills contents pointer.
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_signature_contents(
     _ctx: ContextHandle,
     doc: DocumentHandle,
-    _signature: PdfObjHandle,
+    signature: PdfObjHandle,
     contents: *mut *mut c_char,
 ) -> usize {
-    let store = DOC_SIGNATURES.lock().unwrap();
-    if let Some(sigs) = store.get(&doc) {
-        if let Some(sig) = sigs.first() {
+    let mut store = DOC_SIGNATURES.lock().unwrap();
+    if let Some(sigs) = signatures(&mut store, doc) {
+        if let Some(sig) = sigs.get(signature as usize) {
             if !contents.is_null() && !sig.contents.is_empty() {
                 let len = sig.contents.len();
                 // Allocate using Box to ensure proper memory management
@@ -425,11 +555,11 @@ pub extern "C" fn pdf_signature_contents(
 pub extern "C" fn pdf_signature_incremental_change_since_signing(
     _ctx: ContextHandle,
     doc: DocumentHandle,
-    _signature: PdfObjHandle,
+    signature: PdfObjHandle,
 ) -> i32 {
-    let store = DOC_SIGNATURES.lock().unwrap();
-    if let Some(sigs) = store.get(&doc) {
-        if let Some(sig) = sigs.first() {
+    let mut store = DOC_SIGNATURES.lock().unwrap();
+    if let Some(sigs) = signatures(&mut store, doc) {
+        if let Some(sig) = sigs.get(signature as usize) {
             return if sig.incremental_change { 1 } else { 0 };
         }
     }
@@ -965,6 +1095,7 @@ pub extern "C" fn pdf_clear_all_signatures(_ctx: ContextHandle, doc: DocumentHan
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
 
     #[test]
     fn test_signature_error_types() {
@@ -1187,4 +1318,53 @@ mod tests {
 
         DISTINGUISHED_NAMES.remove(dn_handle);
     }
+
+    #[test]
+    fn test_ffi_signature_from_document() {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R /AcroForm << /Fields [3 0 R] >> >>",
+            "<< /Type /Pages /Kids [] /Count 0 >>",
+            "<< /FT /Sig /T (Sig1) /V << /Type /Sig /ByteRange [0000000000 0000000000 \
+             0000000000 0000000000] /Contents <0102030405> >> >>",
+        ];
+        let mut data = build_pdf(&objects);
+        let start = crate::pdf::parser::find_bytes(&data, b"<0102030405>", 0).unwrap();
+        let end = start + b"<0102030405>".len();
+        let range = format!(
+            "{:010} {:010} {:010} {:010}",
+            0,
+            start,
+            end,
+            data.len() - end
+        );
+        let at = crate::pdf::parser::find_bytes(&data, b"0000000000 ", 0).unwrap();
+        data[at..at + range.len()].copy_from_slice(range.as_bytes());
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(data.clone()));
+
+        assert_eq!(pdf_count_signatures(0, doc), 1);
+        assert_eq!(pdf_signature_byte_range(0, doc, 0, ptr::null_mut()), 2);
+        let mut ranges = [ByteRange::default(), ByteRange::default()];
+        assert_eq!(pdf_signature_byte_range(0, doc, 0, ranges.as_mut_ptr()), 2);
+        assert_eq!((ranges[0].offset, ranges[0].length), (0, start as i64));
+        assert_eq!(ranges[1].offset, end as i64);
+        assert_eq!(ranges[1].offset + ranges[1].length, data.len() as i64);
+
+        let mut digest = [0u8; 32];
+        assert_eq!(pdf_signature_digest(0, doc, 0, digest.as_mut_ptr()), 32);
+        use sha2::Digest;
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(&data[..start]);
+        hasher.update(&data[end..]);
+        assert_eq!(digest, <[u8; 32]>::from(hasher.finalize()));
+
+        assert_eq!(pdf_signature_covers_whole_file(0, doc, 0), 1);
+        assert_eq!(pdf_signature_incremental_change_since_signing(0, doc, 0), 0);
+        let mut contents = ptr::null_mut();
+        assert_eq!(pdf_signature_contents(0, doc, 0, &mut contents), 5);
+        unsafe { drop(Box::from_raw(ptr::slice_from_raw_parts_mut(contents, 5))) };
+        assert_eq!(pdf_signature_digest(0, doc, 1, digest.as_mut_ptr()), 0);
+
+        pdf_clear_all_signatures(0, doc);
+        DOCUMENTS.remove(doc);
+    }
 }
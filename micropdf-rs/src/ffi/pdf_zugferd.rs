@@ -3,7 +3,10 @@
 //! Provides support for ZUGFeRD and Factur-X electronic invoice formats,
 //! enabling extraction and embedding of XML invoice data in PDF documents.
 
-use crate::ffi::{Handle, HandleStore};
+use crate::ffi::document::open_pdf;
+use crate::ffi::{DOCUMENTS, Handle, HandleStore};
+use crate::pdf::document::Document;
+use crate::pdf::zugferd::{self, EmbeddedInvoice, InvoiceMetadata};
 use std::ffi::{CStr, CString, c_char};
 use std::ptr;
 use std::sync::LazyLock;
@@ -91,6 +94,41 @@ impl ZugferdInfo {
     pub fn is_zugferd(&self) -> bool {
         self.profile != PDF_NOT_ZUGFERD
     }
+
+    /// Describe an embedded invoice from the document's XMP metadata.
+    /// Files that are not PDF/A-3 are not ZUGFeRD, whatever they attach.
+    pub fn from_metadata(invoice: &EmbeddedInvoice, metadata: Option<&InvoiceMetadata>) -> Self {
+        let mut info = Self::new();
+        info.xml_filename = invoice.filename.clone();
+        let Some(metadata) = metadata else {
+            return info;
+        };
+        info.has_xmp = true;
+        if !metadata.is_pdfa3() {
+            return info;
+        }
+        info.conformance = metadata.conformance_level.clone().unwrap_or_default();
+        info.profile = profile_from_conformance(&info.conformance);
+        info.version = metadata
+            .version
+            .as_deref()
+            .and_then(|v| v.replace('p', ".").parse().ok())
+            .unwrap_or(0.0);
+        info
+    }
+}
+
+/// Profile constant for an XMP `ConformanceLevel` value
+fn profile_from_conformance(level: &str) -> i32 {
+    match level.trim().to_ascii_uppercase().as_str() {
+        "MINIMUM" => PDF_ZUGFERD_MINIMUM,
+        "BASIC WL" => PDF_ZUGFERD_BASIC_WL,
+        "BASIC" => PDF_ZUGFERD_BASIC,
+        "EN 16931" | "EN16931" | "COMFORT" => PDF_ZUGFERD_COMFORT,
+        "EXTENDED" => PDF_ZUGFERD_EXTENDED,
+        "XRECHNUNG" => PDF_ZUGFERD_XRECHNUNG,
+        _ => PDF_ZUGFERD_UNKNOWN,
+    }
 }
 
 // ============================================================================
@@ -171,6 +209,14 @@ impl ZugferdContext {
 pub static ZUGFERD_CONTEXTS: LazyLock<HandleStore<ZugferdContext>> =
     LazyLock::new(HandleStore::new);
 
+/// Embedded invoice and XMP properties of an open document
+fn load_invoice(doc: DocumentHandle) -> Option<(EmbeddedInvoice, Option<InvoiceMetadata>)> {
+    let parsed = open_pdf(doc)?;
+    let invoice = zugferd::find_invoice(&parsed).ok()??;
+    let metadata = zugferd::read_invoice_metadata(&parsed).ok().flatten();
+    Some((invoice, metadata))
+}
+
 // ============================================================================
 // FFI Functions - Context Management
 // ============================================================================
@@ -213,15 +259,22 @@ pub extern "C" fn pdf_zugferd_profile(
             return info.profile;
         }
 
-        // In a real implementation, this would parse the PDF's XMP metadata
-        // and embedded files to detect ZUGFeRD. For now, return not ZUGFeRD.
-        let info = ZugferdInfo::new();
-        let profile = info.profile;
+        let info = match load_invoice(zctx.document) {
+            Some((invoice, metadata)) => {
+                let info = ZugferdInfo::from_metadata(&invoice, metadata.as_ref());
+                if zctx.xml_data.is_none() {
+                    zctx.xml_data = Some(invoice.xml);
+                }
+                info
+            }
+            None => ZugferdInfo::new(),
+        };
+        let (profile, version) = (info.profile, info.version);
         zctx.info = Some(info);
 
         if !version_out.is_null() {
             unsafe {
-                *version_out = 0.0;
+                *version_out = version;
             }
         }
         return profile;
@@ -278,6 +331,29 @@ pub extern "C" fn pdf_zugferd_xml(
     ptr::null()
 }
 
+/// Copy the embedded invoice XML of a document into `buf`.
+/// Returns the full XML length, or 0 if the document has no invoice
+/// attachment; at most `size` bytes are written and `buf` may be null
+/// to query the length.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdf_zugferd_extract_xml(
+    _ctx: ContextHandle,
+    doc: DocumentHandle,
+    buf: *mut u8,
+    size: usize,
+) -> usize {
+    let Some((invoice, _)) = load_invoice(doc) else {
+        return 0;
+    };
+    if !buf.is_null() {
+        let n = invoice.xml.len().min(size);
+        unsafe {
+            ptr::copy_nonoverlapping(invoice.xml.as_ptr(), buf, n);
+        }
+    }
+    invoice.xml.len()
+}
+
 /// Set XML data for the ZUGFeRD context (for testing/embedding).
 #[unsafe(no_mangle)]
 pub extern "C" fn pdf_zugferd_set_xml(
@@ -512,6 +588,10 @@ pub extern "C" fn pdf_zugferd_af_relationship(_ctx: ContextHandle) -> *mut c_cha
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
 
     #[test]
     fn test_profile_constants() {
@@ -703,4 +783,115 @@ mod tests {
             pdf_zugferd_free_string(af);
         }
     }
+
+    const INVOICE_XML: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <rsm:CrossIndustryInvoice xmlns:rsm=\"urn:un:unece:uncefact:data:standard:CrossIndustryInvoice:100\">\
+        <rsm:ExchangedDocument><ram:ID>INV-0042</ram:ID></rsm:ExchangedDocument>\
+        </rsm:CrossIndustryInvoice>\n";
+
+    /// A Factur-X file: PDF/A-3 XMP and a compressed `factur-x.xml`
+    /// attachment behind a two-level name tree
+    fn factur_x_pdf(pdfa_part: u32) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(INVOICE_XML.as_bytes()).unwrap();
+        let xml = encoder.finish().unwrap();
+        let xmp = format!(
+            "<?xpacket begin=\"\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+             <x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF \
+             xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\
+             <rdf:Description rdf:about=\"\" xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\" \
+             pdfaid:part=\"{pdfa_part}\" pdfaid:conformance=\"B\"/>\
+             <rdf:Description rdf:about=\"\" \
+             xmlns:fx=\"urn:factur-x:pdfa:CrossIndustryDocument:invoice:1p0#\">\
+             <fx:DocumentType>INVOICE</fx:DocumentType>\
+             <fx:DocumentFileName>factur-x.xml</fx:DocumentFileName>\
+             <fx:Version>1.0</fx:Version>\
+             <fx:ConformanceLevel>EN 16931</fx:ConformanceLevel>\
+             </rdf:Description></rdf:RDF></x:xmpmeta>\n<?xpacket end=\"w\"?>"
+        );
+
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R /Metadata 4 0 R /Names << /EmbeddedFiles 5 0 R >> \
+              /AF [7 0 R] >>"
+                .to_vec(),
+            b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] >>".to_vec(),
+        ];
+        let mut metadata = format!(
+            "<< /Type /Metadata /Subtype /XML /Length {} >>\nstream\n",
+            xmp.len()
+        )
+        .into_bytes();
+        metadata.extend_from_slice(xmp.as_bytes());
+        metadata.extend_from_slice(b"\nendstream");
+        objects.push(metadata);
+        objects.push(b"<< /Kids [6 0 R 9 0 R] >>".to_vec());
+        objects.push(
+            b"<< /Limits [(factur-x.xml) (factur-x.xml)] /Names [(factur-x.xml) 7 0 R] >>".to_vec(),
+        );
+        objects.push(
+            b"<< /Type /Filespec /F (factur-x.xml) /UF (factur-x.xml) /AFRelationship /Data \
+              /EF << /F 8 0 R >> >>"
+                .to_vec(),
+        );
+        let mut stream = format!(
+            "<< /Type /EmbeddedFile /Subtype /text#2Fxml /Filter /FlateDecode /Length {} >>\nstream\n",
+            xml.len()
+        )
+        .into_bytes();
+        stream.extend_from_slice(&xml);
+        stream.extend_from_slice(b"\nendstream");
+        objects.push(stream);
+        objects.push(
+            b"<< /Limits [(readme.txt) (readme.txt)] /Names [(readme.txt) << /F (readme.txt) >>] >>"
+                .to_vec(),
+        );
+
+        build_pdf(&objects)
+    }
+
+    #[test]
+    fn test_ffi_extract_factur_x() {
+        let ctx = 0;
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(factur_x_pdf(3)));
+
+        let len = pdf_zugferd_extract_xml(ctx, doc, ptr::null_mut(), 0);
+        assert_eq!(len, INVOICE_XML.len());
+        let mut buf = vec![0u8; len];
+        assert_eq!(
+            pdf_zugferd_extract_xml(ctx, doc, buf.as_mut_ptr(), len),
+            len
+        );
+        assert_eq!(buf, INVOICE_XML.as_bytes());
+
+        let zugferd = pdf_new_zugferd_context(ctx, doc);
+        let mut version: f32 = 0.0;
+        let profile = pdf_zugferd_profile(ctx, zugferd, &mut version);
+        assert_eq!(profile, PDF_FACTURX_EN16931);
+        assert_eq!(version, 1.0);
+        let s = pdf_zugferd_profile_to_string(ctx, profile);
+        unsafe {
+            assert_eq!(
+                CStr::from_ptr(s).to_string_lossy(),
+                "ZUGFeRD Comfort (EN16931)"
+            );
+            pdf_zugferd_free_string(s);
+        }
+        let mut xml_len = 0;
+        assert!(!pdf_zugferd_xml(ctx, zugferd, &mut xml_len).is_null());
+        assert_eq!(xml_len, INVOICE_XML.len());
+        pdf_drop_zugferd_context(ctx, zugferd);
+        DOCUMENTS.remove(doc);
+
+        // The same attachment in a PDF/A-2 file is not an e-invoice
+        let doc = DOCUMENTS.insert(crate::ffi::document::Document::new(factur_x_pdf(2)));
+        assert_eq!(
+            pdf_zugferd_extract_xml(ctx, doc, ptr::null_mut(), 0),
+            INVOICE_XML.len()
+        );
+        let zugferd = pdf_new_zugferd_context(ctx, doc);
+        assert_eq!(pdf_is_zugferd(ctx, zugferd), 0);
+        pdf_drop_zugferd_context(ctx, zugferd);
+        DOCUMENTS.remove(doc);
+    }
 }
@@ -11,7 +11,8 @@ pub struct Pixmap {
     y: i32,
     width: i32,
     height: i32,
-    n: i32, // Number of components
+    n: i32,     // Number of components
+    spots: i32, // Spot colorants after the process colors
     alpha: bool,
     stride: i32,
     samples: Vec<u8>,
@@ -30,6 +31,7 @@ impl Pixmap {
             width,
             height,
             n,
+            spots: 0,
             alpha,
             stride,
             samples: vec![0u8; size],
@@ -50,6 +52,7 @@ impl Pixmap {
             width,
             height,
             n,
+            spots: 0,
             alpha,
             stride,
             samples: vec![0u8; size],
@@ -57,6 +60,16 @@ impl Pixmap {
         }
     }
 
+    /// Add `spots` spot colorants between the process colors and alpha
+    pub fn with_spots(mut self, spots: i32) -> Self {
+        let spots = spots.max(0);
+        self.n += spots;
+        self.spots = spots;
+        self.stride = self.width * self.n;
+        self.samples = vec![0u8; (self.stride * self.height).max(0) as usize];
+        self
+    }
+
     pub fn clear(&mut self) {
         self.samples.fill(0);
     }
@@ -157,11 +170,12 @@ pub extern "C" fn fz_new_pixmap(
     cs: ColorspaceHandle,
     w: i32,
     h: i32,
-    _seps: Handle, // Separations not implemented yet
+    seps: Handle,
     alpha: i32,
 ) -> Handle {
     let cs = if cs == 0 { FZ_COLORSPACE_RGB } else { cs };
-    PIXMAPS.insert(Pixmap::new(cs, w, h, alpha != 0))
+    let spots = super::separation::spot_count(seps);
+    PIXMAPS.insert(Pixmap::new(cs, w, h, alpha != 0).with_spots(spots))
 }
 
 /// Create a new pixmap with bounding box
@@ -170,11 +184,12 @@ pub extern "C" fn fz_new_pixmap_with_bbox(
     _ctx: Handle,
     cs: ColorspaceHandle,
     bbox: fz_irect,
-    _seps: Handle,
+    seps: Handle,
     alpha: i32,
 ) -> Handle {
     let cs = if cs == 0 { FZ_COLORSPACE_RGB } else { cs };
-    PIXMAPS.insert(Pixmap::with_bbox(cs, bbox, alpha != 0))
+    let spots = super::separation::spot_count(seps);
+    PIXMAPS.insert(Pixmap::with_bbox(cs, bbox, alpha != 0).with_spots(spots))
 }
 
 /// Keep (increment ref) pixmap
@@ -244,12 +259,23 @@ pub extern "C" fn fz_pixmap_components(_ctx: Handle, pix: Handle) -> i32 {
     0
 }
 
-/// Get number of colorants (excluding alpha)
+/// Get number of process colorants (excluding spots and alpha)
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_pixmap_colorants(_ctx: Handle, pix: Handle) -> i32 {
     if let Some(p) = PIXMAPS.get(pix) {
         if let Ok(guard) = p.lock() {
-            return guard.n - i32::from(guard.alpha);
+            return guard.n - guard.spots - i32::from(guard.alpha);
+        }
+    }
+    0
+}
+
+/// Get number of spot colorants
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_pixmap_spots(_ctx: Handle, pix: Handle) -> i32 {
+    if let Some(p) = PIXMAPS.get(pix) {
+        if let Ok(guard) = p.lock() {
+            return guard.spots;
         }
     }
     0
@@ -425,6 +451,7 @@ pub extern "C" fn fz_clone_pixmap(_ctx: Handle, pix: Handle) -> Handle {
                 width: guard.width,
                 height: guard.height,
                 n: guard.n,
+                spots: guard.spots,
                 alpha: guard.alpha,
                 stride: guard.stride,
                 samples: guard.samples.clone(),
@@ -459,8 +486,8 @@ pub extern "C" fn fz_convert_pixmap(
 
             let mut new_samples = vec![0u8; new_size];
 
-            // Simple conversion: copy/convert each pixel
-            let src_colorants = (guard.n - i32::from(guard.alpha)) as usize;
+            // Simple conversion: copy/convert each pixel; spots are dropped
+            let src_colorants = (guard.n - guard.spots - i32::from(guard.alpha)) as usize;
             let dst_colorants = target_n as usize;
 
             for y in 0..guard.height {
@@ -491,7 +518,7 @@ pub extern "C" fn fz_convert_pixmap(
 
                     // Copy alpha if requested
                     if alpha && guard.alpha {
-                        let src_alpha_offset = src_offset + src_colorants;
+                        let src_alpha_offset = src_offset + guard.n as usize - 1;
                         let dst_alpha_offset = dst_offset + dst_colorants;
                         if let (Some(&src_alpha), Some(dst_alpha)) = (
                             guard.samples.get(src_alpha_offset),
@@ -515,6 +542,7 @@ pub extern "C" fn fz_convert_pixmap(
                 width: guard.width,
                 height: guard.height,
                 n: new_n,
+                spots: 0,
                 alpha,
                 stride: new_stride,
                 samples: new_samples,
@@ -1148,4 +1176,27 @@ mod tests {
         let result = convert_color(FZ_COLORSPACE_RGB, FZ_COLORSPACE_RGB, &[100, 150, 200], 3);
         assert_eq!(result, vec![100, 150, 200]);
     }
+
+    #[test]
+    fn test_pixmap_with_spot_separations() {
+        use super::super::separation::{
+            SeparationBehavior, fz_add_separation, fz_drop_separations, fz_new_separations,
+            fz_set_separation_behavior,
+        };
+
+        let seps = fz_new_separations(0, 1);
+        let name = c"Gold";
+        fz_add_separation(0, seps, name.as_ptr(), 0, 0.0, 0.2, 0.8, 0.1);
+        fz_add_separation(0, seps, name.as_ptr(), 0, 0.0, 0.0, 0.0, 0.0);
+        fz_set_separation_behavior(0, seps, 0, SeparationBehavior::Spot as i32);
+
+        let pix = fz_new_pixmap(0, FZ_COLORSPACE_RGB, 4, 2, seps, 1);
+        assert_eq!(fz_pixmap_components(0, pix), 5);
+        assert_eq!(fz_pixmap_colorants(0, pix), 3);
+        assert_eq!(fz_pixmap_spots(0, pix), 1);
+        assert_eq!(fz_pixmap_stride(0, pix), 20);
+
+        fz_drop_pixmap(0, pix);
+        fz_drop_separations(0, seps);
+    }
 }
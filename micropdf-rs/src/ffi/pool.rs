@@ -1,16 +1,26 @@
 //! C FFI for memory pool - MuPDF compatible
 //! Safe Rust implementation of fz_pool
+//!
+//! A pool is a bump allocator: allocations are carved in order out of the
+//! current block and are never freed one by one. Resetting the pool frees
+//! everything at once while keeping the blocks, so per-page or
+//! per-document parsing reuses the same memory instead of allocating each
+//! object separately.
 
 use super::{Handle, HandleStore};
+use std::cell::{Cell, RefCell};
 use std::sync::LazyLock;
 
-/// Default block size for pool allocations
+/// Default size of a pool's first block
 const DEFAULT_BLOCK_SIZE: usize = 4096;
 
+/// Blocks double in size up to this limit
+const MAX_BLOCK_SIZE: usize = 1 << 20;
+
 /// Memory block in a pool
 #[derive(Debug)]
 pub struct PoolBlock {
-    /// Block data
+    /// Block data; never resized, so pointers into it stay valid
     data: Vec<u8>,
     /// Current position in block
     pos: usize,
@@ -28,118 +38,223 @@ impl PoolBlock {
         self.data.len() - self.pos
     }
 
-    fn allocate(&mut self, size: usize, align: usize) -> Option<usize> {
-        // Calculate the base address of the data buffer
+    fn allocate(&mut self, size: usize, align: usize) -> Option<*mut u8> {
+        // Align the address, not just the offset
         let base_addr = self.data.as_ptr() as usize;
-
-        // Calculate the address at current position
-        let current_addr = base_addr + self.pos;
-
-        // Align the address (not just the offset)
-        let aligned_addr = (current_addr + align - 1) & !(align - 1);
-
-        // Calculate the new offset
+        let aligned_addr = (base_addr + self.pos).checked_add(align - 1)? & !(align - 1);
         let aligned_pos = aligned_addr - base_addr;
 
-        if aligned_pos + size <= self.data.len() {
-            let offset = aligned_pos;
+        if aligned_pos.checked_add(size)? <= self.data.len() {
             self.pos = aligned_pos + size;
-            Some(offset)
+            Some(self.data.as_mut_ptr().wrapping_add(aligned_pos))
         } else {
             None
         }
     }
 }
 
+/// A value moved into a pool whose destructor must run
+#[derive(Debug)]
+struct PoolDrop {
+    addr: usize,
+    drop: unsafe fn(*mut u8),
+}
+
+/// Drop the `T` at `ptr` in place
+unsafe fn drop_value<T>(ptr: *mut u8) {
+    // SAFETY: Only registered for a live T written by `Pool::alloc`
+    unsafe { ptr.cast::<T>().drop_in_place() }
+}
+
 /// Memory pool structure
+///
+/// Allocation takes `&self`, so any number of allocations can be live at
+/// once; `reset` takes `&mut self`, which guarantees none are.
 #[derive(Debug)]
 pub struct Pool {
-    /// Block size for new allocations
-    pub block_size: usize,
-    /// All allocated blocks
-    pub blocks: Vec<PoolBlock>,
-    /// Total bytes allocated
-    pub total_allocated: usize,
+    /// Minimum block size, and the size of the first block; each new
+    /// block doubles the last
+    block_size: Cell<usize>,
+    /// All allocated blocks, in the order they are filled
+    blocks: RefCell<Vec<PoolBlock>>,
+    /// Index of the block being allocated from
+    current: Cell<usize>,
     /// Total bytes used
-    pub total_used: usize,
+    total_used: Cell<usize>,
     /// High water mark (max used)
-    pub high_water: usize,
+    high_water: Cell<usize>,
     /// Number of allocations
-    pub alloc_count: usize,
+    alloc_count: Cell<usize>,
+    /// Values allocated with `alloc` that need dropping
+    drops: RefCell<Vec<PoolDrop>>,
     /// Pool name (for debugging)
     pub name: String,
 }
 
 impl Default for Pool {
     fn default() -> Self {
+        Self::with_block_size(DEFAULT_BLOCK_SIZE)
+    }
+}
+
+impl Pool {
+    /// A pool whose first block holds `block_size` bytes
+    pub fn with_block_size(block_size: usize) -> Self {
         Self {
-            block_size: DEFAULT_BLOCK_SIZE,
-            blocks: Vec::new(),
-            total_allocated: 0,
-            total_used: 0,
-            high_water: 0,
-            alloc_count: 0,
+            block_size: Cell::new(block_size.max(64)),
+            blocks: RefCell::new(Vec::new()),
+            current: Cell::new(0),
+            total_used: Cell::new(0),
+            high_water: Cell::new(0),
+            alloc_count: Cell::new(0),
+            drops: RefCell::new(Vec::new()),
             name: String::new(),
         }
     }
-}
 
-impl Pool {
-    /// Allocate memory from pool
-    fn alloc(&mut self, size: usize, align: usize) -> Option<*mut u8> {
+    /// Move `value` into the pool
+    ///
+    /// The value lives until the pool is reset or dropped, when its
+    /// destructor runs.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc<T: Send>(&self, value: T) -> &mut T {
+        let size = std::mem::size_of::<T>().max(1);
+        let ptr = self
+            .alloc_bytes(size, std::mem::align_of::<T>())
+            .expect("pool allocation size overflow")
+            .cast::<T>();
+        // SAFETY: The region is fresh, aligned for T and big enough; it is
+        // handed out once and stays put until `reset` or drop, both of
+        // which need `&mut self` and so outlive every returned reference
+        unsafe {
+            ptr.write(value);
+            if std::mem::needs_drop::<T>() {
+                self.drops.borrow_mut().push(PoolDrop {
+                    addr: ptr as usize,
+                    drop: drop_value::<T>,
+                });
+            }
+            &mut *ptr
+        }
+    }
+
+    /// Allocate `size` bytes aligned to `align` (a power of two)
+    ///
+    /// Only the current block is tried, so allocation is constant time.
+    /// When it is full the next block kept from before a reset is used,
+    /// or a new block twice the size of the last is added.
+    pub fn alloc_bytes(&self, size: usize, align: usize) -> Option<*mut u8> {
         if size == 0 {
             return Some(std::ptr::null_mut());
         }
-
         let align = align.max(1);
+        if !align.is_power_of_two() {
+            return None;
+        }
 
-        // Try to allocate from existing blocks
-        for block in &mut self.blocks {
-            if let Some(offset) = block.allocate(size, align) {
-                self.total_used += size;
-                self.alloc_count += 1;
-                if self.total_used > self.high_water {
-                    self.high_water = self.total_used;
+        let mut blocks = self.blocks.borrow_mut();
+        let mut current = self.current.get();
+        let ptr = loop {
+            if let Some(block) = blocks.get_mut(current) {
+                if let Some(ptr) = block.allocate(size, align) {
+                    break ptr;
+                }
+                if current + 1 < blocks.len() {
+                    current += 1;
+                    continue;
                 }
-                return Some(block.data.as_mut_ptr().wrapping_add(offset));
             }
+            let next = match blocks.last() {
+                Some(last) => (last.data.len() * 2).min(MAX_BLOCK_SIZE),
+                None => 0,
+            };
+            let next = next.max(self.block_size.get());
+            blocks.push(PoolBlock::new(next.max(size.checked_add(align)?)));
+            current = blocks.len() - 1;
+        };
+        self.current.set(current);
+
+        let used = self.total_used.get() + size;
+        self.total_used.set(used);
+        self.high_water.set(self.high_water.get().max(used));
+        self.alloc_count.set(self.alloc_count.get() + 1);
+        Some(ptr)
+    }
+
+    /// Free every allocation at once, keeping the blocks for reuse
+    pub fn reset(&mut self) {
+        self.run_drops();
+        for block in self.blocks.get_mut() {
+            block.pos = 0;
         }
+        self.current.set(0);
+        self.total_used.set(0);
+        self.alloc_count.set(0);
+    }
 
-        // Need a new block
-        let new_block_size = self.block_size.max(size + align);
-        let mut new_block = PoolBlock::new(new_block_size);
-        self.total_allocated += new_block_size;
-
-        if let Some(offset) = new_block.allocate(size, align) {
-            let ptr = new_block.data.as_mut_ptr().wrapping_add(offset);
-            self.blocks.push(new_block);
-            self.total_used += size;
-            self.alloc_count += 1;
-            if self.total_used > self.high_water {
-                self.high_water = self.total_used;
-            }
-            return Some(ptr);
+    /// Release blocks holding no allocations
+    pub fn shrink(&mut self) {
+        let blocks = self.blocks.get_mut();
+        blocks.retain(|b| b.pos > 0);
+        self.current.set(blocks.len().saturating_sub(1));
+    }
+
+    fn run_drops(&mut self) {
+        for entry in self.drops.get_mut().drain(..) {
+            // SAFETY: Each entry is a live value written by `alloc`
+            unsafe { (entry.drop)(entry.addr as *mut u8) }
         }
+    }
 
-        None
+    /// Minimum size of new blocks
+    pub fn block_size(&self) -> usize {
+        self.block_size.get()
     }
 
-    /// Reset pool (free all memory for reuse)
-    fn reset(&mut self) {
-        for block in &mut self.blocks {
-            block.pos = 0;
-        }
-        self.total_used = 0;
-        self.alloc_count = 0;
+    /// Set the minimum size of blocks added from now on
+    pub fn set_block_size(&self, size: usize) {
+        self.block_size.set(size.max(64));
     }
 
-    /// Shrink pool (release unused blocks)
-    fn shrink(&mut self) {
-        // Keep only blocks that have been used
-        self.blocks.retain(|b| b.pos > 0);
+    /// Total bytes held in blocks
+    pub fn allocated(&self) -> usize {
+        self.blocks.borrow().iter().map(|b| b.data.len()).sum()
+    }
+
+    /// Bytes handed out since the last reset
+    pub fn used(&self) -> usize {
+        self.total_used.get()
+    }
+
+    /// Most bytes ever in use at once
+    pub fn high_water(&self) -> usize {
+        self.high_water.get()
+    }
+
+    /// Allocations since the last reset
+    pub fn alloc_count(&self) -> usize {
+        self.alloc_count.get()
+    }
+
+    /// Number of blocks
+    pub fn block_count(&self) -> usize {
+        self.blocks.borrow().len()
+    }
 
-        // Recalculate total allocated
-        self.total_allocated = self.blocks.iter().map(|b| b.data.len()).sum();
+    /// Sizes of the blocks, in the order they are filled
+    pub fn block_sizes(&self) -> Vec<usize> {
+        self.blocks.borrow().iter().map(|b| b.data.len()).collect()
+    }
+
+    /// Free space left in all blocks
+    pub fn available(&self) -> usize {
+        self.blocks.borrow().iter().map(PoolBlock::available).sum()
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        self.run_drops();
     }
 }
 
@@ -159,11 +274,7 @@ pub extern "C" fn fz_new_pool(_ctx: Handle) -> Handle {
 /// Create a new memory pool with specified block size
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_new_pool_with_size(_ctx: Handle, block_size: usize) -> Handle {
-    let pool = Pool {
-        block_size: block_size.max(64),
-        ..Default::default()
-    };
-    POOLS.insert(pool)
+    POOLS.insert(Pool::with_block_size(block_size))
 }
 
 /// Create a named pool (for debugging)
@@ -176,10 +287,8 @@ pub extern "C" fn fz_new_pool_named(_ctx: Handle, name: *const std::ffi::c_char)
         c_str.to_str().unwrap_or("").to_string()
     };
 
-    let pool = Pool {
-        name: pool_name,
-        ..Default::default()
-    };
+    let mut pool = Pool::default();
+    pool.name = pool_name;
     POOLS.insert(pool)
 }
 
@@ -191,8 +300,8 @@ pub extern "C" fn fz_new_pool_named(_ctx: Handle, name: *const std::ffi::c_char)
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_pool_alloc(_ctx: Handle, pool: Handle, size: usize) -> *mut u8 {
     if let Some(p) = POOLS.get(pool) {
-        if let Ok(mut guard) = p.lock() {
-            return guard.alloc(size, 8).unwrap_or(std::ptr::null_mut());
+        if let Ok(guard) = p.lock() {
+            return guard.alloc_bytes(size, 8).unwrap_or(std::ptr::null_mut());
         }
     }
     std::ptr::null_mut()
@@ -207,8 +316,10 @@ pub extern "C" fn fz_pool_alloc_aligned(
     align: usize,
 ) -> *mut u8 {
     if let Some(p) = POOLS.get(pool) {
-        if let Ok(mut guard) = p.lock() {
-            return guard.alloc(size, align).unwrap_or(std::ptr::null_mut());
+        if let Ok(guard) = p.lock() {
+            return guard
+                .alloc_bytes(size, align)
+                .unwrap_or(std::ptr::null_mut());
         }
     }
     std::ptr::null_mut()
@@ -219,9 +330,13 @@ pub extern "C" fn fz_pool_alloc_aligned(
 pub extern "C" fn fz_pool_calloc(_ctx: Handle, pool: Handle, count: usize, size: usize) -> *mut u8 {
     let total_size = count.saturating_mul(size);
     if let Some(p) = POOLS.get(pool) {
-        if let Ok(mut guard) = p.lock() {
-            if let Some(ptr) = guard.alloc(total_size, 8) {
-                // Memory is already zeroed in our implementation
+        if let Ok(guard) = p.lock() {
+            if let Some(ptr) = guard.alloc_bytes(total_size, 8) {
+                // Blocks reused after a reset hold old data
+                if !ptr.is_null() {
+                    // SAFETY: alloc_bytes returned total_size writable bytes
+                    unsafe { std::ptr::write_bytes(ptr, 0, total_size) };
+                }
                 return ptr;
             }
         }
@@ -250,8 +365,8 @@ pub extern "C" fn fz_pool_strdup(
     let size = len + 1;
 
     if let Some(p) = POOLS.get(pool) {
-        if let Ok(mut guard) = p.lock() {
-            if let Some(ptr) = guard.alloc(size, 1) {
+        if let Ok(guard) = p.lock() {
+            if let Some(ptr) = guard.alloc_bytes(size, 1) {
                 unsafe {
                     std::ptr::copy_nonoverlapping(s as *const u8, ptr, size);
                 }
@@ -290,8 +405,8 @@ pub extern "C" fn fz_pool_shrink(_ctx: Handle, pool: Handle) {
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_pool_set_block_size(_ctx: Handle, pool: Handle, size: usize) {
     if let Some(p) = POOLS.get(pool) {
-        if let Ok(mut guard) = p.lock() {
-            guard.block_size = size.max(64);
+        if let Ok(guard) = p.lock() {
+            guard.set_block_size(size);
         }
     }
 }
@@ -305,7 +420,7 @@ pub extern "C" fn fz_pool_set_block_size(_ctx: Handle, pool: Handle, size: usize
 pub extern "C" fn fz_pool_allocated(_ctx: Handle, pool: Handle) -> usize {
     if let Some(p) = POOLS.get(pool) {
         if let Ok(guard) = p.lock() {
-            return guard.total_allocated;
+            return guard.allocated();
         }
     }
     0
@@ -316,7 +431,7 @@ pub extern "C" fn fz_pool_allocated(_ctx: Handle, pool: Handle) -> usize {
 pub extern "C" fn fz_pool_used(_ctx: Handle, pool: Handle) -> usize {
     if let Some(p) = POOLS.get(pool) {
         if let Ok(guard) = p.lock() {
-            return guard.total_used;
+            return guard.used();
         }
     }
     0
@@ -327,7 +442,7 @@ pub extern "C" fn fz_pool_used(_ctx: Handle, pool: Handle) -> usize {
 pub extern "C" fn fz_pool_high_water(_ctx: Handle, pool: Handle) -> usize {
     if let Some(p) = POOLS.get(pool) {
         if let Ok(guard) = p.lock() {
-            return guard.high_water;
+            return guard.high_water();
         }
     }
     0
@@ -338,7 +453,7 @@ pub extern "C" fn fz_pool_high_water(_ctx: Handle, pool: Handle) -> usize {
 pub extern "C" fn fz_pool_alloc_count(_ctx: Handle, pool: Handle) -> usize {
     if let Some(p) = POOLS.get(pool) {
         if let Ok(guard) = p.lock() {
-            return guard.alloc_count;
+            return guard.alloc_count();
         }
     }
     0
@@ -349,7 +464,7 @@ pub extern "C" fn fz_pool_alloc_count(_ctx: Handle, pool: Handle) -> usize {
 pub extern "C" fn fz_pool_block_count(_ctx: Handle, pool: Handle) -> usize {
     if let Some(p) = POOLS.get(pool) {
         if let Ok(guard) = p.lock() {
-            return guard.blocks.len();
+            return guard.block_count();
         }
     }
     0
@@ -360,7 +475,7 @@ pub extern "C" fn fz_pool_block_count(_ctx: Handle, pool: Handle) -> usize {
 pub extern "C" fn fz_pool_available(_ctx: Handle, pool: Handle) -> usize {
     if let Some(p) = POOLS.get(pool) {
         if let Ok(guard) = p.lock() {
-            return guard.blocks.iter().map(|b| b.available()).sum();
+            return guard.available();
         }
     }
     0
@@ -371,11 +486,12 @@ pub extern "C" fn fz_pool_available(_ctx: Handle, pool: Handle) -> usize {
 pub extern "C" fn fz_pool_fragmentation(_ctx: Handle, pool: Handle) -> f32 {
     if let Some(p) = POOLS.get(pool) {
         if let Ok(guard) = p.lock() {
-            if guard.total_allocated == 0 {
+            let allocated = guard.allocated();
+            if allocated == 0 {
                 return 0.0;
             }
-            let wasted = guard.total_allocated - guard.total_used;
-            return wasted as f32 / guard.total_allocated as f32;
+            let wasted = allocated - guard.used();
+            return wasted as f32 / allocated as f32;
         }
     }
     0.0
@@ -464,13 +580,13 @@ mod tests {
     fn test_pool_shrink() {
         let pool = fz_new_pool_with_size(0, 256);
 
-        // Allocate multiple blocks
+        // Allocate multiple blocks (256, 512 and 1024 bytes)
         for _ in 0..5 {
             fz_pool_alloc(0, pool, 256);
         }
 
         let blocks_before = fz_pool_block_count(0, pool);
-        assert!(blocks_before >= 5);
+        assert!(blocks_before >= 3);
 
         fz_pool_reset(0, pool);
         fz_pool_shrink(0, pool);
@@ -522,6 +638,72 @@ mod tests {
         fz_drop_pool(0, pool);
     }
 
+    #[test]
+    fn test_pool_growth_and_reset_reuse() {
+        let mut pool = Pool::with_block_size(1024);
+        let mut total = 0u64;
+        for i in 0..10_000u64 {
+            total += *pool.alloc(i);
+        }
+        assert_eq!(total, (0..10_000).sum());
+        assert_eq!(pool.alloc_count(), 10_000);
+
+        // One doubling sequence of blocks, not a block per object
+        let sizes = pool.block_sizes();
+        assert_eq!(sizes.len(), 7);
+        for (i, size) in sizes.iter().enumerate() {
+            assert_eq!(*size, 1024 << i);
+        }
+        let allocated = pool.allocated();
+        assert!(allocated >= 10_000 * 8);
+
+        pool.reset();
+        assert_eq!(pool.used(), 0);
+        assert_eq!(pool.available(), allocated);
+        for i in 0..10_000u64 {
+            pool.alloc(i);
+        }
+        // The same blocks were refilled
+        assert_eq!(pool.block_sizes(), sizes);
+        assert_eq!(pool.allocated(), allocated);
+    }
+
+    #[test]
+    fn test_pool_typed_values_drop_on_reset() {
+        use crate::ffi::pdf_object::types::PdfObj;
+        use crate::pdf::object::Object;
+        use std::sync::Arc;
+
+        let mut pool = Pool::default();
+        let marker = Arc::new(());
+        let a = pool.alloc(PdfObj::from_object(&Object::Int(7)));
+        let b = pool.alloc(Arc::clone(&marker));
+        assert_eq!(a.to_object(), Object::Int(7));
+        assert_eq!(Arc::strong_count(b), 2);
+
+        pool.reset();
+        assert_eq!(Arc::strong_count(&marker), 1);
+
+        pool.alloc(Arc::clone(&marker));
+        drop(pool);
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+
+    #[test]
+    fn test_pool_calloc_after_reset_is_zeroed() {
+        let pool = fz_new_pool_with_size(0, 64);
+        let ptr = fz_pool_alloc(0, pool, 32);
+        // SAFETY: fz_pool_alloc returned 32 writable bytes
+        unsafe { std::ptr::write_bytes(ptr, 0xAB, 32) };
+        fz_pool_reset(0, pool);
+
+        let ptr = fz_pool_calloc(0, pool, 4, 8);
+        // SAFETY: fz_pool_calloc returned 32 bytes
+        let slice = unsafe { std::slice::from_raw_parts(ptr, 32) };
+        assert!(slice.iter().all(|&b| b == 0));
+        fz_drop_pool(0, pool);
+    }
+
     #[test]
     fn test_named_pool() {
         let name = c"test_pool";
@@ -1,7 +1,12 @@
 //! C FFI for separation/spot colors - MuPDF compatible
 //! Safe Rust implementation of fz_separation
 
-use super::{Handle, HandleStore};
+use super::document::{PAGES, open_pdf};
+use super::{DOCUMENTS, Handle, HandleStore};
+use crate::fitz::error::{Error, Result};
+use crate::pdf::document::Document;
+use crate::pdf::function::Function;
+use crate::pdf::object::Object;
 use std::ffi::{CStr, c_char};
 use std::sync::LazyLock;
 
@@ -68,6 +73,210 @@ impl Default for Separations {
 /// Global separations storage
 pub static SEPARATIONS: LazyLock<HandleStore<Separations>> = LazyLock::new(HandleStore::new);
 
+/// Number of separations rendered as their own spot channels
+pub(crate) fn spot_count(seps: Handle) -> i32 {
+    SEPARATIONS
+        .get(seps)
+        .and_then(|s| {
+            s.lock().ok().map(|guard| {
+                guard
+                    .seps
+                    .iter()
+                    .filter(|s| s.behavior == SeparationBehavior::Spot)
+                    .count() as i32
+            })
+        })
+        .unwrap_or(0)
+}
+
+/// Global storage for parsed Separation and DeviceN colorspaces
+pub static SPOT_COLORSPACES: LazyLock<HandleStore<SpotColorspace>> =
+    LazyLock::new(HandleStore::new);
+
+/// Colorspace a tint transform maps into
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlternateSpace {
+    Gray,
+    Rgb,
+    Cmyk,
+    /// CIE L*a*b* with the given white point
+    Lab {
+        white: [f32; 3],
+    },
+}
+
+impl AlternateSpace {
+    /// Parse the alternate space of a Separation or DeviceN colorspace
+    pub fn load(doc: &Document, obj: &Object) -> Result<Self> {
+        let obj = doc.resolve(obj)?;
+        let (family, param) = match &obj {
+            Object::Name(n) => (n.as_str().to_string(), None),
+            Object::Array(items) => match items.first().and_then(Object::as_name) {
+                Some(n) => (n.as_str().to_string(), items.get(1)),
+                None => return Err(Error::format("invalid alternate colorspace")),
+            },
+            _ => return Err(Error::format("invalid alternate colorspace")),
+        };
+        let param = match param {
+            Some(p) => match doc.resolve(p)? {
+                Object::Dict(d) => Some(d),
+                Object::Stream { dict, .. } => Some(dict),
+                _ => None,
+            },
+            None => None,
+        };
+        match family.as_str() {
+            "DeviceGray" | "G" | "CalGray" => Ok(Self::Gray),
+            "DeviceRGB" | "RGB" | "CalRGB" => Ok(Self::Rgb),
+            "DeviceCMYK" | "CMYK" => Ok(Self::Cmyk),
+            "ICCBased" => {
+                let n = match &param {
+                    Some(p) => doc.resolve_key(p, "N")?.and_then(|n| n.as_int()),
+                    None => None,
+                };
+                match n {
+                    Some(1) => Ok(Self::Gray),
+                    Some(3) => Ok(Self::Rgb),
+                    Some(4) => Ok(Self::Cmyk),
+                    _ => Err(Error::format("ICCBased colorspace without a valid /N")),
+                }
+            }
+            "Lab" => {
+                let mut white = [0.9505, 1.0, 1.089];
+                if let Some(Object::Array(wp)) = match &param {
+                    Some(p) => doc.resolve_key(p, "WhitePoint")?,
+                    None => None,
+                } {
+                    for (w, v) in white.iter_mut().zip(&wp) {
+                        *w = v.as_real().unwrap_or(0.0) as f32;
+                    }
+                }
+                Ok(Self::Lab { white })
+            }
+            other => Err(Error::unsupported(format!(
+                "{other} as an alternate colorspace"
+            ))),
+        }
+    }
+
+    /// Number of color components
+    pub fn n(&self) -> usize {
+        match self {
+            Self::Gray => 1,
+            Self::Rgb | Self::Lab { .. } => 3,
+            Self::Cmyk => 4,
+        }
+    }
+
+    /// Convert a color in this space to RGB
+    pub fn to_rgb(&self, color: &[f32]) -> [f32; 3] {
+        let c = |i: usize| color.get(i).copied().unwrap_or(0.0);
+        let rgb = match self {
+            Self::Gray => [c(0); 3],
+            Self::Rgb => [c(0), c(1), c(2)],
+            Self::Cmyk => {
+                let k = c(3);
+                [
+                    (1.0 - c(0)) * (1.0 - k),
+                    (1.0 - c(1)) * (1.0 - k),
+                    (1.0 - c(2)) * (1.0 - k),
+                ]
+            }
+            Self::Lab { white } => lab_to_rgb(c(0), c(1), c(2), white),
+        };
+        rgb.map(|v| v.clamp(0.0, 1.0))
+    }
+}
+
+/// L*a*b* to sRGB through CIE XYZ
+fn lab_to_rgb(l: f32, a: f32, b: f32, white: &[f32; 3]) -> [f32; 3] {
+    let finv = |t: f32| {
+        if t > 6.0 / 29.0 {
+            t * t * t
+        } else {
+            3.0 * (6.0 / 29.0) * (6.0 / 29.0) * (t - 4.0 / 29.0)
+        }
+    };
+    let fy = (l + 16.0) / 116.0;
+    let x = white[0] * finv(fy + a / 500.0);
+    let y = white[1] * finv(fy);
+    let z = white[2] * finv(fy - b / 200.0);
+    let linear = [
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    ];
+    linear.map(|v| {
+        if v <= 0.003_130_8 {
+            12.92 * v
+        } else {
+            1.055 * v.powf(1.0 / 2.4) - 0.055
+        }
+    })
+}
+
+/// A Separation or DeviceN colorspace: named colorants mapped into an
+/// alternate space by a tint transform
+#[derive(Debug, Clone)]
+pub struct SpotColorspace {
+    /// Colorant names; one for Separation
+    pub colorants: Vec<String>,
+    /// Space the tint transform produces colors in
+    pub alternate: AlternateSpace,
+    /// Maps the colorant tints to the alternate space
+    pub tint_transform: Function,
+}
+
+impl SpotColorspace {
+    /// Parse `[/Separation name alternate tintTransform]` or
+    /// `[/DeviceN names alternate tintTransform attributes?]`
+    pub fn load(doc: &Document, obj: &Object) -> Result<Self> {
+        let Object::Array(items) = doc.resolve(obj)? else {
+            return Err(Error::format("spot colorspace is not an array"));
+        };
+        if items.len() < 4 {
+            return Err(Error::format("spot colorspace array too short"));
+        }
+        let family = items[0].as_name().map(|n| n.as_str().to_string());
+        let colorants = match (family.as_deref(), doc.resolve(&items[1])?) {
+            (Some("Separation"), Object::Name(n)) => vec![n.as_str().to_string()],
+            (Some("DeviceN"), Object::Array(names)) => names
+                .iter()
+                .map(|n| {
+                    n.as_name()
+                        .map(|n| n.as_str().to_string())
+                        .ok_or_else(|| Error::format("DeviceN colorant is not a name"))
+                })
+                .collect::<Result<_>>()?,
+            _ => return Err(Error::format("not a Separation or DeviceN colorspace")),
+        };
+        let alternate = AlternateSpace::load(doc, &items[2])?;
+        let tint_transform = Function::load(doc, &items[3])?;
+        if tint_transform.inputs() != colorants.len() {
+            return Err(Error::format(
+                "tint transform inputs do not match colorants",
+            ));
+        }
+        Ok(Self {
+            colorants,
+            alternate,
+            tint_transform,
+        })
+    }
+
+    /// Alternate-space color for the colorant tints
+    pub fn to_alternate(&self, tints: &[f32]) -> Vec<f32> {
+        let mut color = self.tint_transform.eval(tints);
+        color.resize(self.alternate.n(), 0.0);
+        color
+    }
+
+    /// RGB color for the colorant tints
+    pub fn to_rgb(&self, tints: &[f32]) -> [f32; 3] {
+        self.alternate.to_rgb(&self.to_alternate(tints))
+    }
+}
+
 // ============================================================================
 // Separations Creation
 // ============================================================================
@@ -560,6 +769,104 @@ pub extern "C" fn fz_disable_all_separations(_ctx: Handle, seps: Handle) {
     }
 }
 
+// ============================================================================
+// Spot Colorspaces
+// ============================================================================
+
+/// Load the Separation or DeviceN colorspace named `name` in a page's
+/// /ColorSpace resources
+///
+/// Returns a handle, or 0 if the colorspace is missing or invalid.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_load_spot_colorspace(
+    _ctx: Handle,
+    page: Handle,
+    name: *const c_char,
+) -> Handle {
+    if name.is_null() {
+        return 0;
+    }
+    let Ok(name) = unsafe { CStr::from_ptr(name) }.to_str() else {
+        return 0;
+    };
+    match load_page_spot_colorspace(page, name) {
+        Some(cs) => SPOT_COLORSPACES.insert(cs),
+        None => 0,
+    }
+}
+
+fn load_page_spot_colorspace(page: Handle, name: &str) -> Option<SpotColorspace> {
+    let (doc, page_num) = {
+        let page = PAGES.get(page)?;
+        let page = page.lock().ok()?;
+        (page.doc_handle, page.page_num)
+    };
+    let parsed = open_pdf(doc)?;
+    let page = parsed.page(usize::try_from(page_num).ok()?).ok()?;
+    let Object::Dict(resources) = parsed.resolve_key(page.dict(), "Resources").ok()?? else {
+        return None;
+    };
+    let Object::Dict(colorspaces) = parsed.resolve_key(&resources, "ColorSpace").ok()?? else {
+        return None;
+    };
+    SpotColorspace::load(&parsed, colorspaces.get(name)?).ok()
+}
+
+/// Drop a spot colorspace
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_drop_spot_colorspace(_ctx: Handle, cs: Handle) {
+    SPOT_COLORSPACES.remove(cs);
+}
+
+/// Number of colorants of a spot colorspace, or 0 for an invalid handle
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_spot_colorspace_colorants(_ctx: Handle, cs: Handle) -> i32 {
+    SPOT_COLORSPACES
+        .get(cs)
+        .and_then(|cs| cs.lock().ok().map(|cs| cs.colorants.len() as i32))
+        .unwrap_or(0)
+}
+
+/// Convert a Separation tint to RGB
+///
+/// # Safety
+/// `rgb` must point to at least 3 floats.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_separation_to_rgb(_ctx: Handle, cs: Handle, tint: f32, rgb: *mut f32) -> i32 {
+    fz_devicen_to_rgb(_ctx, cs, &tint, 1, rgb)
+}
+
+/// Convert DeviceN tints to RGB
+///
+/// # Safety
+/// - `tints` must point to `n` floats
+/// - `rgb` must point to at least 3 floats
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_devicen_to_rgb(
+    _ctx: Handle,
+    cs: Handle,
+    tints: *const f32,
+    n: i32,
+    rgb: *mut f32,
+) -> i32 {
+    if tints.is_null() || rgb.is_null() || n <= 0 {
+        return -1;
+    }
+    let Some(cs) = SPOT_COLORSPACES.get(cs) else {
+        return -1;
+    };
+    let Ok(cs) = cs.lock() else {
+        return -1;
+    };
+    if n as usize != cs.colorants.len() {
+        return -1;
+    }
+    let tints = unsafe { std::slice::from_raw_parts(tints, n as usize) };
+    let color = cs.to_rgb(tints);
+    unsafe { std::slice::from_raw_parts_mut(rgb, 3) }.copy_from_slice(&color);
+    0
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -567,6 +874,86 @@ pub extern "C" fn fz_disable_all_separations(_ctx: Handle, seps: Handle) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
+
+    /// A one-page document whose /ColorSpace resources are `colorspaces`
+    fn spot_colorspace_pdf(colorspaces: &str) -> Vec<u8> {
+        build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] \
+                 /Resources << /ColorSpace << {colorspaces} >> >> >>"
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_separation_exponential_tint_transform() {
+        // A spot red: CMYK 0 1 1 0 at full tint
+        let data = spot_colorspace_pdf(
+            "/CS0 [/Separation /SpotRed /DeviceCMYK \
+             << /FunctionType 2 /Domain [0 1] /C0 [0 0 0 0] /C1 [0 1 1 0] /N 1 >>]",
+        );
+        let doc = Document::open_bytes(data.clone()).unwrap();
+        let page = doc.page(0).unwrap();
+        let resources = page.dict()["Resources"].as_dict().unwrap();
+        let cs = &resources["ColorSpace"].as_dict().unwrap()["CS0"];
+        let sep = SpotColorspace::load(&doc, cs).unwrap();
+        assert_eq!(sep.colorants, ["SpotRed"]);
+        assert_eq!(sep.alternate, AlternateSpace::Cmyk);
+
+        assert_eq!(sep.to_alternate(&[0.0]), [0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(sep.to_alternate(&[0.5]), [0.0, 0.5, 0.5, 0.0]);
+        assert_eq!(sep.to_alternate(&[1.0]), [0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(sep.to_rgb(&[0.0]), [1.0, 1.0, 1.0]);
+        assert_eq!(sep.to_rgb(&[0.5]), [1.0, 0.5, 0.5]);
+        assert_eq!(sep.to_rgb(&[1.0]), [1.0, 0.0, 0.0]);
+
+        let doc_handle = DOCUMENTS.insert(crate::ffi::document::Document::new(data));
+        let page_handle = crate::ffi::document::fz_load_page(0, doc_handle, 0);
+        let handle = fz_load_spot_colorspace(0, page_handle, c"CS0".as_ptr());
+        assert_eq!(fz_spot_colorspace_colorants(0, handle), 1);
+        let mut rgb = [0.0f32; 3];
+        assert_eq!(fz_separation_to_rgb(0, handle, 0.5, rgb.as_mut_ptr()), 0);
+        assert_eq!(rgb, [1.0, 0.5, 0.5]);
+        assert_eq!(fz_load_spot_colorspace(0, page_handle, c"CS1".as_ptr()), 0);
+        fz_drop_spot_colorspace(0, handle);
+        PAGES.remove(page_handle);
+        DOCUMENTS.remove(doc_handle);
+    }
+
+    #[test]
+    fn test_devicen_calculator_tint_transform() {
+        // Two inks into RGB: the first removes red, the second green
+        let program = "{ exch 1 exch sub exch 1 exch sub 1 }";
+        let data = build_pdf(&[
+            format!(
+                "<< /FunctionType 4 /Domain [0 1 0 1] /Range [0 1 0 1 0 1] /Length {} >>\n\
+                 stream\n{program}\nendstream",
+                program.len()
+            ),
+            "[/DeviceN [/Cyan /Magenta] /DeviceRGB 1 0 R]".to_string(),
+        ]);
+        let doc = Document::open_bytes(data).unwrap();
+        let cs = Object::Ref(crate::pdf::object::ObjRef::new(2, 0));
+        let devicen = SpotColorspace::load(&doc, &cs).unwrap();
+        assert_eq!(devicen.colorants, ["Cyan", "Magenta"]);
+        assert_eq!(devicen.to_rgb(&[1.0, 0.25]), [0.0, 0.75, 1.0]);
+
+        let handle = SPOT_COLORSPACES.insert(devicen);
+        let mut rgb = [0.0f32; 3];
+        assert_eq!(
+            fz_devicen_to_rgb(0, handle, [0.5f32, 0.0].as_ptr(), 2, rgb.as_mut_ptr()),
+            0
+        );
+        assert_eq!(rgb, [0.5, 1.0, 1.0]);
+        assert_eq!(
+            fz_devicen_to_rgb(0, handle, [0.5f32].as_ptr(), 1, rgb.as_mut_ptr()),
+            -1
+        );
+        fz_drop_spot_colorspace(0, handle);
+    }
 
     #[test]
     fn test_new_separations() {
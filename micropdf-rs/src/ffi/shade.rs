@@ -1,7 +1,19 @@
 //! C FFI for shading/gradients - MuPDF compatible
 //! Safe Rust implementation of fz_shade
 
-use super::{Handle, HandleStore};
+use super::colorspace::{
+    FZ_COLORSPACE_CMYK, FZ_COLORSPACE_GRAY, FZ_COLORSPACE_RGB, fz_colorspace_n,
+};
+use super::document::{PAGES, open_pdf};
+use super::geometry::{fz_irect_from_rect, fz_matrix, fz_rect};
+use super::pixmap::Pixmap;
+use super::separation::{AlternateSpace, SpotColorspace};
+use super::{DOCUMENTS, Handle, HandleStore, PIXMAPS};
+use crate::fitz::error::{Error, Result};
+use crate::pdf::document::Document;
+use crate::pdf::function::Function;
+use crate::pdf::object::{Dict, Object};
+use std::ffi::{CStr, c_char};
 use std::sync::LazyLock;
 
 /// Shading type enumeration (PDF spec types)
@@ -137,6 +149,14 @@ pub struct Shade {
     pub domain: [f32; 4],
     /// Matrix for function-based
     pub matrix: [f32; 6],
+    /// Parametric domain `[t0 t1]` of axial and radial shadings
+    pub t_domain: [f32; 2],
+    /// Color functions of `t`: one with n outputs or n with one each. When
+    /// empty, colors come from the color stops.
+    pub functions: Vec<Function>,
+    /// Separation or DeviceN colorspace the functions produce tints in;
+    /// colors are then converted to RGB
+    pub spot: Option<SpotColorspace>,
 }
 
 impl Default for Shade {
@@ -163,10 +183,310 @@ impl Default for Shade {
             bits_per_flag: 2,
             domain: [0.0, 1.0, 0.0, 1.0],
             matrix: [1.0, 0.0, 0.0, 1.0, 0.0, 0.0], // Identity
+            t_domain: [0.0, 1.0],
+            functions: Vec::new(),
+            spot: None,
         }
     }
 }
 
+impl Shade {
+    /// Load an axial (Type 2) or radial (Type 3) shading dictionary
+    pub fn load(doc: &Document, obj: &Object) -> Result<Self> {
+        let obj = doc.resolve(obj)?;
+        let dict = match &obj {
+            Object::Dict(d) => d,
+            Object::Stream { dict, .. } => dict,
+            _ => return Err(Error::format("shading is not a dictionary")),
+        };
+        let shade_type = match doc
+            .resolve_key(dict, "ShadingType")?
+            .and_then(|t| t.as_int())
+        {
+            Some(2) => ShadeType::Linear,
+            Some(3) => ShadeType::Radial,
+            Some(t) => return Err(Error::unsupported(format!("shading type {t}"))),
+            None => return Err(Error::format("shading without /ShadingType")),
+        };
+
+        let cs = doc
+            .resolve_key(dict, "ColorSpace")?
+            .ok_or_else(|| Error::format("shading without /ColorSpace"))?;
+        let is_spot = matches!(&cs, Object::Array(items)
+            if matches!(items.first().and_then(Object::as_name).map(|n| n.as_str()),
+                Some("Separation" | "DeviceN")));
+        let (colorspace, spot) = if is_spot {
+            (FZ_COLORSPACE_RGB, Some(SpotColorspace::load(doc, &cs)?))
+        } else {
+            let colorspace = match AlternateSpace::load(doc, &cs)? {
+                AlternateSpace::Gray => FZ_COLORSPACE_GRAY,
+                AlternateSpace::Rgb => FZ_COLORSPACE_RGB,
+                AlternateSpace::Cmyk => FZ_COLORSPACE_CMYK,
+                AlternateSpace::Lab { .. } => {
+                    return Err(Error::unsupported("Lab shading colorspace"));
+                }
+            };
+            (colorspace, None)
+        };
+
+        let coords = numbers(doc, dict, "Coords")?.unwrap_or_default();
+        let expected = if shade_type == ShadeType::Linear {
+            4
+        } else {
+            6
+        };
+        if coords.len() != expected {
+            return Err(Error::format("invalid shading /Coords"));
+        }
+        let t_domain = match numbers(doc, dict, "Domain")?.as_deref() {
+            Some([t0, t1]) => [*t0, *t1],
+            _ => [0.0, 1.0],
+        };
+        let extend = match doc.resolve_key(dict, "Extend")? {
+            Some(Object::Array(e)) if e.len() == 2 => [
+                e[0].as_bool().unwrap_or(false),
+                e[1].as_bool().unwrap_or(false),
+            ],
+            _ => [false, false],
+        };
+        let functions = match doc.resolve_key(dict, "Function")? {
+            Some(Object::Array(items)) => items
+                .iter()
+                .map(|f| Function::load(doc, f))
+                .collect::<Result<Vec<_>>>()?,
+            Some(f) => vec![Function::load(doc, &f)?],
+            None => return Err(Error::format("shading without /Function")),
+        };
+        let background = match numbers(doc, dict, "Background")? {
+            Some(bg) => {
+                let mut color = [0.0; 4];
+                for (c, v) in color.iter_mut().zip(&bg) {
+                    *c = *v;
+                }
+                Some(color)
+            }
+            None => None,
+        };
+        let bbox = match numbers(doc, dict, "BBox")?.as_deref() {
+            Some([x0, y0, x1, y1]) => [*x0, *y0, *x1, *y1],
+            _ => Self::default().bbox,
+        };
+
+        let mut shade = Self {
+            shade_type,
+            colorspace,
+            bbox,
+            background,
+            use_function: true,
+            extend_start: extend[0],
+            extend_end: extend[1],
+            t_domain,
+            functions,
+            spot,
+            ..Default::default()
+        };
+        if shade_type == ShadeType::Linear {
+            shade.linear_start = ShadePoint {
+                x: coords[0],
+                y: coords[1],
+            };
+            shade.linear_end = ShadePoint {
+                x: coords[2],
+                y: coords[3],
+            };
+        } else {
+            shade.radial_start = ShadePoint {
+                x: coords[0],
+                y: coords[1],
+            };
+            shade.radial_r0 = coords[2];
+            shade.radial_end = ShadePoint {
+                x: coords[3],
+                y: coords[4],
+            };
+            shade.radial_r1 = coords[5];
+        }
+        Ok(shade)
+    }
+
+    /// Color at a point in the shading's domain `[t0 t1]`, in the shade's
+    /// colorspace
+    pub fn color_at(&self, t: f32) -> Vec<f32> {
+        let n = fz_colorspace_n(0, self.colorspace).max(0) as usize;
+        if self.functions.is_empty() {
+            let [t0, t1] = self.t_domain;
+            let s = if t1 == t0 { 0.0 } else { (t - t0) / (t1 - t0) };
+            return self.stop_color(s)[..n.min(4)].to_vec();
+        }
+        let mut color: Vec<f32> = if self.functions.len() == 1 {
+            self.functions[0].eval(&[t])
+        } else {
+            self.functions
+                .iter()
+                .map(|f| f.eval(&[t]).first().copied().unwrap_or(0.0))
+                .collect()
+        };
+        if let Some(spot) = &self.spot {
+            return spot.alternate.to_rgb(&spot.to_alternate(&color)).to_vec();
+        }
+        color.resize(n, 0.0);
+        color
+    }
+
+    /// Color of the color stops at `t` in 0.0..=1.0
+    fn stop_color(&self, t: f32) -> [f32; 4] {
+        if self.color_stops.is_empty() {
+            return [0.0; 4];
+        }
+
+        let t = t.clamp(0.0, 1.0);
+
+        // Find bounding stops
+        let mut prev_stop = &self.color_stops[0];
+        let mut next_stop = &self.color_stops[self.color_stops.len() - 1];
+
+        for stop in &self.color_stops {
+            if stop.offset <= t {
+                prev_stop = stop;
+            }
+            if stop.offset >= t && stop.offset <= next_stop.offset {
+                next_stop = stop;
+                break;
+            }
+        }
+
+        // Interpolate between stops
+        if (next_stop.offset - prev_stop.offset).abs() < f32::EPSILON {
+            prev_stop.color
+        } else {
+            let blend = (t - prev_stop.offset) / (next_stop.offset - prev_stop.offset);
+            std::array::from_fn(|i| {
+                prev_stop.color[i] + blend * (next_stop.color[i] - prev_stop.color[i])
+            })
+        }
+    }
+
+    /// Parametric value `t` of the shading at a point in shading space, or
+    /// `None` where the shading paints nothing
+    pub fn parameter_at(&self, x: f32, y: f32) -> Option<f32> {
+        let s = match self.shade_type {
+            ShadeType::Linear => {
+                let (p0, p1) = (self.linear_start, self.linear_end);
+                let (dx, dy) = (p1.x - p0.x, p1.y - p0.y);
+                let len2 = dx * dx + dy * dy;
+                let s = if len2 == 0.0 {
+                    0.0
+                } else {
+                    ((x - p0.x) * dx + (y - p0.y) * dy) / len2
+                };
+                self.extend(s)?
+            }
+            ShadeType::Radial => self.radial_parameter(x, y)?,
+            _ => return None,
+        };
+        let [t0, t1] = self.t_domain;
+        Some(t0 + s * (t1 - t0))
+    }
+
+    /// `s` clamped to 0..=1 where the shading extends past its ends
+    fn extend(&self, s: f32) -> Option<f32> {
+        if s < 0.0 {
+            self.extend_start.then_some(0.0)
+        } else if s > 1.0 {
+            self.extend_end.then_some(1.0)
+        } else {
+            Some(s)
+        }
+    }
+
+    /// Largest `s` whose circle passes through the point, as the later
+    /// circles are painted over the earlier ones
+    fn radial_parameter(&self, x: f32, y: f32) -> Option<f32> {
+        let (c0, c1) = (self.radial_start, self.radial_end);
+        let (r0, r1) = (self.radial_r0, self.radial_r1);
+        let (cdx, cdy, dr) = (c1.x - c0.x, c1.y - c0.y, r1 - r0);
+        let (pdx, pdy) = (x - c0.x, y - c0.y);
+
+        // |p - c(s)| = r(s) as a s^2 - 2 b s + c = 0
+        let a = cdx * cdx + cdy * cdy - dr * dr;
+        let b = pdx * cdx + pdy * cdy + r0 * dr;
+        let c = pdx * pdx + pdy * pdy - r0 * r0;
+        let roots = if a.abs() < 1e-6 {
+            if b == 0.0 {
+                return None;
+            }
+            [c / (2.0 * b), f32::NAN]
+        } else {
+            let disc = b * b - a * c;
+            if disc < 0.0 {
+                return None;
+            }
+            let sq = disc.sqrt();
+            let (s1, s2) = ((b + sq) / a, (b - sq) / a);
+            [s1.max(s2), s1.min(s2)]
+        };
+        roots
+            .into_iter()
+            .filter(|s| !s.is_nan() && r0 + s * dr >= 0.0)
+            .find_map(|s| self.extend(s))
+    }
+
+    /// Sample the shading over `rect` in device space, with `ctm` mapping
+    /// shading space to device space
+    ///
+    /// The pixmap has an alpha channel; pixels the shading does not paint
+    /// take the background color, if any, and are otherwise transparent.
+    pub fn render(&self, ctm: fz_matrix, rect: fz_rect) -> Pixmap {
+        let bbox = fz_irect_from_rect(rect);
+        let mut pix = Pixmap::with_bbox(self.colorspace, bbox, true);
+        let inverse = super::geometry::fz_invert_matrix(ctm);
+        let n = pix.n() as usize;
+        let (x0, y0, w) = (pix.x(), pix.y(), pix.w().max(0) as usize);
+        if w == 0 {
+            return pix;
+        }
+        let background: Option<Vec<f32>> = self.background.map(|bg| match &self.spot {
+            Some(spot) => spot.alternate.to_rgb(&spot.to_alternate(&bg)).to_vec(),
+            None => bg[..n - 1].to_vec(),
+        });
+        let to_byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        for (row, line) in pix.samples_mut().chunks_exact_mut(w * n).enumerate() {
+            for (col, pixel) in line.chunks_exact_mut(n).enumerate() {
+                let dx = (x0 + col as i32) as f32 + 0.5;
+                let dy = (y0 + row as i32) as f32 + 0.5;
+                let sx = dx * inverse.a + dy * inverse.c + inverse.e;
+                let sy = dx * inverse.b + dy * inverse.d + inverse.f;
+                let color = match self.parameter_at(sx, sy) {
+                    Some(t) => self.color_at(t),
+                    None => match &background {
+                        Some(bg) => bg.clone(),
+                        None => continue,
+                    },
+                };
+                for (out, v) in pixel.iter_mut().zip(&color) {
+                    *out = to_byte(*v);
+                }
+                pixel[n - 1] = 255;
+            }
+        }
+        pix
+    }
+}
+
+/// An array of numbers under `key`
+fn numbers(doc: &Document, dict: &Dict, key: &str) -> Result<Option<Vec<f32>>> {
+    match doc.resolve_key(dict, key)? {
+        Some(Object::Array(items)) => Ok(Some(
+            items
+                .iter()
+                .map(|v| doc.resolve(v).map(|v| v.as_real().unwrap_or(0.0) as f32))
+                .collect::<Result<_>>()?,
+        )),
+        _ => Ok(None),
+    }
+}
+
 /// Global shade storage
 pub static SHADES: LazyLock<HandleStore<Shade>> = LazyLock::new(HandleStore::new);
 
@@ -588,42 +908,68 @@ pub extern "C" fn fz_shade_sample(_ctx: Handle, shade: Handle, t: f32, color: *m
     if let Some(shade_arc) = SHADES.get(shade) {
         if let Ok(guard) = shade_arc.lock() {
             let color_slice = unsafe { std::slice::from_raw_parts_mut(color, 4) };
+            color_slice.copy_from_slice(&guard.stop_color(t));
+        }
+    }
+}
 
-            // Default to black
-            color_slice.fill(0.0);
-
-            if guard.color_stops.is_empty() {
-                return;
-            }
-
-            let t = t.clamp(0.0, 1.0);
+// ============================================================================
+// Shading Rendering
+// ============================================================================
 
-            // Find bounding stops
-            let mut prev_stop = &guard.color_stops[0];
-            let mut next_stop = &guard.color_stops[guard.color_stops.len() - 1];
+/// Load the shading named `name` in a page's /Shading resources
+///
+/// Only axial and radial shadings are supported. Returns a handle, or 0 if
+/// the shading is missing or cannot be loaded.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_load_shade(_ctx: Handle, page: Handle, name: *const c_char) -> Handle {
+    if name.is_null() {
+        return 0;
+    }
+    let Ok(name) = unsafe { CStr::from_ptr(name) }.to_str() else {
+        return 0;
+    };
+    match load_page_shade(page, name) {
+        Some(shade) => SHADES.insert(shade),
+        None => 0,
+    }
+}
 
-            for stop in &guard.color_stops {
-                if stop.offset <= t {
-                    prev_stop = stop;
-                }
-                if stop.offset >= t && stop.offset <= next_stop.offset {
-                    next_stop = stop;
-                    break;
-                }
-            }
+fn load_page_shade(page: Handle, name: &str) -> Option<Shade> {
+    let (doc, page_num) = {
+        let page = PAGES.get(page)?;
+        let page = page.lock().ok()?;
+        (page.doc_handle, page.page_num)
+    };
+    let parsed = open_pdf(doc)?;
+    let page = parsed.page(usize::try_from(page_num).ok()?).ok()?;
+    let Object::Dict(resources) = parsed.resolve_key(page.dict(), "Resources").ok()?? else {
+        return None;
+    };
+    let Object::Dict(shadings) = parsed.resolve_key(&resources, "Shading").ok()?? else {
+        return None;
+    };
+    Shade::load(&parsed, shadings.get(name)?).ok()
+}
 
-            // Interpolate between stops
-            if (next_stop.offset - prev_stop.offset).abs() < f32::EPSILON {
-                color_slice.copy_from_slice(&prev_stop.color);
-            } else {
-                let blend = (t - prev_stop.offset) / (next_stop.offset - prev_stop.offset);
-                for i in 0..4 {
-                    color_slice[i] =
-                        prev_stop.color[i] + blend * (next_stop.color[i] - prev_stop.color[i]);
-                }
-            }
-        }
-    }
+/// Render a shading into a new pixmap covering `rect` in device space
+///
+/// `ctm` maps shading space to device space. Returns a pixmap handle with
+/// an alpha channel, or 0 for an invalid shade.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_render_shade(
+    _ctx: Handle,
+    shade: Handle,
+    ctm: fz_matrix,
+    rect: fz_rect,
+) -> Handle {
+    let Some(shade) = SHADES.get(shade) else {
+        return 0;
+    };
+    let Ok(shade) = shade.lock() else {
+        return 0;
+    };
+    PIXMAPS.insert(shade.render(ctm, rect))
 }
 
 // ============================================================================
@@ -649,6 +995,7 @@ pub extern "C" fn fz_drop_shade(_ctx: Handle, shade: Handle) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
 
     #[test]
     fn test_linear_gradient_creation() {
@@ -695,6 +1042,82 @@ mod tests {
         fz_drop_shade(0, shade);
     }
 
+    #[test]
+    fn test_render_axial_shade() {
+        // Red to blue across x = 0..100, not extended
+        let data = build_pdf(&[
+            "<< /ShadingType 2 /ColorSpace /DeviceRGB /Coords [0 0 100 0] \
+            /Function << /FunctionType 2 /Domain [0 1] /C0 [1 0 0] /C1 [0 0 1] /N 1 >> >>",
+        ]);
+        let doc = Document::open_bytes(data).unwrap();
+        let shade = Shade::load(&doc, &Object::Ref(crate::pdf::object::ObjRef::new(1, 0))).unwrap();
+        assert_eq!(shade.shade_type, ShadeType::Linear);
+        assert_eq!(shade.color_at(0.5), [0.5, 0.0, 0.5]);
+
+        let handle = SHADES.insert(shade);
+        let identity = fz_matrix {
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 1.0,
+            e: 0.0,
+            f: 0.0,
+        };
+        let rect = fz_rect {
+            x0: -10.0,
+            y0: 0.0,
+            x1: 110.0,
+            y1: 4.0,
+        };
+        let pix = fz_render_shade(0, handle, identity, rect);
+        {
+            let pix = PIXMAPS.get(pix).unwrap();
+            let pix = pix.lock().unwrap();
+            assert_eq!((pix.x(), pix.w(), pix.n()), (-10, 120, 4));
+            let at = |x: i32| {
+                let i = ((x - pix.x()) * pix.n()) as usize;
+                pix.samples()[i..i + 4].to_vec()
+            };
+            // Pixel 49 is centered on x = 49.5; its t is 0.495
+            assert_eq!(at(49), [129, 0, 126, 255]);
+            assert_eq!(at(0), [254, 0, 1, 255]);
+            // Outside the axis the shading is not extended
+            assert_eq!(at(-5)[3], 0);
+            assert_eq!(at(105)[3], 0);
+        }
+        PIXMAPS.remove(pix);
+
+        // With /Extend the end colors continue past the axis
+        SHADES.get(handle).unwrap().lock().unwrap().extend_end = true;
+        let pix = fz_render_shade(0, handle, identity, rect);
+        {
+            let pix = PIXMAPS.get(pix).unwrap();
+            let pix = pix.lock().unwrap();
+            let i = ((105 - pix.x()) * pix.n()) as usize;
+            assert_eq!(pix.samples()[i..i + 4], [0, 0, 255, 255]);
+        }
+        PIXMAPS.remove(pix);
+        fz_drop_shade(0, handle);
+    }
+
+    #[test]
+    fn test_radial_shade_parameter() {
+        // Concentric circles, radius 0 to 10
+        let mut shade = Shade {
+            shade_type: ShadeType::Radial,
+            radial_start: ShadePoint { x: 0.0, y: 0.0 },
+            radial_r0: 0.0,
+            radial_end: ShadePoint { x: 0.0, y: 0.0 },
+            radial_r1: 10.0,
+            ..Default::default()
+        };
+        assert!((shade.parameter_at(5.0, 0.0).unwrap() - 0.5).abs() < 1e-6);
+        assert!((shade.parameter_at(0.0, 2.5).unwrap() - 0.25).abs() < 1e-6);
+        assert_eq!(shade.parameter_at(20.0, 0.0), None);
+        shade.extend_end = true;
+        assert_eq!(shade.parameter_at(20.0, 0.0), Some(1.0));
+    }
+
     #[test]
     fn test_mesh_shade() {
         let shade = fz_new_mesh_shade(0, 2, 6, 8, 8, 2); // Coons patch
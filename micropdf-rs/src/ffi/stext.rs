@@ -3,6 +3,7 @@
 //! This module provides C-compatible exports for structured text extraction operations.
 //! Used for text search, format conversion, accessibility, and OCR integration.
 
+use super::bidi::{self, BidiDirection};
 use super::{Handle, HandleStore};
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
@@ -176,6 +177,30 @@ pub struct StextLine {
     pub chars: Vec<StextChar>,
 }
 
+impl StextLine {
+    /// The line's text in reading order
+    ///
+    /// Horizontal lines with right-to-left characters are put in display
+    /// order by position and reordered with the bidi algorithm, since PDF
+    /// producers draw such text in either order.
+    pub fn text(&self) -> String {
+        let codes: Vec<u32> = self.chars.iter().map(|ch| ch.c as u32).collect();
+        let has_rtl = codes
+            .iter()
+            .any(|&c| bidi::fz_bidi_direction_from_char(c) == BidiDirection::Rtl as i32);
+        if self.wmode != 0 || !has_rtl {
+            return codes.into_iter().filter_map(char::from_u32).collect();
+        }
+        let mut order: Vec<usize> = (0..self.chars.len()).collect();
+        order.sort_by(|&a, &b| self.chars[a].origin.x.total_cmp(&self.chars[b].origin.x));
+        let display: String = order
+            .iter()
+            .filter_map(|&i| char::from_u32(codes[i]))
+            .collect();
+        bidi::fz_bidi_reorder(&display, bidi::dominant_direction(&codes) as i32)
+    }
+}
+
 /// Structured text block
 #[derive(Debug, Clone)]
 pub struct StextBlock {
@@ -675,11 +700,7 @@ pub extern "C" fn fz_stext_page_as_text(_ctx: Handle, page: Handle) -> *const c_
             for block in &p.blocks {
                 if block.block_type == StextBlockType::Text {
                     for line in &block.lines {
-                        for ch in &line.chars {
-                            if let Some(c) = char::from_u32(ch.c as u32) {
-                                text.push(c);
-                            }
-                        }
+                        text.push_str(&line.text());
                         text.push('\n');
                     }
                 }
@@ -979,6 +1000,132 @@ pub extern "C" fn fz_search_stext_page(
     0
 }
 
+/// Callback receiving one extracted character: user arg, Unicode value and
+/// glyph quad in page space
+pub type TextCharCallback = extern "C" fn(*mut std::ffi::c_void, u32, FzQuad);
+
+/// Extract the text of a document page a character at a time
+///
+/// `callback` is called for each character, in content stream order, as
+/// the page is interpreted. The characters and quads are the ones
+/// `fz_search_page` matches against, but none are kept. Returns 1 on
+/// success and 0 if the page could not be read.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_extract_page_text_callback(
+    _ctx: Handle,
+    page: Handle,
+    callback: Option<TextCharCallback>,
+    arg: *mut std::ffi::c_void,
+) -> i32 {
+    let Some(callback) = callback else {
+        return 0;
+    };
+    let mut device = crate::fitz::stext::TextCallbackDevice::new(|c: char, quad| {
+        let quad = FzQuad {
+            ul: [quad.ul.x, quad.ul.y],
+            ur: [quad.ur.x, quad.ur.y],
+            ll: [quad.ll.x, quad.ll.y],
+            lr: [quad.lr.x, quad.lr.y],
+        };
+        callback(arg, c as u32, quad);
+    });
+    match super::document::run_page_text(page, &mut device) {
+        Some(()) => 1,
+        None => 0,
+    }
+}
+
+/// Search hits: one list of glyph quads per match
+pub static SEARCH_HITS: LazyLock<HandleStore<Vec<Vec<FzQuad>>>> = LazyLock::new(HandleStore::new);
+
+/// Search a document page for `needle`, case-insensitively
+///
+/// Whitespace in the needle matches any run of whitespace on the page,
+/// including line breaks. Returns a handle to the hits, each a list of
+/// axis-aligned glyph quads in page space, or 0 if the page could not be
+/// read. Release with `fz_drop_search_hits`.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_search_page(_ctx: Handle, page: Handle, needle: *const c_char) -> Handle {
+    if needle.is_null() {
+        return 0;
+    }
+    let Ok(needle) = unsafe { CStr::from_ptr(needle) }.to_str() else {
+        return 0;
+    };
+    let Some(text) = super::document::extract_page_text(page) else {
+        return 0;
+    };
+
+    let hits = text
+        .search(needle, true)
+        .iter()
+        .map(|quads| {
+            quads
+                .iter()
+                .map(|q| {
+                    let r = q.to_rect();
+                    FzQuad {
+                        ul: [r.x0, r.y0],
+                        ur: [r.x1, r.y0],
+                        ll: [r.x0, r.y1],
+                        lr: [r.x1, r.y1],
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    SEARCH_HITS.insert(hits)
+}
+
+/// Number of matches in a search result
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_search_hit_count(_ctx: Handle, hits: Handle) -> i32 {
+    SEARCH_HITS
+        .get(hits)
+        .and_then(|h| h.lock().ok().map(|h| h.len() as i32))
+        .unwrap_or(0)
+}
+
+/// Copy up to `max_quads` quads of match `index` into `quads`
+///
+/// Returns the number of quads in the match, which may exceed `max_quads`.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_search_hit_quads(
+    _ctx: Handle,
+    hits: Handle,
+    index: i32,
+    quads: *mut FzQuad,
+    max_quads: i32,
+) -> i32 {
+    if index < 0 {
+        return 0;
+    }
+    let Some(arc) = SEARCH_HITS.get(hits) else {
+        return 0;
+    };
+    let Ok(guard) = arc.lock() else {
+        return 0;
+    };
+    let Some(hit) = guard.get(index as usize) else {
+        return 0;
+    };
+
+    if !quads.is_null() && max_quads > 0 {
+        for (i, q) in hit.iter().take(max_quads as usize).enumerate() {
+            unsafe {
+                *quads.add(i) = *q;
+            }
+        }
+    }
+    hit.len() as i32
+}
+
+/// Release search hits
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_drop_search_hits(_ctx: Handle, hits: Handle) {
+    SEARCH_HITS.remove(hits);
+}
+
 // ============================================================================
 // Selection Functions
 // ============================================================================
@@ -1470,6 +1617,7 @@ pub extern "C" fn fz_stext_char_count(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
 
     #[test]
     fn test_stext_page_create() {
@@ -1526,6 +1674,36 @@ mod tests {
         fz_drop_stext_page(ctx, page);
     }
 
+    #[test]
+    fn test_stext_text_extraction_rtl() {
+        let ctx = 0;
+        let page = fz_new_stext_page(ctx, 0.0, 0.0, 612.0, 792.0);
+        let block = fz_add_stext_block(ctx, page, 0.0, 0.0, 200.0, 50.0);
+
+        // Glyphs added left to right as they appear on the page: "Shalom 3
+        // Amud" in Hebrew, and Hebrew followed by "PDF 2!"
+        for display in [
+            "\u{5DD}\u{5D5}\u{5DC}\u{5E9} 3 \u{5D3}\u{5D5}\u{5DE}\u{5E2}",
+            "!PDF 2 \u{5D3}\u{5D2}\u{5D1}\u{5D0}",
+        ] {
+            let line = fz_add_stext_line(ctx, page, block, 0.0, 0.0, 200.0, 12.0);
+            for (i, c) in display.chars().enumerate() {
+                fz_add_stext_char(ctx, page, block, line, c as i32, (i * 8) as f32, 12.0, 12.0);
+            }
+        }
+
+        let text = fz_stext_page_as_text(ctx, page);
+        let text_str = unsafe { CStr::from_ptr(text) }.to_str().unwrap();
+        let lines: Vec<&str> = text_str.lines().collect();
+        assert_eq!(
+            lines[0],
+            "\u{5E2}\u{5DE}\u{5D5}\u{5D3} 3 \u{5E9}\u{5DC}\u{5D5}\u{5DD}"
+        );
+        assert_eq!(lines[1], "\u{5D0}\u{5D1}\u{5D2}\u{5D3} PDF 2!");
+
+        fz_drop_stext_page(ctx, page);
+    }
+
     #[test]
     fn test_stext_search() {
         let ctx = 0;
@@ -1631,4 +1809,79 @@ mod tests {
 
         fz_drop_stext_page(ctx, page);
     }
+
+    #[test]
+    fn test_search_page_two_matches() {
+        let content = "BT /F1 12 Tf 72 700 Td (find me and find me) Tj ET";
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R >>".to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ),
+        ];
+        let data = build_pdf(&objects);
+        let doc = crate::ffi::DOCUMENTS.insert(crate::ffi::document::Document::new(data));
+        let page = crate::ffi::document::PAGES.insert(crate::ffi::document::Page::new(doc, 0));
+
+        let hits = fz_search_page(0, page, c"FIND".as_ptr());
+        assert!(hits > 0);
+        assert_eq!(fz_search_hit_count(0, hits), 2);
+
+        let mut quads = [FzQuad::default(); 8];
+        assert_eq!(fz_search_hit_quads(0, hits, 0, quads.as_mut_ptr(), 8), 4);
+        let first = quads[0];
+        // Page space: the baseline at y=700 maps to 92 from the top
+        assert_eq!(first.ul[0], 72.0);
+        assert!(first.ul[1] < first.ll[1] && (first.ll[1] - 92.0).abs() < 0.01);
+        assert!(first.ur[0] > first.ul[0]);
+
+        fz_search_hit_quads(0, hits, 1, quads.as_mut_ptr(), 8);
+        assert!(quads[0].ul[0] > first.ur[0]);
+        assert_eq!(fz_search_hit_quads(0, hits, 2, quads.as_mut_ptr(), 8), 0);
+
+        fz_drop_search_hits(0, hits);
+        crate::ffi::document::PAGES.remove(page);
+        crate::ffi::DOCUMENTS.remove(doc);
+    }
+
+    #[test]
+    fn test_extract_page_text_callback() {
+        extern "C" fn collect(arg: *mut std::ffi::c_void, c: u32, quad: FzQuad) {
+            let chars = unsafe { &mut *(arg as *mut Vec<(char, FzQuad)>) };
+            chars.push((char::from_u32(c).unwrap(), quad));
+        }
+
+        let content = "BT /F1 12 Tf 72 700 Td (Hi) Tj 0 -20 Td (there) Tj ET";
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R >>".to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ),
+        ];
+        let data = build_pdf(&objects);
+        let doc = crate::ffi::DOCUMENTS.insert(crate::ffi::document::Document::new(data));
+        let page = crate::ffi::document::PAGES.insert(crate::ffi::document::Page::new(doc, 0));
+
+        let mut chars: Vec<(char, FzQuad)> = Vec::new();
+        let arg = &mut chars as *mut Vec<(char, FzQuad)> as *mut std::ffi::c_void;
+        assert_eq!(
+            fz_extract_page_text_callback(0, page, Some(collect), arg),
+            1
+        );
+        let text: String = chars.iter().map(|&(c, _)| c).collect();
+        assert_eq!(text, "Hithere");
+        // Page space: the first baseline at y=700 maps to 92 from the top
+        assert_eq!(chars[0].1.ll, [72.0, 92.0]);
+        assert_eq!(chars[2].1.ll[1], 112.0);
+
+        assert_eq!(fz_extract_page_text_callback(0, page, None, arg), 0);
+        crate::ffi::document::PAGES.remove(page);
+        crate::ffi::DOCUMENTS.remove(doc);
+    }
 }
@@ -2,6 +2,8 @@
 //! Safe Rust implementation of fz_store
 
 use super::Handle;
+use super::heap::IndexedHeap;
+use crate::pdf::parse_cache::parse_cache;
 use std::collections::HashMap;
 use std::sync::{
     LazyLock, Mutex,
@@ -113,6 +115,12 @@ pub struct Store {
     pub type_limits: HashMap<StoreType, usize>,
     /// Per-type current sizes
     pub type_sizes: HashMap<StoreType, usize>,
+    /// Eviction candidates keyed by the policy's metric
+    victims: IndexedHeap<u128>,
+    /// Handles of glyph items removed since the glyph cache last looked
+    dropped_glyphs: Vec<Handle>,
+    /// Reference point for time-based eviction keys
+    epoch: Instant,
 }
 
 impl Default for Store {
@@ -129,10 +137,117 @@ impl Default for Store {
             misses: 0,
             type_limits: HashMap::new(),
             type_sizes: HashMap::new(),
+            victims: IndexedHeap::new(),
+            dropped_glyphs: Vec::new(),
+            epoch: Instant::now(),
         }
     }
 }
 
+impl Store {
+    /// Eviction key of an item under the current policy; smallest goes first
+    fn victim_key(&self, id: u64, item: &StoreItem) -> u128 {
+        match self.policy {
+            EvictionPolicy::LRU => item.last_access.duration_since(self.epoch).as_nanos(),
+            EvictionPolicy::LFU => item.access_count as u128,
+            EvictionPolicy::FIFO => item.created.duration_since(self.epoch).as_nanos(),
+            EvictionPolicy::Random => {
+                // SplitMix64 of the id: a stable shuffle
+                let mut z = id.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                (z ^ (z >> 31)) as u128
+            }
+        }
+    }
+
+    /// Bring an item's place in the victim heap up to date
+    fn reindex(&mut self, id: u64) {
+        match self.items.get(&id) {
+            Some(item) if item.evictable && item.refs <= 1 => {
+                let key = self.victim_key(id, item);
+                self.victims.push(id, key);
+            }
+            _ => {
+                self.victims.remove(id);
+            }
+        }
+    }
+
+    /// Switch eviction policy, rekeying every candidate
+    pub fn set_policy(&mut self, policy: EvictionPolicy) {
+        self.policy = policy;
+        let victims: Vec<(u64, u128)> = self
+            .items
+            .iter()
+            .filter(|(_, item)| item.evictable && item.refs <= 1)
+            .map(|(&id, item)| (id, self.victim_key(id, item)))
+            .collect();
+        self.victims.rebuild(victims);
+    }
+
+    /// Add an item, returning its ID
+    fn insert_item(&mut self, item: StoreItem) -> u64 {
+        let id = new_store_id();
+        self.current_size += item.size;
+        *self.type_sizes.entry(item.item_type).or_insert(0) += item.size;
+        if !item.key.is_empty() {
+            self.key_map.insert(item.key.clone(), id);
+        }
+        self.items.insert(id, item);
+        self.reindex(id);
+        self.total_stored += 1;
+        id
+    }
+
+    /// Take an item out of the store and all its indexes
+    fn remove_item(&mut self, id: u64) -> Option<StoreItem> {
+        let item = self.items.remove(&id)?;
+        self.victims.remove(id);
+        if !item.key.is_empty() {
+            self.key_map.remove(&item.key);
+        }
+        self.current_size = self.current_size.saturating_sub(item.size);
+        if let Some(type_size) = self.type_sizes.get_mut(&item.item_type) {
+            *type_size = type_size.saturating_sub(item.size);
+        }
+        if item.item_type == StoreType::Glyph {
+            self.dropped_glyphs.push(item.handle);
+        }
+        Some(item)
+    }
+
+    /// Record an access to an item, returning its handle
+    fn touch(&mut self, id: u64) -> Option<Handle> {
+        let item = self.items.get_mut(&id)?;
+        item.last_access = Instant::now();
+        item.access_count += 1;
+        let handle = item.handle;
+        self.reindex(id);
+        Some(handle)
+    }
+
+    /// Take the handles of glyph items removed or evicted since the last call
+    pub fn take_dropped_glyphs(&mut self) -> Vec<Handle> {
+        std::mem::take(&mut self.dropped_glyphs)
+    }
+
+    /// Remove every item
+    fn clear_items(&mut self) {
+        let glyphs = self
+            .items
+            .values()
+            .filter(|item| item.item_type == StoreType::Glyph)
+            .map(|item| item.handle);
+        self.dropped_glyphs.extend(glyphs);
+        self.items.clear();
+        self.key_map.clear();
+        self.victims.clear();
+        self.current_size = 0;
+        self.type_sizes.clear();
+    }
+}
+
 /// Global store instance
 pub static STORE: LazyLock<Mutex<Store>> = LazyLock::new(|| Mutex::new(Store::default()));
 
@@ -149,13 +264,14 @@ fn new_store_id() -> u64 {
 // ============================================================================
 
 /// Create a new store with specified maximum size
+///
+/// The PDF parse cache follows the same budget.
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_new_store(_ctx: Handle, max_size: usize) -> i32 {
+    parse_cache().set_budget(max_size);
     if let Ok(mut store) = STORE.lock() {
         store.max_size = max_size;
-        store.items.clear();
-        store.key_map.clear();
-        store.current_size = 0;
+        store.clear_items();
         store.total_stored = 0;
         store.total_evicted = 0;
         store.hits = 0;
@@ -168,6 +284,7 @@ pub extern "C" fn fz_new_store(_ctx: Handle, max_size: usize) -> i32 {
 /// Set store maximum size
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_store_set_max_size(_ctx: Handle, max_size: usize) {
+    parse_cache().set_budget(max_size);
     if let Ok(mut store) = STORE.lock() {
         store.max_size = max_size;
         // Evict if over new limit
@@ -204,7 +321,7 @@ pub extern "C" fn fz_store_set_policy(_ctx: Handle, policy: i32) {
     };
 
     if let Ok(mut store) = STORE.lock() {
-        store.policy = p;
+        store.set_policy(p);
     }
 }
 
@@ -284,35 +401,17 @@ pub extern "C" fn fz_store_item(
             }
         }
 
-        // Generate item ID
-        let id = new_store_id();
-
-        // Create item
-        let item = StoreItem {
+        return store.insert_item(StoreItem {
             item_type: t,
             handle,
             size,
             last_access: Instant::now(),
             access_count: 0,
             created: Instant::now(),
-            key: key_data.clone(),
+            key: key_data,
             evictable: true,
             refs: 1,
-        };
-
-        // Update size tracking
-        store.current_size += size;
-        *store.type_sizes.entry(t).or_insert(0) += size;
-
-        // Store item
-        store.items.insert(id, item);
-        if !key_data.is_empty() {
-            store.key_map.insert(key_data, id);
-        }
-
-        store.total_stored += 1;
-
-        return id;
+        });
     }
 
     0
@@ -332,16 +431,7 @@ pub extern "C" fn fz_store_find(_ctx: Handle, key: *const u8, key_len: usize) ->
 
     if let Ok(mut store) = STORE.lock() {
         if let Some(&id) = store.key_map.get(key_data) {
-            let result = if let Some(item) = store.items.get_mut(&id) {
-                // Update access tracking
-                item.last_access = Instant::now();
-                item.access_count += 1;
-                Some(item.handle)
-            } else {
-                None
-            };
-
-            if let Some(handle) = result {
+            if let Some(handle) = store.touch(id) {
                 store.hits += 1;
                 return handle;
             }
@@ -356,15 +446,7 @@ pub extern "C" fn fz_store_find(_ctx: Handle, key: *const u8, key_len: usize) ->
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_store_find_by_id(_ctx: Handle, id: u64) -> Handle {
     if let Ok(mut store) = STORE.lock() {
-        let result = if let Some(item) = store.items.get_mut(&id) {
-            item.last_access = Instant::now();
-            item.access_count += 1;
-            Some(item.handle)
-        } else {
-            None
-        };
-
-        if let Some(handle) = result {
+        if let Some(handle) = store.touch(id) {
             store.hits += 1;
             return handle;
         }
@@ -377,18 +459,7 @@ pub extern "C" fn fz_store_find_by_id(_ctx: Handle, id: u64) -> Handle {
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_store_remove(_ctx: Handle, id: u64) -> Handle {
     if let Ok(mut store) = STORE.lock() {
-        if let Some(item) = store.items.remove(&id) {
-            // Remove from key map
-            if !item.key.is_empty() {
-                store.key_map.remove(&item.key);
-            }
-
-            // Update sizes
-            store.current_size = store.current_size.saturating_sub(item.size);
-            if let Some(type_size) = store.type_sizes.get_mut(&item.item_type) {
-                *type_size = type_size.saturating_sub(item.size);
-            }
-
+        if let Some(item) = store.remove_item(id) {
             return item.handle;
         }
     }
@@ -408,12 +479,8 @@ pub extern "C" fn fz_store_remove_by_key(_ctx: Handle, key: *const u8, key_len:
     let key_data = unsafe { std::slice::from_raw_parts(key, key_len) };
 
     if let Ok(mut store) = STORE.lock() {
-        if let Some(id) = store.key_map.remove(key_data) {
-            if let Some(item) = store.items.remove(&id) {
-                store.current_size = store.current_size.saturating_sub(item.size);
-                if let Some(type_size) = store.type_sizes.get_mut(&item.item_type) {
-                    *type_size = type_size.saturating_sub(item.size);
-                }
+        if let Some(&id) = store.key_map.get(key_data) {
+            if let Some(item) = store.remove_item(id) {
                 return item.handle;
             }
         }
@@ -427,6 +494,7 @@ pub extern "C" fn fz_store_keep(_ctx: Handle, id: u64) -> u64 {
     if let Ok(mut store) = STORE.lock() {
         if let Some(item) = store.items.get_mut(&id) {
             item.refs = item.refs.saturating_add(1);
+            store.reindex(id);
             return id;
         }
     }
@@ -447,15 +515,9 @@ pub extern "C" fn fz_store_drop(_ctx: Handle, id: u64) {
         };
 
         if should_remove {
-            if let Some(item) = store.items.remove(&id) {
-                if !item.key.is_empty() {
-                    store.key_map.remove(&item.key);
-                }
-                store.current_size = store.current_size.saturating_sub(item.size);
-                if let Some(type_size) = store.type_sizes.get_mut(&item.item_type) {
-                    *type_size = type_size.saturating_sub(item.size);
-                }
-            }
+            store.remove_item(id);
+        } else {
+            store.reindex(id);
         }
     }
 }
@@ -470,6 +532,7 @@ pub extern "C" fn fz_store_set_evictable(_ctx: Handle, id: u64, evictable: i32)
     if let Ok(mut store) = STORE.lock() {
         if let Some(item) = store.items.get_mut(&id) {
             item.evictable = evictable != 0;
+            store.reindex(id);
         }
     }
 }
@@ -636,14 +699,7 @@ fn evict_to_size(store: &mut Store, target_size: usize) {
             break;
         }
 
-        if let Some(item) = store.items.remove(&victim_id) {
-            if !item.key.is_empty() {
-                store.key_map.remove(&item.key);
-            }
-            store.current_size = store.current_size.saturating_sub(item.size);
-            if let Some(type_size) = store.type_sizes.get_mut(&item.item_type) {
-                *type_size = type_size.saturating_sub(item.size);
-            }
+        if store.remove_item(victim_id).is_some() {
             store.total_evicted += 1;
         }
     }
@@ -665,16 +721,7 @@ fn evict_type_to_size(store: &mut Store, item_type: StoreType, target_size: usiz
         .collect();
 
     // Sort by eviction policy
-    victims.sort_by(|&a, &b| {
-        let item_a = store.items.get(&a).unwrap();
-        let item_b = store.items.get(&b).unwrap();
-        match store.policy {
-            EvictionPolicy::LRU => item_a.last_access.cmp(&item_b.last_access),
-            EvictionPolicy::LFU => item_a.access_count.cmp(&item_b.access_count),
-            EvictionPolicy::FIFO => item_a.created.cmp(&item_b.created),
-            EvictionPolicy::Random => std::cmp::Ordering::Equal,
-        }
-    });
+    victims.sort_by_key(|&id| (store.victims.key(id), id));
 
     // Evict until under target
     let mut evicted_size = 0;
@@ -685,53 +732,19 @@ fn evict_type_to_size(store: &mut Store, item_type: StoreType, target_size: usiz
             break;
         }
 
-        if let Some(item) = store.items.remove(&victim_id) {
-            if !item.key.is_empty() {
-                store.key_map.remove(&item.key);
-            }
+        if let Some(item) = store.remove_item(victim_id) {
             evicted_size += item.size;
-            store.current_size = store.current_size.saturating_sub(item.size);
-            if let Some(type_size) = store.type_sizes.get_mut(&item.item_type) {
-                *type_size = type_size.saturating_sub(item.size);
-            }
             store.total_evicted += 1;
         }
     }
 }
 
 /// Internal: select victim for eviction based on policy
+///
+/// Candidates are kept in a heap ordered by the policy's metric, so this
+/// is the heap's minimum rather than a scan of every item.
 fn select_victim(store: &Store) -> u64 {
-    let evictable: Vec<_> = store
-        .items
-        .iter()
-        .filter(|(_, item)| item.evictable && item.refs <= 1)
-        .collect();
-
-    if evictable.is_empty() {
-        return 0;
-    }
-
-    match store.policy {
-        EvictionPolicy::LRU => evictable
-            .iter()
-            .min_by_key(|(_, item)| item.last_access)
-            .map(|(id, _)| **id)
-            .unwrap_or(0),
-        EvictionPolicy::LFU => evictable
-            .iter()
-            .min_by_key(|(_, item)| item.access_count)
-            .map(|(id, _)| **id)
-            .unwrap_or(0),
-        EvictionPolicy::FIFO => evictable
-            .iter()
-            .min_by_key(|(_, item)| item.created)
-            .map(|(id, _)| **id)
-            .unwrap_or(0),
-        EvictionPolicy::Random => {
-            // Use simple deterministic selection for reproducibility
-            evictable.first().map(|(id, _)| **id).unwrap_or(0)
-        }
-    }
+    store.victims.peek().unwrap_or(0)
 }
 
 /// Manually trigger eviction
@@ -788,14 +801,7 @@ pub extern "C" fn fz_store_evict_old(_ctx: Handle, max_age_ms: u64) -> usize {
         let count = victims.len();
 
         for id in victims {
-            if let Some(item) = store.items.remove(&id) {
-                if !item.key.is_empty() {
-                    store.key_map.remove(&item.key);
-                }
-                store.current_size = store.current_size.saturating_sub(item.size);
-                if let Some(type_size) = store.type_sizes.get_mut(&item.item_type) {
-                    *type_size = type_size.saturating_sub(item.size);
-                }
+            if store.remove_item(id).is_some() {
                 store.total_evicted += 1;
             }
         }
@@ -810,10 +816,7 @@ pub extern "C" fn fz_store_evict_old(_ctx: Handle, max_age_ms: u64) -> usize {
 pub extern "C" fn fz_store_clear(_ctx: Handle) {
     if let Ok(mut store) = STORE.lock() {
         let count = store.items.len() as u64;
-        store.items.clear();
-        store.key_map.clear();
-        store.current_size = 0;
-        store.type_sizes.clear();
+        store.clear_items();
         store.total_evicted += count;
     }
 }
@@ -1076,4 +1079,69 @@ mod tests {
         }
         fz_store_remove(0, id1);
     }
+
+    #[test]
+    fn test_heap_eviction_touches_fewer_items() {
+        let mut store = Store::default();
+        store.set_policy(EvictionPolicy::LFU);
+
+        // Item k is accessed k % 4 times
+        let ids: Vec<u64> = (0..10_000u64)
+            .map(|k| {
+                let id = store.insert_item(StoreItem {
+                    handle: k,
+                    size: 10,
+                    ..Default::default()
+                });
+                for _ in 0..k % 4 {
+                    store.touch(id);
+                }
+                id
+            })
+            .collect();
+        assert_eq!(store.current_size, 100_000);
+
+        // Halving the store drops exactly the items accessed fewer than twice
+        let visits = store.victims.visits();
+        evict_to_size(&mut store, 50_000);
+        let heap_visits = store.victims.visits() - visits;
+        assert_eq!(store.items.len(), 5_000);
+        assert!(store.items.values().all(|item| item.access_count >= 2));
+
+        // A scan per eviction would look at every remaining candidate
+        let linear_visits: u64 = (5_001..=10_000).sum();
+        assert!(
+            heap_visits * 100 < linear_visits,
+            "heap visited {heap_visits} items, a scan would visit {linear_visits}"
+        );
+
+        // Switching policy rekeys the survivors: FIFO keeps the newest
+        store.set_policy(EvictionPolicy::FIFO);
+        evict_to_size(&mut store, 25_000);
+        let mut kept: Vec<u64> = store.items.keys().copied().collect();
+        kept.sort_unstable();
+        let survivors: Vec<u64> = ids
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| k % 4 >= 2)
+            .map(|(_, &id)| id)
+            .collect();
+        assert_eq!(kept, survivors[2_500..]);
+        assert_eq!(store.victims.len(), 2_500);
+    }
+
+    #[test]
+    fn test_pinned_items_leave_victim_heap() {
+        let mut store = Store::default();
+        let a = store.insert_item(StoreItem::default());
+        let b = store.insert_item(StoreItem::default());
+        assert_eq!(select_victim(&store), a);
+
+        store.items.get_mut(&a).unwrap().evictable = false;
+        store.reindex(a);
+        assert_eq!(select_victim(&store), b);
+
+        store.remove_item(b);
+        assert_eq!(select_victim(&store), 0);
+    }
 }
@@ -8,8 +8,25 @@ use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use std::sync::LazyLock;
 
+use crate::ffi::device::DEVICES;
+use crate::ffi::font::FONTS;
 use crate::ffi::stext::Rect;
 use crate::ffi::{Handle, HandleStore};
+use crate::fitz::colorspace::Colorspace;
+use crate::fitz::device::Device;
+use crate::fitz::font::Font;
+use crate::fitz::geometry::Matrix;
+use crate::fitz::text::{BidiDirection, Text, TextLanguage};
+use std::sync::Arc;
+
+/// Line height as a multiple of the font size
+const LINE_HEIGHT: f32 = 1.2;
+
+/// Space after each paragraph as a multiple of its font size
+const PARAGRAPH_SPACING: f32 = 0.5;
+
+/// Advance, in ems, of characters the font has no metrics for
+const FALLBACK_ADVANCE: f32 = 0.5;
 
 /// Global store for stories
 pub static STORIES: LazyLock<HandleStore<Story>> = LazyLock::new(HandleStore::new);
@@ -156,12 +173,62 @@ impl Default for StoryElementPosition {
     }
 }
 
+/// A paragraph of flowed text in one font and size
+#[derive(Debug, Clone)]
+pub struct StoryParagraph {
+    pub text: String,
+    pub font: Arc<Font>,
+    /// Font size in points
+    pub size: f32,
+    /// Size relative to the story's em, for paragraphs from the HTML
+    em_scale: Option<f32>,
+}
+
+impl StoryParagraph {
+    /// Width of `text` set in this paragraph's font and size
+    pub fn measure(&self, text: &str) -> f32 {
+        text.chars().map(|c| self.advance(c)).sum()
+    }
+
+    fn advance(&self, c: char) -> f32 {
+        match self.font.glyph_id(c as u32) {
+            Some(gid) => self.font.glyph_advance(gid) * self.size,
+            None => FALLBACK_ADVANCE * self.size,
+        }
+    }
+
+    /// Distance from the top of a line to its baseline
+    fn ascent(&self) -> f32 {
+        let ascender = self.font.ascender();
+        if ascender > 0.0 {
+            ascender * self.size
+        } else {
+            self.size * 0.8
+        }
+    }
+}
+
+/// A line of text placed by [`Story::place`]
+#[derive(Debug, Clone)]
+pub struct StoryLine {
+    /// Index of the paragraph the line belongs to
+    pub paragraph: usize,
+    pub text: String,
+    /// Left edge of the line
+    pub x: f32,
+    /// Baseline position, with y growing downwards
+    pub baseline: f32,
+    pub width: f32,
+}
+
 /// Placed content region
 #[derive(Debug, Clone)]
 pub struct PlacedRegion {
     pub rect: Rect,
-    pub elements: Vec<usize>, // Indices into story elements
+    pub elements: Vec<usize>, // Indices into story paragraphs
     pub filled: Rect,
+    /// Lines placed in the region, top to bottom
+    pub lines: Vec<StoryLine>,
 }
 
 /// HTML Story for layout and rendering
@@ -180,8 +247,12 @@ pub struct Story {
     pub state: StoryState,
     /// Parsing/layout warnings
     pub warnings: Vec<String>,
-    /// Current layout position (element index)
+    /// Flowed paragraphs, from the HTML followed by any added directly
+    pub paragraphs: Vec<StoryParagraph>,
+    /// Current layout position (paragraph index)
     pub layout_position: usize,
+    /// Byte offset of the next unplaced text in the current paragraph
+    pub layout_offset: usize,
     /// Placed regions
     pub placed_regions: Vec<PlacedRegion>,
     /// Current rectangle number
@@ -201,7 +272,9 @@ impl Story {
             document: None,
             state: StoryState::Created,
             warnings: Vec::new(),
+            paragraphs: Vec::new(),
             layout_position: 0,
+            layout_offset: 0,
             placed_regions: Vec::new(),
             rectangle_num: 0,
             cached_strings: HashMap::new(),
@@ -209,10 +282,64 @@ impl Story {
 
         // Parse HTML into DOM
         story.parse_html();
+        story.build_paragraphs();
 
         story
     }
 
+    /// Flow the text of each element as a paragraph, with headings set
+    /// larger at the CSS default sizes
+    fn build_paragraphs(&mut self) {
+        let Some(doc) = &self.document else {
+            return;
+        };
+        let regular = Arc::new(Font::new("Helvetica"));
+        let bold = Arc::new(Font::new("Helvetica-Bold"));
+        for elem in &doc.children {
+            let Some(text) = &elem.text else {
+                continue;
+            };
+            let (scale, font) = match elem.heading_level {
+                1 => (2.0, &bold),
+                2 => (1.5, &bold),
+                3 => (1.17, &bold),
+                4 => (1.0, &bold),
+                5 => (0.83, &bold),
+                6 => (0.67, &bold),
+                _ => (1.0, &regular),
+            };
+            self.paragraphs.push(StoryParagraph {
+                text: text.clone(),
+                font: font.clone(),
+                size: self.em * scale,
+                em_scale: Some(scale),
+            });
+        }
+    }
+
+    /// Append a paragraph of text set in `font` at `size` points
+    pub fn add_paragraph(&mut self, text: &str, font: Arc<Font>, size: f32) {
+        self.paragraphs.push(StoryParagraph {
+            text: text.to_string(),
+            font,
+            size,
+            em_scale: None,
+        });
+        if self.state == StoryState::Complete {
+            self.state = StoryState::Drawn;
+        }
+    }
+
+    /// Change the em size, rescaling paragraphs that came from the HTML
+    pub fn set_em(&mut self, em: f32) {
+        self.em = em;
+        for para in &mut self.paragraphs {
+            if let Some(scale) = para.em_scale {
+                para.size = em * scale;
+            }
+        }
+    }
+
     /// Parse HTML into DOM structure
     fn parse_html(&mut self) {
         // Simple HTML parser
@@ -317,98 +444,127 @@ impl Story {
     }
 
     /// Place story content into a rectangle
+    ///
+    /// Lines are broken greedily at whitespace and stacked downwards from
+    /// the top of the rectangle. Content that does not fit is left for the
+    /// next call, which continues mid-paragraph if need be. A word wider
+    /// than the rectangle gets a line of its own unless
+    /// [`PlaceStoryFlag::NoOverflow`] is set, in which case placement stops
+    /// with [`PlaceStoryReturn::OverflowWidth`].
     pub fn place(&mut self, where_rect: Rect, flags: i32) -> (PlaceStoryReturn, Rect) {
-        if self.state == StoryState::Complete {
-            return (PlaceStoryReturn::AllFitted, Rect::default());
-        }
-
-        self.state = StoryState::Placing;
-        self.rectangle_num += 1;
-
         let mut filled = Rect {
             x0: where_rect.x0,
             y0: where_rect.y0,
             x1: where_rect.x0,
             y1: where_rect.y0,
         };
-
-        // Get elements to place
-        let elements = if let Some(ref doc) = self.document {
-            &doc.children
-        } else {
-            return (PlaceStoryReturn::AllFitted, filled);
-        };
-
-        if self.layout_position >= elements.len() {
+        if self.layout_position >= self.paragraphs.len() {
             self.state = StoryState::Complete;
             return (PlaceStoryReturn::AllFitted, filled);
         }
 
-        // Simple layout: stack elements vertically
-        let mut y = where_rect.y0;
-        let line_height = self.em * 1.2;
-        let margin = self.em * 0.5;
+        self.state = StoryState::Placing;
+        self.rectangle_num += 1;
 
-        let mut placed_count = 0;
+        let width = where_rect.x1 - where_rect.x0;
         let check_overflow = flags & (PlaceStoryFlag::NoOverflow as i32) != 0;
-
-        while self.layout_position < elements.len() {
-            let elem = &elements[self.layout_position];
-
-            // Calculate element height based on content
-            let elem_height = if elem.is_heading() {
-                line_height * (1.5 + (6 - elem.heading_level) as f32 * 0.2)
-            } else {
-                line_height
-            };
-
-            // Check if element fits
-            if y + elem_height + margin > where_rect.y1 {
-                // No more room
-                break;
-            }
-
-            // Check width overflow if flag set
-            if check_overflow {
-                if let Some(ref text) = elem.text {
-                    let estimated_width = text.len() as f32 * self.em * 0.5;
-                    if estimated_width > (where_rect.x1 - where_rect.x0) {
-                        self.state = StoryState::Placed;
-                        return (PlaceStoryReturn::OverflowWidth, filled);
-                    }
+        let first = self.layout_position;
+        let mut lines = Vec::new();
+        let mut y = where_rect.y0;
+        let mut result = None;
+
+        'paragraphs: while self.layout_position < self.paragraphs.len() {
+            let para = &self.paragraphs[self.layout_position];
+            let line_height = para.size * LINE_HEIGHT;
+            loop {
+                let rest = &para.text[self.layout_offset..];
+                let rest_start = rest.len() - rest.trim_start().len();
+                self.layout_offset += rest_start;
+                let rest = &rest[rest_start..];
+                if rest.is_empty() {
+                    break;
+                }
+                if y + line_height > where_rect.y1 {
+                    break 'paragraphs;
+                }
+                let (len, line_width) = break_line(para, rest, width);
+                if check_overflow && line_width > width {
+                    result = Some(PlaceStoryReturn::OverflowWidth);
+                    break 'paragraphs;
                 }
+                lines.push(StoryLine {
+                    paragraph: self.layout_position,
+                    text: rest[..len].to_string(),
+                    x: where_rect.x0,
+                    baseline: y + para.ascent(),
+                    width: line_width,
+                });
+                self.layout_offset += len;
+                y += line_height;
+                filled.x1 = filled.x1.max(where_rect.x0 + line_width);
+                filled.y1 = y;
             }
-
-            // Place element
-            y += elem_height + margin;
-            placed_count += 1;
+            y += para.size * PARAGRAPH_SPACING;
             self.layout_position += 1;
-
-            // Update filled rect
-            filled.x1 = where_rect.x1;
-            filled.y1 = y;
+            self.layout_offset = 0;
         }
 
-        // Create placed region
-        let region = PlacedRegion {
+        let last = if self.layout_offset > 0 {
+            self.layout_position + 1
+        } else {
+            self.layout_position
+        };
+        self.placed_regions.push(PlacedRegion {
             rect: where_rect,
-            elements: (self.layout_position - placed_count..self.layout_position).collect(),
+            elements: (first..last.min(self.paragraphs.len())).collect(),
             filled,
-        };
-        self.placed_regions.push(region);
-
-        self.state = StoryState::Placed;
+            lines,
+        });
 
-        if self.layout_position >= elements.len() {
+        if let Some(result) = result {
+            self.state = StoryState::Placed;
+            (result, filled)
+        } else if self.layout_position >= self.paragraphs.len() {
             self.state = StoryState::Complete;
             (PlaceStoryReturn::AllFitted, filled)
         } else {
+            self.state = StoryState::Placed;
             (PlaceStoryReturn::MoreToFit, filled)
         }
     }
 
-    /// Draw the placed story to a device
-    pub fn draw(&mut self, _device: Handle, _ctm: [f32; 6]) {
+    /// Draw the most recently placed lines to a device
+    pub fn draw(&mut self, device: &mut dyn Device, ctm: &Matrix) {
+        if let Some(region) = self.placed_regions.last() {
+            let mut text = Text::new();
+            for line in &region.lines {
+                let para = &self.paragraphs[line.paragraph];
+                let mut x = line.x;
+                for c in line.text.chars() {
+                    // Glyph space is y-up, the layout is y-down
+                    let trm = Matrix::new(para.size, 0.0, 0.0, -para.size, x, line.baseline);
+                    let gid = para.font.glyph_id(c as u32).map_or(c as i32, i32::from);
+                    let advance = para.advance(c);
+                    text.show_glyph_with_advance(
+                        para.font.clone(),
+                        trm,
+                        advance,
+                        gid,
+                        c as i32,
+                        c as i32,
+                        false,
+                        0,
+                        BidiDirection::Ltr,
+                        TextLanguage::Unset,
+                    );
+                    x += advance;
+                }
+            }
+            if !region.lines.is_empty() {
+                device.fill_text(&text, ctm, &Colorspace::device_gray(), &[0.0], 1.0);
+            }
+        }
+
         // Mark as drawn so next place continues from here
         if self.state == StoryState::Placed {
             self.state = StoryState::Drawn;
@@ -418,6 +574,7 @@ impl Story {
     /// Reset layout position to start
     pub fn reset(&mut self) {
         self.layout_position = 0;
+        self.layout_offset = 0;
         self.placed_regions.clear();
         self.rectangle_num = 0;
         self.state = StoryState::Created;
@@ -509,6 +666,38 @@ impl Story {
     }
 }
 
+/// Length in bytes and width of the longest prefix of `text` that fits in
+/// `width`, ending at a word boundary
+///
+/// At least one word is always taken, so the width may exceed `width`.
+fn break_line(para: &StoryParagraph, text: &str, width: f32) -> (usize, f32) {
+    let mut end = 0;
+    let mut line_width = 0.0;
+    let mut pos = 0;
+    while pos < text.len() {
+        let word_start = pos + (text[pos..].len() - text[pos..].trim_start().len());
+        let word_end = text[word_start..]
+            .find(char::is_whitespace)
+            .map_or(text.len(), |i| word_start + i);
+        if word_start == word_end {
+            break;
+        }
+        let gap = if end == 0 {
+            0.0
+        } else {
+            para.measure(&text[end..word_start])
+        };
+        let word = para.measure(&text[word_start..word_end]);
+        if end > 0 && line_width + gap + word > width {
+            break;
+        }
+        line_width += gap + word;
+        end = word_end;
+        pos = word_end;
+    }
+    (end, line_width)
+}
+
 // ============================================================================
 // FFI Functions
 // ============================================================================
@@ -546,6 +735,40 @@ pub extern "C" fn fz_new_story(
     STORIES.insert(story)
 }
 
+/// Append a paragraph of flowed text
+///
+/// A font handle of 0 uses Helvetica. Returns 1 on success, 0 on error.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_story_add_paragraph(
+    _ctx: Handle,
+    story: Handle,
+    text: *const c_char,
+    font: Handle,
+    size: f32,
+) -> i32 {
+    if text.is_null() || size <= 0.0 {
+        return 0;
+    }
+    let Ok(text) = unsafe { CStr::from_ptr(text) }.to_str() else {
+        return 0;
+    };
+    let font = if font == 0 {
+        Arc::new(Font::new("Helvetica"))
+    } else {
+        match FONTS.get(font) {
+            Some(f) => Arc::new(f.lock().unwrap().clone()),
+            None => return 0,
+        }
+    };
+    match STORIES.get(story) {
+        Some(story_arc) => {
+            story_arc.lock().unwrap().add_paragraph(text, font, size);
+            1
+        }
+        None => 0,
+    }
+}
+
 /// Get story parsing warnings
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_story_warnings(_ctx: Handle, story: Handle) -> *const c_char {
@@ -624,11 +847,17 @@ pub extern "C" fn fz_draw_story(
     ctm_e: f32,
     ctm_f: f32,
 ) {
-    let ctm = [ctm_a, ctm_b, ctm_c, ctm_d, ctm_e, ctm_f];
+    let ctm = Matrix::new(ctm_a, ctm_b, ctm_c, ctm_d, ctm_e, ctm_f);
 
     if let Some(story_arc) = STORIES.get(story) {
         let mut story_guard = story_arc.lock().unwrap();
-        story_guard.draw(dev, ctm);
+        match DEVICES.get(dev) {
+            Some(device) => {
+                let mut device = device.lock().unwrap();
+                story_guard.draw(&mut **device, &ctm);
+            }
+            None => story_guard.draw(&mut crate::fitz::device::NullDevice, &ctm),
+        }
     }
 }
 
@@ -751,7 +980,7 @@ pub extern "C" fn fz_story_em(story: Handle) -> f32 {
 pub extern "C" fn fz_story_set_em(story: Handle, em: f32) {
     if let Some(story_arc) = STORIES.get(story) {
         let mut story_guard = story_arc.lock().unwrap();
-        story_guard.em = em;
+        story_guard.set_em(em);
     }
 }
 
@@ -1013,4 +1242,52 @@ mod tests {
         fz_drop_story(ctx, story);
         crate::ffi::buffer::fz_drop_buffer(ctx, buf);
     }
+
+    #[test]
+    fn test_story_wraps_into_narrow_box() {
+        let mut story = Story::new("", "", 12.0, None);
+        story.add_paragraph(
+            "The quick brown fox jumps over the lazy dog",
+            Arc::new(Font::new("Helvetica")),
+            10.0,
+        );
+
+        // Words are 5pt a character, so "The quick" (45pt) fills a line
+        let box1 = Rect {
+            x0: 10.0,
+            y0: 0.0,
+            x1: 60.0,
+            y1: 36.0,
+        };
+        let (result, filled) = story.place(box1, 0);
+        assert_eq!(result, PlaceStoryReturn::MoreToFit);
+        let lines: Vec<&str> = story.placed_regions[0]
+            .lines
+            .iter()
+            .map(|l| l.text.as_str())
+            .collect();
+        assert_eq!(lines, ["The quick", "brown fox", "jumps over"]);
+        assert_eq!(filled.y1, 36.0);
+        assert!(filled.x1 <= box1.x1);
+
+        let mut bbox = crate::fitz::device::BBoxDevice::new();
+        story.draw(&mut bbox, &Matrix::IDENTITY);
+        assert!(!bbox.bbox().is_empty());
+
+        // The remaining words continue in the next box
+        let (result, _) = story.place(box1, 0);
+        assert_eq!(result, PlaceStoryReturn::AllFitted);
+        let lines: Vec<&str> = story.placed_regions[1]
+            .lines
+            .iter()
+            .map(|l| l.text.as_str())
+            .collect();
+        assert_eq!(lines, ["the lazy", "dog"]);
+        assert_eq!(story.state, StoryState::Complete);
+
+        // Without overflow allowed, a word wider than the box stops placement
+        let mut story = Story::new("<p>Incomprehensibilities</p>", "", 12.0, None);
+        let (result, _) = story.place(box1, PlaceStoryFlag::NoOverflow as i32);
+        assert_eq!(result, PlaceStoryReturn::OverflowWidth);
+    }
 }
@@ -2,88 +2,96 @@
 //! Safe Rust implementation using handle-based resource management
 
 use super::{BUFFERS, Handle, STREAMS};
+use crate::fitz::error::Result;
+use bytes::Bytes;
 use std::ffi::c_char;
 
-/// Internal stream state
+/// Stream handle state: a buffered [`fitz::stream::Stream`] over a file
+/// or memory
+///
+/// [`fitz::stream::Stream`]: crate::fitz::stream::Stream
 pub struct Stream {
-    pub(crate) data: Vec<u8>,
-    position: usize,
-    eof: bool,
+    inner: crate::fitz::stream::Stream,
 }
 
 impl Stream {
     pub fn new() -> Self {
-        Self {
-            data: Vec::new(),
-            position: 0,
-            eof: true,
-        }
+        Self::from_memory(Vec::new())
     }
 
     pub fn from_memory(data: Vec<u8>) -> Self {
-        let eof = data.is_empty();
         Self {
-            data,
-            position: 0,
-            eof,
+            inner: crate::fitz::stream::Stream::open_bytes(Bytes::from(data)),
         }
     }
 
-    pub fn read(&mut self, buf: &mut [u8]) -> usize {
-        if self.position >= self.data.len() {
-            self.eof = true;
-            return 0;
-        }
+    /// Open a file, reading it on demand rather than all at once
+    pub fn open_file(path: &str) -> Result<Self> {
+        crate::fitz::stream::Stream::open_file(path).map(|inner| Self { inner })
+    }
 
-        let available = self.data.len() - self.position;
-        let to_read = buf.len().min(available);
-        buf[..to_read].copy_from_slice(&self.data[self.position..self.position + to_read]);
-        self.position += to_read;
+    /// Total length, if the source knows it
+    pub fn len(&self) -> Option<u64> {
+        self.inner.len()
+    }
 
-        if self.position >= self.data.len() {
-            self.eof = true;
-        }
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
 
-        to_read
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        self.inner.read(buf).unwrap_or(0)
     }
 
     pub fn read_byte(&mut self) -> Option<u8> {
-        if self.position >= self.data.len() {
-            self.eof = true;
-            return None;
-        }
-        let byte = self.data[self.position];
-        self.position += 1;
-        if self.position >= self.data.len() {
-            self.eof = true;
-        }
-        Some(byte)
+        self.inner.read_byte().ok().flatten()
     }
 
-    pub fn peek_byte(&self) -> Option<u8> {
-        if self.position >= self.data.len() {
-            return None;
-        }
-        Some(self.data[self.position])
+    pub fn peek_byte(&mut self) -> Option<u8> {
+        self.inner.peek_byte().ok().flatten()
+    }
+
+    pub fn unread_byte(&mut self) {
+        let _ = self.inner.unread_byte();
     }
 
+    /// Seek with C `whence` semantics; an invalid seek leaves the position
+    /// unchanged
     pub fn seek(&mut self, offset: i64, whence: i32) {
-        let new_pos = match whence {
-            0 => offset as usize,                            // SEEK_SET
-            1 => (self.position as i64 + offset) as usize,   // SEEK_CUR
-            2 => (self.data.len() as i64 + offset) as usize, // SEEK_END
-            _ => self.position,
-        };
-        self.position = new_pos.min(self.data.len());
-        self.eof = self.position >= self.data.len();
+        let _ = self.inner.seek(offset, whence);
     }
 
     pub fn tell(&self) -> i64 {
-        self.position as i64
+        self.inner.tell()
+    }
+
+    /// True once no byte is left to read
+    pub fn is_eof(&mut self) -> bool {
+        self.peek_byte().is_none()
+    }
+
+    /// The whole stream regardless of position, mapped or shared rather
+    /// than read where the source allows
+    pub fn source_bytes(&mut self) -> Bytes {
+        if let Some(bytes) = self.inner.source_bytes() {
+            return bytes;
+        }
+        let position = self.tell();
+        self.seek(0, 0);
+        let bytes = Bytes::from(self.read_all());
+        self.seek(position, 0);
+        bytes
     }
 
-    pub fn is_eof(&self) -> bool {
-        self.eof
+    /// Everything from the current position to the end
+    pub fn read_all(&mut self) -> Vec<u8> {
+        let capacity = self
+            .len()
+            .map_or(0, |len| len.saturating_sub(self.tell() as u64));
+        self.inner
+            .read_all(capacity as usize)
+            .map(|buf| buf.to_vec())
+            .unwrap_or_default()
     }
 }
 
@@ -122,8 +130,8 @@ pub extern "C" fn fz_open_file(_ctx: Handle, filename: *const c_char) -> Handle
         Err(_) => return 0,
     };
 
-    match std::fs::read(path) {
-        Ok(data) => STREAMS.insert(Stream::from_memory(data)),
+    match Stream::open_file(path) {
+        Ok(stream) => STREAMS.insert(stream),
         Err(_) => 0,
     }
 }
@@ -191,7 +199,7 @@ pub extern "C" fn fz_read_byte(_ctx: Handle, stm: Handle) -> i32 {
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_peek_byte(_ctx: Handle, stm: Handle) -> i32 {
     if let Some(stream) = STREAMS.get(stm) {
-        if let Ok(guard) = stream.lock() {
+        if let Ok(mut guard) = stream.lock() {
             if let Some(byte) = guard.peek_byte() {
                 return byte as i32;
             }
@@ -204,7 +212,7 @@ pub extern "C" fn fz_peek_byte(_ctx: Handle, stm: Handle) -> i32 {
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_is_eof(_ctx: Handle, stm: Handle) -> i32 {
     if let Some(stream) = STREAMS.get(stm) {
-        if let Ok(guard) = stream.lock() {
+        if let Ok(mut guard) = stream.lock() {
             return i32::from(guard.is_eof());
         }
     }
@@ -436,10 +444,7 @@ pub extern "C" fn fz_read_line(
 pub extern "C" fn fz_unread_byte(_ctx: Handle, stm: Handle) {
     if let Some(stream) = STREAMS.get(stm) {
         if let Ok(mut guard) = stream.lock() {
-            if guard.position > 0 {
-                guard.position -= 1;
-                guard.eof = false;
-            }
+            guard.unread_byte();
         }
     }
 }
@@ -468,10 +473,7 @@ pub extern "C" fn fz_read_all(_ctx: Handle, stm: Handle) -> Handle {
 
     if let Some(stream) = STREAMS.get(stm) {
         if let Ok(mut guard) = stream.lock() {
-            let remaining = &guard.data[guard.position..];
-            let buffer = Buffer::from_data(remaining);
-            guard.position = guard.data.len();
-            guard.eof = true;
+            let buffer = Buffer::from_data(&guard.read_all());
             return BUFFERS.insert(buffer);
         }
     }
@@ -628,15 +630,15 @@ mod tests {
     #[test]
     fn test_stream_internal_new() {
         let stream = Stream::new();
-        assert!(stream.data.is_empty());
-        assert_eq!(stream.position, 0);
+        assert!(stream.is_empty());
+        assert_eq!(stream.tell(), 0);
     }
 
     #[test]
     fn test_stream_internal_from_memory() {
         let stream = Stream::from_memory(vec![1, 2, 3]);
-        assert_eq!(stream.data.len(), 3);
-        assert_eq!(stream.position, 0);
+        assert_eq!(stream.len(), Some(3));
+        assert_eq!(stream.tell(), 0);
     }
 
     #[test]
@@ -716,8 +718,8 @@ mod tests {
 
     #[test]
     fn test_stream_default() {
-        let stream: Stream = Default::default();
-        assert!(stream.data.is_empty());
+        let mut stream: Stream = Default::default();
+        assert!(stream.is_empty());
         assert!(stream.is_eof());
     }
 
@@ -729,6 +731,48 @@ mod tests {
         fz_drop_stream(0, handle);
     }
 
+    #[test]
+    fn test_fz_open_memory_read() {
+        let data = b"%PDF-1.7\nbytes";
+        let handle = fz_open_memory(0, data.as_ptr(), data.len());
+
+        let mut buf = [0u8; 8];
+        assert_eq!(fz_read(0, handle, buf.as_mut_ptr(), buf.len()), 8);
+        assert_eq!(&buf, b"%PDF-1.7");
+        assert_eq!(fz_read_byte(0, handle), b'\n' as i32);
+        fz_unread_byte(0, handle);
+        assert_eq!(fz_read_byte(0, handle), b'\n' as i32);
+        assert_eq!(fz_tell(0, handle), 9);
+
+        fz_seek(0, handle, -2, 1); // SEEK_CUR
+        assert_eq!(fz_read_byte(0, handle), b'7' as i32);
+        fz_seek(0, handle, -1, 2); // SEEK_END
+        assert_eq!(fz_read_byte(0, handle), b's' as i32);
+        assert_eq!(fz_read_byte(0, handle), -1);
+        assert_eq!(fz_is_eof(0, handle), 1);
+        assert_eq!(fz_read(0, handle, buf.as_mut_ptr(), buf.len()), 0);
+        fz_drop_stream(0, handle);
+    }
+
+    #[test]
+    fn test_fz_open_file_read() {
+        let path = std::env::temp_dir().join(format!("micropdf-stream-{}.bin", std::process::id()));
+        let data: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+        let filename = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+
+        let handle = fz_open_file(0, filename.as_ptr());
+        assert_ne!(handle, 0);
+        let mut buf = vec![0u8; 10_000];
+        assert_eq!(fz_read(0, handle, buf.as_mut_ptr(), buf.len()), 10_000);
+        assert_eq!(buf, data[..10_000]);
+        fz_seek(0, handle, 19_999, 0);
+        assert_eq!(fz_read_byte(0, handle), data[19_999] as i32);
+        assert_eq!(fz_read_byte(0, handle), -1);
+        fz_drop_stream(0, handle);
+        std::fs::remove_file(&path).unwrap();
+    }
+
     #[test]
     fn test_fz_open_file_null() {
         let handle = fz_open_file(0, std::ptr::null());
@@ -597,6 +597,158 @@ pub extern "C" fn fz_byte_to_char_offset(
     -1
 }
 
+// ============================================================================
+// PDF Text Strings and Dates
+// ============================================================================
+
+pub use crate::pdf::object::pdf_doc_char;
+
+/// Decode a PDF text string to UTF-8
+///
+/// See [`decode_text_string`](crate::pdf::object::decode_text_string).
+pub fn pdf_string_to_utf8(bytes: &[u8]) -> String {
+    crate::pdf::object::decode_text_string(bytes)
+}
+
+/// Components of a PDF date string
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PdfDate {
+    pub year: i32,
+    pub month: i32,
+    pub day: i32,
+    pub hour: i32,
+    pub minute: i32,
+    pub second: i32,
+    /// Offset from UTC in minutes, positive east of Greenwich
+    pub tz_offset: i32,
+    /// Whether the string gave a timezone (`Z` counts)
+    pub has_tz: i32,
+}
+
+/// Parse a date string of the form `D:YYYYMMDDHHmmSSOHH'mm'`
+///
+/// Everything after the year is optional, as is the `D:` prefix. Missing
+/// fields take their earliest value.
+pub fn parse_pdf_date(s: &[u8]) -> Option<PdfDate> {
+    let s = s.strip_prefix(b"D:").unwrap_or(s);
+    let mut pos = 0;
+    let mut field = |digits: usize, default: i32, range: std::ops::RangeInclusive<i32>| {
+        let Some(chunk) = s
+            .get(pos..pos + digits)
+            .filter(|c| c.iter().all(u8::is_ascii_digit))
+        else {
+            return Some(default);
+        };
+        pos += digits;
+        let value = chunk.iter().fold(0, |n, &d| n * 10 + (d - b'0') as i32);
+        range.contains(&value).then_some(value)
+    };
+
+    if !s.get(..4)?.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let mut date = PdfDate {
+        year: field(4, 0, 0..=9999)?,
+        month: field(2, 1, 1..=12)?,
+        day: field(2, 1, 1..=31)?,
+        hour: field(2, 0, 0..=23)?,
+        minute: field(2, 0, 0..=59)?,
+        second: field(2, 0, 0..=59)?,
+        ..Default::default()
+    };
+
+    let sign = match s.get(pos) {
+        None => return Some(date),
+        Some(b'Z') => 0,
+        Some(b'+') => 1,
+        Some(b'-') => -1,
+        Some(_) => return None,
+    };
+    pos += 1;
+    date.has_tz = 1;
+    let mut field = |digits: usize| {
+        let chunk = s.get(pos..pos + digits)?;
+        if !chunk.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        pos += digits;
+        while s.get(pos) == Some(&b'\'') {
+            pos += 1;
+        }
+        Some(chunk.iter().fold(0, |n, &d| n * 10 + (d - b'0') as i32))
+    };
+    let hours = field(2).unwrap_or(0);
+    let minutes = field(2).unwrap_or(0);
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    date.tz_offset = sign * (hours * 60 + minutes);
+    Some(date)
+}
+
+/// Convert a PDF text string (UTF-16BE, UTF-8 or PDFDocEncoding) to UTF-8
+///
+/// Writes a null-terminated result, truncated to a character boundary if
+/// `output` is too small, and returns its length in bytes.
+///
+/// # Safety
+/// - `data` must point to `len` readable bytes
+/// - `output` must point to at least `output_size` bytes
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_pdf_string_to_utf8(
+    _ctx: Handle,
+    data: *const u8,
+    len: usize,
+    output: *mut c_char,
+    output_size: usize,
+) -> usize {
+    if output.is_null() || output_size == 0 || (data.is_null() && len > 0) {
+        return 0;
+    }
+
+    let bytes = if len == 0 {
+        &[][..]
+    } else {
+        unsafe { std::slice::from_raw_parts(data, len) }
+    };
+    let text = pdf_string_to_utf8(bytes);
+
+    let mut copy_len = text.len().min(output_size - 1);
+    while !text.is_char_boundary(copy_len) {
+        copy_len -= 1;
+    }
+
+    let output_slice = unsafe { std::slice::from_raw_parts_mut(output as *mut u8, copy_len + 1) };
+    output_slice[..copy_len].copy_from_slice(&text.as_bytes()[..copy_len]);
+    output_slice[copy_len] = 0;
+
+    copy_len
+}
+
+/// Parse a PDF date string into its components
+///
+/// Returns 1 on success, 0 if the string is not a valid date.
+///
+/// # Safety
+/// - `date` must be a valid null-terminated string
+/// - `out` must point to a writable `PdfDate`
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_parse_pdf_date(_ctx: Handle, date: *const c_char, out: *mut PdfDate) -> i32 {
+    if date.is_null() || out.is_null() {
+        return 0;
+    }
+
+    let bytes = unsafe { CStr::from_ptr(date) }.to_bytes();
+    match parse_pdf_date(bytes) {
+        Some(parsed) => {
+            unsafe { *out = parsed };
+            1
+        }
+        None => 0,
+    }
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -712,4 +864,85 @@ mod tests {
         assert!(fz_strcoll(0, a.as_ptr(), b.as_ptr(), std::ptr::null()) < 0);
         assert_eq!(fz_strcoll(0, a.as_ptr(), c.as_ptr(), std::ptr::null()), 0);
     }
+
+    #[test]
+    fn test_utf16be_title() {
+        let info = crate::pdf::parser::parse_object(
+            b"<< /Title <FEFF004D00FC006E006300680065006E0020D83DDCC4> >>",
+        )
+        .unwrap();
+        let title = match info {
+            crate::pdf::object::Object::Dict(dict) => match dict.get("Title") {
+                Some(crate::pdf::object::Object::String(s)) => s.as_bytes().to_vec(),
+                other => panic!("no title: {other:?}"),
+            },
+            other => panic!("not a dict: {other:?}"),
+        };
+
+        let mut output = [0u8; 64];
+        let len = fz_pdf_string_to_utf8(
+            0,
+            title.as_ptr(),
+            title.len(),
+            output.as_mut_ptr().cast(),
+            64,
+        );
+        assert_eq!(std::str::from_utf8(&output[..len]).unwrap(), "München 📄");
+
+        // Truncation never splits a character
+        let len = fz_pdf_string_to_utf8(
+            0,
+            title.as_ptr(),
+            title.len(),
+            output.as_mut_ptr().cast(),
+            3,
+        );
+        assert_eq!(&output[..=len], b"M\0");
+    }
+
+    #[test]
+    fn test_pdf_doc_encoding() {
+        assert_eq!(
+            pdf_string_to_utf8(b"\x93nal \x84 \xA0"),
+            "\u{FB01}nal \u{2014} \u{20AC}"
+        );
+        assert_eq!(pdf_string_to_utf8(b"caf\xE9"), "café");
+        // Language escape in UTF-16 text
+        assert_eq!(
+            pdf_string_to_utf8(b"\xFE\xFF\x00\x1Bde\x00\x1B\x00H\x00i"),
+            "Hi"
+        );
+    }
+
+    #[test]
+    fn test_parse_pdf_date_with_offset() {
+        let mut date = PdfDate::default();
+        assert_eq!(
+            fz_parse_pdf_date(0, c"D:20240315143005-05'30'".as_ptr(), &mut date),
+            1
+        );
+        assert_eq!(
+            date,
+            PdfDate {
+                year: 2024,
+                month: 3,
+                day: 15,
+                hour: 14,
+                minute: 30,
+                second: 5,
+                tz_offset: -330,
+                has_tz: 1,
+            }
+        );
+
+        let utc = parse_pdf_date(b"D:19991231235959Z").unwrap();
+        assert_eq!((utc.tz_offset, utc.has_tz), (0, 1));
+
+        let partial = parse_pdf_date(b"D:2023").unwrap();
+        assert_eq!((partial.month, partial.day, partial.has_tz), (1, 1, 0));
+
+        assert!(parse_pdf_date(b"D:20231301").is_none());
+        assert!(parse_pdf_date(b"yesterday").is_none());
+        assert_eq!(fz_parse_pdf_date(0, c"junk".as_ptr(), &mut date), 0);
+    }
 }
@@ -6,7 +6,11 @@
 
 use std::sync::LazyLock;
 
-use crate::ffi::{Handle, HandleStore};
+use crate::ffi::document::{PAGES, open_pdf};
+use crate::ffi::{DOCUMENTS, Handle, HandleStore};
+use crate::fitz::error::Result;
+use crate::pdf::document::Document;
+use crate::pdf::object::{Dict, Object};
 
 /// Global store for transitions
 pub static TRANSITIONS: LazyLock<HandleStore<Transition>> = LazyLock::new(HandleStore::new);
@@ -62,6 +66,26 @@ impl TransitionType {
         }
     }
 
+    /// Get transition type from a `/Trans` dictionary's `/S` style name
+    ///
+    /// `/R` (replace) and unknown styles map to `None`.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "Split" => TransitionType::Split,
+            "Blinds" => TransitionType::Blinds,
+            "Box" => TransitionType::Box,
+            "Wipe" => TransitionType::Wipe,
+            "Dissolve" => TransitionType::Dissolve,
+            "Glitter" => TransitionType::Glitter,
+            "Fly" => TransitionType::Fly,
+            "Push" => TransitionType::Push,
+            "Cover" => TransitionType::Cover,
+            "Uncover" => TransitionType::Uncover,
+            "Fade" => TransitionType::Fade,
+            _ => TransitionType::None,
+        }
+    }
+
     /// Get transition name
     pub fn name(&self) -> &'static str {
         match self {
@@ -93,7 +117,8 @@ pub struct Transition {
     pub vertical: i32,
     /// Direction (0 = inward, 1 = outward) for Split/Box
     pub outwards: i32,
-    /// Direction angle in degrees (for Wipe, Glitter, Fly, etc.)
+    /// Direction angle in degrees (for Wipe, Glitter, Fly, etc.), or -1
+    /// for `/Di /None`
     pub direction: i32,
     /// Internal state variable 0
     pub state0: i32,
@@ -115,6 +140,53 @@ impl Transition {
         }
     }
 
+    /// Parse a page's `/Trans` dictionary
+    ///
+    /// Missing entries take their defaults: style `/R`, a duration of one
+    /// second, horizontal, inward and a direction of 0.
+    pub fn from_dict(doc: &Document, trans: &Dict) -> Result<Self> {
+        let name = |key| -> Result<Option<String>> {
+            Ok(doc
+                .resolve_key(trans, key)?
+                .and_then(|v| v.as_name().map(|n| n.as_str().to_string())))
+        };
+        let transition_type =
+            name("S")?.map_or(TransitionType::None, |s| TransitionType::from_name(&s));
+        let duration = doc
+            .resolve_key(trans, "D")?
+            .and_then(|v| v.as_real())
+            .map_or(1.0, |d| d as f32);
+        let direction = match doc.resolve_key(trans, "Di")? {
+            Some(Object::Name(n)) if n.as_str() == "None" => -1,
+            Some(di) => di.as_real().map_or(0, |d| d as i32),
+            None => 0,
+        };
+        Ok(Self {
+            transition_type,
+            duration,
+            vertical: i32::from(name("Dm")?.as_deref() == Some("V")),
+            outwards: i32::from(name("M")?.as_deref() == Some("O")),
+            direction,
+            state0: 0,
+            state1: 0,
+        })
+    }
+
+    /// Fraction of the transition completed after `elapsed` seconds, in
+    /// 0.0..=1.0
+    pub fn progress(&self, elapsed: f32) -> f32 {
+        if self.duration <= 0.0 || self.transition_type == TransitionType::None {
+            return 1.0;
+        }
+        (elapsed / self.duration).clamp(0.0, 1.0)
+    }
+
+    /// Progress after `elapsed` seconds on the 0-256 scale used by
+    /// [`generate_transition_frame`]
+    pub fn frame_time(&self, elapsed: f32) -> i32 {
+        (self.progress(elapsed) * 256.0).round() as i32
+    }
+
     /// Create a split transition
     pub fn split(duration: f32, vertical: bool, outwards: bool) -> Self {
         Self {
@@ -720,6 +792,39 @@ pub extern "C" fn fz_new_fade_transition(duration: f32) -> Handle {
     TRANSITIONS.insert(trans)
 }
 
+/// Get the transition of a page, parsed from its `/Trans` dictionary
+///
+/// Returns a transition of type None when the page has none or cannot be
+/// read.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_page_presentation(_ctx: Handle, page: Handle) -> Transition {
+    page_transition(page).unwrap_or_default()
+}
+
+fn page_transition(page: Handle) -> Option<Transition> {
+    let (doc, page_num) = {
+        let page = PAGES.get(page)?;
+        let page = page.lock().ok()?;
+        (page.doc_handle, page.page_num)
+    };
+    let parsed = open_pdf(doc)?;
+    let page = parsed.page(usize::try_from(page_num).ok()?).ok()?;
+    match parsed.resolve_key(page.dict(), "Trans").ok()?? {
+        Object::Dict(trans) => Transition::from_dict(&parsed, &trans).ok(),
+        _ => None,
+    }
+}
+
+/// Fraction of a transition completed after `elapsed` seconds (0.0-1.0)
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_transition_progress(trans: Handle, elapsed: f32) -> f32 {
+    if let Some(t) = TRANSITIONS.get(trans) {
+        t.lock().unwrap().progress(elapsed)
+    } else {
+        1.0
+    }
+}
+
 /// Drop a transition
 #[unsafe(no_mangle)]
 pub extern "C" fn fz_drop_transition(_ctx: Handle, trans: Handle) {
@@ -904,6 +1009,7 @@ pub extern "C" fn fz_transition_set_direction(trans: Handle, direction: i32) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
 
     #[test]
     fn test_transition_type_from_i32() {
@@ -914,6 +1020,39 @@ mod tests {
         assert_eq!(TransitionType::from_i32(99), TransitionType::None);
     }
 
+    #[test]
+    fn test_parse_trans_dict() {
+        let data = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] \
+             /Trans << /S /Wipe /D 2 /Di 90 >> >>",
+        ]);
+        let doc = Document::open_bytes(data.clone()).unwrap();
+        let page = doc.page(0).unwrap();
+        let Some(Object::Dict(trans)) = page.dict().get("Trans") else {
+            panic!("no /Trans");
+        };
+        let trans = Transition::from_dict(&doc, trans).unwrap();
+        assert_eq!(trans.transition_type, TransitionType::Wipe);
+        assert_eq!(trans.duration, 2.0);
+        assert_eq!(trans.direction, 90);
+        assert_eq!((trans.vertical, trans.outwards), (0, 0));
+
+        assert_eq!(trans.progress(-1.0), 0.0);
+        assert_eq!(trans.progress(0.5), 0.25);
+        assert_eq!(trans.progress(3.0), 1.0);
+        assert_eq!(trans.frame_time(1.0), 128);
+
+        let doc_handle = DOCUMENTS.insert(crate::ffi::document::Document::new(data));
+        let page_handle = crate::ffi::document::fz_load_page(0, doc_handle, 0);
+        let presentation = fz_page_presentation(0, page_handle);
+        assert_eq!(presentation.transition_type, TransitionType::Wipe);
+        assert_eq!(presentation.direction, 90);
+        PAGES.remove(page_handle);
+        DOCUMENTS.remove(doc_handle);
+    }
+
     #[test]
     fn test_transition_type_name() {
         assert_eq!(TransitionType::None.name(), "None");
@@ -2,7 +2,7 @@
 //! Safe Rust implementation of fz_tree (for tagged PDF support)
 
 use super::{Handle, HandleStore};
-use std::ffi::{CStr, c_char};
+use std::ffi::{CStr, CString, c_char};
 use std::sync::LazyLock;
 
 /// Structure element type (PDF spec)
@@ -102,6 +102,70 @@ pub enum StructureType {
     Form = 62,
 }
 
+/// Standard structure type names (PDF 32000-1 section 14.8.4)
+const STANDARD_ROLES: &[(&str, StructureType)] = &[
+    ("Document", StructureType::Document),
+    ("Part", StructureType::Part),
+    ("Art", StructureType::Art),
+    ("Sect", StructureType::Sect),
+    ("Div", StructureType::Div),
+    ("BlockQuote", StructureType::BlockQuote),
+    ("Caption", StructureType::Caption),
+    ("TOC", StructureType::TOC),
+    ("TOCI", StructureType::TOCI),
+    ("Index", StructureType::Index),
+    ("NonStruct", StructureType::NonStruct),
+    ("Private", StructureType::Private),
+    ("P", StructureType::P),
+    ("H", StructureType::H),
+    ("H1", StructureType::H1),
+    ("H2", StructureType::H2),
+    ("H3", StructureType::H3),
+    ("H4", StructureType::H4),
+    ("H5", StructureType::H5),
+    ("H6", StructureType::H6),
+    ("L", StructureType::L),
+    ("LI", StructureType::LI),
+    ("Lbl", StructureType::Lbl),
+    ("LBody", StructureType::LBody),
+    ("Table", StructureType::Table),
+    ("TR", StructureType::TR),
+    ("TH", StructureType::TH),
+    ("TD", StructureType::TD),
+    ("THead", StructureType::THead),
+    ("TBody", StructureType::TBody),
+    ("TFoot", StructureType::TFoot),
+    ("Span", StructureType::Span),
+    ("Quote", StructureType::Quote),
+    ("Note", StructureType::Note),
+    ("Reference", StructureType::Reference),
+    ("BibEntry", StructureType::BibEntry),
+    ("Code", StructureType::Code),
+    ("Link", StructureType::Link),
+    ("Annot", StructureType::Annot),
+    ("Figure", StructureType::Figure),
+    ("Formula", StructureType::Formula),
+    ("Form", StructureType::Form),
+];
+
+impl StructureType {
+    /// Look up a standard structure type by its /S name
+    pub fn from_name(name: &str) -> Option<Self> {
+        STANDARD_ROLES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|&(_, t)| t)
+    }
+
+    /// The standard /S name, or "Unknown"
+    pub fn name(self) -> &'static str {
+        STANDARD_ROLES
+            .iter()
+            .find(|&&(_, t)| t == self)
+            .map_or("Unknown", |&(n, _)| n)
+    }
+}
+
 /// Reading order for tree traversal
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -143,6 +207,10 @@ pub struct TreeNode {
     pub bbox: [f32; 4],
     /// Associated marked content ID
     pub mcid: i32,
+    /// All marked content IDs owned by this element, in order
+    pub mcids: Vec<i32>,
+    /// Role after /RoleMap resolution (standard name when one is reached)
+    pub role: CString,
     /// Reading order hint
     pub reading_order: ReadingOrder,
     /// Custom attributes
@@ -165,6 +233,8 @@ impl Default for TreeNode {
             page: -1,
             bbox: [0.0, 0.0, 0.0, 0.0],
             mcid: -1,
+            mcids: Vec::new(),
+            role: CString::default(),
             reading_order: ReadingOrder::Column,
             attributes: std::collections::HashMap::new(),
         }
@@ -661,6 +731,286 @@ fn collect_text_recursive(node: Handle, text: &mut String) {
     }
 }
 
+// ============================================================================
+// Tagged PDF Loading
+// ============================================================================
+
+/// Maximum structure element nesting followed when loading
+const MAX_STRUCT_DEPTH: usize = 256;
+
+struct StructLoader<'a> {
+    pdf: &'a crate::pdf::document::Document,
+    role_map: std::collections::HashMap<String, String>,
+    nodes: Vec<Handle>,
+    id_map: std::collections::HashMap<String, Handle>,
+    visited: std::collections::HashSet<crate::pdf::object::ObjRef>,
+}
+
+impl StructLoader<'_> {
+    /// Follow /RoleMap until a standard type is reached; chains are bounded
+    /// so cyclic maps terminate
+    fn resolve_role(&self, name: &str) -> (String, StructureType) {
+        let mut role = name;
+        for _ in 0..self.role_map.len() + 1 {
+            if let Some(t) = StructureType::from_name(role) {
+                return (role.to_string(), t);
+            }
+            match self.role_map.get(role) {
+                Some(mapped) => role = mapped,
+                None => break,
+            }
+        }
+        (role.to_string(), StructureType::Unknown)
+    }
+
+    /// Load the /K entry of an element, collecting child elements and MCIDs
+    fn load_kids(
+        &mut self,
+        kids: &crate::pdf::object::Object,
+        parent: Handle,
+        children: &mut Vec<Handle>,
+        mcids: &mut Vec<i32>,
+        depth: usize,
+    ) {
+        use crate::pdf::object::Object;
+
+        if depth > MAX_STRUCT_DEPTH {
+            return;
+        }
+        if let Some(r) = kids.as_obj_ref() {
+            if !self.visited.insert(r) {
+                return;
+            }
+        }
+        let Ok(kids) = self.pdf.resolve(kids) else {
+            return;
+        };
+        match kids {
+            Object::Int(mcid) => mcids.push(mcid as i32),
+            Object::Array(items) => {
+                for item in &items {
+                    self.load_kids(item, parent, children, mcids, depth + 1);
+                }
+            }
+            Object::Dict(dict) => {
+                let kind = dict.get("Type").and_then(Object::as_name);
+                match kind.map(|n| n.as_str()) {
+                    Some("MCR") => {
+                        if let Some(mcid) = dict.get("MCID").and_then(Object::as_int) {
+                            mcids.push(mcid as i32);
+                        }
+                    }
+                    Some("OBJR") => {}
+                    _ if dict.contains_key("S") => {
+                        children.push(self.load_element(&dict, parent, depth + 1));
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn load_element(
+        &mut self,
+        dict: &crate::pdf::object::Dict,
+        parent: Handle,
+        depth: usize,
+    ) -> Handle {
+        use crate::pdf::object::Object;
+
+        let text = |key: &str| match self.pdf.resolve_key(dict, key) {
+            Ok(Some(Object::String(s))) => s.to_text(),
+            _ => String::new(),
+        };
+        let type_name = dict
+            .get("S")
+            .and_then(Object::as_name)
+            .map(|n| n.as_str().to_string())
+            .unwrap_or_default();
+        let (role, struct_type) = self.resolve_role(&type_name);
+        let page = dict
+            .get("Pg")
+            .and_then(Object::as_obj_ref)
+            .and_then(|r| self.pdf.page_index(r).ok().flatten())
+            .map_or(-1, |i| i as i32);
+
+        let node = TreeNode {
+            struct_type,
+            type_name,
+            title: text("T"),
+            alt_text: text("Alt"),
+            actual_text: text("ActualText"),
+            lang: text("Lang"),
+            expansion: text("E"),
+            id: text("ID"),
+            parent,
+            page,
+            role: CString::new(role).unwrap_or_default(),
+            ..Default::default()
+        };
+        let id = node.id.clone();
+        let handle = TREE_NODES.insert(node);
+        self.nodes.push(handle);
+        if !id.is_empty() {
+            self.id_map.insert(id, handle);
+        }
+
+        let mut children = Vec::new();
+        let mut mcids = Vec::new();
+        if let Some(kids) = dict.get("K") {
+            self.load_kids(kids, handle, &mut children, &mut mcids, depth);
+        }
+        if let Some(n) = TREE_NODES.get(handle) {
+            if let Ok(mut guard) = n.lock() {
+                guard.mcid = mcids.first().copied().unwrap_or(-1);
+                guard.mcids = mcids;
+                guard.children = children;
+            }
+        }
+        handle
+    }
+}
+
+/// Load the structure tree of a tagged PDF from /Root /StructTreeRoot
+///
+/// The returned tree's root is a Document node whose children are the
+/// top-level structure elements. Custom roles are resolved through
+/// /RoleMap. Returns 0 if the document has no structure tree.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_load_struct_tree(_ctx: Handle, doc: Handle) -> Handle {
+    use crate::pdf::object::Object;
+
+    let Some(pdf) = super::document::open_pdf(doc) else {
+        return 0;
+    };
+    let Ok(catalog) = pdf.catalog() else {
+        return 0;
+    };
+    let Ok(Some(Object::Dict(struct_root))) = pdf.resolve_key(&catalog, "StructTreeRoot") else {
+        return 0;
+    };
+
+    let mut role_map = std::collections::HashMap::new();
+    if let Ok(Some(Object::Dict(map))) = pdf.resolve_key(&struct_root, "RoleMap") {
+        for (key, value) in &map {
+            if let Some(target) = value.as_name() {
+                role_map.insert(key.as_str().to_string(), target.as_str().to_string());
+            }
+        }
+    }
+
+    let root = TREE_NODES.insert(TreeNode {
+        struct_type: StructureType::Document,
+        type_name: "Document".to_string(),
+        role: CString::new("Document").unwrap_or_default(),
+        ..Default::default()
+    });
+    let mut loader = StructLoader {
+        pdf: &pdf,
+        role_map,
+        nodes: vec![root],
+        id_map: std::collections::HashMap::new(),
+        visited: std::collections::HashSet::new(),
+    };
+
+    let mut children = Vec::new();
+    let mut mcids = Vec::new();
+    if let Some(kids) = struct_root.get("K") {
+        loader.load_kids(kids, root, &mut children, &mut mcids, 0);
+    }
+    if let Some(n) = TREE_NODES.get(root) {
+        if let Ok(mut guard) = n.lock() {
+            guard.children = children;
+        }
+    }
+
+    let role_map = loader
+        .role_map
+        .keys()
+        .map(|k| {
+            let (_, t) = loader.resolve_role(k);
+            (k.clone(), t)
+        })
+        .collect();
+    STRUCTURE_TREES.insert(StructureTree {
+        root,
+        nodes: loader.nodes,
+        id_map: loader.id_map,
+        role_map,
+    })
+}
+
+/// Get a structure element's role after /RoleMap resolution
+///
+/// Returns the standard name (e.g. "H1") when the mapping reaches one,
+/// otherwise the element's own /S name.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_struct_role(_ctx: Handle, node: Handle) -> *const c_char {
+    static EMPTY: &[u8] = b"\0";
+
+    if let Some(n) = TREE_NODES.get(node) {
+        if let Ok(guard) = n.lock() {
+            if !guard.role.is_empty() {
+                return guard.role.as_ptr();
+            }
+        }
+    }
+    EMPTY.as_ptr().cast()
+}
+
+/// Get a structure element's first child
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_struct_child(ctx: Handle, node: Handle) -> Handle {
+    fz_tree_node_first_child(ctx, node)
+}
+
+/// Get a structure element's next sibling
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_struct_next(_ctx: Handle, node: Handle) -> Handle {
+    let parent = match TREE_NODES.get(node) {
+        Some(n) => match n.lock() {
+            Ok(guard) => guard.parent,
+            Err(_) => return 0,
+        },
+        None => return 0,
+    };
+    if let Some(p) = TREE_NODES.get(parent) {
+        if let Ok(guard) = p.lock() {
+            if let Some(pos) = guard.children.iter().position(|&c| c == node) {
+                return guard.children.get(pos + 1).copied().unwrap_or(0);
+            }
+        }
+    }
+    0
+}
+
+/// Get the number of marked content IDs owned by a structure element
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_struct_mcid_count(_ctx: Handle, node: Handle) -> i32 {
+    if let Some(n) = TREE_NODES.get(node) {
+        if let Ok(guard) = n.lock() {
+            return guard.mcids.len() as i32;
+        }
+    }
+    0
+}
+
+/// Get a structure element's marked content ID at index, or -1
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_struct_mcid(_ctx: Handle, node: Handle, index: i32) -> i32 {
+    if index < 0 {
+        return -1;
+    }
+
+    if let Some(n) = TREE_NODES.get(node) {
+        if let Ok(guard) = n.lock() {
+            return guard.mcids.get(index as usize).copied().unwrap_or(-1);
+        }
+    }
+    -1
+}
+
 // ============================================================================
 // Reference Counting
 // ============================================================================
@@ -691,6 +1041,7 @@ pub extern "C" fn fz_drop_structure_tree(_ctx: Handle, tree: Handle) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pdf::test_pdf::build_pdf;
 
     #[test]
     fn test_create_tree() {
@@ -754,4 +1105,47 @@ mod tests {
 
         fz_drop_structure_tree(0, tree);
     }
+
+    #[test]
+    fn test_load_struct_tree_role_map() {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R /StructTreeRoot 4 0 R /MarkInfo << /Marked true >> >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 200 200] >>",
+            "<< /Type /StructTreeRoot /K 5 0 R /RoleMap << /Heading1 /Title /Title /H1 >> >>",
+            "<< /Type /StructElem /S /Document /P 4 0 R /K [6 0 R 7 0 R] >>",
+            "<< /Type /StructElem /S /Heading1 /P 5 0 R /Pg 3 0 R /K 0 /ID (h1) >>",
+            "<< /Type /StructElem /S /P /P 5 0 R /Pg 3 0 R /K [1 << /Type /MCR /MCID 2 >>] >>",
+        ];
+        let data = build_pdf(&objects);
+        let doc = crate::ffi::DOCUMENTS.insert(crate::ffi::document::Document::new(data));
+
+        let tree = fz_load_struct_tree(0, doc);
+        crate::ffi::DOCUMENTS.remove(doc);
+        assert!(tree > 0);
+
+        let role = |node| {
+            unsafe { CStr::from_ptr(fz_struct_role(0, node)) }
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+        let document = fz_struct_child(0, fz_tree_root(0, tree));
+        assert_eq!(role(document), "Document");
+
+        let heading = fz_struct_child(0, document);
+        assert_eq!(role(heading), "H1");
+        assert_eq!(fz_tree_node_type(0, heading), StructureType::H1 as i32);
+        assert_eq!(fz_tree_node_page(0, heading), 0);
+        assert_eq!(fz_tree_node_mcid(0, heading), 0);
+        assert_eq!(fz_tree_find_by_id(0, tree, c"h1".as_ptr()), heading);
+
+        let para = fz_struct_next(0, heading);
+        assert_eq!(role(para), "P");
+        assert_eq!(fz_struct_mcid_count(0, para), 2);
+        assert_eq!(fz_struct_mcid(0, para, 1), 2);
+        assert_eq!(fz_struct_next(0, para), 0);
+
+        fz_drop_structure_tree(0, tree);
+    }
 }
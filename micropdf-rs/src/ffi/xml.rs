@@ -662,6 +662,152 @@ pub extern "C" fn fz_drop_xml(_ctx: Handle, doc: Handle) {
     XML_DOCS.remove(doc);
 }
 
+// ============================================================================
+// Structured Text Output
+// ============================================================================
+
+/// Escape text for use inside a double-quoted XML attribute
+fn escape_xml_attr(s: &str, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("&#x{:x};", c as u32)),
+            c => out.push(c),
+        }
+    }
+}
+
+fn font_name(font: Option<Handle>) -> String {
+    font.and_then(|h| super::font::FONTS.get(h))
+        .and_then(|f| f.lock().ok().map(|f| f.name().to_string()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Serialize a text page in MuPDF's stext XML layout
+fn stext_xml(page: &super::stext::StextPage) -> String {
+    use super::stext::StextBlockType;
+    use std::fmt::Write;
+
+    let m = page.mediabox;
+    let mut xml = String::new();
+    let _ = writeln!(
+        xml,
+        "<page id=\"page1\" width=\"{}\" height=\"{}\">",
+        m.x1 - m.x0,
+        m.y1 - m.y0
+    );
+
+    for block in &page.blocks {
+        let b = block.bbox;
+        match block.block_type {
+            StextBlockType::Image => {
+                let _ = write!(xml, "<image bbox=\"{} {} {} {}\"", b.x0, b.y0, b.x1, b.y1);
+                let size = block
+                    .image
+                    .and_then(|h| super::image::IMAGES.get(h))
+                    .and_then(|i| i.lock().ok().map(|i| (i.width(), i.height())));
+                if let Some((w, h)) = size {
+                    let _ = write!(xml, " width=\"{w}\" height=\"{h}\"");
+                }
+                xml.push_str("/>\n");
+            }
+            StextBlockType::Text => {
+                let _ = writeln!(xml, "<block bbox=\"{} {} {} {}\">", b.x0, b.y0, b.x1, b.y1);
+                for line in &block.lines {
+                    let l = line.bbox;
+                    let _ = write!(
+                        xml,
+                        "<line bbox=\"{} {} {} {}\" wmode=\"{}\" dir=\"{} {}\" text=\"",
+                        l.x0, l.y0, l.x1, l.y1, line.wmode, line.dir.x, line.dir.y
+                    );
+                    escape_xml_attr(&line.text(), &mut xml);
+                    xml.push_str("\">\n");
+
+                    // Runs of characters sharing a font and size share a <font>
+                    let mut current: Option<(Option<Handle>, f32)> = None;
+                    for ch in &line.chars {
+                        if current != Some((ch.font, ch.size)) {
+                            if current.is_some() {
+                                xml.push_str("</font>\n");
+                            }
+                            xml.push_str("<font name=\"");
+                            escape_xml_attr(&font_name(ch.font), &mut xml);
+                            let _ = writeln!(xml, "\" size=\"{}\">", ch.size);
+                            current = Some((ch.font, ch.size));
+                        }
+                        let q = ch.quad;
+                        let _ = write!(
+                            xml,
+                            "<char quad=\"{} {} {} {} {} {} {} {}\" x=\"{}\" y=\"{}\" bidi=\"{}\" color=\"#{:06x}\" alpha=\"#{:02x}\" flags=\"{}\" c=\"",
+                            q.ul_x,
+                            q.ul_y,
+                            q.ur_x,
+                            q.ur_y,
+                            q.ll_x,
+                            q.ll_y,
+                            q.lr_x,
+                            q.lr_y,
+                            ch.origin.x,
+                            ch.origin.y,
+                            ch.bidi,
+                            ch.argb & 0xFF_FFFF,
+                            ch.argb >> 24,
+                            ch.flags
+                        );
+                        let c = char::from_u32(ch.c as u32).unwrap_or('\u{FFFD}');
+                        escape_xml_attr(c.encode_utf8(&mut [0; 4]), &mut xml);
+                        xml.push_str("\"/>\n");
+                    }
+                    if current.is_some() {
+                        xml.push_str("</font>\n");
+                    }
+                    xml.push_str("</line>\n");
+                }
+                xml.push_str("</block>\n");
+            }
+            _ => {}
+        }
+    }
+    xml.push_str("</page>\n");
+    xml
+}
+
+/// Serialize an extracted text page as MuPDF stext XML
+///
+/// Produces `<page><block><line><font><char>` with `bbox` and `quad`
+/// attributes, and `<image>` elements for image blocks. Returns the full
+/// length (excluding the terminator), which may exceed `output_size` when
+/// the output was truncated, or 0 for an invalid page.
+#[unsafe(no_mangle)]
+pub extern "C" fn fz_stext_to_xml(
+    _ctx: Handle,
+    page: Handle,
+    output: *mut c_char,
+    output_size: usize,
+) -> usize {
+    let Some(arc) = super::stext::STEXT_PAGES.get(page) else {
+        return 0;
+    };
+    let xml = match arc.lock() {
+        Ok(p) => stext_xml(&p),
+        Err(_) => return 0,
+    };
+
+    if !output.is_null() && output_size > 0 {
+        let bytes = xml.as_bytes();
+        let copy_len = bytes.len().min(output_size - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), output as *mut u8, copy_len);
+            *output.add(copy_len) = 0;
+        }
+    }
+    xml.len()
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -737,4 +883,59 @@ mod tests {
 
         fz_drop_xml(0, doc);
     }
+
+    #[test]
+    fn test_stext_to_xml_structure() {
+        use super::super::stext::*;
+        let page = fz_new_stext_page(0, 0.0, 0.0, 612.0, 792.0);
+        let block = fz_add_stext_block(0, page, 0.0, 0.0, 100.0, 20.0);
+        let line = fz_add_stext_line(0, page, block, 0.0, 0.0, 100.0, 12.0);
+        for (i, c) in "a<&\"".chars().enumerate() {
+            fz_add_stext_char(0, page, block, line, c as i32, (i * 8) as f32, 12.0, 12.0);
+        }
+        if let Some(p) = STEXT_PAGES.get(page) {
+            p.lock().unwrap().blocks.push(StextBlock {
+                block_type: StextBlockType::Image,
+                bbox: Rect {
+                    x0: 0.0,
+                    y0: 30.0,
+                    x1: 50.0,
+                    y1: 80.0,
+                },
+                ..Default::default()
+            });
+        }
+
+        let len = fz_stext_to_xml(0, page, std::ptr::null_mut(), 0);
+        let mut buf = vec![0u8; len + 1];
+        assert_eq!(
+            fz_stext_to_xml(0, page, buf.as_mut_ptr() as *mut c_char, buf.len()),
+            len
+        );
+        fz_drop_stext_page(0, page);
+
+        let xml = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(xml.contains("c=\"&lt;\"") && xml.contains("c=\"&amp;\""));
+        assert!(xml.contains("text=\"a&lt;&amp;&quot;\""));
+
+        let cxml = std::ffi::CString::new(xml).unwrap();
+        let doc = fz_parse_xml(0, cxml.as_ptr(), 0);
+        let root = fz_xml_root(0, doc);
+        assert_eq!(fz_xml_is_tag(0, root, c"page".as_ptr()), 1);
+        assert!(fz_xml_find(0, root, c"block/line/font/char".as_ptr()) > 0);
+        assert!(fz_xml_find(0, root, c"image".as_ptr()) > 0);
+
+        let mut chars = [0 as Handle; 8];
+        let font = fz_xml_find(0, root, c"block/line/font".as_ptr());
+        assert_eq!(
+            fz_xml_find_all(0, font, c"char".as_ptr(), chars.as_mut_ptr(), 8),
+            4
+        );
+        let quad = fz_xml_att(0, chars[0], c"quad".as_ptr());
+        assert!(!quad.is_null());
+        let quad = unsafe { CStr::from_ptr(quad) }.to_str().unwrap();
+        assert_eq!(quad.split(' ').count(), 8);
+
+        fz_drop_xml(0, doc);
+    }
 }
@@ -4,10 +4,27 @@
 
 use crate::fitz::buffer::Buffer;
 use crate::fitz::error::{Error, Result};
+use flate2::read::DeflateDecoder;
 use std::collections::HashMap;
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 
+/// Size of a TAR header or data block
+const TAR_BLOCK: usize = 512;
+
+/// Whether an entry name stays inside the archive: it is relative, has no
+/// drive prefix such as `C:` and has no `..` component
+fn is_safe_entry_name(name: &str) -> bool {
+    let mut parts = name.split(['/', '\\']);
+    !name.starts_with('/')
+        && !name.starts_with('\\')
+        && parts
+            .next()
+            .is_some_and(|first| first != ".." && !first.contains(':'))
+        && !parts.any(|part| part == "..")
+}
+
 /// Archive format
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ArchiveFormat {
@@ -78,10 +95,20 @@ trait ArchiveReader {
     fn entry_names(&self) -> Vec<&str>;
 }
 
+/// How a ZIP entry's data is stored
+#[derive(Debug, Clone, Copy)]
+struct ZipStorage {
+    /// Compression method: 0 for stored, 8 for deflate
+    method: u16,
+    /// Size of the stored data
+    compressed_size: usize,
+}
+
 /// ZIP archive reader
 struct ZipArchive {
     entries: HashMap<String, ArchiveEntry>,
     entry_order: Vec<String>,
+    storage: HashMap<String, ZipStorage>,
     data: Vec<u8>,
 }
 
@@ -90,6 +117,7 @@ impl ZipArchive {
         let mut archive = Self {
             entries: HashMap::new(),
             entry_order: Vec::new(),
+            storage: HashMap::new(),
             data,
         };
         archive.parse()?;
@@ -106,7 +134,7 @@ impl ZipArchive {
         let mut eocd_pos = None;
 
         // Search backwards for EOCD (usually at end, but can have comment)
-        for i in (0..self.data.len().saturating_sub(22)).rev() {
+        for i in (0..=self.data.len().saturating_sub(22)).rev() {
             if self.data.get(i..i + 4) == Some(&eocd_sig) {
                 eocd_pos = Some(i);
                 break;
@@ -148,8 +176,10 @@ impl ZipArchive {
             let comment_len =
                 u16::from_le_bytes([self.data[pos + 32], self.data[pos + 33]]) as usize;
 
+            let method = u16::from_le_bytes([self.data[pos + 10], self.data[pos + 11]]);
+
             // Read compressed and uncompressed sizes
-            let _compressed_size = u32::from_le_bytes([
+            let compressed_size = u32::from_le_bytes([
                 self.data[pos + 20],
                 self.data[pos + 21],
                 self.data[pos + 22],
@@ -175,8 +205,12 @@ impl ZipArchive {
                 break;
             }
 
+            // Entries that would escape the archive are left out
             let filename_bytes = &self.data[pos + 46..pos + 46 + filename_len];
-            if let Ok(filename) = std::str::from_utf8(filename_bytes) {
+            if let Some(filename) = std::str::from_utf8(filename_bytes)
+                .ok()
+                .filter(|name| is_safe_entry_name(name))
+            {
                 let entry = ArchiveEntry {
                     name: filename.to_string(),
                     size: uncompressed_size as u64,
@@ -186,6 +220,13 @@ impl ZipArchive {
 
                 self.entry_order.push(filename.to_string());
                 self.entries.insert(filename.to_string(), entry);
+                self.storage.insert(
+                    filename.to_string(),
+                    ZipStorage {
+                        method,
+                        compressed_size,
+                    },
+                );
             }
 
             // Move to next entry
@@ -217,13 +258,48 @@ impl ArchiveReader for ZipArchive {
     }
 
     fn read_entry(&mut self, name: &str) -> Result<Vec<u8>> {
-        let _entry = self
-            .entries
-            .get(name)
-            .ok_or_else(|| Error::Argument(format!("Entry not found: {}", name)))?;
+        let (Some(entry), Some(storage)) = (self.entries.get(name), self.storage.get(name)) else {
+            return Err(Error::Argument(format!("Entry not found: {}", name)));
+        };
 
-        // Simplified: would extract and decompress here
-        Ok(Vec::new())
+        // The local file header repeats the name and has its own extra
+        // field, so the data offset is found from it
+        let header = entry.offset as usize;
+        let local_sig = [0x50, 0x4b, 0x03, 0x04];
+        if self.data.get(header..header + 4) != Some(&local_sig) || header + 30 > self.data.len() {
+            return Err(Error::Generic(format!(
+                "Invalid local header for: {}",
+                name
+            )));
+        }
+        let filename_len =
+            u16::from_le_bytes([self.data[header + 26], self.data[header + 27]]) as usize;
+        let extra_len =
+            u16::from_le_bytes([self.data[header + 28], self.data[header + 29]]) as usize;
+        let start = header + 30 + filename_len + extra_len;
+        let stored = self
+            .data
+            .get(start..start.saturating_add(storage.compressed_size))
+            .ok_or_else(|| Error::Generic(format!("Truncated entry: {}", name)))?;
+
+        match storage.method {
+            0 => Ok(stored.to_vec()),
+            8 => {
+                // Never inflate past the declared size. The size comes from
+                // the central directory, so it only bounds the output and
+                // is not trusted for the allocation
+                let mut out = Vec::with_capacity(stored.len());
+                DeflateDecoder::new(stored)
+                    .take(entry.size)
+                    .read_to_end(&mut out)
+                    .map_err(Error::System)?;
+                Ok(out)
+            }
+            method => Err(Error::Unsupported(format!(
+                "ZIP compression method {}",
+                method
+            ))),
+        }
     }
 
     fn entry_names(&self) -> Vec<&str> {
@@ -232,7 +308,6 @@ impl ArchiveReader for ZipArchive {
 }
 
 /// TAR archive reader
-#[allow(dead_code)]
 struct TarArchive {
     entries: HashMap<String, ArchiveEntry>,
     entry_order: Vec<String>,
@@ -251,10 +326,98 @@ impl TarArchive {
     }
 
     fn parse(&mut self) -> Result<()> {
-        // Simplified TAR parsing
-        // Real implementation would properly parse TAR headers
+        // Each entry is a 512-byte header followed by its data, padded to
+        // whole blocks; the archive ends with zero blocks
+        let mut pos = 0;
+        let mut long_name: Option<String> = None;
+        while pos + TAR_BLOCK <= self.data.len() {
+            let header = &self.data[pos..pos + TAR_BLOCK];
+            if header.iter().all(|&b| b == 0) {
+                break;
+            }
+
+            let size = Self::octal(&header[124..136])
+                .ok_or_else(|| Error::Generic("Invalid TAR entry size".into()))?;
+            let data_start = pos + TAR_BLOCK;
+            let Some(data_end) = data_start
+                .checked_add(size)
+                .filter(|&end| end <= self.data.len())
+            else {
+                break; // Truncated archive
+            };
+            pos = data_start + size.div_ceil(TAR_BLOCK) * TAR_BLOCK;
+
+            let type_flag = header[156];
+            match type_flag {
+                // GNU long name: the data is the name of the next entry
+                b'L' => {
+                    let name = Self::text(&self.data[data_start..data_end]);
+                    long_name = Some(name);
+                    continue;
+                }
+                // pax extended header: a `path` record renames the next entry
+                b'x' => {
+                    long_name = Self::pax_path(&self.data[data_start..data_end]);
+                    continue;
+                }
+                _ => {}
+            }
+
+            let name = long_name.take().unwrap_or_else(|| {
+                let name = Self::text(&header[0..100]);
+                let prefix = Self::text(&header[345..500]);
+                if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                    format!("{}/{}", prefix, name)
+                } else {
+                    name
+                }
+            });
+            let is_dir = type_flag == b'5';
+            // Only files and directories are listed; links and devices,
+            // and entries that would escape the archive, are left out
+            if !(is_dir || type_flag == b'0' || type_flag == 0) || !is_safe_entry_name(&name) {
+                continue;
+            }
+
+            let entry = ArchiveEntry {
+                name: name.clone(),
+                size: size as u64,
+                is_dir,
+                offset: data_start as u64,
+            };
+            if !self.entries.contains_key(&name) {
+                self.entry_order.push(name.clone());
+            }
+            self.entries.insert(name, entry);
+        }
         Ok(())
     }
+
+    /// A NUL-terminated header field as text
+    fn text(field: &[u8]) -> String {
+        let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+        String::from_utf8_lossy(&field[..end]).into_owned()
+    }
+
+    /// An octal number field, padded with spaces or NULs
+    fn octal(field: &[u8]) -> Option<usize> {
+        let digits = Self::text(field);
+        let digits = digits.trim();
+        if digits.is_empty() {
+            return Some(0);
+        }
+        usize::from_str_radix(digits, 8).ok()
+    }
+
+    /// The `path` record of pax extended header data
+    fn pax_path(data: &[u8]) -> Option<String> {
+        // Records are "<length> <key>=<value>\n"
+        let text = String::from_utf8_lossy(data);
+        text.lines()
+            .filter_map(|record| record.split_once(' ')?.1.split_once('='))
+            .find(|(key, _)| *key == "path")
+            .map(|(_, value)| value.to_string())
+    }
 }
 
 impl ArchiveReader for TarArchive {
@@ -278,13 +441,14 @@ impl ArchiveReader for TarArchive {
     }
 
     fn read_entry(&mut self, name: &str) -> Result<Vec<u8>> {
-        let _entry = self
+        let entry = self
             .entries
             .get(name)
             .ok_or_else(|| Error::Argument(format!("Entry not found: {}", name)))?;
 
-        // Simplified: would extract here
-        Ok(Vec::new())
+        // Sizes and offsets were checked against the data while parsing
+        let start = entry.offset as usize;
+        Ok(self.data[start..start + entry.size as usize].to_vec())
     }
 
     fn entry_names(&self) -> Vec<&str> {
@@ -374,11 +538,13 @@ impl ArchiveReader for DirectoryArchive {
     }
 
     fn has_entry(&self, name: &str) -> bool {
-        let entry_path = self.path.join(name);
-        entry_path.exists()
+        is_safe_entry_name(name) && self.path.join(name).exists()
     }
 
     fn read_entry(&mut self, name: &str) -> Result<Vec<u8>> {
+        if !is_safe_entry_name(name) {
+            return Err(Error::Argument(format!("Entry outside archive: {}", name)));
+        }
         let entry_path = self.path.join(name);
         if !entry_path.exists() {
             return Err(Error::Argument(format!("Entry not found: {}", name)));
@@ -614,6 +780,62 @@ mod tests {
         assert!(result.is_ok() || result.is_err()); // Just checking it doesn't panic
     }
 
+    /// A ustar header block for an entry
+    fn tar_header(name: &str, prefix: &str, size: usize, type_flag: u8) -> Vec<u8> {
+        let mut header = vec![0u8; TAR_BLOCK];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+        header[156] = type_flag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+        header
+    }
+
+    #[test]
+    fn test_tar_archive() {
+        let mut data = Vec::new();
+        for (name, prefix, content) in [
+            ("a.txt", "", &b"first"[..]),
+            ("b.txt", "docs", b"second entry"),
+            ("../escape.txt", "", b"outside"),
+        ] {
+            data.extend(tar_header(name, prefix, content.len(), b'0'));
+            data.extend_from_slice(content);
+            data.resize(data.len().div_ceil(TAR_BLOCK) * TAR_BLOCK, 0);
+        }
+        data.extend(tar_header("dir/", "", 0, b'5'));
+        data.extend(vec![0u8; 2 * TAR_BLOCK]);
+
+        let mut archive = Archive::from_buffer(data).unwrap();
+        assert_eq!(archive.format(), ArchiveFormat::Tar);
+        assert_eq!(archive.entry_names(), ["a.txt", "docs/b.txt", "dir/"]);
+        assert_eq!(archive.read_entry("a.txt").unwrap(), b"first");
+        assert_eq!(archive.read_entry("docs/b.txt").unwrap(), b"second entry");
+        assert!(!archive.has_entry("../escape.txt"));
+    }
+
+    #[test]
+    fn test_directory_archive_rejects_traversal() {
+        let temp_dir = TempDir::new().unwrap();
+        let inner = temp_dir.path().join("inner");
+        fs::create_dir(&inner).unwrap();
+        fs::write(temp_dir.path().join("secret.txt"), b"secret").unwrap();
+
+        let mut archive = Archive::open_directory(&inner).unwrap();
+        assert!(!archive.has_entry("../secret.txt"));
+        assert!(archive.read_entry("../secret.txt").is_err());
+    }
+
+    #[test]
+    fn test_entry_names_with_drive_prefix_rejected() {
+        for name in ["C:\\x", "C:x", "c:/x", "../x", "a/../x", "/x", "\\x"] {
+            assert!(!is_safe_entry_name(name), "{name}");
+        }
+        for name in ["x", "docs/b.txt", "a\\b", "docs/c:d", "a..b"] {
+            assert!(is_safe_entry_name(name), "{name}");
+        }
+    }
+
     #[test]
     fn test_from_buffer_unknown() {
         let unknown_data = vec![0x00, 0x01, 0x02, 0x03];
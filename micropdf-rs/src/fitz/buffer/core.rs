@@ -3,6 +3,7 @@
 use crate::fitz::error::{Error, Result};
 use bytes::{BufMut, Bytes, BytesMut};
 use std::fmt;
+use std::io::{self, Write};
 use std::sync::Arc;
 
 /// A reference-counted buffer for efficient byte storage.
@@ -170,12 +171,25 @@ impl Buffer {
         self.mutable = None;
     }
 
-    /// Ensure we have a mutable buffer for writes.
+    /// Ensure we have a mutable buffer for writes that no clone shares.
+    ///
+    /// Clones share pending writes until one of them writes again; the
+    /// writer then takes its own copy, like `Arc::make_mut`.
     pub(super) fn ensure_mutable(&mut self) {
-        if self.mutable.is_none() {
-            self.mutable = Some(Arc::new(std::sync::Mutex::new(BytesMut::with_capacity(
-                256,
-            ))));
+        match &mut self.mutable {
+            None => {
+                self.mutable = Some(Arc::new(std::sync::Mutex::new(BytesMut::with_capacity(
+                    256,
+                ))));
+            }
+            Some(mutable) if Arc::strong_count(mutable) > 1 => {
+                let copy = match mutable.lock() {
+                    Ok(guard) => BytesMut::from(&guard[..]),
+                    Err(poisoned) => BytesMut::from(&poisoned.get_ref()[..]),
+                };
+                *mutable = Arc::new(std::sync::Mutex::new(copy));
+            }
+            Some(_) => {}
         }
     }
 
@@ -193,14 +207,17 @@ impl Buffer {
         self.mutable = None;
     }
 
-    /// Append a byte slice to the buffer.
-    pub fn append_data(&mut self, data: &[u8]) {
+    /// Append a byte slice to the buffer, returning the number of bytes
+    /// appended.
+    pub fn append_data(&mut self, data: &[u8]) -> usize {
         self.ensure_mutable();
         if let Some(ref mutable) = self.mutable {
             if let Ok(mut guard) = mutable.lock() {
                 guard.extend_from_slice(data);
+                return data.len();
             }
         }
+        0
     }
 
     /// Append a single byte to the buffer.
@@ -366,6 +383,21 @@ impl Buffer {
     }
 }
 
+/// Writes append to the buffer; a clone written through keeps its own
+/// copy of the data.
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.append_data(buf) {
+            0 if !buf.is_empty() => Err(io::Error::other("buffer lock poisoned")),
+            n => Ok(n),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 impl Default for Buffer {
     fn default() -> Self {
         Self::new(0)
@@ -456,6 +488,24 @@ mod tests {
         assert_eq!(buf.to_vec(), b"Hello World");
     }
 
+    #[test]
+    fn test_buffer_write_is_copy_on_write() {
+        let mut buf = Buffer::new(0);
+        assert_eq!(buf.append_data(b"Hello"), 5);
+        let name = "World";
+        write!(buf, ", {name}").unwrap();
+        assert_eq!(buf.to_vec(), b"Hello, World");
+
+        // Writing to a clone leaves the original alone, and the other way
+        let mut clone = buf.clone();
+        clone.write_all(b"!").unwrap();
+        assert_eq!(buf.to_vec(), b"Hello, World");
+        assert_eq!(clone.to_vec(), b"Hello, World!");
+        buf.write_all(b"?").unwrap();
+        assert_eq!(buf.to_vec(), b"Hello, World?");
+        assert_eq!(clone.to_vec(), b"Hello, World!");
+    }
+
     #[test]
     fn test_buffer_slice() {
         let buf = Buffer::from_slice(b"Hello, World!");
@@ -2,9 +2,15 @@
 //!
 //! Cookies provide a way to communicate between the application and the document
 //! processing routines, allowing for cancellation and progress reporting.
+//! A cookie can also bound the work done: a step budget or a deadline
+//! makes it ask for an abort once spent.
 
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Stored in place of a step budget or deadline when there is none
+const UNLIMITED: u64 = u64::MAX;
 
 /// Cookie for tracking progress and handling cancellation
 #[derive(Clone)]
@@ -19,6 +25,14 @@ pub struct Cookie {
     errors: Arc<AtomicI32>,
     /// Incomplete flag - set if operation was incomplete
     incomplete: Arc<AtomicBool>,
+    /// Steps taken, counted by [`Cookie::step`]
+    steps: Arc<AtomicU64>,
+    /// Steps allowed before aborting
+    max_steps: Arc<AtomicU64>,
+    /// Deadline, in nanoseconds after `origin`
+    deadline: Arc<AtomicU64>,
+    /// Time the cookie was created, shared by its clones
+    origin: Instant,
 }
 
 impl Cookie {
@@ -30,12 +44,51 @@ impl Cookie {
             progress_max: Arc::new(AtomicI32::new(0)),
             errors: Arc::new(AtomicI32::new(0)),
             incomplete: Arc::new(AtomicBool::new(false)),
+            steps: Arc::new(AtomicU64::new(0)),
+            max_steps: Arc::new(AtomicU64::new(UNLIMITED)),
+            deadline: Arc::new(AtomicU64::new(UNLIMITED)),
+            origin: Instant::now(),
         }
     }
 
-    /// Check if operation should be aborted
+    /// Check if operation should be aborted: abort was requested, the
+    /// step budget is spent or the deadline has passed
     pub fn should_abort(&self) -> bool {
-        self.abort.load(Ordering::Relaxed)
+        if self.abort.load(Ordering::Relaxed) {
+            return true;
+        }
+        if self.steps.load(Ordering::Relaxed) > self.max_steps.load(Ordering::Relaxed) {
+            return true;
+        }
+        let deadline = self.deadline.load(Ordering::Relaxed);
+        deadline != UNLIMITED && self.origin.elapsed().as_nanos() >= u128::from(deadline)
+    }
+
+    /// Count one unit of work, such as a content stream operator
+    pub fn step(&self) {
+        self.steps.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Steps counted so far
+    pub fn steps(&self) -> u64 {
+        self.steps.load(Ordering::Relaxed)
+    }
+
+    /// Ask for an abort once more than `max_steps` steps are taken, or
+    /// never with `None`
+    pub fn set_step_budget(&self, max_steps: Option<u64>) {
+        self.max_steps
+            .store(max_steps.unwrap_or(UNLIMITED), Ordering::Relaxed);
+    }
+
+    /// Ask for an abort once `timeout` from now has passed, or never with
+    /// `None`
+    pub fn set_timeout(&self, timeout: Option<Duration>) {
+        let deadline = timeout.map_or(UNLIMITED, |timeout| {
+            let at = self.origin.elapsed().saturating_add(timeout).as_nanos();
+            u64::try_from(at).unwrap_or(UNLIMITED - 1)
+        });
+        self.deadline.store(deadline, Ordering::Relaxed);
     }
 
     /// Request abortion of current operation
@@ -115,6 +168,7 @@ impl Cookie {
         self.progress_max.store(0, Ordering::Relaxed);
         self.errors.store(0, Ordering::Relaxed);
         self.incomplete.store(false, Ordering::Relaxed);
+        self.steps.store(0, Ordering::Relaxed);
     }
 }
 
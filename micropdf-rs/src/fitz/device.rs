@@ -79,6 +79,69 @@ impl BlendMode {
             Self::Luminosity => "Luminosity",
         }
     }
+
+    /// Whether the mode blends each color component on its own
+    pub fn is_separable(&self) -> bool {
+        (*self as u8) < Self::Hue as u8
+    }
+
+    /// Blend one component of a source color over a backdrop, both
+    /// straight (not premultiplied) values in 0..=1
+    ///
+    /// Non-separable modes are not implemented and return the source, as
+    /// for Normal.
+    pub fn blend(&self, backdrop: f32, source: f32) -> f32 {
+        let (b, s) = (backdrop, source);
+        let hard_light = |b: f32, s: f32| {
+            if s <= 0.5 {
+                b * 2.0 * s
+            } else {
+                let s = 2.0 * s - 1.0;
+                b + s - b * s
+            }
+        };
+        match self {
+            Self::Multiply => b * s,
+            Self::Screen => b + s - b * s,
+            Self::Overlay => hard_light(s, b),
+            Self::Darken => b.min(s),
+            Self::Lighten => b.max(s),
+            Self::ColorDodge => {
+                if b == 0.0 {
+                    0.0
+                } else if s >= 1.0 {
+                    1.0
+                } else {
+                    (b / (1.0 - s)).min(1.0)
+                }
+            }
+            Self::ColorBurn => {
+                if b >= 1.0 {
+                    1.0
+                } else if s <= 0.0 {
+                    0.0
+                } else {
+                    1.0 - ((1.0 - b) / s).min(1.0)
+                }
+            }
+            Self::HardLight => hard_light(b, s),
+            Self::SoftLight => {
+                if s <= 0.5 {
+                    b - (1.0 - 2.0 * s) * b * (1.0 - b)
+                } else {
+                    let d = if b <= 0.25 {
+                        ((16.0 * b - 12.0) * b + 4.0) * b
+                    } else {
+                        b.sqrt()
+                    };
+                    b + (2.0 * s - 1.0) * (d - b)
+                }
+            }
+            Self::Difference => (b - s).abs(),
+            Self::Exclusion => b + s - 2.0 * b * s,
+            _ => s,
+        }
+    }
 }
 
 /// Container stack type for tracking clips/masks/groups
@@ -592,6 +655,19 @@ mod tests {
         assert_eq!(BlendMode::default(), BlendMode::Normal);
     }
 
+    #[test]
+    fn test_blend_formulas() {
+        assert_eq!(BlendMode::Multiply.blend(0.5, 0.5), 0.25);
+        assert_eq!(BlendMode::Screen.blend(0.5, 0.5), 0.75);
+        assert_eq!(BlendMode::Overlay.blend(0.25, 1.0), 0.5);
+        assert_eq!(BlendMode::Darken.blend(0.25, 0.75), 0.25);
+        assert_eq!(BlendMode::Lighten.blend(0.25, 0.75), 0.75);
+        assert_eq!(BlendMode::Difference.blend(0.25, 0.75), 0.5);
+        assert_eq!(BlendMode::Normal.blend(0.25, 0.75), 0.75);
+        assert!(BlendMode::Exclusion.is_separable());
+        assert!(!BlendMode::Hue.is_separable());
+    }
+
     #[test]
     fn test_null_device_fill_path() {
         let mut device = NullDevice;
@@ -8,6 +8,7 @@ use crate::fitz::geometry::{Matrix, Rect};
 use crate::fitz::image::Image;
 use crate::fitz::path::{Path, StrokeState};
 use crate::fitz::text::Text;
+use std::sync::{Arc, Mutex};
 
 /// Display list command
 #[derive(Clone)]
@@ -119,6 +120,9 @@ enum Command {
 pub struct DisplayList {
     mediabox: Rect,
     commands: Vec<Command>,
+    /// Bounds of what each command paints, before the run transform;
+    /// infinite for commands that always run
+    bounds: Vec<Rect>,
 }
 
 impl DisplayList {
@@ -127,6 +131,7 @@ impl DisplayList {
         Self {
             mediabox,
             commands: Vec::new(),
+            bounds: Vec::new(),
         }
     }
 
@@ -145,184 +150,275 @@ impl DisplayList {
         self.commands.is_empty()
     }
 
+    /// Record a command along with the bounds it paints
+    fn push(&mut self, cmd: Command) {
+        self.bounds.push(command_bounds(&cmd));
+        self.commands.push(cmd);
+    }
+
     /// Run the display list through a device
     pub fn run(&self, device: &mut dyn Device, ctm: &Matrix, scissor: Rect) {
         for cmd in &self.commands {
-            // Check if command is within scissor bounds (simplified)
+            replay(cmd, device, ctm, scissor);
+        }
+    }
+
+    /// Run only the commands that paint within `clip`, in device space
+    ///
+    /// Commands whose bounds under `ctm` miss `clip` are skipped; clips,
+    /// groups, masks and everything inside tiles always run. Devices still
+    /// see the whole of each command that runs, so they should clip to
+    /// `clip` themselves.
+    pub fn run_clipped(&self, device: &mut dyn Device, ctm: &Matrix, clip: Rect) {
+        let mut tile_depth = 0usize;
+        for (cmd, bounds) in self.commands.iter().zip(&self.bounds) {
             match cmd {
-                Command::FillPath {
-                    path,
-                    even_odd,
-                    ctm: cmd_ctm,
-                    colorspace,
-                    color,
-                    alpha,
-                } => {
-                    let final_ctm = cmd_ctm.concat(ctm);
-                    device.fill_path(path, *even_odd, &final_ctm, colorspace, color, *alpha);
-                }
-                Command::StrokePath {
-                    path,
-                    stroke,
-                    ctm: cmd_ctm,
-                    colorspace,
-                    color,
-                    alpha,
-                } => {
-                    let final_ctm = cmd_ctm.concat(ctm);
-                    device.stroke_path(path, stroke, &final_ctm, colorspace, color, *alpha);
-                }
-                Command::ClipPath {
-                    path,
-                    even_odd,
-                    ctm: cmd_ctm,
-                    scissor: cmd_scissor,
-                } => {
-                    let final_ctm = cmd_ctm.concat(ctm);
-                    let final_scissor = scissor.intersect(cmd_scissor);
-                    device.clip_path(path, *even_odd, &final_ctm, final_scissor);
-                }
-                Command::ClipStrokePath {
-                    path,
-                    stroke,
-                    ctm: cmd_ctm,
-                    scissor: cmd_scissor,
-                } => {
-                    let final_ctm = cmd_ctm.concat(ctm);
-                    let final_scissor = scissor.intersect(cmd_scissor);
-                    device.clip_stroke_path(path, stroke, &final_ctm, final_scissor);
-                }
-                Command::FillText {
-                    text,
-                    ctm: cmd_ctm,
-                    colorspace,
-                    color,
-                    alpha,
-                } => {
-                    let final_ctm = cmd_ctm.concat(ctm);
-                    device.fill_text(text, &final_ctm, colorspace, color, *alpha);
-                }
-                Command::StrokeText {
-                    text,
-                    stroke,
-                    ctm: cmd_ctm,
-                    colorspace,
-                    color,
-                    alpha,
-                } => {
-                    let final_ctm = cmd_ctm.concat(ctm);
-                    device.stroke_text(text, stroke, &final_ctm, colorspace, color, *alpha);
-                }
-                Command::ClipText {
-                    text,
-                    ctm: cmd_ctm,
-                    scissor: cmd_scissor,
-                } => {
-                    let final_ctm = cmd_ctm.concat(ctm);
-                    let final_scissor = scissor.intersect(cmd_scissor);
-                    device.clip_text(text, &final_ctm, final_scissor);
-                }
-                Command::ClipStrokeText {
-                    text,
-                    stroke,
-                    ctm: cmd_ctm,
-                    scissor: cmd_scissor,
-                } => {
-                    let final_ctm = cmd_ctm.concat(ctm);
-                    let final_scissor = scissor.intersect(cmd_scissor);
-                    device.clip_stroke_text(text, stroke, &final_ctm, final_scissor);
-                }
-                Command::IgnoreText { text, ctm: cmd_ctm } => {
-                    let final_ctm = cmd_ctm.concat(ctm);
-                    device.ignore_text(text, &final_ctm);
-                }
-                Command::FillImage {
-                    image,
-                    ctm: cmd_ctm,
-                    alpha,
-                } => {
-                    let final_ctm = cmd_ctm.concat(ctm);
-                    device.fill_image(image, &final_ctm, *alpha);
-                }
-                Command::FillImageMask {
-                    image,
-                    ctm: cmd_ctm,
-                    colorspace,
-                    color,
-                    alpha,
-                } => {
-                    let final_ctm = cmd_ctm.concat(ctm);
-                    device.fill_image_mask(image, &final_ctm, colorspace, color, *alpha);
-                }
-                Command::ClipImageMask {
-                    image,
-                    ctm: cmd_ctm,
-                    scissor: cmd_scissor,
-                } => {
-                    let final_ctm = cmd_ctm.concat(ctm);
-                    let final_scissor = scissor.intersect(cmd_scissor);
-                    device.clip_image_mask(image, &final_ctm, final_scissor);
-                }
-                Command::PopClip => {
-                    device.pop_clip();
-                }
-                Command::BeginMask {
-                    area,
-                    luminosity,
-                    colorspace,
-                    color,
-                } => {
-                    device.begin_mask(*area, *luminosity, colorspace, color);
-                }
-                Command::EndMask => {
-                    device.end_mask();
-                }
-                Command::BeginGroup {
-                    area,
-                    colorspace,
-                    isolated,
-                    knockout,
-                    blendmode,
-                    alpha,
-                } => {
-                    device.begin_group(
-                        *area,
-                        colorspace.as_ref(),
-                        *isolated,
-                        *knockout,
-                        *blendmode,
-                        *alpha,
-                    );
-                }
-                Command::EndGroup => {
-                    device.end_group();
-                }
-                Command::BeginTile {
-                    area,
-                    view,
-                    xstep,
-                    ystep,
-                    ctm: cmd_ctm,
-                } => {
-                    let final_ctm = cmd_ctm.concat(ctm);
-                    device.begin_tile(*area, *view, *xstep, *ystep, &final_ctm);
-                }
-                Command::EndTile => {
-                    device.end_tile();
-                }
+                Command::BeginTile { .. } => tile_depth += 1,
+                Command::EndTile => tile_depth = tile_depth.saturating_sub(1),
+                _ => {}
+            }
+            // A pixel of slack for anti-aliased edges
+            if tile_depth == 0
+                && !bounds.is_infinite()
+                && !bounds.transform(ctm).expand(1.0).intersects(&clip)
+            {
+                continue;
             }
+            replay(cmd, device, ctm, clip);
         }
     }
 
     /// Clear all commands
     pub fn clear(&mut self) {
         self.commands.clear();
+        self.bounds.clear();
+    }
+
+    /// Move all commands of `other` to the end of this list
+    pub fn append(&mut self, other: &mut DisplayList) {
+        self.commands.append(&mut other.commands);
+        self.bounds.append(&mut other.bounds);
+    }
+
+    /// Rough memory footprint in bytes, for cache accounting
+    pub fn size_estimate(&self) -> usize {
+        let heap: usize = self
+            .commands
+            .iter()
+            .map(|cmd| match cmd {
+                Command::FillPath { path, color, .. } | Command::StrokePath { path, color, .. } => {
+                    path.len() * std::mem::size_of::<crate::fitz::path::PathElement>()
+                        + color.len() * std::mem::size_of::<f32>()
+                }
+                Command::ClipPath { path, .. } | Command::ClipStrokePath { path, .. } => {
+                    path.len() * std::mem::size_of::<crate::fitz::path::PathElement>()
+                }
+                _ => 0,
+            })
+            .sum();
+        std::mem::size_of::<Self>()
+            + self.commands.len() * (std::mem::size_of::<Command>() + std::mem::size_of::<Rect>())
+            + heap
+    }
+}
+
+/// Bounds of what `cmd` paints under its own transform, or infinite for
+/// commands that do not paint by themselves
+fn command_bounds(cmd: &Command) -> Rect {
+    match cmd {
+        Command::FillPath { path, ctm, .. } => path.bounds().transform(ctm),
+        Command::StrokePath {
+            path, stroke, ctm, ..
+        } => {
+            // Miter joins reach furthest from the path
+            let reach = stroke.linewidth * stroke.miterlimit.max(1.0) / 2.0;
+            path.bounds().expand(reach).transform(ctm)
+        }
+        Command::FillText { text, ctm, .. } => text.bounds(None, ctm),
+        Command::StrokeText {
+            text, stroke, ctm, ..
+        } => text.bounds(Some(stroke), ctm),
+        // Images fill the unit square
+        Command::FillImage { ctm, .. } | Command::FillImageMask { ctm, .. } => {
+            Rect::UNIT.transform(ctm)
+        }
+        _ => Rect::INFINITE,
+    }
+}
+
+/// Replay one command into `device`, after `ctm`
+fn replay(cmd: &Command, device: &mut dyn Device, ctm: &Matrix, scissor: Rect) {
+    match cmd {
+        Command::FillPath {
+            path,
+            even_odd,
+            ctm: cmd_ctm,
+            colorspace,
+            color,
+            alpha,
+        } => {
+            let final_ctm = cmd_ctm.concat(ctm);
+            device.fill_path(path, *even_odd, &final_ctm, colorspace, color, *alpha);
+        }
+        Command::StrokePath {
+            path,
+            stroke,
+            ctm: cmd_ctm,
+            colorspace,
+            color,
+            alpha,
+        } => {
+            let final_ctm = cmd_ctm.concat(ctm);
+            device.stroke_path(path, stroke, &final_ctm, colorspace, color, *alpha);
+        }
+        Command::ClipPath {
+            path,
+            even_odd,
+            ctm: cmd_ctm,
+            scissor: cmd_scissor,
+        } => {
+            let final_ctm = cmd_ctm.concat(ctm);
+            let final_scissor = scissor.intersect(cmd_scissor);
+            device.clip_path(path, *even_odd, &final_ctm, final_scissor);
+        }
+        Command::ClipStrokePath {
+            path,
+            stroke,
+            ctm: cmd_ctm,
+            scissor: cmd_scissor,
+        } => {
+            let final_ctm = cmd_ctm.concat(ctm);
+            let final_scissor = scissor.intersect(cmd_scissor);
+            device.clip_stroke_path(path, stroke, &final_ctm, final_scissor);
+        }
+        Command::FillText {
+            text,
+            ctm: cmd_ctm,
+            colorspace,
+            color,
+            alpha,
+        } => {
+            let final_ctm = cmd_ctm.concat(ctm);
+            device.fill_text(text, &final_ctm, colorspace, color, *alpha);
+        }
+        Command::StrokeText {
+            text,
+            stroke,
+            ctm: cmd_ctm,
+            colorspace,
+            color,
+            alpha,
+        } => {
+            let final_ctm = cmd_ctm.concat(ctm);
+            device.stroke_text(text, stroke, &final_ctm, colorspace, color, *alpha);
+        }
+        Command::ClipText {
+            text,
+            ctm: cmd_ctm,
+            scissor: cmd_scissor,
+        } => {
+            let final_ctm = cmd_ctm.concat(ctm);
+            let final_scissor = scissor.intersect(cmd_scissor);
+            device.clip_text(text, &final_ctm, final_scissor);
+        }
+        Command::ClipStrokeText {
+            text,
+            stroke,
+            ctm: cmd_ctm,
+            scissor: cmd_scissor,
+        } => {
+            let final_ctm = cmd_ctm.concat(ctm);
+            let final_scissor = scissor.intersect(cmd_scissor);
+            device.clip_stroke_text(text, stroke, &final_ctm, final_scissor);
+        }
+        Command::IgnoreText { text, ctm: cmd_ctm } => {
+            let final_ctm = cmd_ctm.concat(ctm);
+            device.ignore_text(text, &final_ctm);
+        }
+        Command::FillImage {
+            image,
+            ctm: cmd_ctm,
+            alpha,
+        } => {
+            let final_ctm = cmd_ctm.concat(ctm);
+            device.fill_image(image, &final_ctm, *alpha);
+        }
+        Command::FillImageMask {
+            image,
+            ctm: cmd_ctm,
+            colorspace,
+            color,
+            alpha,
+        } => {
+            let final_ctm = cmd_ctm.concat(ctm);
+            device.fill_image_mask(image, &final_ctm, colorspace, color, *alpha);
+        }
+        Command::ClipImageMask {
+            image,
+            ctm: cmd_ctm,
+            scissor: cmd_scissor,
+        } => {
+            let final_ctm = cmd_ctm.concat(ctm);
+            let final_scissor = scissor.intersect(cmd_scissor);
+            device.clip_image_mask(image, &final_ctm, final_scissor);
+        }
+        Command::PopClip => {
+            device.pop_clip();
+        }
+        Command::BeginMask {
+            area,
+            luminosity,
+            colorspace,
+            color,
+        } => {
+            device.begin_mask(*area, *luminosity, colorspace, color);
+        }
+        Command::EndMask => {
+            device.end_mask();
+        }
+        Command::BeginGroup {
+            area,
+            colorspace,
+            isolated,
+            knockout,
+            blendmode,
+            alpha,
+        } => {
+            device.begin_group(
+                *area,
+                colorspace.as_ref(),
+                *isolated,
+                *knockout,
+                *blendmode,
+                *alpha,
+            );
+        }
+        Command::EndGroup => {
+            device.end_group();
+        }
+        Command::BeginTile {
+            area,
+            view,
+            xstep,
+            ystep,
+            ctm: cmd_ctm,
+        } => {
+            let final_ctm = cmd_ctm.concat(ctm);
+            device.begin_tile(*area, *view, *xstep, *ystep, &final_ctm);
+        }
+        Command::EndTile => {
+            device.end_tile();
+        }
     }
 }
 
 /// List device - records drawing operations to a display list
+///
+/// A device made with [`ListDevice::for_list`] moves what it recorded into
+/// the shared list when closed or dropped.
 pub struct ListDevice {
     list: DisplayList,
+    target: Option<Arc<Mutex<DisplayList>>>,
 }
 
 impl ListDevice {
@@ -330,12 +426,36 @@ impl ListDevice {
     pub fn new(mediabox: Rect) -> Self {
         Self {
             list: DisplayList::new(mediabox),
+            target: None,
+        }
+    }
+
+    /// Create a list device that records into a shared display list
+    pub fn for_list(target: Arc<Mutex<DisplayList>>) -> Self {
+        let mediabox = target
+            .lock()
+            .map(|list| list.mediabox())
+            .unwrap_or_else(|e| e.into_inner().mediabox());
+        Self {
+            list: DisplayList::new(mediabox),
+            target: Some(target),
+        }
+    }
+
+    /// Move recorded commands into the shared list, if any
+    fn flush(&mut self) {
+        if let Some(target) = &self.target {
+            if let Ok(mut target) = target.lock() {
+                target.append(&mut self.list);
+            }
         }
     }
 
     /// Get the display list
-    pub fn into_display_list(self) -> DisplayList {
-        self.list
+    pub fn into_display_list(mut self) -> DisplayList {
+        self.target = None;
+        let mediabox = self.list.mediabox();
+        std::mem::replace(&mut self.list, DisplayList::new(mediabox))
     }
 
     /// Get a reference to the display list
@@ -359,7 +479,7 @@ impl Device for ListDevice {
         color: &[f32],
         alpha: f32,
     ) {
-        self.list.commands.push(Command::FillPath {
+        self.list.push(Command::FillPath {
             path: path.clone(),
             even_odd,
             ctm: *ctm,
@@ -378,7 +498,7 @@ impl Device for ListDevice {
         color: &[f32],
         alpha: f32,
     ) {
-        self.list.commands.push(Command::StrokePath {
+        self.list.push(Command::StrokePath {
             path: path.clone(),
             stroke: stroke.clone(),
             ctm: *ctm,
@@ -389,7 +509,7 @@ impl Device for ListDevice {
     }
 
     fn clip_path(&mut self, path: &Path, even_odd: bool, ctm: &Matrix, scissor: Rect) {
-        self.list.commands.push(Command::ClipPath {
+        self.list.push(Command::ClipPath {
             path: path.clone(),
             even_odd,
             ctm: *ctm,
@@ -398,7 +518,7 @@ impl Device for ListDevice {
     }
 
     fn clip_stroke_path(&mut self, path: &Path, stroke: &StrokeState, ctm: &Matrix, scissor: Rect) {
-        self.list.commands.push(Command::ClipStrokePath {
+        self.list.push(Command::ClipStrokePath {
             path: path.clone(),
             stroke: stroke.clone(),
             ctm: *ctm,
@@ -414,7 +534,7 @@ impl Device for ListDevice {
         color: &[f32],
         alpha: f32,
     ) {
-        self.list.commands.push(Command::FillText {
+        self.list.push(Command::FillText {
             text: text.clone(),
             ctm: *ctm,
             colorspace: colorspace.clone(),
@@ -432,7 +552,7 @@ impl Device for ListDevice {
         color: &[f32],
         alpha: f32,
     ) {
-        self.list.commands.push(Command::StrokeText {
+        self.list.push(Command::StrokeText {
             text: text.clone(),
             stroke: stroke.clone(),
             ctm: *ctm,
@@ -443,7 +563,7 @@ impl Device for ListDevice {
     }
 
     fn clip_text(&mut self, text: &Text, ctm: &Matrix, scissor: Rect) {
-        self.list.commands.push(Command::ClipText {
+        self.list.push(Command::ClipText {
             text: text.clone(),
             ctm: *ctm,
             scissor,
@@ -451,7 +571,7 @@ impl Device for ListDevice {
     }
 
     fn clip_stroke_text(&mut self, text: &Text, stroke: &StrokeState, ctm: &Matrix, scissor: Rect) {
-        self.list.commands.push(Command::ClipStrokeText {
+        self.list.push(Command::ClipStrokeText {
             text: text.clone(),
             stroke: stroke.clone(),
             ctm: *ctm,
@@ -460,14 +580,14 @@ impl Device for ListDevice {
     }
 
     fn ignore_text(&mut self, text: &Text, ctm: &Matrix) {
-        self.list.commands.push(Command::IgnoreText {
+        self.list.push(Command::IgnoreText {
             text: text.clone(),
             ctm: *ctm,
         });
     }
 
     fn fill_image(&mut self, image: &Image, ctm: &Matrix, alpha: f32) {
-        self.list.commands.push(Command::FillImage {
+        self.list.push(Command::FillImage {
             image: image.clone(),
             ctm: *ctm,
             alpha,
@@ -482,7 +602,7 @@ impl Device for ListDevice {
         color: &[f32],
         alpha: f32,
     ) {
-        self.list.commands.push(Command::FillImageMask {
+        self.list.push(Command::FillImageMask {
             image: image.clone(),
             ctm: *ctm,
             colorspace: colorspace.clone(),
@@ -492,7 +612,7 @@ impl Device for ListDevice {
     }
 
     fn clip_image_mask(&mut self, image: &Image, ctm: &Matrix, scissor: Rect) {
-        self.list.commands.push(Command::ClipImageMask {
+        self.list.push(Command::ClipImageMask {
             image: image.clone(),
             ctm: *ctm,
             scissor,
@@ -500,11 +620,11 @@ impl Device for ListDevice {
     }
 
     fn pop_clip(&mut self) {
-        self.list.commands.push(Command::PopClip);
+        self.list.push(Command::PopClip);
     }
 
     fn begin_mask(&mut self, area: Rect, luminosity: bool, colorspace: &Colorspace, color: &[f32]) {
-        self.list.commands.push(Command::BeginMask {
+        self.list.push(Command::BeginMask {
             area,
             luminosity,
             colorspace: colorspace.clone(),
@@ -513,7 +633,7 @@ impl Device for ListDevice {
     }
 
     fn end_mask(&mut self) {
-        self.list.commands.push(Command::EndMask);
+        self.list.push(Command::EndMask);
     }
 
     fn begin_group(
@@ -525,7 +645,7 @@ impl Device for ListDevice {
         blendmode: BlendMode,
         alpha: f32,
     ) {
-        self.list.commands.push(Command::BeginGroup {
+        self.list.push(Command::BeginGroup {
             area,
             colorspace: colorspace.cloned(),
             isolated,
@@ -536,11 +656,11 @@ impl Device for ListDevice {
     }
 
     fn end_group(&mut self) {
-        self.list.commands.push(Command::EndGroup);
+        self.list.push(Command::EndGroup);
     }
 
     fn begin_tile(&mut self, area: Rect, view: Rect, xstep: f32, ystep: f32, ctm: &Matrix) -> i32 {
-        self.list.commands.push(Command::BeginTile {
+        self.list.push(Command::BeginTile {
             area,
             view,
             xstep,
@@ -551,7 +671,17 @@ impl Device for ListDevice {
     }
 
     fn end_tile(&mut self) {
-        self.list.commands.push(Command::EndTile);
+        self.list.push(Command::EndTile);
+    }
+
+    fn close(&mut self) {
+        self.flush();
+    }
+}
+
+impl Drop for ListDevice {
+    fn drop(&mut self) {
+        self.flush();
     }
 }
 
@@ -783,4 +913,41 @@ mod tests {
 
         assert_eq!(device.display_list().len(), 2);
     }
+
+    #[test]
+    fn test_replay_concatenates_ctm() {
+        use crate::fitz::geometry::Point;
+
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.line_to(Point::new(10.0, 10.0));
+        let cs = Colorspace::device_rgb();
+
+        let shared = Arc::new(Mutex::new(DisplayList::new(Rect::new(
+            0.0, 0.0, 100.0, 100.0,
+        ))));
+        let mut recorder = ListDevice::for_list(shared.clone());
+        let offset = Matrix::translate(5.0, 0.0);
+        recorder.fill_path(&path, false, &offset, &cs, &[1.0, 0.0, 0.0], 1.0);
+        recorder.fill_path(&path, true, &Matrix::IDENTITY, &cs, &[0.0, 0.0, 1.0], 1.0);
+        assert!(shared.lock().unwrap().is_empty());
+        drop(recorder);
+
+        let list = shared.lock().unwrap().clone();
+        assert_eq!(list.len(), 2);
+
+        let zoom = Matrix::scale(2.0, 2.0);
+        let mut replay = ListDevice::new(list.mediabox());
+        list.run(&mut replay, &zoom, Rect::INFINITE);
+        let ctms: Vec<Matrix> = replay
+            .display_list()
+            .commands
+            .iter()
+            .map(|cmd| match cmd {
+                Command::FillPath { ctm, .. } => *ctm,
+                _ => panic!("unexpected command"),
+            })
+            .collect();
+        assert_eq!(ctms, [offset.concat(&zoom), zoom]);
+    }
 }
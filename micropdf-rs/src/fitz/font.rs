@@ -3,6 +3,8 @@
 //! Provides comprehensive font support for various PDF font formats.
 
 use crate::fitz::error::{Error, Result};
+use crate::fitz::geometry::Point;
+use crate::fitz::path::Path;
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -258,6 +260,8 @@ pub struct Font {
     widths: HashMap<u16, f32>,
     /// Font data (embedded font file)
     font_data: Option<Vec<u8>>,
+    /// Face index within a font collection
+    face_index: u32,
     /// Encoding name
     encoding: Option<String>,
 }
@@ -276,6 +280,7 @@ impl Font {
             charmap: Arc::new(CharMap::new()),
             widths: HashMap::new(),
             font_data: None,
+            face_index: 0,
             encoding: None,
         }
     }
@@ -443,17 +448,21 @@ impl Font {
     }
 
     /// Create font from font data
-    pub fn from_data(name: &str, data: &[u8], _index: usize) -> Result<Self> {
+    pub fn from_data(name: &str, data: &[u8], index: usize) -> Result<Self> {
         if data.is_empty() {
             return Err(Error::Argument("Empty font data".into()));
         }
 
         let mut font = Font::new(name);
         font.set_font_data(data.to_vec());
+        font.face_index = index as u32;
 
-        // Try to infer font type from data
-        // This is a stub - real implementation would parse font file
-        font.font_type = FontType::TrueType;
+        // OpenType fonts with CFF outlines are tagged 'OTTO'
+        font.font_type = if data.starts_with(b"OTTO") {
+            FontType::OpenType
+        } else {
+            FontType::TrueType
+        };
 
         Ok(font)
     }
@@ -484,10 +493,54 @@ impl Font {
         )
     }
 
-    /// Get glyph outline path (stub)
-    pub fn outline_glyph(&self, _gid: u16) -> crate::fitz::path::Path {
-        // Stub implementation - would need actual glyph outline extraction
-        crate::fitz::path::Path::new()
+    /// Parse the embedded font program, if any
+    fn face(&self) -> Option<ttf_parser::Face<'_>> {
+        ttf_parser::Face::parse(self.font_data.as_deref()?, self.face_index).ok()
+    }
+
+    /// Font design units per em of the embedded font program
+    pub fn units_per_em(&self) -> Option<u16> {
+        self.face().map(|face| face.units_per_em())
+    }
+
+    /// Get glyph outline path in font units
+    ///
+    /// Reads TrueType outlines, resolving composite glyphs through their
+    /// component transforms, and CFF outlines from the embedded font
+    /// program. Fonts without data and glyphs without an outline (such as
+    /// the space) give an empty path.
+    pub fn outline_glyph(&self, gid: u16) -> Path {
+        let mut builder = OutlineBuilder(Path::new());
+        if let Some(face) = self.face() {
+            face.outline_glyph(ttf_parser::GlyphId(gid), &mut builder);
+        }
+        builder.0
+    }
+}
+
+/// Collects outline callbacks from the font parser into a path
+struct OutlineBuilder(Path);
+
+impl ttf_parser::OutlineBuilder for OutlineBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.move_to(Point::new(x, y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.line_to(Point::new(x, y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.0.quad_to(Point::new(x1, y1), Point::new(x, y));
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.0
+            .curve_to(Point::new(x1, y1), Point::new(x2, y2), Point::new(x, y));
+    }
+
+    fn close(&mut self) {
+        self.0.close();
     }
 }
 
@@ -571,8 +624,112 @@ pub mod standard_fonts {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::fitz::geometry::Rect;
+    use crate::fitz::path::PathElement;
+
+    /// A minimal TrueType font with 1000 units per em: glyph 1 is a
+    /// 100-unit square at (100, 0), glyph 2 a composite of glyph 1 and a
+    /// copy scaled by half and moved right by 300
+    pub(crate) fn sample_truetype() -> Vec<u8> {
+        fn be16(out: &mut Vec<u8>, values: &[i32]) {
+            for &v in values {
+                out.extend_from_slice(&(v as u16).to_be_bytes());
+            }
+        }
+
+        let mut square = Vec::new();
+        be16(&mut square, &[1, 100, 0, 200, 100, 3, 0]);
+        square.extend_from_slice(&[0x01; 4]);
+        be16(&mut square, &[100, 100, 0, -100, 0, 0, 100, 0]);
+
+        let mut composite = Vec::new();
+        be16(&mut composite, &[-1, 100, 0, 400, 100]);
+        be16(&mut composite, &[0x0023, 1, 0, 0]);
+        be16(&mut composite, &[0x000B, 1, 300, 0, 0x2000]);
+
+        let glyf = [square.clone(), composite.clone()].concat();
+        let mut loca = Vec::new();
+        for offset in [0, 0, square.len(), glyf.len()] {
+            loca.extend_from_slice(&(offset as u32).to_be_bytes());
+        }
+
+        let mut head = Vec::new();
+        be16(&mut head, &[1, 0, 1, 0, 0, 0, 0x5F0F, 0x3CF5, 0, 1000]);
+        head.extend_from_slice(&[0; 16]);
+        be16(&mut head, &[0, 0, 400, 100, 0, 8, 2, 1, 0]);
+
+        let mut hhea = Vec::new();
+        be16(&mut hhea, &[1, 0, 800, -200, 0, 500, 0, 0, 400, 1, 0, 0]);
+        be16(&mut hhea, &[0, 0, 0, 0, 0, 3]);
+
+        let mut hmtx = Vec::new();
+        be16(&mut hmtx, &[500, 0, 300, 100, 500, 100]);
+
+        let mut maxp = Vec::new();
+        be16(&mut maxp, &[0, 0x5000, 3]);
+
+        let tables: [(&[u8; 4], Vec<u8>); 6] = [
+            (b"glyf", glyf),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"loca", loca),
+            (b"maxp", maxp),
+        ];
+
+        let mut font = Vec::new();
+        be16(&mut font, &[1, 0, tables.len() as i32, 64, 2, 32]);
+        let mut offset = 12 + 16 * tables.len();
+        let mut data = Vec::new();
+        for (tag, table) in &tables {
+            font.extend_from_slice(*tag);
+            font.extend_from_slice(&[0; 4]);
+            font.extend_from_slice(&(offset as u32).to_be_bytes());
+            font.extend_from_slice(&(table.len() as u32).to_be_bytes());
+            data.extend_from_slice(table);
+            while data.len() % 4 != 0 {
+                data.push(0);
+            }
+            offset = 12 + 16 * tables.len() + data.len();
+        }
+        font.extend_from_slice(&data);
+        font
+    }
+
+    fn count(path: &Path, matches: fn(&PathElement) -> bool) -> usize {
+        path.elements().iter().filter(|e| matches(e)).count()
+    }
+
+    #[test]
+    fn test_outline_simple_glyph() {
+        let font = Font::from_data("Sample", &sample_truetype(), 0).unwrap();
+        assert_eq!(font.units_per_em(), Some(1000));
+
+        let path = font.outline_glyph(1);
+        assert!(matches!(
+            path.elements().first(),
+            Some(PathElement::MoveTo(_))
+        ));
+        assert!(matches!(path.elements().last(), Some(PathElement::Close)));
+        assert_eq!(count(&path, |e| matches!(e, PathElement::MoveTo(_))), 1);
+        assert_eq!(path.bounds(), Rect::new(100.0, 0.0, 200.0, 100.0));
+
+        // No outline for the empty glyph or a font without data
+        assert!(font.outline_glyph(0).is_empty());
+        assert!(Font::new("Helvetica").outline_glyph(1).is_empty());
+    }
+
+    #[test]
+    fn test_outline_composite_glyph() {
+        let font = Font::from_data("Sample", &sample_truetype(), 0).unwrap();
+        let path = font.outline_glyph(2);
+        assert_eq!(count(&path, |e| matches!(e, PathElement::MoveTo(_))), 2);
+        assert_eq!(count(&path, |e| matches!(e, PathElement::Close)), 2);
+        // The second component is scaled by half and moved right by 300
+        assert_eq!(path.bounds(), Rect::new(100.0, 0.0, 400.0, 100.0));
+    }
 
     #[test]
     fn test_font_new() {
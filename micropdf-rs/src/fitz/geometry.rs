@@ -123,6 +123,16 @@ impl Rect {
         result.include_point(p3);
         result
     }
+
+    /// Bounding box of a quad's four corners
+    pub fn from_quad(q: &Quad) -> Rect {
+        Rect {
+            x0: q.ul.x.min(q.ur.x).min(q.ll.x).min(q.lr.x),
+            y0: q.ul.y.min(q.ur.y).min(q.ll.y).min(q.lr.y),
+            x1: q.ul.x.max(q.ur.x).max(q.ll.x).max(q.lr.x),
+            y1: q.ul.y.max(q.ur.y).max(q.ll.y).max(q.lr.y),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -146,6 +156,48 @@ impl IRect {
     pub fn is_empty(&self) -> bool {
         self.x0 >= self.x1 || self.y0 >= self.y1
     }
+
+    /// The area inside both rectangles, empty when they do not overlap
+    pub fn intersect(&self, other: &IRect) -> IRect {
+        IRect {
+            x0: self.x0.max(other.x0),
+            y0: self.y0.max(other.y0),
+            x1: self.x1.min(other.x1),
+            y1: self.y1.min(other.y1),
+        }
+    }
+
+    /// The smallest rectangle covering both; an empty one adds nothing
+    pub fn union(&self, other: &IRect) -> IRect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        IRect {
+            x0: self.x0.min(other.x0),
+            y0: self.y0.min(other.y0),
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1),
+        }
+    }
+
+    /// Move by `(dx, dy)`, saturating at the limits of `i32`
+    pub fn translate(&self, dx: i32, dy: i32) -> IRect {
+        IRect {
+            x0: self.x0.saturating_add(dx),
+            y0: self.y0.saturating_add(dy),
+            x1: self.x1.saturating_add(dx),
+            y1: self.y1.saturating_add(dy),
+        }
+    }
+
+    /// Whether the pixel at `(x, y)` lies inside; the right and bottom
+    /// edges are outside
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x0 && x < self.x1 && y >= self.y0 && y < self.y1
+    }
 }
 
 impl From<Rect> for IRect {
@@ -231,6 +283,46 @@ impl Matrix {
         }
     }
 
+    /// The inverse transformation, or `None` for a singular matrix
+    pub fn invert(&self) -> Option<Matrix> {
+        let det = self.a * self.d - self.b * self.c;
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let rdet = 1.0 / det;
+        Some(Self {
+            a: self.d * rdet,
+            b: -self.b * rdet,
+            c: -self.c * rdet,
+            d: self.a * rdet,
+            e: (self.c * self.f - self.d * self.e) * rdet,
+            f: (self.b * self.e - self.a * self.f) * rdet,
+        })
+    }
+
+    /// Split into `(scale_x, scale_y, rotation, skew, tx, ty)`, with the
+    /// rotation in degrees, such that the matrix is
+    /// `[1 0 skew 1 0 0] × scale(scale_x, scale_y) × rotate(rotation)`
+    /// followed by `translate(tx, ty)`
+    ///
+    /// The x axis keeps its direction, so a mirrored matrix has a negative
+    /// `scale_y`. A matrix that collapses the x axis decomposes to zeros.
+    pub fn decompose(&self) -> (f32, f32, f32, f32, f32, f32) {
+        let scale_x = self.a.hypot(self.b);
+        if scale_x == 0.0 {
+            return (0.0, 0.0, 0.0, 0.0, self.e, self.f);
+        }
+        let rotation = self.b.atan2(self.a).to_degrees();
+        let scale_y = (self.a * self.d - self.b * self.c) / scale_x;
+        let skew = (self.a * self.c + self.b * self.d) / (scale_x * scale_x);
+        (scale_x, scale_y, rotation, skew, self.e, self.f)
+    }
+
+    /// Angle of the transformed x axis, in degrees from -180 to 180
+    pub fn rotation_degrees(&self) -> f32 {
+        self.b.atan2(self.a).to_degrees()
+    }
+
     /// Transform a point by this matrix
     pub fn transform_point(&self, p: Point) -> Point {
         Point {
@@ -265,6 +357,41 @@ impl Quad {
             lr: self.lr.transform(m),
         }
     }
+
+    /// Axis-aligned bounding box
+    pub fn to_rect(&self) -> Rect {
+        Rect::from_quad(self)
+    }
+
+    /// Check if the quad's bounding box contains a point
+    pub fn contains_point(&self, p: Point) -> bool {
+        self.to_rect().contains(p.x, p.y)
+    }
+
+    /// Reassign corners so the upper pair has the smaller y and each pair is
+    /// ordered left to right, as for upright text. Quads from rotated or
+    /// mirrored text can arrive with corners in any order.
+    pub fn normalize(&self) -> Quad {
+        let mut corners = [self.ul, self.ur, self.ll, self.lr];
+        corners.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+        let (upper, lower) = corners.split_at(2);
+        let (ul, ur) = if upper[0].x <= upper[1].x {
+            (upper[0], upper[1])
+        } else {
+            (upper[1], upper[0])
+        };
+        let (ll, lr) = if lower[0].x <= lower[1].x {
+            (lower[0], lower[1])
+        } else {
+            (lower[1], lower[0])
+        };
+        Quad { ul, ur, ll, lr }
+    }
+
+    /// Bounding box of both quads
+    pub fn union(&self, other: &Quad) -> Rect {
+        self.to_rect().union(&other.to_rect())
+    }
 }
 
 #[cfg(test)]
@@ -426,6 +553,21 @@ mod tests {
         assert!(!IRect::new(0, 0, 10, 10).is_empty());
     }
 
+    #[test]
+    fn test_irect_intersect_union() {
+        let a = IRect::new(0, 0, 10, 10);
+        let b = IRect::new(5, -5, 20, 8);
+        assert_eq!(a.intersect(&b), IRect::new(5, 0, 10, 8));
+        assert_eq!(a.union(&b), IRect::new(0, -5, 20, 10));
+        assert!(a.intersect(&IRect::new(10, 0, 20, 10)).is_empty());
+        assert_eq!(a.union(&IRect::new(3, 3, 3, 3)), a);
+
+        let moved = a.translate(-2, 3);
+        assert_eq!(moved, IRect::new(-2, 3, 8, 13));
+        assert!(moved.contains(-2, 3));
+        assert!(!moved.contains(8, 5));
+    }
+
     #[test]
     fn test_irect_from_rect() {
         let r = Rect::new(0.5, 1.5, 9.5, 19.5);
@@ -505,6 +647,47 @@ mod tests {
         assert_eq!(result.f, 10.0);
     }
 
+    #[test]
+    fn test_matrix_invert() {
+        let m = Matrix::scale(2.0, 4.0).concat(&Matrix::translate(10.0, 20.0));
+        let p = m.invert().unwrap().transform_point(Point::new(12.0, 24.0));
+        assert_eq!((p.x, p.y), (1.0, 1.0));
+        assert!(Matrix::scale(0.0, 1.0).invert().is_none());
+    }
+
+    #[test]
+    fn test_matrix_decompose() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+
+        let (sx, sy, rot, skew, tx, ty) = Matrix::rotate(30.0).decompose();
+        assert!(close(sx, 1.0) && close(sy, 1.0) && close(rot, 30.0) && close(skew, 0.0));
+        assert_eq!((tx, ty), (0.0, 0.0));
+
+        let (sx, sy, rot, skew, ..) = Matrix::scale(2.0, 3.0).decompose();
+        assert_eq!((sx, sy, rot, skew), (2.0, 3.0, 0.0, 0.0));
+
+        let m = Matrix::scale(2.0, 0.5)
+            .concat(&Matrix::rotate(-120.0))
+            .concat(&Matrix::translate(5.0, 7.0));
+        let (sx, sy, rot, skew, tx, ty) = m.decompose();
+        assert!(close(sx, 2.0) && close(sy, 0.5) && close(rot, -120.0) && close(skew, 0.0));
+        assert!(close(tx, 5.0) && close(ty, 7.0));
+        assert!(close(m.rotation_degrees(), -120.0));
+
+        // Recomposing gives the matrix back, skew and mirroring included
+        let m = Matrix::new(1.0, 0.0, 0.4, 1.0, 0.0, 0.0)
+            .concat(&Matrix::scale(3.0, -2.0))
+            .concat(&Matrix::rotate(75.0));
+        let (sx, sy, rot, skew, ..) = m.decompose();
+        assert!(close(skew, 0.4) && close(sy, -2.0));
+        let back = Matrix::new(1.0, 0.0, skew, 1.0, 0.0, 0.0)
+            .concat(&Matrix::scale(sx, sy))
+            .concat(&Matrix::rotate(rot));
+        for (x, y) in [(m.a, back.a), (m.b, back.b), (m.c, back.c), (m.d, back.d)] {
+            assert!(close(x, y), "{m:?} != {back:?}");
+        }
+    }
+
     #[test]
     fn test_matrix_default() {
         let m: Matrix = Default::default();
@@ -546,4 +729,29 @@ mod tests {
         assert_eq!(q.ll, Point::default());
         assert_eq!(q.lr, Point::default());
     }
+
+    #[test]
+    fn test_quad_normalize_rotated() {
+        let q = Quad::from_rect(&Rect::new(10.0, 20.0, 30.0, 40.0));
+        let rotated = q.transform(&Matrix::new(-1.0, 0.0, 0.0, -1.0, 100.0, 100.0));
+        // A half turn swaps upper and lower, left and right
+        assert_eq!(rotated.ul, Point::new(90.0, 80.0));
+        assert_eq!(rotated.lr, Point::new(70.0, 60.0));
+
+        let n = rotated.normalize();
+        assert_eq!(n.ul, Point::new(70.0, 60.0));
+        assert_eq!(n.ur, Point::new(90.0, 60.0));
+        assert_eq!(n.ll, Point::new(70.0, 80.0));
+        assert_eq!(n.lr, Point::new(90.0, 80.0));
+        assert_eq!(n.normalize(), n);
+        assert_eq!(Rect::from_quad(&rotated), Rect::new(70.0, 60.0, 90.0, 80.0));
+    }
+
+    #[test]
+    fn test_quad_union_adjacent() {
+        let a = Quad::from_rect(&Rect::new(0.0, 0.0, 10.0, 12.0));
+        let b = Quad::from_rect(&Rect::new(10.0, 0.0, 16.0, 12.0));
+        assert_eq!(a.union(&b), Rect::new(0.0, 0.0, 16.0, 12.0));
+        assert!(a.contains_point(Point::new(5.0, 5.0)));
+    }
 }
@@ -6,6 +6,7 @@ use crate::fitz::colorspace::Colorspace;
 use crate::fitz::error::{Error, Result};
 use crate::fitz::geometry::{IRect, Matrix};
 use crate::fitz::pixmap::Pixmap;
+use crate::pdf::filter::{DecodePool, decode_flate_into, decode_pool};
 
 /// Image format/compression type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -291,18 +292,28 @@ impl Image {
             return Ok(());
         }
 
-        // Decode based on format
+        // Update to raw format
+        self.data = self.decode_pooled(decode_pool())?;
+        self.format = ImageFormat::Raw;
+        Ok(())
+    }
+
+    /// Size in bytes of the decoded samples
+    fn decoded_len(&self) -> usize {
+        let row = (self.width as usize * self.n as usize * self.bpc as usize).div_ceil(8);
+        row * self.height as usize
+    }
+
+    /// Decode the compressed data into a buffer borrowed from `pool`,
+    /// leaving the image unchanged
+    ///
+    /// Raw images return a copy of their data. Hand the buffer back with
+    /// [`DecodePool::release`] once the samples have been consumed.
+    pub fn decode_pooled(&self, pool: &DecodePool) -> Result<Vec<u8>> {
         let decoded_data = match self.format {
             ImageFormat::Flate => {
-                // Use flate2 to decompress
-                use flate2::read::ZlibDecoder;
-                use std::io::Read;
-
-                let mut decoder = ZlibDecoder::new(&self.data[..]);
-                let mut decoded = Vec::new();
-                decoder
-                    .read_to_end(&mut decoded)
-                    .map_err(|e| Error::Generic(format!("Flate decode failed: {}", e)))?;
+                let mut decoded = pool.acquire(self.decoded_len());
+                decode_flate_into(&self.data, &mut decoded)?;
                 decoded
             }
             ImageFormat::Jpeg => {
@@ -332,14 +343,12 @@ impl Image {
                 decode_run_length(&self.data)?
             }
             ImageFormat::Raw => {
-                return Ok(());
+                let mut copy = pool.acquire(self.data.len());
+                copy.extend_from_slice(&self.data);
+                copy
             }
         };
-
-        // Update to raw format
-        self.data = decoded_data;
-        self.format = ImageFormat::Raw;
-        Ok(())
+        Ok(decoded_data)
     }
 
     /// Get or create pixmap from image
@@ -352,24 +361,32 @@ impl Image {
 
     /// Convert image to pixmap
     pub fn to_pixmap(&mut self) -> Result<Pixmap> {
-        // Decode if compressed
-        if self.is_compressed() {
-            self.decode()?;
-        }
-
         // Create pixmap
         let colorspace = self.colorspace.clone();
         let has_alpha = self.mask.is_some();
 
         let mut pixmap = Pixmap::new(colorspace, self.width, self.height, has_alpha)?;
 
+        // Compressed data is decoded into a pooled buffer that is handed
+        // back once copied, rather than kept alongside the pixmap
+        let pool = decode_pool();
+        let decoded = if self.is_compressed() {
+            Some(self.decode_pooled(pool)?)
+        } else {
+            None
+        };
+        let data = decoded.as_deref().unwrap_or(&self.data);
+
         // Copy image data to pixmap
-        if !self.data.is_empty() {
+        if !data.is_empty() {
             // Simplified: just copy data
             // In reality, we'd need to handle different bpc values, stride, etc.
             let samples = pixmap.samples_mut();
-            let copy_len = samples.len().min(self.data.len());
-            samples[..copy_len].copy_from_slice(&self.data[..copy_len]);
+            let copy_len = samples.len().min(data.len());
+            samples[..copy_len].copy_from_slice(&data[..copy_len]);
+        }
+        if let Some(decoded) = decoded {
+            pool.release(decoded);
         }
 
         // Apply mask if present
@@ -707,4 +724,41 @@ mod tests {
             Image::from_compressed(10, 10, 8, None, ImageFormat::Flate, vec![1, 2, 3]).unwrap();
         assert!(comp_img.is_compressed());
     }
+
+    #[test]
+    fn test_decode_pooled_reuses_buffers() {
+        use flate2::Compression;
+        use flate2::write::ZlibEncoder;
+        use std::io::Write;
+
+        let pool = DecodePool::new();
+        let mut previous = None;
+        for i in 0..4u8 {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&[i; 16 * 16 * 3]).unwrap();
+            let data = encoder.finish().unwrap();
+            let img = Image::from_compressed(
+                16,
+                16,
+                8,
+                Some(Colorspace::device_rgb()),
+                ImageFormat::Flate,
+                data,
+            )
+            .unwrap();
+
+            let samples = img.decode_pooled(&pool).unwrap();
+            assert_eq!(samples, [i; 16 * 16 * 3]);
+            if let Some(ptr) = previous {
+                assert_eq!(samples.as_ptr(), ptr);
+            }
+            previous = Some(samples.as_ptr());
+            pool.release(samples);
+        }
+
+        // Only the first decode allocated
+        let stats = pool.stats();
+        assert_eq!(stats.allocations, 1);
+        assert_eq!(stats.reuses, 3);
+    }
 }
@@ -20,8 +20,12 @@ pub mod output;
 pub mod page;
 pub mod path;
 pub mod pixmap;
+pub mod render;
+pub mod separation;
+pub mod stext;
 pub mod stream;
 pub mod text;
+pub mod warning;
 
 #[cfg(feature = "parallel")]
 pub mod parallel;
@@ -28,6 +28,11 @@ pub trait OutputWriter: Write + Send {
             "Reset not supported for this output type".into(),
         ))
     }
+
+    /// Bytes written so far, for sinks that keep them in memory
+    fn contents(&self) -> Option<&[u8]> {
+        None
+    }
 }
 
 /// Seek position
@@ -93,6 +98,32 @@ impl Output {
         }
     }
 
+    /// Create output that accumulates its bytes in memory; read them back
+    /// with [`Output::contents`]
+    pub fn memory() -> Self {
+        Self {
+            writer: Box::new(MemoryOutput::new()),
+        }
+    }
+
+    /// Create output that duplicates every write to both `a` and `b`
+    ///
+    /// Flush, close, seek, truncate and reset are applied to each sink;
+    /// `tell` reports the position of `a`.
+    pub fn tee(a: Output, b: Output) -> Self {
+        Self {
+            writer: Box::new(TeeOutput { a, b }),
+        }
+    }
+
+    /// Create output that discards its bytes, for measuring how much a
+    /// writer would produce: `tell` reports the position as usual
+    pub fn null() -> Self {
+        Self {
+            writer: Box::new(CountingOutput::new()),
+        }
+    }
+
     /// Write raw data
     pub fn write_data(&mut self, data: &[u8]) -> Result<()> {
         self.writer.write_all(data).map_err(Error::System)
@@ -117,6 +148,28 @@ impl Output {
         self.write_string(&s)
     }
 
+    /// Write a real number in PDF syntax: `0.5` as `0.5`, `1.0` as `1`,
+    /// never with an exponent
+    pub fn write_f32(&mut self, value: f32) -> Result<()> {
+        self.write_string(&crate::pdf::write::format_real(value as f64))
+    }
+
+    /// Write a PDF name, with the leading `/` and `#xx` escapes for
+    /// delimiters, whitespace and non-ASCII bytes
+    pub fn write_pdf_name(&mut self, name: &str) -> Result<()> {
+        let mut bytes = Vec::with_capacity(name.len() + 1);
+        crate::pdf::write::write_name(&mut bytes, name)?;
+        self.write_data(&bytes)
+    }
+
+    /// Write a PDF string, literal with escapes when mostly printable and
+    /// hex otherwise
+    pub fn write_pdf_string(&mut self, data: &[u8]) -> Result<()> {
+        let mut bytes = Vec::with_capacity(data.len() + 2);
+        crate::pdf::write::write_string(&mut bytes, data)?;
+        self.write_data(&bytes)
+    }
+
     /// Write a single byte
     pub fn write_byte(&mut self, byte: u8) -> Result<()> {
         self.write_data(&[byte])
@@ -238,6 +291,11 @@ impl Output {
     pub fn reset(&mut self) -> Result<()> {
         self.writer.reset()
     }
+
+    /// Bytes written so far, or `None` if the sink does not keep them
+    pub fn contents(&self) -> Option<&[u8]> {
+        self.writer.contents()
+    }
 }
 
 // ============================================================================
@@ -371,6 +429,141 @@ impl OutputWriter for BufferOutput {
         self.position = 0;
         Ok(())
     }
+
+    fn contents(&self) -> Option<&[u8]> {
+        Some(&self.data)
+    }
+}
+
+// ============================================================================
+// Tee Output
+// ============================================================================
+
+struct TeeOutput {
+    a: Output,
+    b: Output,
+}
+
+impl Write for TeeOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.a.writer.write_all(buf)?;
+        self.b.writer.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.a.writer.flush()?;
+        self.b.writer.flush()
+    }
+}
+
+impl OutputWriter for TeeOutput {
+    fn seek(&mut self, offset: i64, whence: SeekFrom) -> Result<u64> {
+        let pos = self.a.seek(offset, whence)?;
+        self.b.seek(offset, whence)?;
+        Ok(pos)
+    }
+
+    fn tell(&mut self) -> Result<u64> {
+        self.a.tell()
+    }
+
+    fn flush_output(&mut self) -> Result<()> {
+        // Flush both even if the first fails
+        let a = self.a.flush();
+        let b = self.b.flush();
+        a.and(b)
+    }
+
+    fn truncate(&mut self) -> Result<()> {
+        self.a.truncate()?;
+        self.b.truncate()
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.a.reset()?;
+        self.b.reset()
+    }
+}
+
+// ============================================================================
+// Counting Output
+// ============================================================================
+
+/// Output that discards data but tracks its size and position
+#[derive(Debug, Default, Clone)]
+pub struct CountingOutput {
+    position: u64,
+    len: u64,
+    written: u64,
+}
+
+impl CountingOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Total bytes passed to `write`, including any that overwrote earlier
+    /// data after a seek
+    pub fn bytes_written(&self) -> u64 {
+        self.written
+    }
+
+    /// Size the output would have: the furthest position written
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Write for CountingOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.position += buf.len() as u64;
+        self.written += buf.len() as u64;
+        self.len = self.len.max(self.position);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl OutputWriter for CountingOutput {
+    fn seek(&mut self, _offset: i64, whence: SeekFrom) -> Result<u64> {
+        let new_pos = match whence {
+            SeekFrom::Start(n) => n as i64,
+            SeekFrom::Current(n) => self.position as i64 + n,
+            SeekFrom::End(n) => self.len as i64 + n,
+        };
+
+        if new_pos < 0 {
+            return Err(Error::Generic("Seek before start of output".into()));
+        }
+        self.position = new_pos as u64;
+        Ok(self.position)
+    }
+
+    fn tell(&mut self) -> Result<u64> {
+        Ok(self.position)
+    }
+
+    fn flush_output(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn truncate(&mut self) -> Result<()> {
+        self.len = self.position;
+        Ok(())
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        *self = Self::default();
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -404,6 +597,10 @@ impl MemoryOutput {
     pub fn as_slice(&self) -> &[u8] {
         &self.data
     }
+
+    pub fn into_buffer(self) -> Buffer {
+        Buffer::from_data(self.data)
+    }
 }
 
 impl Default for MemoryOutput {
@@ -470,6 +667,10 @@ impl OutputWriter for MemoryOutput {
         self.position = 0;
         Ok(())
     }
+
+    fn contents(&self) -> Option<&[u8]> {
+        Some(&self.data)
+    }
 }
 
 #[cfg(test)]
@@ -561,4 +762,120 @@ mod tests {
 
         assert_eq!(out.tell().unwrap(), 12); // 4 + 8 bytes
     }
+
+    #[test]
+    fn test_memory_output_contents() {
+        let mut out = Output::memory();
+        out.write_string("Hello").unwrap();
+        out.seek(0, SeekFrom::Start(0)).unwrap();
+        out.write_byte(b'J').unwrap();
+        out.seek(0, SeekFrom::End(0)).unwrap();
+        out.write_string(", World!").unwrap();
+        assert_eq!(out.contents(), Some(&b"Jello, World!"[..]));
+        assert_eq!(Output::null().contents(), None);
+
+        let mut memory = MemoryOutput::new();
+        memory.write_all(b"abc").unwrap();
+        assert_eq!(memory.into_buffer().to_vec(), b"abc");
+    }
+
+    #[test]
+    fn test_output_pdf_syntax() {
+        let memory = SharedMemory::default();
+        let mut out = Output::from_writer(memory.clone());
+        out.write_f32(0.5).unwrap();
+        out.write_byte(b' ').unwrap();
+        out.write_f32(1.0).unwrap();
+        out.write_byte(b' ').unwrap();
+        out.write_f32(-0.25).unwrap();
+        out.write_byte(b' ').unwrap();
+        out.write_f32(1e20).unwrap();
+        out.write_byte(b' ').unwrap();
+        out.write_pdf_name("A B#").unwrap();
+        out.write_byte(b' ').unwrap();
+        out.write_pdf_string(b"a(b)\\").unwrap();
+        assert_eq!(
+            memory.0.lock().unwrap().as_slice(),
+            b"0.5 1 -0.25 100000002004087734272 /A#20B#23 (a\\(b\\)\\\\)"
+        );
+    }
+
+    /// Memory sink whose bytes stay readable after it is moved into an Output
+    #[derive(Clone, Default)]
+    struct SharedMemory(std::sync::Arc<std::sync::Mutex<MemoryOutput>>);
+
+    impl Write for SharedMemory {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl OutputWriter for SharedMemory {
+        fn seek(&mut self, offset: i64, whence: SeekFrom) -> Result<u64> {
+            self.0.lock().unwrap().seek(offset, whence)
+        }
+
+        fn tell(&mut self) -> Result<u64> {
+            self.0.lock().unwrap().tell()
+        }
+
+        fn flush_output(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn truncate(&mut self) -> Result<()> {
+            self.0.lock().unwrap().truncate()
+        }
+    }
+
+    #[test]
+    fn test_counting_output() {
+        let mut counter = CountingOutput::new();
+        counter.write_all(b"0123456789").unwrap();
+        assert_eq!(counter.tell().unwrap(), 10);
+
+        counter.seek(0, SeekFrom::Start(4)).unwrap();
+        counter.write_all(b"ab").unwrap();
+        assert_eq!(counter.tell().unwrap(), 6);
+        assert_eq!(counter.bytes_written(), 12);
+        assert_eq!(counter.len(), 10);
+        assert_eq!(counter.seek(0, SeekFrom::End(2)).unwrap(), 12);
+
+        counter.seek(0, SeekFrom::Start(3)).unwrap();
+        counter.truncate().unwrap();
+        assert_eq!(counter.len(), 3);
+        assert!(counter.seek(0, SeekFrom::Current(-4)).is_err());
+    }
+
+    #[test]
+    fn test_null_output_measures() {
+        let mut out = Output::null();
+        out.write_string("%PDF-1.7\n").unwrap();
+        out.write_u32_be(0xDEADBEEF).unwrap();
+        out.write_f64_le(1.5).unwrap();
+        assert_eq!(out.tell().unwrap(), 9 + 4 + 8);
+    }
+
+    #[test]
+    fn test_output_tee() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path();
+        let memory = SharedMemory::default();
+
+        let file_out = Output::from_path(path, false).unwrap();
+        let mut tee = Output::tee(file_out, Output::from_writer(memory.clone()));
+        tee.write_string("Hello, ").unwrap();
+        tee.write_u32_be(0xDEADBEEF).unwrap();
+        tee.write_string(" tee").unwrap();
+        assert_eq!(tee.tell().unwrap(), 15);
+        tee.close().unwrap();
+
+        let file_bytes = std::fs::read(path).unwrap();
+        assert_eq!(file_bytes.len(), 15);
+        assert_eq!(file_bytes, memory.0.lock().unwrap().as_slice());
+    }
 }
@@ -2,8 +2,57 @@
 
 use crate::fitz::colorspace::Colorspace;
 use crate::fitz::error::{Error, Result};
+use crate::fitz::geometry::IRect;
+use crate::fitz::separation::Separations;
 use std::sync::Arc;
 
+/// Resampling filter for [`Pixmap::scale`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleFilter {
+    /// Area average: each target pixel is the coverage-weighted mean of the
+    /// source pixels under it. Best for downsampling.
+    #[default]
+    Box,
+    /// Linear interpolation between the two nearest source pixels on each
+    /// axis. Best for upsampling.
+    Bilinear,
+}
+
+impl ScaleFilter {
+    /// Source taps and weights for every target index along one axis
+    fn weights(self, src: usize, dst: usize) -> Vec<Vec<(usize, f32)>> {
+        let ratio = src as f32 / dst as f32;
+        (0..dst)
+            .map(|i| match self {
+                ScaleFilter::Box => {
+                    let start = i as f32 * ratio;
+                    let end = start + ratio;
+                    let mut taps = Vec::new();
+                    let mut j = start.floor() as usize;
+                    while (j as f32) < end && j < src {
+                        let overlap = end.min(j as f32 + 1.0) - start.max(j as f32);
+                        if overlap > 0.0 {
+                            taps.push((j, overlap / ratio));
+                        }
+                        j += 1;
+                    }
+                    taps
+                }
+                ScaleFilter::Bilinear => {
+                    let center = ((i as f32 + 0.5) * ratio - 0.5).clamp(0.0, (src - 1) as f32);
+                    let j = center.floor() as usize;
+                    let t = center - j as f32;
+                    if t > 0.0 && j + 1 < src {
+                        vec![(j, 1.0 - t), (j + 1, t)]
+                    } else {
+                        vec![(j, 1.0)]
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
 #[derive(Clone)]
 pub struct Pixmap {
     inner: Arc<PixmapInner>,
@@ -20,6 +69,8 @@ struct PixmapInner {
     alpha: u8,
     stride: usize,
     colorspace: Option<Colorspace>,
+    /// Spot channels stored after the process colors
+    separations: Option<Separations>,
     samples: Vec<u8>,
 }
 
@@ -44,10 +95,45 @@ impl Pixmap {
                 alpha: if alpha { 1 } else { 0 },
                 stride,
                 colorspace,
+                separations: None,
                 samples: vec![0; stride * (h as usize)],
             }),
         })
     }
+
+    /// Create a pixmap with spot channels after the process colors
+    ///
+    /// Each pixel holds `process_n + spot_n` samples, then alpha. Spot
+    /// samples are tints: 0 is no ink, 255 full coverage.
+    pub fn with_separations(
+        colorspace: Colorspace,
+        separations: Separations,
+        w: i32,
+        h: i32,
+        alpha: bool,
+    ) -> Result<Self> {
+        let n = colorspace.n() as usize + separations.len() + usize::from(alpha);
+        if n > u8::MAX as usize {
+            return Err(Error::limit("Too many separations"));
+        }
+        let mut pixmap = Self::new(Some(colorspace), w, h, alpha)?;
+        let inner = Arc::make_mut(&mut pixmap.inner);
+        inner.n = n as u8;
+        inner.stride = w as usize * n;
+        inner.samples = vec![0; inner.stride * h as usize];
+        inner.separations = (!separations.is_empty()).then_some(separations);
+        Ok(pixmap)
+    }
+
+    /// Blank pixmap of the given size with this one's channel layout
+    fn new_like(&self, w: i32, h: i32) -> Result<Self> {
+        match (&self.inner.colorspace, &self.inner.separations) {
+            (Some(cs), Some(seps)) => {
+                Self::with_separations(cs.clone(), seps.clone(), w, h, self.has_alpha())
+            }
+            _ => Self::new(self.inner.colorspace.clone(), w, h, self.has_alpha()),
+        }
+    }
     pub fn width(&self) -> i32 {
         self.inner.w
     }
@@ -74,6 +160,21 @@ impl Pixmap {
     pub fn colorspace(&self) -> Option<&Colorspace> {
         self.inner.colorspace.as_ref()
     }
+    /// Spot colorants, if the pixmap has any
+    pub fn separations(&self) -> Option<&Separations> {
+        self.inner.separations.as_ref()
+    }
+    /// Number of process color components
+    pub fn process_n(&self) -> usize {
+        self.inner
+            .colorspace
+            .as_ref()
+            .map_or(0, |cs| cs.n() as usize)
+    }
+    /// Number of spot color components
+    pub fn spot_n(&self) -> usize {
+        self.inner.separations.as_ref().map_or(0, Separations::len)
+    }
     pub fn samples(&self) -> &[u8] {
         &self.inner.samples
     }
@@ -91,6 +192,298 @@ impl Pixmap {
         let offset = (y as usize) * self.inner.stride + (x as usize) * (self.inner.n as usize);
         Some(&self.inner.samples[offset..offset + self.inner.n as usize])
     }
+
+    /// Trim border rows and columns whose pixels match `bg` within `tolerance`
+    /// per component. A fully uniform pixmap is returned unchanged.
+    pub fn autocrop(&self, bg: &[u8], tolerance: u8) -> Result<Pixmap> {
+        let n = self.inner.n as usize;
+        if bg.len() != n {
+            return Err(Error::argument(
+                "Background must have one value per component",
+            ));
+        }
+        let (w, h) = (self.inner.w as usize, self.inner.h as usize);
+        let stride = self.inner.stride;
+        let samples = &self.inner.samples;
+
+        let is_bg = |x: usize, y: usize| {
+            let px = &samples[y * stride + x * n..][..n];
+            px.iter().zip(bg).all(|(&a, &b)| a.abs_diff(b) <= tolerance)
+        };
+        let row_is_bg = |y: usize| (0..w).all(|x| is_bg(x, y));
+        let col_is_bg = |x: usize, y0: usize, y1: usize| (y0..y1).all(|y| is_bg(x, y));
+
+        let Some(y0) = (0..h).find(|&y| !row_is_bg(y)) else {
+            return Ok(self.clone());
+        };
+        let y1 = (y0..h).rfind(|&y| !row_is_bg(y)).unwrap() + 1;
+        let x0 = (0..w).find(|&x| !col_is_bg(x, y0, y1)).unwrap();
+        let x1 = (x0..w).rfind(|&x| !col_is_bg(x, y0, y1)).unwrap() + 1;
+
+        if (x0, y0, x1, y1) == (0, 0, w, h) {
+            return Ok(self.clone());
+        }
+
+        self.sub_pixmap(IRect::new(x0 as i32, y0 as i32, x1 as i32, y1 as i32))
+    }
+
+    /// Copy of the pixels inside `rect`, in this pixmap's pixel
+    /// coordinates, clamped to its bounds
+    ///
+    /// The copy remembers where it came from: its origin is offset by the
+    /// corner of the clamped rectangle. A rectangle missing the pixmap
+    /// entirely is an error.
+    pub fn sub_pixmap(&self, rect: IRect) -> Result<Pixmap> {
+        let rect = rect.intersect(&IRect::new(0, 0, self.inner.w, self.inner.h));
+        if rect.is_empty() {
+            return Err(Error::argument("rectangle lies outside the pixmap"));
+        }
+        let n = self.inner.n as usize;
+        let (x0, y0) = (rect.x0 as usize, rect.y0 as usize);
+        let stride = self.inner.stride;
+
+        let mut cropped = self.new_like(rect.width(), rect.height())?;
+        let inner = Arc::make_mut(&mut cropped.inner);
+        inner.x = self.inner.x + rect.x0;
+        inner.y = self.inner.y + rect.y0;
+        let row_len = rect.width() as usize * n;
+        for (dst, y) in inner.samples.chunks_exact_mut(row_len).zip(y0..) {
+            let start = y * stride + x0 * n;
+            dst.copy_from_slice(&self.inner.samples[start..start + row_len]);
+        }
+        Ok(cropped)
+    }
+
+    /// Resample to `new_w` x `new_h`, keeping the colorspace and alpha.
+    ///
+    /// The filter is applied separably, first across rows and then down
+    /// columns. Every component, alpha included, is filtered alike.
+    pub fn scale(&self, new_w: i32, new_h: i32, filter: ScaleFilter) -> Result<Pixmap> {
+        if new_w <= 0 || new_h <= 0 {
+            return Err(Error::argument("Invalid dimensions"));
+        }
+        if (new_w, new_h) == (self.inner.w, self.inner.h) {
+            return Ok(self.clone());
+        }
+
+        let n = self.inner.n as usize;
+        let (w, h) = (self.inner.w as usize, self.inner.h as usize);
+        let (dw, dh) = (new_w as usize, new_h as usize);
+        let stride = self.inner.stride;
+        let samples = &self.inner.samples;
+
+        // Horizontal pass into a float buffer of dw x h
+        let x_taps = filter.weights(w, dw);
+        let mut rows = vec![0f32; dw * h * n];
+        for y in 0..h {
+            let src = &samples[y * stride..][..w * n];
+            let dst = &mut rows[y * dw * n..][..dw * n];
+            for (x, taps) in x_taps.iter().enumerate() {
+                for &(sx, weight) in taps {
+                    for c in 0..n {
+                        dst[x * n + c] += f32::from(src[sx * n + c]) * weight;
+                    }
+                }
+            }
+        }
+
+        // Vertical pass into the target pixmap
+        let mut scaled = self.new_like(new_w, new_h)?;
+        let inner = Arc::make_mut(&mut scaled.inner);
+        inner.x = self.inner.x;
+        inner.y = self.inner.y;
+        let y_taps = filter.weights(h, dh);
+        let row_len = dw * n;
+        for (dst, taps) in inner.samples.chunks_exact_mut(row_len).zip(&y_taps) {
+            for (i, out) in dst.iter_mut().enumerate() {
+                let v: f32 = taps
+                    .iter()
+                    .map(|&(sy, wt)| rows[sy * row_len + i] * wt)
+                    .sum();
+                *out = v.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+        Ok(scaled)
+    }
+
+    /// Number of colour components, excluding alpha
+    fn color_components(&self) -> usize {
+        self.inner.n as usize - self.inner.alpha as usize
+    }
+
+    /// Invert every colour component, leaving alpha untouched. For CMYK this
+    /// inverts ink coverage, which gives the expected negative.
+    pub fn invert(&mut self) {
+        let n = self.inner.n as usize;
+        let colors = self.color_components();
+        for px in self.samples_mut().chunks_exact_mut(n) {
+            for v in &mut px[..colors] {
+                *v = 255 - *v;
+            }
+        }
+    }
+
+    /// Apply `gamma` to every colour component through a lookup table
+    pub fn gamma(&mut self, gamma: f32) {
+        let mut lut = [0u8; 256];
+        for (i, v) in lut.iter_mut().enumerate() {
+            *v = ((i as f32 / 255.0).powf(gamma) * 255.0)
+                .round()
+                .clamp(0.0, 255.0) as u8;
+        }
+        let n = self.inner.n as usize;
+        let colors = self.color_components();
+        for px in self.samples_mut().chunks_exact_mut(n) {
+            for v in &mut px[..colors] {
+                *v = lut[*v as usize];
+            }
+        }
+    }
+
+    /// Remap black to `black` and white to `white`, interpolating linearly in
+    /// between, for duotone effects.
+    ///
+    /// Gray pixmaps use the luminance of the two colours. Other colorspaces
+    /// are rejected.
+    pub fn tint(&mut self, black: [u8; 3], white: [u8; 3]) -> Result<()> {
+        let luma = |c: [u8; 3]| {
+            (0.3 * f32::from(c[0]) + 0.59 * f32::from(c[1]) + 0.11 * f32::from(c[2])).round() as u8
+        };
+        let (black, white): (Vec<u8>, Vec<u8>) = match self.colorspace().map(Colorspace::n) {
+            Some(1) => (vec![luma(black)], vec![luma(white)]),
+            Some(3) => (black.to_vec(), white.to_vec()),
+            _ => return Err(Error::argument("Tint requires a gray or RGB pixmap")),
+        };
+        let n = self.inner.n as usize;
+        for px in self.samples_mut().chunks_exact_mut(n) {
+            for (v, (&lo, &hi)) in px.iter_mut().zip(black.iter().zip(&white)) {
+                let (lo, hi) = (f32::from(lo), f32::from(hi));
+                *v = (lo + (hi - lo) * f32::from(*v) / 255.0).round() as u8;
+            }
+        }
+        Ok(())
+    }
+
+    /// Composite `src` over this pixmap with its top-left corner at (`x`, `y`),
+    /// clipped to this pixmap's bounds.
+    ///
+    /// Samples are treated as straight (non-premultiplied) alpha: colour
+    /// components hold the full colour regardless of coverage. Without an
+    /// alpha channel the source is opaque and simply replaces the target.
+    pub fn blend_over(&mut self, src: &Pixmap, x: i32, y: i32) -> Result<()> {
+        if src.inner.n != self.inner.n
+            || src.colorspace().map(Colorspace::name) != self.colorspace().map(Colorspace::name)
+        {
+            return Err(Error::argument(
+                "Pixmaps must share a colorspace and component count",
+            ));
+        }
+
+        let x0 = x.max(0);
+        let y0 = y.max(0);
+        let x1 = (x + src.inner.w).min(self.inner.w);
+        let y1 = (y + src.inner.h).min(self.inner.h);
+        if x0 >= x1 || y0 >= y1 {
+            return Ok(());
+        }
+
+        let n = self.inner.n as usize;
+        let alpha = self.has_alpha();
+        let src_stride = src.inner.stride;
+        let inner = Arc::make_mut(&mut self.inner);
+        let dst_stride = inner.stride;
+        let span = (x1 - x0) as usize * n;
+        for row in y0..y1 {
+            let s = &src.inner.samples[(row - y) as usize * src_stride + (x0 - x) as usize * n..]
+                [..span];
+            let d = &mut inner.samples[row as usize * dst_stride + x0 as usize * n..][..span];
+            if !alpha {
+                d.copy_from_slice(s);
+                continue;
+            }
+            for (dp, sp) in d.chunks_exact_mut(n).zip(s.chunks_exact(n)) {
+                let sa = f32::from(sp[n - 1]) / 255.0;
+                let da = f32::from(dp[n - 1]) / 255.0;
+                let out_a = sa + da * (1.0 - sa);
+                if out_a <= 0.0 {
+                    dp.fill(0);
+                    continue;
+                }
+                for c in 0..n - 1 {
+                    let v = (f32::from(sp[c]) * sa + f32::from(dp[c]) * da * (1.0 - sa)) / out_a;
+                    dp[c] = v.round().clamp(0.0, 255.0) as u8;
+                }
+                dp[n - 1] = (out_a * 255.0).round() as u8;
+            }
+        }
+        Ok(())
+    }
+
+    /// Flatten process and spot channels into an RGB pixmap
+    ///
+    /// Process colors convert to RGB directly. Each spot channel is
+    /// rendered through its tint transform and multiplied in, as inks
+    /// printed over one another. Alpha is kept.
+    pub fn composite_to_rgb(&self) -> Result<Pixmap> {
+        let process_n = self.process_n();
+        let to_rgb = |c: &[f32]| -> Result<[f32; 3]> {
+            Ok(match process_n {
+                0 => [1.0; 3],
+                1 => [c[0]; 3],
+                3 => [c[0], c[1], c[2]],
+                4 => {
+                    let k = 1.0 - c[3];
+                    [(1.0 - c[0]) * k, (1.0 - c[1]) * k, (1.0 - c[2]) * k]
+                }
+                n => {
+                    return Err(Error::unsupported(format!(
+                        "compositing {n} process components"
+                    )));
+                }
+            })
+        };
+        let seps: Vec<_> = self
+            .separations()
+            .map_or(Vec::new(), |s| s.iter().collect());
+
+        let mut out = Pixmap::new(
+            Some(Colorspace::device_rgb()),
+            self.inner.w,
+            self.inner.h,
+            self.has_alpha(),
+        )?;
+        let out_inner = Arc::make_mut(&mut out.inner);
+        out_inner.x = self.inner.x;
+        out_inner.y = self.inner.y;
+
+        let n = self.inner.n as usize;
+        let out_n = out_inner.n as usize;
+        let mut process = vec![0f32; process_n];
+        for (src, dst) in self
+            .inner
+            .samples
+            .chunks_exact(n)
+            .zip(out_inner.samples.chunks_exact_mut(out_n))
+        {
+            for (p, &v) in process.iter_mut().zip(src) {
+                *p = f32::from(v) / 255.0;
+            }
+            let mut rgb = to_rgb(&process)?;
+            for (sep, &tint) in seps.iter().zip(&src[process_n..]) {
+                let ink = sep.tint.to_rgb(f32::from(tint) / 255.0);
+                for (c, i) in rgb.iter_mut().zip(ink) {
+                    *c *= i;
+                }
+            }
+            for (d, c) in dst.iter_mut().zip(rgb) {
+                *d = (c * 255.0).round().clamp(0.0, 255.0) as u8;
+            }
+            if self.has_alpha() {
+                dst[out_n - 1] = src[n - 1];
+            }
+        }
+        Ok(out)
+    }
 }
 
 #[cfg(test)]
@@ -233,4 +626,174 @@ mod tests {
         assert_eq!(pm1.height(), pm2.height());
         assert_eq!(pm1.n(), pm2.n());
     }
+
+    #[test]
+    fn test_pixmap_autocrop_square() {
+        let cs = Colorspace::device_rgb();
+        let mut pm = Pixmap::new(Some(cs), 20, 10, false).unwrap();
+        pm.clear(255);
+        let stride = pm.stride();
+        let samples = pm.samples_mut();
+        for y in 3..7 {
+            for x in 5..9 {
+                let o = y * stride + x * 3;
+                samples[o..o + 3].copy_from_slice(&[250, 2, 0]);
+            }
+        }
+        // A near-white speck within tolerance is treated as background
+        samples[0..3].copy_from_slice(&[252, 252, 252]);
+
+        let cropped = pm.autocrop(&[255, 255, 255], 8).unwrap();
+        assert_eq!((cropped.width(), cropped.height()), (4, 4));
+        assert!(cropped.samples().chunks(3).all(|px| px == [250, 2, 0]));
+    }
+
+    #[test]
+    fn test_sub_pixmap_crops_inner_rect() {
+        let mut pm = Pixmap::new(Some(Colorspace::device_gray()), 4, 3, false).unwrap();
+        for (i, v) in pm.samples_mut().iter_mut().enumerate() {
+            *v = i as u8;
+        }
+        let inner = pm.sub_pixmap(IRect::new(1, 1, 3, 3)).unwrap();
+        assert_eq!((inner.width(), inner.height()), (2, 2));
+        assert_eq!(inner.samples(), [5, 6, 9, 10]);
+
+        // Clamped to the pixmap, and nothing left is an error
+        let edge = pm.sub_pixmap(IRect::new(2, -5, 10, 1)).unwrap();
+        assert_eq!(edge.samples(), [2, 3]);
+        assert!(pm.sub_pixmap(IRect::new(4, 0, 8, 3)).is_err());
+    }
+
+    #[test]
+    fn test_pixmap_autocrop_uniform() {
+        let cs = Colorspace::device_rgb();
+        let mut pm = Pixmap::new(Some(cs), 8, 8, false).unwrap();
+        pm.clear(255);
+
+        let cropped = pm.autocrop(&[255, 255, 255], 0).unwrap();
+        assert_eq!((cropped.width(), cropped.height()), (8, 8));
+        assert!(pm.autocrop(&[255], 0).is_err());
+    }
+
+    fn checkerboard() -> Pixmap {
+        let mut pm = Pixmap::new(Some(Colorspace::device_gray()), 4, 4, false).unwrap();
+        for (i, px) in pm.samples_mut().iter_mut().enumerate() {
+            *px = if (i % 4 + i / 4) % 2 == 0 { 0 } else { 255 };
+        }
+        pm
+    }
+
+    #[test]
+    fn test_pixmap_scale_box_down() {
+        let pm = checkerboard();
+        let small = pm.scale(2, 2, ScaleFilter::Box).unwrap();
+        assert_eq!((small.width(), small.height(), small.n()), (2, 2, 1));
+        assert_eq!(small.samples(), &[128, 128, 128, 128]);
+
+        let dot = pm.scale(1, 1, ScaleFilter::Box).unwrap();
+        assert_eq!(dot.samples(), &[128]);
+
+        let same = pm.scale(4, 4, ScaleFilter::Bilinear).unwrap();
+        assert_eq!(same.samples(), pm.samples());
+        assert!(pm.scale(0, 4, ScaleFilter::Box).is_err());
+    }
+
+    #[test]
+    fn test_pixmap_scale_bilinear_up() {
+        let pm = checkerboard();
+        let big = pm.scale(8, 8, ScaleFilter::Bilinear).unwrap();
+        assert_eq!((big.width(), big.height()), (8, 8));
+        // Edges clamp to the source, so corners keep their values
+        assert_eq!(big.get_pixel(0, 0), Some(&[0][..]));
+        assert_eq!(big.get_pixel(7, 0), Some(&[255][..]));
+        assert_eq!(big.get_pixel(7, 7), Some(&[0][..]));
+        // Interior samples blend neighbouring squares
+        assert!(big.samples().iter().any(|&v| v > 0 && v < 255));
+
+        let mut rgba = Pixmap::new(Some(Colorspace::device_rgb()), 2, 2, true).unwrap();
+        rgba.clear(200);
+        let scaled = rgba.scale(5, 3, ScaleFilter::Box).unwrap();
+        assert_eq!(scaled.n(), 4);
+        assert!(scaled.has_alpha());
+        assert!(scaled.samples().iter().all(|&v| v == 200));
+    }
+
+    #[test]
+    fn test_pixmap_blend_over() {
+        let rgb = Colorspace::device_rgb();
+        let mut bg = Pixmap::new(Some(rgb.clone()), 4, 4, true).unwrap();
+        bg.clear(255);
+        let mut patch = Pixmap::new(Some(rgb), 2, 2, true).unwrap();
+        for px in patch.samples_mut().chunks_exact_mut(4) {
+            px.copy_from_slice(&[255, 0, 0, 128]);
+        }
+
+        // Partly off the top-left edge: only the bottom-right patch pixel lands
+        bg.blend_over(&patch, -1, -1).unwrap();
+        assert_eq!(bg.get_pixel(0, 0), Some(&[255, 127, 127, 255][..]));
+        assert_eq!(bg.get_pixel(1, 0), Some(&[255, 255, 255, 255][..]));
+
+        bg.blend_over(&patch, 2, 2).unwrap();
+        assert_eq!(bg.get_pixel(3, 3), Some(&[255, 127, 127, 255][..]));
+
+        let gray = Pixmap::new(Some(Colorspace::device_gray()), 2, 2, true).unwrap();
+        assert!(bg.blend_over(&gray, 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_pixmap_invert_and_gamma() {
+        let mut pm = Pixmap::new(Some(Colorspace::device_gray()), 1, 1, true).unwrap();
+        pm.samples_mut().copy_from_slice(&[100, 200]);
+        pm.invert();
+        assert_eq!(pm.samples(), &[155, 200]);
+
+        let mut pm = Pixmap::new(Some(Colorspace::device_gray()), 1, 1, false).unwrap();
+        pm.clear(128);
+        pm.gamma(2.2);
+        assert_eq!(pm.samples(), &[56]);
+        pm.gamma(1.0);
+        assert_eq!(pm.samples(), &[56]);
+    }
+
+    #[test]
+    fn test_pixmap_tint_ramp() {
+        let mut pm = Pixmap::new(Some(Colorspace::device_rgb()), 3, 1, false).unwrap();
+        pm.samples_mut()
+            .copy_from_slice(&[0, 0, 0, 128, 128, 128, 255, 255, 255]);
+        pm.tint([0, 0, 255], [255, 255, 0]).unwrap();
+        assert_eq!(pm.get_pixel(0, 0), Some(&[0, 0, 255][..]));
+        assert_eq!(pm.get_pixel(1, 0), Some(&[128, 128, 127][..]));
+        assert_eq!(pm.get_pixel(2, 0), Some(&[255, 255, 0][..]));
+
+        let mut cmyk = Pixmap::new(Some(Colorspace::device_cmyk()), 1, 1, false).unwrap();
+        assert!(cmyk.tint([0; 3], [255; 3]).is_err());
+        cmyk.invert();
+        assert_eq!(cmyk.samples(), &[255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_spot_channel_composites_at_full_tint() {
+        use crate::fitz::separation::{Separation, TintTransform};
+
+        let mut seps = Separations::new();
+        seps.add(Separation::new(
+            "PANTONE 300 C",
+            TintTransform::Cmyk([1.0, 0.44, 0.0, 0.0]),
+        ));
+        let mut pm = Pixmap::with_separations(Colorspace::device_cmyk(), seps, 2, 1, true).unwrap();
+        assert_eq!((pm.process_n(), pm.spot_n(), pm.n()), (4, 1, 6));
+        assert_eq!(pm.stride(), 12);
+
+        // Left pixel: full spot ink only; right pixel: no ink at all
+        pm.samples_mut()
+            .copy_from_slice(&[0, 0, 0, 0, 255, 255, 0, 0, 0, 0, 0, 128]);
+        let rgb = pm.composite_to_rgb().unwrap();
+        assert_eq!(rgb.n(), 4);
+        assert_eq!(rgb.get_pixel(0, 0).unwrap(), [0, 143, 255, 255]);
+        assert_eq!(rgb.get_pixel(1, 0).unwrap(), [255, 255, 255, 128]);
+
+        // Derived pixmaps keep the spot channel
+        let scaled = pm.scale(4, 2, ScaleFilter::Bilinear).unwrap();
+        assert_eq!((scaled.spot_n(), scaled.n()), (1, 6));
+    }
 }
@@ -13,21 +13,31 @@
 //! - Blending with alpha compositing
 
 use crate::fitz::colorspace::Colorspace;
+use crate::fitz::device::{BlendMode, Device};
+use crate::fitz::display_list::DisplayList;
 use crate::fitz::geometry::{Matrix, Point, Rect};
-use crate::fitz::path::{LineCap, LineJoin, Path, PathElement};
+use crate::fitz::image::Image;
+use crate::fitz::path::{LineCap, LineJoin, Path, PathElement, StrokeState};
 use crate::fitz::pixmap::Pixmap;
+use crate::fitz::text::Text;
+
+/// Largest pattern cell drawn, in pixels per side
+const MAX_TILE_SIZE: i32 = 4096;
+
+/// Most pattern cells repeated for one tiled fill
+const MAX_TILES: i64 = 1 << 20;
 
 /// Edge for scan-line conversion
 #[derive(Debug, Clone)]
 struct Edge {
-    /// Starting x coordinate
+    /// x coordinate at the top of the edge
     x: f32,
-    /// Starting y coordinate (integer scan line)
-    y: i32,
-    /// Change in x per scan line
+    /// Top y coordinate
+    y0: f32,
+    /// Bottom y coordinate
+    y1: f32,
+    /// Change in x per unit of y
     dx: f32,
-    /// Height of edge in scan lines
-    height: i32,
     /// Direction: +1 for down, -1 for up
     direction: i32,
 }
@@ -45,49 +55,32 @@ impl Edge {
             return None;
         };
 
-        let y0 = p0.y.floor() as i32;
-        let y1 = p1.y.floor() as i32;
-        let height = y1 - y0;
-
-        if height <= 0 {
-            return None;
-        }
-
-        // Calculate dx/dy
-        let dy = p1.y - p0.y;
-        let dx = if dy.abs() > 0.0001 {
-            (p1.x - p0.x) / dy
-        } else {
-            0.0
-        };
-
         Some(Self {
             x: p0.x,
-            y: y0,
-            dx,
-            height,
+            y0: p0.y,
+            y1: p1.y,
+            dx: (p1.x - p0.x) / (p1.y - p0.y),
             direction,
         })
     }
 
-    /// Step to the next scan line
-    fn step(&mut self) {
-        self.x += self.dx;
-        self.y += 1;
-        self.height -= 1;
+    /// x coordinate where the edge crosses `y`
+    fn x_at(&self, y: f32) -> f32 {
+        self.x + (y - self.y0) * self.dx
     }
 
-    /// Check if edge is still active
-    fn is_active(&self) -> bool {
-        self.height > 0
+    /// Whether the edge crosses the horizontal line at `y`
+    fn crosses(&self, y: f32) -> bool {
+        self.y0 <= y && y < self.y1
     }
 }
 
-/// Active edge for scan-line algorithm
-#[derive(Debug, Clone)]
-struct ActiveEdge {
-    edge: Edge,
-    winding: i32,
+/// Device color and opacity of a fill
+struct Paint<'a> {
+    color: &'a [u8],
+    alpha: f32,
+    /// Gray coverage that scales the alpha of each pixel
+    mask: Option<&'a Pixmap>,
 }
 
 /// Rasterizer for converting paths to pixels
@@ -98,7 +91,8 @@ pub struct Rasterizer {
     height: i32,
     /// Clip rectangle
     clip: Rect,
-    /// Anti-aliasing level (1 = no AA, 2 = 2x2, 4 = 4x4, 8 = 8x8)
+    /// Anti-aliasing bits, 0-8: edges are sampled on a grid of
+    /// `2^(bits/2)` by `2^(bits/2)` points per pixel
     aa_level: i32,
 }
 
@@ -109,13 +103,23 @@ impl Rasterizer {
             width,
             height,
             clip,
-            aa_level: 8, // Default to 8x8 supersampling
+            aa_level: 8, // Default to 16x16 supersampling
         }
     }
 
-    /// Set anti-aliasing level
-    pub fn set_aa_level(&mut self, level: i32) {
-        self.aa_level = level.max(1).min(8);
+    /// Set the anti-aliasing level in bits, 0 (aliased) to 8
+    pub fn set_aa_level(&mut self, bits: i32) {
+        self.aa_level = bits.clamp(0, 8);
+    }
+
+    /// Anti-aliasing level in bits
+    pub fn aa_level(&self) -> i32 {
+        self.aa_level
+    }
+
+    /// Samples per pixel along each axis
+    fn subsamples(&self) -> i32 {
+        1 << (self.aa_level / 2)
     }
 
     /// Fill a path into a pixmap
@@ -128,6 +132,24 @@ impl Rasterizer {
         color: &[f32],
         alpha: f32,
         dest: &mut Pixmap,
+    ) {
+        self.fill_path_masked(path, even_odd, ctm, colorspace, color, alpha, None, dest);
+    }
+
+    /// Fill a path into a pixmap through a clip mask
+    ///
+    /// `mask` is a gray pixmap the size of `dest`; each pixel's coverage
+    /// scales the fill's alpha there.
+    pub fn fill_path_masked(
+        &self,
+        path: &Path,
+        even_odd: bool,
+        ctm: &Matrix,
+        colorspace: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        mask: Option<&Pixmap>,
+        dest: &mut Pixmap,
     ) {
         // Transform path by CTM
         let transformed_path = self.transform_path(path, ctm);
@@ -140,15 +162,19 @@ impl Rasterizer {
         }
 
         // Sort edges by starting y coordinate
-        edges.sort_by_key(|e| e.y);
+        edges.sort_by(|a, b| a.y0.total_cmp(&b.y0));
 
         // Convert color to destination colorspace
-        let default_cs = Colorspace::device_rgb();
-        let dest_cs = dest.colorspace().unwrap_or(&default_cs);
-        let pixel_color = self.convert_color(colorspace, color, dest_cs, alpha);
+        let dest_n = dest.colorspace().map_or(3, |cs| cs.n() as usize);
+        let pixel_color = self.convert_color(colorspace, color, dest_n);
 
         // Scan-line conversion
-        self.scan_convert(&edges, even_odd, &pixel_color, dest);
+        let paint = Paint {
+            color: &pixel_color,
+            alpha: alpha.clamp(0.0, 1.0),
+            mask,
+        };
+        self.scan_convert(&edges, even_odd, &paint, dest);
     }
 
     /// Stroke a path into a pixmap
@@ -161,18 +187,44 @@ impl Rasterizer {
         color: &[f32],
         alpha: f32,
         dest: &mut Pixmap,
+    ) {
+        self.stroke_path_masked(
+            path,
+            stroke_state,
+            ctm,
+            colorspace,
+            color,
+            alpha,
+            None,
+            dest,
+        );
+    }
+
+    /// Stroke a path into a pixmap through a clip mask, see
+    /// [`fill_path_masked`](Self::fill_path_masked)
+    pub fn stroke_path_masked(
+        &self,
+        path: &Path,
+        stroke_state: &crate::fitz::path::StrokeState,
+        ctm: &Matrix,
+        colorspace: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        mask: Option<&Pixmap>,
+        dest: &mut Pixmap,
     ) {
         // Expand stroke to a filled path
         let stroked_path = self.expand_stroke(path, stroke_state, ctm);
 
         // Fill the stroked path
-        self.fill_path(
+        self.fill_path_masked(
             &stroked_path,
             false,             // Always use non-zero winding for strokes
             &Matrix::IDENTITY, // Already transformed
             colorspace,
             color,
             alpha,
+            mask,
             dest,
         );
     }
@@ -381,127 +433,120 @@ impl Rasterizer {
     }
 
     /// Scan-line conversion algorithm
-    fn scan_convert(&self, edges: &[Edge], even_odd: bool, color: &[u8], dest: &mut Pixmap) {
-        if edges.is_empty() {
+    ///
+    /// Each pixel row is sampled on `subsamples()` sub-scanlines, and each
+    /// span on those at `subsamples()` points per pixel; the fraction of
+    /// samples inside the path scales the paint's alpha.
+    fn scan_convert(&self, edges: &[Edge], even_odd: bool, paint: &Paint, dest: &mut Pixmap) {
+        let s = self.subsamples();
+        let x_min = (self.clip.x0.max(0.0) as i32).min(dest.width());
+        let x_max = (self.clip.x1.min(dest.width() as f32) as i32).max(x_min);
+        let y_min = edges
+            .iter()
+            .map(|e| e.y0)
+            .fold(f32::INFINITY, f32::min)
+            .max(self.clip.y0)
+            .max(0.0)
+            .floor() as i32;
+        let y_max = edges
+            .iter()
+            .map(|e| e.y1)
+            .fold(f32::NEG_INFINITY, f32::max)
+            .min(self.clip.y1)
+            .min(dest.height() as f32)
+            .ceil() as i32;
+        if x_min >= x_max {
             return;
         }
 
-        let mut active_edges: Vec<ActiveEdge> = Vec::new();
-        let mut edge_index = 0;
-
-        // Find y range
-        let min_y = edges.first().unwrap().y.max(self.clip.y0 as i32);
-        let max_y = edges
-            .iter()
-            .map(|e| e.y + e.height)
-            .max()
-            .unwrap_or(0)
-            .min(self.clip.y1 as i32);
-
-        // Process each scan line
-        for y in min_y..max_y {
-            // Add new edges that start on this scan line
-            while edge_index < edges.len() && edges[edge_index].y <= y {
-                active_edges.push(ActiveEdge {
-                    edge: edges[edge_index].clone(),
-                    winding: 0,
-                });
-                edge_index += 1;
+        let mut coverage = vec![0u32; (x_max - x_min) as usize];
+        let mut crossings: Vec<(f32, i32)> = Vec::new();
+        let mut next_edge = 0;
+        let mut active: Vec<&Edge> = Vec::new();
+
+        for y in y_min..y_max {
+            // Edges overlapping this pixel row
+            let row_bottom = (y + 1) as f32;
+            while next_edge < edges.len() && edges[next_edge].y0 < row_bottom {
+                active.push(&edges[next_edge]);
+                next_edge += 1;
             }
-
-            // Remove finished edges
-            active_edges.retain(|ae| ae.edge.is_active());
-
-            if active_edges.is_empty() {
+            active.retain(|e| e.y1 > y as f32);
+            if active.is_empty() {
                 continue;
             }
 
-            // Sort active edges by x coordinate
-            active_edges.sort_by(|a, b| a.edge.x.partial_cmp(&b.edge.x).unwrap());
-
-            // Calculate winding numbers
-            let mut winding = 0;
-            for ae in &mut active_edges {
-                winding += ae.edge.direction;
-                ae.winding = winding;
+            for sub in 0..s {
+                let sample_y = y as f32 + (sub as f32 + 0.5) / s as f32;
+                crossings.clear();
+                crossings.extend(
+                    active
+                        .iter()
+                        .filter(|e| e.crosses(sample_y))
+                        .map(|e| (e.x_at(sample_y), e.direction)),
+                );
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                let mut winding = 0;
+                let mut span_start = 0.0;
+                for &(x, direction) in &crossings {
+                    let was_inside = is_inside(winding, even_odd);
+                    winding += direction;
+                    match (was_inside, is_inside(winding, even_odd)) {
+                        (false, true) => span_start = x,
+                        (true, false) => add_span_coverage(&mut coverage, x_min, s, span_start, x),
+                        _ => {}
+                    }
+                }
             }
 
-            // Fill spans
-            self.fill_spans(&active_edges, y, even_odd, color, dest);
-
-            // Step all active edges to next scan line
-            for ae in &mut active_edges {
-                ae.edge.step();
-            }
+            self.blend_row(&mut coverage, x_min, y, s * s, paint, dest);
         }
     }
 
-    /// Fill spans for a single scan line
-    fn fill_spans(
+    /// Paint one row of accumulated coverage and reset it
+    fn blend_row(
         &self,
-        active_edges: &[ActiveEdge],
+        coverage: &mut [u32],
+        x_min: i32,
         y: i32,
-        even_odd: bool,
-        color: &[u8],
+        samples: i32,
+        paint: &Paint,
         dest: &mut Pixmap,
     ) {
-        if y < 0 || y >= dest.height() {
-            return;
-        }
-
-        let mut inside = false;
-        let mut x_start = 0;
-
-        for i in 0..active_edges.len() {
-            let ae = &active_edges[i];
-            let x = ae.edge.x as i32;
+        let stride = dest.stride();
+        let n = dest.n() as usize;
+        let has_alpha = dest.has_alpha();
+        let colorants = paint.color.len().min(n - usize::from(has_alpha));
+        let row = y as usize * stride;
+        let mask_row = paint.mask.map(|mask| {
+            let start = y as usize * mask.stride() + x_min as usize;
+            &mask.samples()[start..start + coverage.len()]
+        });
+        let pixels = dest.samples_mut();
+
+        for (i, cov) in coverage.iter_mut().enumerate() {
+            if *cov == 0 {
+                continue;
+            }
+            let mut a = paint.alpha * (*cov).min(samples as u32) as f32 / samples as f32;
+            *cov = 0;
+            if let Some(mask_row) = mask_row {
+                a *= f32::from(mask_row[i]) / 255.0;
+            }
+            let blend =
+                |dst: u8, src: u8| (f32::from(src) * a + f32::from(dst) * (1.0 - a)).round() as u8;
 
-            // Check if we're inside based on winding rule
-            let new_inside = if even_odd {
-                ae.winding % 2 != 0
-            } else {
-                ae.winding != 0
+            let offset = row + (x_min as usize + i) * n;
+            let Some(pixel) = pixels.get_mut(offset..offset + n) else {
+                break;
             };
-
-            if new_inside != inside {
-                if new_inside {
-                    // Start of span
-                    x_start = x;
-                } else {
-                    // End of span - fill it
-                    self.fill_span(x_start, x, y, color, dest);
-                }
-                inside = new_inside;
+            // Source-over alpha blending
+            for (dst, &src) in pixel.iter_mut().zip(&paint.color[..colorants]) {
+                *dst = blend(*dst, src);
             }
-        }
-    }
-
-    /// Fill a single horizontal span
-    fn fill_span(&self, x0: i32, x1: i32, y: i32, color: &[u8], dest: &mut Pixmap) {
-        let x0 = x0.max(self.clip.x0 as i32).max(0);
-        let x1 = x1.min(self.clip.x1 as i32).min(dest.width());
-
-        if x0 >= x1 {
-            return;
-        }
-
-        let y = y as usize;
-        let stride = dest.stride() as usize;
-        let n = dest.n() as usize;
-
-        // Get pixel data
-        let samples = dest.samples_mut();
-
-        for x in x0..x1 {
-            let offset = y * stride + (x as usize) * n;
-
-            // Simple alpha blending
-            if color.len() >= n {
-                for i in 0..n {
-                    if offset + i < samples.len() {
-                        samples[offset + i] = color[i];
-                    }
-                }
+            if has_alpha {
+                pixel[n - 1] = blend(pixel[n - 1], 255);
             }
         }
     }
@@ -636,98 +681,522 @@ impl Rasterizer {
         result.close();
     }
 
-    /// Convert color from one colorspace to another
-    fn convert_color(
-        &self,
-        src_cs: &Colorspace,
-        src_color: &[f32],
-        dest_cs: &Colorspace,
-        alpha: f32,
-    ) -> Vec<u8> {
-        // Simplified color conversion
+    /// Convert color from one colorspace to another, as bytes for each
+    /// process component of the destination
+    fn convert_color(&self, src_cs: &Colorspace, src_color: &[f32], dest_n: usize) -> Vec<u8> {
+        // Simplified color conversion through RGB
         // TODO: Implement proper ICC profile-based conversion
-
-        let mut result = Vec::new();
-
-        // Convert to RGB first (simplified)
-        let rgb = match (src_cs.color_type(), src_color.len()) {
-            (crate::fitz::colorspace::ColorType::Gray, 1) => {
-                let g = (src_color[0] * 255.0) as u8;
-                vec![g, g, g]
-            }
-            (crate::fitz::colorspace::ColorType::RGB, 3) => {
-                vec![
-                    (src_color[0] * 255.0) as u8,
-                    (src_color[1] * 255.0) as u8,
-                    (src_color[2] * 255.0) as u8,
+        let unit = |v: f32| v.clamp(0.0, 1.0);
+        let rgb = match (src_cs.n(), src_color) {
+            (1, [g, ..]) => [unit(*g); 3],
+            (3, [r, g, b, ..]) => [unit(*r), unit(*g), unit(*b)],
+            (4, [c, m, y, k, ..]) => {
+                let k = 1.0 - unit(*k);
+                [
+                    (1.0 - unit(*c)) * k,
+                    (1.0 - unit(*m)) * k,
+                    (1.0 - unit(*y)) * k,
                 ]
             }
-            (crate::fitz::colorspace::ColorType::CMYK, 4) => {
-                // Simplified CMYK to RGB
-                let c = src_color[0];
-                let m = src_color[1];
-                let y = src_color[2];
-                let k = src_color[3];
-
-                let r = ((1.0 - c) * (1.0 - k) * 255.0) as u8;
-                let g = ((1.0 - m) * (1.0 - k) * 255.0) as u8;
-                let b = ((1.0 - y) * (1.0 - k) * 255.0) as u8;
-
-                vec![r, g, b]
-            }
-            _ => vec![0, 0, 0], // Default to black
+            _ => [0.0; 3], // Default to black
         };
 
-        // Convert RGB to destination colorspace
-        match dest_cs.color_type() {
-            crate::fitz::colorspace::ColorType::Gray => {
-                // RGB to grayscale
-                let gray =
-                    (0.299 * rgb[0] as f32 + 0.587 * rgb[1] as f32 + 0.114 * rgb[2] as f32) as u8;
-                result.push(gray);
-            }
-            crate::fitz::colorspace::ColorType::RGB => {
-                result.extend_from_slice(&rgb);
-            }
-            crate::fitz::colorspace::ColorType::CMYK => {
-                // RGB to CMYK (simplified)
-                let r = rgb[0] as f32 / 255.0;
-                let g = rgb[1] as f32 / 255.0;
-                let b = rgb[2] as f32 / 255.0;
-
-                let k = 1.0 - r.max(g).max(b);
-                let c = if k < 1.0 {
-                    (1.0 - r - k) / (1.0 - k)
-                } else {
-                    0.0
-                };
-                let m = if k < 1.0 {
-                    (1.0 - g - k) / (1.0 - k)
-                } else {
-                    0.0
-                };
-                let y = if k < 1.0 {
-                    (1.0 - b - k) / (1.0 - k)
-                } else {
-                    0.0
+        let components = match dest_n {
+            1 => vec![0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2]],
+            4 => {
+                let k = 1.0 - rgb[0].max(rgb[1]).max(rgb[2]);
+                let ink = |v: f32| {
+                    if k < 1.0 {
+                        (1.0 - v - k) / (1.0 - k)
+                    } else {
+                        0.0
+                    }
                 };
+                vec![ink(rgb[0]), ink(rgb[1]), ink(rgb[2]), k]
+            }
+            _ => rgb.to_vec(),
+        };
+        components
+            .into_iter()
+            .map(|v| (v * 255.0).round() as u8)
+            .collect()
+    }
+}
+
+/// Whether a winding number is inside the path under the fill rule
+fn is_inside(winding: i32, even_odd: bool) -> bool {
+    if even_odd {
+        winding % 2 != 0
+    } else {
+        winding != 0
+    }
+}
+
+/// Count the horizontal samples of one sub-scanline that fall in the span
+/// `x0..x1`, with `s` samples per pixel, into `coverage` (which starts at
+/// pixel `x_min`)
+fn add_span_coverage(coverage: &mut [u32], x_min: i32, s: i32, x0: f32, x1: f32) {
+    let first = x_min * s;
+    let last = first + coverage.len() as i32 * s;
+    // Sample k sits at (k + 0.5) / s
+    let to_sample = |x: f32| ((x * s as f32 - 0.5).ceil() as i32).clamp(first, last);
+    let (k0, k1) = (to_sample(x0), to_sample(x1));
+    if k0 >= k1 {
+        return;
+    }
+    let (p0, p1) = (k0 / s - x_min, (k1 - 1) / s - x_min);
+    let (p0u, p1u) = (p0 as usize, p1 as usize);
+    if p0 == p1 {
+        coverage[p0u] += (k1 - k0) as u32;
+        return;
+    }
+    coverage[p0u] += ((p0 + x_min + 1) * s - k0) as u32;
+    for cov in &mut coverage[p0u + 1..p1u] {
+        *cov += s as u32;
+    }
+    coverage[p1u] += (k1 - (p1 + x_min) * s) as u32;
+}
+
+/// Composite a group pixmap over `dest` with a blend mode
+///
+/// Pixmaps with alpha hold premultiplied colors, as the rasterizer leaves
+/// them; the blend itself works on straight colors:
+/// `(1 - αs)·cb + αs·((1 - αb)·Cs + αb·B(Cb, Cs))`.
+fn composite_group(group: &Pixmap, dest: &mut Pixmap, blend: BlendMode, alpha: f32) {
+    let gn = group.n() as usize;
+    let dn = dest.n() as usize;
+    let dest_alpha = dest.has_alpha();
+    let colorants = gn - 1;
+    for (src, dst) in group
+        .samples()
+        .chunks_exact(gn)
+        .zip(dest.samples_mut().chunks_exact_mut(dn))
+    {
+        let coverage = f32::from(src[colorants]) / 255.0;
+        if coverage == 0.0 {
+            continue;
+        }
+        let a_s = coverage * alpha;
+        let a_b = if dest_alpha {
+            f32::from(dst[dn - 1]) / 255.0
+        } else {
+            1.0
+        };
+        for (d, &s) in dst[..colorants].iter_mut().zip(&src[..colorants]) {
+            let cs = (f32::from(s) / 255.0 / coverage).min(1.0);
+            let cb_pm = f32::from(*d) / 255.0;
+            let cb = if a_b > 0.0 {
+                (cb_pm / a_b).min(1.0)
+            } else {
+                0.0
+            };
+            let mixed = (1.0 - a_b) * cs + a_b * blend.blend(cb, cs);
+            *d = (((1.0 - a_s) * cb_pm + a_s * mixed) * 255.0).round() as u8;
+        }
+        if dest_alpha {
+            dst[dn - 1] = ((a_s + a_b - a_s * a_b) * 255.0).round() as u8;
+        }
+    }
+}
 
-                result.push((c * 255.0) as u8);
-                result.push((m * 255.0) as u8);
-                result.push((y * 255.0) as u8);
-                result.push((k * 255.0) as u8);
+/// Draw a pattern cell over `dest` with its top-left corner at `(x, y)`,
+/// through the clip mask `clip`
+fn composite_cell(cell: &Pixmap, dest: &mut Pixmap, x: i32, y: i32, clip: Option<&Pixmap>) {
+    let cn = cell.n() as usize;
+    let dn = dest.n() as usize;
+    let dest_alpha = dest.has_alpha();
+    let colorants = cn - 1;
+    let (dw, dh) = (dest.width(), dest.height());
+    let dest_stride = dest.stride();
+    let pixels = dest.samples_mut();
+    for (row, src_row) in cell.samples().chunks_exact(cell.stride()).enumerate() {
+        let dy = y + row as i32;
+        if dy < 0 || dy >= dh {
+            continue;
+        }
+        for (col, src) in src_row.chunks_exact(cn).enumerate() {
+            let dx = x + col as i32;
+            if dx < 0 || dx >= dw || src[colorants] == 0 {
+                continue;
             }
-            _ => {
-                result.extend_from_slice(&rgb);
+            let index = dy as usize * dw as usize + dx as usize;
+            let coverage = clip.map_or(1.0, |clip| f32::from(clip.samples()[index]) / 255.0);
+            let a_s = f32::from(src[colorants]) / 255.0 * coverage;
+            let offset = dy as usize * dest_stride + dx as usize * dn;
+            let dst = &mut pixels[offset..offset + dn];
+            // Source-over with a premultiplied source
+            for (d, &s) in dst[..colorants].iter_mut().zip(&src[..colorants]) {
+                *d = (f32::from(s) * coverage + f32::from(*d) * (1.0 - a_s)).round() as u8;
+            }
+            if dest_alpha {
+                dst[dn - 1] = (a_s * 255.0 + f32::from(dst[dn - 1]) * (1.0 - a_s)).round() as u8;
             }
         }
+    }
+}
+
+/// Device that draws into a pixmap
+///
+/// Fills and strokes are rasterized through the current clip; text and
+/// images are not drawn yet.
+///
+/// A soft mask drawn between `begin_mask` and `end_mask` joins the clip
+/// stack and stays in effect until its `pop_clip`. Transparency groups are
+/// drawn on their own transparent pixmap and composited with their blend
+/// mode and alpha at `end_group`; separable blend modes are supported.
+/// A tiling pattern draws one cell between `begin_tile` and `end_tile`,
+/// which is then repeated over the tile area.
+pub struct DrawDevice {
+    pixmap: Pixmap,
+    rasterizer: Rasterizer,
+    /// Clip masks, innermost last: gray coverage pixmaps the size of the
+    /// drawing, each already intersected with the one below it
+    clips: Vec<Pixmap>,
+    /// Soft masks being drawn, innermost last
+    masks: Vec<MaskBuilder>,
+    /// Transparency groups being drawn, innermost last
+    groups: Vec<GroupBuilder>,
+    /// Pattern cells being drawn, innermost last
+    tiles: Vec<TileBuilder>,
+    /// Device space to the current pixmap, which is offset while a
+    /// pattern cell is drawn
+    offset: Matrix,
+}
 
-        // Add alpha channel if destination has it
-        if dest_cs.has_alpha() {
-            result.push((alpha * 255.0) as u8);
+/// A pattern cell between `begin_tile` and `end_tile`; the cell draws
+/// into its own pixmap, with its own clips, while the page waits here
+struct TileBuilder {
+    dest: Pixmap,
+    clips: Vec<Pixmap>,
+    offset: Matrix,
+    /// Device-space position of the cell pixmap's origin
+    origin: Point,
+    /// Device-space area to cover
+    area: Rect,
+    /// Device-space steps between neighbouring cells
+    xstep: Point,
+    ystep: Point,
+}
+
+/// A transparency group between `begin_group` and `end_group`
+struct GroupBuilder {
+    dest: Pixmap,
+    blend: BlendMode,
+    alpha: f32,
+}
+
+/// A soft mask between `begin_mask` and `end_mask`; its group draws into
+/// the device's pixmap while the page waits here
+struct MaskBuilder {
+    dest: Pixmap,
+    luminosity: bool,
+}
+
+impl DrawDevice {
+    pub fn new(pixmap: Pixmap) -> Self {
+        let (w, h) = (pixmap.width(), pixmap.height());
+        Self {
+            rasterizer: Rasterizer::new(w, h, Rect::new(0.0, 0.0, w as f32, h as f32)),
+            pixmap,
+            clips: Vec::new(),
+            masks: Vec::new(),
+            groups: Vec::new(),
+            tiles: Vec::new(),
+            offset: Matrix::IDENTITY,
         }
+    }
 
-        result
+    /// Number of clips currently pushed
+    pub fn clip_depth(&self) -> usize {
+        self.clips.len()
+    }
+
+    /// A blank gray pixmap the size of the drawing
+    fn gray_pixmap(&self, alpha: bool) -> Pixmap {
+        let mut pixmap = Pixmap::new(
+            Some(Colorspace::device_gray()),
+            self.pixmap.width(),
+            self.pixmap.height(),
+            alpha,
+        )
+        .expect("mask has the drawing's size");
+        pixmap.clear(0);
+        pixmap
+    }
+
+    /// Push a clip mask covering what `draw` paints within the current clip
+    fn push_clip_mask(&mut self, draw: impl FnOnce(&Rasterizer, Option<&Pixmap>, &mut Pixmap)) {
+        let mut mask = self.gray_pixmap(false);
+        draw(&self.rasterizer, self.clips.last(), &mut mask);
+        self.clips.push(mask);
+    }
+
+    /// Push a copy of the current clip, for clips that cannot be drawn
+    /// yet, so that every clip still has its `pop_clip`
+    fn push_current_clip(&mut self) {
+        self.push_clip_mask(|_, current, mask| match current {
+            Some(current) => mask.samples_mut().copy_from_slice(current.samples()),
+            None => mask.clear(255),
+        });
+    }
+
+    /// Set the anti-aliasing level in bits: 0 draws aliased edges, 8 the
+    /// smoothest
+    pub fn set_aa_level(&mut self, bits: u8) {
+        self.rasterizer.set_aa_level(i32::from(bits));
+    }
+
+    /// Anti-aliasing level in bits
+    pub fn aa_level(&self) -> u8 {
+        self.rasterizer.aa_level() as u8
+    }
+
+    pub fn pixmap(&self) -> &Pixmap {
+        &self.pixmap
+    }
+
+    /// The finished drawing
+    pub fn into_pixmap(self) -> Pixmap {
+        self.pixmap
+    }
+
+    /// Redraw the part of `list` under `ctm` that falls within `clip`, in
+    /// pixels, leaving the rest of the pixmap as it is
+    ///
+    /// Only commands whose bounds meet `clip` are rasterized, so repainting
+    /// a small dirty area of a page costs little more than the area itself.
+    pub fn render_clipped(&mut self, list: &DisplayList, ctm: &Matrix, clip: Rect) {
+        let mut area = Path::new();
+        area.rect(clip);
+        self.clip_path(&area, false, &Matrix::IDENTITY, clip);
+        list.run_clipped(self, ctm, clip);
+        self.pop_clip();
+    }
+}
+
+impl Device for DrawDevice {
+    fn fill_path(
+        &mut self,
+        path: &Path,
+        even_odd: bool,
+        ctm: &Matrix,
+        colorspace: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+    ) {
+        self.rasterizer.fill_path_masked(
+            path,
+            even_odd,
+            &ctm.concat(&self.offset),
+            colorspace,
+            color,
+            alpha,
+            self.clips.last(),
+            &mut self.pixmap,
+        );
+    }
+    fn stroke_path(
+        &mut self,
+        path: &Path,
+        stroke: &StrokeState,
+        ctm: &Matrix,
+        colorspace: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+    ) {
+        self.rasterizer.stroke_path_masked(
+            path,
+            stroke,
+            &ctm.concat(&self.offset),
+            colorspace,
+            color,
+            alpha,
+            self.clips.last(),
+            &mut self.pixmap,
+        );
+    }
+    fn clip_path(&mut self, path: &Path, even_odd: bool, ctm: &Matrix, _: Rect) {
+        let white = Colorspace::device_gray();
+        let ctm = &ctm.concat(&self.offset);
+        self.push_clip_mask(|rasterizer, current, mask| {
+            rasterizer.fill_path_masked(path, even_odd, ctm, &white, &[1.0], 1.0, current, mask);
+        });
+    }
+    fn clip_stroke_path(&mut self, path: &Path, stroke: &StrokeState, ctm: &Matrix, _: Rect) {
+        let white = Colorspace::device_gray();
+        let ctm = &ctm.concat(&self.offset);
+        self.push_clip_mask(|rasterizer, current, mask| {
+            rasterizer.stroke_path_masked(path, stroke, ctm, &white, &[1.0], 1.0, current, mask);
+        });
+    }
+    fn fill_text(&mut self, _: &Text, _: &Matrix, _: &Colorspace, _: &[f32], _: f32) {}
+    fn stroke_text(
+        &mut self,
+        _: &Text,
+        _: &StrokeState,
+        _: &Matrix,
+        _: &Colorspace,
+        _: &[f32],
+        _: f32,
+    ) {
+    }
+    fn clip_text(&mut self, _: &Text, _: &Matrix, _: Rect) {
+        self.push_current_clip();
+    }
+    fn clip_stroke_text(&mut self, _: &Text, _: &StrokeState, _: &Matrix, _: Rect) {
+        self.push_current_clip();
+    }
+    fn ignore_text(&mut self, _: &Text, _: &Matrix) {}
+    fn fill_image(&mut self, _: &Image, _: &Matrix, _: f32) {}
+    fn fill_image_mask(&mut self, _: &Image, _: &Matrix, _: &Colorspace, _: &[f32], _: f32) {}
+    fn clip_image_mask(&mut self, _: &Image, _: &Matrix, _: Rect) {
+        self.push_current_clip();
+    }
+    fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+    fn begin_mask(&mut self, _: Rect, luminosity: bool, colorspace: &Colorspace, color: &[f32]) {
+        // Luminosity masks start from the backdrop color, alpha masks from
+        // full transparency
+        let mut group = self.gray_pixmap(!luminosity);
+        if luminosity && !color.is_empty() {
+            group.clear(self.rasterizer.convert_color(colorspace, color, 1)[0]);
+        }
+        let dest = std::mem::replace(&mut self.pixmap, group);
+        self.masks.push(MaskBuilder { dest, luminosity });
+    }
+    fn end_mask(&mut self) {
+        let Some(builder) = self.masks.pop() else {
+            return;
+        };
+        let group = std::mem::replace(&mut self.pixmap, builder.dest);
+        let values = group
+            .samples()
+            .iter()
+            .skip(usize::from(!builder.luminosity));
+        let step = group.n() as usize;
+        self.push_clip_mask(|_, current, mask| {
+            let current = current.map(Pixmap::samples);
+            for (i, (dst, &value)) in mask
+                .samples_mut()
+                .iter_mut()
+                .zip(values.step_by(step))
+                .enumerate()
+            {
+                *dst = match current {
+                    Some(clip) => ((u32::from(value) * u32::from(clip[i]) + 127) / 255) as u8,
+                    None => value,
+                };
+            }
+        });
+    }
+    fn begin_group(
+        &mut self,
+        _: Rect,
+        _: Option<&Colorspace>,
+        _: bool,
+        _: bool,
+        blend: BlendMode,
+        alpha: f32,
+    ) {
+        // Groups are drawn as isolated, starting fully transparent
+        let mut group = Pixmap::new(
+            self.pixmap.colorspace().cloned(),
+            self.pixmap.width(),
+            self.pixmap.height(),
+            true,
+        )
+        .expect("group has the drawing's size");
+        group.clear(0);
+        let dest = std::mem::replace(&mut self.pixmap, group);
+        self.groups.push(GroupBuilder {
+            dest,
+            blend,
+            alpha: alpha.clamp(0.0, 1.0),
+        });
+    }
+    fn end_group(&mut self) {
+        let Some(builder) = self.groups.pop() else {
+            return;
+        };
+        let group = std::mem::replace(&mut self.pixmap, builder.dest);
+        composite_group(&group, &mut self.pixmap, builder.blend, builder.alpha);
+    }
+    fn begin_tile(&mut self, area: Rect, view: Rect, xstep: f32, ystep: f32, ctm: &Matrix) -> i32 {
+        let bounds = view.transform(ctm);
+        let (x0, y0) = (bounds.x0.floor(), bounds.y0.floor());
+        let size = |from: f32, to: f32| ((to.ceil() - from) as i32).clamp(1, MAX_TILE_SIZE);
+        let mut cell = Pixmap::new(
+            self.pixmap.colorspace().cloned(),
+            size(x0, bounds.x1),
+            size(y0, bounds.y1),
+            true,
+        )
+        .expect("cell size is clamped");
+        cell.clear(0);
+        self.tiles.push(TileBuilder {
+            dest: std::mem::replace(&mut self.pixmap, cell),
+            clips: std::mem::take(&mut self.clips),
+            offset: std::mem::replace(&mut self.offset, Matrix::translate(-x0, -y0)),
+            origin: Point::new(x0, y0),
+            area,
+            xstep: Point::new(xstep * ctm.a, xstep * ctm.b),
+            ystep: Point::new(ystep * ctm.c, ystep * ctm.d),
+        });
+        0
+    }
+    fn end_tile(&mut self) {
+        let Some(tile) = self.tiles.pop() else {
+            return;
+        };
+        let cell = std::mem::replace(&mut self.pixmap, tile.dest);
+        self.clips = tile.clips;
+        self.offset = tile.offset;
+
+        // Cells whose origin lies within a cell's size of the area
+        let (w, h) = (self.pixmap.width() as f32, self.pixmap.height() as f32);
+        let visible =
+            Rect::new(0.0, 0.0, w, h).transform(&self.offset.invert().unwrap_or_default());
+        let area = tile.area.intersect(&visible);
+        if area.is_empty() {
+            return;
+        }
+        let origins = Rect::new(
+            area.x0 - cell.width() as f32,
+            area.y0 - cell.height() as f32,
+            area.x1,
+            area.y1,
+        );
+        let lattice = Matrix::new(
+            tile.xstep.x,
+            tile.xstep.y,
+            tile.ystep.x,
+            tile.ystep.y,
+            tile.origin.x,
+            tile.origin.y,
+        );
+        let Some(steps) = lattice.invert().map(|inv| origins.transform(&inv)) else {
+            return;
+        };
+        let (i0, i1) = (steps.x0.floor() as i64, steps.x1.ceil() as i64);
+        let (j0, j1) = (steps.y0.floor() as i64, steps.y1.ceil() as i64);
+        if (i1 - i0 + 1).saturating_mul(j1 - j0 + 1) > MAX_TILES {
+            return;
+        }
+        for j in j0..=j1 {
+            for i in i0..=i1 {
+                let at = lattice
+                    .concat(&self.offset)
+                    .transform_point(Point::new(i as f32, j as f32));
+                composite_cell(
+                    &cell,
+                    &mut self.pixmap,
+                    at.x.round() as i32,
+                    at.y.round() as i32,
+                    self.clips.last(),
+                );
+            }
+        }
     }
 }
 
@@ -741,9 +1210,14 @@ mod tests {
         let p1 = Point::new(10.0, 10.0);
 
         let edge = Edge::new(p0, p1).unwrap();
-        assert_eq!(edge.y, 0);
+        assert_eq!(edge.y0, 0.0);
+        assert_eq!(edge.y1, 10.0);
         assert_eq!(edge.direction, 1);
-        assert_eq!(edge.height, 10);
+
+        // Upward edges are flipped and remember their direction
+        let edge = Edge::new(p1, p0).unwrap();
+        assert_eq!(edge.y0, 0.0);
+        assert_eq!(edge.direction, -1);
     }
 
     #[test]
@@ -755,18 +1229,14 @@ mod tests {
     }
 
     #[test]
-    fn test_edge_step() {
+    fn test_edge_crossing() {
         let p0 = Point::new(0.0, 0.0);
         let p1 = Point::new(10.0, 10.0);
 
-        let mut edge = Edge::new(p0, p1).unwrap();
-        let initial_x = edge.x;
-
-        edge.step();
-
-        assert_eq!(edge.y, 1);
-        assert!(edge.x > initial_x);
-        assert_eq!(edge.height, 9);
+        let edge = Edge::new(p0, p1).unwrap();
+        assert_eq!(edge.x_at(2.5), 2.5);
+        assert!(edge.crosses(0.0));
+        assert!(!edge.crosses(10.0));
     }
 
     #[test]
@@ -775,4 +1245,152 @@ mod tests {
         assert_eq!(rast.width, 100);
         assert_eq!(rast.height, 100);
     }
+
+    #[test]
+    fn test_draw_device_fills_with_alpha() {
+        let mut pixmap = Pixmap::new(Some(Colorspace::device_rgb()), 20, 10, false).unwrap();
+        pixmap.clear(255);
+        let mut dev = DrawDevice::new(pixmap);
+
+        let mut square = Path::new();
+        square.rect(Rect::new(0.0, 0.0, 10.0, 10.0));
+        let rgb = Colorspace::device_rgb();
+        dev.fill_path(
+            &square,
+            false,
+            &Matrix::IDENTITY,
+            &rgb,
+            &[1.0, 0.0, 0.0],
+            1.0,
+        );
+        let half = Matrix::translate(10.0, 0.0);
+        dev.fill_path(&square, false, &half, &rgb, &[0.0, 0.0, 1.0], 0.5);
+
+        let pixmap = dev.into_pixmap();
+        let pixel = |x: usize, y: usize| {
+            let i = y * pixmap.stride() + x * 3;
+            &pixmap.samples()[i..i + 3]
+        };
+        assert_eq!(pixel(5, 5), [255, 0, 0]);
+        assert_eq!(pixel(15, 5), [128, 128, 255]);
+    }
+
+    #[test]
+    fn test_aa_level_edge_coverage() {
+        let draw = |bits: u8| {
+            let mut pixmap = Pixmap::new(Some(Colorspace::device_gray()), 20, 20, false).unwrap();
+            pixmap.clear(255);
+            let mut dev = DrawDevice::new(pixmap);
+            dev.set_aa_level(bits);
+            assert_eq!(dev.aa_level(), bits);
+
+            let mut triangle = Path::new();
+            triangle.move_to(Point::new(0.0, 0.0));
+            triangle.line_to(Point::new(20.0, 0.0));
+            triangle.line_to(Point::new(0.0, 20.0));
+            triangle.close();
+            let gray = Colorspace::device_gray();
+            dev.fill_path(&triangle, false, &Matrix::IDENTITY, &gray, &[0.0], 1.0);
+            dev.into_pixmap().samples().to_vec()
+        };
+
+        let aliased = draw(0);
+        assert!(aliased.iter().all(|&v| v == 0 || v == 255));
+        assert!(aliased.contains(&0) && aliased.contains(&255));
+
+        let smooth = draw(8);
+        assert!(smooth.iter().any(|&v| v > 0 && v < 255));
+        // Pixels on the diagonal are half covered
+        assert!((120..=136).contains(&smooth[5 * 20 + 14]));
+    }
+
+    #[test]
+    fn test_blend_mode_groups() {
+        let composite = |blend: BlendMode| {
+            let mut pixmap = Pixmap::new(Some(Colorspace::device_rgb()), 4, 4, false).unwrap();
+            pixmap.clear(128);
+            let mut dev = DrawDevice::new(pixmap);
+            let mut square = Path::new();
+            square.rect(Rect::new(0.0, 0.0, 4.0, 4.0));
+            let gray = Colorspace::device_gray();
+            dev.begin_group(Rect::INFINITE, None, false, false, blend, 1.0);
+            dev.fill_path(&square, false, &Matrix::IDENTITY, &gray, &[0.5], 1.0);
+            dev.end_group();
+            dev.into_pixmap().samples()[..3].to_vec()
+        };
+
+        // 0.5 · 0.5 and 0.5 + 0.5 - 0.5 · 0.5, from 128/255 over 128/255
+        let to_byte = |v: f32| (v * 255.0).round() as u8;
+        let half = 128.0 / 255.0;
+        assert_eq!(composite(BlendMode::Multiply), [to_byte(half * half); 3]);
+        assert_eq!(
+            composite(BlendMode::Screen),
+            [to_byte(half + half - half * half); 3]
+        );
+        assert_eq!(composite(BlendMode::Normal), [128; 3]);
+    }
+
+    #[test]
+    fn test_fill_under_rect_clip() {
+        let mut pixmap = Pixmap::new(Some(Colorspace::device_gray()), 20, 20, false).unwrap();
+        pixmap.clear(255);
+        let mut dev = DrawDevice::new(pixmap);
+        let gray = Colorspace::device_gray();
+        let rect = |r: Rect| {
+            let mut path = Path::new();
+            path.rect(r);
+            path
+        };
+
+        let clip = rect(Rect::new(5.0, 5.0, 15.0, 10.0));
+        dev.clip_path(&clip, false, &Matrix::IDENTITY, Rect::INFINITE);
+        assert_eq!(dev.clip_depth(), 1);
+        let page = rect(Rect::new(0.0, 0.0, 20.0, 20.0));
+        dev.fill_path(&page, false, &Matrix::IDENTITY, &gray, &[0.0], 1.0);
+        dev.pop_clip();
+        assert_eq!(dev.clip_depth(), 0);
+
+        let samples = dev.into_pixmap().samples().to_vec();
+        for y in 0..20 {
+            for x in 0..20 {
+                let inside = (5..15).contains(&x) && (5..10).contains(&y);
+                let expected = if inside { 0 } else { 255 };
+                assert_eq!(samples[y * 20 + x], expected, "pixel ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_clipped_paints_only_dirty_area() {
+        use crate::fitz::device::BBoxDevice;
+        use crate::fitz::display_list::ListDevice;
+
+        // Three black squares side by side, recorded at half size
+        let mut recorder = ListDevice::new(Rect::new(0.0, 0.0, 20.0, 5.0));
+        let gray = Colorspace::device_gray();
+        for x in [0.0, 7.5, 15.0] {
+            let mut square = Path::new();
+            square.rect(Rect::new(x, 0.0, x + 5.0, 5.0));
+            recorder.fill_path(&square, false, &Matrix::IDENTITY, &gray, &[0.0], 1.0);
+        }
+        let list = recorder.into_display_list();
+        let ctm = Matrix::scale(2.0, 2.0);
+        let dirty = Rect::new(15.0, 0.0, 25.0, 10.0);
+
+        let mut bbox = BBoxDevice::new();
+        list.run_clipped(&mut bbox, &ctm, dirty);
+        assert_eq!(bbox.bbox(), Rect::new(15.0, 0.0, 25.0, 10.0));
+
+        let mut pixmap = Pixmap::new(Some(Colorspace::device_gray()), 40, 10, false).unwrap();
+        pixmap.clear(255);
+        let mut dev = DrawDevice::new(pixmap);
+        dev.render_clipped(&list, &ctm, dirty);
+        assert_eq!(dev.clip_depth(), 0);
+        let pixmap = dev.into_pixmap();
+        let row = &pixmap.samples()[5 * 40..6 * 40];
+        for (x, &value) in row.iter().enumerate() {
+            let expected = if (15..25).contains(&x) { 0 } else { 255 };
+            assert_eq!(value, expected, "pixel ({x}, 5)");
+        }
+    }
 }
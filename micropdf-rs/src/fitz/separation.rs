@@ -0,0 +1,130 @@
+//! Separations - named spot colorants carried alongside process colors
+
+use std::sync::Arc;
+
+/// How a spot colorant looks when flattened to RGB
+#[derive(Clone)]
+pub enum TintTransform {
+    /// Full tint equals this CMYK color; lower tints scale the inks
+    Cmyk([f32; 4]),
+    /// Full tint looks like this RGB color; lower tints blend from white
+    Rgb([f32; 3]),
+    /// Any mapping from a 0-1 tint to RGB, such as a PDF tint transform
+    /// through its alternate space
+    Custom(Arc<dyn Fn(f32) -> [f32; 3] + Send + Sync>),
+}
+
+impl TintTransform {
+    /// RGB appearance of the colorant at `tint` (0 = no ink, 1 = full)
+    pub fn to_rgb(&self, tint: f32) -> [f32; 3] {
+        let tint = tint.clamp(0.0, 1.0);
+        let rgb = match self {
+            Self::Cmyk([c, m, y, k]) => {
+                let k = 1.0 - k * tint;
+                [
+                    (1.0 - c * tint) * k,
+                    (1.0 - m * tint) * k,
+                    (1.0 - y * tint) * k,
+                ]
+            }
+            Self::Rgb(rgb) => rgb.map(|v| 1.0 - (1.0 - v) * tint),
+            Self::Custom(f) => f(tint),
+        };
+        rgb.map(|v| v.clamp(0.0, 1.0))
+    }
+}
+
+impl std::fmt::Debug for TintTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cmyk(cmyk) => f.debug_tuple("Cmyk").field(cmyk).finish(),
+            Self::Rgb(rgb) => f.debug_tuple("Rgb").field(rgb).finish(),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// A named spot colorant
+#[derive(Debug, Clone)]
+pub struct Separation {
+    /// Colorant name, e.g. "PANTONE 185 C"
+    pub name: String,
+    /// Appearance used when compositing
+    pub tint: TintTransform,
+}
+
+impl Separation {
+    pub fn new(name: impl Into<String>, tint: TintTransform) -> Self {
+        Self {
+            name: name.into(),
+            tint,
+        }
+    }
+}
+
+/// The spot colorants of a pixmap, in channel order after the process colors
+#[derive(Debug, Clone, Default)]
+pub struct Separations {
+    seps: Vec<Separation>,
+}
+
+impl Separations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a colorant, returning its index
+    pub fn add(&mut self, sep: Separation) -> usize {
+        self.seps.push(sep);
+        self.seps.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.seps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seps.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Separation> {
+        self.seps.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Separation> {
+        self.seps.iter()
+    }
+
+    /// Index of the colorant called `name`
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.seps.iter().position(|s| s.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tint_transforms() {
+        let red = TintTransform::Rgb([1.0, 0.0, 0.0]);
+        assert_eq!(red.to_rgb(0.0), [1.0, 1.0, 1.0]);
+        assert_eq!(red.to_rgb(0.5), [1.0, 0.5, 0.5]);
+
+        let cyan = TintTransform::Cmyk([1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(cyan.to_rgb(1.0), [0.0, 1.0, 1.0]);
+
+        let custom = TintTransform::Custom(Arc::new(|t| [t, t, t]));
+        assert_eq!(custom.to_rgb(2.0), [1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_separations_lookup() {
+        let mut seps = Separations::new();
+        seps.add(Separation::new("Gold", TintTransform::Rgb([0.8, 0.6, 0.2])));
+        seps.add(Separation::new("Varnish", TintTransform::Rgb([1.0; 3])));
+        assert_eq!(seps.len(), 2);
+        assert_eq!(seps.find("Varnish"), Some(1));
+        assert_eq!(seps.find("Silver"), None);
+    }
+}
@@ -10,8 +10,12 @@
 //! - Word boundaries
 //! - Bounding box tracking
 
-use crate::fitz::geometry::{Matrix, Point, Rect};
-use crate::fitz::text::{BidiDirection, TextItem, TextLanguage, TextSpan};
+use crate::fitz::colorspace::Colorspace;
+use crate::fitz::device::{BlendMode, Device};
+use crate::fitz::geometry::{Matrix, Point, Quad, Rect};
+use crate::fitz::image::Image;
+use crate::fitz::path::{Path, StrokeState};
+use crate::fitz::text::{BidiDirection, Text, TextItem, TextLanguage, TextSpan};
 use std::fmt;
 
 /// Structured text page - top-level container
@@ -59,28 +63,62 @@ impl STextPage {
         result
     }
 
-    /// Search for text on the page
-    pub fn search(&self, needle: &str, case_sensitive: bool) -> Vec<Rect> {
-        let mut results = Vec::new();
-        let search_text = if case_sensitive {
-            needle.to_string()
-        } else {
-            needle.to_lowercase()
+    /// Search for `needle`, returning the glyph quads of each match
+    ///
+    /// Runs of whitespace, including line and block breaks, compare equal to
+    /// a single space, so "hello world" matches across a line break. Each
+    /// match yields the quads of its non-space glyphs, in reading order.
+    pub fn search(&self, needle: &str, case_insensitive: bool) -> Vec<Vec<Quad>> {
+        let fold = |c: char| {
+            if case_insensitive {
+                c.to_lowercase().next().unwrap_or(c)
+            } else {
+                c
+            }
         };
+        let needle: Vec<char> = needle
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .map(fold)
+            .collect();
+        if needle.is_empty() {
+            return Vec::new();
+        }
 
+        // Page text with whitespace collapsed; spaces carry no quad
+        let mut haystack: Vec<(char, Option<Quad>)> = Vec::new();
+        let mut push_space = |haystack: &mut Vec<(char, Option<Quad>)>| {
+            if haystack.last().is_some_and(|&(c, _)| c != ' ') {
+                haystack.push((' ', None));
+            }
+        };
         for block in &self.blocks {
-            let block_text = if case_sensitive {
-                block.get_text()
-            } else {
-                block.get_text().to_lowercase()
-            };
-
-            if block_text.contains(&search_text) {
-                results.push(block.bbox);
+            for line in &block.lines {
+                for ch in &line.chars {
+                    if ch.is_whitespace() {
+                        push_space(&mut haystack);
+                    } else {
+                        haystack.push((fold(ch.c), Some(ch.quad)));
+                    }
+                }
+                push_space(&mut haystack);
             }
         }
 
-        results
+        let mut matches = Vec::new();
+        let mut i = 0;
+        while i + needle.len() <= haystack.len() {
+            let window = &haystack[i..i + needle.len()];
+            if window.iter().map(|&(c, _)| c).eq(needle.iter().copied()) {
+                matches.push(window.iter().filter_map(|&(_, q)| q).collect());
+                i += needle.len();
+            } else {
+                i += 1;
+            }
+        }
+        matches
     }
 
     /// Get the number of characters on the page
@@ -208,9 +246,9 @@ pub struct STextLine {
     pub wmode: WritingMode,
     /// Bounding box
     pub bbox: Rect,
-    /// Baseline Y coordinate (for horizontal) or X (for vertical)
+    /// Offset of the baseline across `dir`: its Y for left-to-right lines
     pub baseline: f32,
-    /// Text direction
+    /// Unit vector along which the glyphs run
     pub dir: Point,
     /// Characters in this line
     pub chars: Vec<STextChar>,
@@ -275,60 +313,6 @@ impl STextLine {
     }
 }
 
-/// Quadrilateral for character bounding box
-#[derive(Debug, Clone, Copy)]
-pub struct Quad {
-    /// Lower-left corner
-    pub ll: Point,
-    /// Lower-right corner
-    pub lr: Point,
-    /// Upper-left corner
-    pub ul: Point,
-    /// Upper-right corner
-    pub ur: Point,
-}
-
-impl Quad {
-    /// Create a new quad
-    pub fn new(ll: Point, lr: Point, ul: Point, ur: Point) -> Self {
-        Self { ll, lr, ul, ur }
-    }
-
-    /// Create a quad from a rectangle
-    pub fn from_rect(rect: &Rect) -> Self {
-        Self {
-            ll: Point::new(rect.x0, rect.y0),
-            lr: Point::new(rect.x1, rect.y0),
-            ul: Point::new(rect.x0, rect.y1),
-            ur: Point::new(rect.x1, rect.y1),
-        }
-    }
-
-    /// Convert quad to axis-aligned bounding box
-    pub fn to_rect(&self) -> Rect {
-        let min_x = self.ll.x.min(self.lr.x).min(self.ul.x).min(self.ur.x);
-        let min_y = self.ll.y.min(self.lr.y).min(self.ul.y).min(self.ur.y);
-        let max_x = self.ll.x.max(self.lr.x).max(self.ul.x).max(self.ur.x);
-        let max_y = self.ll.y.max(self.lr.y).max(self.ul.y).max(self.ur.y);
-        Rect::new(min_x, min_y, max_x, max_y)
-    }
-
-    /// Check if quad contains a point
-    pub fn contains_point(&self, p: Point) -> bool {
-        self.to_rect().contains(p.x, p.y)
-    }
-
-    /// Transform quad by matrix
-    pub fn transform(&self, ctm: &Matrix) -> Self {
-        Self {
-            ll: ctm.transform_point(self.ll),
-            lr: ctm.transform_point(self.lr),
-            ul: ctm.transform_point(self.ul),
-            ur: ctm.transform_point(self.ur),
-        }
-    }
-}
-
 /// Structured text character - a single character with position
 #[derive(Debug, Clone)]
 pub struct STextChar {
@@ -467,41 +451,23 @@ impl STextBuilder {
     }
 
     /// Add a single text item
+    ///
+    /// Glyphs join the current line while they run the same way and sit on
+    /// the same baseline, measured across the direction of the line.
     fn add_text_item(&mut self, item: &TextItem, span: &TextSpan, wmode: WritingMode) {
-        // Convert glyph to character
-        let c = if item.ucs >= 0 {
-            char::from_u32(item.ucs as u32).unwrap_or('?')
-        } else {
-            '?'
-        };
-
-        // Calculate character quad
-        let size = (span.trm.a.abs() + span.trm.b.abs()).max(span.trm.c.abs() + span.trm.d.abs());
-        let origin = Point::new(item.x, item.y);
-        let advance = item.advance;
-
-        // Simplified quad calculation
-        let quad = Quad::from_rect(&Rect::new(
-            origin.x,
-            origin.y - size,
-            origin.x + advance,
-            origin.y,
-        ));
-
+        let (c, quad, size) = text_char(item, &span.trm);
         let ch = STextChar::new(c, quad, size, span.font.name().to_string());
-
-        // Add to current line or create new line
-        if let Some(ref mut line) = self.current_line {
-            // Check if we need a new line (vertical spacing)
-            let baseline_diff = (item.y - line.baseline).abs();
-            if baseline_diff > size * 0.3 {
-                // New line needed
-                self.finish_line();
-                self.start_line(wmode, item.y);
-            }
-        } else {
-            // Start first line
-            self.start_line(wmode, item.y);
+        let (sin, cos) = span.trm.rotation_degrees().to_radians().sin_cos();
+        let dir = Point::new(cos, sin);
+        let baseline = item.y * dir.x - item.x * dir.y;
+
+        let new_line = self.current_line.as_ref().is_none_or(|line| {
+            let turned = line.dir.x * dir.x + line.dir.y * dir.y < 0.99;
+            turned || (baseline - line.baseline).abs() > size * 0.3
+        });
+        if new_line {
+            self.finish_line();
+            self.start_line(wmode, baseline, dir);
         }
 
         if let Some(ref mut line) = self.current_line {
@@ -510,8 +476,9 @@ impl STextBuilder {
     }
 
     /// Start a new line
-    fn start_line(&mut self, wmode: WritingMode, baseline: f32) {
-        let line = STextLine::new(wmode, baseline);
+    fn start_line(&mut self, wmode: WritingMode, baseline: f32, dir: Point) {
+        let mut line = STextLine::new(wmode, baseline);
+        line.dir = dir;
         self.current_line = Some(line);
     }
 
@@ -567,6 +534,230 @@ impl STextBuilder {
     }
 }
 
+/// The character, quad and font size of a glyph placed in device space
+///
+/// The quad spans the glyph's advance along its baseline and the font size
+/// above it.
+fn text_char(item: &TextItem, trm: &Matrix) -> (char, Quad, f32) {
+    let c = u32::try_from(item.ucs)
+        .ok()
+        .and_then(char::from_u32)
+        .unwrap_or('?');
+    let size = (trm.a.abs() + trm.b.abs()).max(trm.c.abs() + trm.d.abs());
+    let origin = Point::new(item.x, item.y);
+    let quad = Quad::from_rect(&Rect::new(
+        origin.x,
+        origin.y - size,
+        origin.x + item.advance,
+        origin.y,
+    ));
+    (c, quad, size)
+}
+
+/// Place the glyphs of `text` in device space, one span per glyph
+///
+/// Advances are measured to the next glyph on the same baseline, falling
+/// back to half the font size.
+fn device_spans(text: &Text, ctm: &Matrix, mut f: impl FnMut(&TextSpan)) {
+    let glyphs: Vec<(&TextSpan, &TextItem, Matrix, Point)> = text
+        .spans()
+        .iter()
+        .flat_map(|span| {
+            let trm = span.trm.concat(ctm);
+            span.items().iter().map(move |item| {
+                (
+                    span,
+                    item,
+                    trm,
+                    ctm.transform_point(Point::new(item.x, item.y)),
+                )
+            })
+        })
+        .collect();
+
+    for (i, &(span, item, trm, pos)) in glyphs.iter().enumerate() {
+        let size = (trm.a.abs() + trm.b.abs()).max(trm.c.abs() + trm.d.abs());
+        let advance = glyphs
+            .get(i + 1)
+            .map(|&(_, _, _, next)| next)
+            .filter(|next| (next.y - pos.y).abs() < size * 0.3 && next.x > pos.x)
+            .map_or(size * 0.5, |next| next.x - pos.x);
+
+        let mut device_span = TextSpan::with_capacity(span.font.clone(), trm, 1);
+        device_span.wmode = span.wmode;
+        device_span.bidi_level = span.bidi_level;
+        device_span.markup_dir = span.markup_dir;
+        device_span.language = span.language;
+        device_span.add_glyph(TextItem::with_advance(
+            pos.x, pos.y, advance, item.gid, item.ucs, item.cid,
+        ));
+        f(&device_span);
+    }
+}
+
+/// Device that collects shown text into a structured text page
+///
+/// Glyph positions are taken in device space.
+pub struct STextDevice {
+    builder: STextBuilder,
+}
+
+impl STextDevice {
+    /// Create a device collecting text for a page with `media_box`
+    pub fn new(media_box: Rect, options: STextOptions) -> Self {
+        Self {
+            builder: STextBuilder::new(media_box, options),
+        }
+    }
+
+    /// Finish collecting and return the structured text page
+    pub fn finish(self) -> STextPage {
+        self.builder.finish()
+    }
+
+    fn add_text(&mut self, text: &Text, ctm: &Matrix) {
+        device_spans(text, ctm, |span| self.builder.add_span(span));
+    }
+}
+
+impl Device for STextDevice {
+    fn fill_path(&mut self, _: &Path, _: bool, _: &Matrix, _: &Colorspace, _: &[f32], _: f32) {}
+    fn stroke_path(
+        &mut self,
+        _: &Path,
+        _: &StrokeState,
+        _: &Matrix,
+        _: &Colorspace,
+        _: &[f32],
+        _: f32,
+    ) {
+    }
+    fn clip_path(&mut self, _: &Path, _: bool, _: &Matrix, _: Rect) {}
+    fn clip_stroke_path(&mut self, _: &Path, _: &StrokeState, _: &Matrix, _: Rect) {}
+    fn fill_text(&mut self, text: &Text, ctm: &Matrix, _: &Colorspace, _: &[f32], _: f32) {
+        self.add_text(text, ctm);
+    }
+    fn stroke_text(
+        &mut self,
+        text: &Text,
+        _: &StrokeState,
+        ctm: &Matrix,
+        _: &Colorspace,
+        _: &[f32],
+        _: f32,
+    ) {
+        self.add_text(text, ctm);
+    }
+    fn clip_text(&mut self, _: &Text, _: &Matrix, _: Rect) {}
+    fn clip_stroke_text(&mut self, _: &Text, _: &StrokeState, _: &Matrix, _: Rect) {}
+    fn ignore_text(&mut self, text: &Text, ctm: &Matrix) {
+        // Invisible text (e.g. an OCR layer) is still searchable
+        self.add_text(text, ctm);
+    }
+    fn fill_image(&mut self, _: &Image, _: &Matrix, _: f32) {}
+    fn fill_image_mask(&mut self, _: &Image, _: &Matrix, _: &Colorspace, _: &[f32], _: f32) {}
+    fn clip_image_mask(&mut self, _: &Image, _: &Matrix, _: Rect) {}
+    fn pop_clip(&mut self) {}
+    fn begin_mask(&mut self, _: Rect, _: bool, _: &Colorspace, _: &[f32]) {}
+    fn end_mask(&mut self) {}
+    fn begin_group(
+        &mut self,
+        _: Rect,
+        _: Option<&Colorspace>,
+        _: bool,
+        _: bool,
+        _: BlendMode,
+        _: f32,
+    ) {
+    }
+    fn end_group(&mut self) {}
+    fn begin_tile(&mut self, _: Rect, _: Rect, _: f32, _: f32, _: &Matrix) -> i32 {
+        0
+    }
+    fn end_tile(&mut self) {}
+}
+
+/// Device that passes each shown character and its quad to a callback
+/// as the text is drawn
+///
+/// Characters are placed exactly as [`STextDevice`] places them, in the
+/// same order, but nothing is kept between text objects.
+pub struct TextCallbackDevice<F: FnMut(char, Quad)> {
+    on_char: F,
+}
+
+impl<F: FnMut(char, Quad)> TextCallbackDevice<F> {
+    /// Create a device calling `on_char` for every character
+    pub fn new(on_char: F) -> Self {
+        Self { on_char }
+    }
+
+    fn add_text(&mut self, text: &Text, ctm: &Matrix) {
+        device_spans(text, ctm, |span| {
+            for item in span.items() {
+                let (c, quad, _) = text_char(item, &span.trm);
+                (self.on_char)(c, quad);
+            }
+        });
+    }
+}
+
+impl<F: FnMut(char, Quad)> Device for TextCallbackDevice<F> {
+    fn fill_path(&mut self, _: &Path, _: bool, _: &Matrix, _: &Colorspace, _: &[f32], _: f32) {}
+    fn stroke_path(
+        &mut self,
+        _: &Path,
+        _: &StrokeState,
+        _: &Matrix,
+        _: &Colorspace,
+        _: &[f32],
+        _: f32,
+    ) {
+    }
+    fn clip_path(&mut self, _: &Path, _: bool, _: &Matrix, _: Rect) {}
+    fn clip_stroke_path(&mut self, _: &Path, _: &StrokeState, _: &Matrix, _: Rect) {}
+    fn fill_text(&mut self, text: &Text, ctm: &Matrix, _: &Colorspace, _: &[f32], _: f32) {
+        self.add_text(text, ctm);
+    }
+    fn stroke_text(
+        &mut self,
+        text: &Text,
+        _: &StrokeState,
+        ctm: &Matrix,
+        _: &Colorspace,
+        _: &[f32],
+        _: f32,
+    ) {
+        self.add_text(text, ctm);
+    }
+    fn clip_text(&mut self, _: &Text, _: &Matrix, _: Rect) {}
+    fn clip_stroke_text(&mut self, _: &Text, _: &StrokeState, _: &Matrix, _: Rect) {}
+    fn ignore_text(&mut self, text: &Text, ctm: &Matrix) {
+        self.add_text(text, ctm);
+    }
+    fn fill_image(&mut self, _: &Image, _: &Matrix, _: f32) {}
+    fn fill_image_mask(&mut self, _: &Image, _: &Matrix, _: &Colorspace, _: &[f32], _: f32) {}
+    fn clip_image_mask(&mut self, _: &Image, _: &Matrix, _: Rect) {}
+    fn pop_clip(&mut self) {}
+    fn begin_mask(&mut self, _: Rect, _: bool, _: &Colorspace, _: &[f32]) {}
+    fn end_mask(&mut self) {}
+    fn begin_group(
+        &mut self,
+        _: Rect,
+        _: Option<&Colorspace>,
+        _: bool,
+        _: bool,
+        _: BlendMode,
+        _: f32,
+    ) {
+    }
+    fn end_group(&mut self) {}
+    fn begin_tile(&mut self, _: Rect, _: Rect, _: f32, _: f32, _: &Matrix) -> i32 {
+        0
+    }
+    fn end_tile(&mut self) {}
+}
+
 impl fmt::Display for STextPage {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.get_text())
@@ -659,4 +850,103 @@ mod tests {
         assert_eq!(words[0], "Hello");
         assert_eq!(words[1], "World");
     }
+
+    #[test]
+    fn test_stext_lines_follow_rotation() {
+        use crate::fitz::font::Font;
+        use std::sync::Arc;
+
+        let font = Arc::new(Font::new("Helvetica"));
+        let mut text = Text::new();
+        let mut show = |c: char, trm: Matrix| {
+            text.show_glyph(
+                font.clone(),
+                trm,
+                c as i32,
+                c as i32,
+                false,
+                0,
+                BidiDirection::Ltr,
+                TextLanguage::Unset,
+            );
+        };
+        // Turned a quarter, running down the page, then level text at the
+        // same height as the first rotated glyph
+        let up = Matrix::scale(10.0, 10.0).concat(&Matrix::rotate(90.0));
+        for (i, c) in "Up".chars().enumerate() {
+            show(
+                c,
+                up.concat(&Matrix::translate(100.0, 100.0 + i as f32 * 6.0)),
+            );
+        }
+        for (i, c) in "level".chars().enumerate() {
+            show(
+                c,
+                Matrix::new(10.0, 0.0, 0.0, 10.0, 110.0 + i as f32 * 6.0, 100.0),
+            );
+        }
+
+        let mut builder = STextBuilder::with_defaults(Rect::new(0.0, 0.0, 612.0, 792.0));
+        device_spans(&text, &Matrix::IDENTITY, |span| builder.add_span(span));
+        let page = builder.finish();
+        let lines: Vec<&STextLine> = page.blocks.iter().flat_map(|b| &b.lines).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].get_text(), "Up");
+        assert!(lines[0].dir.x.abs() < 1e-6 && lines[0].dir.y == 1.0);
+        assert_eq!(lines[1].get_text(), "level");
+        assert_eq!(lines[1].dir, Point::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn test_stext_device_search_across_lines() {
+        use crate::fitz::font::Font;
+        use std::sync::Arc;
+
+        let font = Arc::new(Font::new("Helvetica"));
+        let mut text = Text::new();
+        for (line, y) in [("Say hello", 100.0), ("world, Hello again", 120.0)] {
+            for (i, c) in line.chars().enumerate() {
+                let trm = Matrix::new(10.0, 0.0, 0.0, 10.0, 72.0 + i as f32 * 6.0, y);
+                text.show_glyph(
+                    font.clone(),
+                    trm,
+                    c as i32,
+                    c as i32,
+                    false,
+                    0,
+                    BidiDirection::Ltr,
+                    TextLanguage::Unset,
+                );
+            }
+        }
+
+        let mut device =
+            STextDevice::new(Rect::new(0.0, 0.0, 612.0, 792.0), STextOptions::default());
+        device.fill_text(
+            &text,
+            &Matrix::IDENTITY,
+            &Colorspace::device_gray(),
+            &[0.0],
+            1.0,
+        );
+        let page = device.finish();
+        assert_eq!(page.blocks[0].lines.len(), 2);
+
+        let hits = page.search("hello", true);
+        assert_eq!(hits.len(), 2);
+        for quads in &hits {
+            assert_eq!(quads.len(), 5);
+            let first = quads[0].to_rect();
+            assert!(first.width() > 0.0 && first.height() > 0.0);
+            assert!(quads[1].to_rect().x0 >= first.x0);
+        }
+        assert_eq!(hits[0][0].to_rect().x0, 72.0 + 4.0 * 6.0);
+        assert!(page.search("hello", false).len() == 1);
+
+        // The line break counts as whitespace
+        let spanning = page.search("hello   world", true);
+        assert_eq!(spanning.len(), 1);
+        assert_eq!(spanning[0].len(), 10);
+        assert!(page.search("  ", true).is_empty());
+    }
 }
@@ -38,6 +38,11 @@ pub trait StreamSource: Send + Sync {
     fn is_empty(&self) -> Option<bool> {
         self.len().map(|l| l == 0)
     }
+    /// The whole source as shared bytes, if that is possible without
+    /// reading it.
+    fn bytes(&self) -> Option<Bytes> {
+        None
+    }
 }
 
 /// File-based stream source.
@@ -62,6 +67,16 @@ impl StreamSource for FileSource {
     fn len(&self) -> Option<u64> {
         Some(self.len)
     }
+
+    fn bytes(&self) -> Option<Bytes> {
+        if self.len == 0 {
+            return None;
+        }
+        // SAFETY: The map is read-only; as with any mapping, the file must
+        // not be truncated while it is in use.
+        let map = unsafe { memmap2::Mmap::map(self.reader.get_ref()) }.ok()?;
+        Some(Bytes::from_owner(map))
+    }
 }
 
 /// Memory-based stream source using `bytes::Bytes`.
@@ -102,6 +117,10 @@ impl StreamSource for MemorySource {
     fn len(&self) -> Option<u64> {
         Some(self.data.len() as u64)
     }
+
+    fn bytes(&self) -> Option<Bytes> {
+        Some(self.data.clone())
+    }
 }
 
 const STREAM_BUFFER_SIZE: usize = 8192;
@@ -189,6 +208,13 @@ impl Stream {
         self.eof && self.rp >= self.wp
     }
 
+    /// The whole stream, independent of the read position, without reading
+    /// it: memory streams share their bytes and files are memory-mapped,
+    /// so pages are loaded only as they are touched.
+    pub fn source_bytes(&self) -> Option<Bytes> {
+        self.inner.bytes()
+    }
+
     /// Get the filename if this is a file stream.
     pub fn filename(&self) -> Option<&str> {
         self.filename.as_deref()
@@ -340,21 +366,40 @@ impl Stream {
 
     /// Seek to a position in the stream.
     pub fn seek(&mut self, pos: i64, whence: i32) -> Result<()> {
+        let start = |pos: i64| {
+            u64::try_from(pos)
+                .map(SeekFrom::Start)
+                .map_err(|_| Error::argument("Seek before start"))
+        };
         let seek_from = match whence {
-            0 => SeekFrom::Start(pos as u64),
-            1 => SeekFrom::Current(pos),
+            0 => start(pos)?,
+            // Relative to what the caller has read, not to the source,
+            // which is ahead by whatever is buffered
+            1 => start(self.tell() + pos)?,
             2 => SeekFrom::End(pos),
             _ => return Err(Error::generic("Invalid seek whence")),
         };
 
-        // Clear buffer and seek
+        // Seek, then drop the buffer
+        self.pos = self.inner.seek(seek_from).map_err(Error::System)? as i64;
         self.rp = 0;
         self.wp = 0;
         self.eof = false;
-        self.pos = self.inner.seek(seek_from).map_err(Error::System)? as i64;
         Ok(())
     }
 
+    /// Step back one byte, so the next read returns it again.
+    pub fn unread_byte(&mut self) -> Result<()> {
+        if self.rp > 0 {
+            self.rp -= 1;
+            return Ok(());
+        }
+        match self.tell() {
+            0 => Ok(()),
+            pos => self.seek(pos - 1, 0),
+        }
+    }
+
     /// Read a 16-bit unsigned integer in big-endian format.
     pub fn read_uint16(&mut self) -> Result<u16> {
         let mut buf = [0u8; 2];
@@ -967,6 +1012,19 @@ mod tests {
         assert_eq!(stream.tell(), 0);
     }
 
+    #[test]
+    fn test_stream_seek_current_after_buffered_read() {
+        let mut stream = Stream::open_memory(b"Hello World");
+        assert_eq!(stream.read_byte().unwrap(), Some(b'H'));
+        stream.seek(5, 1).unwrap(); // SEEK_CUR
+        assert_eq!(stream.tell(), 6);
+        assert_eq!(stream.read_byte().unwrap(), Some(b'W'));
+        stream.unread_byte().unwrap();
+        assert_eq!(stream.read_byte().unwrap(), Some(b'W'));
+        assert!(stream.seek(-10, 1).is_err());
+        assert_eq!(stream.tell(), 7);
+    }
+
     #[test]
     fn test_stream_seek_end() {
         let mut stream = Stream::open_memory(b"Hello World");
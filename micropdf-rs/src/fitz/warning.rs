@@ -0,0 +1,29 @@
+//! Recoverable warnings from inside the library
+//!
+//! Parsers and filters that work around a damaged file, such as a wrong
+//! stream /Length or a missing end-of-data marker, report it through
+//! [`warn`] and carry on. Warnings go to stderr until a sink is installed;
+//! the FFI layer installs one that routes them to its log callbacks.
+
+use std::sync::RwLock;
+
+/// Receiver of warnings: the subsystem (e.g. `"parser"`, `"filter"`) and
+/// the message
+pub type WarningSink = fn(category: &str, message: &str);
+
+static SINK: RwLock<Option<WarningSink>> = RwLock::new(None);
+
+/// Route warnings to `sink`, or back to stderr with `None`
+pub fn set_warning_sink(sink: Option<WarningSink>) {
+    if let Ok(mut current) = SINK.write() {
+        *current = sink;
+    }
+}
+
+/// Report a problem that was worked around
+pub fn warn(category: &str, message: &str) {
+    match SINK.read().ok().and_then(|sink| *sink) {
+        Some(sink) => sink(category, message),
+        None => eprintln!("warning: [{category}] {message}"),
+    }
+}
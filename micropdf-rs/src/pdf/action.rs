@@ -0,0 +1,240 @@
+//! PDF actions
+//!
+//! Parses action dictionaries (an /A entry or one value of an /AA
+//! dictionary) into [`Action`] values a viewer can dispatch. Chains built
+//! with /Next are flattened in execution order. Nothing is executed here.
+
+use crate::fitz::error::Result;
+use crate::pdf::document::Document;
+use crate::pdf::object::{Dict, ObjRef, Object, PdfString};
+use crate::pdf::outline::resolve_dest;
+use std::collections::HashSet;
+
+/// Maximum nesting depth of /Next chains
+const MAX_DEPTH: usize = 64;
+
+/// A single PDF action
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Go to a page of this document, if the destination resolves to one
+    GoTo { page: Option<usize> },
+    /// Open a URI
+    Uri(String),
+    /// Launch an application or open a file
+    Launch { file: Option<String> },
+    /// Run a named viewer action such as `NextPage`
+    Named(String),
+    /// Run a script
+    JavaScript(String),
+    /// Go to a page of another document
+    GoToR {
+        file: Option<String>,
+        page: Option<usize>,
+    },
+    /// Submit form fields to a URL
+    SubmitForm { url: Option<String>, flags: i64 },
+    /// Reset form fields to their defaults
+    ResetForm { flags: i64 },
+    /// Any other action type, by its /S name
+    Other(String),
+}
+
+/// Parse an action and the actions chained after it with /Next
+///
+/// With a document, references are resolved and destinations given as
+/// page references or names are looked up; without one, only explicit
+/// page numbers resolve.
+pub fn parse_action(doc: Option<&Document>, action: &Object) -> Result<Vec<Action>> {
+    let mut parser = ActionParser {
+        doc,
+        catalog: match doc {
+            Some(doc) => Some(doc.catalog()?),
+            None => None,
+        },
+        visited: HashSet::new(),
+        actions: Vec::new(),
+    };
+    parser.action(action, 0)?;
+    Ok(parser.actions)
+}
+
+struct ActionParser<'a> {
+    doc: Option<&'a Document>,
+    catalog: Option<Dict>,
+    visited: HashSet<ObjRef>,
+    actions: Vec<Action>,
+}
+
+impl ActionParser<'_> {
+    fn resolve(&self, obj: &Object) -> Result<Object> {
+        match self.doc {
+            Some(doc) => doc.resolve(obj),
+            None => Ok(obj.clone()),
+        }
+    }
+
+    fn get(&self, dict: &Dict, key: &str) -> Result<Option<Object>> {
+        match dict.get(key) {
+            Some(value) => self.resolve(value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn action(&mut self, obj: &Object, depth: usize) -> Result<()> {
+        if depth > MAX_DEPTH {
+            return Ok(());
+        }
+        if let Object::Ref(r) = obj {
+            if !self.visited.insert(*r) {
+                return Ok(());
+            }
+        }
+        let Object::Dict(dict) = self.resolve(obj)? else {
+            return Ok(());
+        };
+        let kind = match dict.get("S").and_then(Object::as_name) {
+            Some(kind) => kind.as_str().to_string(),
+            None => return Ok(()),
+        };
+        let action = match kind.as_str() {
+            "GoTo" => Action::GoTo {
+                page: match self.get(&dict, "D")? {
+                    Some(dest) => self.dest_page(&dest)?,
+                    None => None,
+                },
+            },
+            "URI" => match self.get(&dict, "URI")? {
+                Some(Object::String(uri)) => {
+                    Action::Uri(String::from_utf8_lossy(uri.as_bytes()).into_owned())
+                }
+                _ => Action::Uri(String::new()),
+            },
+            "Launch" => Action::Launch {
+                file: self.file(&dict)?,
+            },
+            "Named" => Action::Named(
+                dict.get("N")
+                    .and_then(Object::as_name)
+                    .map(|n| n.as_str().to_string())
+                    .unwrap_or_default(),
+            ),
+            "JavaScript" => Action::JavaScript(self.script(&dict)?),
+            "GoToR" => Action::GoToR {
+                file: self.file(&dict)?,
+                page: match self.get(&dict, "D")? {
+                    Some(dest) => remote_page(&dest),
+                    None => None,
+                },
+            },
+            "SubmitForm" => Action::SubmitForm {
+                url: self.file(&dict)?,
+                flags: self.flags(&dict)?,
+            },
+            "ResetForm" => Action::ResetForm {
+                flags: self.flags(&dict)?,
+            },
+            _ => Action::Other(kind),
+        };
+        self.actions.push(action);
+
+        if let Some(next) = dict.get("Next") {
+            match self.resolve(next)? {
+                Object::Array(items) => {
+                    for item in &items {
+                        self.action(item, depth + 1)?;
+                    }
+                }
+                _ => self.action(next, depth + 1)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Page index of a destination in this document
+    fn dest_page(&self, dest: &Object) -> Result<Option<usize>> {
+        match (self.doc, &self.catalog) {
+            (Some(doc), Some(catalog)) => resolve_dest(doc, catalog, dest),
+            _ => Ok(remote_page(dest)),
+        }
+    }
+
+    /// The /F file specification, as a string or the /UF or /F of a dictionary
+    fn file(&self, dict: &Dict) -> Result<Option<String>> {
+        match self.get(dict, "F")? {
+            Some(Object::String(s)) => Ok(Some(s.to_text())),
+            Some(Object::Dict(spec)) => {
+                for key in ["UF", "F"] {
+                    if let Some(Object::String(s)) = self.get(&spec, key)? {
+                        return Ok(Some(s.to_text()));
+                    }
+                }
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn script(&self, dict: &Dict) -> Result<String> {
+        let bytes = match (self.get(dict, "JS")?, self.doc) {
+            (Some(Object::String(s)), _) => s.as_bytes().to_vec(),
+            (Some(stream @ Object::Stream { .. }), Some(doc)) => doc.stream_data(&stream)?,
+            _ => Vec::new(),
+        };
+        Ok(PdfString::new(bytes).to_text())
+    }
+
+    fn flags(&self, dict: &Dict) -> Result<i64> {
+        Ok(self
+            .get(dict, "Flags")?
+            .and_then(|f| f.as_int())
+            .unwrap_or(0))
+    }
+}
+
+/// The page number of an explicit destination that gives one, as remote
+/// destinations do
+fn remote_page(dest: &Object) -> Option<usize> {
+    match dest.as_array().and_then(|a| a.first()) {
+        Some(Object::Int(i)) if *i >= 0 => Some(*i as usize),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::object::Name;
+
+    fn dict(entries: Vec<(&str, Object)>) -> Object {
+        Object::Dict(
+            entries
+                .into_iter()
+                .map(|(k, v)| (Name::new(k), v))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_parse_action_chain() {
+        let next = dict(vec![
+            ("S", Object::Name(Name::new("Named"))),
+            ("N", Object::Name(Name::new("NextPage"))),
+        ]);
+        let action = dict(vec![
+            ("S", Object::Name(Name::new("URI"))),
+            (
+                "URI",
+                Object::String(PdfString::new(b"https://example.com/".to_vec())),
+            ),
+            ("Next", next),
+        ]);
+        let actions = parse_action(None, &action).unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                Action::Uri("https://example.com/".into()),
+                Action::Named("NextPage".into()),
+            ]
+        );
+    }
+}
@@ -2,9 +2,18 @@
 //!
 //! Provides types and functionality for PDF annotations (interactive elements).
 
-use crate::fitz::geometry::{Matrix, Rect};
+use crate::fitz::geometry::{Matrix, Point, Quad, Rect};
+use crate::pdf::content::{self, FontMetrics, Operation};
+use crate::pdf::form::{
+    AUTO_FONT_SIZE, LINE_HEIGHT, STANDARD_GLYPH_WIDTH, TEXT_ASCENT, TEXT_PADDING, wrap_text,
+};
+use crate::pdf::geometry::{matrix_to_pdf_array, rect_to_pdf_array};
+use crate::pdf::object::{Dict, Name, Object, PdfString};
 use std::collections::HashMap;
 
+/// Bezier control point offset for approximating a quarter ellipse
+const KAPPA: f32 = 0.552_284_8;
+
 /// PDF annotation types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(i32)]
@@ -335,6 +344,18 @@ pub struct Annotation {
     line_start: Option<(f32, f32)>,
     /// Line end point (for line annotations)
     line_end: Option<(f32, f32)>,
+    /// Line ending styles at the start and end (/LE)
+    line_endings: (LineEnding, LineEnding),
+    /// Marked regions for text markup annotations (/QuadPoints)
+    quad_points: Vec<Quad>,
+    /// Freehand strokes for ink annotations (/InkList), in page space
+    ink_list: Vec<Vec<Point>>,
+    /// Default appearance string for free text (/DA)
+    default_appearance: Option<String>,
+    /// Widths of the /DA font; standard fonts are measured when unset
+    font_metrics: Option<FontMetrics>,
+    /// Normal appearance form XObject (/AP /N)
+    appearance: Option<Object>,
     /// Dirty flag - tracks if annotation has been modified
     dirty: bool,
     /// Additional properties
@@ -360,6 +381,12 @@ impl Annotation {
             popup: None,
             line_start: None,
             line_end: None,
+            line_endings: (LineEnding::None, LineEnding::None),
+            quad_points: Vec::new(),
+            ink_list: Vec::new(),
+            default_appearance: None,
+            font_metrics: None,
+            appearance: None,
             dirty: false,
             properties: HashMap::new(),
         }
@@ -598,6 +625,61 @@ impl Annotation {
         self.mark_dirty();
     }
 
+    /// Get line ending styles (start, end)
+    pub fn line_endings(&self) -> (LineEnding, LineEnding) {
+        self.line_endings
+    }
+
+    /// Set line ending styles (start, end)
+    pub fn set_line_endings(&mut self, start: LineEnding, end: LineEnding) {
+        self.line_endings = (start, end);
+        self.mark_dirty();
+    }
+
+    /// Get quad points
+    pub fn quad_points(&self) -> &[Quad] {
+        &self.quad_points
+    }
+
+    /// Set quad points
+    pub fn set_quad_points(&mut self, quads: Vec<Quad>) {
+        self.quad_points = quads;
+        self.mark_dirty();
+    }
+
+    /// Get ink strokes
+    pub fn ink_list(&self) -> &[Vec<Point>] {
+        &self.ink_list
+    }
+
+    /// Set ink strokes, each a polyline of points
+    pub fn set_ink_list(&mut self, strokes: Vec<Vec<Point>>) {
+        self.ink_list = strokes;
+        self.mark_dirty();
+    }
+
+    /// Get default appearance string (/DA)
+    pub fn default_appearance(&self) -> Option<&str> {
+        self.default_appearance.as_deref()
+    }
+
+    /// Set default appearance string, e.g. "/Helv 12 Tf 0 0 1 rg"
+    pub fn set_default_appearance(&mut self, da: Option<String>) {
+        self.default_appearance = da;
+        self.mark_dirty();
+    }
+
+    /// Set the widths of the /DA font, as resolved from the document
+    pub fn set_font_metrics(&mut self, metrics: Option<FontMetrics>) {
+        self.font_metrics = metrics;
+        self.mark_dirty();
+    }
+
+    /// Normal appearance stream (/AP /N) built by `update_appearance`
+    pub fn appearance(&self) -> Option<&Object> {
+        self.appearance.as_ref()
+    }
+
     /// Check if annotation is dirty (modified)
     pub fn is_dirty(&self) -> bool {
         self.dirty
@@ -614,10 +696,448 @@ impl Annotation {
     }
 
     /// Update annotation appearance (regenerate AP stream)
+    ///
+    /// Square, Circle, Line, Ink, Highlight and FreeText annotations get a
+    /// generated form XObject; other types keep whatever appearance they had.
     pub fn update_appearance(&mut self) {
-        // Mark as clean after updating appearance
+        if let Some(ap) = self.build_appearance() {
+            self.appearance = Some(ap);
+        }
         self.clear_dirty();
     }
+
+    /// Build the /AP /N form XObject for supported annotation types
+    fn build_appearance(&self) -> Option<Object> {
+        let mut ops = Vec::new();
+        let mut gs = Dict::new();
+        let mut resources = Dict::new();
+        let bbox = match self.annot_type {
+            AnnotType::Square | AnnotType::Circle => {
+                self.shape_appearance(&mut ops);
+                self.rect
+            }
+            AnnotType::Line => self.line_appearance(&mut ops),
+            AnnotType::Ink => self.ink_appearance(&mut ops),
+            AnnotType::Highlight => {
+                gs.insert(Name::new("BM"), Object::Name(Name::new("Multiply")));
+                self.highlight_appearance(&mut ops)
+            }
+            AnnotType::FreeText => {
+                self.free_text_appearance(&mut ops, &mut resources);
+                self.rect
+            }
+            _ => return None,
+        };
+
+        if self.opacity < 1.0 {
+            gs.insert(Name::new("CA"), Object::Real(self.opacity as f64));
+            gs.insert(Name::new("ca"), Object::Real(self.opacity as f64));
+        }
+        if !gs.is_empty() {
+            gs.insert(Name::new("Type"), Object::Name(Name::new("ExtGState")));
+            let mut ext = Dict::new();
+            ext.insert(Name::new("H"), Object::Dict(gs));
+            resources.insert(Name::new("ExtGState"), Object::Dict(ext));
+            ops.insert(0, Operation::new("gs", vec![Object::Name(Name::new("H"))]));
+        }
+
+        // Content is drawn in page space, so the form matrix is identity
+        let mut dict = Dict::new();
+        dict.insert(Name::new("Type"), Object::Name(Name::new("XObject")));
+        dict.insert(Name::new("Subtype"), Object::Name(Name::new("Form")));
+        dict.insert(Name::new("BBox"), rect_to_pdf_array(&bbox));
+        dict.insert(Name::new("Matrix"), matrix_to_pdf_array(&Matrix::IDENTITY));
+        if !resources.is_empty() {
+            dict.insert(Name::new("Resources"), Object::Dict(resources));
+        }
+        Some(Object::Stream {
+            dict,
+            data: content::write_content(&ops),
+        })
+    }
+
+    /// Stroke color, line width and dash operators; false if nothing strokes
+    fn stroke_setup(&self, ops: &mut Vec<Operation>) -> bool {
+        let Some(color) = self.color else {
+            return false;
+        };
+        let width = self.border.width;
+        if width <= 0.0 {
+            return false;
+        }
+        ops.extend(color_op(&color, true));
+        ops.push(Operation::new("w", vec![Object::Real(width as f64)]));
+        if self.border.style == BorderStyleType::Dashed && !self.border.dash_pattern.is_empty() {
+            let dash = self
+                .border
+                .dash_pattern
+                .iter()
+                .map(|&d| Object::Real(d as f64))
+                .collect();
+            ops.push(Operation::new(
+                "d",
+                vec![Object::Array(dash), Object::Int(0)],
+            ));
+        }
+        true
+    }
+
+    /// Square and Circle: border inset by half the width so it stays inside
+    fn shape_appearance(&self, ops: &mut Vec<Operation>) {
+        let stroke = self.stroke_setup(ops);
+        let fill = match color_op(&self.interior_color, false) {
+            Some(op) => {
+                ops.push(op);
+                true
+            }
+            None => false,
+        };
+        let Some(paint) = paint_op(stroke, fill) else {
+            ops.clear();
+            return;
+        };
+
+        let inset = if stroke { self.border.width / 2.0 } else { 0.0 };
+        let r = Rect::new(
+            self.rect.x0 + inset,
+            self.rect.y0 + inset,
+            self.rect.x1 - inset,
+            self.rect.y1 - inset,
+        );
+        if self.annot_type == AnnotType::Square {
+            ops.push(Operation::new(
+                "re",
+                [r.x0, r.y0, r.width(), r.height()]
+                    .map(|v| Object::Real(v as f64))
+                    .to_vec(),
+            ));
+        } else {
+            ellipse_ops(ops, r);
+        }
+        ops.push(Operation::new(paint, vec![]));
+    }
+
+    /// Line from /L with optional /LE endings; returns the bounding box
+    fn line_appearance(&self, ops: &mut Vec<Operation>) -> Rect {
+        let (Some(a), Some(b)) = (self.line_start, self.line_end) else {
+            return self.rect;
+        };
+        if !self.stroke_setup(ops) {
+            ops.clear();
+            return self.rect;
+        }
+        let fill = color_op(&self.interior_color, false);
+        let has_fill = fill.is_some();
+        ops.extend(fill);
+
+        let (a, b) = (Point::new(a.0, a.1), Point::new(b.0, b.1));
+        ops.push(point_op("m", a));
+        ops.push(point_op("l", b));
+        ops.push(Operation::new("S", vec![]));
+
+        let size = (self.border.width * 3.0).max(6.0);
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len > 0.0 {
+            let dir = Point::new(dx / len, dy / len);
+            ending_ops(
+                ops,
+                self.line_endings.0,
+                a,
+                Point::new(-dir.x, -dir.y),
+                size,
+                has_fill,
+            );
+            ending_ops(ops, self.line_endings.1, b, dir, size, has_fill);
+        }
+
+        let mut bbox = self.rect;
+        for p in [a, b] {
+            bbox = bbox.union(&Rect::new(p.x, p.y, p.x, p.y).expand(size + self.border.width));
+        }
+        bbox
+    }
+
+    /// Ink: each stroke as a connected polyline; returns the bounding box
+    fn ink_appearance(&self, ops: &mut Vec<Operation>) -> Rect {
+        if !self.stroke_setup(ops) {
+            ops.clear();
+            return self.rect;
+        }
+        ops.push(Operation::new("J", vec![Object::Int(1)]));
+        ops.push(Operation::new("j", vec![Object::Int(1)]));
+        let mut bbox = self.rect;
+        for stroke in self.ink_list.iter().filter(|s| !s.is_empty()) {
+            ops.push(point_op("m", stroke[0]));
+            // A single point still shows as a dot with round caps
+            for &p in stroke
+                .iter()
+                .skip(1)
+                .chain((stroke.len() == 1).then_some(&stroke[0]))
+            {
+                ops.push(point_op("l", p));
+            }
+            for p in stroke {
+                bbox = bbox.union(&Rect::new(p.x, p.y, p.x, p.y).expand(self.border.width));
+            }
+        }
+        ops.push(Operation::new("S", vec![]));
+        bbox
+    }
+
+    /// FreeText: /Contents wrapped to the rect in the /DA font, clipped to
+    /// the rect and drawn over the /C background color
+    fn free_text_appearance(&self, ops: &mut Vec<Operation>, resources: &mut Dict) {
+        let da = self
+            .default_appearance
+            .as_deref()
+            .unwrap_or("/Helv 12 Tf 0 g");
+        let da_ops = content::parse_content(da.as_bytes()).unwrap_or_default();
+        let (font, size) = da_ops
+            .iter()
+            .find(|op| op.operator == "Tf")
+            .and_then(|op| {
+                let font = op.operands.first()?.as_name()?.clone();
+                Some((font, op.operands.get(1)?.as_real()? as f32))
+            })
+            .unwrap_or_else(|| (Name::new("Helv"), 0.0));
+        let size = if size > 0.0 { size } else { AUTO_FONT_SIZE };
+        let metrics = self
+            .font_metrics
+            .clone()
+            .unwrap_or_else(|| FontMetrics::default().with_default_width(STANDARD_GLYPH_WIDTH));
+        let measure = |s: &[u8]| metrics.codes(s).map(|c| metrics.width(c)).sum::<f32>() / 1000.0;
+
+        let mut fonts = Dict::new();
+        fonts.insert(font.clone(), Object::Dict(standard_font_dict(&font)));
+        resources.insert(Name::new("Font"), Object::Dict(fonts));
+
+        let real = |v: f32| Object::Real(v as f64);
+        let r = self.rect;
+        if let Some(color) = self.color {
+            ops.extend(color_op(&color, false));
+            ops.push(Operation::new(
+                "re",
+                vec![real(r.x0), real(r.y0), real(r.width()), real(r.height())],
+            ));
+            ops.push(Operation::new("f", vec![]));
+        }
+
+        // Simple fonts take single-byte codes
+        let text: Vec<u8> = self
+            .contents
+            .chars()
+            .map(|c| u8::try_from(c as u32).unwrap_or(b'?'))
+            .collect();
+        let width = (r.width() - 2.0 * TEXT_PADDING).max(0.0);
+        let lines = wrap_text(&text, width / size, &measure);
+
+        ops.push(Operation::new("q", vec![]));
+        ops.push(Operation::new(
+            "re",
+            vec![
+                real(r.x0 + 1.0),
+                real(r.y0 + 1.0),
+                real(r.width() - 2.0),
+                real(r.height() - 2.0),
+            ],
+        ));
+        ops.push(Operation::new("W", vec![]));
+        ops.push(Operation::new("n", vec![]));
+        ops.push(Operation::new("BT", vec![]));
+        ops.extend(da_ops.into_iter().filter(|op| op.operator != "Tf"));
+        ops.push(Operation::new("Tf", vec![Object::Name(font), real(size)]));
+        let mut y = r.y1 - TEXT_PADDING - TEXT_ASCENT * size;
+        for line in lines {
+            ops.push(Operation::new(
+                "Tm",
+                vec![
+                    real(1.0),
+                    real(0.0),
+                    real(0.0),
+                    real(1.0),
+                    real(r.x0 + TEXT_PADDING),
+                    real(y),
+                ],
+            ));
+            ops.push(Operation::new(
+                "Tj",
+                vec![Object::String(PdfString::new(line))],
+            ));
+            y -= size * LINE_HEIGHT;
+        }
+        ops.push(Operation::new("ET", vec![]));
+        ops.push(Operation::new("Q", vec![]));
+    }
+
+    /// Highlight: filled quads, multiplied over the page; returns the bounding box
+    fn highlight_appearance(&self, ops: &mut Vec<Operation>) -> Rect {
+        let color = self.color.unwrap_or([1.0, 1.0, 0.0]);
+        ops.extend(color_op(&color, false));
+        let quads = if self.quad_points.is_empty() {
+            vec![Quad::from_rect(&self.rect)]
+        } else {
+            self.quad_points.clone()
+        };
+        let mut bbox = self.rect;
+        for q in &quads {
+            ops.push(point_op("m", q.ul));
+            ops.push(point_op("l", q.ur));
+            ops.push(point_op("l", q.lr));
+            ops.push(point_op("l", q.ll));
+            ops.push(Operation::new("h", vec![]));
+            bbox = bbox.union(&q.to_rect());
+        }
+        ops.push(Operation::new("f", vec![]));
+        bbox
+    }
+}
+
+/// Color-setting operator for a gray, RGB or CMYK color
+fn color_op(color: &[f32], stroke: bool) -> Option<Operation> {
+    let op = match (color.len(), stroke) {
+        (1, true) => "G",
+        (1, false) => "g",
+        (3, true) => "RG",
+        (3, false) => "rg",
+        (4, true) => "K",
+        (4, false) => "k",
+        _ => return None,
+    };
+    Some(Operation::new(
+        op,
+        color.iter().map(|&c| Object::Real(c as f64)).collect(),
+    ))
+}
+
+/// Font dictionary for a /DA font resource name, using the standard 14 font
+/// the common Acrobat resource names refer to
+fn standard_font_dict(name: &Name) -> Dict {
+    let base = match name.as_str() {
+        "TiRo" => "Times-Roman",
+        "TiBo" => "Times-Bold",
+        "Cour" => "Courier",
+        "CoBo" => "Courier-Bold",
+        "HeBo" => "Helvetica-Bold",
+        "Symb" => "Symbol",
+        "ZaDb" => "ZapfDingbats",
+        _ => "Helvetica",
+    };
+    let mut font = Dict::new();
+    font.insert(Name::new("Type"), Object::Name(Name::new("Font")));
+    font.insert(Name::new("Subtype"), Object::Name(Name::new("Type1")));
+    font.insert(Name::new("BaseFont"), Object::Name(Name::new(base)));
+    if !matches!(base, "Symbol" | "ZapfDingbats") {
+        font.insert(
+            Name::new("Encoding"),
+            Object::Name(Name::new("WinAnsiEncoding")),
+        );
+    }
+    font
+}
+
+/// Painting operator for a path that is stroked and/or filled
+fn paint_op(stroke: bool, fill: bool) -> Option<&'static str> {
+    match (stroke, fill) {
+        (true, true) => Some("b"),
+        (true, false) => Some("S"),
+        (false, true) => Some("f"),
+        (false, false) => None,
+    }
+}
+
+fn point_op(op: &str, p: Point) -> Operation {
+    Operation::new(op, vec![Object::Real(p.x as f64), Object::Real(p.y as f64)])
+}
+
+/// Closed ellipse inscribed in `r`, as four Bezier curves
+fn ellipse_ops(ops: &mut Vec<Operation>, r: Rect) {
+    let (cx, cy) = ((r.x0 + r.x1) / 2.0, (r.y0 + r.y1) / 2.0);
+    let (rx, ry) = (r.width() / 2.0, r.height() / 2.0);
+    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+    let curve = |x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32| {
+        Operation::new(
+            "c",
+            [x1, y1, x2, y2, x3, y3]
+                .map(|v| Object::Real(v as f64))
+                .to_vec(),
+        )
+    };
+    ops.push(point_op("m", Point::new(cx + rx, cy)));
+    ops.push(curve(cx + rx, cy + ky, cx + kx, cy + ry, cx, cy + ry));
+    ops.push(curve(cx - kx, cy + ry, cx - rx, cy + ky, cx - rx, cy));
+    ops.push(curve(cx - rx, cy - ky, cx - kx, cy - ry, cx, cy - ry));
+    ops.push(curve(cx + kx, cy - ry, cx + rx, cy - ky, cx + rx, cy));
+    ops.push(Operation::new("h", vec![]));
+}
+
+/// Line ending at `p`, with `dir` pointing away from the line
+fn ending_ops(
+    ops: &mut Vec<Operation>,
+    ending: LineEnding,
+    p: Point,
+    dir: Point,
+    size: f32,
+    fill: bool,
+) {
+    // Offset from `p` by `along` units of `dir` and `across` of its normal
+    let at = |along: f32, across: f32| {
+        Point::new(
+            p.x + dir.x * along - dir.y * across,
+            p.y + dir.y * along + dir.x * across,
+        )
+    };
+    let half = size / 2.0;
+    let closed = |ops: &mut Vec<Operation>, pts: &[Point]| {
+        ops.push(point_op("m", pts[0]));
+        for &q in &pts[1..] {
+            ops.push(point_op("l", q));
+        }
+        ops.push(Operation::new("h", vec![]));
+        ops.push(Operation::new(if fill { "b" } else { "s" }, vec![]));
+    };
+    let open = |ops: &mut Vec<Operation>, pts: &[Point]| {
+        ops.push(point_op("m", pts[0]));
+        for &q in &pts[1..] {
+            ops.push(point_op("l", q));
+        }
+        ops.push(Operation::new("S", vec![]));
+    };
+    // Arrow wings sit 30 degrees either side of the line
+    let (back, spread) = (size * 0.866, half);
+    match ending {
+        LineEnding::None => {}
+        LineEnding::Square => closed(
+            ops,
+            &[
+                at(-half, -half),
+                at(half, -half),
+                at(half, half),
+                at(-half, half),
+            ],
+        ),
+        LineEnding::Circle => {
+            ellipse_ops(
+                ops,
+                Rect::new(p.x - half, p.y - half, p.x + half, p.y + half),
+            );
+            ops.push(Operation::new(if fill { "b" } else { "s" }, vec![]));
+        }
+        LineEnding::Diamond => closed(
+            ops,
+            &[at(half, 0.0), at(0.0, half), at(-half, 0.0), at(0.0, -half)],
+        ),
+        LineEnding::OpenArrow => open(ops, &[at(-back, spread), p, at(-back, -spread)]),
+        LineEnding::ClosedArrow => closed(ops, &[at(-back, spread), p, at(-back, -spread)]),
+        LineEnding::ROpenArrow => open(ops, &[at(back, spread), p, at(back, -spread)]),
+        LineEnding::RClosedArrow => closed(ops, &[at(back, spread), p, at(back, -spread)]),
+        LineEnding::Butt => open(ops, &[at(0.0, half), at(0.0, -half)]),
+        LineEnding::Slash => {
+            // Slanted 60 degrees from the line
+            let (along, across) = (half * 0.5, half * 0.866);
+            open(ops, &[at(along, across), at(-along, -across)])
+        }
+    }
 }
 
 impl std::fmt::Debug for Annotation {
@@ -796,4 +1316,119 @@ mod tests {
         assert_eq!(Intent::from_string("PolygonCloud"), Intent::PolygonCloud);
         assert_eq!(Intent::from_string("Unknown"), Intent::Unknown);
     }
+
+    fn appearance_content(annot: &Annotation) -> (Dict, String) {
+        let (dict, data) = annot.appearance().unwrap().as_stream().unwrap();
+        (dict.clone(), String::from_utf8(data.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn test_square_appearance() {
+        let mut annot = Annotation::square(Rect::new(10.0, 10.0, 110.0, 60.0), [1.0, 0.0, 0.0]);
+        annot.set_interior_color(vec![0.0, 0.0, 1.0]);
+        annot.set_opacity(0.5);
+        annot.update_appearance();
+        assert!(!annot.is_dirty());
+
+        let (dict, content) = appearance_content(&annot);
+        assert!(content.contains("1 0 0 RG"));
+        assert!(content.contains("0 0 1 rg"));
+        assert!(content.contains("10.5 10.5 99 49 re"));
+        assert!(content.starts_with("/H gs"));
+        assert_eq!(
+            dict.get("BBox").and_then(Object::as_array).map(Vec::len),
+            Some(4)
+        );
+        assert!(dict.contains_key("Matrix"));
+        let gs = dict
+            .get("Resources")
+            .and_then(Object::as_dict)
+            .and_then(|r| r.get("ExtGState"))
+            .and_then(Object::as_dict)
+            .and_then(|e| e.get("H"))
+            .and_then(Object::as_dict)
+            .unwrap();
+        assert_eq!(gs.get("CA").and_then(Object::as_real), Some(0.5));
+    }
+
+    #[test]
+    fn test_line_and_highlight_appearance() {
+        let mut line = Annotation::new(AnnotType::Line, Rect::new(0.0, 0.0, 100.0, 10.0));
+        line.set_color(Some([0.0, 0.0, 0.0]));
+        line.set_line_start(Some((0.0, 5.0)));
+        line.set_line_end(Some((100.0, 5.0)));
+        line.set_line_endings(LineEnding::None, LineEnding::ClosedArrow);
+        line.update_appearance();
+        let (dict, content) = appearance_content(&line);
+        assert!(content.contains("0 5 m\n100 5 l\nS"));
+        assert!(content.contains("h\ns"));
+        // The arrow head extends the box past the annotation rect
+        let bbox = dict.get("BBox").and_then(Object::as_array).unwrap();
+        assert!(bbox[3].as_real().unwrap() > 10.0);
+
+        let mut hl = Annotation::highlight(Rect::new(0.0, 0.0, 50.0, 12.0), [1.0, 1.0, 0.0]);
+        hl.set_quad_points(vec![Quad::from_rect(&Rect::new(0.0, 0.0, 50.0, 12.0))]);
+        hl.update_appearance();
+        let (dict, content) = appearance_content(&hl);
+        assert!(content.contains("1 1 0 rg"));
+        assert!(content.ends_with("f\n"));
+        assert!(format!("{:?}", dict.get("Resources")).contains("Multiply"));
+
+        let mut text = Annotation::text(Rect::new(0.0, 0.0, 20.0, 20.0), "note");
+        text.update_appearance();
+        assert!(text.appearance().is_none());
+    }
+
+    #[test]
+    fn test_free_text_appearance() {
+        let mut annot = Annotation::free_text(
+            Rect::new(0.0, 0.0, 80.0, 60.0),
+            "The quick brown fox jumps over the lazy dog",
+        );
+        annot.set_default_appearance(Some("/Helv 10 Tf 0 0 1 rg".into()));
+        annot.update_appearance();
+
+        let (dict, content) = appearance_content(&annot);
+        assert!(content.contains("BT\n0 0 1 rg\n/Helv 10 Tf"));
+        assert!(content.contains("W\nn\nBT"));
+        assert!(content.contains("ET\nQ"));
+        // 76pt at 5.56pt per glyph fits 13 characters per line
+        assert!(content.contains("(The quick) Tj"));
+        assert!(content.contains("(brown fox) Tj"));
+        let font = dict
+            .get("Resources")
+            .and_then(Object::as_dict)
+            .and_then(|r| r.get("Font"))
+            .and_then(Object::as_dict)
+            .and_then(|f| f.get("Helv"))
+            .and_then(Object::as_dict)
+            .unwrap();
+        assert_eq!(
+            font.get("BaseFont")
+                .and_then(Object::as_name)
+                .map(Name::as_str),
+            Some("Helvetica")
+        );
+    }
+
+    #[test]
+    fn test_ink_appearance() {
+        let mut annot = Annotation::new(AnnotType::Ink, Rect::new(0.0, 0.0, 50.0, 50.0));
+        annot.set_color(Some([0.0, 0.0, 1.0]));
+        annot.set_ink_list(vec![
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(10.0, 10.0),
+                Point::new(20.0, 0.0),
+            ],
+            vec![Point::new(30.0, 30.0), Point::new(60.0, 30.0)],
+        ]);
+        annot.update_appearance();
+
+        let (dict, content) = appearance_content(&annot);
+        assert!(content.contains("0 0 1 RG\n1 w"));
+        assert!(content.contains("0 0 m\n10 10 l\n20 0 l\n30 30 m\n60 30 l\nS"));
+        let bbox = dict.get("BBox").and_then(Object::as_array).unwrap();
+        assert_eq!(bbox[2].as_real(), Some(61.0));
+    }
 }
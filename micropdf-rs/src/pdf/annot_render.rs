@@ -549,13 +549,16 @@ impl AnnotRenderer {
         opacity: f32,
         device: &mut D,
     ) -> Result<(), String> {
-        // Simplified: draw rectangle outline
-        // Real implementation would use ink path data
-        let rect = annot.rect();
-
         let mut path = Path::new();
-        path.move_to(Point::new(rect.x0, rect.y0));
-        path.line_to(Point::new(rect.x1, rect.y1));
+        for stroke in annot.ink_list().iter().filter(|s| !s.is_empty()) {
+            path.move_to(stroke[0]);
+            for &p in &stroke[1..] {
+                path.line_to(p);
+            }
+        }
+        if path.is_empty() {
+            return Ok(());
+        }
 
         let mut stroke_state = StrokeState::new();
         stroke_state.linewidth = annot.border().width.max(1.0);
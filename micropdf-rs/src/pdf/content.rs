@@ -0,0 +1,1089 @@
+//! PDF content streams
+//!
+//! Content streams are parsed into a flat list of [`Operation`]s and can be
+//! written back out. [`ContentState`] follows the graphics and text state
+//! through the operations to find the page area each one paints.
+
+use crate::fitz::colorspace::Colorspace;
+use crate::fitz::device::BlendMode;
+use crate::fitz::error::{Error, Result};
+use crate::fitz::geometry::{Matrix, Point, Rect};
+use crate::pdf::document::Document;
+use crate::pdf::font as pdf_font;
+use crate::pdf::object::{Dict, Name, Object, PdfString};
+use crate::pdf::parser::{self, Item, Parser};
+use crate::pdf::write::object_to_bytes;
+use std::collections::HashMap;
+
+/// Maximum `q` nesting tracked by [`ContentState`]
+const MAX_GSTATE_DEPTH: usize = 256;
+
+/// Width assumed for glyphs without metrics, in glyph space units.
+/// Deliberately wide so text extents are over- rather than under-estimated.
+const DEFAULT_GLYPH_WIDTH: f32 = 1000.0;
+
+/// Glyph extent above and below the baseline, as a fraction of font size
+const GLYPH_ASCENT: f32 = 1.0;
+const GLYPH_DESCENT: f32 = -0.3;
+
+/// One operator with its operands
+///
+/// An inline image is a single `BI` operation whose operands are the image
+/// dictionary and the raw image data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub operator: String,
+    pub operands: Vec<Object>,
+}
+
+impl Operation {
+    pub fn new(operator: impl Into<String>, operands: Vec<Object>) -> Self {
+        Self {
+            operator: operator.into(),
+            operands,
+        }
+    }
+}
+
+/// Parse a decoded content stream
+pub fn parse_content(data: &[u8]) -> Result<Vec<Operation>> {
+    let mut parser = Parser::new(data);
+    let mut ops = Vec::new();
+    let mut operands = Vec::new();
+    loop {
+        match parser.parse_item()? {
+            Item::Eof => break,
+            Item::Object(obj) => operands.push(obj),
+            Item::Keyword(k) if k == "BI" => {
+                operands.clear();
+                ops.push(parse_inline_image(&mut parser)?);
+            }
+            Item::Keyword(k) => ops.push(Operation::new(k, std::mem::take(&mut operands))),
+        }
+    }
+    Ok(ops)
+}
+
+fn parse_inline_image(parser: &mut Parser) -> Result<Operation> {
+    let mut dict = Dict::new();
+    loop {
+        match parser.parse_item()? {
+            Item::Keyword(k) if k == "ID" => break,
+            Item::Object(Object::Name(key)) => match parser.parse_item()? {
+                Item::Object(value) => {
+                    dict.insert(key, value);
+                }
+                _ => return Err(Error::syntax("invalid inline image dictionary")),
+            },
+            _ => return Err(Error::syntax("invalid inline image dictionary")),
+        }
+    }
+
+    let data = parser.data();
+    // A single whitespace byte separates ID from the image data
+    let start = (parser.pos() + 1).min(data.len());
+    let (end, ei) = inline_image_end(data, start, &dict)
+        .ok_or_else(|| Error::syntax("inline image without EI"))?;
+    parser.seek(ei + 2);
+    Ok(Operation::new(
+        "BI",
+        vec![
+            Object::Dict(dict),
+            Object::String(PdfString::new(data[start..end].to_vec())),
+        ],
+    ))
+}
+
+/// End of the inline image data starting at `start` and position of its
+/// `EI` operator
+pub fn inline_image_end(data: &[u8], start: usize, dict: &Dict) -> Option<(usize, usize)> {
+    if let Some(len) = inline_image_len(dict) {
+        let end = start.checked_add(len)?;
+        let ei = end
+            + data
+                .get(end..)?
+                .iter()
+                .take_while(|b| is_space(**b))
+                .count();
+        if data[ei..].starts_with(b"EI") && ends_token(data, ei + 2) {
+            return Some((end, ei));
+        }
+    }
+
+    // Filtered data has no known length: look for EI between whitespace
+    let mut from = start;
+    while let Some(ei) = parser::find_bytes(data, b"EI", from) {
+        if ei > start && is_space(data[ei - 1]) && ends_token(data, ei + 2) {
+            return Some((ei - 1, ei));
+        }
+        from = ei + 1;
+    }
+    None
+}
+
+/// Byte length of unfiltered inline image data
+fn inline_image_len(dict: &Dict) -> Option<usize> {
+    let get = |short: &str, long: &str| dict.get(short).or_else(|| dict.get(long));
+    if get("F", "Filter").is_some() {
+        return None;
+    }
+    let width = get("W", "Width")?.as_int().filter(|&v| v > 0)? as usize;
+    let height = get("H", "Height")?.as_int().filter(|&v| v > 0)? as usize;
+    let (bpc, comps) = if get("IM", "ImageMask").and_then(Object::as_bool) == Some(true) {
+        (1, 1)
+    } else {
+        let bpc = get("BPC", "BitsPerComponent")?
+            .as_int()
+            .filter(|&v| v > 0)? as usize;
+        let cs = match get("CS", "ColorSpace")? {
+            Object::Array(arr) => arr.first()?.as_name()?.as_str(),
+            obj => obj.as_name()?.as_str(),
+        };
+        let comps = match cs {
+            "G" | "DeviceGray" | "CalGray" | "I" | "Indexed" => 1,
+            "RGB" | "DeviceRGB" | "CalRGB" => 3,
+            "CMYK" | "DeviceCMYK" => 4,
+            _ => return None,
+        };
+        (bpc, comps)
+    };
+    let row = width.checked_mul(bpc)?.checked_mul(comps)?.div_ceil(8);
+    row.checked_mul(height)
+}
+
+fn is_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\r' | b'\n' | b'\x0C' | b'\0')
+}
+
+fn ends_token(data: &[u8], pos: usize) -> bool {
+    data.get(pos).is_none_or(|&b| is_space(b))
+}
+
+/// Serialize operations as a content stream
+pub fn write_content(ops: &[Operation]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for op in ops {
+        if op.operator == "BI" {
+            write_inline_image(&mut out, op);
+            continue;
+        }
+        for operand in &op.operands {
+            out.extend_from_slice(&object_to_bytes(operand));
+            out.push(b' ');
+        }
+        out.extend_from_slice(op.operator.as_bytes());
+        out.push(b'\n');
+    }
+    out
+}
+
+fn write_inline_image(out: &mut Vec<u8>, op: &Operation) {
+    out.extend_from_slice(b"BI\n");
+    if let Some(dict) = op.operands.first().and_then(Object::as_dict) {
+        let mut entries: Vec<_> = dict.iter().collect();
+        entries.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        for (key, value) in entries {
+            out.extend_from_slice(&object_to_bytes(&Object::Name(key.clone())));
+            out.push(b' ');
+            out.extend_from_slice(&object_to_bytes(value));
+            out.push(b'\n');
+        }
+    }
+    out.extend_from_slice(b"ID ");
+    if let Some(data) = op.operands.get(1).and_then(Object::as_string) {
+        out.extend_from_slice(data.as_bytes());
+    }
+    out.extend_from_slice(b"\nEI\n");
+}
+
+/// Render a decoded content stream for reading, one operator per line
+///
+/// Lines are indented two spaces per open `q`, `BT`, marked-content and
+/// compatibility section. Operands are written in their normal form. Inline
+/// image data is not interpreted: it is kept whole as one hex string after
+/// `ID`, so binary data survives in the text.
+pub fn pretty_print(data: &[u8]) -> Result<String> {
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut line = |out: &mut String, depth: usize, text: &str| {
+        out.push_str(&"  ".repeat(depth));
+        out.push_str(text);
+        out.push('\n');
+    };
+    for op in parse_content(data)? {
+        let operator = op.operator.as_str();
+        if matches!(operator, "Q" | "ET" | "EMC" | "EX") {
+            depth = depth.saturating_sub(1);
+        }
+        if operator == "BI" {
+            line(&mut out, depth, "BI");
+            if let Some(dict) = op.operands.first().and_then(Object::as_dict) {
+                let mut entries: Vec<_> = dict.iter().collect();
+                entries.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+                for (key, value) in entries {
+                    let entry = format!(
+                        "{} {}",
+                        String::from_utf8_lossy(&object_to_bytes(&Object::Name(key.clone()))),
+                        String::from_utf8_lossy(&object_to_bytes(value))
+                    );
+                    line(&mut out, depth + 1, &entry);
+                }
+            }
+            let image = op.operands.get(1).and_then(Object::as_string);
+            let hex: String = image
+                .map(|data| data.as_bytes().iter().map(|b| format!("{b:02x}")).collect())
+                .unwrap_or_default();
+            line(&mut out, depth, &format!("ID <{hex}>"));
+            line(&mut out, depth, "EI");
+            continue;
+        }
+
+        let mut text = String::new();
+        for operand in &op.operands {
+            text.push_str(&String::from_utf8_lossy(&object_to_bytes(operand)));
+            text.push(' ');
+        }
+        text.push_str(operator);
+        line(&mut out, depth, &text);
+        if matches!(operator, "q" | "BT" | "BMC" | "BDC" | "BX") {
+            depth += 1;
+        }
+    }
+    Ok(out)
+}
+
+/// Glyph widths of a font, enough to measure shown strings
+#[derive(Debug, Clone)]
+pub struct FontMetrics {
+    widths: HashMap<u32, f32>,
+    default_width: f32,
+    two_byte: bool,
+    substitute: Option<&'static str>,
+    unicode: HashMap<u32, char>,
+    /// Glyph space to text space; only Type 3 fonts set their own
+    font_matrix: Matrix,
+    /// Glyph extent below and above the baseline, in text space
+    descent: f32,
+    ascent: f32,
+}
+
+impl Default for FontMetrics {
+    fn default() -> Self {
+        Self {
+            widths: HashMap::new(),
+            default_width: DEFAULT_GLYPH_WIDTH,
+            two_byte: false,
+            substitute: None,
+            unicode: HashMap::new(),
+            font_matrix: Matrix::scale(0.001, 0.001),
+            descent: GLYPH_DESCENT,
+            ascent: GLYPH_ASCENT,
+        }
+    }
+}
+
+impl FontMetrics {
+    /// Read widths from a font dictionary, resolving indirect entries
+    ///
+    /// Simple fonts use /FirstChar and /Widths, or the standard 14 metrics
+    /// when /Widths is missing, and map codes to Unicode through their
+    /// /Encoding; Type0 fonts use the /W and /DW entries of their
+    /// descendant font and two-byte codes. Type 3 widths and glyph
+    /// extents go through the font's /FontMatrix and /FontBBox.
+    pub fn load(doc: &Document, font: &Dict) -> Result<Self> {
+        let mut metrics = Self::default();
+        let subtype = font.get("Subtype").and_then(Object::as_name);
+        if subtype.is_some_and(|s| s.as_str() == "Type0") {
+            metrics.two_byte = true;
+            let Some(Object::Array(descendants)) = doc.resolve_key(font, "DescendantFonts")? else {
+                return Ok(metrics);
+            };
+            let Some(Object::Dict(cid_font)) =
+                descendants.first().map(|d| doc.resolve(d)).transpose()?
+            else {
+                return Ok(metrics);
+            };
+            if let Some(dw) = doc.resolve_key(&cid_font, "DW")?.and_then(|o| o.as_real()) {
+                metrics.default_width = dw as f32;
+            }
+            if let Some(Object::Array(w)) = doc.resolve_key(&cid_font, "W")? {
+                metrics.load_cid_widths(doc, &w)?;
+            }
+            return Ok(metrics);
+        }
+
+        let first = doc
+            .resolve_key(font, "FirstChar")?
+            .and_then(|o| o.as_int())
+            .unwrap_or(0)
+            .max(0);
+        let base_font = doc
+            .resolve_key(font, "BaseFont")?
+            .and_then(|o| o.as_name().map(|n| n.as_str().to_string()))
+            .unwrap_or_default();
+        if subtype.is_some_and(|s| s.as_str() == "Type3") {
+            if let [a, b, c, d, e, f, ..] = numbers(doc, font, "FontMatrix")?[..] {
+                metrics.font_matrix = Matrix::new(a, b, c, d, e, f);
+                if a != 0.0 {
+                    // Keep glyphs without metrics one em wide
+                    metrics.default_width = DEFAULT_GLYPH_WIDTH * 0.001 / a.abs();
+                }
+            }
+            // An all-zero /FontBBox makes no claim about the glyphs
+            if let [x0, y0, x1, y1, ..] = numbers(doc, font, "FontBBox")?[..] {
+                if y0 != y1 {
+                    let bbox = transform_rect(
+                        &Rect::new(x0, y0.min(y1), x1, y0.max(y1)),
+                        &metrics.font_matrix,
+                    );
+                    metrics.descent = bbox.y0;
+                    metrics.ascent = bbox.y1;
+                }
+            }
+        }
+        let encoding = pdf_font::Encoding::load(doc, font)?;
+        if let Some(Object::Array(widths)) = doc.resolve_key(font, "Widths")? {
+            for (i, w) in widths.iter().enumerate() {
+                if let Some(w) = doc.resolve(w)?.as_real() {
+                    metrics.widths.insert((first as usize + i) as u32, w as f32);
+                }
+            }
+        } else if let Some(standard) = pdf_font::standard_font_name(&base_font) {
+            for code in 0..=255u8 {
+                if let Some(w) = pdf_font::standard_width(standard, &encoding, code) {
+                    metrics.widths.insert(code as u32, w as f32);
+                }
+            }
+        }
+        for code in 0..=255u8 {
+            match encoding.unicode(code) {
+                Some(c) if c as u32 != code as u32 => {
+                    metrics.unicode.insert(code as u32, c);
+                }
+                _ => {}
+            }
+        }
+
+        let desc = match doc.resolve_key(font, "FontDescriptor")? {
+            Some(Object::Dict(desc)) => Some(desc),
+            _ => None,
+        };
+        let embedded = desc.as_ref().is_some_and(|desc| {
+            ["FontFile", "FontFile2", "FontFile3"]
+                .iter()
+                .any(|key| desc.contains_key(*key))
+        });
+        if !embedded && subtype.is_none_or(|s| s.as_str() != "Type3") {
+            metrics.substitute = Some(pdf_font::substitute_name(&base_font));
+        }
+        if let Some(desc) = desc {
+            if let Some(mw) = doc
+                .resolve_key(&desc, "MissingWidth")?
+                .and_then(|o| o.as_real())
+            {
+                metrics.default_width = mw as f32;
+            }
+        }
+        Ok(metrics)
+    }
+
+    /// The metrics of the fonts of `resources`, by resource name; fonts
+    /// without usable metrics fall back to wide default glyphs
+    pub fn load_resources(doc: &Document, resources: &Dict) -> Result<HashMap<Name, Self>> {
+        let Some(Object::Dict(entries)) = doc.resolve_key(resources, "Font")? else {
+            return Ok(HashMap::new());
+        };
+        let mut fonts = HashMap::new();
+        for (name, font) in &entries {
+            let metrics = match doc.resolve(font)? {
+                Object::Dict(d) => Self::load(doc, &d).unwrap_or_default(),
+                _ => Self::default(),
+            };
+            fonts.insert(name.clone(), metrics);
+        }
+        Ok(fonts)
+    }
+
+    /// Parse a CIDFont /W array: `c [w1 w2 ...]` and `c_first c_last w` runs
+    fn load_cid_widths(&mut self, doc: &Document, w: &[Object]) -> Result<()> {
+        let mut i = 0;
+        while i + 1 < w.len() {
+            let Some(first) = w[i].as_int() else {
+                break;
+            };
+            match doc.resolve(&w[i + 1])? {
+                Object::Array(run) => {
+                    for (j, width) in run.iter().enumerate() {
+                        if let Some(width) = width.as_real() {
+                            self.widths
+                                .insert((first as usize + j) as u32, width as f32);
+                        }
+                    }
+                    i += 2;
+                }
+                last => {
+                    let (Some(last), Some(width)) =
+                        (last.as_int(), w.get(i + 2).and_then(Object::as_real))
+                    else {
+                        break;
+                    };
+                    // Cap runs so a corrupt range cannot exhaust memory
+                    for code in first..=last.min(first + 0xFFFF) {
+                        self.widths.insert(code as u32, width as f32);
+                    }
+                    i += 3;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Use `width` for glyphs without metrics, for fonts such as the
+    /// standard 14 whose widths are not in the file
+    pub fn with_default_width(mut self, width: f32) -> Self {
+        self.default_width = width;
+        self
+    }
+
+    /// Width of a character code in glyph space units
+    pub fn width(&self, code: u32) -> f32 {
+        self.widths
+            .get(&code)
+            .copied()
+            .unwrap_or(self.default_width)
+    }
+
+    /// Horizontal advance of a character code in text space, at a font
+    /// size of 1
+    pub fn advance(&self, code: u32) -> f32 {
+        self.width(code) * self.font_matrix.a
+    }
+
+    /// Unicode value of a character code; codes the encoding does not
+    /// name are taken as they are
+    pub fn unicode(&self, code: u32) -> u32 {
+        self.unicode.get(&code).map_or(code, |&c| c as u32)
+    }
+
+    /// Standard 14 font to draw a non-embedded font with
+    pub fn substitute(&self) -> Option<&'static str> {
+        self.substitute
+    }
+
+    /// Split a shown string into character codes
+    pub fn codes<'a>(&self, bytes: &'a [u8]) -> impl Iterator<Item = u32> + 'a {
+        let step = if self.two_byte { 2 } else { 1 };
+        bytes
+            .chunks(step)
+            .map(|c| c.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32))
+    }
+}
+
+/// A soft mask set by an ExtGState's /SMask
+///
+/// The mask is the transparency group /G rendered where the `gs`
+/// operator selects it: its luminosity, or its alpha for `/S /Alpha`.
+#[derive(Debug, Clone)]
+pub struct SoftMask {
+    /// `/S /Luminosity` rather than `/S /Alpha`
+    pub luminosity: bool,
+    /// Decoded content stream of the group
+    pub contents: Vec<u8>,
+    /// Group /BBox in form space
+    pub bbox: Rect,
+    /// Group /Matrix, from form space to user space
+    pub matrix: Matrix,
+    /// Group /Resources
+    pub resources: Option<Dict>,
+    /// Group colorspace, which /BC is given in
+    pub colorspace: Colorspace,
+    /// /BC backdrop color; empty for black
+    pub backdrop: Vec<f32>,
+}
+
+impl SoftMask {
+    /// Read a soft mask dictionary, resolving indirect entries
+    pub fn load(doc: &Document, smask: &Dict) -> Result<Self> {
+        let luminosity = match doc.resolve_key(smask, "S")? {
+            Some(Object::Name(s)) => s.as_str() == "Luminosity",
+            _ => return Err(Error::format("soft mask without /S")),
+        };
+        let group = smask
+            .get("G")
+            .ok_or_else(|| Error::format("soft mask without /G"))?;
+        let Object::Stream { dict, .. } = doc.resolve(group)? else {
+            return Err(Error::format("soft mask group is not a stream"));
+        };
+        let (bbox, matrix, resources) = form_geometry(doc, &dict)?;
+        let colorspace = match doc.resolve_key(&dict, "Group")? {
+            Some(Object::Dict(attrs)) => match doc.resolve_key(&attrs, "CS")? {
+                Some(Object::Name(cs)) if cs.as_str() == "DeviceRGB" => Colorspace::device_rgb(),
+                Some(Object::Name(cs)) if cs.as_str() == "DeviceCMYK" => Colorspace::device_cmyk(),
+                _ => Colorspace::device_gray(),
+            },
+            _ => Colorspace::device_gray(),
+        };
+        Ok(Self {
+            luminosity,
+            contents: doc.stream_data(group)?,
+            bbox,
+            matrix,
+            resources,
+            colorspace,
+            backdrop: numbers(doc, smask, "BC")?,
+        })
+    }
+}
+
+/// The ExtGState parameters applied by the interpreter's `gs` operator
+#[derive(Debug, Clone, Default)]
+pub struct ExtGState {
+    /// /BM blend mode
+    pub blend_mode: Option<BlendMode>,
+    /// /SMask: a soft mask, or `Some(None)` for `/SMask /None`
+    pub soft_mask: Option<Option<SoftMask>>,
+}
+
+impl ExtGState {
+    /// Read a graphics state parameter dictionary, resolving indirect
+    /// entries
+    pub fn load(doc: &Document, state: &Dict) -> Result<Self> {
+        // /BM may be an array of modes to try in order
+        let blend_mode = match doc.resolve_key(state, "BM")? {
+            Some(Object::Name(mode)) => BlendMode::from_name(mode.as_str()),
+            Some(Object::Array(modes)) => modes
+                .iter()
+                .filter_map(Object::as_name)
+                .find_map(|mode| BlendMode::from_name(mode.as_str())),
+            _ => None,
+        };
+        let soft_mask = match doc.resolve_key(state, "SMask")? {
+            Some(Object::Dict(smask)) => Some(Some(SoftMask::load(doc, &smask)?)),
+            Some(Object::Name(none)) if none.as_str() == "None" => Some(None),
+            _ => None,
+        };
+        Ok(Self {
+            blend_mode,
+            soft_mask,
+        })
+    }
+
+    /// The ExtGState entries of `resources`, by resource name
+    pub fn load_resources(doc: &Document, resources: &Dict) -> Result<HashMap<Name, Self>> {
+        let mut states = HashMap::new();
+        let Some(Object::Dict(entries)) = doc.resolve_key(resources, "ExtGState")? else {
+            return Ok(states);
+        };
+        for (name, state) in &entries {
+            if let Object::Dict(state) = doc.resolve(state)? {
+                states.insert(name.clone(), Self::load(doc, &state)?);
+            }
+        }
+        Ok(states)
+    }
+}
+
+/// A tiling pattern (/PatternType 1) from a /Pattern resource
+#[derive(Debug, Clone)]
+pub struct TilingPattern {
+    /// /PaintType 1: the cell sets its own colors. Uncolored cells
+    /// (/PaintType 2) are painted in the fill color.
+    pub colored: bool,
+    /// Pattern cell /BBox in pattern space
+    pub bbox: Rect,
+    /// Horizontal spacing between cells
+    pub xstep: f32,
+    /// Vertical spacing between cells
+    pub ystep: f32,
+    /// /Matrix, from pattern space to the page's default space
+    pub matrix: Matrix,
+    /// Decoded content stream of the cell
+    pub contents: Vec<u8>,
+    /// Pattern /Resources
+    pub resources: Option<Dict>,
+}
+
+impl TilingPattern {
+    /// Read a tiling pattern stream, resolving indirect entries
+    pub fn load(doc: &Document, pattern: &Object) -> Result<Self> {
+        let Object::Stream { dict, .. } = doc.resolve(pattern)? else {
+            return Err(Error::format("tiling pattern is not a stream"));
+        };
+        let int = |key| -> Result<Option<i64>> {
+            Ok(doc.resolve_key(&dict, key)?.and_then(|o| o.as_int()))
+        };
+        if int("PatternType")? != Some(1) {
+            return Err(Error::unsupported("only tiling patterns are supported"));
+        }
+        let step = |key| -> Result<f32> {
+            match doc.resolve_key(&dict, key)?.and_then(|o| o.as_real()) {
+                Some(step) if step != 0.0 => Ok(step as f32),
+                _ => Err(Error::format(format!("tiling pattern without /{key}"))),
+            }
+        };
+        let (bbox, matrix, resources) = form_geometry(doc, &dict)?;
+        Ok(Self {
+            colored: int("PaintType")? != Some(2),
+            bbox,
+            xstep: step("XStep")?,
+            ystep: step("YStep")?,
+            matrix,
+            contents: doc.stream_data(pattern)?,
+            resources,
+        })
+    }
+
+    /// The tiling patterns of `resources`, by resource name; shading
+    /// patterns and patterns that cannot be read are left out
+    pub fn load_resources(doc: &Document, resources: &Dict) -> Result<HashMap<Name, Self>> {
+        let Some(Object::Dict(entries)) = doc.resolve_key(resources, "Pattern")? else {
+            return Ok(HashMap::new());
+        };
+        Ok(entries
+            .iter()
+            .filter_map(|(name, pattern)| Some((name.clone(), Self::load(doc, pattern).ok()?)))
+            .collect())
+    }
+}
+
+/// A Type 3 font, whose glyphs are content streams
+#[derive(Debug, Clone)]
+pub struct Type3Font {
+    /// /FontMatrix, from glyph space to text space
+    pub font_matrix: Matrix,
+    /// Decoded glyph procedures from /CharProcs, by character code
+    pub procs: HashMap<u32, Vec<u8>>,
+    /// Font /Resources for the glyph procedures
+    pub resources: Option<Dict>,
+    /// Glyph widths, in glyph space
+    pub widths: FontMetrics,
+}
+
+impl Type3Font {
+    /// Read a Type 3 font dictionary, resolving indirect entries
+    ///
+    /// Character codes reach their procedures through the glyph names of
+    /// the /Encoding /Differences array.
+    pub fn load(doc: &Document, font: &Dict) -> Result<Self> {
+        let font_matrix = match numbers(doc, font, "FontMatrix")?[..] {
+            [a, b, c, d, e, f, ..] => Matrix::new(a, b, c, d, e, f),
+            _ => Matrix::scale(0.001, 0.001),
+        };
+        let Some(Object::Dict(char_procs)) = doc.resolve_key(font, "CharProcs")? else {
+            return Err(Error::format("Type 3 font without /CharProcs"));
+        };
+        let mut procs = HashMap::new();
+        if let Some(Object::Dict(encoding)) = doc.resolve_key(font, "Encoding")? {
+            if let Some(Object::Array(differences)) = doc.resolve_key(&encoding, "Differences")? {
+                let mut code = 0;
+                for item in &differences {
+                    match doc.resolve(item)? {
+                        Object::Int(first) => code = first as u32,
+                        Object::Name(glyph) => {
+                            if let Some(proc) = char_procs.get(glyph.as_str()) {
+                                procs.insert(code, doc.stream_data(proc)?);
+                            }
+                            code += 1;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        let resources = match doc.resolve_key(font, "Resources")? {
+            Some(Object::Dict(resources)) => Some(resources),
+            _ => None,
+        };
+        Ok(Self {
+            font_matrix,
+            procs,
+            resources,
+            widths: FontMetrics::load(doc, font)?,
+        })
+    }
+
+    /// The Type 3 fonts of `resources`, by resource name; fonts that
+    /// cannot be read are left out
+    pub fn load_resources(doc: &Document, resources: &Dict) -> Result<HashMap<Name, Self>> {
+        let Some(Object::Dict(entries)) = doc.resolve_key(resources, "Font")? else {
+            return Ok(HashMap::new());
+        };
+        let mut fonts = HashMap::new();
+        for (name, font) in &entries {
+            let Object::Dict(font) = doc.resolve(font)? else {
+                continue;
+            };
+            if font
+                .get("Subtype")
+                .and_then(Object::as_name)
+                .map(Name::as_str)
+                == Some("Type3")
+            {
+                if let Ok(font) = Self::load(doc, &font) {
+                    fonts.insert(name.clone(), font);
+                }
+            }
+        }
+        Ok(fonts)
+    }
+}
+
+/// /BBox, /Matrix and /Resources of a form-like stream dictionary
+fn form_geometry(doc: &Document, dict: &Dict) -> Result<(Rect, Matrix, Option<Dict>)> {
+    let bbox = match numbers(doc, dict, "BBox")?[..] {
+        [x0, y0, x1, y1, ..] => Rect::new(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)),
+        _ => return Err(Error::format("stream without /BBox")),
+    };
+    let matrix = match numbers(doc, dict, "Matrix")?[..] {
+        [a, b, c, d, e, f, ..] => Matrix::new(a, b, c, d, e, f),
+        _ => Matrix::IDENTITY,
+    };
+    let resources = match doc.resolve_key(dict, "Resources")? {
+        Some(Object::Dict(resources)) => Some(resources),
+        _ => None,
+    };
+    Ok((bbox, matrix, resources))
+}
+
+/// Numbers of the array at `key`, empty when absent
+fn numbers(doc: &Document, dict: &Dict, key: &str) -> Result<Vec<f32>> {
+    match doc.resolve_key(dict, key)? {
+        Some(Object::Array(items)) => items
+            .iter()
+            .map(|item| Ok(doc.resolve(item)?.as_real().unwrap_or(0.0) as f32))
+            .collect(),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// What a painting operation marks on the page
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mark {
+    /// Glyphs from a text showing operator. `adjustment` is the `TJ` number
+    /// that moves the text position by the same amount as the shown glyphs.
+    Text { bbox: Rect, adjustment: f32 },
+    /// An inline image
+    Image(Rect),
+    /// An XObject painted by `Do` with the current transformation matrix
+    XObject { name: Name, ctm: Matrix },
+    /// A filled or stroked path
+    Path(Rect),
+}
+
+#[derive(Debug, Clone)]
+struct GState {
+    ctm: Matrix,
+    line_width: f32,
+    font: Option<Name>,
+    font_size: f32,
+    char_space: f32,
+    word_space: f32,
+    h_scale: f32,
+    leading: f32,
+    rise: f32,
+    render_mode: i64,
+}
+
+impl Default for GState {
+    fn default() -> Self {
+        Self {
+            ctm: Matrix::IDENTITY,
+            line_width: 1.0,
+            font: None,
+            font_size: 0.0,
+            char_space: 0.0,
+            word_space: 0.0,
+            h_scale: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+            render_mode: 0,
+        }
+    }
+}
+
+/// Graphics and text state followed through a content stream
+pub struct ContentState<'a> {
+    fonts: &'a HashMap<Name, FontMetrics>,
+    default_font: FontMetrics,
+    gs: GState,
+    stack: Vec<GState>,
+    tm: Matrix,
+    tlm: Matrix,
+    path: Rect,
+}
+
+impl<'a> ContentState<'a> {
+    /// Start at the page's default state; `fonts` maps /Font resource names
+    /// to their metrics
+    pub fn new(fonts: &'a HashMap<Name, FontMetrics>) -> Self {
+        Self {
+            fonts,
+            default_font: FontMetrics::default(),
+            gs: GState::default(),
+            stack: Vec::new(),
+            tm: Matrix::IDENTITY,
+            tlm: Matrix::IDENTITY,
+            path: Rect::EMPTY,
+        }
+    }
+
+    /// Current transformation matrix
+    pub fn ctm(&self) -> Matrix {
+        self.gs.ctm
+    }
+
+    /// Number of unmatched `q` operators seen so far
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Current text rendering mode (3 is invisible)
+    pub fn text_render_mode(&self) -> i64 {
+        self.gs.render_mode
+    }
+
+    /// Apply `op` to the state and report what it paints, if anything
+    pub fn step(&mut self, op: &Operation) -> Option<Mark> {
+        let num = |i: usize| op.operands.get(i).and_then(Object::as_real).unwrap_or(0.0) as f32;
+        let matrix = || Matrix::new(num(0), num(1), num(2), num(3), num(4), num(5));
+
+        match op.operator.as_str() {
+            "q" => {
+                if self.stack.len() < MAX_GSTATE_DEPTH {
+                    self.stack.push(self.gs.clone());
+                }
+            }
+            "Q" => {
+                if let Some(gs) = self.stack.pop() {
+                    self.gs = gs;
+                }
+            }
+            "cm" => self.gs.ctm = matrix().concat(&self.gs.ctm),
+            "w" => self.gs.line_width = num(0),
+
+            "m" | "l" | "c" | "v" | "y" => {
+                for pair in op.operands.chunks_exact(2) {
+                    let x = pair[0].as_real().unwrap_or(0.0) as f32;
+                    let y = pair[1].as_real().unwrap_or(0.0) as f32;
+                    self.path
+                        .include_point(Point::new(x, y).transform(&self.gs.ctm));
+                }
+            }
+            "re" => {
+                let (x, y, w, h) = (num(0), num(1), num(2), num(3));
+                for (px, py) in [(x, y), (x + w, y), (x, y + h), (x + w, y + h)] {
+                    self.path
+                        .include_point(Point::new(px, py).transform(&self.gs.ctm));
+                }
+            }
+            "n" => self.path = Rect::EMPTY,
+            "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" => {
+                let mut bbox = std::mem::replace(&mut self.path, Rect::EMPTY);
+                if bbox.x0 > bbox.x1 {
+                    return None;
+                }
+                if !matches!(op.operator.as_str(), "f" | "F" | "f*") {
+                    let m = &self.gs.ctm;
+                    let scale = (m.a * m.d - m.b * m.c).abs().sqrt();
+                    bbox = bbox.expand((self.gs.line_width * scale / 2.0).max(0.5));
+                }
+                return Some(Mark::Path(bbox));
+            }
+
+            "BT" => {
+                self.tm = Matrix::IDENTITY;
+                self.tlm = Matrix::IDENTITY;
+            }
+            "Tm" => {
+                self.tlm = matrix();
+                self.tm = self.tlm;
+            }
+            "Td" => self.move_line(num(0), num(1)),
+            "TD" => {
+                self.gs.leading = -num(1);
+                self.move_line(num(0), num(1));
+            }
+            "T*" => self.move_line(0.0, -self.gs.leading),
+            "TL" => self.gs.leading = num(0),
+            "Tc" => self.gs.char_space = num(0),
+            "Tw" => self.gs.word_space = num(0),
+            "Tz" => self.gs.h_scale = num(0) / 100.0,
+            "Ts" => self.gs.rise = num(0),
+            "Tr" => self.gs.render_mode = op.operands.first().and_then(Object::as_int).unwrap_or(0),
+            "Tf" => {
+                self.gs.font = op.operands.first().and_then(Object::as_name).cloned();
+                self.gs.font_size = num(1);
+            }
+            "Tj" => return Some(self.show(&op.operands[..op.operands.len().min(1)])),
+            "TJ" => {
+                let items = op.operands.first().and_then(Object::as_array)?;
+                return Some(self.show(items));
+            }
+            "'" => {
+                self.move_line(0.0, -self.gs.leading);
+                return Some(self.show(&op.operands[..op.operands.len().min(1)]));
+            }
+            "\"" => {
+                self.gs.word_space = num(0);
+                self.gs.char_space = num(1);
+                self.move_line(0.0, -self.gs.leading);
+                return Some(self.show(op.operands.get(2..).unwrap_or(&[])));
+            }
+
+            "Do" => {
+                let name = op.operands.first().and_then(Object::as_name)?.clone();
+                return Some(Mark::XObject {
+                    name,
+                    ctm: self.gs.ctm,
+                });
+            }
+            "BI" => return Some(Mark::Image(transform_rect(&Rect::UNIT, &self.gs.ctm))),
+            _ => {}
+        }
+        None
+    }
+
+    fn move_line(&mut self, tx: f32, ty: f32) {
+        self.tlm = Matrix::translate(tx, ty).concat(&self.tlm);
+        self.tm = self.tlm;
+    }
+
+    /// Advance the text matrix over strings and `TJ` adjustments, returning
+    /// the area the glyphs may cover
+    fn show(&mut self, items: &[Object]) -> Mark {
+        let gs = &self.gs;
+        let font = gs
+            .font
+            .as_ref()
+            .and_then(|f| self.fonts.get(f))
+            .unwrap_or(&self.default_font);
+        let (size, h_scale) = (gs.font_size, gs.h_scale);
+
+        let mut tx = 0.0f32;
+        let (mut min_x, mut max_x) = (0.0f32, 0.0f32);
+        for item in items {
+            match item {
+                Object::String(s) => {
+                    for code in font.codes(s.as_bytes()) {
+                        let mut advance = font.advance(code) * size + gs.char_space;
+                        // Word spacing applies to the single-byte code 32 only
+                        if code == 32 && !font.two_byte {
+                            advance += gs.word_space;
+                        }
+                        tx += advance * h_scale;
+                        min_x = min_x.min(tx);
+                        max_x = max_x.max(tx);
+                    }
+                }
+                other => {
+                    if let Some(n) = other.as_real() {
+                        tx -= n as f32 / 1000.0 * size * h_scale;
+                    }
+                }
+            }
+        }
+
+        let (y0, y1) = (font.descent * size, font.ascent * size);
+        let text_rect = Rect::new(min_x, y0.min(y1) + gs.rise, max_x, y0.max(y1) + gs.rise);
+        let trm = self.tm.concat(&gs.ctm);
+        let bbox = transform_rect(&text_rect, &trm);
+
+        self.tm = Matrix::translate(tx, 0.0).concat(&self.tm);
+        let adjustment = if size * h_scale != 0.0 {
+            -tx * 1000.0 / (size * h_scale)
+        } else {
+            0.0
+        };
+        Mark::Text { bbox, adjustment }
+    }
+}
+
+/// Transform a rectangle, including degenerate ones, to its bounding box
+fn transform_rect(rect: &Rect, m: &Matrix) -> Rect {
+    let mut out = Rect::EMPTY;
+    for (x, y) in [
+        (rect.x0, rect.y0),
+        (rect.x1, rect.y0),
+        (rect.x0, rect.y1),
+        (rect.x1, rect.y1),
+    ] {
+        out.include_point(Point::new(x, y).transform(m));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_write_roundtrip() {
+        let data = b"q 1 0 0 1 10 20 cm\nBT /F1 12 Tf (Hello) Tj [(A) -120 (B)] TJ ET\n\
+                     BI /W 3 /H 1 /BPC 8 /CS /G ID \x00EI\nEI Q";
+        let ops = parse_content(data).unwrap();
+        let names: Vec<_> = ops.iter().map(|o| o.operator.as_str()).collect();
+        assert_eq!(names, ["q", "cm", "BT", "Tf", "Tj", "TJ", "ET", "BI", "Q"]);
+        // The fixed-length image data contains a fake EI that must be skipped
+        assert_eq!(
+            ops[7].operands[1].as_string().unwrap().as_bytes(),
+            b"\x00EI"
+        );
+
+        let reparsed = parse_content(&write_content(&ops)).unwrap();
+        assert_eq!(reparsed, ops);
+    }
+
+    #[test]
+    fn test_pretty_print() {
+        let data = b"q 1 0 0 1 10 20 cm BT/F1 12 Tf(Hi)Tj ET\n\
+                     /Span<</MCID 0>>BDC BI /W 3 /H 1 /BPC 8 /CS /G ID \x00EI\nEI EMC Q";
+        let pretty = pretty_print(data).unwrap();
+        assert_eq!(
+            pretty,
+            "q\n  1 0 0 1 10 20 cm\n  BT\n    /F1 12 Tf\n    (Hi) Tj\n  ET\n\
+             \x20 /Span <</MCID 0>> BDC\n    BI\n      /BPC 8\n      /CS /G\n      /H 1\n\
+             \x20     /W 3\n    ID <004549>\n    EI\n  EMC\nQ\n"
+        );
+        // Unbalanced closing operators do not indent below the margin
+        assert_eq!(pretty_print(b"Q Q 0 g").unwrap(), "Q\nQ\n0 g\n");
+    }
+
+    #[test]
+    fn test_text_bounds() {
+        let fonts = HashMap::new();
+        let mut state = ContentState::new(&fonts);
+        let ops = parse_content(b"2 0 0 2 0 0 cm BT /F1 10 Tf 5 50 Td (ab) Tj (c) Tj ET").unwrap();
+        let marks: Vec<_> = ops.iter().filter_map(|op| state.step(op)).collect();
+
+        // Unknown font: each glyph is one em wide
+        let Mark::Text { bbox, adjustment } = &marks[0] else {
+            panic!("expected text");
+        };
+        assert_eq!(*bbox, Rect::new(10.0, 94.0, 50.0, 120.0));
+        assert_eq!(*adjustment, -2000.0);
+        let Mark::Text { bbox, .. } = &marks[1] else {
+            panic!("expected text");
+        };
+        assert_eq!(bbox.x0, 50.0);
+    }
+
+    #[test]
+    fn test_standard_font_fallback() {
+        let doc =
+            Document::open_bytes(b"%PDF-1.7\ntrailer\n<< /Size 1 >>\n%%EOF\n".to_vec()).unwrap();
+        let mut font = Dict::new();
+        font.insert(Name::new("Subtype"), Object::Name(Name::new("Type1")));
+        font.insert(Name::new("BaseFont"), Object::Name(Name::new("Helvetica")));
+        let metrics = FontMetrics::load(&doc, &font).unwrap();
+        assert_eq!(metrics.width(32), 278.0);
+        assert_eq!(metrics.width(87), 944.0);
+        assert_eq!(metrics.substitute(), Some("Helvetica"));
+
+        // Explicit widths win over the built-in metrics
+        font.insert(Name::new("FirstChar"), Object::Int(87));
+        font.insert(Name::new("Widths"), Object::Array(vec![Object::Int(500)]));
+        let metrics = FontMetrics::load(&doc, &font).unwrap();
+        assert_eq!(metrics.width(87), 500.0);
+        assert_eq!(metrics.width(32), DEFAULT_GLYPH_WIDTH);
+    }
+}
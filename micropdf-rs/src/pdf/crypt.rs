@@ -3,14 +3,21 @@
 //! Supports RC4 and AES encryption algorithms with password authentication.
 
 use crate::fitz::error::{Error, Result};
-use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use crate::pdf::object::{Dict, Name, Object, PdfString};
+use aes::cipher::{
+    BlockDecrypt, BlockDecryptMut, BlockEncrypt, BlockEncryptMut, KeyInit, KeyIvInit,
+};
 use md5::{Digest, Md5};
+use sha2::{Sha256, Sha384, Sha512};
 
 type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
 type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
 type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
 type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
 
+/// Standard security handler password padding (Algorithm 2, step a)
+const PADDING: &[u8; 32] = b"\x28\xBF\x4E\x5E\x4E\x75\x8A\x41\x64\x00\x4E\x56\xFF\xFA\x01\x08\x2E\x2E\x00\xB6\xD0\x68\x3E\x80\x2F\x0C\xA9\xFE\x64\x53\x69\x7A";
+
 /// PDF encryption algorithm type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EncryptionAlgorithm {
@@ -67,6 +74,17 @@ pub enum Permission {
     PrintHq = 1 << 11,
 }
 
+/// Which password a [`Crypt`] was authenticated with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthLevel {
+    /// No password has been accepted yet
+    None,
+    /// The user password was accepted; /P restrictions apply
+    User,
+    /// The owner password was accepted; all operations are permitted
+    Owner,
+}
+
 /// PDF encryption context
 #[derive(Clone)]
 pub struct Crypt {
@@ -90,6 +108,18 @@ pub struct Crypt {
     encrypt_metadata: bool,
     /// Document ID
     document_id: Vec<u8>,
+    /// /O entry of the encryption dictionary
+    o: Vec<u8>,
+    /// /U entry of the encryption dictionary
+    u: Vec<u8>,
+    /// /OE entry (revision 5+)
+    oe: Vec<u8>,
+    /// /UE entry (revision 5+)
+    ue: Vec<u8>,
+    /// /Perms entry (revision 5+)
+    perms: Vec<u8>,
+    /// Password the key was recovered with
+    auth: AuthLevel,
 }
 
 impl Crypt {
@@ -119,6 +149,12 @@ impl Crypt {
             permissions,
             encrypt_metadata: true,
             document_id,
+            o: Vec::new(),
+            u: Vec::new(),
+            oe: Vec::new(),
+            ue: Vec::new(),
+            perms: Vec::new(),
+            auth: AuthLevel::None,
         };
 
         // Compute encryption key
@@ -127,6 +163,258 @@ impl Crypt {
         Ok(crypt)
     }
 
+    /// Load the standard security handler from an /Encrypt dictionary
+    ///
+    /// `document_id` is the first string of the trailer /ID array. The
+    /// returned context holds no key until [`Crypt::authenticate`] succeeds.
+    pub fn from_encrypt_dict(dict: &Dict, document_id: &[u8]) -> Result<Self> {
+        let filter = dict.get("Filter").and_then(Object::as_name);
+        if filter.is_some_and(|f| f.as_str() != "Standard") {
+            return Err(Error::encryption("unsupported security handler"));
+        }
+
+        let int = |key: &str| dict.get(key).and_then(Object::as_int);
+        let bytes = |key: &str| {
+            dict.get(key)
+                .and_then(Object::as_string)
+                .map(|s| s.as_bytes().to_vec())
+                .unwrap_or_default()
+        };
+
+        let version = int("V").unwrap_or(0) as i32;
+        let revision = int("R").unwrap_or(0) as i32;
+        let length = int("Length").unwrap_or(40) as usize;
+
+        let algorithm = match version {
+            1 => EncryptionAlgorithm::Rc4_40,
+            2 | 3 if length <= 40 => EncryptionAlgorithm::Rc4_40,
+            2 | 3 => EncryptionAlgorithm::Rc4_128,
+            4 => match crypt_filter_method(dict) {
+                Some("AESV2") => EncryptionAlgorithm::Aes128,
+                Some("None") => EncryptionAlgorithm::None,
+                _ => EncryptionAlgorithm::Rc4_128,
+            },
+            5 => EncryptionAlgorithm::Aes256,
+            _ => {
+                return Err(Error::encryption(format!(
+                    "unsupported encryption version {version}"
+                )));
+            }
+        };
+        if !(2..=6).contains(&revision) {
+            return Err(Error::encryption(format!(
+                "unsupported encryption revision {revision}"
+            )));
+        }
+
+        let key_length = match algorithm {
+            EncryptionAlgorithm::Rc4_128 if version >= 2 => (length / 8).clamp(5, 16),
+            EncryptionAlgorithm::None => 16,
+            _ => algorithm.key_length(),
+        };
+
+        let o = bytes("O");
+        let u = bytes("U");
+        let min_len = if revision >= 5 { 48 } else { 32 };
+        if o.len() < min_len || u.len() < min_len {
+            return Err(Error::encryption("invalid /O or /U entry"));
+        }
+
+        Ok(Self {
+            algorithm,
+            version,
+            revision,
+            key_length,
+            key: Vec::new(),
+            owner_password: Vec::new(),
+            user_password: Vec::new(),
+            permissions: int("P").unwrap_or(0) as i32 as u32,
+            encrypt_metadata: dict
+                .get("EncryptMetadata")
+                .and_then(Object::as_bool)
+                .unwrap_or(true),
+            document_id: document_id.to_vec(),
+            o,
+            u,
+            oe: bytes("OE"),
+            ue: bytes("UE"),
+            perms: bytes("Perms"),
+            auth: AuthLevel::None,
+        })
+    }
+
+    /// Authenticate with a password, trying the owner password first and
+    /// then the user password (Algorithms 7 and 6, or 12 and 11 for
+    /// AES-256), as MuPDF does
+    ///
+    /// A password that is both grants owner access. On success the file
+    /// key is recovered and the accepted level returned.
+    pub fn authenticate(&mut self, password: &[u8]) -> AuthLevel {
+        let result = if self.revision >= 5 {
+            self.authenticate_owner_r6(password)
+                .map(|key| (key, AuthLevel::Owner))
+                .or_else(|| {
+                    self.authenticate_user_r6(password)
+                        .map(|key| (key, AuthLevel::User))
+                })
+                .filter(|(key, _)| self.revision == 5 || self.validate_perms(key))
+        } else {
+            self.authenticate_owner_r4(password)
+                .map(|key| (key, AuthLevel::Owner))
+                .or_else(|| {
+                    self.authenticate_user_r4(password)
+                        .map(|key| (key, AuthLevel::User))
+                })
+        };
+
+        match result {
+            Some((key, level)) => {
+                self.key = key;
+                self.auth = level;
+                level
+            }
+            None => AuthLevel::None,
+        }
+    }
+
+    /// Password level accepted by the last successful [`Crypt::authenticate`]
+    pub fn auth_level(&self) -> AuthLevel {
+        self.auth
+    }
+
+    /// Algorithm 6: check a user password against /U
+    fn authenticate_user_r4(&self, password: &[u8]) -> Option<Vec<u8>> {
+        let key = self.compute_file_key_r4(password);
+        let u = self.compute_u_r4(&key);
+        let n = if self.revision >= 3 { 16 } else { 32 };
+        (u[..n] == self.u[..n]).then_some(key)
+    }
+
+    /// Algorithm 7: decrypt /O with the owner key to recover the user
+    /// password, then authenticate with that
+    fn authenticate_owner_r4(&self, password: &[u8]) -> Option<Vec<u8>> {
+        let owner_key = self.compute_owner_key_r4(password);
+        let mut user = self.o[..32].to_vec();
+        if self.revision == 2 {
+            user = rc4(&owner_key, &user);
+        } else {
+            for i in (0..20u8).rev() {
+                let round_key: Vec<u8> = owner_key.iter().map(|b| b ^ i).collect();
+                user = rc4(&round_key, &user);
+            }
+        }
+        self.authenticate_user_r4(&user)
+    }
+
+    /// Algorithm 2: derive the file key from a (user) password
+    fn compute_file_key_r4(&self, password: &[u8]) -> Vec<u8> {
+        let mut hasher = Md5::new();
+        hasher.update(pad_password(password));
+        hasher.update(&self.o[..32]);
+        hasher.update(self.permissions.to_le_bytes());
+        hasher.update(&self.document_id);
+        if self.revision >= 4 && !self.encrypt_metadata {
+            hasher.update([0xFF, 0xFF, 0xFF, 0xFF]);
+        }
+        let mut key = hasher.finalize().to_vec();
+
+        let n = if self.revision == 2 {
+            5
+        } else {
+            self.key_length
+        };
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                key = Md5::digest(&key[..n]).to_vec();
+            }
+        }
+        key.truncate(n);
+        key
+    }
+
+    /// Algorithm 3, steps a-d: the RC4 key used to encrypt /O
+    fn compute_owner_key_r4(&self, owner_password: &[u8]) -> Vec<u8> {
+        let mut digest = Md5::digest(pad_password(owner_password)).to_vec();
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                digest = Md5::digest(&digest).to_vec();
+            }
+        }
+        let n = if self.revision == 2 {
+            5
+        } else {
+            self.key_length
+        };
+        digest.truncate(n);
+        digest
+    }
+
+    /// Algorithm 3: compute /O from the owner and user passwords
+    fn compute_o_r4(&self, owner_password: &[u8], user_password: &[u8]) -> Vec<u8> {
+        let owner_key = self.compute_owner_key_r4(owner_password);
+        let mut o = rc4(&owner_key, &pad_password(user_password));
+        if self.revision >= 3 {
+            for i in 1..20u8 {
+                let round_key: Vec<u8> = owner_key.iter().map(|b| b ^ i).collect();
+                o = rc4(&round_key, &o);
+            }
+        }
+        o
+    }
+
+    /// Algorithms 4 and 5: compute /U from the file key
+    fn compute_u_r4(&self, key: &[u8]) -> Vec<u8> {
+        if self.revision == 2 {
+            return rc4(key, PADDING);
+        }
+        let mut hasher = Md5::new();
+        hasher.update(PADDING);
+        hasher.update(&self.document_id);
+        let mut u = rc4(key, &hasher.finalize());
+        for i in 1..20u8 {
+            let round_key: Vec<u8> = key.iter().map(|b| b ^ i).collect();
+            u = rc4(&round_key, &u);
+        }
+        u.resize(32, 0);
+        u
+    }
+
+    /// Algorithm 11: validate the user password against /U and unwrap /UE
+    fn authenticate_user_r6(&self, password: &[u8]) -> Option<Vec<u8>> {
+        let password = truncate_password(password);
+        let hash = hash_r6(password, &self.u[32..40], &[], self.revision);
+        if hash[..] != self.u[..32] {
+            return None;
+        }
+        let intermediate = hash_r6(password, &self.u[40..48], &[], self.revision);
+        aes256_cbc_unwrap(&intermediate, &self.ue)
+    }
+
+    /// Algorithm 12: validate the owner password against /O and unwrap /OE
+    fn authenticate_owner_r6(&self, password: &[u8]) -> Option<Vec<u8>> {
+        let password = truncate_password(password);
+        let udata = &self.u[..48];
+        let hash = hash_r6(password, &self.o[32..40], udata, self.revision);
+        if hash[..] != self.o[..32] {
+            return None;
+        }
+        let intermediate = hash_r6(password, &self.o[40..48], udata, self.revision);
+        aes256_cbc_unwrap(&intermediate, &self.oe)
+    }
+
+    /// Algorithm 13: check /Perms decrypts to /P with the file key
+    fn validate_perms(&self, key: &[u8]) -> bool {
+        if self.perms.len() < 16 {
+            return false;
+        }
+        let Ok(cipher) = aes::Aes256::new_from_slice(key) else {
+            return false;
+        };
+        let mut block = aes::Block::clone_from_slice(&self.perms[..16]);
+        cipher.decrypt_block(&mut block);
+        &block[9..12] == b"adb" && block[..4] == self.permissions.to_le_bytes()
+    }
+
     /// Create encryption context for new document
     pub fn new_encrypt(
         owner_password: &str,
@@ -151,7 +439,7 @@ impl Crypt {
             _ => return Err(Error::Generic("Invalid encryption algorithm".to_string())),
         };
 
-        Self::new(
+        let mut crypt = Self::new(
             algorithm,
             version,
             revision,
@@ -159,13 +447,112 @@ impl Crypt {
             user_password.as_bytes().to_vec(),
             permissions,
             document_id,
-        )
+        )?;
+        crypt.compute_security_entries()?;
+        Ok(crypt)
+    }
+
+    /// Compute /O, /U (and /OE, /UE, /Perms for AES-256) along with the
+    /// matching file key, so the context can write a standard /Encrypt dict
+    fn compute_security_entries(&mut self) -> Result<()> {
+        let owner = if self.owner_password.is_empty() {
+            self.user_password.clone()
+        } else {
+            self.owner_password.clone()
+        };
+        let user = self.user_password.clone();
+
+        if self.revision >= 5 {
+            let key = random_bytes(32);
+            let user = truncate_password(&user);
+            let owner = truncate_password(&owner);
+
+            let salts = random_bytes(32);
+            let (u_validation, u_key) = (&salts[0..8], &salts[8..16]);
+            let (o_validation, o_key) = (&salts[16..24], &salts[24..32]);
+
+            let mut u = hash_r6(user, u_validation, &[], self.revision).to_vec();
+            u.extend_from_slice(u_validation);
+            u.extend_from_slice(u_key);
+            let ue = aes256_cbc_wrap(&hash_r6(user, u_key, &[], self.revision), &key)?;
+
+            let mut o = hash_r6(owner, o_validation, &u, self.revision).to_vec();
+            o.extend_from_slice(o_validation);
+            o.extend_from_slice(o_key);
+            let oe = aes256_cbc_wrap(&hash_r6(owner, o_key, &u, self.revision), &key)?;
+
+            let mut perms = [0u8; 16];
+            perms[..4].copy_from_slice(&self.permissions.to_le_bytes());
+            perms[4..8].fill(0xFF);
+            perms[8] = if self.encrypt_metadata { b'T' } else { b'F' };
+            perms[9..12].copy_from_slice(b"adb");
+            perms[12..16].copy_from_slice(&random_bytes(4));
+            let cipher = aes::Aes256::new_from_slice(&key)
+                .map_err(|e| Error::encryption(format!("AES key error: {e:?}")))?;
+            let mut block = aes::Block::clone_from_slice(&perms);
+            cipher.encrypt_block(&mut block);
+
+            self.key = key;
+            self.u = u;
+            self.ue = ue;
+            self.o = o;
+            self.oe = oe;
+            self.perms = block.to_vec();
+        } else {
+            self.o = self.compute_o_r4(&owner, &user);
+            self.key = self.compute_file_key_r4(&user);
+            self.u = self.compute_u_r4(&self.key);
+        }
+        self.auth = AuthLevel::Owner;
+        Ok(())
+    }
+
+    /// Build the /Encrypt dictionary describing this context
+    pub fn encrypt_dict(&self) -> Dict {
+        let mut dict = Dict::new();
+        let string = |b: &[u8]| Object::String(PdfString::new(b.to_vec()));
+        dict.insert(Name::new("Filter"), Object::Name(Name::new("Standard")));
+        dict.insert(Name::new("V"), Object::Int(self.version as i64));
+        dict.insert(Name::new("R"), Object::Int(self.revision as i64));
+        dict.insert(Name::new("Length"), Object::Int(self.key_length as i64 * 8));
+        dict.insert(Name::new("O"), string(&self.o));
+        dict.insert(Name::new("U"), string(&self.u));
+        dict.insert(Name::new("P"), Object::Int(self.permissions as i32 as i64));
+        if self.revision >= 5 {
+            dict.insert(Name::new("OE"), string(&self.oe));
+            dict.insert(Name::new("UE"), string(&self.ue));
+            dict.insert(Name::new("Perms"), string(&self.perms));
+        }
+        if self.version >= 4 {
+            let cfm = if self.algorithm == EncryptionAlgorithm::Aes256 {
+                "AESV3"
+            } else if self.algorithm == EncryptionAlgorithm::Aes128 {
+                "AESV2"
+            } else {
+                "V2"
+            };
+            let mut std_cf = Dict::new();
+            std_cf.insert(Name::new("CFM"), Object::Name(Name::new(cfm)));
+            std_cf.insert(Name::new("Length"), Object::Int(self.key_length as i64));
+            let mut cf = Dict::new();
+            cf.insert(Name::new("StdCF"), Object::Dict(std_cf));
+            dict.insert(Name::new("CF"), Object::Dict(cf));
+            dict.insert(Name::new("StmF"), Object::Name(Name::new("StdCF")));
+            dict.insert(Name::new("StrF"), Object::Name(Name::new("StdCF")));
+            if !self.encrypt_metadata {
+                dict.insert(Name::new("EncryptMetadata"), Object::Bool(false));
+            }
+        }
+        dict
     }
 
     /// Compute the encryption key
     fn compute_encryption_key(&mut self) -> Result<()> {
-        // PDF password padding
-        const PADDING: &[u8; 32] = b"\x28\xBF\x4E\x5E\x4E\x75\x8A\x41\x64\x00\x4E\x56\xFF\xFA\x01\x08\x2E\x2E\x00\xB6\xD0\x68\x3E\x80\x2F\x0C\xA9\xFE\x64\x53\x69\x7A";
+        // AES-256 file keys are random rather than derived from the password
+        if self.revision >= 5 {
+            self.key = random_bytes(self.key_length);
+            return Ok(());
+        }
 
         // Create MD5 hash
         let mut hasher = Md5::new();
@@ -214,6 +601,11 @@ impl Crypt {
 
     /// Compute object encryption key
     fn compute_object_key(&self, num: i32, generation: i32) -> Vec<u8> {
+        // AES-256 uses the file key directly (Algorithm 1.A)
+        if self.revision >= 5 {
+            return self.key.clone();
+        }
+
         let mut hasher = Md5::new();
         hasher.update(&self.key);
         hasher.update(&num.to_le_bytes()[..3]); // Lower 3 bytes of object number
@@ -230,32 +622,7 @@ impl Crypt {
 
     /// Encrypt data using RC4
     fn encrypt_rc4(&self, data: &[u8], obj_key: &[u8]) -> Result<Vec<u8>> {
-        // Simple RC4 implementation
-        let mut s: Vec<u8> = (0..=255).collect();
-        let mut j: u8 = 0;
-
-        // Key scheduling
-        for i in 0..256 {
-            j = j
-                .wrapping_add(s[i])
-                .wrapping_add(obj_key[i % obj_key.len()]);
-            s.swap(i, j as usize);
-        }
-
-        // Encryption
-        let mut result = Vec::with_capacity(data.len());
-        let mut i: u8 = 0;
-        let mut j: u8 = 0;
-
-        for &byte in data {
-            i = i.wrapping_add(1);
-            j = j.wrapping_add(s[i as usize]);
-            s.swap(i as usize, j as usize);
-            let k = s[(s[i as usize].wrapping_add(s[j as usize])) as usize];
-            result.push(byte ^ k);
-        }
-
-        Ok(result)
+        Ok(rc4(obj_key, data))
     }
 
     /// Decrypt data using RC4 (same as encrypt for RC4)
@@ -303,9 +670,11 @@ impl Crypt {
             .map_err(|e| Error::Generic(format!("AES key/IV error: {:?}", e)))?;
 
         let mut result = vec![0u8; encrypted.len()];
-        cipher
+        let len = cipher
             .decrypt_padded_b2b_mut::<aes::cipher::block_padding::Pkcs7>(encrypted, &mut result)
-            .map_err(|e| Error::Generic(format!("AES decryption error: {:?}", e)))?;
+            .map_err(|e| Error::Generic(format!("AES decryption error: {:?}", e)))?
+            .len();
+        result.truncate(len);
 
         Ok(result)
     }
@@ -344,9 +713,11 @@ impl Crypt {
             .map_err(|e| Error::Generic(format!("AES key/IV error: {:?}", e)))?;
 
         let mut result = vec![0u8; encrypted.len()];
-        cipher
+        let len = cipher
             .decrypt_padded_b2b_mut::<aes::cipher::block_padding::Pkcs7>(encrypted, &mut result)
-            .map_err(|e| Error::Generic(format!("AES decryption error: {:?}", e)))?;
+            .map_err(|e| Error::Generic(format!("AES decryption error: {:?}", e)))?
+            .len();
+        result.truncate(len);
 
         Ok(result)
     }
@@ -422,8 +793,10 @@ impl Crypt {
     }
 
     /// Check if a permission is granted
+    ///
+    /// Authenticating with the owner password grants every permission.
     pub fn has_permission(&self, perm: Permission) -> bool {
-        (self.permissions & (perm as u32)) != 0
+        self.auth == AuthLevel::Owner || (self.permissions & (perm as u32)) != 0
     }
 
     /// Get encryption method name
@@ -439,6 +812,148 @@ impl Crypt {
     }
 }
 
+/// /CFM of the default crypt filter for V4+ dictionaries
+fn crypt_filter_method(dict: &Dict) -> Option<&str> {
+    let stmf = dict
+        .get("StmF")
+        .and_then(Object::as_name)
+        .map(|n| n.as_str())
+        .unwrap_or("Identity");
+    if stmf == "Identity" {
+        return Some("None");
+    }
+    dict.get("CF")?
+        .as_dict()?
+        .get(stmf)?
+        .as_dict()?
+        .get("CFM")?
+        .as_name()
+        .map(|n| n.as_str())
+}
+
+/// Pad or truncate a password to 32 bytes (Algorithm 2, step a)
+fn pad_password(password: &[u8]) -> [u8; 32] {
+    let mut padded = [0u8; 32];
+    let n = password.len().min(32);
+    padded[..n].copy_from_slice(&password[..n]);
+    padded[n..].copy_from_slice(&PADDING[..32 - n]);
+    padded
+}
+
+/// AES-256 passwords are limited to 127 bytes of UTF-8
+fn truncate_password(password: &[u8]) -> &[u8] {
+    &password[..password.len().min(127)]
+}
+
+/// Plain RC4 keystream XOR
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut s: Vec<u8> = (0..=255).collect();
+    let mut j: u8 = 0;
+    for i in 0..256 {
+        j = j.wrapping_add(s[i]).wrapping_add(key[i % key.len()]);
+        s.swap(i, j as usize);
+    }
+
+    let mut i: u8 = 0;
+    let mut j: u8 = 0;
+    data.iter()
+        .map(|&byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(s[i as usize]);
+            s.swap(i as usize, j as usize);
+            byte ^ s[(s[i as usize].wrapping_add(s[j as usize])) as usize]
+        })
+        .collect()
+}
+
+/// Algorithm 2.B: the revision 6 password hash (plain SHA-256 for R5)
+fn hash_r6(password: &[u8], salt: &[u8], udata: &[u8], revision: i32) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(password);
+    hasher.update(salt);
+    hasher.update(udata);
+    let mut k = hasher.finalize().to_vec();
+
+    if revision >= 6 {
+        let mut round = 0usize;
+        loop {
+            let mut k1 = Vec::with_capacity(64 * (password.len() + k.len() + udata.len()));
+            for _ in 0..64 {
+                k1.extend_from_slice(password);
+                k1.extend_from_slice(&k);
+                k1.extend_from_slice(udata);
+            }
+
+            let mut e = vec![0u8; k1.len()];
+            Aes128CbcEnc::new_from_slices(&k[..16], &k[16..32])
+                .expect("16-byte key and IV")
+                .encrypt_padded_b2b_mut::<aes::cipher::block_padding::NoPadding>(&k1, &mut e)
+                .expect("input is a multiple of the block size");
+
+            let sum: u32 = e[..16].iter().map(|&b| b as u32).sum();
+            k = match sum % 3 {
+                0 => Sha256::digest(&e).to_vec(),
+                1 => Sha384::digest(&e).to_vec(),
+                _ => Sha512::digest(&e).to_vec(),
+            };
+
+            round += 1;
+            if round >= 64 && (*e.last().unwrap() as usize) <= round - 32 {
+                break;
+            }
+        }
+    }
+
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&k[..32]);
+    out
+}
+
+/// Decrypt a 32-byte /UE or /OE value with a zero IV and no padding
+fn aes256_cbc_unwrap(key: &[u8], wrapped: &[u8]) -> Option<Vec<u8>> {
+    if wrapped.len() < 32 {
+        return None;
+    }
+    let mut out = vec![0u8; 32];
+    Aes256CbcDec::new_from_slices(key, &[0u8; 16])
+        .ok()?
+        .decrypt_padded_b2b_mut::<aes::cipher::block_padding::NoPadding>(&wrapped[..32], &mut out)
+        .ok()?;
+    Some(out)
+}
+
+/// Encrypt a 32-byte file key with a zero IV and no padding
+fn aes256_cbc_wrap(key: &[u8], file_key: &[u8]) -> Result<Vec<u8>> {
+    let mut out = vec![0u8; file_key.len()];
+    Aes256CbcEnc::new_from_slices(key, &[0u8; 16])
+        .map_err(|e| Error::encryption(format!("AES key/IV error: {e:?}")))?
+        .encrypt_padded_b2b_mut::<aes::cipher::block_padding::NoPadding>(file_key, &mut out)
+        .map_err(|e| Error::encryption(format!("AES encryption error: {e:?}")))?;
+    Ok(out)
+}
+
+/// Unpredictable bytes for salts and file keys
+fn random_bytes(len: usize) -> Vec<u8> {
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut out = Vec::with_capacity(len);
+    while out.len() < len {
+        let mut h = std::collections::hash_map::RandomState::new().build_hasher();
+        h.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        if let Ok(t) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+            h.write_u128(t.as_nanos());
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(h.finish().to_le_bytes());
+        hasher.update(&out);
+        out.extend_from_slice(&hasher.finalize());
+    }
+    out.truncate(len);
+    out
+}
+
 impl std::fmt::Debug for Crypt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Crypt")
@@ -448,6 +963,7 @@ impl std::fmt::Debug for Crypt {
             .field("key_length", &self.key_length)
             .field("permissions", &format!("0x{:08X}", self.permissions))
             .field("encrypt_metadata", &self.encrypt_metadata)
+            .field("auth", &self.auth)
             .finish()
     }
 }
@@ -612,4 +1128,95 @@ mod tests {
         let decrypted = no_crypt.decrypt_data(&encrypted, 1, 0).unwrap();
         assert_eq!(decrypted, original);
     }
+
+    fn reload(crypt: &Crypt) -> Crypt {
+        Crypt::from_encrypt_dict(&crypt.encrypt_dict(), &crypt.document_id).unwrap()
+    }
+
+    #[test]
+    fn test_standard_handler_r3_user_and_owner() {
+        let perms = !(Permission::Copy as u32) & 0xFFFF_FFFC;
+        let crypt = Crypt::new_encrypt(
+            "owner",
+            "user",
+            vec![7; 16],
+            perms,
+            EncryptionAlgorithm::Rc4_128,
+        )
+        .unwrap();
+
+        let mut loaded = reload(&crypt);
+        assert_eq!(loaded.authenticate(b"wrong"), AuthLevel::None);
+        assert_eq!(loaded.authenticate(b"user"), AuthLevel::User);
+        assert_eq!(loaded.key, crypt.key);
+        assert!(!loaded.has_permission(Permission::Copy));
+        assert!(loaded.has_permission(Permission::Print));
+
+        let mut loaded = reload(&crypt);
+        assert_eq!(loaded.authenticate(b"owner"), AuthLevel::Owner);
+        assert_eq!(loaded.key, crypt.key);
+        assert!(loaded.has_permission(Permission::Copy));
+    }
+
+    #[test]
+    fn test_standard_handler_r6_owner_recovers_key() {
+        let perms = !(Permission::Copy as u32) & 0xFFFF_FFFC;
+        let crypt = Crypt::new_encrypt(
+            "owner",
+            "user",
+            vec![7; 16],
+            perms,
+            EncryptionAlgorithm::Aes256,
+        )
+        .unwrap();
+        let encrypted = crypt.encrypt_data(b"secret text", 4, 0).unwrap();
+
+        let mut loaded = reload(&crypt);
+        assert_eq!(loaded.revision(), 6);
+        assert_eq!(loaded.authenticate(b""), AuthLevel::None);
+        assert_eq!(loaded.authenticate(b"owner"), AuthLevel::Owner);
+        assert_eq!(
+            loaded.decrypt_data(&encrypted, 4, 0).unwrap(),
+            b"secret text"
+        );
+        assert!(loaded.has_permission(Permission::Copy));
+
+        let mut loaded = reload(&crypt);
+        assert_eq!(loaded.authenticate(b"user"), AuthLevel::User);
+        assert_eq!(
+            loaded.decrypt_data(&encrypted, 4, 0).unwrap(),
+            b"secret text"
+        );
+        assert!(!loaded.has_permission(Permission::Copy));
+    }
+
+    #[test]
+    fn test_same_user_and_owner_password_grants_owner() {
+        let perms = !(Permission::Copy as u32) & 0xFFFF_FFFC;
+        for algorithm in [EncryptionAlgorithm::Rc4_128, EncryptionAlgorithm::Aes256] {
+            let crypt = Crypt::new_encrypt("same", "same", vec![7; 16], perms, algorithm).unwrap();
+
+            let mut loaded = reload(&crypt);
+            assert_eq!(loaded.authenticate(b"same"), AuthLevel::Owner);
+            assert_eq!(loaded.auth_level(), AuthLevel::Owner);
+            assert_eq!(loaded.key, crypt.key);
+            assert!(loaded.has_permission(Permission::Copy));
+        }
+    }
+
+    #[test]
+    fn test_standard_handler_rejects_tampered_perms() {
+        let crypt = Crypt::new_encrypt(
+            "owner",
+            "user",
+            vec![7; 16],
+            0xFFFF_FFFC,
+            EncryptionAlgorithm::Aes256,
+        )
+        .unwrap();
+        let mut dict = crypt.encrypt_dict();
+        dict.insert(Name::new("P"), Object::Int(-64));
+        let mut loaded = Crypt::from_encrypt_dict(&dict, &crypt.document_id).unwrap();
+        assert_eq!(loaded.authenticate(b"user"), AuthLevel::None);
+    }
 }
@@ -1 +1,1065 @@
 //! PDF document implementation
+//!
+//! A parsed view over the bytes of a PDF file: the trailer, indirect object
+//! lookup (with decryption for encrypted files) and the flattened page tree.
+
+use crate::fitz::error::{Error, Result};
+use crate::fitz::stream::Stream;
+use crate::pdf::crypt::{AuthLevel, Crypt};
+use crate::pdf::filter;
+use crate::pdf::limits::parse_limits;
+use crate::pdf::object::{Dict, Name, ObjRef, Object, PdfString};
+use crate::pdf::page::{Page, PageRange, Size};
+use crate::pdf::parse_cache::{ParseCache, ParsedXref, parse_cache};
+use crate::pdf::parser;
+use crate::pdf::write;
+use crate::pdf::xref::{self, ObjectStream};
+use bytes::Bytes;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Page attributes inherited from ancestor /Pages nodes
+const INHERITABLE: [&str; 4] = ["Resources", "MediaBox", "CropBox", "Rotate"];
+
+/// Maximum depth of the page tree and of reference chains
+const MAX_DEPTH: usize = 64;
+
+/// A parsed PDF document
+pub struct Document {
+    data: Bytes,
+    xref: Arc<ParsedXref>,
+    crypt: Option<Crypt>,
+    encrypt_ref: Option<ObjRef>,
+    pages: OnceLock<Vec<(ObjRef, Dict)>>,
+    /// Object streams decoded so far, by object number
+    obj_streams: Mutex<HashMap<i32, Arc<ObjectStream>>>,
+    /// Bytes its streams may still decode to, starting from the
+    /// `max_decoded_total` limit in force when the document was opened
+    decode_budget: AtomicU64,
+    /// For documents opened from a linearized prefix, the only page
+    /// available
+    first_page: Option<ObjRef>,
+}
+
+impl Document {
+    /// Open a document from a file on disk
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_bytes(std::fs::read(path)?)
+    }
+
+    /// Open a document from a file on disk by memory-mapping it
+    ///
+    /// Parsing works on the mapping rather than a copy of the file, so its
+    /// pages stay in the page cache, where the system can reclaim them,
+    /// instead of the heap. The document's bytes keep the mapping alive.
+    /// Files that cannot be mapped are read into memory instead.
+    pub fn open_mmap<P: AsRef<Path>>(path: P) -> Result<Self> {
+        match Stream::open_file(path.as_ref())?.source_bytes() {
+            Some(data) => Self::open_bytes(data),
+            None => Self::open(path),
+        }
+    }
+
+    /// Open a document from its bytes
+    ///
+    /// Objects are located through the cross-reference sections when they
+    /// are intact; a missing trailer, bad `startxref` or wrong offset makes
+    /// the file be rescanned for object definitions instead. The object
+    /// offsets and trailer are shared with earlier opens of
+    /// identical bytes through the [`parse_cache`]. Files larger, or with
+    /// more objects, than the [`ParseLimits`](crate::pdf::limits::ParseLimits)
+    /// allow are rejected.
+    pub fn open_bytes(data: impl Into<Bytes>) -> Result<Self> {
+        let data = data.into();
+        if !data.starts_with(b"%PDF-") && parser::find_bytes(&data, b"%PDF-", 0).is_none() {
+            return Err(Error::format("not a PDF file"));
+        }
+        let limits = parse_limits();
+        if data.len() as u64 > limits.max_file_size {
+            return Err(Error::limit(format!(
+                "document of {} bytes is over the limit of {}",
+                data.len(),
+                limits.max_file_size
+            )));
+        }
+        let cache = parse_cache();
+        let key = ParseCache::key(&data);
+        let xref = match cache.get(&key) {
+            Some(xref) => xref,
+            None => {
+                let xref = Arc::new(ParsedXref::load(&data)?);
+                cache.insert(key, xref.clone());
+                xref
+            }
+        };
+        let objects = xref.offsets.len() + xref.compressed.len();
+        if objects as u64 > limits.max_objects {
+            return Err(Error::limit(format!(
+                "document defines {objects} objects, over the limit of {}",
+                limits.max_objects
+            )));
+        }
+
+        let mut doc = Self {
+            data,
+            xref,
+            crypt: None,
+            encrypt_ref: None,
+            pages: OnceLock::new(),
+            obj_streams: Mutex::new(HashMap::new()),
+            decode_budget: AtomicU64::new(limits.max_decoded_total),
+            first_page: None,
+        };
+        doc.load_crypt()?;
+        Ok(doc)
+    }
+
+    /// Open the first page of a linearized file from its leading bytes
+    ///
+    /// See [`open_linearized_prefix`](Self::open_linearized_prefix).
+    pub fn open_first_page(prefix_bytes: &[u8]) -> Result<Page> {
+        Self::open_linearized_prefix(prefix_bytes)?.page(0)
+    }
+
+    /// Open a linearized file from a prefix that covers at least its
+    /// first-page section
+    ///
+    /// The linearization dictionary gives the first page's object and where
+    /// its section ends. Objects are located through the first-page
+    /// cross-reference section that follows the dictionary, and its
+    /// trailer stands in for the final one. Only the first page is
+    /// available, so the page count is 1.
+    pub fn open_linearized_prefix(prefix_bytes: &[u8]) -> Result<Self> {
+        let data = Bytes::copy_from_slice(prefix_bytes);
+        let header = parser::find_bytes(&data, b"%PDF-", 0)
+            .filter(|&pos| pos < 1024)
+            .ok_or_else(|| Error::format("not a PDF file"))?;
+
+        // The linearization dictionary is the first object in the file
+        let window = &data[..data.len().min(header + 1024)];
+        let lin_offset = parser::scan_object_offsets(window)
+            .into_values()
+            .min()
+            .ok_or_else(|| Error::format("no linearization dictionary"))?;
+        let (_, lin) = parser::parse_indirect_object_at(&data, lin_offset)?;
+        let lin = match lin {
+            Object::Dict(d) if d.contains_key("Linearized") => d,
+            _ => return Err(Error::format("not a linearized file")),
+        };
+        let int = |key: &str| {
+            lin.get(key)
+                .and_then(Object::as_int)
+                .filter(|&v| v >= 0)
+                .ok_or_else(|| Error::format(format!("invalid linearization /{key}")))
+        };
+        let first_page = ObjRef::new(int("O")? as i32, 0);
+        let first_page_end = int("E")? as usize;
+        if data.len() < first_page_end {
+            return Err(Error::argument(format!(
+                "prefix of {} bytes ends before the first page section at {first_page_end}",
+                data.len()
+            )));
+        }
+
+        // The first-page cross-reference section, a classic table or a
+        // stream, directly follows the dictionary and covers the objects
+        // of the first-page section
+        let mut p = parser::Parser::at(&data, lin_offset);
+        p.parse_indirect_object()?;
+        let mut xref_offset = p.pos();
+        while data.get(xref_offset).is_some_and(u8::is_ascii_whitespace) {
+            xref_offset += 1;
+        }
+        let (entries, trailer) = xref::read_section(&data, xref_offset)?;
+        let mut offsets = HashMap::new();
+        for entry in entries.iter().filter(|e| e.is_in_use()) {
+            let obj_ref = ObjRef::new(entry.num, i32::from(entry.generation));
+            let offset = usize::try_from(entry.offset)
+                .ok()
+                .filter(|&o| o < data.len())
+                .ok_or_else(|| {
+                    Error::format(format!("object {} is outside the prefix", entry.num))
+                })?;
+            if parser::Parser::at(&data, offset).parse_object_header()? != obj_ref {
+                return Err(Error::format(format!(
+                    "first-page xref points object {} at another object",
+                    entry.num
+                )));
+            }
+            offsets.insert(obj_ref, offset);
+        }
+
+        let mut doc = Self {
+            data,
+            xref: Arc::new(ParsedXref::with_offsets(offsets, trailer)),
+            crypt: None,
+            encrypt_ref: None,
+            pages: OnceLock::new(),
+            obj_streams: Mutex::new(HashMap::new()),
+            decode_budget: AtomicU64::new(parse_limits().max_decoded_total),
+            first_page: Some(first_page),
+        };
+        doc.load_crypt()?;
+        Ok(doc)
+    }
+
+    fn load_crypt(&mut self) -> Result<()> {
+        let Some(encrypt) = self.xref.trailer.get("Encrypt").cloned() else {
+            return Ok(());
+        };
+        self.encrypt_ref = encrypt.as_obj_ref();
+        let dict = match self.resolve(&encrypt)? {
+            Object::Dict(d) => d,
+            _ => return Err(Error::encryption("invalid /Encrypt entry")),
+        };
+        let id = self
+            .xref
+            .trailer
+            .get("ID")
+            .and_then(Object::as_array)
+            .and_then(|ids| ids.first())
+            .and_then(Object::as_string)
+            .map(|s| s.as_bytes().to_vec())
+            .unwrap_or_default();
+
+        let mut crypt = Crypt::from_encrypt_dict(&dict, &id)?;
+        crypt.authenticate(b"");
+        self.crypt = Some(crypt);
+        Ok(())
+    }
+
+    /// Raw bytes of the file
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// The trailer dictionary
+    pub fn trailer(&self) -> &Dict {
+        &self.xref.trailer
+    }
+
+    /// References of every object defined in the file, in ascending order
+    pub fn object_refs(&self) -> Vec<ObjRef> {
+        let mut refs: Vec<ObjRef> = self
+            .xref
+            .offsets
+            .keys()
+            .chain(self.xref.compressed.keys())
+            .copied()
+            .collect();
+        refs.sort();
+        refs
+    }
+
+    /// Whether the document is encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.crypt.is_some()
+    }
+
+    /// The security handler, if the document is encrypted
+    pub fn crypt(&self) -> Option<&Crypt> {
+        self.crypt.as_ref()
+    }
+
+    /// Whether a password is required before objects can be read
+    pub fn needs_password(&self) -> bool {
+        self.crypt
+            .as_ref()
+            .is_some_and(|c| c.auth_level() == AuthLevel::None)
+    }
+
+    /// Authenticate with a user or owner password
+    pub fn authenticate(&mut self, password: &str) -> AuthLevel {
+        let Some(crypt) = self.crypt.as_mut() else {
+            return AuthLevel::Owner;
+        };
+        let level = crypt.authenticate(password.as_bytes());
+        if level != AuthLevel::None {
+            self.pages = OnceLock::new();
+        }
+        level
+    }
+
+    /// Load an indirect object, decrypting strings and stream data
+    ///
+    /// Objects stored in object streams are read from the decoded stream,
+    /// which is already decrypted. Missing objects resolve to null, as the
+    /// specification requires.
+    pub fn load_object(&self, obj_ref: ObjRef) -> Result<Object> {
+        let Some(&offset) = self.xref.offsets.get(&obj_ref) else {
+            return match self.xref.compressed.get(&obj_ref) {
+                Some(&(stm_num, index)) => self.object_stream(stm_num)?.object(obj_ref.num, index),
+                None => Ok(Object::Null),
+            };
+        };
+        let (_, mut obj) = parser::parse_indirect_object_at(&self.data, offset)?;
+
+        if let Some(crypt) = &self.crypt {
+            if Some(obj_ref) != self.encrypt_ref {
+                if crypt.auth_level() == AuthLevel::None {
+                    return Err(Error::encryption("document requires a password"));
+                }
+                decrypt_object(crypt, &mut obj, obj_ref)?;
+            }
+        }
+        Ok(obj)
+    }
+
+    /// The object stream `stm_num`, decoded on first use
+    fn object_stream(&self, stm_num: i32) -> Result<Arc<ObjectStream>> {
+        if let Some(stm) = self.obj_streams.lock().unwrap().get(&stm_num) {
+            return Ok(stm.clone());
+        }
+        // Object streams cannot themselves be compressed
+        let stm_ref = ObjRef::new(stm_num, 0);
+        if !self.xref.offsets.contains_key(&stm_ref) {
+            return Err(Error::syntax(format!(
+                "object stream {stm_num} is not in the file"
+            )));
+        }
+        let obj = self.load_object(stm_ref)?;
+        let Object::Stream { dict, .. } = &obj else {
+            return Err(Error::syntax(format!("object {stm_num} is not a stream")));
+        };
+        let stm = Arc::new(ObjectStream::from_decoded(dict, self.stream_data(&obj)?)?);
+        self.obj_streams
+            .lock()
+            .unwrap()
+            .insert(stm_num, stm.clone());
+        Ok(stm)
+    }
+
+    /// Follow indirect references until a direct object is reached
+    pub fn resolve(&self, obj: &Object) -> Result<Object> {
+        let mut current = obj.clone();
+        for _ in 0..MAX_DEPTH {
+            match current {
+                Object::Ref(r) => current = self.load_object(r)?,
+                other => return Ok(other),
+            }
+        }
+        Err(Error::limit("reference chain too long"))
+    }
+
+    /// Look up `key` in `dict` and resolve the value
+    pub fn resolve_key(&self, dict: &Dict, key: &str) -> Result<Option<Object>> {
+        match dict.get(key) {
+            Some(obj) => self.resolve(obj).map(|o| (!o.is_null()).then_some(o)),
+            None => Ok(None),
+        }
+    }
+
+    /// Resolve the resource `name` of `category` (Font, XObject,
+    /// ExtGState, ColorSpace, Pattern, ...) in a /Resources dictionary
+    pub fn lookup_resource(
+        &self,
+        resources: &Dict,
+        category: &str,
+        name: &str,
+    ) -> Result<Option<Object>> {
+        match self.resolve_key(resources, category)? {
+            Some(Object::Dict(entries)) => self.resolve_key(&entries, name),
+            _ => Ok(None),
+        }
+    }
+
+    /// Decoded data of a stream object, applying its /Filter chain with
+    /// /DecodeParms
+    ///
+    /// Each stream is capped at the [`ParseLimits`](crate::pdf::limits::ParseLimits)
+    /// stream size and at what remains of the document's decode budget.
+    pub fn stream_data(&self, obj: &Object) -> Result<Vec<u8>> {
+        let Object::Stream { dict, data } = self.resolve(obj)? else {
+            return Err(Error::format("expected a stream"));
+        };
+        // Filter names and parameters may be indirect
+        let mut direct = Dict::new();
+        for key in ["Filter", "DecodeParms"] {
+            let value = match self.resolve_key(&dict, key)? {
+                Some(Object::Array(items)) => Object::Array(
+                    items
+                        .iter()
+                        .map(|item| self.resolve(item))
+                        .collect::<Result<_>>()?,
+                ),
+                Some(value) => value,
+                None => continue,
+            };
+            direct.insert(Name::new(key), value);
+        }
+        let max_stream_size = parse_limits().max_stream_size;
+        let remaining = self.decode_budget.load(Ordering::Relaxed);
+        let decoded = filter::decode_stream_limited(&direct, &data, max_stream_size.min(remaining))
+            .map_err(|err| match err {
+                Error::Limit(_) if remaining < max_stream_size => {
+                    Error::limit("document streams decode to more than the total limit")
+                }
+                err => err,
+            })?;
+        // Concurrent decodes may each pass the check; the budget then
+        // saturates and stops the next one
+        let len = decoded.len() as u64;
+        let _ = self
+            .decode_budget
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                Some(left.saturating_sub(len))
+            });
+        Ok(decoded)
+    }
+
+    /// The page's content streams decoded and concatenated
+    pub fn page_contents(&self, page: &Page) -> Result<Vec<u8>> {
+        let streams = match page.dict().get("Contents") {
+            None => return Ok(Vec::new()),
+            Some(contents) => match self.resolve(contents)? {
+                Object::Array(arr) => arr,
+                _ => vec![contents.clone()],
+            },
+        };
+        let mut out = Vec::new();
+        for stream in &streams {
+            out.extend_from_slice(&self.stream_data(stream)?);
+            // Streams are split at token boundaries only
+            out.push(b'\n');
+        }
+        Ok(out)
+    }
+
+    /// The document catalog (/Root)
+    pub fn catalog(&self) -> Result<Dict> {
+        // Decrypted strings depend on the password, so only plain
+        // catalogs are shared through the parse cache
+        if let Some(catalog) = self.xref.catalog.get() {
+            return Ok(catalog.clone());
+        }
+        match self.resolve_key(&self.xref.trailer, "Root")? {
+            Some(Object::Dict(d)) => {
+                if self.crypt.is_none() {
+                    let _ = self.xref.catalog.set(d.clone());
+                }
+                Ok(d)
+            }
+            _ => Err(Error::format("missing document catalog")),
+        }
+    }
+
+    /// Number of pages in the page tree
+    pub fn page_count(&self) -> Result<usize> {
+        Ok(self.page_tree()?.len())
+    }
+
+    /// Load a page by zero-based index
+    pub fn page(&self, index: usize) -> Result<Page> {
+        let pages = self.page_tree()?;
+        let (obj_ref, dict) = pages
+            .get(index)
+            .ok_or_else(|| Error::argument(format!("page {index} out of range")))?;
+        Ok(Page::new(index, *obj_ref, dict.clone()))
+    }
+
+    /// Index of the page whose object is `obj_ref`, if it is in the page tree
+    pub fn page_index(&self, obj_ref: ObjRef) -> Result<Option<usize>> {
+        Ok(self.page_tree()?.iter().position(|(r, _)| *r == obj_ref))
+    }
+
+    /// Size of a page in points and inches
+    pub fn page_size(&self, index: usize) -> Result<Size> {
+        Ok(self.page(index)?.size())
+    }
+
+    /// A new in-memory document with copies of the pages in `range`
+    ///
+    /// Resources are copied with the pages; bookmarks and links that point
+    /// outside the range are dropped, the rest point at the copied pages.
+    pub fn extract_pages(&self, range: PageRange) -> Result<Document> {
+        let pages: Vec<usize> = range.indices().collect();
+        self.subset(&pages)
+    }
+
+    /// A new in-memory document with copies of the pages at `pages`, in
+    /// that order
+    ///
+    /// Pages may be reordered or repeated; a repeated page gets its own page
+    /// object but shares its contents and resources with the other copies.
+    pub fn subset(&self, pages: &[usize]) -> Result<Document> {
+        let count = self.page_count()?;
+        if pages.is_empty() {
+            return Err(Error::argument("no pages selected"));
+        }
+        if let Some(&bad) = pages.iter().find(|&&p| p >= count) {
+            return Err(Error::argument(format!(
+                "page {bad} out of range (document has {count} pages)"
+            )));
+        }
+        let mut data = Vec::new();
+        write::copy_pages(self, pages, &mut data)?;
+        Document::open_bytes(data)
+    }
+
+    fn page_tree(&self) -> Result<&Vec<(ObjRef, Dict)>> {
+        if let Some(pages) = self.pages.get() {
+            return Ok(pages);
+        }
+        let pages = self.collect_pages()?;
+        Ok(self.pages.get_or_init(|| pages))
+    }
+
+    /// Walk the page tree, flattening inherited attributes into each page
+    fn collect_pages(&self) -> Result<Vec<(ObjRef, Dict)>> {
+        if let Some(first_page) = self.first_page {
+            return Ok(vec![self.load_first_page(first_page)?]);
+        }
+        let catalog = self.catalog()?;
+        let root = catalog
+            .get("Pages")
+            .and_then(Object::as_obj_ref)
+            .ok_or_else(|| Error::format("missing page tree"))?;
+
+        let mut pages = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![(root, Dict::new(), 0usize)];
+        while let Some((node_ref, inherited, depth)) = stack.pop() {
+            if depth > MAX_DEPTH || !visited.insert(node_ref) {
+                continue;
+            }
+            let Object::Dict(mut node) = self.load_object(node_ref)? else {
+                continue;
+            };
+
+            let is_leaf = match node.get("Type").and_then(Object::as_name) {
+                Some(t) => t.as_str() == "Page",
+                None => !node.contains_key("Kids"),
+            };
+            if is_leaf {
+                for (key, value) in inherited {
+                    node.entry(key).or_insert(value);
+                }
+                for key in ["MediaBox", "CropBox", "Rotate"] {
+                    if let Some(value) = self.resolve_key(&node, key)? {
+                        node.insert(Name::new(key), value);
+                    }
+                }
+                pages.push((node_ref, node));
+                continue;
+            }
+
+            let mut child_inherited = inherited;
+            for key in INHERITABLE {
+                if let Some(value) = node.get(key) {
+                    child_inherited.insert(Name::new(key), value.clone());
+                }
+            }
+            if let Some(Object::Array(kids)) = self.resolve_key(&node, "Kids")? {
+                // Push in reverse so the first kid is processed first
+                for kid in kids.iter().rev().filter_map(Object::as_obj_ref) {
+                    stack.push((kid, child_inherited.clone(), depth + 1));
+                }
+            }
+        }
+        Ok(pages)
+    }
+
+    /// Load a page on its own, inheriting attributes from whichever
+    /// ancestors are present
+    fn load_first_page(&self, page_ref: ObjRef) -> Result<(ObjRef, Dict)> {
+        let Object::Dict(mut page) = self.load_object(page_ref)? else {
+            return Err(Error::argument("first page is not in the prefix"));
+        };
+        let mut parent = page.get("Parent").and_then(Object::as_obj_ref);
+        let mut visited = HashSet::from([page_ref]);
+        while let Some(node_ref) = parent.filter(|r| visited.insert(*r)) {
+            if visited.len() > MAX_DEPTH {
+                break;
+            }
+            let Object::Dict(node) = self.load_object(node_ref)? else {
+                break;
+            };
+            for key in INHERITABLE {
+                if let Some(value) = node.get(key) {
+                    page.entry(Name::new(key)).or_insert_with(|| value.clone());
+                }
+            }
+            parent = node.get("Parent").and_then(Object::as_obj_ref);
+        }
+        for key in ["MediaBox", "CropBox", "Rotate"] {
+            if let Some(value) = self.resolve_key(&page, key)? {
+                page.insert(Name::new(key), value);
+            }
+        }
+        Ok((page_ref, page))
+    }
+}
+
+/// Decrypt every string and the stream data of an object in place
+fn decrypt_object(crypt: &Crypt, obj: &mut Object, obj_ref: ObjRef) -> Result<()> {
+    match obj {
+        Object::String(s) => {
+            *s = PdfString::new(crypt.decrypt_data(
+                s.as_bytes(),
+                obj_ref.num,
+                obj_ref.generation,
+            )?);
+        }
+        Object::Array(items) => {
+            for item in items {
+                decrypt_object(crypt, item, obj_ref)?;
+            }
+        }
+        Object::Dict(dict) => {
+            for value in dict.values_mut() {
+                decrypt_object(crypt, value, obj_ref)?;
+            }
+        }
+        Object::Stream { dict, data } => {
+            let type_name = dict
+                .get("Type")
+                .and_then(Object::as_name)
+                .map(|n| n.as_str().to_string());
+            for value in dict.values_mut() {
+                decrypt_object(crypt, value, obj_ref)?;
+            }
+            let skip = match type_name.as_deref() {
+                Some("XRef") => true,
+                Some("Metadata") => !crypt.encrypt_metadata(),
+                _ => false,
+            };
+            if !skip {
+                *data = crypt.decrypt_data(data, obj_ref.num, obj_ref.generation)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::test_pdf::{PdfBuilder, build_pdf};
+
+    #[test]
+    fn test_open_and_count_pages() {
+        let data = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 /MediaBox [0 0 612 792] >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 300 400] >>",
+        ]);
+        let doc = Document::open_bytes(data).unwrap();
+        assert!(!doc.is_encrypted());
+        assert_eq!(doc.page_count().unwrap(), 2);
+
+        // MediaBox is inherited by the first page and overridden by the second
+        assert_eq!(doc.page(0).unwrap().media_box().width(), 612.0);
+        assert_eq!(doc.page(1).unwrap().media_box().width(), 300.0);
+        assert!(doc.page(2).is_err());
+    }
+
+    #[test]
+    fn test_open_bytes_uses_parse_cache() {
+        let data = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R /Lang (test_open_bytes_uses_parse_cache) >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+        ]);
+        let scans = crate::pdf::parse_cache::xref_scan_count();
+        let first = Document::open_bytes(data.clone()).unwrap();
+        assert_eq!(first.page_count().unwrap(), 1);
+        let second = Document::open_bytes(data).unwrap();
+        assert_eq!(crate::pdf::parse_cache::xref_scan_count(), scans + 1);
+
+        // The second open shares the first one's offsets and catalog
+        assert!(Arc::ptr_eq(&first.xref, &second.xref));
+        assert!(second.xref.catalog.get().is_some());
+        assert_eq!(second.page_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_parse_cache_key_reads_file_ends() {
+        let mut data = vec![b' '; 1 << 20];
+        data[..9].copy_from_slice(b"%PDF-1.7\n");
+        let key = ParseCache::key(&Bytes::from(data.clone()));
+
+        // The middle of a large file is not read
+        let mut middle = data.clone();
+        middle[1 << 19] = b'x';
+        assert_eq!(ParseCache::key(&Bytes::from(middle)), key);
+
+        // An update appended at the end changes the key
+        let mut end = data.clone();
+        *end.last_mut().unwrap() = b'x';
+        assert_ne!(ParseCache::key(&Bytes::from(end)), key);
+        data.push(b' ');
+        assert_ne!(ParseCache::key(&Bytes::from(data)), key);
+    }
+
+    const TWO_PAGES: [&str; 4] = [
+        "<< /Type /Catalog /Pages 2 0 R >>",
+        "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>",
+        "<< /Type /Page /Parent 2 0 R >>",
+        "<< /Type /Page /Parent 2 0 R >>",
+    ];
+
+    #[test]
+    fn test_open_with_corrupt_startxref() {
+        let intact = Document::open_bytes(build_pdf(&TWO_PAGES)).unwrap();
+        assert_eq!(intact.page_count().unwrap(), 2);
+
+        // startxref points into the middle of object 1
+        let data = PdfBuilder::new(&TWO_PAGES).startxref(15).build();
+        let doc = Document::open_bytes(data).unwrap();
+        assert_eq!(doc.page_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_open_recovers_lost_trailer() {
+        // Truncated before the xref table, so there is no trailer at all
+        let mut data = build_pdf(&TWO_PAGES);
+        let xref = parser::rfind_bytes(&data, b"xref\n0").unwrap();
+        data.truncate(xref);
+        data.extend_from_slice(b"5 0 obj\n<< /Producer (test) >>\nendobj\n");
+
+        let doc = Document::open_bytes(data).unwrap();
+        assert_eq!(
+            doc.trailer().get("Root").unwrap().as_obj_ref(),
+            Some(ObjRef::new(1, 0))
+        );
+        assert_eq!(
+            doc.trailer().get("Info").unwrap().as_obj_ref(),
+            Some(ObjRef::new(5, 0))
+        );
+        assert_eq!(doc.page_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_open_with_page_tree_in_object_stream() {
+        use crate::pdf::filter::encode_flate;
+
+        // The catalog, page tree and both pages live in object stream 5
+        let bodies = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 200 100] >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+        ];
+        let (mut header, mut body) = (String::new(), String::new());
+        for (i, obj) in bodies.iter().enumerate() {
+            header.push_str(&format!("{} {} ", i + 1, body.len()));
+            body.push_str(obj);
+            body.push(' ');
+        }
+        let members = encode_flate(format!("{header}{body}").as_bytes(), 6).unwrap();
+        let mut data = b"%PDF-1.7\n".to_vec();
+        let objstm = data.len();
+        data.extend_from_slice(
+            format!(
+                "5 0 obj\n<< /Type /ObjStm /N 4 /First {} /Filter /FlateDecode /Length {} >>\nstream\n",
+                header.len(),
+                members.len()
+            )
+            .as_bytes(),
+        );
+        data.extend_from_slice(&members);
+        data.extend_from_slice(b"\nendstream\nendobj\n");
+
+        // An unfiltered cross-reference stream with /W [1 2 1]
+        let xref = data.len();
+        let mut rows = vec![[0, 0, 0, 255]];
+        rows.extend((0..4).map(|i| [2, 0, 5, i]));
+        rows.push([1, (objstm >> 8) as u8, objstm as u8, 0]);
+        rows.push([1, (xref >> 8) as u8, xref as u8, 0]);
+        data.extend_from_slice(
+            format!(
+                "6 0 obj\n<< /Type /XRef /Size 7 /W [1 2 1] /Root 1 0 R /Length {} >>\nstream\n",
+                rows.len() * 4
+            )
+            .as_bytes(),
+        );
+        data.extend(rows.concat());
+        data.extend_from_slice(
+            format!("\nendstream\nendobj\nstartxref\n{xref}\n%%EOF\n").as_bytes(),
+        );
+
+        let decodes = xref::object_stream_decode_count();
+        let doc = Document::open_bytes(data).unwrap();
+        assert_eq!(doc.page_count().unwrap(), 2);
+        assert_eq!(doc.page(0).unwrap().media_box().x1, 200.0);
+        assert_eq!(doc.object_refs().len(), 6);
+        assert_eq!(xref::object_stream_decode_count(), decodes + 1);
+    }
+
+    #[test]
+    fn test_decode_budget_spans_streams() {
+        // Two streams of 100 decoded bytes, each under the stream limit
+        let hex = "41".repeat(100);
+        let stream = format!(
+            "<< /Filter /ASCIIHexDecode /Length {} >>\nstream\n{hex}>\nendstream",
+            hex.len() + 1
+        );
+        let data = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [] /Count 0 >>".to_string(),
+            stream.clone(),
+            stream,
+        ]);
+        let doc = Document::open_bytes(data).unwrap();
+        doc.decode_budget.store(150, Ordering::Relaxed);
+
+        let first = doc.stream_data(&Object::Ref(ObjRef::new(3, 0))).unwrap();
+        assert_eq!(first.len(), 100);
+        let err = doc
+            .stream_data(&Object::Ref(ObjRef::new(4, 0)))
+            .unwrap_err();
+        assert!(matches!(err, Error::Limit(_)));
+    }
+
+    #[test]
+    fn test_page_tree_cycle() {
+        let data = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 2 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+        ]);
+        let doc = Document::open_bytes(data).unwrap();
+        assert_eq!(doc.page_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_missing_object_is_null() {
+        let data = build_pdf(&["<< /Type /Catalog /Pages 2 0 R >>"]);
+        let doc = Document::open_bytes(data).unwrap();
+        assert!(doc.load_object(ObjRef::new(9, 0)).unwrap().is_null());
+    }
+
+    #[test]
+    fn test_extract_pages() {
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R /Outlines 13 0 R \
+             /Dests << /third [5 0 R /XYZ 0 792 0] >> >>"
+                .to_string(),
+            "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R 6 0 R 7 0 R] /Count 5 \
+             /MediaBox [0 0 612 792] /Resources << /Font << /F1 18 0 R >> >> >>"
+                .to_string(),
+        ];
+        for i in 0..5 {
+            let annots = if i == 1 {
+                "/Annots [16 0 R 17 0 R]"
+            } else {
+                ""
+            };
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /Contents {} 0 R {annots} >>",
+                8 + i
+            ));
+        }
+        for i in 0..5 {
+            let content = format!("BT /F1 12 Tf 72 700 Td (Page {}) Tj ET", i + 1);
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ));
+        }
+        objects.extend([
+            "<< /Type /Outlines /First 14 0 R /Last 15 0 R >>".to_string(),
+            "<< /Title (Start) /Dest [3 0 R /Fit] /Next 15 0 R >>".to_string(),
+            "<< /Title (Middle) /Dest [4 0 R /Fit] /Prev 14 0 R >>".to_string(),
+            "<< /Type /Annot /Subtype /Link /Rect [0 0 10 10] /Dest /third >>".to_string(),
+            "<< /Type /Annot /Subtype /Link /Rect [0 0 10 10] /Dest [7 0 R /Fit] >>".to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        ]);
+        let doc = Document::open_bytes(build_pdf(&objects)).unwrap();
+
+        let extracted = doc.extract_pages((1..=2).into()).unwrap();
+        assert_eq!(extracted.page_count().unwrap(), 2);
+        for (i, marker) in ["(Page 2)", "(Page 3)"].iter().enumerate() {
+            let page = extracted.page(i).unwrap();
+            let content = extracted.page_contents(&page).unwrap();
+            assert!(String::from_utf8_lossy(&content).contains(marker));
+            // Inherited resources come along with the page
+            let resources = extracted.resolve(&page.dict()["Resources"]).unwrap();
+            let font = &resources.as_dict().unwrap()["Font"].as_dict().unwrap()["F1"];
+            let Object::Dict(font) = extracted.resolve(font).unwrap() else {
+                panic!("font not copied");
+            };
+            assert_eq!(font["BaseFont"], Object::Name(Name::new("Helvetica")));
+        }
+
+        // The link to page 5 is dropped; the named link to page 3 is kept
+        let page = extracted.page(0).unwrap();
+        let annots = page.dict()["Annots"].as_array().unwrap();
+        assert_eq!(annots.len(), 1);
+        let Object::Dict(link) = extracted.resolve(&annots[0]).unwrap() else {
+            panic!("link not copied");
+        };
+        let dest = link["Dest"].as_array().unwrap();
+        assert_eq!(dest[0], Object::Ref(extracted.page(1).unwrap().obj_ref()));
+
+        // Only the bookmark for a copied page remains
+        let outline = crate::pdf::outline::load_outline(&extracted).unwrap();
+        assert_eq!(outline.len(), 1);
+        assert_eq!(outline[0].title, "Middle");
+        assert_eq!(outline[0].page, Some(0));
+
+        assert!(doc.extract_pages(PageRange::new(3, 5)).is_err());
+    }
+
+    #[test]
+    fn test_subset_reorders_and_repeats_pages() {
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R] /Count 3 /MediaBox [0 0 612 792] >>"
+                .to_string(),
+        ];
+        for i in 0..3 {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /Contents {} 0 R >>",
+                6 + i
+            ));
+        }
+        for i in 0..3 {
+            let content = format!("(Page {}) Tj", i + 1);
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ));
+        }
+        let doc = Document::open_bytes(build_pdf(&objects)).unwrap();
+        let text = |doc: &Document, i| {
+            let page = doc.page(i).unwrap();
+            String::from_utf8(doc.page_contents(&page).unwrap()).unwrap()
+        };
+
+        let subset = doc.subset(&[2, 0]).unwrap();
+        assert_eq!(subset.page_count().unwrap(), 2);
+        assert!(text(&subset, 0).contains("(Page 3)"));
+        assert!(text(&subset, 1).contains("(Page 1)"));
+
+        let repeated = doc.subset(&[1, 1]).unwrap();
+        let (a, b) = (repeated.page(0).unwrap(), repeated.page(1).unwrap());
+        assert_ne!(a.obj_ref(), b.obj_ref());
+        assert_eq!(a.dict()["Contents"], b.dict()["Contents"]);
+
+        assert!(doc.subset(&[]).is_err());
+        assert!(doc.subset(&[0, 3]).is_err());
+    }
+
+    /// A linearized file whose page tree root and second page come after
+    /// the first-page section, returned with the section's end offset
+    ///
+    /// The first-page xref covers objects 1 to 4 and its trailer's /Prev
+    /// points at the main xref, which covers the rest; the final
+    /// `startxref` points back at the first-page xref.
+    fn build_linearized_pdf() -> (Vec<u8>, usize) {
+        let content = "0 0 1 rg 10 20 100 50 re f";
+        let first_page = [
+            "<< /Type /Catalog /Pages 5 0 R >>".to_string(),
+            "<< /Type /Page /Parent 5 0 R /MediaBox [0 0 200 300] /Contents 4 0 R >>".into(),
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ),
+        ];
+        let rest = [
+            "<< /Type /Pages /Kids [3 0 R 6 0 R] /Count 2 >>",
+            "<< /Type /Page /Parent 5 0 R /MediaBox [0 0 200 300] >>",
+        ];
+        let obj = |num: usize, body: &str| format!("{num} 0 obj\n{body}\nendobj\n");
+        let entries = |offsets: &[usize]| -> String {
+            offsets
+                .iter()
+                .map(|o| format!("{o:010} 00000 n\r\n"))
+                .collect()
+        };
+
+        // Offsets and lengths are fixed-width, so the layout is found with
+        // zeros before the real values are filled in
+        let header = "%PDF-1.7\n";
+        let lin = |total: usize, end: usize, main: usize| {
+            obj(
+                1,
+                &format!("<< /Linearized 1 /L {total:07} /O 3 /E {end:07} /N 2 /T {main:07} >>"),
+            )
+        };
+        let first_xref = |main: usize, offsets: &[usize]| {
+            format!(
+                "xref\n0 5\n0000000000 65535 f\r\n{}trailer\n<< /Size 7 /Root 2 0 R /Prev {main:07} >>\n\
+                 startxref\n0\n%%EOF\n",
+                entries(offsets)
+            )
+        };
+        let first_xref_at = header.len() + lin(0, 0, 0).len();
+        let mut offsets = vec![header.len()];
+        let mut pos = first_xref_at + first_xref(0, &[0; 4]).len();
+        let mut body = String::new();
+        for (i, o) in first_page.iter().enumerate() {
+            offsets.push(pos);
+            let o = obj(i + 2, o);
+            pos += o.len();
+            body.push_str(&o);
+        }
+        let end = pos;
+        let mut rest_offsets = Vec::new();
+        for (i, o) in rest.iter().enumerate() {
+            rest_offsets.push(pos);
+            let o = obj(i + 5, o);
+            pos += o.len();
+            body.push_str(&o);
+        }
+        let main = pos;
+        let main_xref = format!(
+            "xref\n0 1\n0000000000 65535 f\r\n5 2\n{}trailer\n<< /Size 7 >>\n\
+             startxref\n{first_xref_at}\n%%EOF\n",
+            entries(&rest_offsets)
+        );
+        let total = main + main_xref.len();
+        let data = format!(
+            "{header}{}{}{body}{main_xref}",
+            lin(total, end, main),
+            first_xref(main, &offsets)
+        );
+        assert_eq!(data.len(), total);
+        (data.into_bytes(), end)
+    }
+
+    #[test]
+    fn test_open_first_page_from_prefix() {
+        let (data, end) = build_linearized_pdf();
+        let page = Document::open_first_page(&data[..end]).unwrap();
+        assert_eq!(page.media_box().height(), 300.0);
+
+        // The page renders from the prefix alone
+        let doc = Document::open_linearized_prefix(&data[..end]).unwrap();
+        assert_eq!(doc.page_count().unwrap(), 1);
+        let contents = doc.page_contents(&doc.page(0).unwrap()).unwrap();
+        let mut device = crate::fitz::device::BBoxDevice::new();
+        crate::pdf::interpret::Interpreter::new()
+            .interpret(&contents, &mut device)
+            .unwrap();
+        let bbox = device.bbox();
+        assert_eq!(
+            (bbox.x0, bbox.y0, bbox.x1, bbox.y1),
+            (10.0, 20.0, 110.0, 70.0)
+        );
+
+        // Objects come from the first-page xref alone
+        let refs: Vec<i32> = doc.object_refs().iter().map(|r| r.num).collect();
+        assert_eq!(refs, [1, 2, 3, 4]);
+        assert_eq!(doc.trailer()["Root"], Object::Ref(ObjRef::new(2, 0)));
+
+        let mut broken = data.clone();
+        let at = parser::find_bytes(&broken, b"xref", 0).unwrap();
+        broken[at] = b'X';
+        assert!(Document::open_first_page(&broken[..end]).is_err());
+
+        assert!(Document::open_first_page(&data[..end - 1]).is_err());
+        assert!(Document::open_first_page(&build_pdf(&["<< /Type /Catalog >>"])).is_err());
+        assert_eq!(Document::open_bytes(data).unwrap().page_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_not_a_pdf() {
+        assert!(Document::open_bytes(b"hello".to_vec()).is_err());
+    }
+}
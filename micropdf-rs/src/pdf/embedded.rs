@@ -0,0 +1,241 @@
+//! Embedded files
+//!
+//! Attachments registered in the catalog's /Names /EmbeddedFiles name
+//! tree. Each value is a file specification whose /EF entry holds the
+//! embedded file stream, with its size and MD5 checksum in /Params.
+
+use crate::fitz::error::{Error, Result};
+use crate::pdf::document::Document;
+use crate::pdf::name_tree;
+use crate::pdf::object::{Dict, Name, ObjRef, Object, PdfString};
+use md5::{Digest, Md5};
+use std::collections::BTreeMap;
+
+/// An attachment read back from a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedFile {
+    /// Key in the name tree
+    pub name: String,
+    /// MIME type from the stream's /Subtype
+    pub mime: Option<String>,
+    /// Decoded file contents
+    pub data: Vec<u8>,
+    /// /Params /CheckSum, the MD5 of the contents
+    pub checksum: Option<[u8; 16]>,
+}
+
+impl EmbeddedFile {
+    /// Whether the contents match the stored checksum; files without one
+    /// always pass
+    pub fn checksum_matches(&self) -> bool {
+        self.checksum
+            .is_none_or(|sum| sum == <[u8; 16]>::from(Md5::digest(&self.data)))
+    }
+}
+
+/// The /EmbeddedFiles name tree, if the document has one
+fn embedded_files_tree(doc: &Document) -> Result<Option<Object>> {
+    let catalog = doc.catalog()?;
+    match doc.resolve_key(&catalog, "Names")? {
+        Some(Object::Dict(names)) => Ok(names.get("EmbeddedFiles").cloned()),
+        _ => Ok(None),
+    }
+}
+
+/// Names and file specifications of the document's attachments, by name
+pub fn embedded_file_specs(doc: &Document) -> Result<Vec<(String, Object)>> {
+    let Some(tree) = embedded_files_tree(doc)? else {
+        return Ok(Vec::new());
+    };
+    name_tree::name_entries(&tree, |o| doc.resolve(o))?
+        .into_iter()
+        .map(|(key, filespec)| Ok((PdfString::new(key).to_text(), doc.resolve(&filespec)?)))
+        .collect()
+}
+
+/// Number of attachments
+pub fn count_embedded_files(doc: &Document) -> Result<usize> {
+    Ok(embedded_file_specs(doc)?.len())
+}
+
+/// Read the attachment at `index`, in name order
+pub fn load_embedded_file(doc: &Document, index: usize) -> Result<EmbeddedFile> {
+    let mut specs = embedded_file_specs(doc)?;
+    if index >= specs.len() {
+        return Err(Error::argument(format!(
+            "embedded file {index} out of range (document has {})",
+            specs.len()
+        )));
+    }
+    let (name, filespec) = specs.swap_remove(index);
+    let Object::Dict(filespec) = filespec else {
+        return Err(Error::format("file specification is not a dictionary"));
+    };
+    let Some(Object::Dict(ef)) = doc.resolve_key(&filespec, "EF")? else {
+        return Err(Error::format(format!("{name} has no embedded file stream")));
+    };
+    let Some(stream) = ef.get("UF").or_else(|| ef.get("F")) else {
+        return Err(Error::format(format!("{name} has no embedded file stream")));
+    };
+    let Object::Stream { dict, .. } = doc.resolve(stream)? else {
+        return Err(Error::format("embedded file is not a stream"));
+    };
+    let mime = match doc.resolve_key(&dict, "Subtype")? {
+        Some(Object::Name(mime)) => Some(mime.as_str().to_string()),
+        _ => None,
+    };
+    let checksum = match doc.resolve_key(&dict, "Params")? {
+        Some(Object::Dict(params)) => match doc.resolve_key(&params, "CheckSum")? {
+            Some(Object::String(sum)) => sum.as_bytes().try_into().ok(),
+            _ => None,
+        },
+        _ => None,
+    };
+    Ok(EmbeddedFile {
+        name,
+        mime,
+        data: doc.stream_data(stream)?,
+        checksum,
+    })
+}
+
+/// Attach `data` under `name`, replacing any attachment with that name
+///
+/// Returns the changed objects: the new file specification and stream,
+/// and the catalog, /Names dictionary and name tree that register them.
+/// The name tree is rewritten as a single leaf.
+pub fn add_embedded_file(
+    doc: &Document,
+    name: &str,
+    data: &[u8],
+    mime: Option<&str>,
+) -> Result<BTreeMap<ObjRef, Object>> {
+    let root = doc
+        .trailer()
+        .get("Root")
+        .and_then(Object::as_obj_ref)
+        .ok_or_else(|| Error::format("trailer has no /Root"))?;
+    let mut next_num = doc
+        .trailer()
+        .get("Size")
+        .and_then(Object::as_int)
+        .unwrap_or(1)
+        .max(1) as i32;
+    let mut new_object = || {
+        next_num += 1;
+        ObjRef::new(next_num - 1, 0)
+    };
+    let mut changes = BTreeMap::new();
+
+    let mut params = Dict::new();
+    params.insert(Name::new("Size"), Object::Int(data.len() as i64));
+    params.insert(
+        Name::new("CheckSum"),
+        Object::String(PdfString::new(Md5::digest(data).to_vec())),
+    );
+    let mut stream_dict = Dict::new();
+    stream_dict.insert(Name::new("Type"), Object::Name(Name::new("EmbeddedFile")));
+    if let Some(mime) = mime {
+        stream_dict.insert(Name::new("Subtype"), Object::Name(Name::new(mime)));
+    }
+    stream_dict.insert(Name::new("Params"), Object::Dict(params));
+    let stream_ref = new_object();
+    changes.insert(
+        stream_ref,
+        Object::Stream {
+            dict: stream_dict,
+            data: data.to_vec(),
+        },
+    );
+
+    let filename = Object::String(PdfString::from_text(name));
+    let mut ef = Dict::new();
+    ef.insert(Name::new("F"), Object::Ref(stream_ref));
+    ef.insert(Name::new("UF"), Object::Ref(stream_ref));
+    let mut filespec = Dict::new();
+    filespec.insert(Name::new("Type"), Object::Name(Name::new("Filespec")));
+    filespec.insert(Name::new("F"), filename.clone());
+    filespec.insert(Name::new("UF"), filename);
+    filespec.insert(Name::new("EF"), Object::Dict(ef));
+    let filespec_ref = new_object();
+    changes.insert(filespec_ref, Object::Dict(filespec));
+
+    let Object::Dict(mut catalog) = doc.load_object(root)? else {
+        return Err(Error::format("catalog is not a dictionary"));
+    };
+    let names_ref = catalog.get("Names").and_then(Object::as_obj_ref);
+    let mut names = match doc.resolve_key(&catalog, "Names")? {
+        Some(Object::Dict(names)) => names,
+        _ => Dict::new(),
+    };
+
+    let key = PdfString::from_text(name).as_bytes().to_vec();
+    let mut entries = match names.get("EmbeddedFiles") {
+        Some(tree) => name_tree::name_entries(tree, |o| doc.resolve(o))?,
+        None => Vec::new(),
+    };
+    match entries.binary_search_by(|(k, _)| k.cmp(&key)) {
+        Ok(i) => entries[i].1 = Object::Ref(filespec_ref),
+        Err(i) => entries.insert(i, (key, Object::Ref(filespec_ref))),
+    }
+    let tree_ref = match names.get("EmbeddedFiles").and_then(Object::as_obj_ref) {
+        Some(r) => r,
+        None => new_object(),
+    };
+    changes.insert(tree_ref, Object::Dict(name_tree::name_leaf(entries)));
+    names.insert(Name::new("EmbeddedFiles"), Object::Ref(tree_ref));
+
+    match names_ref {
+        Some(r) => {
+            changes.insert(r, Object::Dict(names));
+        }
+        None => {
+            catalog.insert(Name::new("Names"), Object::Dict(names));
+            changes.insert(root, Object::Dict(catalog));
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::test_pdf::build_pdf;
+    use crate::pdf::write;
+
+    fn minimal_pdf() -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [] /Count 0 >>",
+        ];
+        build_pdf(&objects)
+    }
+
+    fn attach(doc: &Document, name: &str, data: &[u8]) -> Document {
+        let changes = add_embedded_file(doc, name, data, Some("text/plain")).unwrap();
+        let mut out = Vec::new();
+        write::append_incremental(doc.data(), &changes, &mut out).unwrap();
+        Document::open_bytes(out).unwrap()
+    }
+
+    #[test]
+    fn test_add_and_load_embedded_files() {
+        let doc = Document::open_bytes(minimal_pdf()).unwrap();
+        assert_eq!(count_embedded_files(&doc).unwrap(), 0);
+
+        let doc = attach(&doc, "notes.txt", b"hello");
+        let doc = attach(&doc, "a.txt", b"first");
+        let doc = attach(&doc, "notes.txt", b"replaced");
+        assert_eq!(count_embedded_files(&doc).unwrap(), 2);
+
+        let first = load_embedded_file(&doc, 0).unwrap();
+        assert_eq!(first.name, "a.txt");
+        assert_eq!(first.data, b"first");
+        assert_eq!(first.mime.as_deref(), Some("text/plain"));
+        assert!(first.checksum.is_some() && first.checksum_matches());
+
+        let notes = load_embedded_file(&doc, 1).unwrap();
+        assert_eq!(notes.data, b"replaced");
+        assert!(load_embedded_file(&doc, 2).is_err());
+    }
+}
@@ -1,12 +1,25 @@
 //! ASCII85Decode Filter Implementation
 
 use crate::fitz::error::{Error, Result};
+use crate::fitz::warning;
+use crate::pdf::limits::parse_limits;
 
 /// Decode ASCII85 encoded data
 pub fn decode_ascii85(data: &[u8]) -> Result<Vec<u8>> {
+    decode_ascii85_limited(data, parse_limits().max_stream_size)
+}
+
+/// Decode ASCII85 data, failing with [`Error::Limit`] once the output
+/// passes `max_size` bytes
+pub fn decode_ascii85_limited(data: &[u8], max_size: u64) -> Result<Vec<u8>> {
+    let over = |result: &Vec<u8>| {
+        (result.len() as u64 > max_size)
+            .then(|| Error::limit(format!("ASCII85Decode output exceeds {max_size} bytes")))
+    };
     let mut result = Vec::with_capacity(data.len() * 4 / 5);
     let mut group: u32 = 0;
     let mut count = 0;
+    let mut ended = false;
 
     for &byte in data {
         // Skip whitespace
@@ -16,6 +29,7 @@ pub fn decode_ascii85(data: &[u8]) -> Result<Vec<u8>> {
 
         // End of data marker
         if byte == b'~' {
+            ended = true;
             break;
         }
 
@@ -25,6 +39,9 @@ pub fn decode_ascii85(data: &[u8]) -> Result<Vec<u8>> {
                 return Err(Error::Generic("Invalid 'z' in ASCII85 stream".into()));
             }
             result.extend_from_slice(&[0, 0, 0, 0]);
+            if let Some(err) = over(&result) {
+                return Err(err);
+            }
             continue;
         }
 
@@ -44,11 +61,18 @@ pub fn decode_ascii85(data: &[u8]) -> Result<Vec<u8>> {
             result.push((group >> 16) as u8);
             result.push((group >> 8) as u8);
             result.push(group as u8);
+            if let Some(err) = over(&result) {
+                return Err(err);
+            }
             group = 0;
             count = 0;
         }
     }
 
+    if !ended {
+        warning::warn("filter", "ASCII85Decode data without end-of-data marker");
+    }
+
     // Handle remaining bytes
     if count > 0 {
         // Pad with 'u' characters
@@ -59,6 +83,9 @@ pub fn decode_ascii85(data: &[u8]) -> Result<Vec<u8>> {
         for i in 0..(count - 1) {
             result.push((group >> (24 - i * 8)) as u8);
         }
+        if let Some(err) = over(&result) {
+            return Err(err);
+        }
     }
 
     Ok(result)
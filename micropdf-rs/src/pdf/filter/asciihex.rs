@@ -1,11 +1,21 @@
 //! ASCIIHexDecode Filter Implementation
 
 use crate::fitz::error::{Error, Result};
+use crate::fitz::warning;
+use crate::pdf::limits::parse_limits;
 
 /// Decode ASCIIHex encoded data
 pub fn decode_ascii_hex(data: &[u8]) -> Result<Vec<u8>> {
-    let mut result = Vec::with_capacity(data.len() / 2);
+    decode_ascii_hex_limited(data, parse_limits().max_stream_size)
+}
+
+/// Decode ASCIIHex data, failing with [`Error::Limit`] once the output
+/// passes `max_size` bytes
+pub fn decode_ascii_hex_limited(data: &[u8], max_size: u64) -> Result<Vec<u8>> {
+    let max_len = usize::try_from(max_size).unwrap_or(usize::MAX);
+    let mut result = Vec::with_capacity((data.len() / 2).min(max_len));
     let mut high_nibble: Option<u8> = None;
+    let mut ended = false;
 
     for &byte in data {
         // Skip whitespace
@@ -15,6 +25,7 @@ pub fn decode_ascii_hex(data: &[u8]) -> Result<Vec<u8>> {
 
         // End of data marker
         if byte == b'>' {
+            ended = true;
             break;
         }
 
@@ -28,14 +39,28 @@ pub fn decode_ascii_hex(data: &[u8]) -> Result<Vec<u8>> {
         match high_nibble {
             None => high_nibble = Some(nibble),
             Some(high) => {
+                if result.len() >= max_len {
+                    return Err(Error::limit(format!(
+                        "ASCIIHexDecode output exceeds {max_size} bytes"
+                    )));
+                }
                 result.push((high << 4) | nibble);
                 high_nibble = None;
             }
         }
     }
 
+    if !ended {
+        warning::warn("filter", "ASCIIHexDecode data without end-of-data marker");
+    }
+
     // Handle odd number of hex digits
     if let Some(high) = high_nibble {
+        if result.len() >= max_len {
+            return Err(Error::limit(format!(
+                "ASCIIHexDecode output exceeds {max_size} bytes"
+            )));
+        }
         result.push(high << 4);
     }
 
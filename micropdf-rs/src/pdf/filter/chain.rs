@@ -23,22 +23,38 @@ impl FilterChain {
     }
 
     /// Decode data through the filter chain (in order)
+    ///
+    /// Intermediate buffers go back to the [`decode_pool`] once the next
+    /// filter has consumed them. Every stage is capped at the
+    /// [`ParseLimits`](crate::pdf::limits::ParseLimits) stream size.
     pub fn decode(&self, mut data: Vec<u8>) -> Result<Vec<u8>> {
+        let max_size = crate::pdf::limits::parse_limits().max_stream_size;
+        let pool = decode_pool();
         for filter in &self.filters {
-            data = match filter {
-                FilterType::FlateDecode => decode_flate(&data, None)?,
-                FilterType::LZWDecode => decode_lzw(&data, None)?,
-                FilterType::ASCII85Decode => decode_ascii85(&data)?,
-                FilterType::ASCIIHexDecode => decode_ascii_hex(&data)?,
-                FilterType::RunLengthDecode => decode_run_length(&data)?,
+            let decoded = match filter {
+                FilterType::FlateDecode => {
+                    let mut out = pool.acquire(data.len().saturating_mul(4));
+                    decode_flate_into(&data, &mut out)?;
+                    out
+                }
+                FilterType::LZWDecode => decode_lzw_limited(&data, None, max_size)?,
+                FilterType::ASCII85Decode => decode_ascii85_limited(&data, max_size)?,
+                FilterType::ASCIIHexDecode => decode_ascii_hex_limited(&data, max_size)?,
+                FilterType::RunLengthDecode => decode_run_length_limited(&data, max_size)?,
                 FilterType::CCITTFaxDecode => {
                     decode_ccitt_fax(&data, &CCITTFaxDecodeParams::default())?
                 }
                 FilterType::DCTDecode => decode_dct(&data, None)?,
                 FilterType::JPXDecode => decode_jpx(&data)?,
                 FilterType::JBIG2Decode => decode_jbig2(&data, None)?,
-                FilterType::Crypt => data, // Encryption handled separately
+                FilterType::Crypt => continue, // Encryption handled separately
             };
+            if decoded.len() as u64 > max_size {
+                return Err(Error::limit(format!(
+                    "decoded stream exceeds {max_size} bytes"
+                )));
+            }
+            pool.release(std::mem::replace(&mut data, decoded));
         }
         Ok(data)
     }
@@ -19,20 +19,37 @@ pub fn decode_dct(data: &[u8], _params: Option<&DCTDecodeParams>) -> Result<Vec<
 
 /// Encode data with JPEG compression
 pub fn encode_dct(data: &[u8], width: u32, height: u32, quality: u8) -> Result<Vec<u8>> {
+    // Assume RGB data
+    encode_dct_components(data, width, height, 3, quality)
+}
+
+/// Encode gray (1 component) or RGB (3 component) data with JPEG compression
+pub fn encode_dct_components(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    components: usize,
+    quality: u8,
+) -> Result<Vec<u8>> {
+    use image::ExtendedColorType;
     use image::codecs::jpeg::JpegEncoder;
-    use image::{ImageBuffer, Rgb};
     use std::io::Cursor;
 
-    // Assume RGB data
-    let img: ImageBuffer<Rgb<u8>, _> = ImageBuffer::from_raw(width, height, data.to_vec())
-        .ok_or_else(|| Error::Generic("Invalid image dimensions".into()))?;
+    let color = match components {
+        1 => ExtendedColorType::L8,
+        3 => ExtendedColorType::Rgb8,
+        _ => return Err(Error::Generic("DCTEncode needs 1 or 3 components".into())),
+    };
+    if width == 0 || height == 0 || data.len() != width as usize * height as usize * components {
+        return Err(Error::Generic("Invalid image dimensions".into()));
+    }
 
     let mut output = Cursor::new(Vec::new());
 
     // Use JpegEncoder to specify quality (1-100)
     let mut encoder = JpegEncoder::new_with_quality(&mut output, quality);
     encoder
-        .encode(img.as_raw(), width, height, image::ExtendedColorType::Rgb8)
+        .encode(data, width, height, color)
         .map_err(|e| Error::Generic(format!("DCTEncode failed: {}", e)))?;
 
     Ok(output.into_inner())
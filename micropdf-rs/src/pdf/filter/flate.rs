@@ -3,17 +3,25 @@
 use super::params::FlateDecodeParams;
 use super::predictor::apply_predictor_decode;
 use crate::fitz::error::{Error, Result};
+use crate::pdf::limits::parse_limits;
 use flate2::Compression;
 use flate2::read::{ZlibDecoder, ZlibEncoder};
 use std::io::Read;
 
 /// Decode FlateDecode (zlib/deflate) compressed data
 pub fn decode_flate(data: &[u8], params: Option<&FlateDecodeParams>) -> Result<Vec<u8>> {
-    let mut decoder = ZlibDecoder::new(data);
+    decode_flate_limited(data, params, parse_limits().max_stream_size)
+}
+
+/// Decode FlateDecode data, failing with [`Error::Limit`] rather than
+/// inflating more than `max_size` bytes
+pub fn decode_flate_limited(
+    data: &[u8],
+    params: Option<&FlateDecodeParams>,
+    max_size: u64,
+) -> Result<Vec<u8>> {
     let mut decompressed = Vec::new();
-    decoder
-        .read_to_end(&mut decompressed)
-        .map_err(|e| Error::Generic(format!("FlateDecode failed: {}", e)))?;
+    inflate_into(data, &mut decompressed, max_size)?;
 
     // Apply predictor if specified
     if let Some(params) = params {
@@ -25,6 +33,26 @@ pub fn decode_flate(data: &[u8], params: Option<&FlateDecodeParams>) -> Result<V
     Ok(decompressed)
 }
 
+/// Decode FlateDecode data, appending to `out` without applying a predictor
+pub fn decode_flate_into(data: &[u8], out: &mut Vec<u8>) -> Result<()> {
+    inflate_into(data, out, parse_limits().max_stream_size)
+}
+
+fn inflate_into(data: &[u8], out: &mut Vec<u8>, max_size: u64) -> Result<()> {
+    // Read one byte past the limit to tell a full stream from a cut one
+    let room = max_size.saturating_sub(out.len() as u64).saturating_add(1);
+    ZlibDecoder::new(data)
+        .take(room)
+        .read_to_end(out)
+        .map_err(|e| Error::Generic(format!("FlateDecode failed: {}", e)))?;
+    if out.len() as u64 > max_size {
+        return Err(Error::limit(format!(
+            "FlateDecode output exceeds {max_size} bytes"
+        )));
+    }
+    Ok(())
+}
+
 /// Encode data with FlateDecode (zlib/deflate)
 pub fn encode_flate(data: &[u8], level: u32) -> Result<Vec<u8>> {
     let compression = match level {
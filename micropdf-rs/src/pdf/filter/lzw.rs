@@ -3,9 +3,21 @@
 use super::params::{FlateDecodeParams, LZWDecodeParams};
 use super::predictor::apply_predictor_decode;
 use crate::fitz::error::{Error, Result};
+use crate::fitz::warning;
+use crate::pdf::limits::parse_limits;
 
 /// Decode LZW compressed data
 pub fn decode_lzw(data: &[u8], params: Option<&LZWDecodeParams>) -> Result<Vec<u8>> {
+    decode_lzw_limited(data, params, parse_limits().max_stream_size)
+}
+
+/// Decode LZW data, failing with [`Error::Limit`] as soon as the output
+/// passes `max_size` bytes
+pub fn decode_lzw_limited(
+    data: &[u8],
+    params: Option<&LZWDecodeParams>,
+    max_size: u64,
+) -> Result<Vec<u8>> {
     let early_change = params.map(|p| p.early_change != 0).unwrap_or(true);
 
     let mut decoder = weezl::decode::Decoder::with_tiff_size_switch(
@@ -13,9 +25,29 @@ pub fn decode_lzw(data: &[u8], params: Option<&LZWDecodeParams>) -> Result<Vec<u
         if early_change { 8 } else { 9 },
     );
 
-    let decompressed = decoder
-        .decode(data)
-        .map_err(|e| Error::Generic(format!("LZWDecode failed: {:?}", e)))?;
+    // Decode a chunk at a time so that a bomb stops at the limit
+    let mut decompressed = Vec::new();
+    let mut chunk = vec![0u8; 1 << 16];
+    let mut input = data;
+    loop {
+        let step = decoder.decode_bytes(input, &mut chunk);
+        input = &input[step.consumed_in..];
+        decompressed.extend_from_slice(&chunk[..step.consumed_out]);
+        if decompressed.len() as u64 > max_size {
+            return Err(Error::limit(format!(
+                "LZWDecode output exceeds {max_size} bytes"
+            )));
+        }
+        match step.status {
+            Ok(weezl::LzwStatus::Ok) => {}
+            Ok(weezl::LzwStatus::Done) => break,
+            Ok(weezl::LzwStatus::NoProgress) => {
+                warning::warn("filter", "LZWDecode data ends before its end code");
+                break;
+            }
+            Err(e) => return Err(Error::Generic(format!("LZWDecode failed: {:?}", e))),
+        }
+    }
 
     // Apply predictor if specified
     let mut result = decompressed;
@@ -2,6 +2,12 @@
 //!
 //! This module implements all PDF stream filters for decompression and compression.
 //! Supports the complete set of PDF filters as defined in PDF 1.7 specification.
+//!
+//! The decoders cap their output at the
+//! [`ParseLimits`](crate::pdf::limits::ParseLimits) stream size, failing
+//! with [`Error::Limit`](crate::fitz::error::Error) past it. Their
+//! `*_limited` variants take the cap instead, which documents use to
+//! charge each stream to their total decoded budget.
 
 // Module declarations
 pub mod ascii85;
@@ -14,8 +20,10 @@ pub mod jbig2;
 pub mod jpx;
 pub mod lzw;
 pub mod params;
+pub mod pool;
 pub mod predictor;
 pub mod runlength;
+pub mod stream;
 
 // Re-exports
 pub use ascii85::*;
@@ -28,8 +36,10 @@ pub use jbig2::*;
 pub use jpx::*;
 pub use lzw::*;
 pub use params::*;
+pub use pool::*;
 pub use predictor::*;
 pub use runlength::*;
+pub use stream::*;
 
 /// PDF Filter types as defined in PDF specification
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
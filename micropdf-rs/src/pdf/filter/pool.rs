@@ -0,0 +1,144 @@
+//! Reusable decode buffers
+//!
+//! Decoding many images allocates and frees a large output buffer per
+//! image. A [`DecodePool`] keeps buffers that have been handed back so the
+//! next decode of a similar size can reuse one instead of allocating.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+/// Maximum number of idle buffers kept by a pool
+const MAX_BUFFERS: usize = 16;
+
+/// Maximum total capacity of idle buffers kept by a pool
+const MAX_POOLED_BYTES: usize = 256 << 20;
+
+/// Buffers with more than this many times the requested capacity are not
+/// handed out for the request, so small decodes do not pin large buffers
+const MAX_WASTE_FACTOR: usize = 4;
+
+/// Counters for a [`DecodePool`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecodePoolStats {
+    /// Requests served by allocating a new buffer
+    pub allocations: u64,
+    /// Requests served from an idle buffer
+    pub reuses: u64,
+    /// Buffers handed back and kept
+    pub returns: u64,
+    /// Buffers handed back and dropped because the pool was full
+    pub drops: u64,
+}
+
+/// A bounded set of idle `Vec<u8>` buffers
+#[derive(Debug, Default)]
+pub struct DecodePool {
+    idle: Mutex<Vec<Vec<u8>>>,
+    allocations: AtomicU64,
+    reuses: AtomicU64,
+    returns: AtomicU64,
+    drops: AtomicU64,
+}
+
+impl DecodePool {
+    /// Create an empty pool
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An empty buffer with room for at least `capacity` bytes
+    ///
+    /// The smallest idle buffer that fits is reused; otherwise a new one is
+    /// allocated.
+    pub fn acquire(&self, capacity: usize) -> Vec<u8> {
+        if let Ok(mut idle) = self.idle.lock() {
+            let max = capacity.max(1).saturating_mul(MAX_WASTE_FACTOR);
+            let best = idle
+                .iter()
+                .enumerate()
+                .filter(|(_, buf)| buf.capacity() >= capacity && buf.capacity() <= max)
+                .min_by_key(|(_, buf)| buf.capacity())
+                .map(|(i, _)| i);
+            if let Some(i) = best {
+                self.reuses.fetch_add(1, Ordering::Relaxed);
+                return idle.swap_remove(i);
+            }
+        }
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        Vec::with_capacity(capacity)
+    }
+
+    /// Hand a buffer back for reuse
+    pub fn release(&self, mut buf: Vec<u8>) {
+        if buf.capacity() == 0 {
+            return;
+        }
+        if let Ok(mut idle) = self.idle.lock() {
+            let pooled: usize = idle.iter().map(Vec::capacity).sum();
+            if idle.len() < MAX_BUFFERS && pooled + buf.capacity() <= MAX_POOLED_BYTES {
+                buf.clear();
+                idle.push(buf);
+                self.returns.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        }
+        self.drops.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of idle buffers
+    pub fn idle_count(&self) -> usize {
+        self.idle.lock().map(|idle| idle.len()).unwrap_or(0)
+    }
+
+    /// Drop all idle buffers
+    pub fn clear(&self) {
+        if let Ok(mut idle) = self.idle.lock() {
+            idle.clear();
+        }
+    }
+
+    /// Counters since the pool was created
+    pub fn stats(&self) -> DecodePoolStats {
+        DecodePoolStats {
+            allocations: self.allocations.load(Ordering::Relaxed),
+            reuses: self.reuses.load(Ordering::Relaxed),
+            returns: self.returns.load(Ordering::Relaxed),
+            drops: self.drops.load(Ordering::Relaxed),
+        }
+    }
+}
+
+static DECODE_POOL: LazyLock<DecodePool> = LazyLock::new(DecodePool::new);
+
+/// The pool shared by the filter chain and image decoding
+pub fn decode_pool() -> &'static DecodePool {
+    &DECODE_POOL
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_pool_reuse() {
+        let pool = DecodePool::new();
+        let buf = pool.acquire(1000);
+        let ptr = buf.as_ptr();
+        pool.release(buf);
+
+        // A fitting buffer is reused, a much smaller request is not served
+        // from it
+        let buf = pool.acquire(900);
+        assert_eq!(buf.as_ptr(), ptr);
+        assert!(buf.is_empty());
+        pool.release(buf);
+        let small = pool.acquire(10);
+        assert_eq!(pool.idle_count(), 1);
+
+        let stats = pool.stats();
+        assert_eq!(stats.allocations, 2);
+        assert_eq!(stats.reuses, 1);
+        assert_eq!(stats.returns, 2);
+        drop(small);
+    }
+}
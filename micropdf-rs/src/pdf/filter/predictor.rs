@@ -71,7 +71,7 @@ pub fn apply_png_predictor_decode(
             // Incomplete row, pad with zeros
             let mut padded = row.to_vec();
             padded.resize(bytes_per_row, 0);
-            decode_png_filter(
+            decode_png_filter_fast(
                 filter_type,
                 &padded,
                 &prev_row,
@@ -79,7 +79,7 @@ pub fn apply_png_predictor_decode(
                 &mut result,
             )?;
         } else {
-            decode_png_filter(
+            decode_png_filter_fast(
                 filter_type,
                 &row[..bytes_per_row],
                 &prev_row,
@@ -96,6 +96,89 @@ pub fn apply_png_predictor_decode(
     Ok(result)
 }
 
+/// Decode a single PNG filter row, using word-at-a-time arithmetic for
+/// the Sub and Up filters when pixels are at most 4 bytes
+///
+/// Produces exactly the output of [`decode_png_filter`], which remains the
+/// reference implementation and handles everything else.
+pub fn decode_png_filter_fast(
+    filter_type: u8,
+    row: &[u8],
+    prev_row: &[u8],
+    bytes_per_pixel: usize,
+    output: &mut Vec<u8>,
+) -> Result<()> {
+    if !(1..=4).contains(&bytes_per_pixel) || prev_row.len() < row.len() {
+        return decode_png_filter(filter_type, row, prev_row, bytes_per_pixel, output);
+    }
+    match filter_type {
+        1 => {
+            let start = output.len();
+            output.extend_from_slice(row);
+            sub_words(&mut output[start..], bytes_per_pixel);
+            Ok(())
+        }
+        2 => {
+            let start = output.len();
+            output.extend_from_slice(row);
+            up_words(&mut output[start..], &prev_row[..row.len()]);
+            Ok(())
+        }
+        _ => decode_png_filter(filter_type, row, prev_row, bytes_per_pixel, output),
+    }
+}
+
+const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+
+/// Add each byte of `a` to the matching byte of `b`, wrapping, without
+/// carries crossing byte boundaries
+#[inline]
+fn add_bytes(a: u64, b: u64) -> u64 {
+    ((a & !HIGH_BITS) + (b & !HIGH_BITS)) ^ ((a ^ b) & HIGH_BITS)
+}
+
+/// Undo the Up filter in place, eight bytes at a time
+fn up_words(row: &mut [u8], prev_row: &[u8]) {
+    let mut chunks = row.chunks_exact_mut(8);
+    let mut above = prev_row.chunks_exact(8);
+    for (chunk, up) in (&mut chunks).zip(&mut above) {
+        let sum = add_bytes(
+            u64::from_le_bytes(chunk.try_into().unwrap()),
+            u64::from_le_bytes(up.try_into().unwrap()),
+        );
+        chunk.copy_from_slice(&sum.to_le_bytes());
+    }
+    for (byte, &up) in chunks.into_remainder().iter_mut().zip(above.remainder()) {
+        *byte = byte.wrapping_add(up);
+    }
+}
+
+/// Undo the Sub filter in place, one whole pixel at a time
+fn sub_words(row: &mut [u8], bytes_per_pixel: usize) {
+    match bytes_per_pixel {
+        1 => sub_pixels::<1>(row),
+        2 => sub_pixels::<2>(row),
+        3 => sub_pixels::<3>(row),
+        _ => sub_pixels::<4>(row),
+    }
+}
+
+fn sub_pixels<const N: usize>(row: &mut [u8]) {
+    let mut left = 0u64;
+    let mut chunks = row.chunks_exact_mut(N);
+    for pixel in &mut chunks {
+        let mut word = [0u8; 8];
+        word[..N].copy_from_slice(pixel);
+        left = add_bytes(u64::from_le_bytes(word), left);
+        pixel.copy_from_slice(&left.to_le_bytes()[..N]);
+    }
+    // A trailing partial pixel still adds the byte one pixel to the left
+    let left = left.to_le_bytes();
+    for (byte, &l) in chunks.into_remainder().iter_mut().zip(&left) {
+        *byte = byte.wrapping_add(l);
+    }
+}
+
 /// Decode a single PNG filter row
 pub fn decode_png_filter(
     filter_type: u8,
@@ -392,4 +475,56 @@ mod tests {
             assert_eq!(result, vec![1, 2, 3]);
         }
     }
+
+    /// Deterministic pseudo-random bytes (xorshift64)
+    fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                (seed >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_png_filter_fast_matches_scalar() {
+        for filter_type in 0..=4u8 {
+            for bpp in 1..=6 {
+                for len in [0, 1, 3, 7, 8, 13, 64, 301] {
+                    let seed = 0x9E37_79B9_7F4A_7C15
+                        ^ ((filter_type as u64) << 40 | (bpp << 20) as u64 | len as u64);
+                    let row = random_bytes(len, seed);
+                    let prev_row = random_bytes(len, seed.rotate_left(17));
+                    let prefix = random_bytes(5, seed.rotate_left(31));
+
+                    let mut scalar = prefix.clone();
+                    decode_png_filter(filter_type, &row, &prev_row, bpp, &mut scalar).unwrap();
+                    let mut fast = prefix.clone();
+                    decode_png_filter_fast(filter_type, &row, &prev_row, bpp, &mut fast).unwrap();
+                    assert_eq!(fast, scalar, "filter {filter_type}, bpp {bpp}, len {len}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_png_predictor_decode_random_image() {
+        // Every row uses a different filter, as encoders choosing per row do
+        let (bytes_per_row, bpp) = (3 * 97, 3);
+        let mut data = random_bytes(40 * (bytes_per_row + 1), 42);
+        for (i, row) in data.chunks_mut(bytes_per_row + 1).enumerate() {
+            row[0] = (i % 5) as u8;
+        }
+        let fast = apply_png_predictor_decode(&data, bytes_per_row, bpp).unwrap();
+
+        let mut scalar = Vec::new();
+        let mut prev_row = vec![0u8; bytes_per_row];
+        for row in data.chunks(bytes_per_row + 1) {
+            decode_png_filter(row[0], &row[1..], &prev_row, bpp, &mut scalar).unwrap();
+            prev_row.copy_from_slice(&scalar[scalar.len() - bytes_per_row..]);
+        }
+        assert_eq!(fast, scalar);
+    }
 }
@@ -1,11 +1,20 @@
 //! RunLengthDecode Filter Implementation
 
 use crate::fitz::error::{Error, Result};
+use crate::fitz::warning;
+use crate::pdf::limits::parse_limits;
 
 /// Decode RunLength encoded data
 pub fn decode_run_length(data: &[u8]) -> Result<Vec<u8>> {
+    decode_run_length_limited(data, parse_limits().max_stream_size)
+}
+
+/// Decode RunLength data, failing with [`Error::Limit`] before the output
+/// passes `max_size` bytes
+pub fn decode_run_length_limited(data: &[u8], max_size: u64) -> Result<Vec<u8>> {
     let mut result = Vec::new();
     let mut i = 0;
+    let mut ended = false;
 
     while i < data.len() {
         let length_byte = data[i];
@@ -13,6 +22,7 @@ pub fn decode_run_length(data: &[u8]) -> Result<Vec<u8>> {
 
         if length_byte == 128 {
             // End of data
+            ended = true;
             break;
         } else if length_byte < 128 {
             // Copy next (length_byte + 1) bytes literally
@@ -22,6 +32,11 @@ pub fn decode_run_length(data: &[u8]) -> Result<Vec<u8>> {
                     "RunLengthDecode: unexpected end of data".into(),
                 ));
             }
+            if (result.len() + count) as u64 > max_size {
+                return Err(Error::limit(format!(
+                    "RunLengthDecode output exceeds {max_size} bytes"
+                )));
+            }
             result.extend_from_slice(&data[i..i + count]);
             i += count;
         } else {
@@ -34,10 +49,19 @@ pub fn decode_run_length(data: &[u8]) -> Result<Vec<u8>> {
             }
             let byte = data[i];
             i += 1;
+            if (result.len() + count) as u64 > max_size {
+                return Err(Error::limit(format!(
+                    "RunLengthDecode output exceeds {max_size} bytes"
+                )));
+            }
             result.resize(result.len() + count, byte);
         }
     }
 
+    if !ended {
+        warning::warn("filter", "RunLengthDecode data without end-of-data marker");
+    }
+
     Ok(result)
 }
 
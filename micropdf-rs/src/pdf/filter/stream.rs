@@ -0,0 +1,275 @@
+//! Stream Decoding from /Filter and /DecodeParms
+
+use super::FilterType;
+use super::params::{
+    CCITTFaxDecodeParams, DCTDecodeParams, FlateDecodeParams, JBIG2DecodeParams, LZWDecodeParams,
+};
+use super::*;
+use crate::fitz::error::{Error, Result};
+use crate::pdf::limits::parse_limits;
+use crate::pdf::object::{Dict, Object};
+
+/// Stream data decoded as far as its filter chain allows
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedStream {
+    /// Output of the filters that were applied
+    pub data: Vec<u8>,
+    /// The final filter, left for the caller when it is not one we know,
+    /// such as an image codec handled elsewhere
+    pub remaining: Option<String>,
+}
+
+/// Decode stream data through its full /Filter and /DecodeParms chain
+///
+/// Fails if any filter in the chain is unknown; use
+/// [`decode_stream_partial`] to stop before an unknown final filter.
+pub fn decode_stream(dict: &Dict, raw: &[u8]) -> Result<Vec<u8>> {
+    decode_stream_limited(dict, raw, parse_limits().max_stream_size)
+}
+
+/// Decode stream data like [`decode_stream`], failing with
+/// [`Error::Limit`] when any stage of the chain produces more than
+/// `max_size` bytes
+pub fn decode_stream_limited(dict: &Dict, raw: &[u8], max_size: u64) -> Result<Vec<u8>> {
+    let decoded = decode_chain(dict, raw, max_size)?;
+    match decoded.remaining {
+        Some(name) => Err(Error::unsupported(format!("unknown filter /{name}"))),
+        None => Ok(decoded.data),
+    }
+}
+
+/// Decode stream data, stopping before an unknown final filter
+///
+/// Filters apply left to right, each with the /DecodeParms entry at the
+/// same position. A single /DecodeParms dictionary applies to every
+/// filter, and null entries mean defaults. The dictionary must be direct:
+/// indirect references in it are not followed.
+pub fn decode_stream_partial(dict: &Dict, raw: &[u8]) -> Result<DecodedStream> {
+    decode_chain(dict, raw, parse_limits().max_stream_size)
+}
+
+fn decode_chain(dict: &Dict, raw: &[u8], max_size: u64) -> Result<DecodedStream> {
+    let names: Vec<&str> = match dict.get("Filter") {
+        None | Some(Object::Null) => Vec::new(),
+        Some(Object::Name(name)) => vec![name.as_str()],
+        Some(Object::Array(names)) => names
+            .iter()
+            .map(|n| {
+                n.as_name()
+                    .map(|n| n.as_str())
+                    .ok_or_else(|| Error::syntax("invalid /Filter"))
+            })
+            .collect::<Result<_>>()?,
+        Some(_) => return Err(Error::syntax("invalid /Filter")),
+    };
+
+    let mut data = raw.to_vec();
+    for (i, name) in names.iter().enumerate() {
+        let Some(filter) = FilterType::from_name(name) else {
+            if i + 1 == names.len() {
+                return Ok(DecodedStream {
+                    data,
+                    remaining: Some(name.to_string()),
+                });
+            }
+            return Err(Error::unsupported(format!("unknown filter /{name}")));
+        };
+        let parms = match dict.get("DecodeParms") {
+            Some(Object::Dict(parms)) => Some(parms),
+            Some(Object::Array(parms)) => parms.get(i).and_then(Object::as_dict),
+            _ => None,
+        };
+        data = apply_filter(filter, &data, parms, max_size)?;
+        if data.len() as u64 > max_size {
+            return Err(Error::limit(format!(
+                "decoded stream exceeds {max_size} bytes"
+            )));
+        }
+    }
+    Ok(DecodedStream {
+        data,
+        remaining: None,
+    })
+}
+
+/// Apply one decode filter with its parameters
+fn apply_filter(
+    filter: FilterType,
+    data: &[u8],
+    parms: Option<&Dict>,
+    max_size: u64,
+) -> Result<Vec<u8>> {
+    let empty = Dict::new();
+    let parms = parms.unwrap_or(&empty);
+    let int =
+        |key: &str, default: i64| parms.get(key).and_then(Object::as_int).unwrap_or(default) as i32;
+    let flag =
+        |key: &str, default: bool| parms.get(key).and_then(Object::as_bool).unwrap_or(default);
+
+    match filter {
+        FilterType::FlateDecode => decode_flate_limited(
+            data,
+            Some(&FlateDecodeParams {
+                predictor: int("Predictor", 1),
+                colors: int("Colors", 1),
+                bits_per_component: int("BitsPerComponent", 8),
+                columns: int("Columns", 1),
+            }),
+            max_size,
+        ),
+        FilterType::LZWDecode => decode_lzw_limited(
+            data,
+            Some(&LZWDecodeParams {
+                predictor: int("Predictor", 1),
+                colors: int("Colors", 1),
+                bits_per_component: int("BitsPerComponent", 8),
+                columns: int("Columns", 1),
+                early_change: int("EarlyChange", 1),
+            }),
+            max_size,
+        ),
+        FilterType::ASCII85Decode => decode_ascii85_limited(data, max_size),
+        FilterType::ASCIIHexDecode => decode_ascii_hex_limited(data, max_size),
+        FilterType::RunLengthDecode => decode_run_length_limited(data, max_size),
+        FilterType::CCITTFaxDecode => {
+            let defaults = CCITTFaxDecodeParams::default();
+            decode_ccitt_fax(
+                data,
+                &CCITTFaxDecodeParams {
+                    k: int("K", defaults.k as i64),
+                    end_of_line: flag("EndOfLine", defaults.end_of_line),
+                    encoded_byte_align: flag("EncodedByteAlign", defaults.encoded_byte_align),
+                    columns: int("Columns", defaults.columns as i64),
+                    rows: int("Rows", defaults.rows as i64),
+                    end_of_block: flag("EndOfBlock", defaults.end_of_block),
+                    black_is_1: flag("BlackIs1", defaults.black_is_1),
+                    damaged_rows_before_error: int(
+                        "DamagedRowsBeforeError",
+                        defaults.damaged_rows_before_error as i64,
+                    ),
+                },
+            )
+        }
+        FilterType::DCTDecode => decode_dct(
+            data,
+            Some(&DCTDecodeParams {
+                color_transform: int("ColorTransform", 0),
+            }),
+        ),
+        FilterType::JPXDecode => decode_jpx(data),
+        FilterType::JBIG2Decode => decode_jbig2(
+            data,
+            Some(&JBIG2DecodeParams {
+                jbig2_globals: match parms.get("JBIG2Globals") {
+                    Some(Object::Stream { data, .. }) => Some(data.to_vec()),
+                    _ => None,
+                },
+            }),
+        ),
+        // Encryption is handled when the object is loaded
+        FilterType::Crypt => Ok(data.to_vec()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::parser;
+
+    fn stream_dict(src: &str) -> Dict {
+        match parser::parse_object(src.as_bytes()).unwrap() {
+            Object::Dict(dict) => dict,
+            other => panic!("not a dict: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_decompression_bomb_rejected() {
+        // 64 MiB of zeros deflate to about 64 KiB
+        let bomb = encode_flate(&vec![0u8; 64 << 20], 9).unwrap();
+        assert!(bomb.len() < 1 << 20);
+        let dict = stream_dict("<< /Filter /FlateDecode >>");
+        let err = decode_stream_limited(&dict, &bomb, 1 << 20).unwrap_err();
+        assert!(matches!(err, Error::Limit(_)), "{err}");
+
+        // The cap covers every stage of the chain, not just Flate
+        let runs = encode_flate(&[0x81, 0].repeat(1 << 12), 6).unwrap();
+        let dict = stream_dict("<< /Filter [/FlateDecode /RunLengthDecode] >>");
+        assert_eq!(decode_stream(&dict, &runs).unwrap().len(), 128 << 12);
+        let err = decode_stream_limited(&dict, &runs, 64 << 10).unwrap_err();
+        assert!(matches!(err, Error::Limit(_)), "{err}");
+
+        let small = encode_flate(b"q 1 0 0 1 0 0 cm Q", 6).unwrap();
+        let dict = stream_dict("<< /Filter /FlateDecode >>");
+        assert_eq!(decode_stream_limited(&dict, &small, 18).unwrap().len(), 18);
+        assert!(decode_stream_limited(&dict, &small, 17).is_err());
+    }
+
+    #[test]
+    fn test_lzw_and_run_length_bombs_rejected() {
+        // Each filter stops at the cap while decoding, not after
+        let zeros = vec![0u8; 16 << 20];
+        let lzw = encode_lzw(&zeros).unwrap();
+        assert!(lzw.len() < 1 << 20);
+        let dict = stream_dict("<< /Filter /LZWDecode >>");
+        let err = decode_stream_limited(&dict, &lzw, 1 << 20).unwrap_err();
+        assert!(matches!(err, Error::Limit(_)), "{err}");
+        assert_eq!(decode_stream(&dict, &lzw).unwrap().len(), zeros.len());
+
+        let runs = [0x81, 0].repeat(1 << 16);
+        let dict = stream_dict("<< /Filter /RunLengthDecode >>");
+        let err = decode_stream_limited(&dict, &runs, 1 << 20).unwrap_err();
+        assert!(matches!(err, Error::Limit(_)), "{err}");
+        assert!(matches!(
+            decode_run_length_limited(&runs, 127),
+            Err(Error::Limit(_))
+        ));
+
+        let a85 = b"zzzz~>";
+        assert_eq!(decode_ascii85_limited(a85, 16).unwrap().len(), 16);
+        assert!(matches!(
+            decode_ascii85_limited(a85, 15),
+            Err(Error::Limit(_))
+        ));
+        assert!(matches!(
+            decode_ascii_hex_limited(b"0102>", 1),
+            Err(Error::Limit(_))
+        ));
+    }
+
+    #[test]
+    fn test_ascii85_then_flate() {
+        let original = b"BT /F1 12 Tf (Hello) Tj ET";
+        let raw = encode_ascii85(&encode_flate(original, 6).unwrap()).unwrap();
+        let dict = stream_dict("<< /Filter [/ASCII85Decode /FlateDecode] >>");
+        assert_eq!(decode_stream(&dict, &raw).unwrap(), original);
+
+        let dict = stream_dict("<< /Filter [/A85 /Fl] /DecodeParms [null null] >>");
+        assert_eq!(decode_stream(&dict, &raw).unwrap(), original);
+    }
+
+    #[test]
+    fn test_decode_parms_follow_filter_position() {
+        // Two rows of three bytes with PNG Up prediction
+        let rows = [2u8, 1, 2, 3, 2, 1, 1, 1];
+        let raw = encode_ascii_hex(&encode_flate(&rows, 6).unwrap()).unwrap();
+        let dict = stream_dict(
+            "<< /Filter [/ASCIIHexDecode /FlateDecode] \
+               /DecodeParms [null << /Predictor 12 /Columns 3 >>] >>",
+        );
+        assert_eq!(decode_stream(&dict, &raw).unwrap(), [1, 2, 3, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_unknown_final_filter_is_left_over() {
+        let raw = encode_ascii_hex(b"opaque").unwrap();
+        let dict = stream_dict("<< /Filter [/ASCIIHexDecode /FancyImageDecode] >>");
+        let decoded = decode_stream_partial(&dict, &raw).unwrap();
+        assert_eq!(decoded.data, b"opaque");
+        assert_eq!(decoded.remaining.as_deref(), Some("FancyImageDecode"));
+        assert!(decode_stream(&dict, &raw).is_err());
+
+        let dict = stream_dict("<< /Filter [/FancyImageDecode /ASCIIHexDecode] >>");
+        assert!(decode_stream_partial(&dict, &raw).is_err());
+    }
+}
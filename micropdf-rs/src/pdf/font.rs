@@ -1 +1,1309 @@
-//! PDF font module stub
+//! PDF fonts
+//!
+//! Viewers must provide the standard 14 fonts themselves, so a simple font
+//! that names one as its /BaseFont may leave out both the font program and
+//! /Widths. The widths here are those of the Adobe Core 14 AFM files.
+
+use crate::fitz::error::Result;
+use crate::fitz::font::{Font, standard_fonts};
+use crate::pdf::document::Document;
+use crate::pdf::object::{Dict, Object};
+use std::collections::HashMap;
+
+/// Base encoding of a simple font, mapping codes to glyph names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BaseEncoding {
+    #[default]
+    Standard,
+    WinAnsi,
+    MacRoman,
+    /// Built-in encoding of the Symbol font
+    Symbol,
+}
+
+impl BaseEncoding {
+    /// Parse an /Encoding or /BaseEncoding name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "StandardEncoding" => Some(Self::Standard),
+            "WinAnsiEncoding" => Some(Self::WinAnsi),
+            "MacRomanEncoding" => Some(Self::MacRoman),
+            _ => None,
+        }
+    }
+
+    /// Glyph name of a character code, if the encoding defines one
+    pub fn glyph_name(self, code: u8) -> Option<&'static str> {
+        match (self, code) {
+            (Self::Symbol, 32..=126) => Some(SYMBOL_GLYPHS[code as usize - 32]),
+            (Self::Symbol, _) => None,
+            (Self::WinAnsi | Self::MacRoman, 39) => Some("quotesingle"),
+            (Self::WinAnsi | Self::MacRoman, 96) => Some("grave"),
+            (_, 32..=126) => Some(ASCII_GLYPHS[code as usize - 32]),
+            (Self::Standard, _) => standard_glyph(code),
+            (Self::WinAnsi, 160..=255) => Some(WIN_ANSI_LATIN1[code as usize - 160]),
+            (Self::WinAnsi, _) => win_ansi_glyph(code),
+            (Self::MacRoman, 128..=255) => Some(MAC_ROMAN_HIGH[code as usize - 128]),
+            (Self::MacRoman, _) => None,
+        }
+    }
+}
+
+/// Code-to-glyph-name mapping of a simple font: a base encoding with the
+/// /Differences of its /Encoding dictionary applied
+#[derive(Debug, Clone, Default)]
+pub struct Encoding {
+    base: Option<BaseEncoding>,
+    differences: HashMap<u8, String>,
+}
+
+impl Encoding {
+    pub fn new(base: Option<BaseEncoding>) -> Self {
+        Self {
+            base,
+            differences: HashMap::new(),
+        }
+    }
+
+    /// Read the /Encoding of a simple font dictionary
+    ///
+    /// Without a named base encoding the font's built-in one applies:
+    /// StandardEncoding for text fonts, none for ZapfDingbats, whose codes
+    /// are left as they are.
+    pub fn load(doc: &Document, font: &Dict) -> Result<Self> {
+        let base_font = doc.resolve_key(font, "BaseFont")?;
+        let builtin = match base_font
+            .as_ref()
+            .and_then(Object::as_name)
+            .and_then(|name| standard_font_name(name.as_str()))
+        {
+            Some(standard_fonts::SYMBOL) => Some(BaseEncoding::Symbol),
+            Some(standard_fonts::ZAPF_DINGBATS) => None,
+            _ => Some(BaseEncoding::Standard),
+        };
+        let named = |name: Option<Object>| {
+            name.as_ref()
+                .and_then(Object::as_name)
+                .and_then(|name| BaseEncoding::from_name(name.as_str()))
+                .or(builtin)
+        };
+
+        let mut encoding = Self::new(builtin);
+        match doc.resolve_key(font, "Encoding")? {
+            Some(Object::Dict(dict)) => {
+                encoding.base = named(doc.resolve_key(&dict, "BaseEncoding")?);
+                if let Some(Object::Array(differences)) = doc.resolve_key(&dict, "Differences")? {
+                    let mut code = 0i64;
+                    for item in &differences {
+                        match doc.resolve(item)? {
+                            Object::Int(first) => code = first,
+                            Object::Name(glyph) => {
+                                if let Ok(code) = u8::try_from(code) {
+                                    encoding
+                                        .differences
+                                        .insert(code, glyph.as_str().to_string());
+                                }
+                                code += 1;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            name @ Some(Object::Name(_)) => encoding.base = named(name),
+            _ => {}
+        }
+        Ok(encoding)
+    }
+
+    /// Glyph name of a character code
+    pub fn glyph_name(&self, code: u8) -> Option<&str> {
+        match self.differences.get(&code) {
+            Some(name) => Some(name),
+            None => self.base?.glyph_name(code),
+        }
+    }
+
+    /// Unicode value of a character code, through its glyph name
+    pub fn unicode(&self, code: u8) -> Option<char> {
+        glyph_unicode(self.glyph_name(code)?)
+    }
+}
+
+/// Unicode value of a glyph name
+///
+/// Names come from the Adobe Glyph List, or are `uniXXXX` and `uXXXX` to
+/// `uXXXXXX` code points, or `gXX` hex codes as some producers write them.
+/// Suffixes such as `.sc` and all but the first ligature component are
+/// ignored.
+pub fn glyph_unicode(name: &str) -> Option<char> {
+    let name = name.split(['.', '_']).next()?;
+    if let Some(i) = ASCII_GLYPHS.iter().position(|&g| g == name) {
+        return Some(match name {
+            "quoteright" => '\u{2019}',
+            "quoteleft" => '\u{2018}',
+            _ => char::from(32 + i as u8),
+        });
+    }
+    if let Some(i) = WIN_ANSI_LATIN1.iter().position(|&g| g == name) {
+        return char::from_u32(160 + i as u32);
+    }
+    if let Some(c) = agl_unicode(name) {
+        return Some(c);
+    }
+
+    let hex = |digits: &str| {
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .filter(|c| !c.is_control())
+    };
+    if let Some(digits) = name.strip_prefix("uni") {
+        return hex(digits.get(..4)?);
+    }
+    if let Some(digits) = name
+        .strip_prefix('u')
+        .filter(|d| (4..=6).contains(&d.len()))
+    {
+        return hex(digits);
+    }
+    if let Some(digits) = name
+        .strip_prefix(['g', 'G'])
+        .filter(|d| (2..=4).contains(&d.len()))
+    {
+        return hex(digits);
+    }
+    None
+}
+
+/// Canonical standard 14 name for a /BaseFont, if it is one of them
+///
+/// Subset tags are ignored and the common Windows aliases are accepted:
+/// `ABCDEF+Arial,Bold` and `Arial-BoldMT` are both Helvetica-Bold.
+pub fn standard_font_name(base_font: &str) -> Option<&'static str> {
+    let name: String = strip_subset(base_font)
+        .chars()
+        .filter(|&c| c != ' ')
+        .collect();
+    let (family, style) = match name.find([',', '-']) {
+        Some(i) => name.split_at(i),
+        None => (name.as_str(), ""),
+    };
+    let family = match family {
+        "Helvetica" | "Arial" | "ArialMT" => Family::Helvetica,
+        "Times" | "TimesNewRoman" | "TimesNewRomanPS" | "TimesNewRomanPSMT" => Family::Times,
+        "Courier" | "CourierNew" | "CourierNewPS" | "CourierNewPSMT" => Family::Courier,
+        "Symbol" => return Some(standard_fonts::SYMBOL),
+        "ZapfDingbats" => return Some(standard_fonts::ZAPF_DINGBATS),
+        _ => return None,
+    };
+    let italic = style.contains("Italic") || style.contains("Oblique");
+    Some(family.styled(style.contains("Bold"), italic))
+}
+
+/// Standard 14 font to render a non-embedded font with
+///
+/// Fonts outside the standard 14 get the closest family and style judging
+/// by their name, falling back to Helvetica.
+pub fn substitute_name(base_font: &str) -> &'static str {
+    if let Some(name) = standard_font_name(base_font) {
+        return name;
+    }
+    let name = strip_subset(base_font);
+    let lower = name.to_ascii_lowercase();
+    let family = if lower.contains("courier") || lower.contains("mono") {
+        Family::Courier
+    } else if lower.contains("times")
+        || lower.contains("roman")
+        || (lower.contains("serif") && !lower.contains("sans"))
+    {
+        Family::Times
+    } else {
+        Family::Helvetica
+    };
+    let bold = lower.contains("bold") || lower.contains("black") || lower.contains("heavy");
+    let italic = lower.contains("italic") || lower.contains("oblique");
+    family.styled(bold, italic)
+}
+
+/// Create the substitute of a non-embedded font
+pub fn substitute_font(base_font: &str) -> Font {
+    standard_fonts::create(substitute_name(base_font))
+}
+
+/// Advance width of a code in a standard 14 font, in thousandths of an em
+///
+/// `font` is a canonical name from [`standard_font_name`]. Symbol and
+/// ZapfDingbats widths always follow their built-in encodings.
+pub fn standard_width(font: &str, encoding: &Encoding, code: u8) -> Option<u16> {
+    let symbolic = match font {
+        standard_fonts::SYMBOL => Some(&SYMBOL_WIDTHS),
+        standard_fonts::ZAPF_DINGBATS => Some(&ZAPF_DINGBATS_WIDTHS),
+        _ => None,
+    };
+    if let Some(widths) = symbolic {
+        return (32..=126)
+            .contains(&code)
+            .then(|| widths[code as usize - 32]);
+    }
+    glyph_width(font, encoding.glyph_name(code)?)
+}
+
+/// Advance width of a named glyph in a standard 14 text font
+pub fn glyph_width(font: &str, glyph: &str) -> Option<u16> {
+    let (ascii, extra) = match font {
+        standard_fonts::HELVETICA | standard_fonts::HELVETICA_OBLIQUE => {
+            (&HELVETICA_ASCII, &HELVETICA_EXTRA)
+        }
+        standard_fonts::HELVETICA_BOLD | standard_fonts::HELVETICA_BOLD_OBLIQUE => {
+            (&HELVETICA_BOLD_ASCII, &HELVETICA_BOLD_EXTRA)
+        }
+        standard_fonts::TIMES_ROMAN => (&TIMES_ROMAN_ASCII, &TIMES_ROMAN_EXTRA),
+        standard_fonts::TIMES_BOLD => (&TIMES_BOLD_ASCII, &TIMES_BOLD_EXTRA),
+        standard_fonts::TIMES_ITALIC => (&TIMES_ITALIC_ASCII, &TIMES_ITALIC_EXTRA),
+        standard_fonts::TIMES_BOLD_ITALIC => (&TIMES_BOLD_ITALIC_ASCII, &TIMES_BOLD_ITALIC_EXTRA),
+        // Every Courier glyph is 600 units wide
+        name if name.starts_with("Courier") => {
+            return latin_width(&HELVETICA_ASCII, &HELVETICA_EXTRA, glyph).map(|_| 600);
+        }
+        _ => return None,
+    };
+    latin_width(ascii, extra, glyph)
+}
+
+fn latin_width(ascii: &[u16; 95], extra: &[u16; 62], glyph: &str) -> Option<u16> {
+    if let Some(i) = ASCII_GLYPHS.iter().position(|&g| g == glyph) {
+        return Some(ascii[i]);
+    }
+    if let Some(i) = EXTRA_GLYPHS.iter().position(|&g| g == glyph) {
+        return Some(extra[i]);
+    }
+    // Spacing accents are 333 units in all four families
+    if ACCENTS.contains(&glyph) {
+        return Some(333);
+    }
+    // Accented letters are as wide as their base letter, with a dotless i
+    let base = ACCENTS
+        .iter()
+        .find_map(|accent| glyph.strip_suffix(accent))
+        .filter(|base| base.len() == 1)?;
+    let base = if base == "i" { "dotlessi" } else { base };
+    latin_width(ascii, extra, base)
+}
+
+/// Drop the `ABCDEF+` tag of a subset font name
+fn strip_subset(name: &str) -> &str {
+    match name.split_once('+') {
+        Some((tag, rest)) if tag.len() == 6 && tag.bytes().all(|b| b.is_ascii_uppercase()) => rest,
+        _ => name,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Family {
+    Helvetica,
+    Times,
+    Courier,
+}
+
+impl Family {
+    fn styled(self, bold: bool, italic: bool) -> &'static str {
+        use standard_fonts::*;
+        match (self, bold, italic) {
+            (Self::Helvetica, false, false) => HELVETICA,
+            (Self::Helvetica, true, false) => HELVETICA_BOLD,
+            (Self::Helvetica, false, true) => HELVETICA_OBLIQUE,
+            (Self::Helvetica, true, true) => HELVETICA_BOLD_OBLIQUE,
+            (Self::Times, false, false) => TIMES_ROMAN,
+            (Self::Times, true, false) => TIMES_BOLD,
+            (Self::Times, false, true) => TIMES_ITALIC,
+            (Self::Times, true, true) => TIMES_BOLD_ITALIC,
+            (Self::Courier, false, false) => COURIER,
+            (Self::Courier, true, false) => COURIER_BOLD,
+            (Self::Courier, false, true) => COURIER_OBLIQUE,
+            (Self::Courier, true, true) => COURIER_BOLD_OBLIQUE,
+        }
+    }
+}
+
+fn standard_glyph(code: u8) -> Option<&'static str> {
+    Some(match code {
+        161 => "exclamdown",
+        162 => "cent",
+        163 => "sterling",
+        164 => "fraction",
+        165 => "yen",
+        166 => "florin",
+        167 => "section",
+        168 => "currency",
+        169 => "quotesingle",
+        170 => "quotedblleft",
+        171 => "guillemotleft",
+        172 => "guilsinglleft",
+        173 => "guilsinglright",
+        174 => "fi",
+        175 => "fl",
+        177 => "endash",
+        178 => "dagger",
+        179 => "daggerdbl",
+        180 => "periodcentered",
+        182 => "paragraph",
+        183 => "bullet",
+        184 => "quotesinglbase",
+        185 => "quotedblbase",
+        186 => "quotedblright",
+        187 => "guillemotright",
+        188 => "ellipsis",
+        189 => "perthousand",
+        191 => "questiondown",
+        193 => "grave",
+        194 => "acute",
+        195 => "circumflex",
+        196 => "tilde",
+        197 => "macron",
+        198 => "breve",
+        199 => "dotaccent",
+        200 => "dieresis",
+        202 => "ring",
+        203 => "cedilla",
+        205 => "hungarumlaut",
+        206 => "ogonek",
+        207 => "caron",
+        208 => "emdash",
+        225 => "AE",
+        227 => "ordfeminine",
+        232 => "Lslash",
+        233 => "Oslash",
+        234 => "OE",
+        235 => "ordmasculine",
+        241 => "ae",
+        245 => "dotlessi",
+        248 => "lslash",
+        249 => "oslash",
+        250 => "oe",
+        251 => "germandbls",
+        _ => return None,
+    })
+}
+
+fn win_ansi_glyph(code: u8) -> Option<&'static str> {
+    Some(match code {
+        128 => "Euro",
+        130 => "quotesinglbase",
+        131 => "florin",
+        132 => "quotedblbase",
+        133 => "ellipsis",
+        134 => "dagger",
+        135 => "daggerdbl",
+        136 => "circumflex",
+        137 => "perthousand",
+        138 => "Scaron",
+        139 => "guilsinglleft",
+        140 => "OE",
+        142 => "Zcaron",
+        145 => "quoteleft",
+        146 => "quoteright",
+        147 => "quotedblleft",
+        148 => "quotedblright",
+        149 => "bullet",
+        150 => "endash",
+        151 => "emdash",
+        152 => "tilde",
+        153 => "trademark",
+        154 => "scaron",
+        155 => "guilsinglright",
+        156 => "oe",
+        158 => "zcaron",
+        159 => "Ydieresis",
+        _ => return None,
+    })
+}
+
+/// Glyph names of codes 32-126 in StandardEncoding
+const ASCII_GLYPHS: [&str; 95] = [
+    "space",
+    "exclam",
+    "quotedbl",
+    "numbersign",
+    "dollar",
+    "percent",
+    "ampersand",
+    "quoteright",
+    "parenleft",
+    "parenright",
+    "asterisk",
+    "plus",
+    "comma",
+    "hyphen",
+    "period",
+    "slash",
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "colon",
+    "semicolon",
+    "less",
+    "equal",
+    "greater",
+    "question",
+    "at",
+    "A",
+    "B",
+    "C",
+    "D",
+    "E",
+    "F",
+    "G",
+    "H",
+    "I",
+    "J",
+    "K",
+    "L",
+    "M",
+    "N",
+    "O",
+    "P",
+    "Q",
+    "R",
+    "S",
+    "T",
+    "U",
+    "V",
+    "W",
+    "X",
+    "Y",
+    "Z",
+    "bracketleft",
+    "backslash",
+    "bracketright",
+    "asciicircum",
+    "underscore",
+    "quoteleft",
+    "a",
+    "b",
+    "c",
+    "d",
+    "e",
+    "f",
+    "g",
+    "h",
+    "i",
+    "j",
+    "k",
+    "l",
+    "m",
+    "n",
+    "o",
+    "p",
+    "q",
+    "r",
+    "s",
+    "t",
+    "u",
+    "v",
+    "w",
+    "x",
+    "y",
+    "z",
+    "braceleft",
+    "bar",
+    "braceright",
+    "asciitilde",
+];
+
+/// Glyph names of codes 160-255 in WinAnsiEncoding, which follows Latin-1
+const WIN_ANSI_LATIN1: [&str; 96] = [
+    "space",
+    "exclamdown",
+    "cent",
+    "sterling",
+    "currency",
+    "yen",
+    "brokenbar",
+    "section",
+    "dieresis",
+    "copyright",
+    "ordfeminine",
+    "guillemotleft",
+    "logicalnot",
+    "hyphen",
+    "registered",
+    "macron",
+    "degree",
+    "plusminus",
+    "twosuperior",
+    "threesuperior",
+    "acute",
+    "mu",
+    "paragraph",
+    "periodcentered",
+    "cedilla",
+    "onesuperior",
+    "ordmasculine",
+    "guillemotright",
+    "onequarter",
+    "onehalf",
+    "threequarters",
+    "questiondown",
+    "Agrave",
+    "Aacute",
+    "Acircumflex",
+    "Atilde",
+    "Adieresis",
+    "Aring",
+    "AE",
+    "Ccedilla",
+    "Egrave",
+    "Eacute",
+    "Ecircumflex",
+    "Edieresis",
+    "Igrave",
+    "Iacute",
+    "Icircumflex",
+    "Idieresis",
+    "Eth",
+    "Ntilde",
+    "Ograve",
+    "Oacute",
+    "Ocircumflex",
+    "Otilde",
+    "Odieresis",
+    "multiply",
+    "Oslash",
+    "Ugrave",
+    "Uacute",
+    "Ucircumflex",
+    "Udieresis",
+    "Yacute",
+    "Thorn",
+    "germandbls",
+    "agrave",
+    "aacute",
+    "acircumflex",
+    "atilde",
+    "adieresis",
+    "aring",
+    "ae",
+    "ccedilla",
+    "egrave",
+    "eacute",
+    "ecircumflex",
+    "edieresis",
+    "igrave",
+    "iacute",
+    "icircumflex",
+    "idieresis",
+    "eth",
+    "ntilde",
+    "ograve",
+    "oacute",
+    "ocircumflex",
+    "otilde",
+    "odieresis",
+    "divide",
+    "oslash",
+    "ugrave",
+    "uacute",
+    "ucircumflex",
+    "udieresis",
+    "yacute",
+    "thorn",
+    "ydieresis",
+];
+
+/// Glyph names of codes 32-126 in the Symbol font's built-in encoding
+const SYMBOL_GLYPHS: [&str; 95] = [
+    "space",
+    "exclam",
+    "universal",
+    "numbersign",
+    "existential",
+    "percent",
+    "ampersand",
+    "suchthat",
+    "parenleft",
+    "parenright",
+    "asteriskmath",
+    "plus",
+    "comma",
+    "minus",
+    "period",
+    "slash",
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "colon",
+    "semicolon",
+    "less",
+    "equal",
+    "greater",
+    "question",
+    "congruent",
+    "Alpha",
+    "Beta",
+    "Chi",
+    "Delta",
+    "Epsilon",
+    "Phi",
+    "Gamma",
+    "Eta",
+    "Iota",
+    "theta1",
+    "Kappa",
+    "Lambda",
+    "Mu",
+    "Nu",
+    "Omicron",
+    "Pi",
+    "Theta",
+    "Rho",
+    "Sigma",
+    "Tau",
+    "Upsilon",
+    "sigma1",
+    "Omega",
+    "Xi",
+    "Psi",
+    "Zeta",
+    "bracketleft",
+    "therefore",
+    "bracketright",
+    "perpendicular",
+    "underscore",
+    "radicalex",
+    "alpha",
+    "beta",
+    "chi",
+    "delta",
+    "epsilon",
+    "phi",
+    "gamma",
+    "eta",
+    "iota",
+    "phi1",
+    "kappa",
+    "lambda",
+    "mu",
+    "nu",
+    "omicron",
+    "pi",
+    "theta",
+    "rho",
+    "sigma",
+    "tau",
+    "upsilon",
+    "omega1",
+    "omega",
+    "xi",
+    "psi",
+    "zeta",
+    "braceleft",
+    "bar",
+    "braceright",
+    "similar",
+];
+
+/// Glyph names of codes 128-255 in MacRomanEncoding
+const MAC_ROMAN_HIGH: [&str; 128] = [
+    "Adieresis",
+    "Aring",
+    "Ccedilla",
+    "Eacute",
+    "Ntilde",
+    "Odieresis",
+    "Udieresis",
+    "aacute",
+    "agrave",
+    "acircumflex",
+    "adieresis",
+    "atilde",
+    "aring",
+    "ccedilla",
+    "eacute",
+    "egrave",
+    "ecircumflex",
+    "edieresis",
+    "iacute",
+    "igrave",
+    "icircumflex",
+    "idieresis",
+    "ntilde",
+    "oacute",
+    "ograve",
+    "ocircumflex",
+    "odieresis",
+    "otilde",
+    "uacute",
+    "ugrave",
+    "ucircumflex",
+    "udieresis",
+    "dagger",
+    "degree",
+    "cent",
+    "sterling",
+    "section",
+    "bullet",
+    "paragraph",
+    "germandbls",
+    "registered",
+    "copyright",
+    "trademark",
+    "acute",
+    "dieresis",
+    "notequal",
+    "AE",
+    "Oslash",
+    "infinity",
+    "plusminus",
+    "lessequal",
+    "greaterequal",
+    "yen",
+    "mu",
+    "partialdiff",
+    "summation",
+    "product",
+    "pi",
+    "integral",
+    "ordfeminine",
+    "ordmasculine",
+    "Omega",
+    "ae",
+    "oslash",
+    "questiondown",
+    "exclamdown",
+    "logicalnot",
+    "radical",
+    "florin",
+    "approxequal",
+    "Delta",
+    "guillemotleft",
+    "guillemotright",
+    "ellipsis",
+    "space",
+    "Agrave",
+    "Atilde",
+    "Otilde",
+    "OE",
+    "oe",
+    "endash",
+    "emdash",
+    "quotedblleft",
+    "quotedblright",
+    "quoteleft",
+    "quoteright",
+    "divide",
+    "lozenge",
+    "ydieresis",
+    "Ydieresis",
+    "fraction",
+    "currency",
+    "guilsinglleft",
+    "guilsinglright",
+    "fi",
+    "fl",
+    "daggerdbl",
+    "periodcentered",
+    "quotesinglbase",
+    "quotedblbase",
+    "perthousand",
+    "Acircumflex",
+    "Ecircumflex",
+    "Aacute",
+    "Edieresis",
+    "Egrave",
+    "Iacute",
+    "Icircumflex",
+    "Idieresis",
+    "Igrave",
+    "Oacute",
+    "Ocircumflex",
+    "apple",
+    "Ograve",
+    "Uacute",
+    "Ucircumflex",
+    "Ugrave",
+    "dotlessi",
+    "circumflex",
+    "tilde",
+    "macron",
+    "breve",
+    "dotaccent",
+    "ring",
+    "cedilla",
+    "hungarumlaut",
+    "ogonek",
+    "caron",
+];
+
+/// Adobe Glyph List entries outside ASCII and Latin-1: those of the
+/// standard encodings, the Symbol font and common Latin Extended letters
+fn agl_unicode(name: &str) -> Option<char> {
+    let code: u32 = match name {
+        "quotesingle" => 0x27,
+        "grave" => 0x60,
+        "nbspace" => 0xA0,
+        "sfthyphen" => 0xAD,
+        "Euro" => 0x20AC,
+        "florin" => 0x0192,
+        "circumflex" => 0x02C6,
+        "caron" => 0x02C7,
+        "breve" => 0x02D8,
+        "dotaccent" => 0x02D9,
+        "ring" => 0x02DA,
+        "ogonek" => 0x02DB,
+        "tilde" => 0x02DC,
+        "hungarumlaut" => 0x02DD,
+        "dotlessi" => 0x0131,
+        "Lslash" => 0x0141,
+        "lslash" => 0x0142,
+        "OE" => 0x0152,
+        "oe" => 0x0153,
+        "Scaron" => 0x0160,
+        "scaron" => 0x0161,
+        "Ydieresis" => 0x0178,
+        "Zcaron" => 0x017D,
+        "zcaron" => 0x017E,
+        "Abreve" => 0x0102,
+        "abreve" => 0x0103,
+        "Aogonek" => 0x0104,
+        "aogonek" => 0x0105,
+        "Cacute" => 0x0106,
+        "cacute" => 0x0107,
+        "Ccaron" => 0x010C,
+        "ccaron" => 0x010D,
+        "Dcaron" => 0x010E,
+        "dcaron" => 0x010F,
+        "Dcroat" => 0x0110,
+        "dcroat" => 0x0111,
+        "Eogonek" => 0x0118,
+        "eogonek" => 0x0119,
+        "Ecaron" => 0x011A,
+        "ecaron" => 0x011B,
+        "Gbreve" => 0x011E,
+        "gbreve" => 0x011F,
+        "Idotaccent" => 0x0130,
+        "Lacute" => 0x0139,
+        "lacute" => 0x013A,
+        "Lcaron" => 0x013D,
+        "lcaron" => 0x013E,
+        "Nacute" => 0x0143,
+        "nacute" => 0x0144,
+        "Ncaron" => 0x0147,
+        "ncaron" => 0x0148,
+        "Ohungarumlaut" => 0x0150,
+        "ohungarumlaut" => 0x0151,
+        "Racute" => 0x0154,
+        "racute" => 0x0155,
+        "Rcaron" => 0x0158,
+        "rcaron" => 0x0159,
+        "Sacute" => 0x015A,
+        "sacute" => 0x015B,
+        "Scedilla" => 0x015E,
+        "scedilla" => 0x015F,
+        "Tcaron" => 0x0164,
+        "tcaron" => 0x0165,
+        "Uring" => 0x016E,
+        "uring" => 0x016F,
+        "Uhungarumlaut" => 0x0170,
+        "uhungarumlaut" => 0x0171,
+        "Zacute" => 0x0179,
+        "zacute" => 0x017A,
+        "Zdotaccent" => 0x017B,
+        "zdotaccent" => 0x017C,
+        "endash" => 0x2013,
+        "emdash" => 0x2014,
+        "quotesinglbase" => 0x201A,
+        "quotedblleft" => 0x201C,
+        "quotedblright" => 0x201D,
+        "quotedblbase" => 0x201E,
+        "dagger" => 0x2020,
+        "daggerdbl" => 0x2021,
+        "bullet" => 0x2022,
+        "ellipsis" => 0x2026,
+        "perthousand" => 0x2030,
+        "guilsinglleft" => 0x2039,
+        "guilsinglright" => 0x203A,
+        "fraction" => 0x2044,
+        "trademark" => 0x2122,
+        "Omega" => 0x2126,
+        "minus" => 0x2212,
+        "fi" => 0xFB01,
+        "fl" => 0xFB02,
+        "ff" => 0xFB00,
+        "ffi" => 0xFB03,
+        "ffl" => 0xFB04,
+        "apple" => 0xF8FF,
+        "Alpha" => 0x0391,
+        "Beta" => 0x0392,
+        "Gamma" => 0x0393,
+        "Delta" => 0x2206,
+        "Epsilon" => 0x0395,
+        "Zeta" => 0x0396,
+        "Eta" => 0x0397,
+        "Theta" => 0x0398,
+        "Iota" => 0x0399,
+        "Kappa" => 0x039A,
+        "Lambda" => 0x039B,
+        "Mu" => 0x039C,
+        "Nu" => 0x039D,
+        "Xi" => 0x039E,
+        "Omicron" => 0x039F,
+        "Pi" => 0x03A0,
+        "Rho" => 0x03A1,
+        "Sigma" => 0x03A3,
+        "Tau" => 0x03A4,
+        "Upsilon" => 0x03A5,
+        "Phi" => 0x03A6,
+        "Chi" => 0x03A7,
+        "Psi" => 0x03A8,
+        "alpha" => 0x03B1,
+        "beta" => 0x03B2,
+        "gamma" => 0x03B3,
+        "delta" => 0x03B4,
+        "epsilon" => 0x03B5,
+        "zeta" => 0x03B6,
+        "eta" => 0x03B7,
+        "theta" => 0x03B8,
+        "iota" => 0x03B9,
+        "kappa" => 0x03BA,
+        "lambda" => 0x03BB,
+        "nu" => 0x03BD,
+        "xi" => 0x03BE,
+        "omicron" => 0x03BF,
+        "pi" => 0x03C0,
+        "rho" => 0x03C1,
+        "sigma1" => 0x03C2,
+        "sigma" => 0x03C3,
+        "tau" => 0x03C4,
+        "upsilon" => 0x03C5,
+        "phi" => 0x03C6,
+        "chi" => 0x03C7,
+        "psi" => 0x03C8,
+        "omega" => 0x03C9,
+        "theta1" => 0x03D1,
+        "phi1" => 0x03D5,
+        "omega1" => 0x03D6,
+        "universal" => 0x2200,
+        "partialdiff" => 0x2202,
+        "existential" => 0x2203,
+        "suchthat" => 0x220B,
+        "product" => 0x220F,
+        "summation" => 0x2211,
+        "asteriskmath" => 0x2217,
+        "radical" => 0x221A,
+        "infinity" => 0x221E,
+        "integral" => 0x222B,
+        "therefore" => 0x2234,
+        "similar" => 0x223C,
+        "congruent" => 0x2245,
+        "approxequal" => 0x2248,
+        "notequal" => 0x2260,
+        "lessequal" => 0x2264,
+        "greaterequal" => 0x2265,
+        "perpendicular" => 0x22A5,
+        "lozenge" => 0x25CA,
+        _ => return None,
+    };
+    char::from_u32(code)
+}
+
+/// Spacing accents, also the suffixes of accented letter names
+const ACCENTS: [&str; 13] = [
+    "grave",
+    "acute",
+    "circumflex",
+    "tilde",
+    "macron",
+    "breve",
+    "dotaccent",
+    "dieresis",
+    "ring",
+    "cedilla",
+    "hungarumlaut",
+    "ogonek",
+    "caron",
+];
+
+/// Helvetica and Helvetica-Oblique
+const HELVETICA_ASCII: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 222, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 222, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Helvetica-Bold and Helvetica-BoldOblique
+const HELVETICA_BOLD_ASCII: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 278, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 278, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+/// Times-Roman
+const TIMES_ROMAN_ASCII: [u16; 95] = [
+    250, 333, 408, 500, 500, 833, 778, 333, 333, 333, 500, 564, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 278, 278, 564, 564, 564, 444, 921, 722, 667, 667, 722, 611,
+    556, 722, 722, 333, 389, 722, 611, 889, 722, 722, 556, 722, 667, 556, 611, 722, 722, 944, 722,
+    722, 611, 333, 278, 333, 469, 500, 333, 444, 500, 444, 500, 444, 333, 500, 500, 278, 278, 500,
+    278, 778, 500, 500, 500, 500, 333, 389, 278, 500, 500, 722, 500, 500, 444, 480, 200, 480, 541,
+];
+
+/// Times-Bold
+const TIMES_BOLD_ASCII: [u16; 95] = [
+    250, 333, 555, 500, 500, 1000, 833, 333, 333, 333, 500, 570, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 333, 333, 570, 570, 570, 500, 930, 722, 667, 722, 722, 667,
+    611, 778, 778, 389, 500, 778, 667, 944, 722, 778, 611, 778, 722, 556, 667, 722, 722, 1000, 722,
+    722, 667, 333, 278, 333, 581, 500, 333, 500, 556, 444, 556, 444, 333, 500, 556, 278, 333, 556,
+    278, 833, 556, 500, 556, 556, 444, 389, 333, 556, 500, 722, 500, 500, 444, 394, 220, 394, 520,
+];
+
+/// Times-Italic
+const TIMES_ITALIC_ASCII: [u16; 95] = [
+    250, 333, 420, 500, 500, 833, 778, 333, 333, 333, 500, 675, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 333, 333, 675, 675, 675, 500, 920, 611, 611, 667, 722, 611,
+    611, 722, 722, 333, 444, 667, 556, 833, 667, 722, 611, 722, 611, 500, 556, 722, 611, 833, 611,
+    556, 556, 389, 278, 389, 422, 500, 333, 500, 500, 444, 500, 444, 278, 500, 500, 278, 278, 444,
+    278, 722, 500, 500, 500, 500, 389, 389, 278, 500, 444, 667, 444, 444, 389, 400, 275, 400, 541,
+];
+
+/// Times-BoldItalic
+const TIMES_BOLD_ITALIC_ASCII: [u16; 95] = [
+    250, 389, 555, 500, 500, 833, 778, 333, 333, 333, 500, 570, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 333, 333, 570, 570, 570, 500, 832, 667, 667, 667, 722, 667,
+    667, 722, 778, 389, 500, 667, 611, 889, 722, 722, 611, 722, 667, 556, 611, 722, 667, 889, 667,
+    611, 611, 333, 278, 333, 570, 500, 333, 500, 500, 444, 500, 444, 333, 500, 556, 278, 278, 500,
+    278, 778, 556, 500, 500, 500, 389, 389, 278, 556, 444, 667, 500, 444, 389, 348, 220, 348, 570,
+];
+
+/// Symbol, by code in its built-in encoding
+const SYMBOL_WIDTHS: [u16; 95] = [
+    250, 333, 713, 500, 549, 833, 778, 439, 333, 333, 500, 549, 250, 549, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 278, 278, 549, 549, 549, 444, 549, 722, 667, 722, 612, 611,
+    763, 603, 722, 333, 631, 722, 686, 889, 722, 722, 768, 741, 556, 592, 611, 690, 439, 768, 645,
+    795, 611, 333, 863, 333, 658, 500, 500, 631, 549, 549, 494, 439, 521, 411, 603, 329, 603, 549,
+    549, 576, 521, 549, 549, 521, 549, 603, 439, 576, 713, 686, 493, 686, 494, 480, 200, 480, 549,
+];
+
+/// ZapfDingbats, by code in its built-in encoding
+const ZAPF_DINGBATS_WIDTHS: [u16; 95] = [
+    278, 974, 961, 974, 980, 719, 789, 790, 791, 690, 960, 939, 549, 855, 911, 933, 911, 945, 974,
+    755, 846, 762, 761, 571, 677, 763, 760, 759, 754, 494, 552, 537, 577, 692, 786, 788, 788, 790,
+    793, 794, 816, 823, 789, 841, 823, 833, 816, 831, 923, 744, 723, 749, 790, 792, 695, 776, 768,
+    792, 759, 707, 708, 682, 701, 826, 815, 789, 789, 707, 687, 696, 689, 786, 787, 713, 791, 785,
+    791, 873, 761, 762, 762, 759, 759, 892, 892, 788, 784, 438, 138, 277, 415, 392, 392, 668, 668,
+];
+
+/// Glyphs outside the ASCII range, in the order of the `*_EXTRA` tables
+const EXTRA_GLYPHS: [&str; 62] = [
+    "quotesingle",
+    "bullet",
+    "endash",
+    "emdash",
+    "quotedblleft",
+    "quotedblright",
+    "quotesinglbase",
+    "quotedblbase",
+    "ellipsis",
+    "dagger",
+    "daggerdbl",
+    "perthousand",
+    "guilsinglleft",
+    "guilsinglright",
+    "guillemotleft",
+    "guillemotright",
+    "trademark",
+    "copyright",
+    "registered",
+    "degree",
+    "exclamdown",
+    "questiondown",
+    "cent",
+    "sterling",
+    "yen",
+    "currency",
+    "brokenbar",
+    "section",
+    "ordfeminine",
+    "ordmasculine",
+    "logicalnot",
+    "plusminus",
+    "twosuperior",
+    "threesuperior",
+    "onesuperior",
+    "mu",
+    "paragraph",
+    "periodcentered",
+    "onequarter",
+    "onehalf",
+    "threequarters",
+    "multiply",
+    "divide",
+    "AE",
+    "ae",
+    "Oslash",
+    "oslash",
+    "germandbls",
+    "Eth",
+    "eth",
+    "Thorn",
+    "thorn",
+    "OE",
+    "oe",
+    "florin",
+    "fi",
+    "fl",
+    "Euro",
+    "Lslash",
+    "lslash",
+    "dotlessi",
+    "fraction",
+];
+
+const HELVETICA_EXTRA: [u16; 62] = [
+    191, 350, 556, 1000, 333, 333, 222, 333, 1000, 556, 556, 1000, 333, 333, 556, 556, 1000, 737,
+    737, 400, 333, 611, 556, 556, 556, 556, 260, 556, 370, 365, 584, 584, 333, 333, 333, 556, 537,
+    278, 834, 834, 834, 584, 584, 1000, 889, 778, 611, 611, 722, 556, 667, 556, 1000, 944, 556,
+    500, 500, 556, 556, 222, 278, 167,
+];
+
+const HELVETICA_BOLD_EXTRA: [u16; 62] = [
+    238, 350, 556, 1000, 500, 500, 278, 500, 1000, 556, 556, 1000, 333, 333, 556, 556, 1000, 737,
+    737, 400, 333, 611, 556, 556, 556, 556, 280, 556, 370, 365, 584, 584, 333, 333, 333, 611, 556,
+    278, 834, 834, 834, 584, 584, 1000, 889, 778, 611, 611, 722, 611, 667, 611, 1000, 944, 556,
+    611, 611, 556, 611, 278, 278, 167,
+];
+
+const TIMES_ROMAN_EXTRA: [u16; 62] = [
+    180, 350, 500, 1000, 444, 444, 333, 444, 1000, 500, 500, 1000, 333, 333, 500, 500, 980, 760,
+    760, 400, 333, 444, 500, 500, 500, 500, 200, 500, 276, 310, 564, 564, 300, 300, 300, 500, 453,
+    250, 750, 750, 750, 564, 564, 889, 667, 722, 500, 500, 722, 500, 556, 500, 889, 722, 500, 556,
+    556, 500, 611, 278, 278, 167,
+];
+
+const TIMES_BOLD_EXTRA: [u16; 62] = [
+    278, 350, 500, 1000, 500, 500, 333, 500, 1000, 500, 500, 1000, 333, 333, 500, 500, 1000, 747,
+    747, 400, 333, 500, 500, 500, 500, 500, 220, 500, 300, 330, 570, 570, 300, 300, 300, 556, 540,
+    250, 750, 750, 750, 570, 570, 1000, 722, 778, 500, 556, 722, 500, 611, 556, 1000, 722, 500,
+    556, 556, 500, 667, 278, 278, 167,
+];
+
+const TIMES_ITALIC_EXTRA: [u16; 62] = [
+    214, 350, 500, 889, 556, 556, 333, 556, 889, 500, 500, 1000, 333, 333, 500, 500, 980, 760, 760,
+    400, 389, 500, 500, 500, 500, 500, 275, 500, 276, 310, 675, 675, 300, 300, 300, 500, 523, 250,
+    750, 750, 750, 675, 675, 889, 667, 722, 500, 500, 722, 500, 611, 500, 944, 667, 500, 500, 500,
+    500, 556, 278, 278, 167,
+];
+
+const TIMES_BOLD_ITALIC_EXTRA: [u16; 62] = [
+    278, 350, 500, 1000, 500, 500, 333, 500, 1000, 500, 500, 1000, 333, 333, 500, 500, 1000, 747,
+    747, 400, 389, 500, 500, 500, 500, 500, 220, 500, 266, 300, 606, 570, 300, 300, 300, 576, 500,
+    250, 750, 750, 750, 570, 570, 944, 722, 722, 500, 500, 722, 500, 611, 500, 944, 722, 500, 556,
+    556, 500, 611, 278, 278, 167,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_helvetica_widths() {
+        let helvetica = standard_fonts::HELVETICA;
+        let standard = Encoding::new(Some(BaseEncoding::Standard));
+        let win_ansi = Encoding::new(Some(BaseEncoding::WinAnsi));
+        assert_eq!(standard_width(helvetica, &standard, b' '), Some(278));
+        assert_eq!(standard_width(helvetica, &standard, b'W'), Some(944));
+        // Accented letters take their base letter's width
+        assert_eq!(standard_width(helvetica, &win_ansi, 0xE9), Some(556));
+        assert_eq!(standard_width(helvetica, &win_ansi, 0xEF), Some(278));
+        assert_eq!(standard_width(helvetica, &win_ansi, 149), Some(350));
+        assert_eq!(
+            standard_width(standard_fonts::COURIER, &standard, b'W'),
+            Some(600)
+        );
+        assert_eq!(
+            standard_width(standard_fonts::SYMBOL, &standard, b'a'),
+            Some(631)
+        );
+        assert_eq!(standard_width(helvetica, &standard, 149), None);
+    }
+
+    #[test]
+    fn test_standard_font_name() {
+        assert_eq!(standard_font_name("Helvetica"), Some("Helvetica"));
+        assert_eq!(
+            standard_font_name("ABCDEF+Arial,Bold"),
+            Some("Helvetica-Bold")
+        );
+        assert_eq!(
+            standard_font_name("TimesNewRomanPS-BoldItalicMT"),
+            Some("Times-BoldItalic")
+        );
+        assert_eq!(standard_font_name("Times-Roman"), Some("Times-Roman"));
+        assert_eq!(
+            standard_font_name("Courier New,Italic"),
+            Some("Courier-Oblique")
+        );
+        assert_eq!(standard_font_name("Calibri"), None);
+
+        assert_eq!(substitute_name("Calibri-Bold"), "Helvetica-Bold");
+        assert_eq!(substitute_name("DejaVuSerif-Italic"), "Times-Italic");
+        assert_eq!(substitute_name("LiberationMono"), "Courier");
+    }
+
+    #[test]
+    fn test_glyph_unicode() {
+        assert_eq!(glyph_unicode("A"), Some('A'));
+        assert_eq!(glyph_unicode("quoteright"), Some('\u{2019}'));
+        assert_eq!(glyph_unicode("eacute"), Some('é'));
+        assert_eq!(glyph_unicode("bullet"), Some('\u{2022}'));
+        assert_eq!(glyph_unicode("uni20AC"), Some('€'));
+        assert_eq!(glyph_unicode("u1F600"), Some('\u{1F600}'));
+        assert_eq!(glyph_unicode("g41"), Some('A'));
+        assert_eq!(glyph_unicode("a.sc"), Some('a'));
+        assert_eq!(glyph_unicode("f_i"), Some('f'));
+        assert_eq!(glyph_unicode("g03"), None);
+        assert_eq!(glyph_unicode("a12"), None);
+    }
+
+    #[test]
+    fn test_encoding_differences() {
+        let doc =
+            Document::open_bytes(b"%PDF-1.7\ntrailer\n<< /Size 1 >>\n%%EOF\n".to_vec()).unwrap();
+        let font = crate::pdf::parser::parse_object(
+            b"<< /BaseFont /Helvetica /Encoding << /BaseEncoding /MacRomanEncoding \
+              /Differences [65 /bullet /uni00E9 200 /Omega] >> >>",
+        )
+        .unwrap();
+        let Object::Dict(font) = font else {
+            panic!("expected a dictionary");
+        };
+        let encoding = Encoding::load(&doc, &font).unwrap();
+        assert_eq!(encoding.unicode(65), Some('\u{2022}'));
+        assert_eq!(encoding.unicode(66), Some('é'));
+        assert_eq!(encoding.unicode(67), Some('C'));
+        assert_eq!(encoding.unicode(200), Some('\u{2126}'));
+        assert_eq!(encoding.unicode(0x8E), Some('é'));
+        assert_eq!(encoding.unicode(39), Some('\''));
+
+        // The Symbol font's built-in encoding is Greek
+        let symbol = Encoding::new(Some(BaseEncoding::Symbol));
+        assert_eq!(symbol.unicode(b'a'), Some('α'));
+    }
+}
@@ -5,7 +5,32 @@
 use crate::fitz::error::{Error, Result};
 use crate::fitz::geometry::Rect;
 use crate::pdf::annot::Annotation;
-use std::collections::HashMap;
+use crate::pdf::content::{self, FontMetrics, Operation};
+use crate::pdf::document::Document;
+use crate::pdf::geometry::rect_from_pdf_array;
+use crate::pdf::object::{Dict, Name, ObjRef, Object, PdfString};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Maximum nesting depth of the field tree
+const MAX_FIELD_DEPTH: usize = 64;
+
+/// Font size auto-sized multiline text starts from
+pub(crate) const AUTO_FONT_SIZE: f32 = 12.0;
+
+/// Smallest font size auto-sizing picks
+const MIN_AUTO_FONT_SIZE: f32 = 4.0;
+
+/// Inset of text appearances from the widget edges
+pub(crate) const TEXT_PADDING: f32 = 2.0;
+
+/// Baseline-to-baseline distance as a multiple of the font size
+pub(crate) const LINE_HEIGHT: f32 = 1.15;
+
+/// Glyph extent above the baseline, as a fraction of the font size
+pub(crate) const TEXT_ASCENT: f32 = 0.8;
+
+/// Average glyph width of standard fonts, whose widths are not in the file
+pub(crate) const STANDARD_GLYPH_WIDTH: f32 = 556.0;
 
 /// Widget/Field type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -186,6 +211,12 @@ pub struct FormField {
     pub selected_index: i32,
     /// Choice options (simpler representation for FFI)
     pub choices: Vec<(String, String)>,
+    /// The field dictionary, for fields loaded from a document
+    pub obj: Option<ObjRef>,
+    /// On appearance states of a checkbox or radio button's widgets
+    pub on_states: Vec<String>,
+    /// The value changed since the appearance was last generated
+    pub dirty: bool,
 }
 
 impl FormField {
@@ -215,6 +246,9 @@ impl FormField {
             multi_select: false,
             selected_index: -1,
             choices: Vec::new(),
+            obj: None,
+            on_states: Vec::new(),
+            dirty: false,
         }
     }
 
@@ -323,9 +357,9 @@ impl FormField {
                 self.value = value;
             }
             WidgetType::Checkbox | WidgetType::RadioButton => {
-                if value != "Yes" && value != "Off" {
+                if value != "Off" && !self.is_on_state(&value) {
                     return Err(Error::Argument(
-                        "Checkbox/radio button value must be 'Yes' or 'Off'".into(),
+                        "Checkbox/radio button value must be an on state or 'Off'".into(),
                     ));
                 }
                 self.value = value;
@@ -343,9 +377,20 @@ impl FormField {
                 self.value = value;
             }
         }
+        self.dirty = true;
         Ok(())
     }
 
+    /// Whether `state` turns a checkbox or radio button on: one of its
+    /// widgets' on states, or `Yes` when none are known
+    fn is_on_state(&self, state: &str) -> bool {
+        if self.on_states.is_empty() {
+            state == "Yes"
+        } else {
+            self.on_states.iter().any(|s| s == state)
+        }
+    }
+
     /// Get rectangle
     pub fn rect(&self) -> Rect {
         self.rect
@@ -466,7 +511,7 @@ impl FormField {
         matches!(
             self.field_type,
             WidgetType::Checkbox | WidgetType::RadioButton
-        ) && self.value == "Yes"
+        ) && self.is_on_state(&self.value)
     }
 
     /// Set checkbox/radio checked state
@@ -480,10 +525,14 @@ impl FormField {
             ));
         }
         self.value = if checked {
-            "Yes".to_string()
+            self.on_states
+                .first()
+                .cloned()
+                .unwrap_or_else(|| "Yes".to_string())
         } else {
             "Off".to_string()
         };
+        self.dirty = true;
         Ok(())
     }
 
@@ -639,6 +688,552 @@ impl std::fmt::Debug for Form {
     }
 }
 
+/// Field attributes inherited from ancestors in the field tree
+#[derive(Clone, Default)]
+struct Inherited {
+    name: String,
+    field_type: Option<String>,
+    flags: u32,
+    value: Option<Object>,
+    default_value: Option<Object>,
+}
+
+/// Load the fields of a document's /AcroForm, in /Fields order
+///
+/// Each terminal field is named by its fully-qualified name, joining the
+/// /T of its ancestors with periods. Checkbox and radio button values
+/// fall back to the /AS of a widget that is switched on.
+pub fn load_form(doc: &Document) -> Result<Form> {
+    let mut form = Form::new();
+    let catalog = doc.catalog()?;
+    let Some(Object::Dict(acroform)) = doc.resolve_key(&catalog, "AcroForm")? else {
+        return Ok(form);
+    };
+    let Some(Object::Array(fields)) = doc.resolve_key(&acroform, "Fields")? else {
+        return Ok(form);
+    };
+    let mut visited = HashSet::new();
+    for field in &fields {
+        load_field(
+            doc,
+            field,
+            &Inherited::default(),
+            &mut visited,
+            0,
+            &mut form,
+        )?;
+    }
+    Ok(form)
+}
+
+fn load_field(
+    doc: &Document,
+    obj: &Object,
+    parent: &Inherited,
+    visited: &mut HashSet<ObjRef>,
+    depth: usize,
+    form: &mut Form,
+) -> Result<()> {
+    if depth > MAX_FIELD_DEPTH {
+        return Ok(());
+    }
+    let obj_ref = obj.as_obj_ref();
+    if let Some(r) = obj_ref {
+        if !visited.insert(r) {
+            return Ok(());
+        }
+    }
+    let Object::Dict(dict) = doc.resolve(obj)? else {
+        return Ok(());
+    };
+
+    let mut inherited = parent.clone();
+    if let Some(Object::String(t)) = doc.resolve_key(&dict, "T")? {
+        let t = t.to_text();
+        inherited.name = if parent.name.is_empty() {
+            t
+        } else {
+            format!("{}.{}", parent.name, t)
+        };
+    }
+    if let Some(ft) = dict.get("FT").and_then(Object::as_name) {
+        inherited.field_type = Some(ft.as_str().to_string());
+    }
+    if let Some(ff) = doc.resolve_key(&dict, "Ff")?.and_then(|f| f.as_int()) {
+        inherited.flags = ff as u32;
+    }
+    if let Some(v) = doc.resolve_key(&dict, "V")? {
+        inherited.value = Some(v);
+    }
+    if let Some(dv) = doc.resolve_key(&dict, "DV")? {
+        inherited.default_value = Some(dv);
+    }
+
+    // Kids with a /T are fields; kids without one are this field's widgets
+    let kids = match doc.resolve_key(&dict, "Kids")? {
+        Some(Object::Array(kids)) => kids,
+        _ => Vec::new(),
+    };
+    let mut widgets = Vec::new();
+    for kid in &kids {
+        match doc.resolve(kid)? {
+            Object::Dict(kid_dict) if kid_dict.contains_key("T") => {
+                load_field(doc, kid, &inherited, visited, depth + 1, form)?;
+            }
+            Object::Dict(kid_dict) => widgets.push(kid_dict),
+            _ => {}
+        }
+    }
+    if widgets.is_empty() && !kids.is_empty() {
+        return Ok(());
+    }
+    if widgets.is_empty() {
+        widgets.push(dict.clone());
+    }
+
+    let flags = FieldFlags::new(inherited.flags);
+    let field_type = match inherited.field_type.as_deref() {
+        Some("Btn") if flags.has(FieldFlags::PUSHBUTTON) => WidgetType::Button,
+        Some("Btn") if flags.has(FieldFlags::RADIO) => WidgetType::RadioButton,
+        Some("Btn") => WidgetType::Checkbox,
+        Some("Ch") if flags.has(FieldFlags::COMBO) => WidgetType::ComboBox,
+        Some("Ch") => WidgetType::ListBox,
+        Some(ft) => WidgetType::from_string(ft),
+        None => WidgetType::Unknown,
+    };
+    let rect = widget_rect(doc, &widgets[0])?;
+
+    let mut field = FormField::new(inherited.name.clone(), field_type, rect);
+    field.flags = flags;
+    field.obj = obj_ref;
+    field.is_combo = field_type == WidgetType::ComboBox;
+    field.editable = flags.has(FieldFlags::EDIT);
+    field.multi_select = flags.has(FieldFlags::MULTI_SELECT);
+    field.value = inherited.value.as_ref().map(value_text).unwrap_or_default();
+    field.default_value = inherited
+        .default_value
+        .as_ref()
+        .map(value_text)
+        .unwrap_or_default();
+    if let Some(Object::String(tu)) = doc.resolve_key(&dict, "TU")? {
+        field.tooltip = Some(tu.to_text());
+    }
+    if let Some(max_len) = doc.resolve_key(&dict, "MaxLen")?.and_then(|m| m.as_int()) {
+        field.max_len = usize::try_from(max_len).ok();
+    }
+    if let Some(Object::Array(opts)) = doc.resolve_key(&dict, "Opt")? {
+        for opt in &opts {
+            let option = match doc.resolve(opt)? {
+                Object::Array(pair) if pair.len() == 2 => {
+                    ChoiceOption::new(value_text(&pair[1]), value_text(&pair[0]))
+                }
+                other => ChoiceOption::simple(value_text(&other)),
+            };
+            field
+                .choices
+                .push((option.label.clone(), option.value.clone()));
+            field.options.push(option);
+        }
+    }
+    if matches!(field_type, WidgetType::Checkbox | WidgetType::RadioButton) {
+        let mut current = None;
+        for widget in &widgets {
+            let states = on_states(doc, widget)?;
+            if let Some(Object::Name(state)) = widget.get("AS") {
+                if states.iter().any(|s| s == state.as_str()) {
+                    current = Some(state.as_str().to_string());
+                }
+            }
+            for state in states {
+                if !field.on_states.contains(&state) {
+                    field.on_states.push(state);
+                }
+            }
+        }
+        if inherited.value.is_none() {
+            field.value = current.unwrap_or_else(|| "Off".to_string());
+        }
+    }
+    form.add_field(field);
+    Ok(())
+}
+
+/// The text of a field value: a name, a text string, or the first of an
+/// array of selections
+fn value_text(value: &Object) -> String {
+    match value {
+        Object::Name(name) => name.as_str().to_string(),
+        Object::String(s) => s.to_text(),
+        Object::Array(items) => items.first().map(value_text).unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// Names of a widget's normal appearance states other than `Off`
+fn on_states(doc: &Document, widget: &Dict) -> Result<Vec<String>> {
+    let Some(Object::Dict(ap)) = doc.resolve_key(widget, "AP")? else {
+        return Ok(Vec::new());
+    };
+    let Some(Object::Dict(normal)) = doc.resolve_key(&ap, "N")? else {
+        return Ok(Vec::new());
+    };
+    let mut states: Vec<String> = normal
+        .keys()
+        .map(|k| k.as_str().to_string())
+        .filter(|k| k != "Off")
+        .collect();
+    states.sort();
+    Ok(states)
+}
+
+/// The objects to write to store a loaded field's value in its document:
+/// the field's /V and, for checkboxes and radio buttons, the /AS of each
+/// widget
+pub fn field_value_changes(doc: &Document, field: &FormField) -> Result<BTreeMap<ObjRef, Object>> {
+    let obj = field
+        .obj
+        .ok_or_else(|| Error::argument("field was not loaded from a document"))?;
+    let Object::Dict(mut dict) = doc.load_object(obj)? else {
+        return Err(Error::format("field is not a dictionary"));
+    };
+    let is_button = matches!(
+        field.field_type,
+        WidgetType::Checkbox | WidgetType::RadioButton
+    );
+    let value = if is_button {
+        Object::Name(Name::new(field.value()))
+    } else {
+        Object::String(PdfString::from_text(field.value()))
+    };
+    dict.insert(Name::new("V"), value);
+
+    let mut changes = BTreeMap::new();
+    if is_button {
+        let widgets: Vec<ObjRef> = match doc.resolve_key(&dict, "Kids")? {
+            Some(Object::Array(kids)) => kids.iter().filter_map(Object::as_obj_ref).collect(),
+            _ => Vec::new(),
+        };
+        if widgets.is_empty() {
+            set_appearance_state(doc, &mut dict, field.value())?;
+        }
+        for widget_ref in widgets {
+            if let Object::Dict(mut widget) = doc.load_object(widget_ref)? {
+                if widget.contains_key("T") {
+                    continue;
+                }
+                set_appearance_state(doc, &mut widget, field.value())?;
+                changes.insert(widget_ref, Object::Dict(widget));
+            }
+        }
+    }
+    changes.insert(obj, Object::Dict(dict));
+    Ok(changes)
+}
+
+/// Switch a widget to `state` if it has that appearance, otherwise off
+fn set_appearance_state(doc: &Document, widget: &mut Dict, state: &str) -> Result<()> {
+    let on = on_states(doc, widget)?.iter().any(|s| s == state);
+    let state = if on { state } else { "Off" };
+    widget.insert(Name::new("AS"), Object::Name(Name::new(state)));
+    Ok(())
+}
+
+/// A widget's /Rect, normalized
+fn widget_rect(doc: &Document, widget: &Dict) -> Result<Rect> {
+    Ok(doc
+        .resolve_key(widget, "Rect")?
+        .as_ref()
+        .and_then(rect_from_pdf_array)
+        .unwrap_or(Rect::EMPTY))
+}
+
+/// The widgets of a field: its kids without a /T, or the field itself when
+/// field and widget are merged
+fn field_widgets(doc: &Document, obj: ObjRef, dict: &Dict) -> Result<Vec<(ObjRef, Dict)>> {
+    let mut widgets = Vec::new();
+    if let Some(Object::Array(kids)) = doc.resolve_key(dict, "Kids")? {
+        for kid_ref in kids.iter().filter_map(Object::as_obj_ref) {
+            if let Object::Dict(kid) = doc.load_object(kid_ref)? {
+                if !kid.contains_key("T") {
+                    widgets.push((kid_ref, kid));
+                }
+            }
+        }
+    }
+    if widgets.is_empty() {
+        widgets.push((obj, dict.clone()));
+    }
+    Ok(widgets)
+}
+
+/// Look up an inheritable attribute on `dict` or its /Parent chain
+fn inherited_key(doc: &Document, dict: &Dict, key: &str) -> Result<Option<Object>> {
+    let mut current = dict.clone();
+    for _ in 0..MAX_FIELD_DEPTH {
+        if let Some(value) = doc.resolve_key(&current, key)? {
+            return Ok(Some(value));
+        }
+        match doc.resolve_key(&current, "Parent")? {
+            Some(Object::Dict(parent)) => current = parent,
+            _ => break,
+        }
+    }
+    Ok(None)
+}
+
+/// The objects to write to regenerate a loaded field's appearance
+///
+/// Text fields get a new `/Tx BMC ... EMC` normal appearance for each
+/// widget, laid out with the font, size and color of the /DA and the
+/// AcroForm /DR resources, and clipped to the widget rect. A /DA font size
+/// of 0 picks the largest size that fits. Checkboxes and radio buttons
+/// switch each widget between its on and off appearance states.
+pub fn field_appearance_changes(
+    doc: &Document,
+    field: &FormField,
+) -> Result<BTreeMap<ObjRef, Object>> {
+    let obj = field
+        .obj
+        .ok_or_else(|| Error::argument("field was not loaded from a document"))?;
+    let Object::Dict(dict) = doc.load_object(obj)? else {
+        return Err(Error::format("field is not a dictionary"));
+    };
+    let widgets = field_widgets(doc, obj, &dict)?;
+
+    let mut changes = BTreeMap::new();
+    match field.field_type {
+        WidgetType::Checkbox | WidgetType::RadioButton => {
+            for (widget_ref, mut widget) in widgets {
+                set_appearance_state(doc, &mut widget, field.value())?;
+                changes.insert(widget_ref, Object::Dict(widget));
+            }
+        }
+        WidgetType::Text => {
+            let acroform = match doc.resolve_key(&doc.catalog()?, "AcroForm")? {
+                Some(Object::Dict(d)) => d,
+                _ => Dict::new(),
+            };
+            let resources = match doc.resolve_key(&acroform, "DR")? {
+                Some(Object::Dict(d)) => d,
+                _ => Dict::new(),
+            };
+            let next_num = doc
+                .trailer()
+                .get("Size")
+                .and_then(Object::as_int)
+                .unwrap_or(1)
+                .max(1) as i32;
+
+            for (num, (widget_ref, mut widget)) in (next_num..).zip(widgets) {
+                let da = match inherited_key(doc, &widget, "DA")? {
+                    Some(da) => Some(da),
+                    None => doc.resolve_key(&acroform, "DA")?,
+                };
+                let da = da
+                    .as_ref()
+                    .and_then(Object::as_string)
+                    .map(|s| s.as_bytes().to_vec())
+                    .unwrap_or_default();
+                let quadding = inherited_key(doc, &widget, "Q")?
+                    .and_then(|q| q.as_int())
+                    .unwrap_or(0);
+                let rect = widget_rect(doc, &widget)?;
+                let data = text_appearance(doc, field, &da, &resources, rect, quadding)?;
+
+                let mut xobject = Dict::new();
+                xobject.insert(Name::new("Type"), Object::Name(Name::new("XObject")));
+                xobject.insert(Name::new("Subtype"), Object::Name(Name::new("Form")));
+                xobject.insert(
+                    Name::new("BBox"),
+                    Object::Array(vec![
+                        Object::Int(0),
+                        Object::Int(0),
+                        Object::Real(rect.width() as f64),
+                        Object::Real(rect.height() as f64),
+                    ]),
+                );
+                if !resources.is_empty() {
+                    xobject.insert(Name::new("Resources"), Object::Dict(resources.clone()));
+                }
+                let stream_ref = ObjRef::new(num, 0);
+                changes.insert(
+                    stream_ref,
+                    Object::Stream {
+                        dict: xobject,
+                        data,
+                    },
+                );
+
+                let mut ap = Dict::new();
+                ap.insert(Name::new("N"), Object::Ref(stream_ref));
+                widget.insert(Name::new("AP"), Object::Dict(ap));
+                changes.insert(widget_ref, Object::Dict(widget));
+            }
+        }
+        _ => return Err(Error::unsupported("appearance for this field type")),
+    }
+    Ok(changes)
+}
+
+/// Content of a text field appearance of size `rect`
+fn text_appearance(
+    doc: &Document,
+    field: &FormField,
+    da: &[u8],
+    resources: &Dict,
+    rect: Rect,
+    quadding: i64,
+) -> Result<Vec<u8>> {
+    let da_ops = content::parse_content(da).unwrap_or_default();
+    let (font, da_size) = da_ops
+        .iter()
+        .find(|op| op.operator == "Tf")
+        .and_then(|op| {
+            let font = op.operands.first()?.as_name()?.clone();
+            Some((font, op.operands.get(1)?.as_real()? as f32))
+        })
+        .unwrap_or_else(|| (Name::new("Helv"), 0.0));
+    let metrics = font_metrics(doc, resources, &font)?;
+    // Width of a string at font size 1
+    let measure = |s: &[u8]| metrics.codes(s).map(|c| metrics.width(c)).sum::<f32>() / 1000.0;
+
+    let text: Vec<u8> = if field.flags.has(FieldFlags::PASSWORD) {
+        vec![b'*'; field.value().chars().count()]
+    } else {
+        // Simple fonts take single-byte codes
+        field
+            .value()
+            .chars()
+            .map(|c| u8::try_from(c as u32).unwrap_or(b'?'))
+            .collect()
+    };
+    let width = (rect.width() - 2.0 * TEXT_PADDING).max(0.0);
+    let height = (rect.height() - 2.0 * TEXT_PADDING).max(0.0);
+    let multiline = field.flags.has(FieldFlags::MULTILINE);
+
+    let (size, lines) = if multiline {
+        let lines_at = |size: f32| wrap_text(&text, width / size, &measure);
+        if da_size > 0.0 {
+            (da_size, lines_at(da_size))
+        } else {
+            let mut size = AUTO_FONT_SIZE;
+            let mut lines = lines_at(size);
+            while size > MIN_AUTO_FONT_SIZE && lines.len() as f32 * size * LINE_HEIGHT > height {
+                size -= 1.0;
+                lines = lines_at(size);
+            }
+            (size, lines)
+        }
+    } else {
+        let size = if da_size > 0.0 {
+            da_size
+        } else {
+            let text_width = measure(&text);
+            let fit_width = if text_width > 0.0 {
+                width / text_width
+            } else {
+                f32::INFINITY
+            };
+            (height / LINE_HEIGHT)
+                .min(fit_width)
+                .max(MIN_AUTO_FONT_SIZE)
+        };
+        (size, vec![text])
+    };
+
+    let real = |v: f32| Object::Real(v as f64);
+    let mut ops = vec![
+        Operation::new("BMC", vec![Object::Name(Name::new("Tx"))]),
+        Operation::new("q", vec![]),
+        Operation::new(
+            "re",
+            vec![
+                real(1.0),
+                real(1.0),
+                real(rect.width() - 2.0),
+                real(rect.height() - 2.0),
+            ],
+        ),
+        Operation::new("W", vec![]),
+        Operation::new("n", vec![]),
+        Operation::new("BT", vec![]),
+    ];
+    ops.extend(da_ops.into_iter().filter(|op| op.operator != "Tf"));
+    ops.push(Operation::new("Tf", vec![Object::Name(font), real(size)]));
+    let mut y = if multiline {
+        rect.height() - TEXT_PADDING - TEXT_ASCENT * size
+    } else {
+        // Center the glyph box, which extends below the baseline
+        (rect.height() - size) / 2.0 + (1.0 - TEXT_ASCENT) * size
+    };
+    for line in lines {
+        let slack = width - measure(&line) * size;
+        let x = TEXT_PADDING
+            + match quadding {
+                1 => slack / 2.0,
+                2 => slack,
+                _ => 0.0,
+            };
+        ops.push(Operation::new(
+            "Tm",
+            vec![real(1.0), real(0.0), real(0.0), real(1.0), real(x), real(y)],
+        ));
+        ops.push(Operation::new(
+            "Tj",
+            vec![Object::String(PdfString::new(line))],
+        ));
+        y -= size * LINE_HEIGHT;
+    }
+    ops.push(Operation::new("ET", vec![]));
+    ops.push(Operation::new("Q", vec![]));
+    ops.push(Operation::new("EMC", vec![]));
+    Ok(content::write_content(&ops))
+}
+
+/// Metrics of a /DR font; fonts without widths in the file, such as the
+/// standard 14, are measured with an average glyph width
+fn font_metrics(doc: &Document, resources: &Dict, font: &Name) -> Result<FontMetrics> {
+    let font = match doc.resolve_key(resources, "Font")? {
+        Some(Object::Dict(fonts)) => doc.resolve_key(&fonts, font.as_str())?,
+        _ => None,
+    };
+    match font {
+        Some(Object::Dict(d)) if d.contains_key("Widths") || d.contains_key("DescendantFonts") => {
+            FontMetrics::load(doc, &d)
+        }
+        _ => Ok(FontMetrics::default().with_default_width(STANDARD_GLYPH_WIDTH)),
+    }
+}
+
+/// Break text into lines at most `max_width` wide, as measured by
+/// `measure`, at newlines and spaces; a word wider than a line is left on
+/// its own line to be clipped
+pub(crate) fn wrap_text(
+    text: &[u8],
+    max_width: f32,
+    measure: &impl Fn(&[u8]) -> f32,
+) -> Vec<Vec<u8>> {
+    let mut lines = Vec::new();
+    for paragraph in text.split(|&b| b == b'\n') {
+        let paragraph = paragraph.strip_suffix(b"\r").unwrap_or(paragraph);
+        let mut line: Vec<u8> = Vec::new();
+        for word in paragraph.split(|&b| b == b' ') {
+            let mut candidate = line.clone();
+            if !candidate.is_empty() {
+                candidate.push(b' ');
+            }
+            candidate.extend_from_slice(word);
+            if line.is_empty() || measure(&candidate) <= max_width {
+                line = candidate;
+            } else {
+                lines.push(std::mem::replace(&mut line, word.to_vec()));
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
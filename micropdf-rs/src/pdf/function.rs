@@ -0,0 +1,885 @@
+//! PDF functions
+//!
+//! Sampled (Type 0), exponential (Type 2), stitching (Type 3) and
+//! PostScript calculator (Type 4) functions, as used by tint transforms and
+//! shadings. Inputs are clipped to /Domain and outputs to /Range.
+
+use crate::fitz::error::{Error, Result};
+use crate::pdf::document::Document;
+use crate::pdf::object::{Dict, Object};
+
+/// Maximum nesting of stitching functions and calculator blocks
+const MAX_DEPTH: usize = 32;
+
+/// Operand stack limit of the PostScript calculator
+const MAX_STACK: usize = 100;
+
+/// Largest sample table accepted for a sampled function
+const MAX_SAMPLES: usize = 1 << 24;
+
+/// A parsed PDF function
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    /// Input bounds `[min0 max0 min1 max1 ...]`
+    pub domain: Vec<f32>,
+    /// Output bounds, required for sampled and calculator functions
+    pub range: Option<Vec<f32>>,
+    kind: FunctionKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FunctionKind {
+    Sampled {
+        size: Vec<usize>,
+        max_sample: f32,
+        encode: Vec<f32>,
+        decode: Vec<f32>,
+        samples: Vec<f32>,
+    },
+    Exponential {
+        c0: Vec<f32>,
+        c1: Vec<f32>,
+        n: f32,
+    },
+    Stitching {
+        functions: Vec<Function>,
+        bounds: Vec<f32>,
+        encode: Vec<f32>,
+    },
+    PostScript(Vec<PsOp>),
+}
+
+impl Function {
+    /// Load a function dictionary or stream
+    pub fn load(doc: &Document, obj: &Object) -> Result<Self> {
+        Self::load_depth(doc, obj, 0)
+    }
+
+    fn load_depth(doc: &Document, obj: &Object, depth: usize) -> Result<Self> {
+        if depth > MAX_DEPTH {
+            return Err(Error::limit("functions nested too deeply"));
+        }
+        let obj = doc.resolve(obj)?;
+        let dict = match &obj {
+            Object::Dict(d) => d,
+            Object::Stream { dict, .. } => dict,
+            _ => return Err(Error::format("function is not a dictionary")),
+        };
+        let domain = numbers(doc, dict, "Domain")?
+            .filter(|d| d.len() >= 2 && d.len() % 2 == 0)
+            .ok_or_else(|| Error::format("function without /Domain"))?;
+        let range = numbers(doc, dict, "Range")?.filter(|r| r.len() >= 2 && r.len() % 2 == 0);
+        let function_type = doc
+            .resolve_key(dict, "FunctionType")?
+            .and_then(|t| t.as_int())
+            .ok_or_else(|| Error::format("function without /FunctionType"))?;
+
+        let kind = match function_type {
+            0 => {
+                let range = range
+                    .as_ref()
+                    .ok_or_else(|| Error::format("sampled function without /Range"))?;
+                let size: Vec<usize> = numbers(doc, dict, "Size")?
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|&s| s as usize)
+                    .collect();
+                if size.len() != domain.len() / 2 || size.contains(&0) {
+                    return Err(Error::format("invalid sampled function /Size"));
+                }
+                let bits = doc
+                    .resolve_key(dict, "BitsPerSample")?
+                    .and_then(|b| b.as_int())
+                    .filter(|b| [1, 2, 4, 8, 12, 16, 24, 32].contains(b))
+                    .ok_or_else(|| Error::format("invalid /BitsPerSample"))?
+                    as u32;
+                let outputs = range.len() / 2;
+                let count = size
+                    .iter()
+                    .try_fold(outputs, |n, &s| n.checked_mul(s))
+                    .filter(|&n| n <= MAX_SAMPLES)
+                    .ok_or_else(|| Error::limit("sampled function too large"))?;
+                let encode = numbers(doc, dict, "Encode")?
+                    .filter(|e| e.len() == size.len() * 2)
+                    .unwrap_or_else(|| size.iter().flat_map(|&s| [0.0, (s - 1) as f32]).collect());
+                let decode = numbers(doc, dict, "Decode")?
+                    .filter(|d| d.len() == range.len())
+                    .unwrap_or_else(|| range.clone());
+                let data = doc.stream_data(&obj)?;
+                FunctionKind::Sampled {
+                    size,
+                    max_sample: ((1u64 << bits) - 1) as f32,
+                    encode,
+                    decode,
+                    samples: read_samples(&data, bits, count),
+                }
+            }
+            2 => {
+                let c0 = numbers(doc, dict, "C0")?.unwrap_or_else(|| vec![0.0]);
+                let c1 = numbers(doc, dict, "C1")?.unwrap_or_else(|| vec![1.0]);
+                if c0.len() != c1.len() {
+                    return Err(Error::format("/C0 and /C1 differ in length"));
+                }
+                let n = doc
+                    .resolve_key(dict, "N")?
+                    .and_then(|n| n.as_real())
+                    .ok_or_else(|| Error::format("exponential function without /N"))?
+                    as f32;
+                FunctionKind::Exponential { c0, c1, n }
+            }
+            3 => {
+                let Some(Object::Array(items)) = doc.resolve_key(dict, "Functions")? else {
+                    return Err(Error::format("stitching function without /Functions"));
+                };
+                let functions = items
+                    .iter()
+                    .map(|f| Self::load_depth(doc, f, depth + 1))
+                    .collect::<Result<Vec<_>>>()?;
+                let bounds = numbers(doc, dict, "Bounds")?.unwrap_or_default();
+                let encode = numbers(doc, dict, "Encode")?.unwrap_or_default();
+                if functions.is_empty()
+                    || bounds.len() + 1 != functions.len()
+                    || encode.len() != functions.len() * 2
+                {
+                    return Err(Error::format("invalid stitching function"));
+                }
+                FunctionKind::Stitching {
+                    functions,
+                    bounds,
+                    encode,
+                }
+            }
+            4 => {
+                if range.is_none() {
+                    return Err(Error::format("calculator function without /Range"));
+                }
+                FunctionKind::PostScript(parse_program(&doc.stream_data(&obj)?)?)
+            }
+            t => return Err(Error::unsupported(format!("function type {t}"))),
+        };
+        Ok(Self {
+            domain,
+            range,
+            kind,
+        })
+    }
+
+    /// Number of input values
+    pub fn inputs(&self) -> usize {
+        self.domain.len() / 2
+    }
+
+    /// Number of output values
+    pub fn outputs(&self) -> usize {
+        match (&self.range, &self.kind) {
+            (Some(range), _) => range.len() / 2,
+            (None, FunctionKind::Exponential { c0, .. }) => c0.len(),
+            (None, FunctionKind::Stitching { functions, .. }) => functions[0].outputs(),
+            (None, _) => 0,
+        }
+    }
+
+    /// Evaluate the function
+    ///
+    /// Missing inputs are taken as 0. Calculator programs that fail at run
+    /// time produce the low end of the range.
+    pub fn eval(&self, input: &[f32]) -> Vec<f32> {
+        let x: Vec<f32> = (0..self.inputs())
+            .map(|i| {
+                let v = input.get(i).copied().unwrap_or(0.0);
+                clamp(v, self.domain[2 * i], self.domain[2 * i + 1])
+            })
+            .collect();
+
+        let mut out = match &self.kind {
+            FunctionKind::Sampled {
+                size,
+                max_sample,
+                encode,
+                decode,
+                samples,
+            } => {
+                let outputs = decode.len() / 2;
+                let e: Vec<f32> = x
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &v)| {
+                        let e = interpolate(
+                            v,
+                            self.domain[2 * i],
+                            self.domain[2 * i + 1],
+                            encode[2 * i],
+                            encode[2 * i + 1],
+                        );
+                        clamp(e, 0.0, (size[i] - 1) as f32)
+                    })
+                    .collect();
+                (0..outputs)
+                    .map(|j| {
+                        let s = sample_multilinear(samples, size, outputs, j, &e);
+                        interpolate(s, 0.0, *max_sample, decode[2 * j], decode[2 * j + 1])
+                    })
+                    .collect()
+            }
+            FunctionKind::Exponential { c0, c1, n } => {
+                let xn = x[0].powf(*n);
+                c0.iter().zip(c1).map(|(a, b)| a + xn * (b - a)).collect()
+            }
+            FunctionKind::Stitching {
+                functions,
+                bounds,
+                encode,
+            } => {
+                let v = x[0];
+                let k = bounds.iter().position(|&b| v < b).unwrap_or(bounds.len());
+                let low = if k == 0 {
+                    self.domain[0]
+                } else {
+                    bounds[k - 1]
+                };
+                let high = bounds.get(k).copied().unwrap_or(self.domain[1]);
+                let t = if high > low {
+                    interpolate(v, low, high, encode[2 * k], encode[2 * k + 1])
+                } else {
+                    encode[2 * k]
+                };
+                functions[k].eval(&[t])
+            }
+            FunctionKind::PostScript(program) => {
+                let outputs = self.outputs();
+                let mut stack: Vec<PsValue> = x.iter().map(|&v| PsValue::Real(v as f64)).collect();
+                match run(program, &mut stack) {
+                    Ok(()) if stack.len() >= outputs => stack[stack.len() - outputs..]
+                        .iter()
+                        .map(|v| v.as_f64().unwrap_or(0.0) as f32)
+                        .collect(),
+                    _ => vec![f32::NEG_INFINITY; outputs],
+                }
+            }
+        };
+
+        if let Some(range) = &self.range {
+            for (j, v) in out.iter_mut().enumerate().take(range.len() / 2) {
+                *v = clamp(*v, range[2 * j], range[2 * j + 1]);
+            }
+        }
+        out
+    }
+}
+
+/// An array of numbers under `key`
+fn numbers(doc: &Document, dict: &Dict, key: &str) -> Result<Option<Vec<f32>>> {
+    match doc.resolve_key(dict, key)? {
+        Some(Object::Array(items)) => {
+            let mut out = Vec::with_capacity(items.len());
+            for item in &items {
+                let v = doc
+                    .resolve(item)?
+                    .as_real()
+                    .ok_or_else(|| Error::format(format!("non-numeric /{key}")))?;
+                out.push(v as f32);
+            }
+            Ok(Some(out))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn clamp(v: f32, min: f32, max: f32) -> f32 {
+    if v.is_nan() { min } else { v.max(min).min(max) }
+}
+
+fn interpolate(x: f32, x0: f32, x1: f32, y0: f32, y1: f32) -> f32 {
+    if x1 == x0 {
+        return y0;
+    }
+    y0 + (x - x0) * (y1 - y0) / (x1 - x0)
+}
+
+/// Unpack `count` big-endian samples of `bits` each
+fn read_samples(data: &[u8], bits: u32, count: usize) -> Vec<f32> {
+    let mut samples = Vec::with_capacity(count);
+    let mut acc: u64 = 0;
+    let mut held = 0u32;
+    let mut bytes = data.iter();
+    while samples.len() < count {
+        while held < bits {
+            acc = (acc << 8) | u64::from(*bytes.next().unwrap_or(&0));
+            held += 8;
+        }
+        held -= bits;
+        samples.push(((acc >> held) & ((1u64 << bits) - 1)) as f32);
+    }
+    samples
+}
+
+/// Output `j` of a sample table at fractional sample coordinates `e`
+fn sample_multilinear(samples: &[f32], size: &[usize], outputs: usize, j: usize, e: &[f32]) -> f32 {
+    let mut total = 0.0;
+    for corner in 0..1usize << e.len() {
+        let mut weight = 1.0;
+        let mut index = 0;
+        let mut stride = outputs;
+        for (i, &ei) in e.iter().enumerate() {
+            let lo = (ei.floor() as usize).min(size[i] - 1);
+            let frac = ei - lo as f32;
+            let (pos, w) = if corner & (1 << i) == 0 {
+                (lo, 1.0 - frac)
+            } else {
+                ((lo + 1).min(size[i] - 1), frac)
+            };
+            weight *= w;
+            index += pos * stride;
+            stride *= size[i];
+        }
+        if weight > 0.0 {
+            total += weight * samples.get(index + j).copied().unwrap_or(0.0);
+        }
+    }
+    total
+}
+
+// ============================================================================
+// PostScript calculator
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PsValue {
+    Int(i64),
+    Real(f64),
+    Bool(bool),
+}
+
+impl PsValue {
+    fn as_f64(self) -> Option<f64> {
+        match self {
+            PsValue::Int(i) => Some(i as f64),
+            PsValue::Real(r) => Some(r),
+            PsValue::Bool(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PsOp {
+    Push(PsValue),
+    Operator(PsOperator),
+    If(Vec<PsOp>),
+    IfElse(Vec<PsOp>, Vec<PsOp>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PsOperator {
+    Abs,
+    Add,
+    Atan,
+    Ceiling,
+    Cos,
+    Cvi,
+    Cvr,
+    Div,
+    Exp,
+    Floor,
+    Idiv,
+    Ln,
+    Log,
+    Mod,
+    Mul,
+    Neg,
+    Round,
+    Sin,
+    Sqrt,
+    Sub,
+    Truncate,
+    And,
+    Bitshift,
+    Eq,
+    Ge,
+    Gt,
+    Le,
+    Lt,
+    Ne,
+    Not,
+    Or,
+    Xor,
+    Copy,
+    Dup,
+    Exch,
+    Index,
+    Pop,
+    Roll,
+}
+
+impl PsOperator {
+    fn from_name(name: &str) -> Option<Self> {
+        use PsOperator::*;
+        Some(match name {
+            "abs" => Abs,
+            "add" => Add,
+            "atan" => Atan,
+            "ceiling" => Ceiling,
+            "cos" => Cos,
+            "cvi" => Cvi,
+            "cvr" => Cvr,
+            "div" => Div,
+            "exp" => Exp,
+            "floor" => Floor,
+            "idiv" => Idiv,
+            "ln" => Ln,
+            "log" => Log,
+            "mod" => Mod,
+            "mul" => Mul,
+            "neg" => Neg,
+            "round" => Round,
+            "sin" => Sin,
+            "sqrt" => Sqrt,
+            "sub" => Sub,
+            "truncate" => Truncate,
+            "and" => And,
+            "bitshift" => Bitshift,
+            "eq" => Eq,
+            "ge" => Ge,
+            "gt" => Gt,
+            "le" => Le,
+            "lt" => Lt,
+            "ne" => Ne,
+            "not" => Not,
+            "or" => Or,
+            "xor" => Xor,
+            "copy" => Copy,
+            "dup" => Dup,
+            "exch" => Exch,
+            "index" => Index,
+            "pop" => Pop,
+            "roll" => Roll,
+            _ => return None,
+        })
+    }
+}
+
+/// Parse a calculator program, `{ ... }`
+fn parse_program(data: &[u8]) -> Result<Vec<PsOp>> {
+    let mut tokens = tokenize(data).into_iter();
+    if tokens.next().as_deref() != Some("{") {
+        return Err(Error::format("calculator program does not start with {"));
+    }
+    parse_block(&mut tokens, 0)
+}
+
+fn tokenize(data: &[u8]) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            b'{' | b'}' => {
+                tokens.push((data[i] as char).to_string());
+                i += 1;
+            }
+            b'%' => {
+                while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            c if c.is_ascii_whitespace() => i += 1,
+            _ => {
+                let start = i;
+                while i < data.len()
+                    && !data[i].is_ascii_whitespace()
+                    && !matches!(data[i], b'{' | b'}' | b'%')
+                {
+                    i += 1;
+                }
+                tokens.push(String::from_utf8_lossy(&data[start..i]).into_owned());
+            }
+        }
+    }
+    tokens
+}
+
+/// Parse up to and including the closing brace of a block
+fn parse_block(tokens: &mut impl Iterator<Item = String>, depth: usize) -> Result<Vec<PsOp>> {
+    if depth > MAX_DEPTH {
+        return Err(Error::limit("calculator blocks nested too deeply"));
+    }
+    let mut ops = Vec::new();
+    // Blocks waiting for their `if` or `ifelse`
+    let mut pending: Vec<Vec<PsOp>> = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token.as_str() {
+            "}" => {
+                if !pending.is_empty() {
+                    return Err(Error::format("calculator block without if"));
+                }
+                return Ok(ops);
+            }
+            "{" => pending.push(parse_block(tokens, depth + 1)?),
+            "if" => {
+                let then = pending
+                    .pop()
+                    .filter(|_| pending.is_empty())
+                    .ok_or_else(|| Error::format("if without one block"))?;
+                ops.push(PsOp::If(then));
+            }
+            "ifelse" => {
+                let otherwise = pending.pop();
+                let then = pending.pop();
+                match (then, otherwise) {
+                    (Some(then), Some(otherwise)) if pending.is_empty() => {
+                        ops.push(PsOp::IfElse(then, otherwise));
+                    }
+                    _ => return Err(Error::format("ifelse without two blocks")),
+                }
+            }
+            "true" => ops.push(PsOp::Push(PsValue::Bool(true))),
+            "false" => ops.push(PsOp::Push(PsValue::Bool(false))),
+            word => {
+                if !pending.is_empty() {
+                    return Err(Error::format("calculator block without if"));
+                }
+                if let Some(op) = PsOperator::from_name(word) {
+                    ops.push(PsOp::Operator(op));
+                } else if let Ok(i) = word.parse::<i64>() {
+                    ops.push(PsOp::Push(PsValue::Int(i)));
+                } else if let Ok(r) = word.parse::<f64>() {
+                    ops.push(PsOp::Push(PsValue::Real(r)));
+                } else {
+                    return Err(Error::format(format!("unknown calculator operator {word}")));
+                }
+            }
+        }
+    }
+    Err(Error::format("unterminated calculator block"))
+}
+
+fn run(program: &[PsOp], stack: &mut Vec<PsValue>) -> Result<()> {
+    for op in program {
+        match op {
+            PsOp::Push(v) => push(stack, *v)?,
+            PsOp::If(then) => {
+                if pop_bool(stack)? {
+                    run(then, stack)?;
+                }
+            }
+            PsOp::IfElse(then, otherwise) => {
+                if pop_bool(stack)? {
+                    run(then, stack)?;
+                } else {
+                    run(otherwise, stack)?;
+                }
+            }
+            PsOp::Operator(op) => apply(*op, stack)?,
+        }
+    }
+    Ok(())
+}
+
+fn push(stack: &mut Vec<PsValue>, v: PsValue) -> Result<()> {
+    if stack.len() >= MAX_STACK {
+        return Err(Error::limit("calculator stack overflow"));
+    }
+    stack.push(v);
+    Ok(())
+}
+
+fn pop(stack: &mut Vec<PsValue>) -> Result<PsValue> {
+    stack
+        .pop()
+        .ok_or_else(|| Error::format("calculator stack underflow"))
+}
+
+fn pop_num(stack: &mut Vec<PsValue>) -> Result<f64> {
+    pop(stack)?
+        .as_f64()
+        .ok_or_else(|| Error::format("calculator type check"))
+}
+
+fn pop_int(stack: &mut Vec<PsValue>) -> Result<i64> {
+    match pop(stack)? {
+        PsValue::Int(i) => Ok(i),
+        _ => Err(Error::format("calculator type check")),
+    }
+}
+
+fn pop_bool(stack: &mut Vec<PsValue>) -> Result<bool> {
+    match pop(stack)? {
+        PsValue::Bool(b) => Ok(b),
+        _ => Err(Error::format("calculator type check")),
+    }
+}
+
+/// Integer result when both operands are integers and it fits, otherwise
+/// real
+fn arith(
+    stack: &mut Vec<PsValue>,
+    int: fn(i64, i64) -> Option<i64>,
+    real: fn(f64, f64) -> f64,
+) -> Result<()> {
+    let b = pop(stack)?;
+    let a = pop(stack)?;
+    let v = match (a, b) {
+        (PsValue::Int(a), PsValue::Int(b)) => match int(a, b) {
+            Some(v) => PsValue::Int(v),
+            None => PsValue::Real(real(a as f64, b as f64)),
+        },
+        (a, b) => match (a.as_f64(), b.as_f64()) {
+            (Some(a), Some(b)) => PsValue::Real(real(a, b)),
+            _ => return Err(Error::format("calculator type check")),
+        },
+    };
+    push(stack, v)
+}
+
+/// Real result of a one-operand function
+fn unary(stack: &mut Vec<PsValue>, f: fn(f64) -> f64) -> Result<()> {
+    let v = pop_num(stack)?;
+    push(stack, PsValue::Real(f(v)))
+}
+
+/// Rounding that keeps integers as integers
+fn rounding(stack: &mut Vec<PsValue>, f: fn(f64) -> f64) -> Result<()> {
+    match pop(stack)? {
+        PsValue::Int(i) => push(stack, PsValue::Int(i)),
+        PsValue::Real(r) => push(stack, PsValue::Real(f(r))),
+        PsValue::Bool(_) => Err(Error::format("calculator type check")),
+    }
+}
+
+fn compare(stack: &mut Vec<PsValue>, f: fn(f64, f64) -> bool) -> Result<()> {
+    let b = pop_num(stack)?;
+    let a = pop_num(stack)?;
+    push(stack, PsValue::Bool(f(a, b)))
+}
+
+fn logical(
+    stack: &mut Vec<PsValue>,
+    b: fn(bool, bool) -> bool,
+    i: fn(i64, i64) -> i64,
+) -> Result<()> {
+    let y = pop(stack)?;
+    let x = pop(stack)?;
+    let v = match (x, y) {
+        (PsValue::Bool(x), PsValue::Bool(y)) => PsValue::Bool(b(x, y)),
+        (PsValue::Int(x), PsValue::Int(y)) => PsValue::Int(i(x, y)),
+        _ => return Err(Error::format("calculator type check")),
+    };
+    push(stack, v)
+}
+
+fn apply(op: PsOperator, stack: &mut Vec<PsValue>) -> Result<()> {
+    use PsOperator::*;
+    match op {
+        Abs => match pop(stack)? {
+            PsValue::Int(i) => push(
+                stack,
+                i.checked_abs()
+                    .map_or(PsValue::Real((i as f64).abs()), PsValue::Int),
+            ),
+            PsValue::Real(r) => push(stack, PsValue::Real(r.abs())),
+            PsValue::Bool(_) => Err(Error::format("calculator type check")),
+        },
+        Neg => match pop(stack)? {
+            PsValue::Int(i) => push(
+                stack,
+                i.checked_neg()
+                    .map_or(PsValue::Real(-(i as f64)), PsValue::Int),
+            ),
+            PsValue::Real(r) => push(stack, PsValue::Real(-r)),
+            PsValue::Bool(_) => Err(Error::format("calculator type check")),
+        },
+        Add => arith(stack, i64::checked_add, |a, b| a + b),
+        Sub => arith(stack, i64::checked_sub, |a, b| a - b),
+        Mul => arith(stack, i64::checked_mul, |a, b| a * b),
+        Div => {
+            let b = pop_num(stack)?;
+            let a = pop_num(stack)?;
+            if b == 0.0 {
+                return Err(Error::format("calculator division by zero"));
+            }
+            push(stack, PsValue::Real(a / b))
+        }
+        Idiv | Mod => {
+            let b = pop_int(stack)?;
+            let a = pop_int(stack)?;
+            let v = if op == Idiv {
+                a.checked_div(b)
+            } else {
+                a.checked_rem(b)
+            };
+            push(
+                stack,
+                PsValue::Int(v.ok_or_else(|| Error::format("calculator division by zero"))?),
+            )
+        }
+        Atan => {
+            let den = pop_num(stack)?;
+            let num = pop_num(stack)?;
+            let angle = num.atan2(den).to_degrees();
+            push(
+                stack,
+                PsValue::Real(if angle < 0.0 { angle + 360.0 } else { angle }),
+            )
+        }
+        Cos => unary(stack, |d| d.to_radians().cos()),
+        Sin => unary(stack, |d| d.to_radians().sin()),
+        Sqrt => unary(stack, f64::sqrt),
+        Ln => unary(stack, f64::ln),
+        Log => unary(stack, f64::log10),
+        Exp => {
+            let exponent = pop_num(stack)?;
+            let base = pop_num(stack)?;
+            push(stack, PsValue::Real(base.powf(exponent)))
+        }
+        Ceiling => rounding(stack, f64::ceil),
+        Floor => rounding(stack, f64::floor),
+        Round => rounding(stack, |r| (r + 0.5).floor()),
+        Truncate => rounding(stack, f64::trunc),
+        Cvi => {
+            let v = pop_num(stack)?;
+            push(stack, PsValue::Int(v.trunc() as i64))
+        }
+        Cvr => {
+            let v = pop_num(stack)?;
+            push(stack, PsValue::Real(v))
+        }
+        Eq | Ne => {
+            let b = pop(stack)?;
+            let a = pop(stack)?;
+            let equal = match (a.as_f64(), b.as_f64()) {
+                (Some(a), Some(b)) => a == b,
+                _ => a == b,
+            };
+            push(stack, PsValue::Bool(equal == (op == Eq)))
+        }
+        Ge => compare(stack, |a, b| a >= b),
+        Gt => compare(stack, |a, b| a > b),
+        Le => compare(stack, |a, b| a <= b),
+        Lt => compare(stack, |a, b| a < b),
+        And => logical(stack, |a, b| a && b, |a, b| a & b),
+        Or => logical(stack, |a, b| a || b, |a, b| a | b),
+        Xor => logical(stack, |a, b| a ^ b, |a, b| a ^ b),
+        Not => match pop(stack)? {
+            PsValue::Bool(b) => push(stack, PsValue::Bool(!b)),
+            PsValue::Int(i) => push(stack, PsValue::Int(!i)),
+            PsValue::Real(_) => Err(Error::format("calculator type check")),
+        },
+        Bitshift => {
+            let shift = pop_int(stack)?;
+            let v = pop_int(stack)?;
+            let shifted = match shift {
+                s if s <= -64 || s >= 64 => 0,
+                s if s >= 0 => v << s,
+                s => v >> -s,
+            };
+            push(stack, PsValue::Int(shifted))
+        }
+        Dup => {
+            let v = *stack
+                .last()
+                .ok_or_else(|| Error::format("calculator stack underflow"))?;
+            push(stack, v)
+        }
+        Exch => {
+            let b = pop(stack)?;
+            let a = pop(stack)?;
+            stack.push(b);
+            stack.push(a);
+            Ok(())
+        }
+        Pop => pop(stack).map(drop),
+        Copy => {
+            let n = usize::try_from(pop_int(stack)?)
+                .ok()
+                .filter(|&n| n <= stack.len())
+                .ok_or_else(|| Error::format("calculator range check"))?;
+            for i in stack.len() - n..stack.len() {
+                push(stack, stack[i])?;
+            }
+            Ok(())
+        }
+        Index => {
+            let n = usize::try_from(pop_int(stack)?)
+                .ok()
+                .filter(|&n| n < stack.len())
+                .ok_or_else(|| Error::format("calculator range check"))?;
+            push(stack, stack[stack.len() - 1 - n])
+        }
+        Roll => {
+            let j = pop_int(stack)?;
+            let n = usize::try_from(pop_int(stack)?)
+                .ok()
+                .filter(|&n| n <= stack.len())
+                .ok_or_else(|| Error::format("calculator range check"))?;
+            if n > 0 {
+                let start = stack.len() - n;
+                let shift = j.rem_euclid(n as i64) as usize;
+                stack[start..].rotate_right(shift);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A document whose object 1 is the given function
+    fn function_doc(dict: &str, stream: Option<&str>) -> Document {
+        let body = match stream {
+            Some(data) => format!(
+                "<< {dict} /Length {} >>\nstream\n{data}\nendstream",
+                data.len()
+            ),
+            None => format!("<< {dict} >>"),
+        };
+        let pdf = format!("%PDF-1.7\n1 0 obj\n{body}\nendobj\ntrailer\n<< /Size 2 >>\n%%EOF\n");
+        Document::open_bytes(pdf.into_bytes()).unwrap()
+    }
+
+    fn load(doc: &Document) -> Function {
+        Function::load(doc, &Object::Ref(crate::pdf::object::ObjRef::new(1, 0))).unwrap()
+    }
+
+    #[test]
+    fn test_calculator_function() {
+        // CMYK from a single tint, with a branch and stack operators
+        let doc = function_doc(
+            "/FunctionType 4 /Domain [0 1] /Range [0 1 0 1 0 1 0 1]",
+            Some("{ dup 0.5 gt { 0.2 mul } { 2 div } ifelse 0 1 index 0 4 1 roll 4 -1 roll }"),
+        );
+        let f = load(&doc);
+        assert_eq!((f.inputs(), f.outputs()), (1, 4));
+        assert_eq!(f.eval(&[1.0]), vec![0.2, 0.0, 0.2, 0.0]);
+        assert_eq!(f.eval(&[0.4]), vec![0.2, 0.0, 0.2, 0.0]);
+        // Out of domain input is clipped
+        assert_eq!(f.eval(&[2.0]), vec![0.2, 0.0, 0.2, 0.0]);
+
+        let bad = function_doc(
+            "/FunctionType 4 /Domain [0 1] /Range [0 1]",
+            Some("{ pop pop }"),
+        );
+        assert_eq!(load(&bad).eval(&[0.5]), vec![0.0]);
+    }
+
+    #[test]
+    fn test_stitching_and_sampled_functions() {
+        let doc = function_doc(
+            "/FunctionType 3 /Domain [0 1] /Bounds [0.5] /Encode [0 1 1 0] \
+             /Functions [<< /FunctionType 2 /Domain [0 1] /C0 [0] /C1 [1] /N 1 >> \
+             << /FunctionType 2 /Domain [0 1] /C0 [0] /C1 [1] /N 1 >>]",
+            None,
+        );
+        let f = load(&doc);
+        assert_eq!(f.eval(&[0.25]), vec![0.5]);
+        assert_eq!(f.eval(&[0.75]), vec![0.5]);
+
+        // Two 8-bit samples, 0 and 255, interpolated linearly
+        let sampled = function_doc(
+            "/FunctionType 0 /Domain [0 1] /Range [0 1] /Size [2] /BitsPerSample 8",
+            Some("\u{0}\u{7f}"),
+        );
+        let f = load(&sampled);
+        assert!((f.eval(&[1.0])[0] - 127.0 / 255.0).abs() < 1e-6);
+        assert!((f.eval(&[0.5])[0] - 63.5 / 255.0).abs() < 1e-6);
+    }
+}
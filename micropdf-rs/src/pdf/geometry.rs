@@ -0,0 +1,122 @@
+//! Geometry stored as PDF number arrays
+//!
+//! Rectangles (`/Rect`, `/BBox`, `/MediaBox`), matrices (`/Matrix`) and
+//! quadrilaterals (`/QuadPoints`) are plain arrays of numbers in a PDF
+//! file. These helpers convert them to and from [`fitz`](crate::fitz)
+//! geometry. Readers return `None` for arrays that are too short or hold
+//! anything other than direct numbers.
+
+use crate::fitz::geometry::{Matrix, Point, Quad, Rect};
+use crate::pdf::object::Object;
+
+/// The first `N` entries of an array of numbers
+fn numbers<const N: usize>(obj: &Object) -> Option<[f32; N]> {
+    let arr = obj.as_array()?;
+    let mut out = [0.0; N];
+    for (value, item) in out.iter_mut().zip(arr.get(..N)?) {
+        *value = item.as_real()? as f32;
+    }
+    Some(out)
+}
+
+fn real_array(values: impl IntoIterator<Item = f32>) -> Object {
+    Object::Array(
+        values
+            .into_iter()
+            .map(|v| Object::Real(f64::from(v)))
+            .collect(),
+    )
+}
+
+/// Read a `[x0 y0 x1 y1]` array as a rectangle, normalized so that
+/// opposite corners given in any order make the same rectangle
+pub fn rect_from_pdf_array(obj: &Object) -> Option<Rect> {
+    let [x0, y0, x1, y1] = numbers(obj)?;
+    Some(Rect::new(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)))
+}
+
+/// A rectangle as a `[x0 y0 x1 y1]` array
+pub fn rect_to_pdf_array(rect: &Rect) -> Object {
+    real_array([rect.x0, rect.y0, rect.x1, rect.y1])
+}
+
+/// Read a `[a b c d e f]` array as a matrix
+pub fn matrix_from_pdf_array(obj: &Object) -> Option<Matrix> {
+    let [a, b, c, d, e, f] = numbers(obj)?;
+    Some(Matrix::new(a, b, c, d, e, f))
+}
+
+/// A matrix as a `[a b c d e f]` array
+pub fn matrix_to_pdf_array(m: &Matrix) -> Object {
+    real_array([m.a, m.b, m.c, m.d, m.e, m.f])
+}
+
+/// Read a /QuadPoints array, eight numbers per quad in the order upper
+/// left, upper right, lower left, lower right, as Acrobat writes them
+///
+/// Numbers left over after the last whole quad are ignored.
+pub fn quad_points_from_pdf_array(obj: &Object) -> Option<Vec<Quad>> {
+    let values = obj
+        .as_array()?
+        .iter()
+        .map(|item| item.as_real().map(|v| v as f32))
+        .collect::<Option<Vec<_>>>()?;
+    Some(
+        values
+            .chunks_exact(8)
+            .map(|q| Quad {
+                ul: Point::new(q[0], q[1]),
+                ur: Point::new(q[2], q[3]),
+                ll: Point::new(q[4], q[5]),
+                lr: Point::new(q[6], q[7]),
+            })
+            .collect(),
+    )
+}
+
+/// Quads as a /QuadPoints array
+pub fn quad_points_to_pdf_array(quads: &[Quad]) -> Object {
+    real_array(
+        quads
+            .iter()
+            .flat_map(|q| [q.ul, q.ur, q.ll, q.lr])
+            .flat_map(|p| [p.x, p.y]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rect_round_trip() {
+        let media_box = Object::Array([0, 0, 612, 792].map(Object::Int).to_vec());
+        let rect = rect_from_pdf_array(&media_box).unwrap();
+        assert_eq!(rect, Rect::new(0.0, 0.0, 612.0, 792.0));
+        assert_eq!(rect_from_pdf_array(&rect_to_pdf_array(&rect)), Some(rect));
+
+        // Corners in the other order make the same rectangle
+        let swapped = Object::Array([612, 792, 0, 0].map(Object::Int).to_vec());
+        assert_eq!(rect_from_pdf_array(&swapped), Some(rect));
+
+        assert_eq!(
+            rect_from_pdf_array(&Object::Array(vec![Object::Int(1)])),
+            None
+        );
+        assert_eq!(rect_from_pdf_array(&Object::Int(1)), None);
+    }
+
+    #[test]
+    fn test_matrix_and_quad_round_trip() {
+        let m = Matrix::new(2.0, 0.0, 0.0, 2.0, 10.0, 20.5);
+        assert_eq!(matrix_from_pdf_array(&matrix_to_pdf_array(&m)), Some(m));
+
+        let quads = vec![
+            Quad::from_rect(&Rect::new(0.0, 0.0, 50.0, 12.0)),
+            Quad::from_rect(&Rect::new(10.0, 20.0, 30.0, 40.0)),
+        ];
+        let array = quad_points_to_pdf_array(&quads);
+        assert_eq!(array.as_array().map(Vec::len), Some(16));
+        assert_eq!(quad_points_from_pdf_array(&array), Some(quads));
+    }
+}
@@ -15,11 +15,29 @@
 //! - Transparency groups
 
 use crate::fitz::colorspace::Colorspace;
-use crate::fitz::device::Device;
-use crate::fitz::geometry::{Matrix, Point};
+use crate::fitz::cookie::Cookie;
+use crate::fitz::device::{BlendMode, Device};
+use crate::fitz::font::{Font, standard_fonts};
+use crate::fitz::geometry::{Matrix, Point, Rect};
+use crate::fitz::image::{Image, ImageFormat};
 use crate::fitz::path::{LineCap, LineJoin, Path};
+use crate::fitz::text::{BidiDirection, Text, TextLanguage};
+use crate::pdf::content::{self, ExtGState, FontMetrics, SoftMask, TilingPattern, Type3Font};
+use crate::pdf::filter::{FilterChain, FilterType};
 use crate::pdf::lexer::{LexBuf, Lexer, Token};
-use crate::pdf::object::{Dict, Object};
+use crate::pdf::object::{Dict, Name, Object, PdfString};
+use crate::pdf::ocg::HiddenContent;
+use crate::pdf::trace::{OperatorCategory, RenderTrace, TraceHooks};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Content stream rendering options
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Record per-operator counts and timings, see [`Interpreter::trace`]
+    pub trace: bool,
+}
 
 /// PDF graphics state
 #[derive(Debug, Clone)]
@@ -49,6 +67,9 @@ pub struct GraphicsState {
     /// Current fill color components
     pub fill_color: Vec<f32>,
 
+    /// Pattern resource that fills paint with, set by `scn` with a name
+    pub fill_pattern: Option<Name>,
+
     /// Current stroke colorspace
     pub stroke_colorspace: Colorspace,
 
@@ -91,6 +112,9 @@ pub struct GraphicsState {
 
     /// Current text rise
     pub text_rise: f32,
+
+    /// Clips pushed to the device since this state was saved
+    pub clip_depth: usize,
 }
 
 impl Default for GraphicsState {
@@ -105,6 +129,7 @@ impl Default for GraphicsState {
             dash_phase: 0.0,
             fill_colorspace: Colorspace::device_gray(),
             fill_color: vec![0.0],
+            fill_pattern: None,
             stroke_colorspace: Colorspace::device_gray(),
             stroke_color: vec![0.0],
             fill_alpha: 1.0,
@@ -120,6 +145,7 @@ impl Default for GraphicsState {
             leading: 0.0,
             text_render_mode: 0,
             text_rise: 0.0,
+            clip_depth: 0,
         }
     }
 }
@@ -135,8 +161,46 @@ pub struct Interpreter {
     /// Current point in path
     current_point: Option<Point>,
 
+    /// Fill rule of a `W` or `W*` waiting for the next painting operator;
+    /// true for even-odd
+    pending_clip: Option<bool>,
+
     /// Resource dictionary
     resources: Option<Dict>,
+
+    /// Glyph widths of the fonts in the resource dictionary
+    font_metrics: HashMap<Name, FontMetrics>,
+
+    /// Parameters of the ExtGState resources
+    ext_gstates: HashMap<Name, ExtGState>,
+
+    /// Tiling patterns of the Pattern resources
+    patterns: HashMap<Name, TilingPattern>,
+
+    /// Type 3 fonts of the Font resources
+    type3_fonts: HashMap<Name, Type3Font>,
+
+    /// The initial transformation, which pattern space maps to
+    base_ctm: Matrix,
+
+    /// Whether color operators are ignored, inside an uncolored pattern
+    uncolored: bool,
+
+    /// Optional content hidden by the current layer state
+    hidden: HiddenContent,
+
+    /// Open marked-content sequences; true for those hiding their content
+    marked_content: Vec<bool>,
+
+    /// Operator timings, when enabled by [`RenderOptions::trace`]
+    trace: Option<RenderTrace>,
+
+    /// Caller-supplied tracing callbacks
+    hooks: Option<Box<dyn TraceHooks>>,
+
+    /// Cancellation cookie checked before each operator, and counting
+    /// one step for each
+    cookie: Option<Cookie>,
 }
 
 impl Interpreter {
@@ -146,8 +210,44 @@ impl Interpreter {
             state_stack: vec![GraphicsState::default()],
             current_path: None,
             current_point: None,
+            pending_clip: None,
             resources: None,
+            font_metrics: HashMap::new(),
+            ext_gstates: HashMap::new(),
+            patterns: HashMap::new(),
+            type3_fonts: HashMap::new(),
+            base_ctm: Matrix::IDENTITY,
+            uncolored: false,
+            hidden: HiddenContent::default(),
+            marked_content: Vec::new(),
+            trace: None,
+            hooks: None,
+            cookie: None,
+        }
+    }
+
+    /// Create an interpreter with rendering options
+    pub fn with_options(options: RenderOptions) -> Self {
+        let mut interp = Self::new();
+        if options.trace {
+            interp.trace = Some(RenderTrace::new());
         }
+        interp
+    }
+
+    /// Set callbacks to run after each operator
+    pub fn set_trace_hooks(&mut self, hooks: Box<dyn TraceHooks>) {
+        self.hooks = Some(hooks);
+    }
+
+    /// Operator counts and timings recorded so far, if tracing is enabled
+    pub fn trace(&self) -> Option<&RenderTrace> {
+        self.trace.as_ref()
+    }
+
+    /// Take the recorded trace, leaving an empty one in its place
+    pub fn take_trace(&mut self) -> Option<RenderTrace> {
+        self.trace.as_mut().map(std::mem::take)
     }
 
     /// Set the resource dictionary
@@ -155,6 +255,55 @@ impl Interpreter {
         self.resources = Some(resources);
     }
 
+    /// Set glyph widths used to advance the text position, keyed by font
+    /// resource name; fonts without metrics use a width of 1000
+    pub fn set_font_metrics(&mut self, metrics: HashMap<Name, FontMetrics>) {
+        self.font_metrics = metrics;
+    }
+
+    /// Set the graphics states applied by `gs`, keyed by ExtGState
+    /// resource name, see [`ExtGState::load_resources`]
+    pub fn set_ext_gstates(&mut self, states: HashMap<Name, ExtGState>) {
+        self.ext_gstates = states;
+    }
+
+    /// Set the tiling patterns that fills can paint with, keyed by
+    /// Pattern resource name, see [`TilingPattern::load_resources`]
+    pub fn set_patterns(&mut self, patterns: HashMap<Name, TilingPattern>) {
+        self.patterns = patterns;
+    }
+
+    /// Set the Type 3 fonts whose glyph procedures shown text runs,
+    /// keyed by Font resource name, see [`Type3Font::load_resources`]
+    pub fn set_type3_fonts(&mut self, fonts: HashMap<Name, Type3Font>) {
+        self.type3_fonts = fonts;
+    }
+
+    /// Set a cookie whose abort flag stops interpretation between operators
+    ///
+    /// Each operator, including those of forms, patterns and Type 3
+    /// glyphs, is one step of the cookie's step budget.
+    pub fn set_cookie(&mut self, cookie: Cookie) {
+        self.cookie = Some(cookie);
+    }
+
+    /// Set the initial transformation, mapping user space to device space
+    pub fn set_ctm(&mut self, ctm: Matrix) {
+        self.base_ctm = ctm;
+        self.state_mut().ctm = ctm;
+    }
+
+    /// Set the optional content to skip, see
+    /// [`OptionalContent::hidden_content`](crate::pdf::ocg::OptionalContent::hidden_content)
+    pub fn set_hidden_content(&mut self, hidden: HiddenContent) {
+        self.hidden = hidden;
+    }
+
+    /// Whether painting is suppressed by a hidden marked-content sequence
+    fn content_hidden(&self) -> bool {
+        self.marked_content.iter().any(|&hidden| hidden)
+    }
+
     /// Get the current graphics state
     fn state(&self) -> &GraphicsState {
         self.state_stack.last().unwrap()
@@ -167,19 +316,51 @@ impl Interpreter {
 
     /// Push a new graphics state (q operator)
     fn push_state(&mut self) {
-        let current = self.state().clone();
+        let mut current = self.state().clone();
+        current.clip_depth = 0;
         self.state_stack.push(current);
     }
 
-    /// Pop a graphics state (Q operator)
-    fn pop_state(&mut self) {
+    /// Pop a graphics state (Q operator), popping the clips set within it
+    fn pop_state<D: Device + ?Sized>(&mut self, device: &mut D) {
         if self.state_stack.len() > 1 {
-            self.state_stack.pop();
+            let state = self.state_stack.pop().unwrap();
+            for _ in 0..state.clip_depth {
+                device.pop_clip();
+            }
+        }
+    }
+
+    /// Pop every clip still pushed to the device
+    fn pop_all_clips<D: Device + ?Sized>(&mut self, device: &mut D) {
+        for state in &mut self.state_stack {
+            for _ in 0..std::mem::take(&mut state.clip_depth) {
+                device.pop_clip();
+            }
         }
+        self.pending_clip = None;
     }
 
     /// Interpret a content stream and call device methods
-    pub fn interpret<D: Device>(&mut self, stream: &[u8], device: &mut D) -> Result<(), String> {
+    ///
+    /// Clips set by the stream are popped from the device before returning,
+    /// even when interpretation fails.
+    pub fn interpret<D: Device + ?Sized>(
+        &mut self,
+        stream: &[u8],
+        device: &mut D,
+    ) -> Result<(), String> {
+        let result = self.interpret_operators(stream, device);
+        self.pop_all_clips(device);
+        result
+    }
+
+    /// Lex a content stream and process its operators
+    fn interpret_operators<D: Device + ?Sized>(
+        &mut self,
+        stream: &[u8],
+        device: &mut D,
+    ) -> Result<(), String> {
         let mut lexer = Lexer::new(stream);
         let mut buf = LexBuf::new();
         let mut operands: Vec<Object> = Vec::new();
@@ -188,9 +369,28 @@ impl Interpreter {
             match lexer.lex(&mut buf) {
                 Ok(Token::Eof) => break,
                 Ok(Token::Keyword) => {
+                    if let Some(cookie) = &self.cookie {
+                        cookie.step();
+                        if cookie.should_abort() {
+                            cookie.set_incomplete(true);
+                            return Err("aborted".into());
+                        }
+                    }
                     // Process the operator with accumulated operands
-                    let op = buf.as_str();
-                    self.process_operator(op, &operands, device)?;
+                    let op = if buf.as_str() == "BI" {
+                        operands = self.parse_inline_image(&mut lexer, &mut buf)?;
+                        "BI"
+                    } else {
+                        buf.as_str()
+                    };
+                    if self.trace.is_some() || self.hooks.is_some() {
+                        let start = Instant::now();
+                        let result = self.process_operator(op, &operands, device);
+                        self.record_operator(op, start);
+                        result?;
+                    } else {
+                        self.process_operator(op, &operands, device)?;
+                    }
                     operands.clear();
                 }
                 Ok(Token::Int) => {
@@ -236,6 +436,18 @@ impl Interpreter {
         Ok(())
     }
 
+    /// Pass an operator's run time to the trace and hooks
+    fn record_operator(&mut self, op: &str, start: Instant) {
+        let elapsed = start.elapsed();
+        let category = OperatorCategory::of(op);
+        if let Some(trace) = &mut self.trace {
+            trace.operator(category, op, elapsed);
+        }
+        if let Some(hooks) = &mut self.hooks {
+            hooks.operator(category, op, elapsed);
+        }
+    }
+
     /// Parse an array from the token stream
     fn parse_array(&self, lexer: &mut Lexer, buf: &mut LexBuf) -> Result<Vec<Object>, String> {
         let mut array = Vec::new();
@@ -316,24 +528,139 @@ impl Interpreter {
         Ok(dict)
     }
 
+    /// Parse an inline image after its `BI`, up to and including `EI`
+    ///
+    /// The binary image data cannot be lexed, so its end is found from the
+    /// dictionary or by scanning for `EI`. Returns the dictionary and data
+    /// as the operands of the `BI` operator.
+    fn parse_inline_image(
+        &self,
+        lexer: &mut Lexer,
+        buf: &mut LexBuf,
+    ) -> Result<Vec<Object>, String> {
+        let mut dict = Dict::new();
+
+        loop {
+            match lexer.lex(buf) {
+                Ok(Token::Keyword) if buf.as_str() == "ID" => break,
+                Ok(Token::Name) => {
+                    let key = Name::new(buf.as_str());
+                    let value = match lexer.lex(buf) {
+                        Ok(Token::Int) => Object::Int(buf.as_int()),
+                        Ok(Token::Real) => Object::Real(buf.as_float()),
+                        Ok(Token::Name) => Object::Name(Name::new(buf.as_str())),
+                        Ok(Token::String) => Object::String(PdfString::new(buf.as_bytes())),
+                        Ok(Token::True) => Object::Bool(true),
+                        Ok(Token::False) => Object::Bool(false),
+                        Ok(Token::Null) => Object::Null,
+                        Ok(Token::OpenArray) => Object::Array(self.parse_array(lexer, buf)?),
+                        Ok(Token::OpenDict) => Object::Dict(self.parse_dict(lexer, buf)?),
+                        _ => {
+                            return Err(format!(
+                                "Invalid value for inline image key '{}'",
+                                key.as_str()
+                            ));
+                        }
+                    };
+                    dict.insert(key, value);
+                }
+                Ok(Token::Eof) => {
+                    return Err("Unexpected end of stream in inline image".to_string());
+                }
+                Err(e) => return Err(format!("Error parsing inline image: {}", e)),
+                _ => return Err("Invalid inline image dictionary".to_string()),
+            }
+        }
+
+        let data = lexer.data();
+        // A single whitespace byte separates ID from the image data
+        let start = (lexer.pos() + 1).min(data.len());
+        let (end, ei) = content::inline_image_end(data, start, &dict)
+            .ok_or_else(|| "Inline image without EI".to_string())?;
+        lexer.seek(ei + 2);
+
+        Ok(vec![
+            Object::Dict(dict),
+            Object::String(PdfString::new(data[start..end].to_vec())),
+        ])
+    }
+
     /// Process a single PDF operator
-    fn process_operator<D: Device>(
+    fn process_operator<D: Device + ?Sized>(
         &mut self,
         op: &str,
         operands: &[Object],
         device: &mut D,
     ) -> Result<(), String> {
+        // A clip set by W or W* takes effect after the path is painted
+        let clip = match op {
+            "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" | "n" => self
+                .pending_clip
+                .take()
+                .and_then(|even_odd| Some((self.current_path.clone()?, even_odd))),
+            _ => None,
+        };
+        // Painting in a blend mode other than Normal goes through a group
+        // that the device composites with that mode
+        let blend = match op {
+            "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" | "BI" | "sh" | "Do" | "Tj"
+            | "TJ" | "'" | "\"" => BlendMode::from_name(&self.state().blend_mode)
+                .filter(|&mode| mode != BlendMode::Normal),
+            _ => None,
+        };
+        if let Some(mode) = blend {
+            device.begin_group(Rect::INFINITE, None, false, false, mode, 1.0);
+        }
+        let result = self.paint_operator(op, operands, device);
+        if blend.is_some() {
+            device.end_group();
+        }
+        if let Some((path, even_odd)) = clip {
+            device.clip_path(&path, even_odd, &self.state().ctm, Rect::INFINITE);
+            self.state_mut().clip_depth += 1;
+        }
+        result
+    }
+
+    /// Dispatch a single PDF operator
+    fn paint_operator<D: Device + ?Sized>(
+        &mut self,
+        op: &str,
+        operands: &[Object],
+        device: &mut D,
+    ) -> Result<(), String> {
+        if self.content_hidden() {
+            match op {
+                // Hidden paths are constructed but not painted
+                "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" => {
+                    self.op_end_path();
+                    return Ok(());
+                }
+                "BI" | "sh" => return Ok(()),
+                _ => {}
+            }
+        }
+
+        if self.uncolored
+            && matches!(
+                op,
+                "CS" | "cs" | "SC" | "SCN" | "sc" | "scn" | "G" | "g" | "RG" | "rg" | "K" | "k"
+            )
+        {
+            return Ok(());
+        }
+
         match op {
             // Graphics state operators
             "q" => self.op_save_state(),
-            "Q" => self.op_restore_state(),
+            "Q" => self.op_restore_state(device),
             "cm" => self.op_concat_matrix(operands)?,
             "w" => self.op_set_line_width(operands)?,
             "J" => self.op_set_line_cap(operands)?,
             "j" => self.op_set_line_join(operands)?,
             "M" => self.op_set_miter_limit(operands)?,
             "d" => self.op_set_dash(operands)?,
-            "gs" => self.op_set_gstate(operands)?,
+            "gs" => self.op_set_gstate(operands, device)?,
 
             // Path construction operators
             "m" => self.op_move_to(operands)?,
@@ -400,9 +727,7 @@ impl Interpreter {
             "Do" => self.op_paint_xobject(operands, device)?,
 
             // Image operators
-            "BI" => self.op_begin_inline_image(operands)?,
-            "ID" => self.op_inline_image_data(operands)?,
-            "EI" => self.op_end_inline_image(device)?,
+            "BI" => self.op_inline_image(operands, device)?,
 
             // Marked content operators
             "MP" => self.op_marked_content_point(operands)?,
@@ -412,6 +737,10 @@ impl Interpreter {
             "EMC" => self.op_end_marked_content()?,
 
             // Compatibility operators
+            // Type 3 glyph width operators; d1 glyphs take the fill color
+            "d0" => {}
+            "d1" => self.uncolored = true,
+
             "BX" => self.op_begin_compat(),
             "EX" => self.op_end_compat(),
 
@@ -435,8 +764,8 @@ impl Interpreter {
         self.push_state();
     }
 
-    fn op_restore_state(&mut self) {
-        self.pop_state();
+    fn op_restore_state<D: Device + ?Sized>(&mut self, device: &mut D) {
+        self.pop_state(device);
     }
 
     fn op_concat_matrix(&mut self, operands: &[Object]) -> Result<(), String> {
@@ -524,9 +853,56 @@ impl Interpreter {
         Ok(())
     }
 
-    fn op_set_gstate(&mut self, _operands: &[Object]) -> Result<(), String> {
-        // TODO: Look up graphics state from ExtGState resource dictionary
-        Ok(())
+    fn op_set_gstate<D: Device + ?Sized>(
+        &mut self,
+        operands: &[Object],
+        device: &mut D,
+    ) -> Result<(), String> {
+        let Some(Object::Name(name)) = operands.first() else {
+            return Err("gs operator requires a name".to_string());
+        };
+        let Some(params) = self.ext_gstates.get(name).cloned() else {
+            return Ok(());
+        };
+        // TODO: Apply the other ExtGState parameters
+        if let Some(mode) = params.blend_mode {
+            self.state_mut().blend_mode = mode.name().to_string();
+        }
+        match params.soft_mask {
+            Some(Some(mask)) => self.apply_soft_mask(&mask, device),
+            _ => Ok(()),
+        }
+    }
+
+    /// Render a soft mask's group and push it to the device
+    ///
+    /// The mask stays in effect until the graphics state that set it is
+    /// restored, like a clip; `/SMask /None` does not lift it earlier.
+    fn apply_soft_mask<D: Device + ?Sized>(
+        &mut self,
+        mask: &SoftMask,
+        device: &mut D,
+    ) -> Result<(), String> {
+        let ctm = mask.matrix.concat(&self.state().ctm);
+        let area = mask.bbox.transform(&ctm);
+        let mut bbox = Path::new();
+        bbox.rect(mask.bbox);
+
+        let mut group = Interpreter::new();
+        group.set_ctm(ctm);
+        if let Some(resources) = mask.resources.as_ref().or(self.resources.as_ref()) {
+            group.set_resources(resources.clone());
+        }
+        group.font_metrics = self.font_metrics.clone();
+        group.cookie = self.cookie.clone();
+
+        device.begin_mask(area, mask.luminosity, &mask.colorspace, &mask.backdrop);
+        device.clip_path(&bbox, false, &ctm, area);
+        let result = group.interpret(&mask.contents, device);
+        device.pop_clip();
+        device.end_mask();
+        self.state_mut().clip_depth += 1;
+        result
     }
 
     // ========================================================================
@@ -671,177 +1047,170 @@ impl Interpreter {
     // Path Painting Operators
     // ========================================================================
 
-    fn op_stroke<D: Device>(&mut self, device: &mut D) -> Result<(), String> {
+    fn op_stroke<D: Device + ?Sized>(&mut self, device: &mut D) -> Result<(), String> {
         if let Some(path) = self.current_path.take() {
-            let state = self.state();
-
-            // Create stroke state
-            let line_cap = line_cap_from_i32(state.line_cap);
-            let stroke_state = crate::fitz::path::StrokeState {
-                linewidth: state.line_width,
-                miterlimit: state.miter_limit,
-                start_cap: line_cap,
-                dash_cap: line_cap,
-                end_cap: line_cap,
-                linejoin: line_join_from_i32(state.line_join),
-                dash_phase: state.dash_phase,
-                dash_pattern: state.dash_pattern.clone(),
-            };
-
-            // Call device stroke method
-            device.stroke_path(
-                &path,
-                &stroke_state,
-                &state.ctm,
-                &state.stroke_colorspace,
-                &state.stroke_color,
-                state.stroke_alpha,
-            );
+            self.stroke(&path, device);
         }
 
         self.current_point = None;
         Ok(())
     }
 
-    fn op_close_and_stroke<D: Device>(&mut self, device: &mut D) -> Result<(), String> {
+    fn op_close_and_stroke<D: Device + ?Sized>(&mut self, device: &mut D) -> Result<(), String> {
         self.op_close_path();
         self.op_stroke(device)
     }
 
-    fn op_fill<D: Device>(&mut self, device: &mut D) -> Result<(), String> {
-        if let Some(path) = self.current_path.take() {
-            let state = self.state();
-
-            // Call device fill method (non-zero winding rule)
-            device.fill_path(
-                &path,
-                false, // even_odd = false (non-zero winding)
-                &state.ctm,
-                &state.fill_colorspace,
-                &state.fill_color,
-                state.fill_alpha,
-            );
-        }
-
-        self.current_point = None;
-        Ok(())
+    fn op_fill<D: Device + ?Sized>(&mut self, device: &mut D) -> Result<(), String> {
+        self.fill_and_stroke(false, false, device)
     }
 
-    fn op_fill_even_odd<D: Device>(&mut self, device: &mut D) -> Result<(), String> {
-        if let Some(path) = self.current_path.take() {
-            let state = self.state();
-
-            // Call device fill method (even-odd rule)
-            device.fill_path(
-                &path,
-                true, // even_odd = true
-                &state.ctm,
-                &state.fill_colorspace,
-                &state.fill_color,
-                state.fill_alpha,
-            );
-        }
+    fn op_fill_even_odd<D: Device + ?Sized>(&mut self, device: &mut D) -> Result<(), String> {
+        self.fill_and_stroke(true, false, device)
+    }
 
-        self.current_point = None;
-        Ok(())
+    fn op_fill_and_stroke<D: Device + ?Sized>(&mut self, device: &mut D) -> Result<(), String> {
+        self.fill_and_stroke(false, true, device)
     }
 
-    fn op_fill_and_stroke<D: Device>(&mut self, device: &mut D) -> Result<(), String> {
-        // Need to clone path since both operations consume it
-        if let Some(ref path) = self.current_path {
-            let state = self.state();
+    fn op_fill_and_stroke_even_odd<D: Device + ?Sized>(
+        &mut self,
+        device: &mut D,
+    ) -> Result<(), String> {
+        self.fill_and_stroke(true, true, device)
+    }
 
-            // Fill
-            device.fill_path(
-                path,
-                false,
-                &state.ctm,
-                &state.fill_colorspace,
-                &state.fill_color,
-                state.fill_alpha,
-            );
+    fn op_close_fill_and_stroke<D: Device + ?Sized>(
+        &mut self,
+        device: &mut D,
+    ) -> Result<(), String> {
+        self.op_close_path();
+        self.op_fill_and_stroke(device)
+    }
 
-            // Stroke
-            let line_cap = line_cap_from_i32(state.line_cap);
-            let stroke_state = crate::fitz::path::StrokeState {
-                linewidth: state.line_width,
-                miterlimit: state.miter_limit,
-                start_cap: line_cap,
-                dash_cap: line_cap,
-                end_cap: line_cap,
-                linejoin: line_join_from_i32(state.line_join),
-                dash_phase: state.dash_phase,
-                dash_pattern: state.dash_pattern.clone(),
-            };
+    fn op_close_fill_and_stroke_even_odd<D: Device + ?Sized>(
+        &mut self,
+        device: &mut D,
+    ) -> Result<(), String> {
+        self.op_close_path();
+        self.op_fill_and_stroke_even_odd(device)
+    }
 
-            device.stroke_path(
-                path,
-                &stroke_state,
-                &state.ctm,
-                &state.stroke_colorspace,
-                &state.stroke_color,
-                state.stroke_alpha,
-            );
-        }
+    /// Fill the current path, then stroke it if `stroke` is set
+    fn fill_and_stroke<D: Device + ?Sized>(
+        &mut self,
+        even_odd: bool,
+        stroke: bool,
+        device: &mut D,
+    ) -> Result<(), String> {
+        let result = match self.current_path.take() {
+            Some(path) => {
+                let result = self.fill(&path, even_odd, device);
+                if stroke {
+                    self.stroke(&path, device);
+                }
+                result
+            }
+            None => Ok(()),
+        };
 
-        self.current_path = None;
         self.current_point = None;
-        Ok(())
+        result
     }
 
-    fn op_fill_and_stroke_even_odd<D: Device>(&mut self, device: &mut D) -> Result<(), String> {
-        if let Some(ref path) = self.current_path {
-            let state = self.state();
-
-            // Fill with even-odd
-            device.fill_path(
-                path,
-                true,
-                &state.ctm,
-                &state.fill_colorspace,
-                &state.fill_color,
-                state.fill_alpha,
-            );
-
-            // Stroke
-            let line_cap = line_cap_from_i32(state.line_cap);
-            let stroke_state = crate::fitz::path::StrokeState {
-                linewidth: state.line_width,
-                miterlimit: state.miter_limit,
-                start_cap: line_cap,
-                dash_cap: line_cap,
-                end_cap: line_cap,
-                linejoin: line_join_from_i32(state.line_join),
-                dash_phase: state.dash_phase,
-                dash_pattern: state.dash_pattern.clone(),
-            };
-
-            device.stroke_path(
-                path,
-                &stroke_state,
-                &state.ctm,
-                &state.stroke_colorspace,
-                &state.stroke_color,
-                state.stroke_alpha,
-            );
+    /// Fill a path with the current fill color or pattern
+    fn fill<D: Device + ?Sized>(
+        &mut self,
+        path: &Path,
+        even_odd: bool,
+        device: &mut D,
+    ) -> Result<(), String> {
+        let state = self.state();
+        let pattern = state
+            .fill_pattern
+            .as_ref()
+            .and_then(|name| self.patterns.get(name));
+        if let Some(pattern) = pattern.cloned() {
+            let ctm = state.ctm;
+            device.clip_path(path, even_odd, &ctm, Rect::INFINITE);
+            let result = self.fill_pattern(&pattern, path.bounds().transform(&ctm), device);
+            device.pop_clip();
+            return result;
         }
 
-        self.current_path = None;
-        self.current_point = None;
+        device.fill_path(
+            path,
+            even_odd,
+            &state.ctm,
+            &state.fill_colorspace,
+            &state.fill_color,
+            state.fill_alpha,
+        );
         Ok(())
     }
 
-    fn op_close_fill_and_stroke<D: Device>(&mut self, device: &mut D) -> Result<(), String> {
-        self.op_close_path();
-        self.op_fill_and_stroke(device)
-    }
-
-    fn op_close_fill_and_stroke_even_odd<D: Device>(
+    /// Draw a tiling pattern's cell and repeat it over `area`, in device
+    /// space
+    fn fill_pattern<D: Device + ?Sized>(
         &mut self,
+        pattern: &TilingPattern,
+        area: Rect,
         device: &mut D,
     ) -> Result<(), String> {
-        self.op_close_path();
-        self.op_fill_and_stroke_even_odd(device)
+        // Pattern space maps to the page's default space, not the CTM
+        let ctm = pattern.matrix.concat(&self.base_ctm);
+        let mut cell = Interpreter::new();
+        cell.set_ctm(ctm);
+        if let Some(resources) = pattern.resources.as_ref().or(self.resources.as_ref()) {
+            cell.set_resources(resources.clone());
+        }
+        cell.font_metrics = self.font_metrics.clone();
+        cell.cookie = self.cookie.clone();
+        if !pattern.colored {
+            // Uncolored cells paint in the fill color and ignore their own
+            let state = self.state();
+            let (colorspace, color) = (state.fill_colorspace.clone(), state.fill_color.clone());
+            let cell_state = cell.state_mut();
+            cell_state.stroke_colorspace = colorspace.clone();
+            cell_state.stroke_color = color.clone();
+            cell_state.fill_colorspace = colorspace;
+            cell_state.fill_color = color;
+            cell.uncolored = true;
+        }
+
+        let mut bbox = Path::new();
+        bbox.rect(pattern.bbox);
+        device.begin_tile(area, pattern.bbox, pattern.xstep, pattern.ystep, &ctm);
+        device.clip_path(&bbox, false, &ctm, Rect::INFINITE);
+        let result = cell.interpret(&pattern.contents, device);
+        device.pop_clip();
+        device.end_tile();
+        result
+    }
+
+    /// Stroke a path with the current stroke state and color
+    fn stroke<D: Device + ?Sized>(&self, path: &Path, device: &mut D) {
+        let state = self.state();
+        let line_cap = line_cap_from_i32(state.line_cap);
+        let stroke_state = crate::fitz::path::StrokeState {
+            linewidth: state.line_width,
+            miterlimit: state.miter_limit,
+            start_cap: line_cap,
+            dash_cap: line_cap,
+            end_cap: line_cap,
+            linejoin: line_join_from_i32(state.line_join),
+            dash_phase: state.dash_phase,
+            dash_pattern: state.dash_pattern.clone(),
+        };
+
+        device.stroke_path(
+            path,
+            &stroke_state,
+            &state.ctm,
+            &state.stroke_colorspace,
+            &state.stroke_color,
+            state.stroke_alpha,
+        );
     }
 
     fn op_end_path(&mut self) {
@@ -854,11 +1223,11 @@ impl Interpreter {
     // ========================================================================
 
     fn op_clip(&mut self) {
-        // TODO: Implement clipping with current path (non-zero winding)
+        self.pending_clip = Some(false);
     }
 
     fn op_clip_even_odd(&mut self) {
-        // TODO: Implement clipping with current path (even-odd rule)
+        self.pending_clip = Some(true);
     }
 
     // ========================================================================
@@ -870,8 +1239,20 @@ impl Interpreter {
         Ok(())
     }
 
-    fn op_set_fill_colorspace(&mut self, _operands: &[Object]) -> Result<(), String> {
-        // TODO: Set fill colorspace from resource dictionary
+    fn op_set_fill_colorspace(&mut self, operands: &[Object]) -> Result<(), String> {
+        let state = self.state_mut();
+        state.fill_pattern = None;
+        // TODO: Set other fill colorspaces from the resource dictionary
+        let (colorspace, color) = match operands.first().and_then(Object::as_name) {
+            Some(name) if name.as_str() == "DeviceGray" => (Colorspace::device_gray(), vec![0.0]),
+            Some(name) if name.as_str() == "DeviceRGB" => (Colorspace::device_rgb(), vec![0.0; 3]),
+            Some(name) if name.as_str() == "DeviceCMYK" => {
+                (Colorspace::device_cmyk(), vec![0.0, 0.0, 0.0, 1.0])
+            }
+            _ => return Ok(()),
+        };
+        state.fill_colorspace = colorspace;
+        state.fill_color = color;
         Ok(())
     }
 
@@ -893,9 +1274,21 @@ impl Interpreter {
             .iter()
             .filter_map(|obj| get_f32(obj).ok())
             .collect();
+        let pattern = operands.last().and_then(Object::as_name).cloned();
 
+        let state = self.state_mut();
+        if pattern.is_some() {
+            // Components before the name color an uncolored pattern
+            match color.len() {
+                1 => state.fill_colorspace = Colorspace::device_gray(),
+                3 => state.fill_colorspace = Colorspace::device_rgb(),
+                4 => state.fill_colorspace = Colorspace::device_cmyk(),
+                _ => {}
+            }
+            state.fill_pattern = pattern;
+        }
         if !color.is_empty() {
-            self.state_mut().fill_color = color;
+            state.fill_color = color;
         }
 
         Ok(())
@@ -922,6 +1315,7 @@ impl Interpreter {
         let gray = get_f32(&operands[0])?;
         let state = self.state_mut();
         state.fill_colorspace = Colorspace::device_gray();
+        state.fill_pattern = None;
         state.fill_color = vec![gray];
 
         Ok(())
@@ -954,6 +1348,7 @@ impl Interpreter {
 
         let state = self.state_mut();
         state.fill_colorspace = Colorspace::device_rgb();
+        state.fill_pattern = None;
         state.fill_color = vec![r, g, b];
 
         Ok(())
@@ -988,6 +1383,7 @@ impl Interpreter {
 
         let state = self.state_mut();
         state.fill_colorspace = Colorspace::device_cmyk();
+        state.fill_pattern = None;
         state.fill_color = vec![c, m, y, k];
 
         Ok(())
@@ -1155,33 +1551,27 @@ impl Interpreter {
     // Text Showing Operators
     // ========================================================================
 
-    fn op_show_text<D: Device>(
+    fn op_show_text<D: Device + ?Sized>(
         &mut self,
         operands: &[Object],
-        _device: &mut D,
+        device: &mut D,
     ) -> Result<(), String> {
         if operands.len() != 1 {
             return Err("Tj operator requires 1 operand".to_string());
         }
 
-        let _text_str = match &operands[0] {
-            Object::String(s) => s.as_str(),
+        let bytes = match &operands[0] {
+            Object::String(s) => s.as_bytes(),
             _ => return Err("Invalid text string".to_string()),
         };
 
-        // TODO: Full text rendering requires:
-        // 1. Font loading from resources
-        // 2. Glyph lookup and positioning
-        // 3. Creating Text object with TextSpan
-        // 4. Calling device.fill_text() or device.stroke_text()
-        //
-        // For now, we successfully parse the text operator but don't render.
-        // This will be implemented in Phase 2 (Fonts & Text).
-
-        Ok(())
+        let mut text = Text::new();
+        self.show_string(&mut text, bytes);
+        self.paint_text(&text, device);
+        self.paint_type3_glyphs(&text, device)
     }
 
-    fn op_show_text_adjusted<D: Device>(
+    fn op_show_text_adjusted<D: Device + ?Sized>(
         &mut self,
         operands: &[Object],
         device: &mut D,
@@ -1195,18 +1585,169 @@ impl Interpreter {
             _ => return Err("Invalid text array".to_string()),
         };
 
+        let mut text = Text::new();
         for item in array {
             if let Object::String(s) = item {
-                let _ = self.op_show_text(&[Object::String(s.clone())], device);
-            } else if let Ok(_offset) = get_f32(item) {
-                // TODO: Adjust text position based on offset
+                self.show_string(&mut text, s.as_bytes());
+            } else if let Ok(offset) = get_f32(item) {
+                let state = self.state_mut();
+                let tx = -offset / 1000.0 * state.font_size * state.horizontal_scaling / 100.0;
+                state.text_matrix = Matrix::translate(tx, 0.0).concat(&state.text_matrix);
             }
         }
+        self.paint_text(&text, device);
+        self.paint_type3_glyphs(&text, device)
+    }
+
+    /// Add the glyphs of a shown string to `text`, advancing the text matrix
+    ///
+    /// Glyph ids are the character codes and Unicode values come from the
+    /// font's encoding; fonts are not loaded, so only positioning follows
+    /// the font's widths.
+    fn show_string(&mut self, text: &mut Text, bytes: &[u8]) {
+        let default_metrics = FontMetrics::default();
+        let state = self.state_stack.last_mut().unwrap();
+        // Type 3 widths are in glyph space, others in thousandths of an em
+        let type3 = state
+            .font
+            .as_deref()
+            .and_then(|name| self.type3_fonts.get(name));
+        let width_scale = type3.map_or(0.001, |font| font.font_matrix.a);
+        let metrics = match type3 {
+            Some(font) => &font.widths,
+            None => state
+                .font
+                .as_deref()
+                .and_then(|name| self.font_metrics.get(name))
+                .unwrap_or(&default_metrics),
+        };
+        let font = Arc::new(match metrics.substitute() {
+            Some(name) => standard_fonts::create(name),
+            None => Font::new(state.font.as_deref().unwrap_or("")),
+        });
+        let scale = state.horizontal_scaling / 100.0;
+        let params = Matrix::new(
+            state.font_size * scale,
+            0.0,
+            0.0,
+            state.font_size,
+            0.0,
+            state.text_rise,
+        );
+
+        for code in metrics.codes(bytes) {
+            let trm = params.concat(&state.text_matrix);
+            text.show_glyph(
+                font.clone(),
+                trm,
+                code as i32,
+                metrics.unicode(code) as i32,
+                false,
+                0,
+                BidiDirection::Ltr,
+                TextLanguage::Unset,
+            );
 
+            let mut advance =
+                metrics.width(code) * width_scale * state.font_size + state.char_spacing;
+            if code == 32 {
+                advance += state.word_spacing;
+            }
+            state.text_matrix = Matrix::translate(advance * scale, 0.0).concat(&state.text_matrix);
+        }
+    }
+
+    /// Run the glyph procedures of text shown in a Type 3 font
+    ///
+    /// Each procedure draws in glyph space, mapped through the font matrix
+    /// and the glyph's text rendering matrix. The text itself has already
+    /// gone to the device, for extraction.
+    fn paint_type3_glyphs<D: Device + ?Sized>(
+        &mut self,
+        text: &Text,
+        device: &mut D,
+    ) -> Result<(), String> {
+        let state = self.state();
+        let Some(font) = state
+            .font
+            .as_deref()
+            .and_then(|name| self.type3_fonts.get(name))
+        else {
+            return Ok(());
+        };
+        if text.is_empty() || self.content_hidden() || matches!(state.text_render_mode, 3 | 7) {
+            return Ok(());
+        }
+
+        for span in text.spans() {
+            for item in span.items() {
+                let Some(proc) = font.procs.get(&(item.gid as u32)) else {
+                    continue;
+                };
+                let trm = Matrix {
+                    e: item.x,
+                    f: item.y,
+                    ..span.trm
+                };
+                let mut glyph = Interpreter::new();
+                glyph.set_ctm(font.font_matrix.concat(&trm).concat(&state.ctm));
+                if let Some(resources) = font.resources.as_ref().or(self.resources.as_ref()) {
+                    glyph.set_resources(resources.clone());
+                }
+                glyph.font_metrics = self.font_metrics.clone();
+                glyph.cookie = self.cookie.clone();
+                let glyph_state = glyph.state_mut();
+                glyph_state.fill_colorspace = state.fill_colorspace.clone();
+                glyph_state.fill_color = state.fill_color.clone();
+                glyph_state.stroke_colorspace = state.fill_colorspace.clone();
+                glyph_state.stroke_color = state.fill_color.clone();
+                glyph.interpret(proc, device)?;
+            }
+        }
         Ok(())
     }
 
-    fn op_show_text_next_line<D: Device>(
+    /// Send shown text to the device according to the text rendering mode
+    fn paint_text<D: Device + ?Sized>(&self, text: &Text, device: &mut D) {
+        if text.is_empty() || self.content_hidden() {
+            return;
+        }
+        let state = self.state();
+        match state.text_render_mode {
+            0 | 2 | 4 | 6 => device.fill_text(
+                text,
+                &state.ctm,
+                &state.fill_colorspace,
+                &state.fill_color,
+                state.fill_alpha,
+            ),
+            3 | 7 => device.ignore_text(text, &state.ctm),
+            _ => {}
+        }
+        if matches!(state.text_render_mode, 1 | 2 | 5 | 6) {
+            let line_cap = line_cap_from_i32(state.line_cap);
+            let stroke_state = crate::fitz::path::StrokeState {
+                linewidth: state.line_width,
+                miterlimit: state.miter_limit,
+                start_cap: line_cap,
+                dash_cap: line_cap,
+                end_cap: line_cap,
+                linejoin: line_join_from_i32(state.line_join),
+                dash_phase: state.dash_phase,
+                dash_pattern: state.dash_pattern.clone(),
+            };
+            device.stroke_text(
+                text,
+                &stroke_state,
+                &state.ctm,
+                &state.stroke_colorspace,
+                &state.stroke_color,
+                state.stroke_alpha,
+            );
+        }
+    }
+
+    fn op_show_text_next_line<D: Device + ?Sized>(
         &mut self,
         operands: &[Object],
         device: &mut D,
@@ -1215,7 +1756,7 @@ impl Interpreter {
         self.op_show_text(operands, device)
     }
 
-    fn op_show_text_next_line_with_spacing<D: Device>(
+    fn op_show_text_next_line_with_spacing<D: Device + ?Sized>(
         &mut self,
         operands: &[Object],
         device: &mut D,
@@ -1233,11 +1774,18 @@ impl Interpreter {
     // XObject Operators
     // ========================================================================
 
-    fn op_paint_xobject<D: Device>(
+    fn op_paint_xobject<D: Device + ?Sized>(
         &mut self,
-        _operands: &[Object],
+        operands: &[Object],
         _device: &mut D,
     ) -> Result<(), String> {
+        let name = match operands.first() {
+            Some(Object::Name(n)) => n,
+            _ => return Err("Do operator requires a name".to_string()),
+        };
+        if self.content_hidden() || self.hidden.xobjects.contains(name) {
+            return Ok(());
+        }
         // TODO: Look up XObject from resources and paint it
         Ok(())
     }
@@ -1246,18 +1794,38 @@ impl Interpreter {
     // Image Operators
     // ========================================================================
 
-    fn op_begin_inline_image(&mut self, _operands: &[Object]) -> Result<(), String> {
-        // TODO: Begin inline image parsing
-        Ok(())
-    }
-
-    fn op_inline_image_data(&mut self, _operands: &[Object]) -> Result<(), String> {
-        // TODO: Parse inline image data
-        Ok(())
-    }
-
-    fn op_end_inline_image<D: Device>(&mut self, _device: &mut D) -> Result<(), String> {
-        // TODO: Paint inline image
+    fn op_inline_image<D: Device + ?Sized>(
+        &mut self,
+        operands: &[Object],
+        device: &mut D,
+    ) -> Result<(), String> {
+        let (Some(Object::Dict(dict)), Some(Object::String(data))) =
+            (operands.first(), operands.get(1))
+        else {
+            return Err("BI operator requires an image".to_string());
+        };
+        // Images that cannot be decoded are skipped, like unknown operators
+        let Some(image) = inline_image(dict, data.as_bytes()) else {
+            return Ok(());
+        };
+        let is_mask = dict
+            .get("IM")
+            .or_else(|| dict.get("ImageMask"))
+            .and_then(Object::as_bool)
+            == Some(true);
+
+        let state = self.state();
+        if is_mask {
+            device.fill_image_mask(
+                &image,
+                &state.ctm,
+                &state.fill_colorspace,
+                &state.fill_color,
+                state.fill_alpha,
+            );
+        } else {
+            device.fill_image(&image, &state.ctm, state.fill_alpha);
+        }
         Ok(())
     }
 
@@ -1274,14 +1842,24 @@ impl Interpreter {
     }
 
     fn op_begin_marked_content(&mut self, _operands: &[Object]) -> Result<(), String> {
+        self.marked_content.push(false);
         Ok(())
     }
 
-    fn op_begin_marked_content_with_props(&mut self, _operands: &[Object]) -> Result<(), String> {
+    fn op_begin_marked_content_with_props(&mut self, operands: &[Object]) -> Result<(), String> {
+        // Optional content is tagged `/OC /Name BDC` with Name in /Properties
+        let hidden = match operands {
+            [Object::Name(tag), Object::Name(props)] if tag.as_str() == "OC" => {
+                self.hidden.properties.contains(props)
+            }
+            _ => false,
+        };
+        self.marked_content.push(hidden);
         Ok(())
     }
 
     fn op_end_marked_content(&mut self) -> Result<(), String> {
+        self.marked_content.pop();
         Ok(())
     }
 
@@ -1301,7 +1879,11 @@ impl Interpreter {
     // Shading Operator
     // ========================================================================
 
-    fn op_shade<D: Device>(&mut self, _operands: &[Object], _device: &mut D) -> Result<(), String> {
+    fn op_shade<D: Device + ?Sized>(
+        &mut self,
+        _operands: &[Object],
+        _device: &mut D,
+    ) -> Result<(), String> {
         // TODO: Paint shading pattern
         Ok(())
     }
@@ -1335,6 +1917,56 @@ fn get_i32(obj: &Object) -> Result<i32, String> {
     }
 }
 
+/// Build an image from an inline image dictionary and its data
+///
+/// Filters are decoded up front, except a final DCTDecode whose data is
+/// kept as JPEG. Only the device colorspaces are supported.
+fn inline_image(dict: &Dict, data: &[u8]) -> Option<Image> {
+    let get = |short: &str, long: &str| dict.get(short).or_else(|| dict.get(long));
+    let width = get("W", "Width")?.as_int()? as i32;
+    let height = get("H", "Height")?.as_int()? as i32;
+
+    let filters: Vec<FilterType> = match get("F", "Filter") {
+        None => Vec::new(),
+        Some(Object::Name(name)) => vec![FilterType::from_name(name.as_str())?],
+        Some(Object::Array(names)) => names
+            .iter()
+            .map(|f| FilterType::from_name(f.as_name()?.as_str()))
+            .collect::<Option<_>>()?,
+        Some(_) => return None,
+    };
+    let jpeg = filters.last() == Some(&FilterType::DCTDecode);
+    let mut chain = FilterChain::new();
+    for filter in &filters[..filters.len() - jpeg as usize] {
+        chain.add(*filter);
+    }
+    let data = chain.decode(data.to_vec()).ok()?;
+
+    if get("IM", "ImageMask").and_then(Object::as_bool) == Some(true) {
+        return Image::from_mask(width, height, data).ok();
+    }
+    let bpc = get("BPC", "BitsPerComponent")?.as_int()? as u8;
+    let colorspace = match get("CS", "ColorSpace")?.as_name()?.as_str() {
+        "G" | "DeviceGray" => Colorspace::device_gray(),
+        "RGB" | "DeviceRGB" => Colorspace::device_rgb(),
+        "CMYK" | "DeviceCMYK" => Colorspace::device_cmyk(),
+        _ => return None,
+    };
+    if jpeg {
+        Image::from_compressed(
+            width,
+            height,
+            bpc,
+            Some(colorspace),
+            ImageFormat::Jpeg,
+            data,
+        )
+        .ok()
+    } else {
+        Image::from_raw(width, height, bpc, colorspace, data).ok()
+    }
+}
+
 /// Convert PDF line cap value to LineCap enum
 fn line_cap_from_i32(cap: i32) -> LineCap {
     match cap {
@@ -1374,7 +2006,7 @@ mod tests {
         let mut interp = Interpreter::new();
         interp.push_state();
         assert_eq!(interp.state_stack.len(), 2);
-        interp.pop_state();
+        interp.pop_state(&mut crate::fitz::device::NullDevice);
         assert_eq!(interp.state_stack.len(), 1);
     }
 
@@ -1403,4 +2035,352 @@ mod tests {
         assert_eq!(get_f32(&Object::Real(3.5)).unwrap(), 3.5f32);
         assert!(get_f32(&Object::Null).is_err());
     }
+
+    #[test]
+    fn test_render_trace() {
+        use crate::fitz::device::NullDevice;
+        use std::sync::Mutex;
+
+        struct Ops(Arc<Mutex<Vec<String>>>);
+        impl TraceHooks for Ops {
+            fn operator(&mut self, _: OperatorCategory, op: &str, _: std::time::Duration) {
+                self.0.lock().unwrap().push(op.to_string());
+            }
+        }
+
+        let content = b"q 1 0 0 rg 10 10 100 50 re f Q BT /F1 12 Tf 72 700 Td (Hello) Tj ET";
+        let mut interp = Interpreter::with_options(RenderOptions { trace: true });
+        let ops = Arc::new(Mutex::new(Vec::new()));
+        interp.set_trace_hooks(Box::new(Ops(ops.clone())));
+        interp.interpret(content, &mut NullDevice).unwrap();
+
+        let trace = interp.trace().unwrap();
+        assert_eq!(trace.count(OperatorCategory::Path), 2);
+        assert_eq!(trace.count(OperatorCategory::Text), 5);
+        assert_eq!(trace.count(OperatorCategory::State), 3);
+        assert_eq!(trace.count(OperatorCategory::Image), 0);
+        assert_eq!(trace.total_count(), 10);
+        assert_eq!(ops.lock().unwrap().len(), 10);
+        assert_eq!(ops.lock().unwrap()[3], "f");
+
+        assert_eq!(interp.take_trace().unwrap().total_count(), 10);
+        assert_eq!(interp.trace().unwrap().total_count(), 0);
+        assert!(Interpreter::new().trace().is_none());
+    }
+
+    #[test]
+    fn test_clip_path() {
+        use crate::fitz::pixmap::Pixmap;
+        use crate::fitz::render::DrawDevice;
+
+        let mut pixmap = Pixmap::new(Some(Colorspace::device_gray()), 20, 20, false).unwrap();
+        pixmap.clear(255);
+        let mut dev = DrawDevice::new(pixmap);
+        // The clip ends with its q/Q, so the second fill is not clipped
+        let content = b"q 5 5 10 5 re W n 0 0 20 20 re f Q 0 18 2 2 re f";
+        Interpreter::new().interpret(content, &mut dev).unwrap();
+        assert_eq!(dev.clip_depth(), 0);
+
+        let samples = dev.into_pixmap().samples().to_vec();
+        assert_eq!(samples[7 * 20 + 10], 0);
+        assert_eq!(samples[2 * 20 + 2], 255);
+        assert_eq!(samples[12 * 20 + 10], 255);
+        assert_eq!(samples[19 * 20], 0);
+
+        // Clips left open by the stream are popped at the end
+        let mut dev =
+            DrawDevice::new(Pixmap::new(Some(Colorspace::device_gray()), 4, 4, false).unwrap());
+        Interpreter::new()
+            .interpret(b"q 0 0 2 2 re W* n 1 1 2 2 re W n", &mut dev)
+            .unwrap();
+        assert_eq!(dev.clip_depth(), 0);
+    }
+
+    #[test]
+    fn test_luminosity_soft_mask() {
+        use crate::fitz::pixmap::Pixmap;
+        use crate::fitz::render::DrawDevice;
+
+        // Ten bands fading from white to black, left to right
+        let contents: String = (0..10)
+            .map(|i| format!("{} g {} 0 10 10 re f ", 1.0 - i as f32 / 9.0, i * 10))
+            .collect();
+        let mask = SoftMask {
+            luminosity: true,
+            contents: contents.into_bytes(),
+            bbox: Rect::new(0.0, 0.0, 100.0, 10.0),
+            matrix: Matrix::IDENTITY,
+            resources: None,
+            colorspace: Colorspace::device_gray(),
+            backdrop: Vec::new(),
+        };
+
+        let mut pixmap = Pixmap::new(Some(Colorspace::device_gray()), 100, 10, false).unwrap();
+        pixmap.clear(255);
+        let mut dev = DrawDevice::new(pixmap);
+        let mut interp = Interpreter::new();
+        let state = ExtGState {
+            soft_mask: Some(Some(mask)),
+            ..Default::default()
+        };
+        interp.set_ext_gstates(HashMap::from([(Name::new("GS1"), state)]));
+        interp
+            .interpret(b"q /GS1 gs 0 g 0 0 100 10 re f Q", &mut dev)
+            .unwrap();
+        assert_eq!(dev.clip_depth(), 0);
+
+        // The black fill fades out towards the right
+        let samples = dev.into_pixmap().samples().to_vec();
+        let row: Vec<u8> = (0..10)
+            .map(|band| samples[5 * 100 + band * 10 + 5])
+            .collect();
+        assert_eq!(row[0], 0);
+        assert_eq!(row[9], 255);
+        assert!(row.windows(2).all(|w| w[0] < w[1]), "{row:?}");
+    }
+
+    #[test]
+    fn test_gstate_blend_mode() {
+        use crate::fitz::pixmap::Pixmap;
+        use crate::fitz::render::DrawDevice;
+
+        let paint = |mode: BlendMode| {
+            let pixmap = Pixmap::new(Some(Colorspace::device_gray()), 4, 4, false).unwrap();
+            let mut dev = DrawDevice::new(pixmap);
+            let mut interp = Interpreter::new();
+            let state = ExtGState {
+                blend_mode: Some(mode),
+                ..Default::default()
+            };
+            interp.set_ext_gstates(HashMap::from([(Name::new("GS1"), state)]));
+            interp
+                .interpret(b"0.5 g 0 0 4 4 re f /GS1 gs 0 0 4 4 re f", &mut dev)
+                .unwrap();
+            dev.into_pixmap().samples()[0]
+        };
+
+        let half = 128.0 / 255.0;
+        assert_eq!(
+            paint(BlendMode::Multiply),
+            (half * half * 255.0f32).round() as u8
+        );
+        assert_eq!(
+            paint(BlendMode::Screen),
+            ((half + half - half * half) * 255.0f32).round() as u8
+        );
+    }
+
+    #[test]
+    fn test_tiling_pattern_fill() {
+        use crate::fitz::pixmap::Pixmap;
+        use crate::fitz::render::DrawDevice;
+
+        // A 20x20 cell with black squares at its top-left and bottom-right
+        let checker = |colored: bool, contents: &[u8]| TilingPattern {
+            colored,
+            bbox: Rect::new(0.0, 0.0, 20.0, 20.0),
+            xstep: 20.0,
+            ystep: 20.0,
+            matrix: Matrix::IDENTITY,
+            contents: contents.to_vec(),
+            resources: None,
+        };
+        let render = |content: &[u8]| {
+            let mut pixmap = Pixmap::new(Some(Colorspace::device_gray()), 60, 60, false).unwrap();
+            pixmap.clear(255);
+            let mut dev = DrawDevice::new(pixmap);
+            let mut interp = Interpreter::new();
+            interp.set_patterns(HashMap::from([
+                (
+                    Name::new("P1"),
+                    checker(true, b"0 g 0 0 10 10 re 10 10 10 10 re f"),
+                ),
+                (
+                    Name::new("P2"),
+                    checker(false, b"1 g 0 0 10 10 re 10 10 10 10 re f"),
+                ),
+            ]));
+            interp.interpret(content, &mut dev).unwrap();
+            assert_eq!(dev.clip_depth(), 0);
+            dev.into_pixmap().samples().to_vec()
+        };
+
+        let samples = render(b"/Pattern cs /P1 scn 0 0 50 50 re f");
+        let pixel = |x: usize, y: usize| samples[y * 60 + x];
+        for (cx, cy) in [(0, 0), (20, 0), (40, 20), (20, 40)] {
+            assert_eq!(pixel(cx + 5, cy + 5), 0, "cell at ({cx}, {cy})");
+            assert_eq!(pixel(cx + 15, cy + 5), 255);
+            assert_eq!(pixel(cx + 5, cy + 15), 255);
+        }
+        assert_eq!(pixel(45, 45), 0);
+        // Outside the filled rect
+        assert_eq!(pixel(55, 55), 255);
+
+        // Uncolored cells use the fill color instead of their own
+        let samples = render(b"/Pattern cs 0.5 /P2 scn 0 0 20 20 re f");
+        assert_eq!(samples[5 * 60 + 5], 128);
+        assert_eq!(samples[5 * 60 + 15], 255);
+    }
+
+    #[test]
+    fn test_type3_glyphs() {
+        use crate::fitz::pixmap::Pixmap;
+        use crate::fitz::render::DrawDevice;
+
+        // A d1 glyph that fills its 1000-unit em square; its own color is
+        // ignored in favour of the fill color
+        let font = Type3Font {
+            font_matrix: Matrix::scale(0.001, 0.001),
+            procs: HashMap::from([(
+                65,
+                b"1000 0 0 0 1000 1000 d1 1 g 0 0 1000 1000 re f".to_vec(),
+            )]),
+            resources: None,
+            widths: FontMetrics::default(),
+        };
+
+        let mut pixmap = Pixmap::new(Some(Colorspace::device_gray()), 40, 20, false).unwrap();
+        pixmap.clear(255);
+        let mut dev = DrawDevice::new(pixmap);
+        let mut interp = Interpreter::new();
+        interp.set_type3_fonts(HashMap::from([(Name::new("T3"), font)]));
+        interp
+            .interpret(b"BT /T3 10 Tf 5 5 Td (AA) Tj ET", &mut dev)
+            .unwrap();
+
+        // Two 10x10 glyphs side by side, advanced by the 1000-unit width
+        let samples = dev.into_pixmap().samples().to_vec();
+        assert_eq!(samples[10 * 40 + 10], 0);
+        assert_eq!(samples[10 * 40 + 20], 0);
+        assert_eq!(samples[10 * 40 + 30], 255);
+        assert_eq!(samples[2 * 40 + 10], 255);
+    }
+
+    #[test]
+    fn test_encoding_differences_extraction() {
+        use crate::fitz::geometry::Rect;
+        use crate::fitz::stext::{STextDevice, STextOptions};
+        use crate::pdf::document::Document;
+
+        let doc =
+            Document::open_bytes(b"%PDF-1.7\ntrailer\n<< /Size 1 >>\n%%EOF\n".to_vec()).unwrap();
+        let Object::Dict(font) = crate::pdf::parser::parse_object(
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica \
+              /Encoding << /Differences [65 /bullet] >> >>",
+        )
+        .unwrap() else {
+            panic!("expected a dictionary");
+        };
+        let metrics = FontMetrics::load(&doc, &font).unwrap();
+
+        let mut dev = STextDevice::new(Rect::new(0.0, 0.0, 200.0, 100.0), STextOptions::default());
+        let mut interp = Interpreter::new();
+        interp.set_font_metrics(HashMap::from([(Name::new("F1"), metrics)]));
+        interp
+            .interpret(b"BT /F1 12 Tf 10 50 Td (AB) Tj ET", &mut dev)
+            .unwrap();
+        assert_eq!(dev.finish().get_text().trim(), "\u{2022}B");
+    }
+
+    #[test]
+    fn test_inline_image() {
+        use crate::fitz::device::BlendMode;
+        use crate::fitz::geometry::Rect;
+        use crate::fitz::path::StrokeState;
+
+        /// Records painted images and text
+        #[derive(Default)]
+        struct Recorder(Vec<String>);
+
+        impl Device for Recorder {
+            fn fill_path(
+                &mut self,
+                _: &Path,
+                _: bool,
+                _: &Matrix,
+                _: &Colorspace,
+                _: &[f32],
+                _: f32,
+            ) {
+            }
+            fn stroke_path(
+                &mut self,
+                _: &Path,
+                _: &StrokeState,
+                _: &Matrix,
+                _: &Colorspace,
+                _: &[f32],
+                _: f32,
+            ) {
+            }
+            fn clip_path(&mut self, _: &Path, _: bool, _: &Matrix, _: Rect) {}
+            fn clip_stroke_path(&mut self, _: &Path, _: &StrokeState, _: &Matrix, _: Rect) {}
+            fn fill_text(&mut self, text: &Text, _: &Matrix, _: &Colorspace, _: &[f32], _: f32) {
+                self.0.push(format!("text {}", text.text_content()));
+            }
+            fn stroke_text(
+                &mut self,
+                _: &Text,
+                _: &StrokeState,
+                _: &Matrix,
+                _: &Colorspace,
+                _: &[f32],
+                _: f32,
+            ) {
+            }
+            fn clip_text(&mut self, _: &Text, _: &Matrix, _: Rect) {}
+            fn clip_stroke_text(&mut self, _: &Text, _: &StrokeState, _: &Matrix, _: Rect) {}
+            fn ignore_text(&mut self, _: &Text, _: &Matrix) {}
+            fn fill_image(&mut self, image: &Image, ctm: &Matrix, _: f32) {
+                self.0.push(format!(
+                    "image {}x{} {:?} scale {}",
+                    image.width(),
+                    image.height(),
+                    image.data(),
+                    ctm.a
+                ));
+            }
+            fn fill_image_mask(
+                &mut self,
+                _: &Image,
+                _: &Matrix,
+                _: &Colorspace,
+                _: &[f32],
+                _: f32,
+            ) {
+            }
+            fn clip_image_mask(&mut self, _: &Image, _: &Matrix, _: Rect) {}
+            fn pop_clip(&mut self) {}
+            fn begin_mask(&mut self, _: Rect, _: bool, _: &Colorspace, _: &[f32]) {}
+            fn end_mask(&mut self) {}
+            fn begin_group(
+                &mut self,
+                _: Rect,
+                _: Option<&Colorspace>,
+                _: bool,
+                _: bool,
+                _: BlendMode,
+                _: f32,
+            ) {
+            }
+            fn end_group(&mut self) {}
+            fn begin_tile(&mut self, _: Rect, _: Rect, _: f32, _: f32, _: &Matrix) -> i32 {
+                0
+            }
+            fn end_tile(&mut self) {}
+        }
+
+        // The raw samples contain " EI ", so the data length must come from
+        // the dictionary rather than a scan for EI
+        let mut content = b"q 10 0 0 10 0 0 cm BI /W 2 /H 2 /BPC 8 /CS /G ID ".to_vec();
+        content.extend_from_slice(b" EI ");
+        content.extend_from_slice(b"\nEI Q BT /F1 12 Tf 72 700 Td (Hello) Tj ET");
+
+        let mut device = Recorder::default();
+        Interpreter::new().interpret(&content, &mut device).unwrap();
+        assert_eq!(
+            device.0,
+            ["image 2x2 [32, 69, 73, 32] scale 10", "text Hello"]
+        );
+    }
 }
@@ -106,6 +106,14 @@ impl LexBuf {
     pub fn as_float(&self) -> f64 {
         self.float_value
     }
+
+    /// Get the raw bytes of a string token
+    ///
+    /// String bytes are stored one per `char` (U+0000..=U+00FF), so this
+    /// recovers binary data such as encrypted strings losslessly.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        self.buffer.chars().map(|c| c as u32 as u8).collect()
+    }
 }
 
 impl Default for LexBuf {
@@ -126,6 +134,21 @@ impl<'a> Lexer<'a> {
         Self { data, pos: 0 }
     }
 
+    /// Current byte offset into the input
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Move to a byte offset in the input (clamped to the end)
+    pub fn seek(&mut self, pos: usize) {
+        self.pos = pos.min(self.data.len());
+    }
+
+    /// The complete input being tokenized
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
     /// Get the next token
     pub fn lex(&mut self, buf: &mut LexBuf) -> Result<Token> {
         buf.clear();
@@ -290,18 +313,28 @@ impl<'a> Lexer<'a> {
     }
 
     fn lex_hex_string(&mut self, buf: &mut LexBuf) -> Result<Token> {
+        let mut high: Option<u8> = None;
         while !self.is_eof() {
             let ch = self.data[self.pos];
+            self.pos += 1;
             if ch == b'>' {
-                self.pos += 1;
                 break;
             }
-            if Self::is_whitespace(ch) {
-                self.pos += 1;
-                continue;
+            let nibble = match ch {
+                b'0'..=b'9' => ch - b'0',
+                b'a'..=b'f' => ch - b'a' + 10,
+                b'A'..=b'F' => ch - b'A' + 10,
+                // Whitespace and stray characters are ignored
+                _ => continue,
+            };
+            match high.take() {
+                Some(h) => buf.buffer.push(((h << 4) | nibble) as char),
+                None => high = Some(nibble),
             }
-            buf.buffer.push(ch as char);
-            self.pos += 1;
+        }
+        // An odd final digit is treated as if followed by 0
+        if let Some(h) = high {
+            buf.buffer.push((h << 4) as char);
         }
         Ok(Token::String)
     }
@@ -486,6 +519,22 @@ mod tests {
         assert_eq!(buf.as_str(), "Line\nBreak\tTab");
     }
 
+    #[test]
+    fn test_lex_hex_strings() {
+        let data = b"<48 65 6C6C6F> <FF0> <>";
+        let mut lexer = Lexer::new(data);
+        let mut buf = LexBuf::new();
+
+        assert_eq!(lexer.lex(&mut buf).unwrap(), Token::String);
+        assert_eq!(buf.as_str(), "Hello");
+
+        assert_eq!(lexer.lex(&mut buf).unwrap(), Token::String);
+        assert_eq!(buf.as_bytes(), vec![0xFF, 0x00]);
+
+        assert_eq!(lexer.lex(&mut buf).unwrap(), Token::String);
+        assert!(buf.as_bytes().is_empty());
+    }
+
     #[test]
     fn test_lex_keywords() {
         let data = b"true false null R obj endobj";
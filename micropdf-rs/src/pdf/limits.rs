@@ -0,0 +1,58 @@
+//! Limits on parsing untrusted files
+//!
+//! Hostile files can declare streams that inflate to gigabytes, nest
+//! arrays without end or define millions of objects. The parser, the
+//! cross-reference reader and the stream filters check the process-wide
+//! [`ParseLimits`] and fail with [`Error::Limit`](crate::fitz::error::Error)
+//! instead of exhausting memory. Many streams each under the stream limit
+//! are held back by a budget on all the data one document decodes.
+
+use std::sync::RwLock;
+
+/// Resource limits applied while parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Largest decoded stream, in bytes, including every stage of its
+    /// filter chain
+    pub max_stream_size: u64,
+    /// Deepest nesting of arrays and dictionaries
+    pub max_nesting: usize,
+    /// Most objects a document may define
+    pub max_objects: u64,
+    /// Largest document file, in bytes, that is opened
+    pub max_file_size: u64,
+    /// Most bytes the streams of one document may decode to in total
+    pub max_decoded_total: u64,
+}
+
+impl ParseLimits {
+    /// Limits generous enough for any legitimate file
+    pub const DEFAULT: Self = Self {
+        max_stream_size: 1 << 30,
+        max_nesting: 256,
+        // The implementation limit on indirect objects in PDF 1.7
+        max_objects: 8_388_607,
+        max_file_size: 4 << 30,
+        max_decoded_total: 16 << 30,
+    };
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+static LIMITS: RwLock<ParseLimits> = RwLock::new(ParseLimits::DEFAULT);
+
+/// The limits currently in force
+pub fn parse_limits() -> ParseLimits {
+    LIMITS.read().map_or(ParseLimits::DEFAULT, |limits| *limits)
+}
+
+/// Replace the limits for all documents parsed from now on
+pub fn set_parse_limits(limits: ParseLimits) {
+    if let Ok(mut current) = LIMITS.write() {
+        *current = limits;
+    }
+}
@@ -1,18 +1,34 @@
 //! PDF-specific parsing and document handling
 
+pub mod action;
 pub mod annot;
 pub mod cmap;
 pub mod colorspace;
+pub mod content;
 pub mod crypt;
 pub mod document;
+pub mod embedded;
 pub mod filter;
 pub mod font;
 pub mod form;
+pub mod function;
+pub mod geometry;
 pub mod image;
 pub mod interpret;
 pub mod lexer;
+pub mod limits;
+pub mod name_tree;
 pub mod object;
+pub mod ocg;
+pub mod outline;
 pub mod page;
+pub mod parse_cache;
 pub mod parser;
+pub mod render;
+pub mod signature;
+#[cfg(test)]
+pub(crate) mod test_pdf;
+pub mod trace;
 pub mod write;
 pub mod xref;
+pub mod zugferd;
@@ -0,0 +1,369 @@
+//! Name trees and number trees
+//!
+//! Balanced trees mapping string keys (`/Names`: Dests, EmbeddedFiles,
+//! JavaScript) or integer keys (`/Nums`: PageLabels, ParentTree) to values.
+//! Interior nodes list `/Kids` with `/Limits [low high]`; leaves hold
+//! key/value pairs sorted by key, so both levels are binary searched.
+
+use crate::fitz::error::Result;
+use crate::pdf::object::{Dict, Name, ObjRef, Object, PdfString};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+/// Maximum depth of a tree
+const MAX_DEPTH: usize = 64;
+
+/// Look up `key` in the name tree rooted at `tree`
+///
+/// `resolve` turns indirect references into objects, e.g.
+/// `|o| doc.resolve(o)`.
+pub fn lookup_name(
+    tree: &Object,
+    key: &[u8],
+    resolve: impl Fn(&Object) -> Result<Object>,
+) -> Result<Option<Object>> {
+    Lookup {
+        key: Key::Name(key),
+        leaf: "Names",
+        resolve,
+        visited: HashSet::new(),
+    }
+    .node(tree, 0)
+}
+
+/// Look up `key` in the number tree rooted at `tree`
+pub fn lookup_number(
+    tree: &Object,
+    key: i64,
+    resolve: impl Fn(&Object) -> Result<Object>,
+) -> Result<Option<Object>> {
+    Lookup {
+        key: Key::Number(key),
+        leaf: "Nums",
+        resolve,
+        visited: HashSet::new(),
+    }
+    .node(tree, 0)
+}
+
+/// Every key/value pair of the number tree rooted at `tree`, by key
+///
+/// For trees consulted by range rather than exact key, such as
+/// /PageLabels where a page takes the entry with the largest key not
+/// above its index.
+pub fn number_entries(
+    tree: &Object,
+    resolve: impl Fn(&Object) -> Result<Object>,
+) -> Result<Vec<(i64, Object)>> {
+    let mut entries = Vec::new();
+    collect_numbers(tree, &resolve, 0, &mut HashSet::new(), &mut entries)?;
+    entries.sort_by_key(|(key, _)| *key);
+    Ok(entries)
+}
+
+/// Every key/value pair of the name tree rooted at `tree`, by key
+///
+/// Values are returned as stored, so indirect values such as file
+/// specifications stay references and the entries can be written back
+/// into a rebuilt tree.
+pub fn name_entries(
+    tree: &Object,
+    resolve: impl Fn(&Object) -> Result<Object>,
+) -> Result<Vec<(Vec<u8>, Object)>> {
+    let mut entries = Vec::new();
+    collect_names(tree, &resolve, 0, &mut HashSet::new(), &mut entries)?;
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(entries)
+}
+
+/// A single-leaf name tree holding `entries`, which must be sorted by key
+pub fn name_leaf(entries: Vec<(Vec<u8>, Object)>) -> Dict {
+    let names = entries
+        .into_iter()
+        .flat_map(|(key, value)| [Object::String(PdfString::new(key)), value])
+        .collect();
+    let mut leaf = Dict::new();
+    leaf.insert(Name::new("Names"), Object::Array(names));
+    leaf
+}
+
+fn collect_names(
+    node: &Object,
+    resolve: &impl Fn(&Object) -> Result<Object>,
+    depth: usize,
+    visited: &mut HashSet<ObjRef>,
+    out: &mut Vec<(Vec<u8>, Object)>,
+) -> Result<()> {
+    if depth > MAX_DEPTH {
+        return Ok(());
+    }
+    if let Some(r) = node.as_obj_ref() {
+        if !visited.insert(r) {
+            return Ok(());
+        }
+    }
+    let Object::Dict(node) = resolve(node)? else {
+        return Ok(());
+    };
+    if let Some(pairs) = node.get("Names") {
+        if let Object::Array(pairs) = resolve(pairs)? {
+            for pair in pairs.chunks_exact(2) {
+                if let Object::String(key) = resolve(&pair[0])? {
+                    out.push((key.as_bytes().to_vec(), pair[1].clone()));
+                }
+            }
+        }
+    }
+    if let Some(kids) = node.get("Kids") {
+        if let Object::Array(kids) = resolve(kids)? {
+            for kid in &kids {
+                collect_names(kid, resolve, depth + 1, visited, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn collect_numbers(
+    node: &Object,
+    resolve: &impl Fn(&Object) -> Result<Object>,
+    depth: usize,
+    visited: &mut HashSet<ObjRef>,
+    out: &mut Vec<(i64, Object)>,
+) -> Result<()> {
+    if depth > MAX_DEPTH {
+        return Ok(());
+    }
+    if let Some(r) = node.as_obj_ref() {
+        if !visited.insert(r) {
+            return Ok(());
+        }
+    }
+    let Object::Dict(node) = resolve(node)? else {
+        return Ok(());
+    };
+    if let Some(pairs) = node.get("Nums") {
+        if let Object::Array(pairs) = resolve(pairs)? {
+            for pair in pairs.chunks_exact(2) {
+                if let Object::Int(key) = resolve(&pair[0])? {
+                    out.push((key, resolve(&pair[1])?));
+                }
+            }
+        }
+    }
+    if let Some(kids) = node.get("Kids") {
+        if let Object::Array(kids) = resolve(kids)? {
+            for kid in &kids {
+                collect_numbers(kid, resolve, depth + 1, visited, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+enum Key<'a> {
+    Name(&'a [u8]),
+    Number(i64),
+}
+
+impl Key<'_> {
+    /// Order of the search key relative to a tree key, or `None` if the
+    /// tree key has the wrong type
+    fn cmp(&self, other: &Object) -> Option<Ordering> {
+        match (self, other) {
+            (Key::Name(key), Object::String(s)) => Some(key.cmp(&s.as_bytes())),
+            (Key::Number(key), Object::Int(n)) => Some(key.cmp(n)),
+            _ => None,
+        }
+    }
+}
+
+struct Lookup<'a, R> {
+    key: Key<'a>,
+    leaf: &'static str,
+    resolve: R,
+    visited: HashSet<ObjRef>,
+}
+
+impl<R: Fn(&Object) -> Result<Object>> Lookup<'_, R> {
+    fn node(&mut self, node: &Object, depth: usize) -> Result<Option<Object>> {
+        if depth > MAX_DEPTH {
+            return Ok(None);
+        }
+        if let Some(r) = node.as_obj_ref() {
+            if !self.visited.insert(r) {
+                return Ok(None);
+            }
+        }
+        let Object::Dict(node) = (self.resolve)(node)? else {
+            return Ok(None);
+        };
+
+        if let Some(pairs) = node.get(self.leaf) {
+            if let Object::Array(pairs) = (self.resolve)(pairs)? {
+                if let Some(value) = self.search_leaf(&pairs)? {
+                    return Ok(Some(value));
+                }
+            }
+        }
+        if let Some(kids) = node.get("Kids") {
+            if let Object::Array(kids) = (self.resolve)(kids)? {
+                return self.search_kids(&kids, depth);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Binary search sorted key/value pairs, scanning them in order if a
+    /// key has the wrong type
+    fn search_leaf(&self, pairs: &[Object]) -> Result<Option<Object>> {
+        let (mut lo, mut hi) = (0, pairs.len() / 2);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            match self.key.cmp(&(self.resolve)(&pairs[2 * mid])?) {
+                Some(Ordering::Equal) => return (self.resolve)(&pairs[2 * mid + 1]).map(Some),
+                Some(Ordering::Less) => hi = mid,
+                Some(Ordering::Greater) => lo = mid + 1,
+                None => break,
+            }
+        }
+        if lo >= hi {
+            return Ok(None);
+        }
+        for pair in pairs.chunks_exact(2) {
+            if self.key.cmp(&(self.resolve)(&pair[0])?) == Some(Ordering::Equal) {
+                return (self.resolve)(&pair[1]).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Descend into the kid whose /Limits cover the key, trying every kid
+    /// when some have no usable limits
+    fn search_kids(&mut self, kids: &[Object], depth: usize) -> Result<Option<Object>> {
+        let (mut lo, mut hi) = (0, kids.len());
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            match self.limits_cmp(&kids[mid])? {
+                Some(Ordering::Equal) => return self.node(&kids[mid], depth + 1),
+                Some(Ordering::Less) => hi = mid,
+                Some(Ordering::Greater) => lo = mid + 1,
+                None => break,
+            }
+        }
+        if lo >= hi {
+            return Ok(None);
+        }
+        for kid in kids {
+            if let Some(value) = self.node(kid, depth + 1)? {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Where the key falls relative to a kid's /Limits: `Equal` when
+    /// inside them
+    fn limits_cmp(&self, kid: &Object) -> Result<Option<Ordering>> {
+        let Object::Dict(kid) = (self.resolve)(kid)? else {
+            return Ok(None);
+        };
+        let Some(limits) = kid.get("Limits") else {
+            return Ok(None);
+        };
+        let Object::Array(limits) = (self.resolve)(limits)? else {
+            return Ok(None);
+        };
+        let [low, high] = limits.as_slice() else {
+            return Ok(None);
+        };
+        let low = self.key.cmp(&(self.resolve)(low)?);
+        let high = self.key.cmp(&(self.resolve)(high)?);
+        Ok(match (low, high) {
+            (Some(Ordering::Less), Some(_)) => Some(Ordering::Less),
+            (Some(_), Some(Ordering::Greater)) => Some(Ordering::Greater),
+            (Some(_), Some(_)) => Some(Ordering::Equal),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::parser;
+
+    fn direct(obj: &Object) -> Result<Object> {
+        Ok(obj.clone())
+    }
+
+    #[test]
+    fn test_two_level_name_tree_limits() {
+        let tree = parser::parse_object(
+            b"<< /Kids [ \
+                << /Limits [(apple) (fig)] /Names [(apple) 1 (cherry) 2 (fig) 3] >> \
+                << /Limits [(grape) (pear)] /Names [(grape) 4 (kiwi) 5 (pear) 6] >> \
+              ] >>",
+        )
+        .unwrap();
+        for (key, value) in [
+            ("apple", 1),
+            ("fig", 3),
+            ("grape", 4),
+            ("pear", 6),
+            ("kiwi", 5),
+        ] {
+            let found = lookup_name(&tree, key.as_bytes(), direct).unwrap();
+            assert_eq!(found.and_then(|o| o.as_int()), Some(value), "{key}");
+        }
+        for key in ["aardvark", "date", "fog", "zebra"] {
+            assert!(
+                lookup_name(&tree, key.as_bytes(), direct)
+                    .unwrap()
+                    .is_none()
+            );
+        }
+    }
+
+    #[test]
+    fn test_number_tree() {
+        let tree = parser::parse_object(
+            b"<< /Kids [ << /Limits [0 2] /Nums [0 (a) 2 (b)] >> \
+                         << /Limits [10 10] /Nums [10 (c)] >> ] >>",
+        )
+        .unwrap();
+        let text = |n| {
+            lookup_number(&tree, n, direct)
+                .unwrap()
+                .and_then(|o| o.as_string().map(|s| s.to_text()))
+        };
+        assert_eq!(text(0).as_deref(), Some("a"));
+        assert_eq!(text(2).as_deref(), Some("b"));
+        assert_eq!(text(10).as_deref(), Some("c"));
+        assert_eq!(text(1), None);
+        assert_eq!(text(11), None);
+
+        let keys: Vec<i64> = number_entries(&tree, direct)
+            .unwrap()
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(keys, [0, 2, 10]);
+    }
+
+    #[test]
+    fn test_name_entries_round_trip() {
+        let tree = parser::parse_object(
+            b"<< /Kids [ << /Names [(b) 5 0 R (c) 3] >> << /Names [(a) 1] >> ] >>",
+        )
+        .unwrap();
+        let entries = name_entries(&tree, direct).unwrap();
+        let keys: Vec<&[u8]> = entries.iter().map(|(k, _)| k.as_slice()).collect();
+        assert_eq!(keys, [b"a", b"b", b"c"]);
+        assert_eq!(entries[1].1.as_obj_ref(), Some(ObjRef::new(5, 0)));
+
+        let leaf = Object::Dict(name_leaf(entries));
+        let found = lookup_name(&leaf, b"c", direct).unwrap();
+        assert_eq!(found.and_then(|o| o.as_int()), Some(3));
+    }
+}
@@ -88,6 +88,12 @@ impl AsRef<str> for Name {
     }
 }
 
+impl std::borrow::Borrow<str> for Name {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
 impl From<&str> for Name {
     fn from(s: &str) -> Self {
         Self::new(s)
@@ -229,7 +235,7 @@ static COMMON_NAMES: LazyLock<Vec<(&'static str, Arc<str>)>> = LazyLock::new(||
     ]
 });
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PdfString(Vec<u8>);
 impl PdfString {
     pub fn new(data: Vec<u8>) -> Self {
@@ -241,9 +247,79 @@ impl PdfString {
     pub fn as_str(&self) -> Option<&str> {
         std::str::from_utf8(&self.0).ok()
     }
+    /// Encode a text string: ASCII as is, anything else as UTF-16BE with a
+    /// byte order mark
+    pub fn from_text(text: &str) -> Self {
+        if text.is_ascii() {
+            return Self(text.as_bytes().to_vec());
+        }
+        let mut data = vec![0xFE, 0xFF];
+        for unit in text.encode_utf16() {
+            data.extend_from_slice(&unit.to_be_bytes());
+        }
+        Self(data)
+    }
+    /// Decode as a text string: UTF-16BE or UTF-8 when the data starts with
+    /// a byte order mark, otherwise PDFDocEncoding
+    pub fn to_text(&self) -> String {
+        decode_text_string(&self.0)
+    }
+}
+
+/// PDFDocEncoding code points that differ from Latin-1 (0x18-0x1F, 0x7F-0xAD)
+///
+/// U+FFFD marks bytes the encoding leaves undefined.
+const PDF_DOC_18: [char; 8] = [
+    '\u{02D8}', '\u{02C7}', '\u{02C6}', '\u{02D9}', '\u{02DD}', '\u{02DB}', '\u{02DA}', '\u{02DC}',
+];
+const PDF_DOC_7F: [char; 47] = [
+    '\u{FFFD}', '\u{2022}', '\u{2020}', '\u{2021}', '\u{2026}', '\u{2014}', '\u{2013}', '\u{0192}',
+    '\u{2044}', '\u{2039}', '\u{203A}', '\u{2212}', '\u{2030}', '\u{201E}', '\u{201C}', '\u{201D}',
+    '\u{2018}', '\u{2019}', '\u{201A}', '\u{2122}', '\u{FB01}', '\u{FB02}', '\u{0141}', '\u{0152}',
+    '\u{0160}', '\u{0178}', '\u{017D}', '\u{0131}', '\u{0142}', '\u{0153}', '\u{0161}', '\u{017E}',
+    '\u{FFFD}', '\u{20AC}', '\u{00A1}', '\u{00A2}', '\u{00A3}', '\u{00A4}', '\u{00A5}', '\u{00A6}',
+    '\u{00A7}', '\u{00A8}', '\u{00A9}', '\u{00AA}', '\u{00AB}', '\u{00AC}', '\u{FFFD}',
+];
+
+/// Map one PDFDocEncoding byte to its character
+pub fn pdf_doc_char(byte: u8) -> char {
+    match byte {
+        0x18..=0x1F => PDF_DOC_18[(byte - 0x18) as usize],
+        0x7F..=0xAD => PDF_DOC_7F[(byte - 0x7F) as usize],
+        _ => byte as char,
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Decode a PDF text string to UTF-8
+///
+/// Strings starting with a UTF-16BE or UTF-8 byte order mark are decoded
+/// as such, with language escapes (`ESC lang ESC`) dropped; anything else
+/// is PDFDocEncoding.
+pub fn decode_text_string(bytes: &[u8]) -> String {
+    match bytes {
+        [0xFE, 0xFF, rest @ ..] => {
+            let units: Vec<u16> = rest
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            let mut out = String::with_capacity(units.len());
+            let mut in_escape = false;
+            for c in char::decode_utf16(units) {
+                match c {
+                    Ok('\u{1B}') => in_escape = !in_escape,
+                    _ if in_escape => {}
+                    Ok(c) => out.push(c),
+                    Err(_) => out.push(char::REPLACEMENT_CHARACTER),
+                }
+            }
+            out
+        }
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => bytes.iter().map(|&b| pdf_doc_char(b)).collect(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ObjRef {
     pub num: i32,
     pub generation: i32,
@@ -257,7 +333,7 @@ impl ObjRef {
 pub type Dict = HashMap<Name, Object>;
 pub type Array = Vec<Object>;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub enum Object {
     #[default]
     Null,
@@ -322,8 +398,22 @@ impl Object {
         }
     }
     pub fn as_dict(&self) -> Option<&Dict> {
-        if let Object::Dict(d) = self {
-            Some(d)
+        match self {
+            Object::Dict(d) => Some(d),
+            Object::Stream { dict, .. } => Some(dict),
+            _ => None,
+        }
+    }
+    pub fn as_obj_ref(&self) -> Option<ObjRef> {
+        if let Object::Ref(r) = self {
+            Some(*r)
+        } else {
+            None
+        }
+    }
+    pub fn as_stream(&self) -> Option<(&Dict, &[u8])> {
+        if let Object::Stream { dict, data } = self {
+            Some((dict, data))
         } else {
             None
         }
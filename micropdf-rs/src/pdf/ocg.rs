@@ -0,0 +1,350 @@
+//! PDF optional content (layers)
+//!
+//! Reads the optional content groups listed in the catalog's
+//! /OCProperties, applies the default configuration (/D) and decides
+//! whether content tagged with a group or membership dictionary is visible.
+
+use crate::fitz::error::Result;
+use crate::pdf::document::Document;
+use crate::pdf::object::{Dict, Name, ObjRef, Object};
+use std::collections::HashSet;
+
+/// An optional content group (OCG)
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionalContentGroup {
+    /// Reference of the group dictionary
+    pub obj_ref: ObjRef,
+    /// The group's /Name
+    pub name: String,
+    /// Whether content in the group is shown
+    pub enabled: bool,
+}
+
+/// Resource names whose content is hidden by the current layer state
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HiddenContent {
+    /// Entries of /Properties used as `BDC /OC /Name`
+    pub properties: HashSet<Name>,
+    /// XObjects whose /OC entry hides them
+    pub xobjects: HashSet<Name>,
+}
+
+/// Optional content groups of a document and their visibility
+#[derive(Debug, Clone, Default)]
+pub struct OptionalContent {
+    groups: Vec<OptionalContentGroup>,
+}
+
+impl OptionalContent {
+    /// Read /OCProperties, setting visibility from the default configuration
+    ///
+    /// /BaseState /OFF hides every group not listed in /ON; otherwise groups
+    /// are visible unless listed in /OFF. Documents without optional
+    /// content have no groups.
+    pub fn load(doc: &Document) -> Result<Self> {
+        let catalog = doc.catalog()?;
+        let Some(Object::Dict(props)) = doc.resolve_key(&catalog, "OCProperties")? else {
+            return Ok(Self::default());
+        };
+        let Some(Object::Array(ocgs)) = doc.resolve_key(&props, "OCGs")? else {
+            return Ok(Self::default());
+        };
+        let config = match doc.resolve_key(&props, "D")? {
+            Some(Object::Dict(d)) => d,
+            _ => Dict::new(),
+        };
+        let base_on = !matches!(
+            doc.resolve_key(&config, "BaseState")?,
+            Some(Object::Name(n)) if n.as_str() == "OFF"
+        );
+        let on = ref_list(doc, &config, "ON")?;
+        let off = ref_list(doc, &config, "OFF")?;
+
+        let mut groups = Vec::with_capacity(ocgs.len());
+        let mut seen = HashSet::new();
+        for item in &ocgs {
+            let Some(obj_ref) = item.as_obj_ref() else {
+                continue;
+            };
+            if !seen.insert(obj_ref) {
+                continue;
+            }
+            let name = match doc.load_object(obj_ref)? {
+                Object::Dict(d) => match doc.resolve_key(&d, "Name")? {
+                    Some(Object::String(s)) => s.to_text(),
+                    _ => String::new(),
+                },
+                _ => continue,
+            };
+            let enabled = if base_on {
+                !off.contains(&obj_ref)
+            } else {
+                on.contains(&obj_ref)
+            };
+            groups.push(OptionalContentGroup {
+                obj_ref,
+                name,
+                enabled,
+            });
+        }
+        Ok(Self { groups })
+    }
+
+    /// The groups in /OCGs order
+    pub fn groups(&self) -> &[OptionalContentGroup] {
+        &self.groups
+    }
+
+    /// Number of groups
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Whether the document has no optional content
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Show or hide the group at `index`; returns false if out of range
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) -> bool {
+        match self.groups.get_mut(index) {
+            Some(group) => {
+                group.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the group is shown; groups not in /OCGs are treated as shown
+    pub fn is_group_enabled(&self, obj_ref: ObjRef) -> bool {
+        self.groups
+            .iter()
+            .find(|g| g.obj_ref == obj_ref)
+            .is_none_or(|g| g.enabled)
+    }
+
+    /// Whether content tagged with `oc` is hidden
+    ///
+    /// `oc` is a group or an optional content membership dictionary (OCMD),
+    /// whose /P policy (default /AnyOn) combines the states of its /OCGs.
+    pub fn is_hidden(&self, doc: &Document, oc: &Object) -> Result<bool> {
+        let Object::Dict(dict) = doc.resolve(oc)? else {
+            return Ok(false);
+        };
+        let is_ocmd = matches!(dict.get("Type"), Some(Object::Name(n)) if n.as_str() == "OCMD");
+        if !is_ocmd {
+            return Ok(oc.as_obj_ref().is_some_and(|r| !self.is_group_enabled(r)));
+        }
+
+        let states: Vec<bool> = match dict.get("OCGs") {
+            Some(Object::Ref(r)) => vec![self.is_group_enabled(*r)],
+            Some(other) => match doc.resolve(other)? {
+                Object::Array(arr) => arr
+                    .iter()
+                    .filter_map(Object::as_obj_ref)
+                    .map(|r| self.is_group_enabled(r))
+                    .collect(),
+                _ => Vec::new(),
+            },
+            None => Vec::new(),
+        };
+        if states.is_empty() {
+            return Ok(false);
+        }
+        let policy = match doc.resolve_key(&dict, "P")? {
+            Some(Object::Name(n)) => n.as_str().to_string(),
+            _ => String::new(),
+        };
+        let visible = match policy.as_str() {
+            "AllOn" => states.iter().all(|&on| on),
+            "AnyOff" => states.iter().any(|&on| !on),
+            "AllOff" => states.iter().all(|&on| !on),
+            _ => states.iter().any(|&on| on),
+        };
+        Ok(!visible)
+    }
+
+    /// Names in a resource dictionary whose content is currently hidden
+    pub fn hidden_content(&self, doc: &Document, resources: &Dict) -> Result<HiddenContent> {
+        let mut hidden = HiddenContent::default();
+        if self.groups.iter().all(|g| g.enabled) {
+            return Ok(hidden);
+        }
+        if let Some(Object::Dict(props)) = doc.resolve_key(resources, "Properties")? {
+            for (name, value) in &props {
+                if self.is_hidden(doc, value)? {
+                    hidden.properties.insert(name.clone());
+                }
+            }
+        }
+        if let Some(Object::Dict(xobjects)) = doc.resolve_key(resources, "XObject")? {
+            for (name, value) in &xobjects {
+                let oc = match doc.resolve(value)? {
+                    Object::Stream { dict, .. } | Object::Dict(dict) => dict.get("OC").cloned(),
+                    _ => None,
+                };
+                if let Some(oc) = oc {
+                    if self.is_hidden(doc, &oc)? {
+                        hidden.xobjects.insert(name.clone());
+                    }
+                }
+            }
+        }
+        Ok(hidden)
+    }
+}
+
+/// References listed in an array entry of a configuration dictionary
+fn ref_list(doc: &Document, dict: &Dict, key: &str) -> Result<HashSet<ObjRef>> {
+    Ok(match doc.resolve_key(dict, key)? {
+        Some(Object::Array(arr)) => arr.iter().filter_map(Object::as_obj_ref).collect(),
+        _ => HashSet::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fitz::colorspace::Colorspace;
+    use crate::fitz::device::{BlendMode, Device};
+    use crate::fitz::geometry::{Matrix, Rect};
+    use crate::fitz::image::Image;
+    use crate::fitz::path::{Path, StrokeState};
+    use crate::fitz::text::Text;
+    use crate::pdf::interpret::Interpreter;
+    use crate::pdf::test_pdf::build_pdf;
+
+    /// Page content with one run of text in each layer and one outside
+    const CONTENT: &str = "/OC /L1 BDC BT /F1 12 Tf 72 700 Td (Layer one) Tj ET EMC \
+        /OC /L2 BDC BT /F1 12 Tf 72 680 Td (Layer two) Tj ET EMC \
+        /OC /Both BDC BT /F1 12 Tf 72 660 Td (Both) Tj ET EMC \
+        BT /F1 12 Tf 72 640 Td (Always) Tj ET";
+
+    fn layered_pdf() -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R /OCProperties << /OCGs [5 0 R 6 0 R] \
+             /D << /Order [5 0 R 6 0 R] /OFF [6 0 R] >> >> >>"
+                .to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R \
+             /Resources << /Properties << /L1 5 0 R /L2 6 0 R /Both 7 0 R >> >> >>"
+                .to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{CONTENT}\nendstream",
+                CONTENT.len() + 1
+            ),
+            "<< /Type /OCG /Name (Artwork) >>".to_string(),
+            "<< /Type /OCG /Name <FEFF004E006F007400650073> >>".to_string(),
+            "<< /Type /OCMD /OCGs [5 0 R 6 0 R] /P /AllOn >>".to_string(),
+        ];
+        build_pdf(&objects)
+    }
+
+    /// Collects shown text, ignoring everything else
+    #[derive(Default)]
+    struct TextCollector(String);
+
+    impl Device for TextCollector {
+        fn fill_path(&mut self, _: &Path, _: bool, _: &Matrix, _: &Colorspace, _: &[f32], _: f32) {}
+        fn stroke_path(
+            &mut self,
+            _: &Path,
+            _: &StrokeState,
+            _: &Matrix,
+            _: &Colorspace,
+            _: &[f32],
+            _: f32,
+        ) {
+        }
+        fn clip_path(&mut self, _: &Path, _: bool, _: &Matrix, _: Rect) {}
+        fn clip_stroke_path(&mut self, _: &Path, _: &StrokeState, _: &Matrix, _: Rect) {}
+        fn fill_text(&mut self, text: &Text, _: &Matrix, _: &Colorspace, _: &[f32], _: f32) {
+            self.0.push_str(&text.text_content());
+            self.0.push('\n');
+        }
+        fn stroke_text(
+            &mut self,
+            _: &Text,
+            _: &StrokeState,
+            _: &Matrix,
+            _: &Colorspace,
+            _: &[f32],
+            _: f32,
+        ) {
+        }
+        fn clip_text(&mut self, _: &Text, _: &Matrix, _: Rect) {}
+        fn clip_stroke_text(&mut self, _: &Text, _: &StrokeState, _: &Matrix, _: Rect) {}
+        fn ignore_text(&mut self, _: &Text, _: &Matrix) {}
+        fn fill_image(&mut self, _: &Image, _: &Matrix, _: f32) {}
+        fn fill_image_mask(&mut self, _: &Image, _: &Matrix, _: &Colorspace, _: &[f32], _: f32) {}
+        fn clip_image_mask(&mut self, _: &Image, _: &Matrix, _: Rect) {}
+        fn pop_clip(&mut self) {}
+        fn begin_mask(&mut self, _: Rect, _: bool, _: &Colorspace, _: &[f32]) {}
+        fn end_mask(&mut self) {}
+        fn begin_group(
+            &mut self,
+            _: Rect,
+            _: Option<&Colorspace>,
+            _: bool,
+            _: bool,
+            _: BlendMode,
+            _: f32,
+        ) {
+        }
+        fn end_group(&mut self) {}
+        fn begin_tile(&mut self, _: Rect, _: Rect, _: f32, _: f32, _: &Matrix) -> i32 {
+            0
+        }
+        fn end_tile(&mut self) {}
+    }
+
+    fn extract(doc: &Document, oc: &OptionalContent) -> String {
+        let page = doc.page(0).unwrap();
+        let resources = page.dict()["Resources"].as_dict().unwrap().clone();
+        let mut interp = Interpreter::new();
+        interp.set_hidden_content(oc.hidden_content(doc, &resources).unwrap());
+        interp.set_resources(resources);
+        let mut dev = TextCollector::default();
+        interp
+            .interpret(&doc.page_contents(&page).unwrap(), &mut dev)
+            .unwrap();
+        dev.0
+    }
+
+    #[test]
+    fn test_load_default_configuration() {
+        let doc = Document::open_bytes(layered_pdf()).unwrap();
+        let oc = OptionalContent::load(&doc).unwrap();
+        let names: Vec<_> = oc.groups().iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, ["Artwork", "Notes"]);
+        assert!(oc.groups()[0].enabled);
+        assert!(!oc.groups()[1].enabled);
+
+        // The /OFF layer's text is not extracted by default
+        let text = extract(&doc, &oc);
+        assert!(text.contains("Layer one"));
+        assert!(!text.contains("Layer two"));
+        assert!(!text.contains("Both"));
+        assert!(text.contains("Always"));
+    }
+
+    #[test]
+    fn test_toggle_layers() {
+        let doc = Document::open_bytes(layered_pdf()).unwrap();
+        let mut oc = OptionalContent::load(&doc).unwrap();
+
+        assert!(oc.set_enabled(0, false));
+        let text = extract(&doc, &oc);
+        assert!(!text.contains("Layer one"));
+        assert!(text.contains("Always"));
+
+        assert!(oc.set_enabled(0, true));
+        assert!(oc.set_enabled(1, true));
+        let text = extract(&doc, &oc);
+        assert!(text.contains("Layer one"));
+        assert!(text.contains("Layer two"));
+        assert!(text.contains("Both"));
+        assert!(!oc.set_enabled(2, true));
+    }
+}
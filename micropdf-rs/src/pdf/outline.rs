@@ -0,0 +1,160 @@
+//! PDF document outline (bookmarks)
+//!
+//! Reads the /Outlines tree of the catalog and resolves each item's
+//! destination to a page index.
+
+use crate::fitz::error::Result;
+use crate::pdf::document::Document;
+use crate::pdf::name_tree;
+use crate::pdf::object::{Dict, ObjRef, Object};
+use std::collections::HashSet;
+
+/// Maximum nesting depth of outline items
+const MAX_DEPTH: usize = 64;
+
+/// An outline item and its children
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineItem {
+    /// The item's /Title
+    pub title: String,
+    /// Zero-based index of the destination page, if it resolves to one
+    pub page: Option<usize>,
+    /// Child items in order
+    pub children: Vec<OutlineItem>,
+}
+
+/// Read the document outline; documents without one have no items
+pub fn load_outline(doc: &Document) -> Result<Vec<OutlineItem>> {
+    let catalog = doc.catalog()?;
+    let Some(Object::Dict(outlines)) = doc.resolve_key(&catalog, "Outlines")? else {
+        return Ok(Vec::new());
+    };
+    let mut visited = HashSet::new();
+    load_items(doc, &catalog, &outlines, &mut visited, 0)
+}
+
+/// The children of an outline node, following /First and /Next
+fn load_items(
+    doc: &Document,
+    catalog: &Dict,
+    node: &Dict,
+    visited: &mut HashSet<ObjRef>,
+    depth: usize,
+) -> Result<Vec<OutlineItem>> {
+    let mut items = Vec::new();
+    if depth > MAX_DEPTH {
+        return Ok(items);
+    }
+    let mut next = node.get("First").and_then(Object::as_obj_ref);
+    while let Some(item_ref) = next {
+        if !visited.insert(item_ref) {
+            break;
+        }
+        let Object::Dict(item) = doc.load_object(item_ref)? else {
+            break;
+        };
+        let title = match doc.resolve_key(&item, "Title")? {
+            Some(Object::String(s)) => s.to_text(),
+            _ => String::new(),
+        };
+        let dest = match doc.resolve_key(&item, "Dest")? {
+            Some(dest) => Some(dest),
+            None => match doc.resolve_key(&item, "A")? {
+                Some(Object::Dict(action))
+                    if action
+                        .get("S")
+                        .and_then(Object::as_name)
+                        .is_some_and(|s| s.as_str() == "GoTo") =>
+                {
+                    doc.resolve_key(&action, "D")?
+                }
+                _ => None,
+            },
+        };
+        let page = match dest {
+            Some(dest) => resolve_dest(doc, catalog, &dest)?,
+            None => None,
+        };
+        let children = load_items(doc, catalog, &item, visited, depth + 1)?;
+        items.push(OutlineItem {
+            title,
+            page,
+            children,
+        });
+        next = item.get("Next").and_then(Object::as_obj_ref);
+    }
+    Ok(items)
+}
+
+/// Page index of a destination: an explicit `[page /XYZ ...]` array or a
+/// named destination from the catalog's /Dests or the /Names /Dests tree
+pub fn resolve_dest(doc: &Document, catalog: &Dict, dest: &Object) -> Result<Option<usize>> {
+    let dest = match doc.resolve(dest)? {
+        Object::Name(name) => match doc.resolve_key(catalog, "Dests")? {
+            Some(Object::Dict(dests)) => match dests.get(name.as_str()) {
+                Some(d) => doc.resolve(d)?,
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        },
+        Object::String(name) => {
+            let tree = match doc.resolve_key(catalog, "Names")? {
+                Some(Object::Dict(names)) => doc.resolve_key(&names, "Dests")?,
+                _ => None,
+            };
+            let tree = tree.unwrap_or_default();
+            match name_tree::lookup_name(&tree, name.as_bytes(), |o| doc.resolve(o))? {
+                Some(d) => d,
+                None => return Ok(None),
+            }
+        }
+        other => other,
+    };
+    // Named destinations may be wrapped in a dictionary with /D
+    let dest = match dest {
+        Object::Dict(d) => doc.resolve_key(&d, "D")?.unwrap_or(Object::Null),
+        other => other,
+    };
+    let Some(target) = dest.as_array().and_then(|a| a.first()) else {
+        return Ok(None);
+    };
+    match target {
+        Object::Ref(r) => doc.page_index(*r),
+        // Remote-style destinations give a page number instead of a reference
+        Object::Int(i) if *i >= 0 && (*i as usize) < doc.page_count()? => Ok(Some(*i as usize)),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::test_pdf::build_pdf;
+
+    #[test]
+    fn test_load_outline_destinations() {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R /Outlines 5 0 R /Dests << /second [4 0 R /Fit] >> \
+             /Names << /Dests << /Names [(first) << /D [3 0 R /Fit] >>] >> >> >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Type /Outlines /First 6 0 R /Last 7 0 R >>",
+            "<< /Title (One) /Dest (first) /Next 7 0 R /First 8 0 R >>",
+            "<< /Title <FEFF00540077006F> /A << /S /GoTo /D /second >> /Next 6 0 R >>",
+            "<< /Title (Child) /Dest [4 0 R /XYZ 0 0 0] >>",
+        ];
+        let data = build_pdf(&objects);
+        let doc = Document::open_bytes(data).unwrap();
+
+        let outline = load_outline(&doc).unwrap();
+        // The /Next cycle back to the first item is not followed
+        assert_eq!(outline.len(), 2);
+        assert_eq!(outline[0].title, "One");
+        assert_eq!(outline[0].page, Some(0));
+        assert_eq!(outline[0].children[0].title, "Child");
+        assert_eq!(outline[0].children[0].page, Some(1));
+        assert_eq!(outline[1].title, "Two");
+        assert_eq!(outline[1].page, Some(1));
+    }
+}
@@ -1 +1,919 @@
 //! PDF page implementation
+
+use crate::fitz::device::Device;
+use crate::fitz::error::{Error, Result};
+use crate::fitz::geometry::{Matrix, Quad, Rect};
+use crate::fitz::stext::{STextDevice, STextOptions, STextPage, TextCallbackDevice};
+use crate::pdf::document::Document;
+use crate::pdf::geometry::rect_from_pdf_array;
+use crate::pdf::name_tree;
+use crate::pdf::object::{Dict, Name, ObjRef, Object};
+use crate::pdf::render::page_interpreter;
+use std::collections::{BTreeMap, HashSet};
+
+/// Points per inch in PDF user space
+pub const POINTS_PER_INCH: f32 = 72.0;
+
+/// Default MediaBox when a page has none (US Letter)
+const DEFAULT_MEDIA_BOX: Rect = Rect {
+    x0: 0.0,
+    y0: 0.0,
+    x1: 612.0,
+    y1: 792.0,
+};
+
+/// Transform from PDF user space to page space for `page_box` shown with
+/// `rotation` degrees clockwise: y grows down and the rotated box has its
+/// top-left corner at the origin
+pub fn page_transform(page_box: Rect, rotation: i32, user_unit: f32) -> Matrix {
+    let u = user_unit;
+    let ctm = match rotation.rem_euclid(360) {
+        90 => Matrix::new(0.0, u, u, 0.0, 0.0, 0.0),
+        180 => Matrix::new(-u, 0.0, 0.0, u, 0.0, 0.0),
+        270 => Matrix::new(0.0, -u, -u, 0.0, 0.0, 0.0),
+        _ => Matrix::new(u, 0.0, 0.0, -u, 0.0, 0.0),
+    };
+    let bounds = page_box.transform(&ctm);
+    ctm.concat(&Matrix::translate(-bounds.x0, -bounds.y0))
+}
+
+/// Displayed page dimensions, after applying CropBox and rotation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Size {
+    pub width_pt: f32,
+    pub height_pt: f32,
+    pub width_in: f32,
+    pub height_in: f32,
+}
+
+impl Size {
+    /// Create a size from dimensions in points
+    pub fn from_points(width_pt: f32, height_pt: f32) -> Self {
+        Self {
+            width_pt,
+            height_pt,
+            width_in: width_pt / POINTS_PER_INCH,
+            height_in: height_pt / POINTS_PER_INCH,
+        }
+    }
+}
+
+/// The page boundaries a page dictionary can define
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageBox {
+    MediaBox,
+    CropBox,
+    BleedBox,
+    TrimBox,
+    ArtBox,
+}
+
+impl PageBox {
+    /// The page dictionary key holding the box
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::MediaBox => "MediaBox",
+            Self::CropBox => "CropBox",
+            Self::BleedBox => "BleedBox",
+            Self::TrimBox => "TrimBox",
+            Self::ArtBox => "ArtBox",
+        }
+    }
+}
+
+/// An inclusive range of zero-based page indices
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRange {
+    pub first: usize,
+    pub last: usize,
+}
+
+impl PageRange {
+    /// Pages `first` through `last`, in either order
+    pub fn new(first: usize, last: usize) -> Self {
+        Self {
+            first: first.min(last),
+            last: first.max(last),
+        }
+    }
+
+    /// A single page
+    pub fn single(index: usize) -> Self {
+        Self::new(index, index)
+    }
+
+    /// Number of pages in the range
+    pub fn len(&self) -> usize {
+        self.last - self.first + 1
+    }
+
+    /// Always false: a range holds at least one page
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// The page indices in order
+    pub fn indices(&self) -> std::ops::RangeInclusive<usize> {
+        self.first..=self.last
+    }
+}
+
+impl From<std::ops::RangeInclusive<usize>> for PageRange {
+    fn from(range: std::ops::RangeInclusive<usize>) -> Self {
+        Self::new(*range.start(), *range.end())
+    }
+}
+
+/// A page from a [`Document`](crate::pdf::document::Document)
+///
+/// The page dictionary has inheritable attributes (Resources, MediaBox,
+/// CropBox, Rotate) already merged in from its ancestors.
+#[derive(Debug, Clone)]
+pub struct Page {
+    index: usize,
+    obj_ref: ObjRef,
+    dict: Dict,
+}
+
+impl Page {
+    pub(crate) fn new(index: usize, obj_ref: ObjRef, dict: Dict) -> Self {
+        Self {
+            index,
+            obj_ref,
+            dict,
+        }
+    }
+
+    /// Zero-based page index
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Reference of the page object
+    pub fn obj_ref(&self) -> ObjRef {
+        self.obj_ref
+    }
+
+    /// The page dictionary
+    pub fn dict(&self) -> &Dict {
+        &self.dict
+    }
+
+    /// The MediaBox, defaulting to US Letter
+    pub fn media_box(&self) -> Rect {
+        self.dict
+            .get("MediaBox")
+            .and_then(rect_from_pdf_array)
+            .filter(|r| !r.is_empty())
+            .unwrap_or(DEFAULT_MEDIA_BOX)
+    }
+
+    /// The effective CropBox: the CropBox clipped to the MediaBox
+    pub fn crop_box(&self) -> Rect {
+        let media = self.media_box();
+        self.dict
+            .get("CropBox")
+            .and_then(rect_from_pdf_array)
+            .map(|crop| crop.intersect(&media))
+            .filter(|r| !r.is_empty())
+            .unwrap_or(media)
+    }
+
+    /// The effective page boundary `which`, with x0 < x1 and y0 < y1
+    ///
+    /// MediaBox defaults to US Letter and CropBox to the MediaBox; the
+    /// bleed, trim and art boxes default to the CropBox. Every box is
+    /// clipped to the MediaBox, and one that ends up empty falls back to
+    /// its default.
+    pub fn page_box(&self, which: PageBox) -> Rect {
+        match which {
+            PageBox::MediaBox => self.media_box(),
+            PageBox::CropBox => self.crop_box(),
+            PageBox::BleedBox | PageBox::TrimBox | PageBox::ArtBox => {
+                let media = self.media_box();
+                self.dict
+                    .get(which.key())
+                    .and_then(rect_from_pdf_array)
+                    .map(|r| r.intersect(&media))
+                    .filter(|r| !r.is_empty())
+                    .unwrap_or_else(|| self.crop_box())
+            }
+        }
+    }
+
+    /// Page rotation in degrees, normalized to 0, 90, 180 or 270
+    pub fn rotation(&self) -> i32 {
+        let rotate = self
+            .dict
+            .get("Rotate")
+            .and_then(Object::as_int)
+            .unwrap_or(0);
+        let rotate = (rotate.rem_euclid(360) as i32 + 45) / 90 * 90;
+        rotate % 360
+    }
+
+    /// Size of a default user space unit in points (/UserUnit)
+    pub fn user_unit(&self) -> f32 {
+        self.dict
+            .get("UserUnit")
+            .and_then(Object::as_real)
+            .filter(|&u| u > 0.0)
+            .map_or(1.0, |u| u as f32)
+    }
+
+    /// Transform from PDF user space to page space, where the rotated
+    /// CropBox has its top-left corner at the origin and y grows down
+    pub fn transform(&self) -> Matrix {
+        page_transform(self.crop_box(), self.rotation(), self.user_unit())
+    }
+
+    /// Transform from PDF user space to device pixels at `dpi`: the
+    /// rotated CropBox maps to `(0, 0)`–`(width, height)` pixels with y
+    /// growing down
+    pub fn render_transform(&self, dpi: f32) -> Matrix {
+        let zoom = dpi / POINTS_PER_INCH;
+        self.transform().concat(&Matrix::scale(zoom, zoom))
+    }
+
+    /// The CropBox in page space: at the origin, sized as displayed
+    pub fn bounds(&self) -> Rect {
+        self.crop_box().transform(&self.transform())
+    }
+
+    /// Displayed size of the page in points of /UserUnit, swapping
+    /// dimensions for 90/270 rotation; the same size as [`Page::bounds`]
+    pub fn size(&self) -> Size {
+        let bounds = self.bounds();
+        Size::from_points(bounds.width(), bounds.height())
+    }
+
+    /// The page's /Resources dictionary
+    ///
+    /// A page without its own /Resources inherits them from the nearest
+    /// ancestor in the page tree that has some; a page with none at all
+    /// gets an empty dictionary.
+    pub fn resources(&self, doc: &Document) -> Result<Dict> {
+        match doc.resolve_key(&self.dict, "Resources")? {
+            Some(Object::Dict(resources)) => Ok(resources),
+            _ => Ok(Dict::new()),
+        }
+    }
+
+    /// The label a viewer shows for the page, from the catalog's /PageLabels
+    ///
+    /// The labelling range is the entry with the largest page index not
+    /// above this page's. Pages before the first range, and documents
+    /// without labels, are numbered in decimal from 1.
+    pub fn label(&self, doc: &Document) -> Result<String> {
+        let catalog = doc.catalog()?;
+        let ranges = match catalog.get("PageLabels") {
+            Some(tree) => name_tree::number_entries(tree, |o| doc.resolve(o))?,
+            None => Vec::new(),
+        };
+        let index = self.index as i64;
+        let Some((start, style)) = ranges.iter().rev().find(|(start, _)| *start <= index) else {
+            return Ok((index + 1).to_string());
+        };
+        let Object::Dict(style) = doc.resolve(style)? else {
+            return Ok((index + 1).to_string());
+        };
+
+        let mut label = match doc.resolve_key(&style, "P")? {
+            Some(Object::String(prefix)) => prefix.to_text(),
+            _ => String::new(),
+        };
+        let first = style
+            .get("St")
+            .and_then(Object::as_int)
+            .filter(|&st| st >= 1);
+        let Some(number) = first.unwrap_or(1).checked_add(index - start) else {
+            label.push_str(&(index + 1).to_string());
+            return Ok(label);
+        };
+        let style = style.get("S").and_then(Object::as_name).map(|s| s.as_str());
+        match style {
+            // Huge numbers would make huge numerals; write them as digits
+            Some("R" | "r" | "A" | "a") if number > MAX_LETTERED_LABEL => {
+                label.push_str(&number.to_string());
+            }
+            Some("D") => label.push_str(&number.to_string()),
+            Some("R") => label.push_str(&roman_numeral(number)),
+            Some("r") => label.push_str(&roman_numeral(number).to_lowercase()),
+            Some("A") => label.push_str(&alphabetic(number)),
+            Some("a") => label.push_str(&alphabetic(number).to_lowercase()),
+            // Without a style the label is the prefix alone
+            _ => {}
+        }
+        Ok(label)
+    }
+
+    /// The page's text, laid out in page space
+    pub fn extract_text(&self, doc: &Document) -> Result<STextPage> {
+        let mut device = STextDevice::new(self.bounds(), STextOptions::default());
+        self.run_text(doc, &mut device)?;
+        Ok(device.finish())
+    }
+
+    /// Pass each character of the page's text and its quad in page space
+    /// to `on_char` as the contents are interpreted
+    ///
+    /// The characters and quads are those of [`Page::extract_text`], in
+    /// content stream order, but none are kept, so memory use does not grow
+    /// with the amount of text.
+    pub fn extract_text_streaming(
+        &self,
+        doc: &Document,
+        on_char: impl FnMut(char, Quad),
+    ) -> Result<()> {
+        self.run_text(doc, &mut TextCallbackDevice::new(on_char))
+    }
+
+    fn run_text(&self, doc: &Document, device: &mut dyn Device) -> Result<()> {
+        let contents = doc.page_contents(self)?;
+        page_interpreter(doc, self, &Matrix::IDENTITY)
+            .interpret(&contents, device)
+            .map_err(Error::format)
+    }
+}
+
+// ============================================================================
+// Page Tree Editing
+// ============================================================================
+
+/// Attributes a page can inherit from its ancestors
+const INHERITABLE: [&str; 4] = ["Resources", "MediaBox", "CropBox", "Rotate"];
+
+/// Deepest page tree walked when updating ancestor counts
+const MAX_TREE_DEPTH: usize = 64;
+
+/// Objects that remove page `index` from the page tree
+///
+/// The page leaves its parent's /Kids and every ancestor's /Count drops by
+/// one. The page object itself is left in the file, unreferenced. Deleting
+/// the only page is rejected, as a page tree must have at least one page.
+pub fn delete_page(doc: &Document, index: usize) -> Result<BTreeMap<ObjRef, Object>> {
+    let count = doc.page_count()?;
+    if count <= 1 {
+        return Err(Error::argument("cannot delete the last page"));
+    }
+    let page_ref = doc.page(index)?.obj_ref();
+    let mut edit = TreeEdit::new(doc);
+    edit.detach(page_ref)?;
+    Ok(edit.changes)
+}
+
+/// Objects that insert the page `page` so it becomes page `at`
+///
+/// `page` is a page object already in the file, or a page dictionary to
+/// add as a new object. It joins the /Kids of the node holding its new
+/// neighbour; `at` equal to the page count appends it.
+pub fn insert_page(doc: &Document, at: usize, page: Object) -> Result<BTreeMap<ObjRef, Object>> {
+    let mut edit = TreeEdit::new(doc);
+    let page_ref = match page {
+        Object::Ref(r) => {
+            if doc.page_index(r)?.is_some() {
+                return Err(Error::argument("page is already in the page tree"));
+            }
+            r
+        }
+        Object::Dict(mut dict) => {
+            dict.insert(Name::new("Type"), Object::Name(Name::new("Page")));
+            let r = edit.new_object();
+            edit.changes.insert(r, Object::Dict(dict));
+            r
+        }
+        _ => return Err(Error::argument("page must be a dictionary or reference")),
+    };
+    let pages: Vec<ObjRef> = (0..doc.page_count()?)
+        .map(|i| doc.page(i).map(|p| p.obj_ref()))
+        .collect::<Result<_>>()?;
+    edit.attach(page_ref, &pages, at)?;
+    Ok(edit.changes)
+}
+
+/// Objects that move page `from` so it becomes page `to`
+///
+/// Attributes the page inherited from its old ancestors are copied into it
+/// so its appearance does not change under a new parent.
+pub fn move_page(doc: &Document, from: usize, to: usize) -> Result<BTreeMap<ObjRef, Object>> {
+    let count = doc.page_count()?;
+    if to >= count {
+        return Err(Error::argument(format!("page {to} out of range")));
+    }
+    let page = doc.page(from)?;
+    let page_ref = page.obj_ref();
+    let mut edit = TreeEdit::new(doc);
+    if from == to {
+        return Ok(edit.changes);
+    }
+
+    let mut dict = edit.dict(page_ref)?;
+    for key in INHERITABLE {
+        if !dict.contains_key(key) {
+            if let Some(value) = page.dict().get(key) {
+                dict.insert(Name::new(key), value.clone());
+            }
+        }
+    }
+    edit.changes.insert(page_ref, Object::Dict(dict));
+
+    edit.detach(page_ref)?;
+    let mut rest = Vec::with_capacity(count - 1);
+    for i in (0..count).filter(|&i| i != from) {
+        rest.push(doc.page(i)?.obj_ref());
+    }
+    edit.attach(page_ref, &rest, to)?;
+    Ok(edit.changes)
+}
+
+/// Page tree changes being built up, read back before the original file
+struct TreeEdit<'a> {
+    doc: &'a Document,
+    changes: BTreeMap<ObjRef, Object>,
+    next_num: i32,
+}
+
+impl<'a> TreeEdit<'a> {
+    fn new(doc: &'a Document) -> Self {
+        let next_num = doc
+            .trailer()
+            .get("Size")
+            .and_then(Object::as_int)
+            .unwrap_or(1)
+            .max(1) as i32;
+        Self {
+            doc,
+            changes: BTreeMap::new(),
+            next_num,
+        }
+    }
+
+    fn new_object(&mut self) -> ObjRef {
+        self.next_num += 1;
+        ObjRef::new(self.next_num - 1, 0)
+    }
+
+    fn dict(&self, r: ObjRef) -> Result<Dict> {
+        let obj = match self.changes.get(&r) {
+            Some(obj) => obj.clone(),
+            None => self.doc.load_object(r)?,
+        };
+        match obj {
+            Object::Dict(dict) => Ok(dict),
+            _ => Err(Error::format(format!(
+                "object {} is not a dictionary",
+                r.num
+            ))),
+        }
+    }
+
+    fn parent(&self, r: ObjRef) -> Result<ObjRef> {
+        self.dict(r)?
+            .get("Parent")
+            .and_then(Object::as_obj_ref)
+            .ok_or_else(|| Error::format("page tree node has no /Parent"))
+    }
+
+    /// Kids of a node, with the reference of the array if it is indirect
+    fn kids(&self, node: &Dict) -> Result<(Option<ObjRef>, Vec<Object>)> {
+        match node.get("Kids") {
+            Some(Object::Ref(r)) => {
+                let kids = match self.changes.get(r) {
+                    Some(obj) => obj.clone(),
+                    None => self.doc.load_object(*r)?,
+                };
+                match kids {
+                    Object::Array(kids) => Ok((Some(*r), kids)),
+                    _ => Err(Error::format("/Kids is not an array")),
+                }
+            }
+            Some(Object::Array(kids)) => Ok((None, kids.clone())),
+            _ => Ok((None, Vec::new())),
+        }
+    }
+
+    fn set_kids(
+        &mut self,
+        node_ref: ObjRef,
+        mut node: Dict,
+        kids: Vec<Object>,
+        array: Option<ObjRef>,
+    ) {
+        match array {
+            Some(r) => {
+                self.changes.insert(r, Object::Array(kids));
+            }
+            None => {
+                node.insert(Name::new("Kids"), Object::Array(kids));
+            }
+        }
+        self.changes.insert(node_ref, Object::Dict(node));
+    }
+
+    /// Add `delta` to the /Count of `node_ref` and each of its ancestors
+    fn adjust_counts(&mut self, node_ref: ObjRef, delta: i64) -> Result<()> {
+        let mut visited = HashSet::new();
+        let mut current = Some(node_ref);
+        while let Some(r) = current.filter(|r| visited.insert(*r)) {
+            if visited.len() > MAX_TREE_DEPTH {
+                break;
+            }
+            let mut node = self.dict(r)?;
+            let count = node.get("Count").and_then(Object::as_int).unwrap_or(0);
+            node.insert(Name::new("Count"), Object::Int((count + delta).max(0)));
+            current = node.get("Parent").and_then(Object::as_obj_ref);
+            self.changes.insert(r, Object::Dict(node));
+        }
+        Ok(())
+    }
+
+    /// Take a page out of its parent's /Kids
+    fn detach(&mut self, page_ref: ObjRef) -> Result<()> {
+        let parent_ref = self.parent(page_ref)?;
+        let parent = self.dict(parent_ref)?;
+        let (array, mut kids) = self.kids(&parent)?;
+        kids.retain(|k| k.as_obj_ref() != Some(page_ref));
+        self.set_kids(parent_ref, parent, kids, array);
+        self.adjust_counts(parent_ref, -1)
+    }
+
+    /// Put a page into the tree at index `at` of `pages`, the page order
+    /// without it, next to the page it will follow or precede
+    fn attach(&mut self, page_ref: ObjRef, pages: &[ObjRef], at: usize) -> Result<()> {
+        if at > pages.len() {
+            return Err(Error::argument(format!("page {at} out of range")));
+        }
+        let (anchor, before) = match pages.get(at) {
+            Some(&next) => (next, true),
+            None => (
+                *pages
+                    .last()
+                    .ok_or_else(|| Error::argument("page tree is empty"))?,
+                false,
+            ),
+        };
+        let parent_ref = self.parent(anchor)?;
+        let parent = self.dict(parent_ref)?;
+        let (array, mut kids) = self.kids(&parent)?;
+        let pos = kids
+            .iter()
+            .position(|k| k.as_obj_ref() == Some(anchor))
+            .ok_or_else(|| Error::format("page is missing from its parent's /Kids"))?;
+        kids.insert(if before { pos } else { pos + 1 }, Object::Ref(page_ref));
+        self.set_kids(parent_ref, parent, kids, array);
+
+        let mut page = self.dict(page_ref)?;
+        page.insert(Name::new("Parent"), Object::Ref(parent_ref));
+        self.changes.insert(page_ref, Object::Dict(page));
+        self.adjust_counts(parent_ref, 1)
+    }
+}
+
+/// Largest page number labelled with roman numerals or letters
+const MAX_LETTERED_LABEL: i64 = 4999;
+
+/// Upper-case roman numeral; thousands beyond MMM repeat M
+fn roman_numeral(mut n: i64) -> String {
+    const NUMERALS: [(i64, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            out.push_str(numeral);
+            n -= value;
+        }
+    }
+    out
+}
+
+/// Upper-case letters numbered like spreadsheet columns: A to Z, then
+/// AA, AB and so on
+fn alphabetic(mut n: i64) -> String {
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push(b'A' + (n % 26) as u8);
+        n /= 26;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{alphabetic, roman_numeral};
+    use crate::fitz::geometry::{Point, Rect};
+    use crate::pdf::document::Document;
+    use crate::pdf::test_pdf::build_pdf;
+
+    fn letter_pdf(page_extra: &str) -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 612 792] >>".to_string(),
+            format!("<< /Type /Page /Parent 2 0 R {page_extra} >>"),
+        ];
+        build_pdf(&objects)
+    }
+
+    #[test]
+    fn test_page_size_rotated_letter() {
+        let doc = Document::open_bytes(letter_pdf("/Rotate 90")).unwrap();
+        let size = doc.page_size(0).unwrap();
+        assert_eq!((size.width_pt, size.height_pt), (792.0, 612.0));
+        assert_eq!(size.width_in, size.width_pt / 72.0);
+        assert_eq!(size.height_in, 8.5);
+
+        let doc = Document::open_bytes(letter_pdf("/Rotate -270")).unwrap();
+        assert_eq!(doc.page(0).unwrap().rotation(), 90);
+    }
+
+    #[test]
+    fn test_streaming_text_matches_extract_text() {
+        let content = "BT /F1 12 Tf 72 700 Td (First line) Tj 0 -14 Td (second) Tj ET \
+                       BT /F1 10 Tf 300 100 Td (apart) Tj ET";
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 612 792] >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /Contents 4 0 R \
+             /Resources << /Font << /F1 << /Type /Font /Subtype /Type1 \
+             /BaseFont /Helvetica >> >> >> >>"
+                .to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ),
+        ];
+        let out = build_pdf(&objects);
+        let doc = Document::open_bytes(out).unwrap();
+        let page = doc.page(0).unwrap();
+
+        let mut streamed = Vec::new();
+        page.extract_text_streaming(&doc, |c, quad| streamed.push((c, quad)))
+            .unwrap();
+        let buffered: Vec<_> = page
+            .extract_text(&doc)
+            .unwrap()
+            .blocks
+            .iter()
+            .flat_map(|block| &block.lines)
+            .flat_map(|line| &line.chars)
+            .map(|ch| (ch.c, ch.quad))
+            .collect();
+
+        let text: String = streamed.iter().map(|&(c, _)| c).collect();
+        assert_eq!(text, "First linesecondapart");
+        assert_eq!(streamed, buffered);
+    }
+
+    #[test]
+    fn test_rotated_page_transform() {
+        let doc = Document::open_bytes(letter_pdf("/Rotate 90")).unwrap();
+        let page = doc.page(0).unwrap();
+        assert_eq!(page.bounds(), Rect::new(0.0, 0.0, 792.0, 612.0));
+        // The bottom-left corner of user space ends up at the top left
+        let ctm = page.transform();
+        assert_eq!(
+            ctm.transform_point(Point::new(0.0, 0.0)),
+            Point::new(0.0, 0.0)
+        );
+        assert_eq!(
+            ctm.transform_point(Point::new(612.0, 0.0)),
+            Point::new(0.0, 612.0)
+        );
+
+        let doc = Document::open_bytes(letter_pdf("")).unwrap();
+        let ctm = doc.page(0).unwrap().transform();
+        assert_eq!(
+            ctm.transform_point(Point::new(0.0, 792.0)),
+            Point::new(0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_render_transform_at_dpi() {
+        let doc = Document::open_bytes(letter_pdf("/Rotate 90")).unwrap();
+        let page = doc.page(0).unwrap();
+        let ctm = page.render_transform(144.0);
+        let pixels = page.crop_box().transform(&ctm);
+        assert_eq!(pixels, Rect::new(0.0, 0.0, 1584.0, 1224.0));
+        // User space origin (bottom left) lands at the top left
+        assert_eq!(
+            ctm.transform_point(Point::new(0.0, 0.0)),
+            Point::new(0.0, 0.0)
+        );
+
+        let doc = Document::open_bytes(letter_pdf("")).unwrap();
+        let ctm = doc.page(0).unwrap().render_transform(144.0);
+        assert_eq!(
+            ctm.transform_point(Point::new(0.0, 0.0)),
+            Point::new(0.0, 1584.0)
+        );
+    }
+
+    #[test]
+    fn test_move_page_across_intermediate_nodes() {
+        use super::{delete_page, move_page};
+        use crate::pdf::object::{ObjRef, Object};
+
+        // Root 2 has kids 3 (pages 5, 6) and 4 (page 7, which inherits a
+        // MediaBox from node 4)
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 3 >>",
+            "<< /Type /Pages /Parent 2 0 R /Kids [5 0 R 6 0 R] /Count 2 >>",
+            "<< /Type /Pages /Parent 2 0 R /Kids [7 0 R] /Count 1 /MediaBox [0 0 100 50] >>",
+            "<< /Type /Page /Parent 3 0 R >>",
+            "<< /Type /Page /Parent 3 0 R >>",
+            "<< /Type /Page /Parent 4 0 R >>",
+        ];
+        let data = build_pdf(&objects);
+        let doc = Document::open_bytes(data).unwrap();
+        let dict = |changes: &std::collections::BTreeMap<ObjRef, Object>, num| {
+            changes[&ObjRef::new(num, 0)].as_dict().unwrap().clone()
+        };
+        let count = |d: &crate::pdf::object::Dict| d.get("Count").and_then(Object::as_int);
+
+        let changes = move_page(&doc, 2, 0).unwrap();
+        let (root, left, right) = (dict(&changes, 2), dict(&changes, 3), dict(&changes, 4));
+        assert_eq!(
+            (count(&root), count(&left), count(&right)),
+            (Some(3), Some(3), Some(0))
+        );
+        assert_eq!(
+            left.get("Kids"),
+            Some(&Object::Array(vec![
+                Object::Ref(ObjRef::new(7, 0)),
+                Object::Ref(ObjRef::new(5, 0)),
+                Object::Ref(ObjRef::new(6, 0)),
+            ]))
+        );
+        let moved = dict(&changes, 7);
+        assert_eq!(moved.get("Parent"), Some(&Object::Ref(ObjRef::new(3, 0))));
+        assert!(moved.contains_key("MediaBox"));
+
+        let changes = delete_page(&doc, 0).unwrap();
+        assert_eq!(count(&dict(&changes, 3)), Some(1));
+        assert_eq!(count(&dict(&changes, 2)), Some(2));
+    }
+
+    #[test]
+    fn test_page_boxes_fall_back_and_clip() {
+        use super::PageBox;
+
+        // MediaBox inherited from the Pages node, CropBox inside it
+        let doc = Document::open_bytes(letter_pdf(
+            "/CropBox [36 36 576 756] /BleedBox [0 0 700 900]",
+        ))
+        .unwrap();
+        let page = doc.page(0).unwrap();
+        let letter = Rect::new(0.0, 0.0, 612.0, 792.0);
+        let crop = Rect::new(36.0, 36.0, 576.0, 756.0);
+        assert_eq!(page.page_box(PageBox::MediaBox), letter);
+        assert_eq!(page.page_box(PageBox::CropBox), crop);
+        // Larger than the MediaBox: clipped to it
+        assert_eq!(page.page_box(PageBox::BleedBox), letter);
+        // Missing: the CropBox
+        assert_eq!(page.page_box(PageBox::TrimBox), crop);
+        assert_eq!(page.page_box(PageBox::ArtBox), crop);
+    }
+
+    #[test]
+    fn test_page_boxes_with_swapped_corners() {
+        use super::PageBox;
+
+        let doc = Document::open_bytes(letter_pdf(
+            "/CropBox [600 780 10 20] /TrimBox [500 700 50 100] /ArtBox [10 10 10 500]",
+        ))
+        .unwrap();
+        let page = doc.page(0).unwrap();
+        assert_eq!(
+            page.page_box(PageBox::CropBox),
+            Rect::new(10.0, 20.0, 600.0, 780.0)
+        );
+        let trim = page.page_box(PageBox::TrimBox);
+        assert_eq!(trim, Rect::new(50.0, 100.0, 500.0, 700.0));
+        assert!(trim.height() > 0.0 && trim.width() > 0.0);
+        // Zero width: falls back to the CropBox
+        assert_eq!(
+            page.page_box(PageBox::ArtBox),
+            Rect::new(10.0, 20.0, 600.0, 780.0)
+        );
+    }
+
+    #[test]
+    fn test_page_size_uses_crop_box() {
+        let doc = Document::open_bytes(letter_pdf("/CropBox [36 36 576 1000]")).unwrap();
+        let size = doc.page(0).unwrap().size();
+        assert_eq!((size.width_pt, size.height_pt), (540.0, 756.0));
+        assert_eq!(size.width_in, 7.5);
+    }
+
+    #[test]
+    fn test_page_size_scaled_by_user_unit() {
+        let doc = Document::open_bytes(letter_pdf("/UserUnit 2 /Rotate 90")).unwrap();
+        let page = doc.page(0).unwrap();
+        let size = page.size();
+        assert_eq!((size.width_pt, size.height_pt), (1584.0, 1224.0));
+        assert_eq!(size.width_pt, page.bounds().width());
+        assert_eq!(size.height_in, 17.0);
+    }
+
+    #[test]
+    fn test_resources_inherited_from_pages_node() {
+        let pdf = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 /Resources << /Font << /F1 4 0 R >> >> >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>",
+        ]);
+
+        let doc = Document::open_bytes(pdf).unwrap();
+        let resources = doc.page(0).unwrap().resources(&doc).unwrap();
+        let font = doc.lookup_resource(&resources, "Font", "F1").unwrap();
+        let Some(crate::pdf::object::Object::Dict(font)) = font else {
+            panic!("font did not resolve");
+        };
+        assert_eq!(
+            font.get("BaseFont")
+                .and_then(|o| o.as_name())
+                .map(|n| n.as_str()),
+            Some("Helvetica")
+        );
+        assert!(
+            doc.lookup_resource(&resources, "Font", "F2")
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            doc.lookup_resource(&resources, "XObject", "Im0")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    /// Six pages labelled by the number tree entries `nums`
+    fn labelled_pdf(nums: &str) -> Vec<u8> {
+        let mut objects = vec![
+            format!("<< /Type /Catalog /Pages 2 0 R /PageLabels << /Nums [{nums}] >> >>"),
+            "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R 6 0 R 7 0 R 8 0 R] /Count 6 >>".to_string(),
+        ];
+        objects.extend((0..6).map(|_| "<< /Type /Page /Parent 2 0 R >>".to_string()));
+        build_pdf(&objects)
+    }
+
+    #[test]
+    fn test_page_labels_roman_then_decimal() {
+        let doc = Document::open_bytes(labelled_pdf("0 << /S /r >> 3 << /S /D >>")).unwrap();
+        let labels: Vec<String> = (0..6)
+            .map(|i| doc.page(i).unwrap().label(&doc).unwrap())
+            .collect();
+        assert_eq!(labels, ["i", "ii", "iii", "1", "2", "3"]);
+
+        // Without /PageLabels pages are numbered from 1
+        let doc = Document::open_bytes(letter_pdf("")).unwrap();
+        assert_eq!(doc.page(0).unwrap().label(&doc).unwrap(), "1");
+    }
+
+    #[test]
+    fn test_page_labels_with_huge_start() {
+        let label = |nums: &str, i: usize| {
+            let doc = Document::open_bytes(labelled_pdf(nums)).unwrap();
+            doc.page(i).unwrap().label(&doc).unwrap()
+        };
+        // Numerals that long are written as digits
+        let nums = "0 << /S /R /St 1000000000000 >> 3 << /S /a /St 5000 >>";
+        assert_eq!(label(nums, 1), "1000000000001");
+        assert_eq!(label(nums, 4), "5001");
+        assert_eq!(label("0 << /S /R /St 4999 >>", 0), "MMMMCMXCIX");
+        // A start that overflows falls back to the page number
+        assert_eq!(
+            label("0 << /P (p) /S /D /St 9223372036854775807 >>", 2),
+            "p3"
+        );
+    }
+
+    #[test]
+    fn test_label_numbering_styles() {
+        assert_eq!(roman_numeral(4), "IV");
+        assert_eq!(roman_numeral(1994), "MCMXCIV");
+        assert_eq!(roman_numeral(4000), "MMMM");
+        assert_eq!(alphabetic(1), "A");
+        assert_eq!(alphabetic(26), "Z");
+        assert_eq!(alphabetic(27), "AA");
+        assert_eq!(alphabetic(52), "AZ");
+        assert_eq!(alphabetic(703), "AAA");
+    }
+}
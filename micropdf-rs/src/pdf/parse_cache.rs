@@ -0,0 +1,237 @@
+//! Content-addressed cache of parsed document structure
+//!
+//! Servers often open the same bytes again and again. The object offsets
+//! and trailer found when a document is first opened are kept here, keyed
+//! by the length and an MD5 of the ends of its bytes, so later opens of
+//! the same file skip the scan. The cache's byte budget follows the
+//! resource store's.
+
+use crate::fitz::error::{Error, Result};
+use crate::pdf::object::{Dict, ObjRef};
+use crate::pdf::parser::{self, Parser};
+use crate::pdf::xref::XrefTable;
+use bytes::Bytes;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
+
+/// Default budget, matching the resource store's default maximum size
+const DEFAULT_BUDGET: usize = 256 << 20;
+
+/// Bytes hashed from each end of a document for its key
+const KEY_WINDOW: usize = 64 << 10;
+
+/// Approximate cost of one object offset entry, including table overhead
+const OFFSET_ENTRY_SIZE: usize = 32;
+
+/// Approximate cost of one trailer or catalog entry
+const DICT_ENTRY_SIZE: usize = 64;
+
+thread_local! {
+    static SCANS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Structure found by scanning a document's bytes
+#[derive(Debug)]
+pub struct ParsedXref {
+    /// Offset of the last definition of each object
+    pub offsets: HashMap<ObjRef, usize>,
+    /// Object stream number and index of each object stored in one
+    pub compressed: HashMap<ObjRef, (i32, usize)>,
+    /// The trailer dictionary
+    pub trailer: Dict,
+    /// The catalog, once loaded, for documents that are not encrypted
+    pub(crate) catalog: OnceLock<Dict>,
+}
+
+impl ParsedXref {
+    /// Read the structure of `data` from its cross-reference sections, or
+    /// by scanning for object definitions when they are missing or damaged
+    pub fn load(data: &Bytes) -> Result<Self> {
+        SCANS.with(|scans| scans.set(scans.get() + 1));
+        match Self::read(data) {
+            Some(xref) => Ok(xref),
+            None => Self::recover(data),
+        }
+    }
+
+    /// Take offsets from the cross-reference sections, provided every
+    /// in-use entry points at the object it names
+    fn read(data: &Bytes) -> Option<Self> {
+        let table = XrefTable::parse(data.clone()).ok()?;
+        table.trailer().get("Root")?;
+        let mut offsets = HashMap::new();
+        let mut compressed = HashMap::new();
+        for num in table.object_numbers() {
+            let entry = table.get(num)?;
+            if entry.is_compressed() {
+                let stm_num = i32::try_from(entry.offset).ok()?;
+                compressed.insert(ObjRef::new(num, 0), (stm_num, usize::from(entry.stm_index)));
+                continue;
+            }
+            if !entry.is_in_use() {
+                continue;
+            }
+            let obj_ref = ObjRef::new(num, i32::from(entry.generation));
+            let offset = usize::try_from(entry.offset).ok()?;
+            let found = Parser::at(data, offset).parse_object_header().ok()?;
+            if found != obj_ref {
+                return None;
+            }
+            offsets.insert(obj_ref, offset);
+        }
+        let mut xref = Self::with_offsets(offsets, table.trailer().clone());
+        xref.compressed = compressed;
+        Some(xref)
+    }
+
+    /// Rebuild the structure by scanning for `num gen obj` definitions,
+    /// recovering the trailer from the catalog if it is lost too
+    pub fn recover(data: &[u8]) -> Result<Self> {
+        let offsets = parser::scan_object_offsets(data);
+        let trailer = match parser::find_trailer(data) {
+            Some(trailer) if trailer.contains_key("Root") => Some(trailer),
+            found => parser::recover_trailer(data, &offsets).or(found),
+        }
+        .ok_or_else(|| Error::format("no trailer"))?;
+        Ok(Self::with_offsets(offsets, trailer))
+    }
+
+    /// Wrap offsets that have already been found
+    pub fn with_offsets(offsets: HashMap<ObjRef, usize>, trailer: Dict) -> Self {
+        Self {
+            offsets,
+            compressed: HashMap::new(),
+            trailer,
+            catalog: OnceLock::new(),
+        }
+    }
+
+    /// Approximate memory used by the entry
+    fn size(&self) -> usize {
+        let catalog = self.catalog.get().map_or(0, |c| c.len());
+        (self.offsets.len() + self.compressed.len()) * OFFSET_ENTRY_SIZE
+            + (self.trailer.len() + catalog) * DICT_ENTRY_SIZE
+    }
+}
+
+/// Number of times document structure was read on the current thread
+pub fn xref_scan_count() -> u64 {
+    SCANS.with(Cell::get)
+}
+
+struct CacheEntry {
+    parsed: Arc<ParsedXref>,
+    last_used: u64,
+}
+
+/// Parsed structure keyed by the MD5 of the document bytes
+pub struct ParseCache {
+    entries: Mutex<HashMap<[u8; 16], CacheEntry>>,
+    budget: AtomicUsize,
+    clock: AtomicU64,
+}
+
+impl ParseCache {
+    /// Create an empty cache with a byte budget
+    pub fn new(budget: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            budget: AtomicUsize::new(budget),
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    /// Key for a document's bytes
+    ///
+    /// Hashing every byte would read every page of a memory-mapped file,
+    /// so the key covers the length, the header and the end of the file,
+    /// where the trailer, `startxref` and newest cross-reference section
+    /// are. Saving a file or appending an update changes its end; files of
+    /// one length with the same ends are taken to be the same.
+    pub fn key(data: &Bytes) -> [u8; 16] {
+        use md5::{Digest, Md5};
+
+        let mut hasher = Md5::new();
+        hasher.update((data.len() as u64).to_le_bytes());
+        if data.len() <= 2 * KEY_WINDOW {
+            hasher.update(data);
+        } else {
+            hasher.update(&data[..KEY_WINDOW]);
+            hasher.update(&data[data.len() - KEY_WINDOW..]);
+        }
+        hasher.finalize().into()
+    }
+
+    /// The cached structure for `key`, if any
+    pub fn get(&self, key: &[u8; 16]) -> Option<Arc<ParsedXref>> {
+        let mut entries = self.entries.lock().ok()?;
+        let entry = entries.get_mut(key)?;
+        entry.last_used = self.clock.fetch_add(1, Ordering::Relaxed);
+        Some(entry.parsed.clone())
+    }
+
+    /// Cache `parsed` under `key`, evicting the least recently used
+    /// entries to stay within budget
+    pub fn insert(&self, key: [u8; 16], parsed: Arc<ParsedXref>) {
+        if let Ok(mut entries) = self.entries.lock() {
+            let last_used = self.clock.fetch_add(1, Ordering::Relaxed);
+            entries.insert(key, CacheEntry { parsed, last_used });
+            evict(&mut entries, self.budget.load(Ordering::Relaxed));
+        }
+    }
+
+    /// The byte budget
+    pub fn budget(&self) -> usize {
+        self.budget.load(Ordering::Relaxed)
+    }
+
+    /// Change the byte budget, evicting entries that no longer fit
+    pub fn set_budget(&self, budget: usize) {
+        self.budget.store(budget, Ordering::Relaxed);
+        if let Ok(mut entries) = self.entries.lock() {
+            evict(&mut entries, budget);
+        }
+    }
+
+    /// Number of cached documents
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.len()).unwrap_or(0)
+    }
+
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop all entries
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+fn evict(entries: &mut HashMap<[u8; 16], CacheEntry>, budget: usize) {
+    let mut total: usize = entries.values().map(|e| e.parsed.size()).sum();
+    while total > budget {
+        let Some(key) = entries
+            .iter()
+            .min_by_key(|(_, e)| e.last_used)
+            .map(|(key, _)| *key)
+        else {
+            break;
+        };
+        if let Some(entry) = entries.remove(&key) {
+            total = total.saturating_sub(entry.parsed.size());
+        }
+    }
+}
+
+static PARSE_CACHE: LazyLock<ParseCache> = LazyLock::new(|| ParseCache::new(DEFAULT_BUDGET));
+
+/// The cache used by [`Document::open_bytes`](crate::pdf::document::Document::open_bytes)
+pub fn parse_cache() -> &'static ParseCache {
+    &PARSE_CACHE
+}
@@ -1 +1,527 @@
 //! PDF parser
+//!
+//! Builds [`Object`] values from the token stream produced by the [`Lexer`],
+//! including indirect objects (`N G obj ... endobj`) and their stream data.
+
+use crate::fitz::error::{Error, Result};
+use crate::fitz::warning;
+use crate::pdf::lexer::{LexBuf, Lexer, Token};
+use crate::pdf::limits::parse_limits;
+use crate::pdf::object::{Array, Dict, Name, ObjRef, Object, PdfString};
+use std::collections::HashMap;
+
+/// An operand or operator read from a content stream
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Object(Object),
+    Keyword(String),
+    Eof,
+}
+
+/// Object parser over a byte slice
+pub struct Parser<'a> {
+    lexer: Lexer<'a>,
+    buf: LexBuf,
+    max_nesting: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Create a parser positioned at the start of `data`
+    pub fn new(data: &'a [u8]) -> Self {
+        Self::at(data, 0)
+    }
+
+    /// Create a parser positioned at `offset`
+    pub fn at(data: &'a [u8], offset: usize) -> Self {
+        let mut lexer = Lexer::new(data);
+        lexer.seek(offset);
+        Self {
+            lexer,
+            buf: LexBuf::new(),
+            max_nesting: parse_limits().max_nesting,
+        }
+    }
+
+    /// Current byte offset
+    pub fn pos(&self) -> usize {
+        self.lexer.pos()
+    }
+
+    /// Move to a byte offset
+    pub fn seek(&mut self, pos: usize) {
+        self.lexer.seek(pos);
+    }
+
+    /// The bytes being parsed
+    pub fn data(&self) -> &'a [u8] {
+        self.lexer.data()
+    }
+
+    /// Parse the next object or bare keyword, as found in content streams
+    pub fn parse_item(&mut self) -> Result<Item> {
+        let token = self.lexer.lex(&mut self.buf)?;
+        match token {
+            Token::Eof => Ok(Item::Eof),
+            Token::OpenBrace => Ok(Item::Keyword("{".into())),
+            Token::CloseBrace => Ok(Item::Keyword("}".into())),
+            Token::CloseArray => Ok(Item::Keyword("]".into())),
+            Token::CloseDict => Ok(Item::Keyword(">>".into())),
+            Token::Null
+            | Token::True
+            | Token::False
+            | Token::Real
+            | Token::Int
+            | Token::Name
+            | Token::String
+            | Token::OpenArray
+            | Token::OpenDict => self.parse_value(token, 0).map(Item::Object),
+            _ => Ok(Item::Keyword(self.buf.as_str().to_string())),
+        }
+    }
+
+    /// Parse the next direct object
+    pub fn parse_object(&mut self) -> Result<Object> {
+        let token = self.lexer.lex(&mut self.buf)?;
+        self.parse_value(token, 0)
+    }
+
+    /// Parse an indirect object definition at the current position
+    ///
+    /// Stream data is read using a direct `/Length` when it is valid, and by
+    /// scanning for `endstream` otherwise (indirect or damaged lengths).
+    pub fn parse_indirect_object(&mut self) -> Result<(ObjRef, Object)> {
+        let obj_ref = self.parse_object_header()?;
+        let (num, generation) = (obj_ref.num, obj_ref.generation);
+
+        let token = self.lexer.lex(&mut self.buf)?;
+        if token == Token::EndObj {
+            return Ok((obj_ref, Object::Null));
+        }
+        let object = self.parse_value(token, 0)?;
+
+        let after_object = self.pos();
+        match self.lexer.lex(&mut self.buf)? {
+            Token::Stream => {
+                let dict = match object {
+                    Object::Dict(d) => d,
+                    _ => return Err(Error::syntax("stream without dictionary")),
+                };
+                let data = self.read_stream_data(&dict)?;
+                Ok((obj_ref, Object::Stream { dict, data }))
+            }
+            Token::EndObj => Ok((obj_ref, object)),
+            _ => {
+                // Missing endobj is common in damaged files; accept the object
+                warning::warn(
+                    "parser",
+                    &format!("missing endobj after object {num} {generation}"),
+                );
+                self.seek(after_object);
+                Ok((obj_ref, object))
+            }
+        }
+    }
+
+    /// Read a `num gen obj` header at the current position
+    pub fn parse_object_header(&mut self) -> Result<ObjRef> {
+        let num = self.expect_int()?;
+        let generation = self.expect_int()?;
+        if self.lexer.lex(&mut self.buf)? != Token::Obj {
+            return Err(Error::syntax(format!(
+                "expected 'obj' after {num} {generation}"
+            )));
+        }
+        Ok(ObjRef::new(num as i32, generation as i32))
+    }
+
+    fn expect_int(&mut self) -> Result<i64> {
+        match self.lexer.lex(&mut self.buf)? {
+            Token::Int => Ok(self.buf.as_int()),
+            t => Err(Error::syntax(format!("expected integer, found {t:?}"))),
+        }
+    }
+
+    fn parse_value(&mut self, token: Token, depth: usize) -> Result<Object> {
+        if depth > self.max_nesting {
+            return Err(Error::limit("object nesting too deep"));
+        }
+        match token {
+            Token::Null => Ok(Object::Null),
+            Token::True => Ok(Object::Bool(true)),
+            Token::False => Ok(Object::Bool(false)),
+            Token::Real => Ok(Object::Real(self.buf.as_float())),
+            Token::Name => Ok(Object::Name(Name::new(self.buf.as_str()))),
+            Token::String => Ok(Object::String(PdfString::new(self.buf.as_bytes()))),
+            Token::Int => self.parse_int_or_ref(),
+            Token::OpenArray => self.parse_array(depth + 1),
+            Token::OpenDict => self.parse_dict(depth + 1),
+            Token::Eof => Err(Error::Eof),
+            t => Err(Error::syntax(format!(
+                "unexpected token {t:?} '{}'",
+                self.buf.as_str()
+            ))),
+        }
+    }
+
+    /// An integer may be the start of an `N G R` reference
+    fn parse_int_or_ref(&mut self) -> Result<Object> {
+        let value = self.buf.as_int();
+        let save = self.pos();
+
+        if let Ok(Token::Int) = self.lexer.lex(&mut self.buf) {
+            let generation = self.buf.as_int();
+            if let Ok(Token::R) = self.lexer.lex(&mut self.buf) {
+                return Ok(Object::Ref(ObjRef::new(value as i32, generation as i32)));
+            }
+        }
+
+        self.seek(save);
+        Ok(Object::Int(value))
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<Object> {
+        let mut array = Array::new();
+        loop {
+            let token = self.lexer.lex(&mut self.buf)?;
+            match token {
+                Token::CloseArray => break,
+                Token::Eof => return Err(Error::syntax("unterminated array")),
+                _ => array.push(self.parse_value(token, depth)?),
+            }
+        }
+        Ok(Object::Array(array))
+    }
+
+    fn parse_dict(&mut self, depth: usize) -> Result<Object> {
+        let mut dict = Dict::new();
+        loop {
+            match self.lexer.lex(&mut self.buf)? {
+                Token::CloseDict => break,
+                Token::Name => {
+                    let key = Name::new(self.buf.as_str());
+                    let token = self.lexer.lex(&mut self.buf)?;
+                    if token == Token::CloseDict {
+                        // Key without a value: treat as null and finish
+                        dict.insert(key, Object::Null);
+                        break;
+                    }
+                    let value = self.parse_value(token, depth)?;
+                    dict.insert(key, value);
+                }
+                Token::Eof => return Err(Error::syntax("unterminated dictionary")),
+                t => return Err(Error::syntax(format!("expected name key, found {t:?}"))),
+            }
+        }
+        Ok(Object::Dict(dict))
+    }
+
+    fn read_stream_data(&mut self, dict: &Dict) -> Result<Vec<u8>> {
+        let data = self.lexer.data();
+        let mut start = self.pos();
+        // The 'stream' keyword is followed by CRLF or LF
+        if data.get(start) == Some(&b'\r') {
+            start += 1;
+        }
+        if data.get(start) == Some(&b'\n') {
+            start += 1;
+        }
+
+        if let Some(len) = dict.get("Length").and_then(Object::as_int) {
+            let end = start.saturating_add(len.max(0) as usize);
+            if end <= data.len() && followed_by_endstream(data, end) {
+                self.seek(end);
+                self.lexer.lex(&mut self.buf)?; // endstream
+                self.skip_endobj();
+                return Ok(data[start..end].to_vec());
+            }
+            warning::warn(
+                "parser",
+                &format!("stream /Length {len} is wrong; scanning for endstream"),
+            );
+        }
+
+        let end = find_bytes(data, b"endstream", start)
+            .ok_or_else(|| Error::syntax("missing endstream"))?;
+        let mut data_end = end;
+        if data_end > start && data[data_end - 1] == b'\n' {
+            data_end -= 1;
+        }
+        if data_end > start && data[data_end - 1] == b'\r' {
+            data_end -= 1;
+        }
+        self.seek(end + b"endstream".len());
+        self.skip_endobj();
+        Ok(data[start..data_end].to_vec())
+    }
+
+    fn skip_endobj(&mut self) {
+        let save = self.pos();
+        if !matches!(self.lexer.lex(&mut self.buf), Ok(Token::EndObj)) {
+            self.seek(save);
+        }
+    }
+}
+
+fn followed_by_endstream(data: &[u8], pos: usize) -> bool {
+    let rest = &data[pos..];
+    let trimmed = rest
+        .iter()
+        .position(|b| !matches!(b, b' ' | b'\t' | b'\r' | b'\n' | b'\x0C'))
+        .map(|i| &rest[i..])
+        .unwrap_or(&[]);
+    trimmed.starts_with(b"endstream")
+}
+
+/// Find the first occurrence of `needle` at or after `from`
+pub fn find_bytes(data: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if from >= data.len() || needle.is_empty() {
+        return None;
+    }
+    data[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|i| i + from)
+}
+
+/// Find the last occurrence of `needle` in `data`
+pub fn rfind_bytes(data: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() || needle.len() > data.len() {
+        return None;
+    }
+    data.windows(needle.len()).rposition(|w| w == needle)
+}
+
+/// Parse a direct object from a byte slice
+pub fn parse_object(data: &[u8]) -> Result<Object> {
+    Parser::new(data).parse_object()
+}
+
+/// Parse the indirect object definition starting at `offset`
+pub fn parse_indirect_object_at(data: &[u8], offset: usize) -> Result<(ObjRef, Object)> {
+    Parser::at(data, offset).parse_indirect_object()
+}
+
+/// Locate the byte offset of the definition `num gen obj` by scanning.
+///
+/// When an object is redefined by incremental updates the last definition
+/// wins, matching how a reader applies updates.
+pub fn find_object_offset(data: &[u8], num: i32, generation: i32) -> Option<usize> {
+    let header = format!("{num} {generation} obj");
+    let needle = header.as_bytes();
+    let mut found = None;
+    let mut from = 0;
+    while let Some(pos) = find_bytes(data, needle, from) {
+        let starts_token = pos == 0 || !data[pos - 1].is_ascii_digit();
+        let ends_token = data
+            .get(pos + needle.len())
+            .is_none_or(|b| !b.is_ascii_alphanumeric());
+        if starts_token && ends_token {
+            found = Some(pos);
+        }
+        from = pos + 1;
+    }
+    found
+}
+
+/// Byte offsets of every `num gen obj` definition in the file
+///
+/// Like [`find_object_offset`], later definitions replace earlier ones.
+pub fn scan_object_offsets(data: &[u8]) -> HashMap<ObjRef, usize> {
+    let mut offsets = HashMap::new();
+    let mut from = 0;
+    while let Some(pos) = find_bytes(data, b"obj", from) {
+        from = pos + 3;
+        if data.get(pos + 3).is_some_and(|b| b.is_ascii_alphanumeric()) {
+            continue;
+        }
+        // Walk back over whitespace, the generation, whitespace and the number
+        let mut i = pos;
+        let mut fields = [(0usize, 0usize); 2];
+        let mut valid = true;
+        for field in &mut fields {
+            let end = i;
+            while i > 0 && is_whitespace(data[i - 1]) {
+                i -= 1;
+            }
+            let digits_end = i;
+            while i > 0 && data[i - 1].is_ascii_digit() {
+                i -= 1;
+            }
+            if digits_end == end || i == digits_end {
+                valid = false;
+                break;
+            }
+            *field = (i, digits_end);
+        }
+        if !valid {
+            continue;
+        }
+        let number = |(start, end): (usize, usize)| {
+            std::str::from_utf8(&data[start..end])
+                .ok()
+                .and_then(|s| s.parse::<i32>().ok())
+        };
+        if let (Some(generation), Some(num)) = (number(fields[0]), number(fields[1])) {
+            offsets.insert(ObjRef::new(num, generation), i);
+        }
+    }
+    offsets
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\r' | b'\n' | b'\x0C')
+}
+
+/// Locate and parse an object by scanning for its definition
+pub fn find_object(data: &[u8], obj_ref: ObjRef) -> Option<Object> {
+    let offset = find_object_offset(data, obj_ref.num, obj_ref.generation)?;
+    parse_indirect_object_at(data, offset)
+        .ok()
+        .map(|(_, obj)| obj)
+}
+
+/// Parse the last classic `trailer` dictionary in the file
+pub fn find_trailer(data: &[u8]) -> Option<Dict> {
+    let pos = rfind_bytes(data, b"trailer")?;
+    let mut parser = Parser::at(data, pos + b"trailer".len());
+    match parser.parse_object().ok()? {
+        Object::Dict(d) => Some(d),
+        _ => None,
+    }
+}
+
+/// Rebuild a trailer for a file whose own is missing or damaged
+///
+/// The newest cross-reference stream dictionary naming a /Root is used
+/// when there is one. Otherwise /Root is the newest /Type /Catalog
+/// dictionary and /Info the newest untyped dictionary carrying document
+/// information keys.
+pub fn recover_trailer(data: &[u8], offsets: &HashMap<ObjRef, usize>) -> Option<Dict> {
+    let mut objects: Vec<(ObjRef, usize)> = offsets.iter().map(|(&r, &o)| (r, o)).collect();
+    objects.sort_unstable_by_key(|&(_, offset)| std::cmp::Reverse(offset));
+
+    let mut root = None;
+    let mut info = None;
+    for &(obj_ref, offset) in &objects {
+        let dict = match parse_indirect_object_at(data, offset) {
+            Ok((_, Object::Stream { dict, .. })) => {
+                let is_xref = dict
+                    .get("Type")
+                    .and_then(Object::as_name)
+                    .is_some_and(|t| t.as_str() == "XRef");
+                if is_xref && root.is_none() && dict.contains_key("Root") {
+                    return Some(dict);
+                }
+                continue;
+            }
+            Ok((_, Object::Dict(dict))) => dict,
+            _ => continue,
+        };
+        match dict.get("Type").and_then(Object::as_name) {
+            Some(t) if t.as_str() == "Catalog" => {
+                root.get_or_insert(obj_ref);
+            }
+            None if ["Producer", "Creator", "CreationDate", "ModDate"]
+                .iter()
+                .any(|k| dict.contains_key(*k)) =>
+            {
+                info.get_or_insert(obj_ref);
+            }
+            _ => {}
+        }
+    }
+
+    let mut trailer = Dict::new();
+    trailer.insert("Root".into(), Object::Ref(root?));
+    if let Some(info) = info {
+        trailer.insert("Info".into(), Object::Ref(info));
+    }
+    let size = objects.iter().map(|(r, _)| r.num + 1).max().unwrap_or(1);
+    trailer.insert("Size".into(), Object::Int(size as i64));
+    Some(trailer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::limits::ParseLimits;
+
+    #[test]
+    fn test_parse_direct_objects() {
+        let obj = parse_object(b"<< /Type /Page /Count 3 /Kids [1 0 R 2 0 R] /F 1.5 >>").unwrap();
+        let dict = obj.as_dict().unwrap();
+        assert_eq!(
+            dict.get("Type").unwrap().as_name().unwrap().as_str(),
+            "Page"
+        );
+        assert_eq!(dict.get("Count").unwrap().as_int(), Some(3));
+        let kids = dict.get("Kids").unwrap().as_array().unwrap();
+        assert_eq!(kids.len(), 2);
+        assert_eq!(kids[1].as_obj_ref(), Some(ObjRef::new(2, 0)));
+        assert_eq!(dict.get("F").unwrap().as_real(), Some(1.5));
+    }
+
+    #[test]
+    fn test_parse_ints_not_refs() {
+        let obj = parse_object(b"[0 0 612 792]").unwrap();
+        let arr = obj.as_array().unwrap();
+        assert_eq!(arr.len(), 4);
+        assert_eq!(arr[3].as_int(), Some(792));
+    }
+
+    #[test]
+    fn test_parse_binary_string() {
+        let obj = parse_object(b"<< /O <00ff10> /U (\\377\\000) >>").unwrap();
+        let dict = obj.as_dict().unwrap();
+        assert_eq!(
+            dict.get("O").unwrap().as_string().unwrap().as_bytes(),
+            &[0, 0xFF, 0x10]
+        );
+        assert_eq!(
+            dict.get("U").unwrap().as_string().unwrap().as_bytes(),
+            &[0xFF, 0]
+        );
+    }
+
+    #[test]
+    fn test_parse_indirect_stream() {
+        let data = b"4 0 obj\n<< /Length 5 >>\nstream\nHello\nendstream\nendobj\n";
+        let (r, obj) = parse_indirect_object_at(data, 0).unwrap();
+        assert_eq!(r, ObjRef::new(4, 0));
+        let (_, bytes) = obj.as_stream().unwrap();
+        assert_eq!(bytes, b"Hello");
+    }
+
+    #[test]
+    fn test_parse_stream_with_bad_length() {
+        let data = b"4 0 obj\n<< /Length 99 >>\nstream\r\nHello\r\nendstream\nendobj\n";
+        let (_, obj) = parse_indirect_object_at(data, 0).unwrap();
+        assert_eq!(obj.as_stream().unwrap().1, b"Hello");
+    }
+
+    #[test]
+    fn test_find_object_last_definition_wins() {
+        let data = b"%PDF-1.4\n1 0 obj\n(old)\nendobj\n11 0 obj\n(other)\nendobj\n1 0 obj\n(new)\nendobj\n";
+        let obj = find_object(data, ObjRef::new(1, 0)).unwrap();
+        assert_eq!(obj.as_string().unwrap().as_bytes(), b"new");
+        let other = find_object(data, ObjRef::new(11, 0)).unwrap();
+        assert_eq!(other.as_string().unwrap().as_bytes(), b"other");
+    }
+
+    #[test]
+    fn test_find_trailer() {
+        let data = b"xref\n0 1\n0000000000 65535 f \ntrailer\n<< /Size 1 /Root 1 0 R >>\nstartxref\n0\n%%EOF";
+        let trailer = find_trailer(data).unwrap();
+        assert_eq!(
+            trailer.get("Root").unwrap().as_obj_ref(),
+            Some(ObjRef::new(1, 0))
+        );
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let mut data = vec![b'['; ParseLimits::DEFAULT.max_nesting + 10];
+        data.extend(vec![b']'; ParseLimits::DEFAULT.max_nesting + 10]);
+        assert!(parse_object(&data).is_err());
+    }
+}
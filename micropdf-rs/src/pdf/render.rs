@@ -0,0 +1,349 @@
+//! Page rendering
+//!
+//! Sets up an [`Interpreter`] for a page and rasterizes pages to RGB
+//! pixmaps, one at a time or several at once on worker threads.
+
+use crate::fitz::colorspace::Colorspace;
+use crate::fitz::cookie::Cookie;
+use crate::fitz::device::Device;
+use crate::fitz::error::{Error, Result};
+use crate::fitz::geometry::Matrix;
+use crate::fitz::pixmap::Pixmap;
+use crate::fitz::render::DrawDevice;
+use crate::pdf::content::{ExtGState, FontMetrics, TilingPattern, Type3Font};
+use crate::pdf::document::Document;
+use crate::pdf::interpret::Interpreter;
+use crate::pdf::page::Page;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// An interpreter ready to run the contents of `page`, with the page
+/// transform followed by `ctm` and the page resources loaded
+///
+/// Resources that cannot be read are left out, so their operators paint
+/// nothing.
+pub fn page_interpreter(doc: &Document, page: &Page, ctm: &Matrix) -> Interpreter {
+    // Content draws in page space: y down, rotated by /Rotate
+    let mut interp = Interpreter::new();
+    interp.set_ctm(page.transform().concat(ctm));
+    if let Ok(resources) = page.resources(doc) {
+        interp.set_ext_gstates(ExtGState::load_resources(doc, &resources).unwrap_or_default());
+        interp.set_patterns(TilingPattern::load_resources(doc, &resources).unwrap_or_default());
+        interp.set_font_metrics(FontMetrics::load_resources(doc, &resources).unwrap_or_default());
+        interp.set_type3_fonts(Type3Font::load_resources(doc, &resources).unwrap_or_default());
+        interp.set_resources(resources);
+    }
+    interp
+}
+
+/// Most pixels [`render_page`] allocates for one page: 16384 × 16384
+pub const DEFAULT_MAX_PIXELS: u64 = 1 << 28;
+
+/// A rendered page, complete or stopped part way by its cookie
+pub struct RenderedPage {
+    /// What was drawn, on white where nothing was
+    pub pixmap: Pixmap,
+    /// [`Error::Abort`] when the cookie stopped the page's contents before
+    /// the end
+    pub error: Option<Error>,
+}
+
+/// Render the page at `index` to an RGB pixmap at `dpi`, on white
+///
+/// The page is sized in points, so pages with a /UserUnit come out at
+/// their physical size. Pages needing more than [`DEFAULT_MAX_PIXELS`]
+/// are rejected. A `cookie` stops the render as in [`run_page`]; the
+/// pixmap drawn so far is then returned with [`Error::Abort`].
+pub fn render_page(
+    doc: &Document,
+    index: usize,
+    dpi: f32,
+    cookie: Option<&Cookie>,
+) -> Result<RenderedPage> {
+    render_page_limited(doc, index, dpi, DEFAULT_MAX_PIXELS, cookie)
+}
+
+/// Render like [`render_page`], rejecting pages that need more than
+/// `max_pixels` pixels with [`Error::Limit`] before anything is allocated
+pub fn render_page_limited(
+    doc: &Document,
+    index: usize,
+    dpi: f32,
+    max_pixels: u64,
+    cookie: Option<&Cookie>,
+) -> Result<RenderedPage> {
+    if !dpi.is_finite() || dpi <= 0.0 {
+        return Err(Error::argument("resolution must be positive"));
+    }
+    let page = doc.page(index)?;
+    let zoom = dpi / 72.0;
+    let bounds = page.bounds();
+    let width = (f64::from(bounds.width()) * f64::from(zoom))
+        .ceil()
+        .max(1.0);
+    let height = (f64::from(bounds.height()) * f64::from(zoom))
+        .ceil()
+        .max(1.0);
+    if width * height > max_pixels as f64 || width.max(height) > f64::from(i32::MAX) {
+        return Err(Error::limit(format!(
+            "page needs {width} x {height} pixels, over the limit of {max_pixels}"
+        )));
+    }
+    let (width, height) = (width as i32, height as i32);
+    let ctm = Matrix::translate(-bounds.x0, -bounds.y0).concat(&Matrix::scale(zoom, zoom));
+
+    let mut pixmap = Pixmap::new(Some(Colorspace::device_rgb()), width, height, false)?;
+    pixmap.clear(255);
+    let mut device = DrawDevice::new(pixmap);
+    let error = match run_page(doc, index, &ctm, &mut device, cookie) {
+        Ok(()) => None,
+        Err(Error::Abort) => Some(Error::Abort),
+        Err(err) => return Err(err),
+    };
+    Ok(RenderedPage {
+        pixmap: device.into_pixmap(),
+        error,
+    })
+}
+
+/// Run the contents of the page at `index` into `device`, with the page
+/// transform followed by `ctm`
+///
+/// With a `cookie`, interpretation stops between operators once it is
+/// aborted, its step budget is spent or its deadline passes. The device
+/// keeps what was drawn until then, and the error is [`Error::Abort`].
+pub fn run_page(
+    doc: &Document,
+    index: usize,
+    ctm: &Matrix,
+    device: &mut dyn Device,
+    cookie: Option<&Cookie>,
+) -> Result<()> {
+    if cookie.is_some_and(Cookie::should_abort) {
+        return Err(Error::Abort);
+    }
+    let page = doc.page(index)?;
+    let contents = doc.page_contents(&page)?;
+    let mut interp = page_interpreter(doc, &page, ctm);
+    if let Some(cookie) = cookie {
+        interp.set_cookie(cookie.clone());
+    }
+    interp.interpret(&contents, device).map_err(|err| {
+        if cookie.is_some_and(Cookie::should_abort) {
+            Error::Abort
+        } else {
+            Error::format(err)
+        }
+    })
+}
+
+/// Render the pages at `pages` on `num_threads` worker threads
+///
+/// Workers share the document, which is immutable once parsed, and take
+/// pages in order as they finish; each has its own interpreter and device.
+/// The pages come back in the order of `pages` and match those of
+/// [`render_page`]. The first page that fails to render is the error.
+///
+/// The `cookie` is shared by all workers: once it aborts, the page in
+/// progress on each worker is returned as drawn so far and the pages not
+/// yet started come back blank, all with [`Error::Abort`].
+pub fn render_pages_parallel(
+    doc: &Document,
+    pages: &[usize],
+    dpi: f32,
+    num_threads: usize,
+    cookie: Option<&Cookie>,
+) -> Result<Vec<RenderedPage>> {
+    let next = AtomicUsize::new(0);
+    let results: Vec<Mutex<Option<Result<RenderedPage>>>> =
+        pages.iter().map(|_| Mutex::new(None)).collect();
+    let workers = num_threads.clamp(1, pages.len().max(1));
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&index) = pages.get(i) else {
+                        break;
+                    };
+                    let result = render_page(doc, index, dpi, cookie);
+                    if let Ok(mut slot) = results[i].lock() {
+                        *slot = Some(result);
+                    }
+                }
+            });
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|slot| {
+            slot.into_inner()
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| Err(Error::generic("page render did not finish")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::test_pdf::build_pdf;
+
+    /// Documents are shared by reference between render workers
+    #[test]
+    fn test_document_is_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Document>();
+    }
+
+    #[test]
+    fn test_step_budget_keeps_partial_render() {
+        // 100 fills down the page, one 1 pt row each
+        let content: String = (0..100)
+            .map(|y| format!("0 0 1 rg 0 {} 10 1 re f\n", 99 - y))
+            .collect();
+        let pdf = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 10 100] /Contents 4 0 R >>".to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ),
+        ]);
+        let doc = Document::open_bytes(pdf).unwrap();
+
+        let mut pixmap = Pixmap::new(Some(Colorspace::device_rgb()), 10, 100, false).unwrap();
+        pixmap.clear(255);
+        let mut device = DrawDevice::new(pixmap);
+        let cookie = Cookie::new();
+        // Each fill is three operators: rg, re and f
+        cookie.set_step_budget(Some(30));
+        let result = run_page(&doc, 0, &Matrix::IDENTITY, &mut device, Some(&cookie));
+        assert!(matches!(result, Err(Error::Abort)));
+        assert!(cookie.is_incomplete());
+        assert_eq!(cookie.steps(), 31);
+
+        // The first ten rows are painted and the rest left white
+        let pixmap = device.into_pixmap();
+        let row = |y: usize| &pixmap.samples()[y * 30..y * 30 + 3];
+        assert_eq!(row(5), [0, 0, 255]);
+        assert_eq!(row(9), [0, 0, 255]);
+        assert_eq!(row(10), [255, 255, 255]);
+        assert_eq!(row(99), [255, 255, 255]);
+
+        let cookie = Cookie::new();
+        cookie.set_timeout(Some(std::time::Duration::ZERO));
+        let mut device = DrawDevice::new(Pixmap::new(None, 10, 100, true).unwrap());
+        let result = run_page(&doc, 0, &Matrix::IDENTITY, &mut device, Some(&cookie));
+        assert!(matches!(result, Err(Error::Abort)));
+    }
+
+    #[test]
+    fn test_render_page_pixel_budget() {
+        // A 2000 in square: 14400 units of 10 points each
+        let pdf = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 14400 14400] /UserUnit 10 >>",
+        ]);
+        let doc = Document::open_bytes(pdf).unwrap();
+        assert_eq!(doc.page(0).unwrap().bounds().width(), 144000.0);
+
+        assert!(matches!(
+            render_page(&doc, 0, 72.0, None),
+            Err(Error::Limit(_))
+        ));
+        // 1125 × 1125 pixels at 1/128 zoom
+        assert!(matches!(
+            render_page_limited(&doc, 0, 0.5625, 1_000_000, None),
+            Err(Error::Limit(_))
+        ));
+        let page = render_page_limited(&doc, 0, 0.5625, 2_000_000, None).unwrap();
+        assert_eq!((page.pixmap.width(), page.pixmap.height()), (1125, 1125));
+        assert!(page.error.is_none());
+    }
+
+    #[test]
+    fn test_render_pages_parallel_matches_serial() {
+        let contents = [
+            "1 0 0 rg 10 10 50 50 re f",
+            "0 1 0 rg 20 30 40 60 re f",
+            "0 0 1 RG 4 w 5 5 m 90 90 l S",
+            "0.5 g 0 0 100 20 re f 1 1 0 rg 30 30 30 30 re f",
+        ];
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R 6 0 R] /Count 4 \
+             /MediaBox [0 0 100 100] >>"
+                .to_string(),
+        ];
+        for i in 0..4 {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /Contents {} 0 R >>",
+                7 + i
+            ));
+        }
+        for data in contents {
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{data}\nendstream",
+                data.len()
+            ));
+        }
+        let pdf = build_pdf(&objects);
+        let doc = Document::open_bytes(pdf).unwrap();
+
+        let pages = [0, 1, 2, 3];
+        let parallel = render_pages_parallel(&doc, &pages, 144.0, 4, None).unwrap();
+        assert_eq!(parallel.len(), 4);
+        for (&index, page) in pages.iter().zip(&parallel) {
+            let serial = render_page(&doc, index, 144.0, None).unwrap();
+            assert_eq!((page.pixmap.width(), page.pixmap.height()), (200, 200));
+            assert_eq!(page.pixmap.samples(), serial.pixmap.samples());
+            assert!(page.error.is_none());
+        }
+        // The pages differ, so each result is its own page
+        assert_ne!(parallel[0].pixmap.samples(), parallel[1].pixmap.samples());
+
+        // An aborted cookie leaves every page blank and aborted
+        let cookie = Cookie::new();
+        cookie.abort();
+        let aborted = render_pages_parallel(&doc, &pages, 144.0, 4, Some(&cookie)).unwrap();
+        for page in &aborted {
+            assert!(matches!(page.error, Some(Error::Abort)));
+            assert!(page.pixmap.samples().iter().all(|&s| s == 255));
+        }
+    }
+
+    #[test]
+    fn test_render_page_returns_partial_pixmap_on_abort() {
+        // 100 fills down the page, one 1 pt row each
+        let content: String = (0..100)
+            .map(|y| format!("0 0 1 rg 0 {} 10 1 re f\n", 99 - y))
+            .collect();
+        let pdf = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 10 100] /Contents 4 0 R >>".to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ),
+        ]);
+        let doc = Document::open_bytes(pdf).unwrap();
+
+        let cookie = Cookie::new();
+        cookie.set_step_budget(Some(30));
+        let page = render_page(&doc, 0, 72.0, Some(&cookie)).unwrap();
+        assert!(matches!(page.error, Some(Error::Abort)));
+        let row = |y: usize| &page.pixmap.samples()[y * 30..y * 30 + 3];
+        assert_eq!(row(9), [0, 0, 255]);
+        assert_eq!(row(10), [255, 255, 255]);
+
+        let page = render_page(&doc, 0, 72.0, None).unwrap();
+        assert!(page.error.is_none());
+        assert_eq!(&page.pixmap.samples()[99 * 30..99 * 30 + 3], [0, 0, 255]);
+    }
+}
@@ -0,0 +1,300 @@
+//! PDF digital signature fields
+//!
+//! Locates signed `/Sig` form fields and checks which bytes of the file
+//! their `/ByteRange` covers. Verifying the PKCS#7 blob itself is left to
+//! the caller; [`Signature::digest`] gives the hash it should sign.
+
+use crate::fitz::error::{Error, Result};
+use crate::pdf::document::Document;
+use crate::pdf::object::{Dict, Name, ObjRef, Object};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+/// Maximum depth of the form field tree
+const MAX_DEPTH: usize = 64;
+
+/// A signed signature field
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    /// The field dictionary
+    pub field: ObjRef,
+    /// Fully qualified field name
+    pub name: String,
+    /// `(offset, length)` pairs from /ByteRange
+    pub byte_range: Vec<(usize, usize)>,
+    /// The /Contents value, normally a DER-encoded PKCS#7 object
+    pub contents: Vec<u8>,
+    /// /Name of the signer, if given
+    pub signer: Option<String>,
+    /// /Reason for signing
+    pub reason: Option<String>,
+    /// /Location of signing
+    pub location: Option<String>,
+}
+
+impl Signature {
+    /// SHA-256 over the bytes covered by the byte range
+    pub fn digest(&self, data: &[u8]) -> Result<[u8; 32]> {
+        let mut hasher = Sha256::new();
+        for &(offset, length) in &self.byte_range {
+            let range = offset
+                .checked_add(length)
+                .filter(|&end| end <= data.len())
+                .map(|end| offset..end)
+                .ok_or_else(|| Error::format("signature byte range outside the file"))?;
+            hasher.update(&data[range]);
+        }
+        Ok(hasher.finalize().into())
+    }
+
+    /// Bytes that the byte range leaves out, as `(offset, length)` pairs,
+    /// including anything after its end
+    pub fn gaps(&self, file_len: usize) -> Vec<(usize, usize)> {
+        let mut ranges = self.byte_range.clone();
+        ranges.sort();
+        let mut gaps = Vec::new();
+        let mut pos = 0;
+        for (offset, length) in ranges {
+            if offset > pos {
+                gaps.push((pos, offset - pos));
+            }
+            pos = pos.max(offset.saturating_add(length));
+        }
+        if pos < file_len {
+            gaps.push((pos, file_len - pos));
+        }
+        gaps
+    }
+
+    /// Whether the byte range covers the whole file except the /Contents
+    /// hex string
+    ///
+    /// False when bytes were appended after signing (such as an
+    /// incremental update) or when the excluded bytes are anything other
+    /// than `<...>`, either of which can hide changes from the signature.
+    pub fn covers_whole_file(&self, data: &[u8]) -> bool {
+        match self.gaps(data.len()).as_slice() {
+            [(offset, length)] => {
+                self.byte_range
+                    .iter()
+                    .all(|&(o, l)| o.saturating_add(l) <= data.len())
+                    && is_hex_string(&data[*offset..offset + length])
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Whether `bytes` is exactly one hex string, `<` hex digits `>`
+fn is_hex_string(bytes: &[u8]) -> bool {
+    bytes.len() >= 2
+        && bytes[0] == b'<'
+        && bytes[bytes.len() - 1] == b'>'
+        && bytes[1..bytes.len() - 1]
+            .iter()
+            .all(|b| b.is_ascii_hexdigit() || b.is_ascii_whitespace())
+}
+
+/// Decode the hex string excluded by a byte range
+///
+/// Strings in signature dictionaries are not encrypted, so reading them
+/// from the file avoids decrypting what was never encrypted.
+fn decode_hex_gap(bytes: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = bytes[1..bytes.len() - 1]
+        .iter()
+        .filter_map(|b| (*b as char).to_digit(16).map(|d| d as u8))
+        .collect();
+    digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+/// Signed signature fields of the document's AcroForm, in field order
+pub fn find_signatures(doc: &Document) -> Result<Vec<Signature>> {
+    let catalog = doc.catalog()?;
+    let Some(Object::Dict(acroform)) = doc.resolve_key(&catalog, "AcroForm")? else {
+        return Ok(Vec::new());
+    };
+    let Some(Object::Array(fields)) = doc.resolve_key(&acroform, "Fields")? else {
+        return Ok(Vec::new());
+    };
+    let mut signatures = Vec::new();
+    let mut visited = HashSet::new();
+    for field in &fields {
+        if let Some(field) = field.as_obj_ref() {
+            collect_fields(doc, field, "", None, 0, &mut visited, &mut signatures)?;
+        }
+    }
+    Ok(signatures)
+}
+
+fn collect_fields(
+    doc: &Document,
+    field_ref: ObjRef,
+    parent_name: &str,
+    parent_type: Option<&Name>,
+    depth: usize,
+    visited: &mut HashSet<ObjRef>,
+    out: &mut Vec<Signature>,
+) -> Result<()> {
+    if depth > MAX_DEPTH || !visited.insert(field_ref) {
+        return Ok(());
+    }
+    let Object::Dict(field) = doc.load_object(field_ref)? else {
+        return Ok(());
+    };
+    let name = match doc.resolve_key(&field, "T")? {
+        Some(Object::String(t)) if parent_name.is_empty() => t.to_text(),
+        Some(Object::String(t)) => format!("{}.{}", parent_name, t.to_text()),
+        _ => parent_name.to_string(),
+    };
+    // /FT is inheritable
+    let field_type = match field.get("FT").and_then(Object::as_name) {
+        Some(ft) => Some(ft.clone()),
+        None => parent_type.cloned(),
+    };
+
+    if let Some(Object::Array(kids)) = doc.resolve_key(&field, "Kids")? {
+        for kid in kids.iter().filter_map(Object::as_obj_ref) {
+            collect_fields(
+                doc,
+                kid,
+                &name,
+                field_type.as_ref(),
+                depth + 1,
+                visited,
+                out,
+            )?;
+        }
+    }
+
+    if field_type.as_ref().is_none_or(|ft| ft.as_str() != "Sig") {
+        return Ok(());
+    }
+    let Some(Object::Dict(value)) = doc.resolve_key(&field, "V")? else {
+        return Ok(());
+    };
+    out.push(signature_from_value(doc, field_ref, name, &value)?);
+    Ok(())
+}
+
+fn signature_from_value(
+    doc: &Document,
+    field: ObjRef,
+    name: String,
+    value: &Dict,
+) -> Result<Signature> {
+    let numbers: Vec<i64> = match doc.resolve_key(value, "ByteRange")? {
+        Some(Object::Array(arr)) => arr.iter().filter_map(Object::as_int).collect(),
+        _ => Vec::new(),
+    };
+    let byte_range: Vec<(usize, usize)> = numbers
+        .chunks_exact(2)
+        .filter(|pair| pair[0] >= 0 && pair[1] >= 0)
+        .map(|pair| (pair[0] as usize, pair[1] as usize))
+        .collect();
+    let text = |key: &str| -> Result<Option<String>> {
+        Ok(match doc.resolve_key(value, key)? {
+            Some(Object::String(s)) => Some(s.to_text()),
+            _ => None,
+        })
+    };
+
+    let mut signature = Signature {
+        field,
+        name,
+        byte_range,
+        contents: Vec::new(),
+        signer: text("Name")?,
+        reason: text("Reason")?,
+        location: text("Location")?,
+    };
+
+    let data = doc.data();
+    let gaps = signature.gaps(data.len());
+    signature.contents = match gaps.first() {
+        Some(&(offset, length)) if is_hex_string(&data[offset..offset + length]) => {
+            decode_hex_gap(&data[offset..offset + length])
+        }
+        _ => match doc.resolve_key(value, "Contents")? {
+            Some(Object::String(s)) => s.as_bytes().to_vec(),
+            _ => Vec::new(),
+        },
+    };
+    Ok(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::test_pdf::build_pdf;
+
+    /// A file with a signed field whose /ByteRange excludes the /Contents
+    /// hex string, computed the way a signer fills in the placeholder
+    fn signed_pdf() -> Vec<u8> {
+        let contents = format!("<{}>", "3082".repeat(8));
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R /AcroForm << /Fields [4 0 R] /SigFlags 3 >> >>"
+                .to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Annots [4 0 R] >>".to_string(),
+            "<< /FT /Sig /T (Approval) /Type /Annot /Subtype /Widget /Rect [0 0 0 0] \
+             /P 3 0 R /V 5 0 R >>"
+                .to_string(),
+            format!(
+                "<< /Type /Sig /Filter /Adobe.PPKLite /SubFilter /adbe.pkcs7.detached \
+                 /Name (Jane Doe) /Reason (Approved) /ByteRange [0000000000 0000000000 \
+                 0000000000 0000000000] /Contents {contents} >>"
+            ),
+        ];
+        let mut data = build_pdf(&objects);
+
+        let start = crate::pdf::parser::find_bytes(&data, contents.as_bytes(), 0).unwrap();
+        let end = start + contents.len();
+        let range = format!(
+            "{:010} {:010} {:010} {:010}",
+            0,
+            start,
+            end,
+            data.len() - end
+        );
+        let placeholder = crate::pdf::parser::find_bytes(&data, b"0000000000 ", 0).unwrap();
+        data[placeholder..placeholder + range.len()].copy_from_slice(range.as_bytes());
+        data
+    }
+
+    #[test]
+    fn test_find_signatures_byte_range() {
+        let data = signed_pdf();
+        let doc = Document::open_bytes(data.clone()).unwrap();
+        let sigs = find_signatures(&doc).unwrap();
+        assert_eq!(sigs.len(), 1);
+        let sig = &sigs[0];
+        assert_eq!(sig.name, "Approval");
+        assert_eq!(sig.signer.as_deref(), Some("Jane Doe"));
+        assert_eq!(sig.contents, [0x30, 0x82].repeat(8));
+
+        // The only excluded bytes are the /Contents hex string
+        let gaps = sig.gaps(data.len());
+        assert_eq!(gaps.len(), 1);
+        let (offset, length) = gaps[0];
+        assert_eq!(
+            &data[offset..offset + length],
+            format!("<{}>", "3082".repeat(8)).as_bytes()
+        );
+        assert!(sig.covers_whole_file(&data));
+
+        let mut covered = data[..offset].to_vec();
+        covered.extend_from_slice(&data[offset + length..]);
+        let expected: [u8; 32] = Sha256::digest(&covered).into();
+        assert_eq!(sig.digest(&data).unwrap(), expected);
+
+        // Appending an update leaves bytes outside the signed range
+        let mut updated = data.clone();
+        updated.extend_from_slice(b"1 0 obj\n<< >>\nendobj\n");
+        assert!(!sig.covers_whole_file(&updated));
+        assert_eq!(sig.digest(&updated).unwrap(), expected);
+        assert!(sig.digest(&data[..data.len() - 10]).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! Hand-built PDF files for tests
+//!
+//! Object bodies are numbered from 1, in order, and the trailer's /Root
+//! is object 1.
+
+/// Builder for a PDF file made of object bodies numbered from 1
+pub(crate) struct PdfBuilder {
+    objects: Vec<Vec<u8>>,
+    trailer: String,
+    xref: bool,
+    startxref: Option<usize>,
+}
+
+impl PdfBuilder {
+    /// A file with a classic xref table for `objects`
+    pub(crate) fn new<S: AsRef<[u8]>>(objects: &[S]) -> Self {
+        Self {
+            objects: objects.iter().map(|o| o.as_ref().to_vec()).collect(),
+            trailer: String::new(),
+            xref: true,
+            startxref: None,
+        }
+    }
+
+    /// Add `entries` to the trailer after /Size and /Root
+    pub(crate) fn trailer(mut self, entries: &str) -> Self {
+        self.trailer = entries.to_string();
+        self
+    }
+
+    /// Leave out the xref table and `startxref`, so readers have to scan
+    /// for the objects
+    pub(crate) fn without_xref(mut self) -> Self {
+        self.xref = false;
+        self
+    }
+
+    /// Record `offset` after the `startxref` keyword instead of the
+    /// table's offset
+    pub(crate) fn startxref(mut self, offset: usize) -> Self {
+        self.startxref = Some(offset);
+        self
+    }
+
+    pub(crate) fn build(self) -> Vec<u8> {
+        let mut out = b"%PDF-1.7\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, body) in self.objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            out.extend_from_slice(body);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+
+        let size = self.objects.len() + 1;
+        let xref = out.len();
+        if self.xref {
+            out.extend_from_slice(format!("xref\n0 {size}\n0000000000 65535 f \n").as_bytes());
+            for offset in offsets {
+                out.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+            }
+        }
+        let extra = if self.trailer.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", self.trailer)
+        };
+        out.extend_from_slice(
+            format!("trailer\n<< /Size {size} /Root 1 0 R{extra} >>\n").as_bytes(),
+        );
+        if self.xref {
+            let startxref = self.startxref.unwrap_or(xref);
+            out.extend_from_slice(format!("startxref\n{startxref}\n").as_bytes());
+        }
+        out.extend_from_slice(b"%%EOF\n");
+        out
+    }
+}
+
+/// A PDF file with a classic xref table made of `objects`, numbered from 1
+pub(crate) fn build_pdf<S: AsRef<[u8]>>(objects: &[S]) -> Vec<u8> {
+    PdfBuilder::new(objects).build()
+}
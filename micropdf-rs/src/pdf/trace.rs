@@ -0,0 +1,125 @@
+//! Content stream performance tracing
+//!
+//! Times each operator run by the [`Interpreter`](crate::pdf::interpret::Interpreter)
+//! and attributes it to a category, so that slow pages can be broken down
+//! into path, text, image and state work.
+
+use std::time::Duration;
+
+/// The kind of work an operator does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperatorCategory {
+    /// Path construction, painting and clipping
+    Path,
+    /// Text objects, text state and text showing
+    Text,
+    /// Images, XObjects and shadings
+    Image,
+    /// Graphics state and colors
+    State,
+    /// Marked content, compatibility sections and unknown operators
+    Other,
+}
+
+impl OperatorCategory {
+    /// All categories, in the order used to index [`RenderTrace`]
+    pub const ALL: [OperatorCategory; 5] = [
+        OperatorCategory::Path,
+        OperatorCategory::Text,
+        OperatorCategory::Image,
+        OperatorCategory::State,
+        OperatorCategory::Other,
+    ];
+
+    /// The category of a content stream operator
+    pub fn of(op: &str) -> Self {
+        match op {
+            "m" | "l" | "c" | "v" | "y" | "h" | "re" | "S" | "s" | "f" | "F" | "f*" | "B"
+            | "B*" | "b" | "b*" | "n" | "W" | "W*" => Self::Path,
+            "BT" | "ET" | "Td" | "TD" | "Tm" | "T*" | "Tc" | "Tw" | "Tz" | "TL" | "Tf" | "Tr"
+            | "Ts" | "Tj" | "TJ" | "'" | "\"" | "d0" | "d1" => Self::Text,
+            "Do" | "BI" | "ID" | "EI" | "sh" => Self::Image,
+            "q" | "Q" | "cm" | "w" | "J" | "j" | "M" | "d" | "ri" | "i" | "gs" | "CS" | "cs"
+            | "SC" | "SCN" | "sc" | "scn" | "G" | "g" | "RG" | "rg" | "K" | "k" => Self::State,
+            _ => Self::Other,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Callbacks run by the interpreter after each operator
+pub trait TraceHooks: Send {
+    /// `op` finished after `elapsed`
+    fn operator(&mut self, category: OperatorCategory, op: &str, elapsed: Duration);
+}
+
+/// Operator count and time spent in one category
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CategoryStats {
+    /// Number of operators run
+    pub count: u64,
+    /// Total time spent running them
+    pub time: Duration,
+}
+
+/// Per-category operator counts and timings for one or more content streams
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderTrace {
+    stats: [CategoryStats; OperatorCategory::ALL.len()],
+}
+
+impl RenderTrace {
+    /// Create an empty trace
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Statistics for one category
+    pub fn stats(&self, category: OperatorCategory) -> CategoryStats {
+        self.stats[category.index()]
+    }
+
+    /// Number of operators run in a category
+    pub fn count(&self, category: OperatorCategory) -> u64 {
+        self.stats(category).count
+    }
+
+    /// Time spent in a category
+    pub fn time(&self, category: OperatorCategory) -> Duration {
+        self.stats(category).time
+    }
+
+    /// Number of operators run across all categories
+    pub fn total_count(&self) -> u64 {
+        self.stats.iter().map(|s| s.count).sum()
+    }
+
+    /// Time spent across all categories
+    pub fn total_time(&self) -> Duration {
+        self.stats.iter().map(|s| s.time).sum()
+    }
+
+    /// Add another trace's counts and times to this one
+    pub fn merge(&mut self, other: &RenderTrace) {
+        for (mine, theirs) in self.stats.iter_mut().zip(&other.stats) {
+            mine.count += theirs.count;
+            mine.time += theirs.time;
+        }
+    }
+
+    /// Forget everything recorded so far
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl TraceHooks for RenderTrace {
+    fn operator(&mut self, category: OperatorCategory, _op: &str, elapsed: Duration) {
+        let stats = &mut self.stats[category.index()];
+        stats.count += 1;
+        stats.time += elapsed;
+    }
+}
@@ -1 +1,1264 @@
-//! PDF write module stub
+//! PDF writing
+//!
+//! Object serialization, complete files and incremental updates.
+
+use crate::fitz::buffer::Buffer;
+use crate::fitz::error::{Error, Result};
+use crate::fitz::geometry::{Matrix, Point};
+use crate::fitz::path::{Path, PathElement};
+use crate::pdf::document::Document;
+use crate::pdf::filter::{FilterChain, FilterType};
+use crate::pdf::object::{Dict, Name, ObjRef, Object, PdfString};
+use crate::pdf::outline::{self, OutlineItem};
+use crate::pdf::parser::{self, Parser};
+use crate::pdf::xref::XrefEntry;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::Write;
+
+/// Trailer keys that only make sense in the section that defined them
+const SECTION_KEYS: [&str; 9] = [
+    "Prev",
+    "XRefStm",
+    "Type",
+    "W",
+    "Index",
+    "Filter",
+    "DecodeParms",
+    "Length",
+    "Size",
+];
+
+/// Serialize an object in PDF syntax
+pub fn write_object<W: Write>(out: &mut W, obj: &Object) -> Result<()> {
+    match obj {
+        Object::Null => out.write_all(b"null")?,
+        Object::Bool(b) => out.write_all(if *b { b"true" } else { b"false" })?,
+        Object::Int(i) => write!(out, "{i}")?,
+        Object::Real(r) => out.write_all(format_real(*r).as_bytes())?,
+        Object::String(s) => write_string(out, s.as_bytes())?,
+        Object::Name(n) => write_name(out, n.as_str())?,
+        Object::Array(items) => {
+            out.write_all(b"[")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b" ")?;
+                }
+                write_object(out, item)?;
+            }
+            out.write_all(b"]")?;
+        }
+        Object::Dict(dict) => write_dict(out, dict)?,
+        Object::Stream { dict, data } => {
+            let mut dict = dict.clone();
+            dict.insert("Length".into(), Object::Int(data.len() as i64));
+            write_dict(out, &dict)?;
+            out.write_all(b"\nstream\n")?;
+            out.write_all(data)?;
+            out.write_all(b"\nendstream")?;
+        }
+        Object::Ref(r) => write!(out, "{} {} R", r.num, r.generation)?,
+    }
+    Ok(())
+}
+
+/// Serialize an object into a byte vector
+pub fn object_to_bytes(obj: &Object) -> Vec<u8> {
+    let mut out = Vec::new();
+    // Writing to a Vec cannot fail
+    let _ = write_object(&mut out, obj);
+    out
+}
+
+/// Write a dictionary with keys in sorted order, so output is reproducible
+fn write_dict<W: Write>(out: &mut W, dict: &Dict) -> Result<()> {
+    let mut entries: Vec<_> = dict.iter().collect();
+    entries.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+    out.write_all(b"<<")?;
+    for (key, value) in entries {
+        write_name(out, key.as_str())?;
+        out.write_all(b" ")?;
+        write_object(out, value)?;
+    }
+    out.write_all(b">>")?;
+    Ok(())
+}
+
+/// Format a real number the way PDF expects: no exponent, no trailing
+/// zeros, at most six decimals, and integral values without a point
+pub(crate) fn format_real(r: f64) -> String {
+    if !r.is_finite() {
+        return "0".into();
+    }
+    if r.fract() == 0.0 && r.abs() < 1e15 {
+        return format!("{}", r as i64);
+    }
+    let s = format!("{r:.6}");
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" { "0".into() } else { s.to_string() }
+}
+
+pub(crate) fn write_name<W: Write>(out: &mut W, name: &str) -> Result<()> {
+    out.write_all(b"/")?;
+    for &b in name.as_bytes() {
+        if b.is_ascii_graphic() && !is_delimiter(b) && b != b'#' {
+            out.write_all(&[b])?;
+        } else {
+            write!(out, "#{b:02X}")?;
+        }
+    }
+    Ok(())
+}
+
+fn is_delimiter(b: u8) -> bool {
+    matches!(
+        b,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+/// Literal strings for mostly-printable data, hex strings otherwise
+pub(crate) fn write_string<W: Write>(out: &mut W, bytes: &[u8]) -> Result<()> {
+    let binary = bytes
+        .iter()
+        .filter(|&&b| !(b.is_ascii_graphic() || b == b' ' || b == b'\n'))
+        .count();
+    if binary * 4 > bytes.len() {
+        out.write_all(b"<")?;
+        for b in bytes {
+            write!(out, "{b:02x}")?;
+        }
+        out.write_all(b">")?;
+        return Ok(());
+    }
+
+    out.write_all(b"(")?;
+    for &b in bytes {
+        match b {
+            b'(' | b')' | b'\\' => out.write_all(&[b'\\', b])?,
+            b'\n' => out.write_all(b"\\n")?,
+            b'\r' => out.write_all(b"\\r")?,
+            b' '..=b'~' => out.write_all(&[b])?,
+            _ => write!(out, "\\{b:03o}")?,
+        }
+    }
+    out.write_all(b")")?;
+    Ok(())
+}
+
+/// How streams without a /Filter are encoded when written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamEncoding {
+    /// Write the data as it is
+    #[default]
+    AsIs,
+    /// Use whichever of Flate, RunLength or no filter is smallest
+    Auto,
+    /// Always encode with this filter
+    Filter(FilterType),
+}
+
+/// Bytes a single-filter /Filter entry adds to a stream dictionary
+const FILTER_ENTRY_LEN: usize = " /Filter /FlateDecode".len();
+
+/// `dict` and `data` of an unfiltered stream encoded as `encoding` asks
+///
+/// Returns `None` when the stream is written as it is. Streams that
+/// already have a /Filter, such as JPEG or fax images, are never
+/// re-encoded.
+fn encode_stream(
+    dict: &Dict,
+    data: &[u8],
+    encoding: StreamEncoding,
+) -> Result<Option<(Dict, Vec<u8>)>> {
+    if dict.contains_key("Filter") {
+        return Ok(None);
+    }
+    let encode = |filter: FilterType| -> Result<Vec<u8>> {
+        let mut chain = FilterChain::new();
+        chain.add(filter);
+        chain.encode(data.to_vec())
+    };
+    let (filter, encoded) = match encoding {
+        StreamEncoding::AsIs => return Ok(None),
+        StreamEncoding::Filter(filter) => (filter, encode(filter)?),
+        StreamEncoding::Auto => {
+            let mut best: Option<(FilterType, Vec<u8>)> = None;
+            for filter in [FilterType::FlateDecode, FilterType::RunLengthDecode] {
+                let encoded = encode(filter)?;
+                if best.as_ref().is_none_or(|(_, b)| encoded.len() < b.len()) {
+                    best = Some((filter, encoded));
+                }
+            }
+            match best {
+                Some(best) if best.1.len() + FILTER_ENTRY_LEN < data.len() => best,
+                _ => return Ok(None),
+            }
+        }
+    };
+    let mut dict = dict.clone();
+    dict.remove("DecodeParms");
+    dict.insert("Filter".into(), Object::Name(Name::new(filter.to_name())));
+    Ok(Some((dict, encoded)))
+}
+
+/// Write a complete PDF file containing `objects`
+///
+/// `trailer` should contain /Root; /Size is filled in. Object numbers that
+/// are not used get free entries in the xref table.
+pub fn write_pdf<W: Write>(
+    objects: &BTreeMap<ObjRef, Object>,
+    trailer: &Dict,
+    out: &mut W,
+) -> Result<()> {
+    write_pdf_with(objects, trailer, StreamEncoding::AsIs, out)
+}
+
+/// Write a complete PDF file like [`write_pdf`], encoding unfiltered
+/// streams as `encoding` asks
+pub fn write_pdf_with<W: Write>(
+    objects: &BTreeMap<ObjRef, Object>,
+    trailer: &Dict,
+    encoding: StreamEncoding,
+    out: &mut W,
+) -> Result<()> {
+    let header = b"%PDF-1.7\n%\xE2\xE3\xCF\xD3\n";
+    out.write_all(header)?;
+    let mut offset = header.len();
+
+    let size = objects.keys().map(|r| r.num + 1).max().unwrap_or(1).max(1);
+    let mut offsets = vec![None; size as usize];
+    for (obj_ref, obj) in objects {
+        let mut body = format!("{} {} obj\n", obj_ref.num, obj_ref.generation).into_bytes();
+        let encoded = match obj {
+            Object::Stream { dict, data } => encode_stream(dict, data, encoding)?,
+            _ => None,
+        };
+        match encoded {
+            Some((dict, data)) => write_object(&mut body, &Object::Stream { dict, data })?,
+            None => write_object(&mut body, obj)?,
+        }
+        body.extend_from_slice(b"\nendobj\n");
+        out.write_all(&body)?;
+        offsets[obj_ref.num as usize] = Some((offset, obj_ref.generation));
+        offset += body.len();
+    }
+
+    write!(out, "xref\n0 {size}\n")?;
+    for entry in &offsets {
+        match entry {
+            Some((off, generation)) => write!(out, "{off:010} {generation:05} n\r\n")?,
+            None => out.write_all(b"0000000000 65535 f\r\n")?,
+        }
+    }
+    let mut trailer = trailer.clone();
+    trailer.insert("Size".into(), Object::Int(size as i64));
+    out.write_all(b"trailer\n")?;
+    write_dict(out, &trailer)?;
+    write!(out, "\nstartxref\n{offset}\n%%EOF\n")?;
+    Ok(())
+}
+
+/// Write a complete copy of `doc` with the objects in `changed` replaced
+///
+/// Only objects reachable from the trailer's /Root and /Info are written,
+/// so replaced or unreferenced definitions are dropped and the output
+/// shrinks when they do. An [`Object::Null`] value removes the object. The
+/// output has an xref table and is not encrypted.
+pub fn rewrite_document<W: Write>(
+    doc: &Document,
+    changed: &BTreeMap<ObjRef, Object>,
+    out: &mut W,
+) -> Result<()> {
+    let encrypt_ref = doc.trailer().get("Encrypt").and_then(Object::as_obj_ref);
+    let mut trailer = Dict::new();
+    for key in ["Root", "Info", "ID"] {
+        if let Some(value) = doc.trailer().get(key) {
+            trailer.insert(key.into(), value.clone());
+        }
+    }
+
+    let mut objects = BTreeMap::new();
+    let mut queue = VecDeque::new();
+    push_refs(&Object::Dict(trailer.clone()), &mut queue);
+    while let Some(obj_ref) = queue.pop_front() {
+        if objects.contains_key(&obj_ref) || Some(obj_ref) == encrypt_ref {
+            continue;
+        }
+        let obj = match changed.get(&obj_ref) {
+            Some(obj) => obj.clone(),
+            None => doc.load_object(obj_ref)?,
+        };
+        if obj.is_null() {
+            continue;
+        }
+        push_refs(&obj, &mut queue);
+        objects.insert(obj_ref, obj);
+    }
+    write_pdf(&objects, &trailer, out)
+}
+
+/// Queue the references held by `obj`
+fn push_refs(obj: &Object, queue: &mut VecDeque<ObjRef>) {
+    match obj {
+        Object::Ref(r) => queue.push_back(*r),
+        Object::Array(items) => items.iter().for_each(|item| push_refs(item, queue)),
+        Object::Dict(dict) | Object::Stream { dict, .. } => {
+            dict.values().for_each(|value| push_refs(value, queue))
+        }
+        _ => {}
+    }
+}
+
+/// Write a new document containing copies of the pages at `pages`, in order
+///
+/// Objects reachable from the copied pages are copied and renumbered.
+/// References to pages that are not copied, and to page tree nodes, become
+/// null. Links to pages that are not copied are removed, and bookmarks are
+/// kept only if they point at a copied page. The output is not encrypted.
+pub fn copy_pages<W: Write>(doc: &Document, pages: &[usize], out: &mut W) -> Result<()> {
+    merge_pages(&[(doc, pages)], out)
+}
+
+/// Write a new document containing the pages of several documents, in order
+///
+/// Each source is copied as by [`copy_pages`], with the bookmarks of all
+/// sources concatenated. Font programs and image XObjects with the same
+/// decoded data and dictionary are stored once and shared, so documents
+/// made from one template do not repeat its fonts and images.
+pub fn merge_pages<W: Write>(sources: &[(&Document, &[usize])], out: &mut W) -> Result<()> {
+    let Some(&(first, _)) = sources.first() else {
+        return Err(Error::argument("no documents to merge"));
+    };
+    let catalog_ref = ObjRef::new(1, 0);
+    let pages_ref = ObjRef::new(2, 0);
+    let mut copier = Copier {
+        doc: first,
+        map: HashMap::new(),
+        dropped: HashSet::new(),
+        queue: VecDeque::new(),
+        objects: BTreeMap::new(),
+        next: 3,
+        font_files: HashSet::new(),
+        digests: BTreeMap::new(),
+    };
+
+    let mut kids = Vec::new();
+    let mut bookmarks = Vec::new();
+    for &(doc, pages) in sources {
+        copier.doc = doc;
+        copier.map.clear();
+        copier.dropped.clear();
+        let source_catalog = doc.catalog()?;
+
+        let mut selected = Vec::with_capacity(pages.len());
+        // Source page index to the first copy of the page
+        let mut page_refs = HashMap::new();
+        for &index in pages {
+            let page = doc.page(index)?;
+            // A page selected twice gets a second page object sharing the rest
+            let new_ref = match copier.map.get(&page.obj_ref()) {
+                Some(_) => copier.allocate(),
+                None => {
+                    let new_ref = copier.allocate();
+                    copier.map.insert(page.obj_ref(), new_ref);
+                    page_refs.insert(index, new_ref);
+                    new_ref
+                }
+            };
+            selected.push((new_ref, page));
+        }
+        for index in 0..doc.page_count()? {
+            let obj_ref = doc.page(index)?.obj_ref();
+            if !copier.map.contains_key(&obj_ref) {
+                copier.dropped.insert(obj_ref);
+            }
+        }
+
+        for (new_ref, page) in &selected {
+            let mut dict = Dict::new();
+            for (key, value) in page.dict() {
+                match key.as_str() {
+                    "Parent" => {}
+                    "Annots" => {
+                        let annots = copier.copy_annots(value, &source_catalog, &page_refs)?;
+                        if !annots.is_empty() {
+                            dict.insert(key.clone(), Object::Array(annots));
+                        }
+                    }
+                    _ => {
+                        dict.insert(key.clone(), copier.copy(value));
+                    }
+                }
+            }
+            dict.insert("Parent".into(), Object::Ref(pages_ref));
+            copier.objects.insert(*new_ref, Object::Dict(dict));
+            kids.push(Object::Ref(*new_ref));
+        }
+        copier.run()?;
+        bookmarks.extend(kept_bookmarks(&outline::load_outline(doc)?, &page_refs));
+    }
+    copier.share_streams();
+
+    let mut pages_dict = Dict::new();
+    pages_dict.insert("Type".into(), Object::Name(Name::new("Pages")));
+    pages_dict.insert("Count".into(), Object::Int(kids.len() as i64));
+    pages_dict.insert("Kids".into(), Object::Array(kids));
+    let mut catalog = Dict::new();
+    catalog.insert("Type".into(), Object::Name(Name::new("Catalog")));
+    catalog.insert("Pages".into(), Object::Ref(pages_ref));
+    if !bookmarks.is_empty() {
+        let outlines_ref = copier.allocate();
+        let (first, last, count) = copier.write_bookmarks(outlines_ref, &bookmarks);
+        let mut outlines = Dict::new();
+        outlines.insert("Type".into(), Object::Name(Name::new("Outlines")));
+        outlines.insert("First".into(), Object::Ref(first));
+        outlines.insert("Last".into(), Object::Ref(last));
+        outlines.insert("Count".into(), Object::Int(count));
+        copier.objects.insert(outlines_ref, Object::Dict(outlines));
+        catalog.insert("Outlines".into(), Object::Ref(outlines_ref));
+    }
+    copier.objects.insert(pages_ref, Object::Dict(pages_dict));
+    copier.objects.insert(catalog_ref, Object::Dict(catalog));
+
+    let mut trailer = Dict::new();
+    trailer.insert("Root".into(), Object::Ref(catalog_ref));
+    write_pdf(&copier.objects, &trailer, out)
+}
+
+/// A bookmark kept by [`merge_pages`], pointing at a copied page
+struct Bookmark {
+    title: String,
+    page: ObjRef,
+    children: Vec<Bookmark>,
+}
+
+/// Bookmarks that point at copied pages; children of dropped bookmarks
+/// take their place
+fn kept_bookmarks(items: &[OutlineItem], page_refs: &HashMap<usize, ObjRef>) -> Vec<Bookmark> {
+    let mut kept = Vec::new();
+    for item in items {
+        let children = kept_bookmarks(&item.children, page_refs);
+        match item.page.and_then(|p| page_refs.get(&p)) {
+            Some(&page) => kept.push(Bookmark {
+                title: item.title.clone(),
+                page,
+                children,
+            }),
+            None => kept.extend(children),
+        }
+    }
+    kept
+}
+
+/// Destination of a link annotation: /Dest or the /D of a GoTo action
+fn link_dest(dict: &Dict) -> Option<&Object> {
+    dict.get("Dest").or_else(|| {
+        let action = dict.get("A")?.as_dict()?;
+        let goto = action.get("S")?.as_name()?.as_str() == "GoTo";
+        if goto { action.get("D") } else { None }
+    })
+}
+
+/// Copies objects from a document, renumbering references
+struct Copier<'a> {
+    doc: &'a Document,
+    /// Old reference to new reference
+    map: HashMap<ObjRef, ObjRef>,
+    /// References replaced by null
+    dropped: HashSet<ObjRef>,
+    /// Objects referenced but not yet copied
+    queue: VecDeque<(ObjRef, ObjRef)>,
+    objects: BTreeMap<ObjRef, Object>,
+    next: i32,
+    /// Copied streams that are font programs
+    font_files: HashSet<ObjRef>,
+    /// Digest of the decoded data of copied font programs and images
+    digests: BTreeMap<ObjRef, [u8; 32]>,
+}
+
+impl Copier<'_> {
+    fn allocate(&mut self) -> ObjRef {
+        let obj_ref = ObjRef::new(self.next, 0);
+        self.next += 1;
+        obj_ref
+    }
+
+    /// Copy a direct object, queueing the objects it references
+    fn copy(&mut self, obj: &Object) -> Object {
+        match obj {
+            Object::Ref(r) => {
+                if self.dropped.contains(r) {
+                    return Object::Null;
+                }
+                let new_ref = match self.map.get(r) {
+                    Some(new_ref) => *new_ref,
+                    None => {
+                        let new_ref = self.allocate();
+                        self.map.insert(*r, new_ref);
+                        self.queue.push_back((*r, new_ref));
+                        new_ref
+                    }
+                };
+                Object::Ref(new_ref)
+            }
+            Object::Array(items) => Object::Array(items.iter().map(|o| self.copy(o)).collect()),
+            Object::Dict(dict) => Object::Dict(self.copy_dict(dict)),
+            Object::Stream { dict, data } => Object::Stream {
+                dict: self.copy_dict(dict),
+                data: data.clone(),
+            },
+            other => other.clone(),
+        }
+    }
+
+    fn copy_dict(&mut self, dict: &Dict) -> Dict {
+        dict.iter()
+            .map(|(k, v)| {
+                let copied = self.copy(v);
+                if let (true, Object::Ref(r)) = (k.as_str().starts_with("FontFile"), &copied) {
+                    self.font_files.insert(*r);
+                }
+                (k.clone(), copied)
+            })
+            .collect()
+    }
+
+    /// Copy a page's annotations, dropping links to pages that are not
+    /// copied and making named link destinations explicit
+    fn copy_annots(
+        &mut self,
+        annots: &Object,
+        catalog: &Dict,
+        page_refs: &HashMap<usize, ObjRef>,
+    ) -> Result<Vec<Object>> {
+        let Object::Array(annots) = self.doc.resolve(annots)? else {
+            return Ok(Vec::new());
+        };
+        let mut copied = Vec::with_capacity(annots.len());
+        for annot in &annots {
+            let Some(annot_ref) = annot.as_obj_ref() else {
+                copied.push(self.copy(annot));
+                continue;
+            };
+            if let Some(new_ref) = self.map.get(&annot_ref) {
+                copied.push(Object::Ref(*new_ref));
+                continue;
+            }
+            let Object::Dict(mut dict) = self.doc.load_object(annot_ref)? else {
+                continue;
+            };
+
+            let is_link = dict
+                .get("Subtype")
+                .and_then(Object::as_name)
+                .is_some_and(|s| s.as_str() == "Link");
+            if let Some(dest) = link_dest(&dict).filter(|_| is_link).cloned() {
+                // Unresolvable destinations are copied unchanged
+                if let Some(index) = outline::resolve_dest(self.doc, catalog, &dest)? {
+                    if !page_refs.contains_key(&index) {
+                        continue;
+                    }
+                    if !matches!(self.doc.resolve(&dest)?, Object::Array(_)) {
+                        let page = self.doc.page(index)?.obj_ref();
+                        dict.remove("A");
+                        dict.insert(
+                            "Dest".into(),
+                            Object::Array(vec![Object::Ref(page), Object::Name(Name::new("Fit"))]),
+                        );
+                    }
+                }
+            }
+
+            let new_ref = self.allocate();
+            self.map.insert(annot_ref, new_ref);
+            let obj = self.copy(&Object::Dict(dict));
+            self.objects.insert(new_ref, obj);
+            copied.push(Object::Ref(new_ref));
+        }
+        Ok(copied)
+    }
+
+    /// Write outline items under `parent`, returning the first and last
+    /// item and the number of items including descendants
+    fn write_bookmarks(&mut self, parent: ObjRef, items: &[Bookmark]) -> (ObjRef, ObjRef, i64) {
+        let refs: Vec<ObjRef> = items.iter().map(|_| self.allocate()).collect();
+        let mut count = 0;
+        for (i, item) in items.iter().enumerate() {
+            let mut dict = Dict::new();
+            dict.insert(
+                "Title".into(),
+                Object::String(PdfString::from_text(&item.title)),
+            );
+            dict.insert("Parent".into(), Object::Ref(parent));
+            dict.insert(
+                "Dest".into(),
+                Object::Array(vec![Object::Ref(item.page), Object::Name(Name::new("Fit"))]),
+            );
+            if i > 0 {
+                dict.insert("Prev".into(), Object::Ref(refs[i - 1]));
+            }
+            if let Some(next) = refs.get(i + 1) {
+                dict.insert("Next".into(), Object::Ref(*next));
+            }
+            if !item.children.is_empty() {
+                let (first, last, n) = self.write_bookmarks(refs[i], &item.children);
+                dict.insert("First".into(), Object::Ref(first));
+                dict.insert("Last".into(), Object::Ref(last));
+                dict.insert("Count".into(), Object::Int(n));
+                count += n;
+            }
+            self.objects.insert(refs[i], Object::Dict(dict));
+            count += 1;
+        }
+        (refs[0], refs[refs.len() - 1], count)
+    }
+
+    /// Copy queued objects until everything reachable has been copied
+    fn run(&mut self) -> Result<()> {
+        while let Some((old_ref, new_ref)) = self.queue.pop_front() {
+            let obj = self.doc.load_object(old_ref)?;
+            let is_page_node = obj
+                .as_dict()
+                .and_then(|d| d.get("Type"))
+                .and_then(Object::as_name)
+                .is_some_and(|t| t.as_str() == "Pages" || t.as_str() == "Page");
+            let copied = if is_page_node {
+                Object::Null
+            } else {
+                self.copy(&obj)
+            };
+            if let Object::Stream { dict, data } = &obj {
+                let is_image = dict
+                    .get("Subtype")
+                    .and_then(Object::as_name)
+                    .is_some_and(|s| s.as_str() == "Image");
+                if is_image || self.font_files.contains(&new_ref) {
+                    // Streams that cannot be decoded compare by their raw data
+                    let decoded = self.doc.stream_data(&Object::Ref(old_ref));
+                    let data = decoded.as_deref().unwrap_or(data);
+                    self.digests.insert(new_ref, Sha256::digest(data).into());
+                }
+            }
+            self.objects.insert(new_ref, copied);
+        }
+        Ok(())
+    }
+
+    /// Replace font programs and images that duplicate an earlier one by
+    /// references to it
+    ///
+    /// Streams match when their decoded data and their dictionaries, less
+    /// the encoding keys, are equal. Sharing one stream can make the
+    /// dictionaries of others equal, such as images with the same soft
+    /// mask, so this repeats until nothing changes.
+    fn share_streams(&mut self) {
+        loop {
+            let mut first: HashMap<Vec<u8>, ObjRef> = HashMap::new();
+            let mut aliases = HashMap::new();
+            for (&obj_ref, digest) in &self.digests {
+                let Some(Object::Stream { dict, .. }) = self.objects.get(&obj_ref) else {
+                    continue;
+                };
+                let mut dict = dict.clone();
+                for key in ["Length", "Filter", "DecodeParms"] {
+                    dict.remove(key);
+                }
+                let mut key = digest.to_vec();
+                key.extend(object_to_bytes(&Object::Dict(dict)));
+                match first.get(&key) {
+                    Some(&canonical) => {
+                        aliases.insert(obj_ref, canonical);
+                    }
+                    None => {
+                        first.insert(key, obj_ref);
+                    }
+                }
+            }
+            if aliases.is_empty() {
+                return;
+            }
+            for obj_ref in aliases.keys() {
+                self.objects.remove(obj_ref);
+                self.digests.remove(obj_ref);
+            }
+            for obj in self.objects.values_mut() {
+                rename_refs(obj, &aliases);
+            }
+        }
+    }
+}
+
+/// Point references in `obj` at their replacements in `aliases`
+fn rename_refs(obj: &mut Object, aliases: &HashMap<ObjRef, ObjRef>) {
+    match obj {
+        Object::Ref(r) => {
+            if let Some(&alias) = aliases.get(r) {
+                *r = alias;
+            }
+        }
+        Object::Array(items) => items.iter_mut().for_each(|item| rename_refs(item, aliases)),
+        Object::Dict(dict) | Object::Stream { dict, .. } => dict
+            .values_mut()
+            .for_each(|value| rename_refs(value, aliases)),
+        _ => {}
+    }
+}
+
+/// Byte offset recorded by the last `startxref` in the file
+pub fn find_startxref(data: &[u8]) -> Option<usize> {
+    let pos = parser::rfind_bytes(data, b"startxref")?;
+    let mut parser = Parser::at(data, pos + b"startxref".len());
+    parser
+        .parse_object()
+        .ok()?
+        .as_int()
+        .filter(|&v| v >= 0)
+        .map(|v| v as usize)
+}
+
+/// Trailer dictionary of the xref section at `offset`, which may be a
+/// classic `xref` table or a cross-reference stream
+fn trailer_at(data: &[u8], offset: usize) -> Result<Dict> {
+    if data[offset.min(data.len())..].starts_with(b"xref") {
+        let pos = parser::find_bytes(data, b"trailer", offset)
+            .ok_or_else(|| Error::format("xref table without trailer"))?;
+        return match Parser::at(data, pos + b"trailer".len()).parse_object()? {
+            Object::Dict(d) => Ok(d),
+            _ => Err(Error::format("invalid trailer")),
+        };
+    }
+    match parser::parse_indirect_object_at(data, offset)?.1 {
+        Object::Stream { dict, .. } => Ok(dict),
+        _ => Err(Error::format("startxref does not point at an xref section")),
+    }
+}
+
+/// Append an incremental update to `original`
+///
+/// The original bytes are written unchanged, followed by the objects in
+/// `changed`, a new xref section covering them and a trailer whose `/Prev`
+/// points at the previous section. An [`Object::Null`] value deletes the
+/// object: it gets a free entry with its generation number bumped.
+///
+/// Because unchanged bytes are preserved, existing signatures stay valid.
+pub fn append_incremental<W: Write>(
+    original: &[u8],
+    changed: &BTreeMap<ObjRef, Object>,
+    out: &mut W,
+) -> Result<()> {
+    let prev = find_startxref(original).ok_or_else(|| Error::format("missing startxref"))?;
+    let prev_trailer = trailer_at(original, prev)?;
+
+    out.write_all(original)?;
+    let mut offset = original.len();
+    if !original.ends_with(b"\n") && !original.ends_with(b"\r") {
+        out.write_all(b"\n")?;
+        offset += 1;
+    }
+
+    let mut entries = Vec::with_capacity(changed.len());
+    for (obj_ref, obj) in changed {
+        if obj.is_null() {
+            let next_gen = (obj_ref.generation + 1).min(65535) as u16;
+            entries.push(XrefEntry::free(obj_ref.num, next_gen));
+            continue;
+        }
+        let mut body = format!("{} {} obj\n", obj_ref.num, obj_ref.generation).into_bytes();
+        write_object(&mut body, obj)?;
+        body.extend_from_slice(b"\nendobj\n");
+        out.write_all(&body)?;
+        entries.push(XrefEntry::in_use(
+            obj_ref.num,
+            obj_ref.generation as u16,
+            offset as i64,
+        ));
+        offset += body.len();
+    }
+
+    let xref_offset = offset;
+    out.write_all(b"xref\n")?;
+    let mut i = 0;
+    while i < entries.len() {
+        // One subsection per run of consecutive object numbers
+        let start = entries[i].num;
+        let mut end = i + 1;
+        while end < entries.len() && entries[end].num == start + (end - i) as i32 {
+            end += 1;
+        }
+        writeln!(out, "{} {}", start, end - i)?;
+        for entry in &entries[i..end] {
+            let kind = if entry.is_free() { 'f' } else { 'n' };
+            write!(
+                out,
+                "{:010} {:05} {kind}\r\n",
+                entry.offset, entry.generation
+            )?;
+        }
+        i = end;
+    }
+
+    let prev_size = prev_trailer
+        .get("Size")
+        .and_then(Object::as_int)
+        .unwrap_or(0);
+    let max_num = changed.keys().map(|r| r.num as i64 + 1).max().unwrap_or(0);
+    let mut trailer: Dict = prev_trailer
+        .into_iter()
+        .filter(|(k, _)| !SECTION_KEYS.contains(&k.as_str()))
+        .collect();
+    trailer.insert("Size".into(), Object::Int(prev_size.max(max_num)));
+    trailer.insert("Prev".into(), Object::Int(prev as i64));
+
+    out.write_all(b"trailer\n")?;
+    write_dict(out, &trailer)?;
+    write!(out, "\nstartxref\n{xref_offset}\n%%EOF\n")?;
+    Ok(())
+}
+
+/// A content stream written one operator at a time
+///
+/// Each method appends its operands and operator as a line, with numbers
+/// formatted like the rest of the writer, so callers never assemble
+/// operator text by hand. Methods chain:
+/// `builder.set_rgb_fill(1.0, 0.0, 0.0).rect(0.0, 0.0, 10.0, 10.0).fill()`.
+#[derive(Debug, Clone, Default)]
+pub struct ContentBuilder {
+    buf: Buffer,
+}
+
+impl ContentBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The operators written so far
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.buf.to_vec()
+    }
+
+    pub fn into_buffer(self) -> Buffer {
+        self.buf
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    fn numbers(&mut self, operands: &[f32]) {
+        for &v in operands {
+            self.buf.append_string(&format_real(f64::from(v)));
+            self.buf.append_byte(b' ');
+        }
+    }
+
+    fn name(&mut self, name: &str) {
+        // Appending to a buffer cannot fail
+        let _ = write_name(&mut self.buf, name);
+        self.buf.append_byte(b' ');
+    }
+
+    fn op(&mut self, operator: &str) -> &mut Self {
+        self.buf.append_string(operator);
+        self.buf.append_byte(b'\n');
+        self
+    }
+
+    fn op_numbers(&mut self, operands: &[f32], operator: &str) -> &mut Self {
+        self.numbers(operands);
+        self.op(operator)
+    }
+
+    /// `q`: save the graphics state
+    pub fn save(&mut self) -> &mut Self {
+        self.op("q")
+    }
+
+    /// `Q`: restore the graphics state
+    pub fn restore(&mut self) -> &mut Self {
+        self.op("Q")
+    }
+
+    /// `cm`: transform user space by `m`
+    pub fn concat(&mut self, m: &Matrix) -> &mut Self {
+        self.op_numbers(&[m.a, m.b, m.c, m.d, m.e, m.f], "cm")
+    }
+
+    /// `gs`: apply the named /ExtGState resource
+    pub fn set_ext_gstate(&mut self, name: &str) -> &mut Self {
+        self.name(name);
+        self.op("gs")
+    }
+
+    pub fn set_line_width(&mut self, width: f32) -> &mut Self {
+        self.op_numbers(&[width], "w")
+    }
+
+    /// `J`: 0 butt, 1 round, 2 square
+    pub fn set_line_cap(&mut self, cap: i32) -> &mut Self {
+        self.op_numbers(&[cap as f32], "J")
+    }
+
+    /// `j`: 0 miter, 1 round, 2 bevel
+    pub fn set_line_join(&mut self, join: i32) -> &mut Self {
+        self.op_numbers(&[join as f32], "j")
+    }
+
+    /// `d`: dash lengths and the phase to start at; no lengths is solid
+    pub fn set_dash(&mut self, pattern: &[f32], phase: f32) -> &mut Self {
+        self.buf.append_byte(b'[');
+        for (i, &v) in pattern.iter().enumerate() {
+            if i > 0 {
+                self.buf.append_byte(b' ');
+            }
+            self.buf.append_string(&format_real(f64::from(v)));
+        }
+        self.buf.append_string("] ");
+        self.op_numbers(&[phase], "d")
+    }
+
+    pub fn set_gray_fill(&mut self, gray: f32) -> &mut Self {
+        self.op_numbers(&[gray], "g")
+    }
+
+    pub fn set_gray_stroke(&mut self, gray: f32) -> &mut Self {
+        self.op_numbers(&[gray], "G")
+    }
+
+    pub fn set_rgb_fill(&mut self, r: f32, g: f32, b: f32) -> &mut Self {
+        self.op_numbers(&[r, g, b], "rg")
+    }
+
+    pub fn set_rgb_stroke(&mut self, r: f32, g: f32, b: f32) -> &mut Self {
+        self.op_numbers(&[r, g, b], "RG")
+    }
+
+    pub fn move_to(&mut self, x: f32, y: f32) -> &mut Self {
+        self.op_numbers(&[x, y], "m")
+    }
+
+    pub fn line_to(&mut self, x: f32, y: f32) -> &mut Self {
+        self.op_numbers(&[x, y], "l")
+    }
+
+    /// `c`: a cubic Bézier curve through two control points to `(x3, y3)`
+    pub fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32) -> &mut Self {
+        self.op_numbers(&[x1, y1, x2, y2, x3, y3], "c")
+    }
+
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32) -> &mut Self {
+        self.op_numbers(&[x, y, width, height], "re")
+    }
+
+    /// `h`: close the current subpath
+    pub fn close_path(&mut self) -> &mut Self {
+        self.op("h")
+    }
+
+    /// Construct `path`; quadratic curves become cubic ones
+    pub fn path(&mut self, path: &Path) -> &mut Self {
+        let mut current = Point::new(0.0, 0.0);
+        let mut start = current;
+        for element in path.elements() {
+            match *element {
+                PathElement::MoveTo(p) => {
+                    self.move_to(p.x, p.y);
+                    (current, start) = (p, p);
+                }
+                PathElement::LineTo(p) => {
+                    self.line_to(p.x, p.y);
+                    current = p;
+                }
+                PathElement::QuadTo(c, p) => {
+                    let c1 = Point::new(
+                        current.x + (c.x - current.x) * 2.0 / 3.0,
+                        current.y + (c.y - current.y) * 2.0 / 3.0,
+                    );
+                    let c2 =
+                        Point::new(p.x + (c.x - p.x) * 2.0 / 3.0, p.y + (c.y - p.y) * 2.0 / 3.0);
+                    self.curve_to(c1.x, c1.y, c2.x, c2.y, p.x, p.y);
+                    current = p;
+                }
+                PathElement::CurveTo(c1, c2, p) => {
+                    self.curve_to(c1.x, c1.y, c2.x, c2.y, p.x, p.y);
+                    current = p;
+                }
+                PathElement::Close => {
+                    self.close_path();
+                    current = start;
+                }
+                PathElement::Rect(r) => {
+                    self.rect(r.x0, r.y0, r.width(), r.height());
+                    current = Point::new(r.x0, r.y0);
+                    start = current;
+                }
+            }
+        }
+        self
+    }
+
+    /// `f`: fill with the nonzero winding rule
+    pub fn fill(&mut self) -> &mut Self {
+        self.op("f")
+    }
+
+    /// `f*`: fill with the even-odd rule
+    pub fn fill_even_odd(&mut self) -> &mut Self {
+        self.op("f*")
+    }
+
+    /// `S`: stroke
+    pub fn stroke(&mut self) -> &mut Self {
+        self.op("S")
+    }
+
+    /// `B`: fill, then stroke
+    pub fn fill_stroke(&mut self) -> &mut Self {
+        self.op("B")
+    }
+
+    /// `n`: end the path without painting it
+    pub fn end_path(&mut self) -> &mut Self {
+        self.op("n")
+    }
+
+    /// `Do`: paint the named /XObject resource
+    pub fn draw_xobject(&mut self, name: &str) -> &mut Self {
+        self.name(name);
+        self.op("Do")
+    }
+
+    pub fn begin_text(&mut self) -> &mut Self {
+        self.op("BT")
+    }
+
+    pub fn end_text(&mut self) -> &mut Self {
+        self.op("ET")
+    }
+
+    /// `Tf`: the named /Font resource at `size`
+    pub fn set_font(&mut self, name: &str, size: f32) -> &mut Self {
+        self.name(name);
+        self.op_numbers(&[size], "Tf")
+    }
+
+    /// `Tm`: set the text matrix
+    pub fn set_text_matrix(&mut self, m: &Matrix) -> &mut Self {
+        self.op_numbers(&[m.a, m.b, m.c, m.d, m.e, m.f], "Tm")
+    }
+
+    /// `Td`: move to the start of the next line, offset from this one
+    pub fn move_text(&mut self, x: f32, y: f32) -> &mut Self {
+        self.op_numbers(&[x, y], "Td")
+    }
+
+    /// `Tj`: show `text` as a literal string
+    pub fn show_text(&mut self, text: &str) -> &mut Self {
+        self.buf.append_pdf_string(text);
+        self.buf.append_byte(b' ');
+        self.op("Tj")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::object::{Name, PdfString};
+    use crate::pdf::test_pdf::build_pdf;
+
+    /// A small PDF with a correct classic xref table
+    fn original_pdf() -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [] /Count 0 >>",
+            "(original)",
+        ];
+        build_pdf(&objects)
+    }
+
+    #[test]
+    fn test_write_object_roundtrip() {
+        let mut dict = Dict::new();
+        dict.insert(Name::new("A B"), Object::Real(1.5));
+        dict.insert(
+            Name::new("S"),
+            Object::String(PdfString::new(b"a(b)\\c".to_vec())),
+        );
+        dict.insert(
+            Name::new("Bin"),
+            Object::String(PdfString::new(vec![0, 1, 2, 255])),
+        );
+        dict.insert(
+            Name::new("K"),
+            Object::Array(vec![Object::Ref(ObjRef::new(3, 0)), Object::Int(-2)]),
+        );
+        let bytes = object_to_bytes(&Object::Dict(dict.clone()));
+        let parsed = parser::parse_object(&bytes).unwrap();
+        assert_eq!(parsed.as_dict().unwrap(), &dict);
+    }
+
+    #[test]
+    fn test_auto_stream_encoding() {
+        let stream = |dict: &[(&str, Object)], data: &[u8]| Object::Stream {
+            dict: dict
+                .iter()
+                .map(|(k, v)| (Name::new(k), v.clone()))
+                .collect(),
+            data: data.to_vec(),
+        };
+        let repetitive = b"0 0 m 10 10 l S\n".repeat(200);
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xE0].repeat(100);
+        let mut objects = BTreeMap::new();
+        objects.insert(
+            ObjRef::new(1, 0),
+            parser::parse_object(b"<< /Type /Catalog >>").unwrap(),
+        );
+        objects.insert(ObjRef::new(2, 0), stream(&[], &repetitive));
+        objects.insert(ObjRef::new(3, 0), stream(&[], b"q Q"));
+        objects.insert(
+            ObjRef::new(4, 0),
+            stream(&[("Filter", Object::Name(Name::new("DCTDecode")))], &jpeg),
+        );
+        let mut trailer = Dict::new();
+        trailer.insert("Root".into(), Object::Ref(ObjRef::new(1, 0)));
+
+        let mut out = Vec::new();
+        write_pdf_with(&objects, &trailer, StreamEncoding::Auto, &mut out).unwrap();
+        assert!(out.len() < repetitive.len());
+        let doc = Document::open_bytes(out).unwrap();
+        let filter = |num| {
+            let obj = doc.load_object(ObjRef::new(num, 0)).unwrap();
+            obj.as_dict().unwrap().get("Filter").cloned()
+        };
+        assert_eq!(filter(2), Some(Object::Name(Name::new("FlateDecode"))));
+        let data = doc.stream_data(&Object::Ref(ObjRef::new(2, 0))).unwrap();
+        assert_eq!(data, repetitive);
+        // Compressing three bytes would only make the stream bigger
+        assert_eq!(filter(3), None);
+        // Already compressed data is passed through untouched
+        assert_eq!(filter(4), Some(Object::Name(Name::new("DCTDecode"))));
+        let Object::Stream { data, .. } = doc.load_object(ObjRef::new(4, 0)).unwrap() else {
+            panic!("expected stream");
+        };
+        assert_eq!(data, jpeg);
+
+        let mut out = Vec::new();
+        let forced = StreamEncoding::Filter(FilterType::ASCIIHexDecode);
+        write_pdf_with(&objects, &trailer, forced, &mut out).unwrap();
+        let doc = Document::open_bytes(out).unwrap();
+        let obj = doc.load_object(ObjRef::new(3, 0)).unwrap();
+        assert_eq!(
+            obj.as_dict().unwrap().get("Filter"),
+            Some(&Object::Name(Name::new("ASCIIHexDecode")))
+        );
+        assert_eq!(
+            doc.stream_data(&Object::Ref(ObjRef::new(3, 0))).unwrap(),
+            b"q Q"
+        );
+    }
+
+    #[test]
+    fn test_copy_pages_renumbers_and_prunes() {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R] /Count 3 /Resources 6 0 R >>",
+            "<< /Type /Page /Parent 2 0 R /Annots [7 0 R] >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Font << /F1 8 0 R >> >>",
+            "<< /Type /Annot /Subtype /Link /P 3 0 R /A << /S /GoTo /D [5 0 R /Fit] >> \
+             /Next [4 0 R] >>",
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>",
+        ];
+        let data = build_pdf(&objects);
+        let doc = Document::open_bytes(data).unwrap();
+
+        let mut out = Vec::new();
+        copy_pages(&doc, &[2, 0], &mut out).unwrap();
+        let copy = Document::open_bytes(out).unwrap();
+        assert_eq!(copy.page_count().unwrap(), 2);
+
+        // Inherited resources are copied into each page, sharing one font
+        let first = copy.page(0).unwrap();
+        let second = copy.page(1).unwrap();
+        assert_eq!(first.dict()["Resources"], second.dict()["Resources"]);
+
+        let annots = second.dict()["Annots"].as_array().unwrap();
+        let Object::Dict(link) = copy.resolve(&annots[0]).unwrap() else {
+            panic!("annotation not copied");
+        };
+        assert_eq!(link["P"], Object::Ref(second.obj_ref()));
+        let dest = link["A"].as_dict().unwrap()["D"].as_array().unwrap();
+        assert_eq!(dest[0], Object::Ref(first.obj_ref()));
+        // The dropped page becomes null
+        assert_eq!(link["Next"], Object::Array(vec![Object::Null]));
+    }
+
+    #[test]
+    fn test_append_incremental_chains_prev() {
+        let original = original_pdf();
+        let old_xref = find_startxref(&original).unwrap();
+
+        let mut changed = BTreeMap::new();
+        changed.insert(
+            ObjRef::new(3, 0),
+            Object::String(PdfString::new(b"modified".to_vec())),
+        );
+        changed.insert(ObjRef::new(2, 0), Object::Null);
+        let mut out = Vec::new();
+        append_incremental(&original, &changed, &mut out).unwrap();
+
+        // Original bytes are preserved verbatim
+        assert!(out.starts_with(&original));
+
+        let new_xref = find_startxref(&out).unwrap();
+        assert!(new_xref > old_xref);
+        let trailer = trailer_at(&out, new_xref).unwrap();
+        assert_eq!(trailer.get("Prev").unwrap().as_int(), Some(old_xref as i64));
+        assert_eq!(trailer.get("Size").unwrap().as_int(), Some(4));
+        assert!(trailer.contains_key("Root"));
+        assert!(!trailer_at(&out, old_xref).unwrap().contains_key("Prev"));
+
+        let section = std::str::from_utf8(&out[new_xref..]).unwrap();
+        assert!(section.contains("2 2\n0000000000 00001 f\r\n"));
+
+        let obj = parser::find_object(&out, ObjRef::new(3, 0)).unwrap();
+        assert_eq!(obj.as_string().unwrap().as_bytes(), b"modified");
+    }
+
+    #[test]
+    fn test_content_builder_rect_fill() {
+        let mut content = ContentBuilder::new();
+        content
+            .save()
+            .set_rgb_fill(1.0, 0.5, 0.0)
+            .rect(10.0, 20.5, 100.0, 50.0)
+            .fill()
+            .restore();
+        assert_eq!(
+            content.to_vec(),
+            b"q\n1 0.5 0 rg\n10 20.5 100 50 re\nf\nQ\n"
+        );
+
+        let mut path = Path::new();
+        path.rect(crate::fitz::geometry::Rect::new(10.0, 20.5, 110.0, 70.5));
+        let mut from_path = ContentBuilder::new();
+        from_path.path(&path);
+        assert_eq!(from_path.to_vec(), b"10 20.5 100 50 re\n");
+    }
+
+    #[test]
+    fn test_content_builder_show_text() {
+        let mut content = ContentBuilder::new();
+        content
+            .begin_text()
+            .set_font("F1", 12.0)
+            .move_text(72.0, 720.0)
+            .show_text("Hello (world)")
+            .end_text();
+        assert_eq!(
+            content.to_vec(),
+            b"BT\n/F1 12 Tf\n72 720 Td\n(Hello \\(world\\)) Tj\nET\n"
+        );
+    }
+}
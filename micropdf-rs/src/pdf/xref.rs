@@ -1,9 +1,23 @@
 //! Cross-reference table - PDF object location tracking
 //!
 //! The xref table maps object numbers to file offsets for efficient PDF parsing.
+//! [`XrefTable::parse`] reads classic tables, cross-reference streams and
+//! hybrid files, following `/Prev` chains from the last `startxref`.
 
 use crate::fitz::error::{Error, Result};
-use std::collections::HashMap;
+use crate::pdf::filter::decode_stream;
+use crate::pdf::object::{Dict, ObjRef, Object};
+use crate::pdf::parser::{self, Item, Parser};
+use bytes::Bytes;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+
+/// Maximum number of sections followed through `/Prev`
+const MAX_SECTIONS: usize = 4096;
+
+thread_local! {
+    static OBJSTM_DECODES: Cell<u64> = const { Cell::new(0) };
+}
 
 /// Type of xref entry
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -137,16 +151,82 @@ pub struct XrefTable {
     lookup: HashMap<i32, XrefEntry>,
     /// Maximum object number
     max_num: i32,
+    /// File bytes objects are read from, for parsed tables
+    data: Bytes,
+    /// Trailer of the newest section
+    trailer: Dict,
+    /// Objects already loaded by [`XrefTable::resolve`]
+    cache: HashMap<ObjRef, Object>,
+    /// Decoded object streams by object number
+    obj_streams: HashMap<i32, ObjectStream>,
+}
+
+/// A decoded `/Type /ObjStm` stream
+pub(crate) struct ObjectStream {
+    /// Decoded stream data
+    data: Vec<u8>,
+    /// Object number and offset into `data` of each member, in index order
+    members: Vec<(i32, usize)>,
+}
+
+impl ObjectStream {
+    /// Inflate the stream and read the `N` number/offset pairs of its header
+    fn decode(dict: &Dict, data: Vec<u8>) -> Result<Self> {
+        Self::from_decoded(dict, decode_stream(dict, &data)?)
+    }
+
+    /// Read the header of a stream whose filters have already been applied
+    pub(crate) fn from_decoded(dict: &Dict, data: Vec<u8>) -> Result<Self> {
+        OBJSTM_DECODES.with(|decodes| decodes.set(decodes.get() + 1));
+        let int = |key: &str| {
+            dict.get(key)
+                .and_then(Object::as_int)
+                .filter(|&v| v >= 0)
+                .ok_or_else(|| Error::syntax(format!("invalid object stream /{key}")))
+        };
+        let (count, first) = (int("N")? as usize, int("First")? as usize);
+        if first > data.len() {
+            return Err(Error::syntax(format!(
+                "object stream /First {first} is past its {} bytes",
+                data.len()
+            )));
+        }
+
+        let mut header = Parser::new(&data[..first]);
+        let mut members = Vec::with_capacity(count.min(first));
+        for _ in 0..count {
+            match (header.parse_object()?, header.parse_object()?) {
+                (Object::Int(num), Object::Int(offset))
+                    if offset >= 0 && first + (offset as usize) < data.len() =>
+                {
+                    members.push((num as i32, first + offset as usize))
+                }
+                _ => return Err(Error::syntax("invalid object stream header")),
+            }
+        }
+        Ok(Self { data, members })
+    }
+
+    /// Parse member `index`, which the xref says is object `num`
+    pub(crate) fn object(&self, num: i32, index: usize) -> Result<Object> {
+        match self.members.get(index) {
+            Some(&(found, offset)) if found == num => Parser::at(&self.data, offset).parse_object(),
+            _ => Err(Error::syntax(format!(
+                "object {num} is not at index {index} of its object stream"
+            ))),
+        }
+    }
+}
+
+/// Number of object streams inflated on the current thread
+pub fn object_stream_decode_count() -> u64 {
+    OBJSTM_DECODES.with(Cell::get)
 }
 
 impl XrefTable {
     /// Create a new empty xref table
     pub fn new() -> Self {
-        Self {
-            subsections: Vec::new(),
-            lookup: HashMap::new(),
-            max_num: 0,
-        }
+        Self::with_capacity(0)
     }
 
     /// Create xref table with initial capacity
@@ -155,7 +235,137 @@ impl XrefTable {
             subsections: Vec::new(),
             lookup: HashMap::with_capacity(capacity),
             max_num: 0,
+            data: Bytes::new(),
+            trailer: Dict::new(),
+            cache: HashMap::new(),
+            obj_streams: HashMap::new(),
+        }
+    }
+
+    /// Read the cross-reference sections of a complete PDF file
+    ///
+    /// Starts at the offset after the last `startxref` and follows `/Prev`
+    /// links, so the newest definition of each object wins. Classic
+    /// sections may point at a cross-reference stream with `/XRefStm`; its
+    /// entries fill in objects the table leaves out or marks free.
+    pub fn parse(data: impl Into<Bytes>) -> Result<Self> {
+        let data = data.into();
+        let startxref = parser::rfind_bytes(&data, b"startxref")
+            .ok_or_else(|| Error::format("no startxref"))?;
+        let mut offset = match Parser::at(&data, startxref + b"startxref".len()).parse_object()? {
+            Object::Int(o) if o >= 0 => o as usize,
+            _ => return Err(Error::syntax("invalid startxref offset")),
+        };
+
+        let mut table = Self::new();
+        let mut visited = HashSet::new();
+        loop {
+            if !visited.insert(offset) {
+                return Err(Error::syntax(format!("xref /Prev loop at offset {offset}")));
+            }
+            if visited.len() > MAX_SECTIONS {
+                return Err(Error::limit("too many xref sections"));
+            }
+
+            let (entries, trailer) = read_section(&data, offset)?;
+            let mut section: HashMap<i32, XrefEntry> =
+                entries.into_iter().map(|e| (e.num, e)).collect();
+            if let Some(stm_offset) = trailer.get("XRefStm").and_then(Object::as_int) {
+                let (hidden, _) = read_section(&data, stm_offset.max(0) as usize)?;
+                for entry in hidden {
+                    if section.get(&entry.num).is_none_or(XrefEntry::is_free) {
+                        section.insert(entry.num, entry);
+                    }
+                }
+            }
+            for (num, entry) in section {
+                if !table.lookup.contains_key(&num) {
+                    table.add_entry(entry);
+                }
+            }
+
+            let prev = trailer.get("Prev").and_then(Object::as_int);
+            if visited.len() == 1 {
+                table.trailer = trailer;
+            }
+            match prev {
+                Some(p) if p >= 0 => offset = p as usize,
+                _ => break,
+            }
+        }
+        table.data = data;
+        Ok(table)
+    }
+
+    /// Trailer dictionary of the newest section
+    ///
+    /// For cross-reference streams this is the stream's dictionary.
+    pub fn trailer(&self) -> &Dict {
+        &self.trailer
+    }
+
+    /// Load an indirect object, caching it for later calls
+    ///
+    /// Objects inside object streams are read by decoding their stream.
+    /// Free, missing and generation-mismatched references resolve to null,
+    /// as the specification requires. Encryption is not applied.
+    pub fn resolve(&mut self, r: ObjRef) -> Result<Object> {
+        if let Some(obj) = self.cache.get(&r) {
+            return Ok(obj.clone());
+        }
+        let Some(entry) = self.lookup.get(&r.num).cloned() else {
+            return Ok(Object::Null);
+        };
+        let obj = match entry.entry_type {
+            XrefEntryType::Free => return Ok(Object::Null),
+            XrefEntryType::InUse => {
+                if i32::from(entry.generation) != r.generation {
+                    return Ok(Object::Null);
+                }
+                let offset = usize::try_from(entry.offset)
+                    .ok()
+                    .filter(|&o| o < self.data.len())
+                    .ok_or_else(|| {
+                        Error::syntax(format!("object {} offset out of range", r.num))
+                    })?;
+                let (found, obj) = parser::parse_indirect_object_at(&self.data, offset)?;
+                if found.num != r.num {
+                    return Err(Error::syntax(format!(
+                        "xref points object {} at object {}",
+                        r.num, found.num
+                    )));
+                }
+                obj
+            }
+            XrefEntryType::ObjStm => {
+                if r.generation != 0 {
+                    return Ok(Object::Null);
+                }
+                self.object_stream(entry.offset)?
+                    .object(r.num, usize::from(entry.stm_index))?
+            }
+        };
+        self.cache.insert(r, obj.clone());
+        Ok(obj)
+    }
+
+    /// The decoded object stream `stm_num`, inflating it on first use
+    fn object_stream(&mut self, stm_num: i64) -> Result<&ObjectStream> {
+        let stm_ref = ObjRef::new(stm_num as i32, 0);
+        if !self.obj_streams.contains_key(&stm_ref.num) {
+            if !self.get(stm_ref.num).is_some_and(XrefEntry::is_in_use) {
+                return Err(Error::syntax(format!(
+                    "object stream {stm_num} is not in use"
+                )));
+            }
+            let Object::Stream { dict, data } = self.resolve(stm_ref)? else {
+                return Err(Error::syntax(format!("object {stm_num} is not a stream")));
+            };
+            let stm = ObjectStream::decode(&dict, data)?;
+            self.cache.remove(&stm_ref);
+            self.obj_streams.insert(stm_ref.num, stm);
         }
+        Ok(&self.obj_streams[&stm_ref.num])
     }
 
     /// Add a subsection to the xref table
@@ -296,6 +506,123 @@ impl XrefTable {
     }
 }
 
+/// Read the one cross-reference section at `offset`, a classic table or a
+/// stream, with its trailer; `/Prev` is not followed
+pub fn read_section(data: &[u8], offset: usize) -> Result<(Vec<XrefEntry>, Dict)> {
+    if offset >= data.len() {
+        return Err(Error::syntax(format!("xref offset {offset} out of range")));
+    }
+    let mut parser = Parser::at(data, offset);
+    match parser.parse_item()? {
+        Item::Keyword(k) if k == "xref" => read_table(&mut parser),
+        _ => match parser::parse_indirect_object_at(data, offset)? {
+            (_, Object::Stream { dict, data })
+                if dict
+                    .get("Type")
+                    .and_then(Object::as_name)
+                    .is_some_and(|t| t.as_str() == "XRef") =>
+            {
+                let entries = read_stream_entries(&dict, data)?;
+                Ok((entries, dict))
+            }
+            _ => Err(Error::syntax(format!("no xref section at offset {offset}"))),
+        },
+    }
+}
+
+/// Read the subsections of a classic table and the trailer that follows
+fn read_table(parser: &mut Parser) -> Result<(Vec<XrefEntry>, Dict)> {
+    let mut entries = Vec::new();
+    loop {
+        let start = match parser.parse_item()? {
+            Item::Keyword(k) if k == "trailer" => break,
+            Item::Object(Object::Int(start)) if start >= 0 => start as i32,
+            _ => return Err(Error::syntax("invalid xref subsection header")),
+        };
+        let Item::Object(Object::Int(count)) = parser.parse_item()? else {
+            return Err(Error::syntax("invalid xref subsection count"));
+        };
+        for i in 0..count.max(0) as i32 {
+            let (Item::Object(Object::Int(field)), Item::Object(Object::Int(generation))) =
+                (parser.parse_item()?, parser.parse_item()?)
+            else {
+                return Err(Error::syntax("invalid xref entry"));
+            };
+            let num = start + i;
+            let generation = generation.clamp(0, u16::MAX as i64) as u16;
+            entries.push(match parser.parse_item()? {
+                Item::Keyword(k) if k == "n" => XrefEntry::in_use(num, generation, field),
+                Item::Keyword(k) if k == "f" => XrefEntry::free(num, generation),
+                _ => {
+                    return Err(Error::syntax(format!(
+                        "invalid xref entry for object {num}"
+                    )));
+                }
+            });
+        }
+    }
+    match parser.parse_object()? {
+        Object::Dict(trailer) => Ok((entries, trailer)),
+        _ => Err(Error::syntax("trailer is not a dictionary")),
+    }
+}
+
+/// Entries of a cross-reference stream, laid out by `/W` and `/Index`
+fn read_stream_entries(dict: &Dict, data: Vec<u8>) -> Result<Vec<XrefEntry>> {
+    let ints = |key: &str| -> Option<Vec<i64>> {
+        dict.get(key)?
+            .as_array()?
+            .iter()
+            .map(|o| o.as_int().filter(|&v| v >= 0))
+            .collect()
+    };
+    let widths = ints("W")
+        .filter(|w| w.len() == 3 && w.iter().all(|&v| v <= 8))
+        .ok_or_else(|| Error::syntax("invalid xref stream /W"))?;
+    let size = dict.get("Size").and_then(Object::as_int).unwrap_or(0);
+    let index = match dict.get("Index") {
+        Some(_) => ints("Index")
+            .filter(|i| i.len() % 2 == 0)
+            .ok_or_else(|| Error::syntax("invalid xref stream /Index"))?,
+        None => vec![0, size],
+    };
+
+    let decoded = decode_stream(dict, &data)?;
+    let row = widths.iter().sum::<i64>() as usize;
+    if row == 0 {
+        return Err(Error::syntax("empty xref stream /W"));
+    }
+    let mut rows = decoded.chunks_exact(row);
+    let mut entries = Vec::new();
+    for pair in index.chunks(2) {
+        for num in pair[0]..pair[0] + pair[1] {
+            let Some(bytes) = rows.next() else {
+                return Ok(entries);
+            };
+            let mut fields = [0i64; 3];
+            let mut pos = 0;
+            for (field, &width) in fields.iter_mut().zip(&widths) {
+                for &b in &bytes[pos..pos + width as usize] {
+                    *field = (*field << 8) | i64::from(b);
+                }
+                pos += width as usize;
+            }
+            // A missing type field means type 1
+            let kind = if widths[0] == 0 { 1 } else { fields[0] };
+            let num = num as i32;
+            let generation = fields[2].clamp(0, u16::MAX as i64) as u16;
+            match kind {
+                0 => entries.push(XrefEntry::free(num, generation)),
+                1 => entries.push(XrefEntry::in_use(num, generation, fields[1])),
+                2 => entries.push(XrefEntry::compressed(num, fields[1], generation)),
+                // Unknown types are references to the null object
+                _ => {}
+            }
+        }
+    }
+    Ok(entries)
+}
+
 impl Default for XrefTable {
     fn default() -> Self {
         Self::new()
@@ -489,4 +816,164 @@ mod tests {
         assert_eq!(table.free_count(), 1);
         assert_eq!(table.compressed_count(), 1);
     }
+
+    /// Append `objects` as `N 0 obj` definitions, returning their offsets
+    fn append_objects(out: &mut Vec<u8>, objects: &[(i32, &[u8])]) -> Vec<(i32, usize)> {
+        let mut offsets = Vec::new();
+        for &(num, body) in objects {
+            offsets.push((num, out.len()));
+            out.extend_from_slice(format!("{num} 0 obj\n").as_bytes());
+            out.extend_from_slice(body);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+        offsets
+    }
+
+    #[test]
+    fn test_parse_classic_xref_with_update() {
+        let mut pdf = b"%PDF-1.7\n".to_vec();
+        let offsets = append_objects(
+            &mut pdf,
+            &[
+                (1, b"<< /Type /Catalog /Pages 2 0 R >>"),
+                (2, b"<< /Type /Pages /Kids [] /Count 0 >>"),
+                (3, b"(old)"),
+            ],
+        );
+        let first_xref = pdf.len();
+        pdf.extend_from_slice(b"xref\n0 4\n0000000000 65535 f \n");
+        for (_, offset) in &offsets {
+            pdf.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        pdf.extend_from_slice(b"trailer\n<< /Size 4 /Root 1 0 R >>\n");
+        pdf.extend_from_slice(format!("startxref\n{first_xref}\n%%EOF\n").as_bytes());
+
+        // Incremental update: object 2 is replaced and object 3 deleted
+        let update = append_objects(&mut pdf, &[(2, b"<< /Type /Pages /Kids [] /Count 7 >>")]);
+        let second_xref = pdf.len();
+        pdf.extend_from_slice(
+            format!(
+                "xref\n2 2\n{:010} 00000 n \n0000000000 00001 f \ntrailer\n<< /Size 4 /Root 1 0 R /Prev {first_xref} >>\nstartxref\n{second_xref}\n%%EOF\n",
+                update[0].1
+            )
+            .as_bytes(),
+        );
+
+        let mut table = XrefTable::parse(pdf).unwrap();
+        assert_eq!(
+            table.trailer().get("Root").unwrap().as_obj_ref(),
+            Some(ObjRef::new(1, 0))
+        );
+        assert_eq!(table.len(), 4);
+        assert_eq!(table.get(2).unwrap().offset, update[0].1 as i64);
+
+        let pages = table.resolve(ObjRef::new(2, 0)).unwrap();
+        assert_eq!(
+            pages.as_dict().unwrap().get("Count").unwrap().as_int(),
+            Some(7)
+        );
+        let catalog = table.resolve(ObjRef::new(1, 0)).unwrap();
+        assert_eq!(
+            catalog
+                .as_dict()
+                .unwrap()
+                .get("Pages")
+                .unwrap()
+                .as_obj_ref(),
+            Some(ObjRef::new(2, 0))
+        );
+        assert!(table.resolve(ObjRef::new(3, 0)).unwrap().is_null());
+        assert!(table.resolve(ObjRef::new(1, 1)).unwrap().is_null());
+        assert!(table.resolve(ObjRef::new(9, 0)).unwrap().is_null());
+    }
+
+    #[test]
+    fn test_parse_xref_stream_with_object_stream() {
+        use crate::pdf::filter::encode_flate;
+
+        // Objects 1 and 2 live in object stream 3
+        let members = b"1 0 2 34 << /Type /Catalog /Pages 2 0 R >> << /Type /Pages /Count 0 >>";
+        let first = members.iter().position(|&b| b == b'<').unwrap();
+        let objstm = format!(
+            "<< /Type /ObjStm /N 2 /First {first} /Length {} >>\nstream\n{}\nendstream",
+            members.len(),
+            std::str::from_utf8(members).unwrap()
+        );
+        let mut pdf = b"%PDF-1.7\n".to_vec();
+        let offsets = append_objects(&mut pdf, &[(3, objstm.as_bytes()), (4, b"(plain)")]);
+
+        // Rows of /W [1 2 1], each prefixed by a PNG "None" filter byte
+        let xref_offset = pdf.len();
+        let rows: [[u8; 4]; 6] = [
+            [0, 0, 0, 255],
+            [2, 0, 3, 0],
+            [2, 0, 3, 1],
+            [1, (offsets[0].1 >> 8) as u8, offsets[0].1 as u8, 0],
+            [1, (offsets[1].1 >> 8) as u8, offsets[1].1 as u8, 0],
+            [1, (xref_offset >> 8) as u8, xref_offset as u8, 0],
+        ];
+        let mut raw = Vec::new();
+        for row in rows {
+            raw.push(0);
+            raw.extend_from_slice(&row);
+        }
+        let compressed = encode_flate(&raw, 6).unwrap();
+        pdf.extend_from_slice(
+            format!(
+                "5 0 obj\n<< /Type /XRef /Size 6 /W [1 2 1] /Root 1 0 R /Filter /FlateDecode /DecodeParms << /Predictor 12 /Columns 4 >> /Length {} >>\nstream\n",
+                compressed.len()
+            )
+            .as_bytes(),
+        );
+        pdf.extend_from_slice(&compressed);
+        pdf.extend_from_slice(
+            format!("\nendstream\nendobj\nstartxref\n{xref_offset}\n%%EOF\n").as_bytes(),
+        );
+
+        let mut table = XrefTable::parse(pdf).unwrap();
+        assert_eq!(table.compressed_count(), 2);
+        let decodes = object_stream_decode_count();
+        assert_eq!(
+            table.trailer().get("Root").unwrap().as_obj_ref(),
+            Some(ObjRef::new(1, 0))
+        );
+
+        let catalog = table.resolve(ObjRef::new(1, 0)).unwrap();
+        assert_eq!(
+            catalog
+                .as_dict()
+                .unwrap()
+                .get("Type")
+                .unwrap()
+                .as_name()
+                .unwrap()
+                .as_str(),
+            "Catalog"
+        );
+        let pages = table.resolve(ObjRef::new(2, 0)).unwrap();
+        assert_eq!(
+            pages.as_dict().unwrap().get("Count").unwrap().as_int(),
+            Some(0)
+        );
+        assert_eq!(object_stream_decode_count(), decodes + 1);
+        let plain = table.resolve(ObjRef::new(4, 0)).unwrap();
+        assert_eq!(plain.as_string().unwrap().as_bytes(), b"plain");
+    }
+
+    #[test]
+    fn test_object_stream_first_past_end() {
+        let dict = match parser::parse_object(b"<< /Type /ObjStm /N 1 /First 100 >>").unwrap() {
+            Object::Dict(d) => d,
+            _ => unreachable!(),
+        };
+        assert!(ObjectStream::decode(&dict, b"1 0 null".to_vec()).is_err());
+
+        let dict = match parser::parse_object(b"<< /Type /ObjStm /N 1 /First 4 >>").unwrap() {
+            Object::Dict(d) => d,
+            _ => unreachable!(),
+        };
+        let stm = ObjectStream::decode(&dict, b"7 0 null".to_vec()).unwrap();
+        assert_eq!(stm.members, vec![(7, 4)]);
+        assert!(ObjectStream::decode(&dict, b"7 9 null".to_vec()).is_err());
+    }
 }
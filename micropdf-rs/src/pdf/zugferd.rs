@@ -0,0 +1,211 @@
+//! ZUGFeRD / Factur-X electronic invoices
+//!
+//! Finds the invoice XML attached through the /Names /EmbeddedFiles tree
+//! and reads the Factur-X and PDF/A identification properties from the
+//! catalog's XMP metadata.
+
+use crate::fitz::error::Result;
+use crate::pdf::document::Document;
+use crate::pdf::name_tree;
+use crate::pdf::object::{Dict, Object};
+
+/// Attachment names used for the invoice XML, compared case-insensitively
+pub const INVOICE_FILENAMES: [&str; 4] = [
+    "factur-x.xml",
+    "zugferd-invoice.xml",
+    "xrechnung.xml",
+    "order-x.xml",
+];
+
+/// Namespace prefixes of the Factur-X and ZUGFeRD XMP extension schemas
+const INVOICE_NAMESPACES: [&str; 3] = ["urn:factur-x", "urn:zugferd", "urn:ferd"];
+
+/// The embedded invoice XML
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedInvoice {
+    /// Attachment filename as stored in the file specification
+    pub filename: String,
+    /// Decoded XML bytes
+    pub xml: Vec<u8>,
+}
+
+/// Invoice-related XMP properties
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InvoiceMetadata {
+    /// `pdfaid:part`
+    pub pdfa_part: Option<u32>,
+    /// `pdfaid:conformance`
+    pub pdfa_conformance: Option<String>,
+    /// `fx:ConformanceLevel`, such as `EN 16931`
+    pub conformance_level: Option<String>,
+    /// `fx:DocumentFileName`
+    pub document_file_name: Option<String>,
+    /// `fx:DocumentType`, normally `INVOICE`
+    pub document_type: Option<String>,
+    /// `fx:Version`
+    pub version: Option<String>,
+}
+
+impl InvoiceMetadata {
+    /// Parse the properties out of an XMP packet
+    pub fn from_xmp(xmp: &str) -> Self {
+        let prefixes = invoice_prefixes(xmp);
+        let invoice_property = |name: &str| {
+            prefixes
+                .iter()
+                .find_map(|prefix| xmp_property(xmp, &format!("{prefix}:{name}")))
+        };
+        Self {
+            pdfa_part: xmp_property(xmp, "pdfaid:part").and_then(|p| p.parse().ok()),
+            pdfa_conformance: xmp_property(xmp, "pdfaid:conformance"),
+            conformance_level: invoice_property("ConformanceLevel"),
+            document_file_name: invoice_property("DocumentFileName"),
+            document_type: invoice_property("DocumentType"),
+            version: invoice_property("Version"),
+        }
+    }
+
+    /// Whether the metadata identifies the file as PDF/A-3, the only
+    /// PDF/A part that allows arbitrary attachments
+    pub fn is_pdfa3(&self) -> bool {
+        self.pdfa_part == Some(3)
+    }
+}
+
+/// XML prefixes bound to a Factur-X or ZUGFeRD namespace
+fn invoice_prefixes(xmp: &str) -> Vec<String> {
+    let mut prefixes = Vec::new();
+    let mut rest = xmp;
+    while let Some(pos) = rest.find("xmlns:") {
+        rest = &rest[pos + 6..];
+        let Some(eq) = rest.find('=') else { break };
+        let prefix = rest[..eq].trim();
+        let value = rest[eq + 1..].trim_start();
+        let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
+        };
+        let Some(end) = value[1..].find(quote) else {
+            break;
+        };
+        let uri = &value[1..1 + end];
+        if INVOICE_NAMESPACES.iter().any(|ns| uri.starts_with(ns)) {
+            prefixes.push(prefix.to_string());
+        }
+    }
+    prefixes
+}
+
+/// Value of a simple XMP property written either as an attribute,
+/// `name="value"`, or as an element, `<name>value</name>`
+fn xmp_property(xmp: &str, name: &str) -> Option<String> {
+    let element = format!("<{name}>");
+    if let Some(pos) = xmp.find(&element) {
+        let value = &xmp[pos + element.len()..];
+        let end = value.find('<')?;
+        return Some(unescape_xml(value[..end].trim()));
+    }
+    let mut rest = xmp;
+    while let Some(pos) = rest.find(name) {
+        let preceded_by_space = rest[..pos]
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        rest = &rest[pos + name.len()..];
+        let after = rest.trim_start();
+        if !preceded_by_space || !after.starts_with('=') {
+            continue;
+        }
+        let value = after[1..].trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let end = value[1..].find(quote)?;
+        return Some(unescape_xml(&value[1..1 + end]));
+    }
+    None
+}
+
+fn unescape_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// The catalog's XMP metadata stream, decoded
+pub fn read_xmp(doc: &Document) -> Result<Option<Vec<u8>>> {
+    let catalog = doc.catalog()?;
+    match catalog.get("Metadata") {
+        Some(metadata) => doc.stream_data(metadata).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Invoice-related properties from the document's XMP metadata
+pub fn read_invoice_metadata(doc: &Document) -> Result<Option<InvoiceMetadata>> {
+    Ok(read_xmp(doc)?.map(|xmp| InvoiceMetadata::from_xmp(&String::from_utf8_lossy(&xmp))))
+}
+
+/// The embedded invoice XML, found by its attachment filename
+pub fn find_invoice(doc: &Document) -> Result<Option<EmbeddedInvoice>> {
+    let catalog = doc.catalog()?;
+    let tree = match doc.resolve_key(&catalog, "Names")? {
+        Some(Object::Dict(names)) => doc.resolve_key(&names, "EmbeddedFiles")?,
+        _ => None,
+    };
+    let Some(tree) = tree else {
+        return Ok(None);
+    };
+
+    for (_, filespec) in name_tree::name_entries(&tree, |o| doc.resolve(o))? {
+        let Object::Dict(filespec) = doc.resolve(&filespec)? else {
+            continue;
+        };
+        let Some(filename) = filespec_name(doc, &filespec)? else {
+            continue;
+        };
+        if !INVOICE_FILENAMES
+            .iter()
+            .any(|name| filename.eq_ignore_ascii_case(name))
+        {
+            continue;
+        }
+        let Some(Object::Dict(ef)) = doc.resolve_key(&filespec, "EF")? else {
+            continue;
+        };
+        let Some(stream) = ef.get("UF").or_else(|| ef.get("F")) else {
+            continue;
+        };
+        let xml = doc.stream_data(stream)?;
+        return Ok(Some(EmbeddedInvoice { filename, xml }));
+    }
+    Ok(None)
+}
+
+/// The file specification's name, preferring the Unicode /UF
+fn filespec_name(doc: &Document, filespec: &Dict) -> Result<Option<String>> {
+    for key in ["UF", "F"] {
+        if let Some(Object::String(s)) = doc.resolve_key(filespec, key)? {
+            return Ok(Some(s.to_text()));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xmp_property_forms() {
+        let xmp = "<rdf:Description xmlns:zf=\"urn:zugferd:pdfa:CrossIndustryDocument:invoice:2p0#\" \
+                   zf:ConformanceLevel='BASIC' pdfaid:part = \"2\"/>\
+                   <rdf:Description xmlns:fx=\"urn:factur-x:pdfa:CrossIndustryDocument:invoice:1p0#\">\
+                   <fx:DocumentType>INVOICE</fx:DocumentType></rdf:Description>";
+        let metadata = InvoiceMetadata::from_xmp(xmp);
+        assert_eq!(metadata.conformance_level.as_deref(), Some("BASIC"));
+        assert_eq!(metadata.document_type.as_deref(), Some("INVOICE"));
+        assert_eq!(metadata.pdfa_part, Some(2));
+        assert!(!metadata.is_pdfa3());
+        assert_eq!(metadata.version, None);
+    }
+}